@@ -39,6 +39,24 @@ export class TokenBridgeInstructionCoder implements InstructionCoder {
       case "transferNativeWithPayload": {
         return encodeTransferNativeWithPayload(ix);
       }
+      case "completeNativeWithPayload": {
+        return encodeCompleteNativeWithPayload(ix);
+      }
+      case "completeWrappedWithPayload": {
+        return encodeCompleteWrappedWithPayload(ix);
+      }
+      case "queryRegisteredEmitter": {
+        return encodeQueryRegisteredEmitter(ix);
+      }
+      case "setPaused": {
+        return encodeSetPaused(ix);
+      }
+      case "setMetadataAuthority": {
+        return encodeSetMetadataAuthority(ix);
+      }
+      case "deregisterChain": {
+        return encodeDeregisterChain(ix);
+      }
       default: {
         throw new Error(`Invalid instruction: ${ixName}`);
       }
@@ -68,6 +86,10 @@ export enum TokenBridgeInstruction {
   CompleteWrappedWithPayload,
   TransferWrappedWithPayload,
   TransferNativeWithPayload,
+  QueryRegisteredEmitter,
+  SetPaused,
+  SetMetadataAuthority,
+  DeregisterChain,
 }
 
 function encodeTokenBridgeInstructionData(
@@ -252,3 +274,40 @@ function encodeTransferNativeWithPayload({
     })
   );
 }
+
+function encodeCompleteNativeWithPayload({}: any) {
+  return encodeTokenBridgeInstructionData(
+    TokenBridgeInstruction.CompleteNativeWithPayload
+  );
+}
+
+function encodeCompleteWrappedWithPayload({}: any) {
+  return encodeTokenBridgeInstructionData(
+    TokenBridgeInstruction.CompleteWrappedWithPayload
+  );
+}
+
+function encodeQueryRegisteredEmitter({ chain }: any) {
+  const serialized = Buffer.alloc(2);
+  serialized.writeUInt16LE(chain, 0);
+  return encodeTokenBridgeInstructionData(
+    TokenBridgeInstruction.QueryRegisteredEmitter,
+    serialized
+  );
+}
+
+function encodeSetPaused({}: any) {
+  return encodeTokenBridgeInstructionData(TokenBridgeInstruction.SetPaused);
+}
+
+function encodeSetMetadataAuthority({}: any) {
+  return encodeTokenBridgeInstructionData(
+    TokenBridgeInstruction.SetMetadataAuthority
+  );
+}
+
+function encodeDeregisterChain({}: any) {
+  return encodeTokenBridgeInstructionData(
+    TokenBridgeInstruction.DeregisterChain
+  );
+}
@@ -7,7 +7,11 @@ import {
   TransactionInstruction,
 } from "@solana/web3.js";
 import { createReadOnlyTokenBridgeProgramInterface } from "../program";
-import { deriveClaimKey, derivePostedVaaKey } from "../../wormhole";
+import {
+  deriveClaimKey,
+  deriveGovernanceClaimKey,
+  derivePostedVaaKey,
+} from "../../wormhole";
 import {
   deriveEndpointKey,
   deriveTokenBridgeConfigKey,
@@ -55,6 +59,7 @@ export interface RegisterChainAccounts {
   endpoint: PublicKey;
   vaa: PublicKey;
   claim: PublicKey;
+  legacyClaim: PublicKey;
   rent: PublicKey;
   systemProgram: PublicKey;
   wormholeProgram: PublicKey;
@@ -76,7 +81,13 @@ export function getRegisterChainAccounts(
       parsed.foreignAddress
     ),
     vaa: derivePostedVaaKey(wormholeProgramId, parsed.hash),
-    claim: deriveClaimKey(
+    claim: deriveGovernanceClaimKey(
+      tokenBridgeProgramId,
+      parsed.emitterAddress,
+      parsed.emitterChain,
+      parsed.sequence
+    ),
+    legacyClaim: deriveClaimKey(
       tokenBridgeProgramId,
       parsed.emitterAddress,
       parsed.emitterChain,
@@ -120,6 +131,7 @@ export interface UpgradeContractAccounts {
   payer: PublicKey;
   vaa: PublicKey;
   claim: PublicKey;
+  legacyClaim: PublicKey;
   upgradeAuthority: PublicKey;
   spill: PublicKey;
   implementation: PublicKey;
@@ -142,7 +154,13 @@ export function getUpgradeContractAccounts(
   return {
     payer: new PublicKey(payer),
     vaa: derivePostedVaaKey(wormholeProgramId, parsed.hash),
-    claim: deriveClaimKey(
+    claim: deriveGovernanceClaimKey(
+      tokenBridgeProgramId,
+      parsed.emitterAddress,
+      parsed.emitterChain,
+      parsed.sequence
+    ),
+    legacyClaim: deriveClaimKey(
       tokenBridgeProgramId,
       parsed.emitterAddress,
       parsed.emitterChain,
@@ -12,6 +12,17 @@ export function deriveClaimKey(
   emitterChain: number,
   sequence: bigint | number
 ): PublicKey {
+  return deriveAddress(
+    deriveClaimSeeds(emitterAddress, emitterChain, sequence),
+    programId
+  );
+}
+
+function deriveClaimSeeds(
+  emitterAddress: Buffer | Uint8Array | string,
+  emitterChain: number,
+  sequence: bigint | number
+): Buffer[] {
   const address =
     typeof emitterAddress == "string"
       ? Buffer.from(emitterAddress, "hex")
@@ -23,18 +34,28 @@ export function deriveClaimKey(
   sequenceSerialized.writeBigUInt64BE(
     typeof sequence == "number" ? BigInt(sequence) : sequence
   );
-  return deriveAddress(
-    [
-      address,
-      (() => {
-        const buf = Buffer.alloc(2);
-        buf.writeUInt16BE(emitterChain as number);
-        return buf;
-      })(),
-      sequenceSerialized,
-    ],
-    programId
-  );
+  return [
+    address,
+    (() => {
+      const buf = Buffer.alloc(2);
+      buf.writeUInt16BE(emitterChain as number);
+      return buf;
+    })(),
+    sequenceSerialized,
+  ];
+}
+
+/** Claim of a governance decree consumed by a module, derived like `deriveClaimKey` under a
+ * "governance" prefix so that it can never collide with the claim of a transfer.
+ */
+export function deriveGovernanceClaimKey(
+  programId: PublicKeyInitData,
+  emitterAddress: Buffer | Uint8Array | string,
+  emitterChain: number,
+  sequence: bigint | number
+): PublicKey {
+  const claim = deriveClaimSeeds(emitterAddress, emitterChain, sequence);
+  return deriveAddress([Buffer.from("governance"), ...claim], programId);
 }
 
 export async function getClaim(
@@ -1,19 +1,27 @@
 pub mod bridge;
 pub mod claim;
+pub mod draft_message;
 pub mod fee_collector;
+pub mod fee_exemption;
 pub mod guardian_set;
 pub mod posted_message;
 pub mod posted_vaa;
 pub mod sequence;
 pub mod signature_set;
+pub mod vaa_consumer;
+pub mod verified_hash;
 
 pub use self::{
     bridge::*,
     claim::*,
+    draft_message::*,
     fee_collector::*,
+    fee_exemption::*,
     guardian_set::*,
     posted_message::*,
     posted_vaa::*,
     sequence::*,
     signature_set::*,
+    vaa_consumer::*,
+    verified_hash::*,
 };
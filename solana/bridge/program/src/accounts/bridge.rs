@@ -1,6 +1,7 @@
 //! The Bridge account contains the main state for the wormhole bridge, as well as tracking
 //! configuration options for how the bridge should behave.
 
+use crate::error::Error::InvalidBridge;
 use borsh::{
     BorshDeserialize,
     BorshSerialize,
@@ -9,22 +10,41 @@ use serde::{
     Deserialize,
     Serialize,
 };
+use solana_program::{
+    account_info::AccountInfo,
+    pubkey::Pubkey,
+};
 use solitaire::{
     AccountOwner,
     AccountState,
     Data,
     Derive,
     Owned,
+    Result,
+};
+use std::{
+    cell::Ref,
+    convert::TryInto,
+    io::{
+        self,
+        Write,
+    },
 };
 
-pub type Bridge<'a, const State: AccountState> = Derive<Data<'a, BridgeData, { State }>, "Bridge">;
+/// Seed of the bridge config.
+pub const BRIDGE_SEED: &str = "Bridge";
+
+pub type Bridge<'a, const State: AccountState> =
+    Derive<Data<'a, BridgeData, { State }>, { BRIDGE_SEED }>;
 
 #[derive(Clone, Default, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
 pub struct BridgeData {
     /// The current guardian set index, used to decide which signature sets to accept.
     pub guardian_set_index: u32,
 
-    /// Lamports in the collection account
+    /// Lamports in the collection account that have already paid for a message. Anything above
+    /// this pays the fee of the next `post_message`, `post_message_unreliable` or
+    /// `post_prepared_message`.
     pub last_lamports: u64,
 
     /// Bridge configuration, which is set once upon initialization.
@@ -41,13 +61,12 @@ impl Owned for BridgeData {
 #[cfg(feature = "cpi")]
 impl Owned for BridgeData {
     fn owner(&self) -> AccountOwner {
-        use solana_program::pubkey::Pubkey;
         use std::str::FromStr;
         AccountOwner::Other(Pubkey::from_str(env!("BRIDGE_ADDRESS")).unwrap())
     }
 }
 
-#[derive(Clone, Default, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub struct BridgeConfig {
     /// Period for how long a guardian set is valid after it has been replaced by a new one.  This
     /// guarantees that VAAs issued by that set can still be submitted for a certain period.  In
@@ -56,4 +75,110 @@ pub struct BridgeConfig {
 
     /// Amount of lamports that needs to be paid to the protocol to post a message
     pub fee: u64,
+
+    /// Mint messages pay `token_fee` in instead of lamports, set by governance.
+    pub fee_mint: Option<Pubkey>,
+
+    /// Fee per message in the smallest unit of `fee_mint`.
+    pub token_fee: u64,
+}
+
+// The token fee is only stored while a fee mint is set, so bridges created before it existed keep
+// deserializing and charging lamports at their original size. `set_fee_mint` resizes the account
+// accordingly. This relies on the config being the last field of `BridgeData`.
+impl BorshSerialize for BridgeConfig {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.guardian_set_expiration_time.serialize(writer)?;
+        self.fee.serialize(writer)?;
+        if let Some(fee_mint) = &self.fee_mint {
+            fee_mint.serialize(writer)?;
+            self.token_fee.serialize(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for BridgeConfig {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        let guardian_set_expiration_time = u32::deserialize(buf)?;
+        let fee = u64::deserialize(buf)?;
+        let (fee_mint, token_fee) = if buf.is_empty() {
+            (None, 0)
+        } else {
+            (Some(Pubkey::deserialize(buf)?), u64::deserialize(buf)?)
+        };
+        Ok(BridgeConfig {
+            guardian_set_expiration_time,
+            fee,
+            fee_mint,
+            token_fee,
+        })
+    }
+}
+
+/// Size of a serialized `BridgeData`.
+pub const BRIDGE_SIZE: usize = 24;
+
+/// Size of a serialized `BridgeData` with a fee mint set.
+pub const BRIDGE_WITH_FEE_MINT_SIZE: usize = BRIDGE_SIZE + 40;
+
+/// Address of the bridge config of the core bridge deployed at `program_id`, along with its bump.
+pub fn find_bridge(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[BRIDGE_SEED.as_bytes()], program_id)
+}
+
+/// Zero-copy view of the bridge config, for programs that need the current fee or guardian set
+/// without deserializing the account.
+pub struct BridgeV1<'a> {
+    data: Ref<'a, [u8]>,
+}
+
+impl<'a> BridgeV1<'a> {
+    /// Borrow the data of `info`, checking that it holds a bridge config. The owner is not checked,
+    /// callers must make sure the account belongs to the core bridge.
+    pub fn load(info: &'a AccountInfo) -> Result<Self> {
+        let data = Ref::map(info.data.borrow(), |data| &**data);
+        if data.len() != BRIDGE_SIZE && data.len() != BRIDGE_WITH_FEE_MINT_SIZE {
+            return Err(InvalidBridge.into());
+        }
+        Ok(BridgeV1 { data })
+    }
+
+    /// Index of the guardian set new VAAs must be signed by.
+    pub fn guardian_set_index(&self) -> u32 {
+        u32::from_le_bytes(self.data[0..4].try_into().unwrap())
+    }
+
+    /// Balance of the fee collector after the last fee was paid, see `BridgeData::last_lamports`.
+    pub fn last_lamports(&self) -> u64 {
+        u64::from_le_bytes(self.data[4..12].try_into().unwrap())
+    }
+
+    /// How long a replaced guardian set stays valid, in seconds.
+    pub fn guardian_set_expiration_time(&self) -> u32 {
+        u32::from_le_bytes(self.data[12..16].try_into().unwrap())
+    }
+
+    /// Lamports to pay into the fee collector for each message, unless a fee mint is set.
+    pub fn fee(&self) -> u64 {
+        u64::from_le_bytes(self.data[16..24].try_into().unwrap())
+    }
+
+    /// Mint messages pay their fee in, if governance set one.
+    pub fn fee_mint(&self) -> Option<Pubkey> {
+        if self.data.len() == BRIDGE_SIZE {
+            return None;
+        }
+        Some(Pubkey::new_from_array(
+            self.data[24..56].try_into().unwrap(),
+        ))
+    }
+
+    /// Fee per message in the smallest unit of the fee mint, 0 if none is set.
+    pub fn token_fee(&self) -> u64 {
+        if self.data.len() == BRIDGE_SIZE {
+            return 0;
+        }
+        u64::from_le_bytes(self.data[56..64].try_into().unwrap())
+    }
 }
@@ -62,33 +62,69 @@ pub fn consume<T>(
 where
     T: DeserializePayload,
 {
-    // Verify that the claim account is derived correctly before claiming.
-    claim.verify_derivation(
-        ctx.program_id,
+    // Claim the account by initializing it with a value, creating it fails unless it is derived
+    // from the message.
+    claim.create(
         &ClaimDerivationData {
             emitter_address: message.meta().emitter_address,
             emitter_chain: message.meta().emitter_chain,
             sequence: message.meta().sequence,
         },
+        ctx,
+        payer,
+        Exempt,
     )?;
 
-    // Claim the account by initializing it with a value.
-    claim.create(
+    claim.claimed = true;
+
+    Ok(())
+}
+
+/// Consume the claim of a governance decree. Decrees are claimed under a prefixed seed, so they can
+/// never share a claim with another message of the same emitter, chain and sequence.
+///
+/// `legacy_claim` is the unprefixed claim decrees were consumed under before, it must still be
+/// unclaimed so that decrees processed back then cannot be replayed.
+pub fn claim_vaa<T>(
+    ctx: &ExecutionContext,
+    payer: &Pubkey,
+    claim: &mut Claim,
+    legacy_claim: &Info,
+    message: &PayloadMessage<T>,
+) -> Result<()>
+where
+    T: DeserializePayload,
+{
+    let legacy_key = Claim::key(
         &ClaimDerivationData {
             emitter_address: message.meta().emitter_address,
             emitter_chain: message.meta().emitter_chain,
             sequence: message.meta().sequence,
         },
-        ctx,
-        payer,
-        Exempt,
-    )?;
+        ctx.program_id,
+    );
+    if *legacy_claim.key != legacy_key {
+        return Err(SolitaireError::InvalidDerive(*legacy_claim.key, legacy_key));
+    }
+    if !legacy_claim.data_is_empty() {
+        return Err(SolitaireError::AlreadyInitialized(legacy_key));
+    }
+
+    let derivation_data = GovernanceClaimDerivationData {
+        emitter_address: message.meta().emitter_address,
+        emitter_chain: message.meta().emitter_chain,
+        sequence: message.meta().sequence,
+    };
+    claim.create(&derivation_data, ctx, payer, Exempt)?;
 
     claim.claimed = true;
 
     Ok(())
 }
 
+/// Size of a claim account, holding a single `ClaimData`.
+pub const CLAIM_SIZE: usize = 1;
+
 #[derive(Default, Clone, Copy, BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
 pub struct ClaimData {
     pub claimed: bool,
@@ -115,3 +151,23 @@ impl<'b> Seeded<&ClaimDerivationData> for Claim<'b> {
         ];
     }
 }
+
+/// Seed prefix of governance claims, see `claim_vaa`.
+pub const GOVERNANCE_CLAIM_SEED: &[u8] = b"governance";
+
+pub struct GovernanceClaimDerivationData {
+    pub emitter_address: [u8; 32],
+    pub emitter_chain: u16,
+    pub sequence: u64,
+}
+
+impl<'b> Seeded<&GovernanceClaimDerivationData> for Claim<'b> {
+    fn seeds(data: &GovernanceClaimDerivationData) -> Vec<Vec<u8>> {
+        vec![
+            GOVERNANCE_CLAIM_SEED.to_vec(),
+            data.emitter_address.to_vec(),
+            data.emitter_chain.to_be_bytes().to_vec(),
+            data.sequence.to_be_bytes().to_vec(),
+        ]
+    }
+}
@@ -0,0 +1,78 @@
+//! Draft messages are allocated at the size of the final posted message and payload chunks are
+//! written straight to the offsets the posted message uses. Posting a draft then only rewrites the
+//! header in place, the payload is never copied or reserialized.
+//!
+//! | bytes   | draft                    | posted message             |
+//! |---------+--------------------------+----------------------------|
+//! | 0..3    | `msd`                    | `msg`                      |
+//! | 3..91   | status, emitter, padding | `MessageData` header       |
+//! | 91..95  | payload length           | payload length             |
+//! | 95..    | payload                  | payload                    |
+
+use super::{
+    PAYLOAD_LEN_OFFSET,
+    PAYLOAD_OFFSET,
+};
+use crate::error::Error::InvalidDraftMessage;
+use solana_program::pubkey::Pubkey;
+use solitaire::Result;
+use std::convert::TryInto;
+
+pub const DRAFT_MESSAGE_MAGIC: &[u8; 3] = b"msd";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(u8)]
+pub enum DraftStatus {
+    /// Chunks may still be written.
+    Writing = 0,
+    /// The payload is complete and the draft may be posted.
+    Finalized = 1,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DraftMessageHeader {
+    pub status: DraftStatus,
+    /// Only the emitter may write to, finalize and post the draft.
+    pub emitter: Pubkey,
+    pub payload_size: u32,
+}
+
+impl DraftMessageHeader {
+    /// Account size needed to hold a draft, and later the posted message, for `payload_size`.
+    pub fn account_size(payload_size: u32) -> usize {
+        PAYLOAD_OFFSET + payload_size as usize
+    }
+
+    /// Read the header without touching the payload.
+    pub fn read(data: &[u8]) -> Result<Self> {
+        if data.len() < PAYLOAD_OFFSET || &data[0..3] != DRAFT_MESSAGE_MAGIC {
+            return Err(InvalidDraftMessage.into());
+        }
+
+        let status = match data[3] {
+            0 => DraftStatus::Writing,
+            1 => DraftStatus::Finalized,
+            _ => return Err(InvalidDraftMessage.into()),
+        };
+        let emitter = Pubkey::new_from_array(data[4..36].try_into().unwrap());
+        let payload_size =
+            u32::from_le_bytes(data[PAYLOAD_LEN_OFFSET..PAYLOAD_OFFSET].try_into().unwrap());
+
+        if data.len() != Self::account_size(payload_size) {
+            return Err(InvalidDraftMessage.into());
+        }
+
+        Ok(DraftMessageHeader {
+            status,
+            emitter,
+            payload_size,
+        })
+    }
+
+    pub fn write(&self, data: &mut [u8]) {
+        data[0..3].copy_from_slice(DRAFT_MESSAGE_MAGIC);
+        data[3] = self.status as u8;
+        data[4..36].copy_from_slice(self.emitter.as_ref());
+        data[PAYLOAD_LEN_OFFSET..PAYLOAD_OFFSET].copy_from_slice(&self.payload_size.to_le_bytes());
+    }
+}
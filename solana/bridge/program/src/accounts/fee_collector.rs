@@ -1,8 +1,39 @@
-//! The FeeCollector is a simple account that collects SOL fees.
+//! The FeeCollector is a simple account that collects SOL fees. Fees paid in a token set by
+//! governance are collected in a token account per mint instead, held by the FeeAuthority.
 
+use crate::types::SplAccount;
+use solana_program::pubkey::Pubkey;
 use solitaire::{
+    processors::seeded::Seeded,
+    AccountState,
+    Data,
     Derive,
     Info,
 };
 
-pub type FeeCollector<'a> = Derive<Info<'a>, "fee_collector">;
+/// Seed of the fee collector.
+pub const FEE_COLLECTOR_SEED: &str = "fee_collector";
+
+pub type FeeCollector<'a> = Derive<Info<'a>, { FEE_COLLECTOR_SEED }>;
+
+/// Address of the fee collector of the core bridge deployed at `program_id`, along with its bump.
+pub fn find_fee_collector(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FEE_COLLECTOR_SEED.as_bytes()], program_id)
+}
+
+/// Authority of the fee token accounts, signing token fee payouts.
+pub type FeeAuthority<'a> = Derive<Info<'a>, "fee_authority">;
+
+pub type FeeTokenAccount<'a, const State: AccountState> = Data<'a, SplAccount, { State }>;
+
+pub struct FeeTokenAccountDerivationData {
+    pub mint: Pubkey,
+}
+
+impl<'a, const State: AccountState> Seeded<&FeeTokenAccountDerivationData>
+    for FeeTokenAccount<'a, { State }>
+{
+    fn seeds(data: &FeeTokenAccountDerivationData) -> Vec<Vec<u8>> {
+        vec![b"fee_token".to_vec(), data.mint.to_bytes().to_vec()]
+    }
+}
@@ -0,0 +1,41 @@
+//! Fee exemptions let governance waive the message fee for an emitter. They are created by the
+//! set-fee-exemption decree and are never written to by anything else.
+
+use borsh::{
+    BorshDeserialize,
+    BorshSerialize,
+};
+use solana_program::pubkey::Pubkey;
+use solitaire::{
+    processors::seeded::Seeded,
+    AccountOwner,
+    AccountState,
+    Data,
+    Owned,
+};
+
+pub type FeeExemption<'b, const State: AccountState> = Data<'b, FeeExemptionData, { State }>;
+
+#[derive(Default, Clone, Copy, BorshDeserialize, BorshSerialize)]
+pub struct FeeExemptionData {
+    /// Cleared when governance revokes the exemption, the account itself is kept.
+    pub exempt: bool,
+}
+
+pub struct FeeExemptionDerivationData {
+    pub emitter: Pubkey,
+}
+
+impl<'b, const State: AccountState> Seeded<&FeeExemptionDerivationData>
+    for FeeExemption<'b, { State }>
+{
+    fn seeds(data: &FeeExemptionDerivationData) -> Vec<Vec<u8>> {
+        vec![b"fee_exemption".to_vec(), data.emitter.to_bytes().to_vec()]
+    }
+}
+
+impl Owned for FeeExemptionData {
+    fn owner(&self) -> AccountOwner {
+        AccountOwner::This
+    }
+}
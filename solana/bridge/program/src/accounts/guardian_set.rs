@@ -10,6 +10,7 @@ use serde::{
     Deserialize,
     Serialize,
 };
+use solana_program::pubkey::Pubkey;
 use solitaire::{
     processors::seeded::Seeded,
     AccountOwner,
@@ -35,6 +36,9 @@ pub struct GuardianSetData {
     pub expiration_time: u32,
 }
 
+/// Seed prefix of guardian set accounts, followed by the big endian index.
+pub const GUARDIAN_SET_SEED: &[u8] = b"GuardianSet";
+
 /// GuardianSet account PDAs are indexed by their version number.
 pub struct GuardianSetDerivationData {
     pub index: u32,
@@ -45,12 +49,18 @@ impl<'a, const State: AccountState> Seeded<&GuardianSetDerivationData>
 {
     fn seeds(data: &GuardianSetDerivationData) -> Vec<Vec<u8>> {
         vec![
-            "GuardianSet".as_bytes().to_vec(),
+            GUARDIAN_SET_SEED.to_vec(),
             data.index.to_be_bytes().to_vec(),
         ]
     }
 }
 
+/// Address of guardian set `index` of the core bridge deployed at `program_id`, along with its
+/// bump.
+pub fn find_guardian_set(program_id: &Pubkey, index: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GUARDIAN_SET_SEED, &index.to_be_bytes()], program_id)
+}
+
 impl GuardianSetData {
     /// Number of guardians in the set
     pub fn num_guardians(&self) -> u8 {
@@ -58,6 +68,27 @@ impl GuardianSetData {
     }
 }
 
+/// Whether `keys` can form a guardian set: quorum is taken over every key, so there must be at least
+/// one and each has to belong to a distinct guardian that can actually sign.
+pub fn valid_guardian_keys(keys: &[GuardianPublicKey]) -> bool {
+    !keys.is_empty()
+        && !keys
+            .iter()
+            .enumerate()
+            .any(|(i, key)| *key == [0u8; 20] || keys[..i].contains(key))
+}
+
+/// Number of signatures required to reach consensus in a guardian set of `num_guardians`. This
+/// calculation is in expanded form to ease auditing.
+pub fn quorum(num_guardians: usize) -> usize {
+    // Fixed point number transformation with one decimal to deal with rounding.
+    let len = (num_guardians * 10) / 3;
+    // Multiplication by two to get a 2/3 quorum.
+    let len = len * 2;
+    // Division to bring number back into range.
+    len / 10 + 1
+}
+
 impl Owned for GuardianSetData {
     fn owner(&self) -> AccountOwner {
         AccountOwner::This
@@ -1,3 +1,10 @@
+use crate::{
+    error::Error::InvalidPostedMessage,
+    types::{
+        ChainId,
+        ForeignEmitter,
+    },
+};
 use borsh::{
     BorshDeserialize,
     BorshSerialize,
@@ -6,14 +13,21 @@ use serde::{
     Deserialize,
     Serialize,
 };
-use solana_program::pubkey::Pubkey;
+use solana_program::{
+    account_info::AccountInfo,
+    pubkey::Pubkey,
+};
 use solitaire::{
+    processors::seeded::Seeded,
     AccountOwner,
     AccountState,
     Data,
     Owned,
+    Result,
 };
 use std::{
+    cell::Ref,
+    convert::TryInto,
     io::{
         Error,
         ErrorKind::InvalidData,
@@ -25,6 +39,25 @@ use std::{
     },
 };
 
+pub const POSTED_MESSAGE_MAGIC: &[u8; 3] = b"msg";
+pub const POSTED_MESSAGE_UNRELIABLE_MAGIC: &[u8; 3] = b"msu";
+
+// Offsets into a posted message or posted VAA account, the Borsh layout of `MessageData` behind
+// the magic.
+pub const VAA_VERSION_OFFSET: usize = 3;
+pub const CONSISTENCY_LEVEL_OFFSET: usize = 4;
+pub const VAA_TIME_OFFSET: usize = 5;
+pub const VAA_SIGNATURE_ACCOUNT_OFFSET: usize = 9;
+pub const SUBMISSION_TIME_OFFSET: usize = 41;
+pub const NONCE_OFFSET: usize = 45;
+pub const SEQUENCE_OFFSET: usize = 49;
+pub const EMITTER_CHAIN_OFFSET: usize = 57;
+pub const EMITTER_ADDRESS_OFFSET: usize = 59;
+/// Offset of the payload length prefix, shared by drafts and posted messages.
+pub const PAYLOAD_LEN_OFFSET: usize = 91;
+/// Offset of the payload, shared by drafts and posted messages.
+pub const PAYLOAD_OFFSET: usize = 95;
+
 pub type PostedMessage<'a, const State: AccountState> = Data<'a, PostedMessageData, { State }>;
 
 #[repr(transparent)]
@@ -33,6 +66,25 @@ pub struct PostedMessageData {
     pub message: MessageData,
 }
 
+/// Messages posted through `post_message_derived` live at a PDA of the bridge instead of a keypair
+/// account, derived from the emitter and the sequence the message is assigned.
+pub struct PostedMessageDerivationData<'a> {
+    pub emitter_key: &'a Pubkey,
+    pub sequence: u64,
+}
+
+impl<'a, 'b, const State: AccountState> Seeded<&PostedMessageDerivationData<'a>>
+    for PostedMessage<'b, { State }>
+{
+    fn seeds(data: &PostedMessageDerivationData) -> Vec<Vec<u8>> {
+        vec![
+            b"msg".to_vec(),
+            data.emitter_key.to_bytes().to_vec(),
+            data.sequence.to_be_bytes().to_vec(),
+        ]
+    }
+}
+
 pub type PostedMessageUnreliable<'a, const State: AccountState> =
     Data<'a, PostedMessageUnreliableData, { State }>;
 
@@ -79,7 +131,7 @@ pub struct MessageData {
 
 impl BorshSerialize for PostedMessageData {
     fn serialize<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
-        writer.write_all(b"msg")?;
+        writer.write_all(POSTED_MESSAGE_MAGIC)?;
         BorshSerialize::serialize(&self.message, writer)
     }
 }
@@ -90,7 +142,7 @@ impl BorshDeserialize for PostedMessageData {
             return Err(Error::new(InvalidData, "Not enough bytes"));
         }
 
-        let expected = b"msg";
+        let expected = POSTED_MESSAGE_MAGIC;
         let magic: &[u8] = &buf[0..3];
         if magic != expected {
             return Err(Error::new(
@@ -149,7 +201,7 @@ impl Owned for PostedMessageData {
 
 impl BorshSerialize for PostedMessageUnreliableData {
     fn serialize<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
-        writer.write_all(b"msu")?;
+        writer.write_all(POSTED_MESSAGE_UNRELIABLE_MAGIC)?;
         BorshSerialize::serialize(&self.message, writer)
     }
 }
@@ -160,7 +212,7 @@ impl BorshDeserialize for PostedMessageUnreliableData {
             return Err(Error::new(InvalidData, "Not enough bytes"));
         }
 
-        let expected = b"msu";
+        let expected = POSTED_MESSAGE_UNRELIABLE_MAGIC;
         let magic: &[u8] = &buf[0..3];
         if magic != expected {
             return Err(Error::new(
@@ -214,3 +266,81 @@ impl Owned for PostedMessageUnreliableData {
         AccountOwner::Other(Pubkey::from_str(env!("BRIDGE_ADDRESS")).unwrap())
     }
 }
+
+/// Zero-copy view of a posted message account, reliable or unreliable, for programs and indexers
+/// that only need a few fields and would rather not deserialize the whole payload.
+pub struct PostedMessageV1<'a> {
+    data: Ref<'a, [u8]>,
+}
+
+impl<'a> PostedMessageV1<'a> {
+    /// Borrow the data of `info`, checking the magic and that the payload fits the account. The
+    /// owner is not checked, callers must make sure the account belongs to the core bridge.
+    pub fn load(info: &'a AccountInfo) -> Result<Self> {
+        let data = Ref::map(info.data.borrow(), |data| &**data);
+        if data.len() < PAYLOAD_OFFSET {
+            return Err(InvalidPostedMessage.into());
+        }
+        if &data[..3] != POSTED_MESSAGE_MAGIC && &data[..3] != POSTED_MESSAGE_UNRELIABLE_MAGIC {
+            return Err(InvalidPostedMessage.into());
+        }
+
+        let message = PostedMessageV1 { data };
+        if message.data.len() < PAYLOAD_OFFSET + message.payload_len() {
+            return Err(InvalidPostedMessage.into());
+        }
+        Ok(message)
+    }
+
+    /// Whether the account was posted with `post_message_unreliable` and may be overwritten.
+    pub fn is_unreliable(&self) -> bool {
+        &self.data[..3] == POSTED_MESSAGE_UNRELIABLE_MAGIC
+    }
+
+    pub fn consistency_level(&self) -> u8 {
+        self.data[CONSISTENCY_LEVEL_OFFSET]
+    }
+
+    pub fn submission_time(&self) -> u32 {
+        u32::from_le_bytes(self.read_at(SUBMISSION_TIME_OFFSET))
+    }
+
+    pub fn nonce(&self) -> u32 {
+        u32::from_le_bytes(self.read_at(NONCE_OFFSET))
+    }
+
+    pub fn sequence(&self) -> u64 {
+        u64::from_le_bytes(self.read_at(SEQUENCE_OFFSET))
+    }
+
+    pub fn emitter_chain(&self) -> u16 {
+        u16::from_le_bytes(self.read_at(EMITTER_CHAIN_OFFSET))
+    }
+
+    pub fn emitter_chain_id(&self) -> ChainId {
+        self.emitter_chain().into()
+    }
+
+    pub fn emitter_address(&self) -> [u8; 32] {
+        self.read_at(EMITTER_ADDRESS_OFFSET)
+    }
+
+    pub fn emitter(&self) -> ForeignEmitter {
+        ForeignEmitter {
+            chain: self.emitter_chain(),
+            address: self.emitter_address(),
+        }
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.data[PAYLOAD_OFFSET..PAYLOAD_OFFSET + self.payload_len()]
+    }
+
+    fn payload_len(&self) -> usize {
+        u32::from_le_bytes(self.read_at(PAYLOAD_LEN_OFFSET)) as usize
+    }
+
+    fn read_at<const N: usize>(&self, offset: usize) -> [u8; N] {
+        self.data[offset..offset + N].try_into().unwrap()
+    }
+}
@@ -1,16 +1,42 @@
-use crate::MessageData;
+use super::{
+    CONSISTENCY_LEVEL_OFFSET,
+    EMITTER_ADDRESS_OFFSET,
+    EMITTER_CHAIN_OFFSET,
+    NONCE_OFFSET,
+    PAYLOAD_LEN_OFFSET,
+    PAYLOAD_OFFSET,
+    SEQUENCE_OFFSET,
+    VAA_SIGNATURE_ACCOUNT_OFFSET,
+    VAA_TIME_OFFSET,
+    VAA_VERSION_OFFSET,
+};
+use crate::{
+    error::Error::InvalidPostedVAA,
+    types::{
+        ChainId,
+        ForeignEmitter,
+    },
+    MessageData,
+};
 use borsh::{
     BorshDeserialize,
     BorshSerialize,
 };
+use solana_program::{
+    account_info::AccountInfo,
+    pubkey::Pubkey,
+};
 use solitaire::{
     processors::seeded::Seeded,
     AccountOwner,
     AccountState,
     Data,
     Owned,
+    Result,
 };
 use std::{
+    cell::Ref,
+    convert::TryInto,
     io::{
         Error,
         ErrorKind::InvalidData,
@@ -22,6 +48,11 @@ use std::{
     },
 };
 
+pub const POSTED_VAA_MAGIC: &[u8; 3] = b"vaa";
+
+/// Seed prefix of posted VAAs, followed by the hash of the VAA body.
+pub const POSTED_VAA_SEED: &[u8] = b"PostedVAA";
+
 pub type PostedVAA<'b, const State: AccountState> = Data<'b, PostedVAAData, { State }>;
 
 pub struct PostedVAADerivationData {
@@ -30,10 +61,21 @@ pub struct PostedVAADerivationData {
 
 impl<'a, const State: AccountState> Seeded<&PostedVAADerivationData> for PostedVAA<'a, { State }> {
     fn seeds(data: &PostedVAADerivationData) -> Vec<Vec<u8>> {
-        vec![b"PostedVAA".to_vec(), data.payload_hash.to_vec()]
+        vec![POSTED_VAA_SEED.to_vec(), data.payload_hash.to_vec()]
     }
 }
 
+/// Address the VAA with body hash `message_hash` is posted to by the core bridge deployed at
+/// `program_id`, along with its bump. See `vaa::compute_message_hash`.
+pub fn find_posted_vaa(program_id: &Pubkey, message_hash: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[POSTED_VAA_SEED, message_hash], program_id)
+}
+
+/// Size of the account `post_vaa` creates for a VAA carrying `payload_len` bytes of payload.
+pub fn posted_vaa_size(payload_len: usize) -> usize {
+    PAYLOAD_OFFSET + payload_len
+}
+
 #[repr(transparent)]
 #[derive(Default)]
 pub struct PostedVAAData {
@@ -42,7 +84,7 @@ pub struct PostedVAAData {
 
 impl BorshSerialize for PostedVAAData {
     fn serialize<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
-        writer.write_all(b"vaa")?;
+        writer.write_all(POSTED_VAA_MAGIC)?;
         BorshSerialize::serialize(&self.message, writer)
     }
 }
@@ -54,7 +96,7 @@ impl BorshDeserialize for PostedVAAData {
         }
 
         // We accept "vaa", "msg", or "msu" because it's convenient to read all of these as PostedVAAData
-        let expected: [&[u8]; 3] = [b"vaa", b"msg", b"msu"];
+        let expected: [&[u8]; 3] = [POSTED_VAA_MAGIC, b"msg", b"msu"];
         let magic: &[u8] = &buf[0..3];
         if !expected.contains(&magic) {
             return Err(Error::new(InvalidData, "Magic mismatch."));
@@ -97,8 +139,87 @@ impl Owned for PostedVAAData {
 #[cfg(feature = "cpi")]
 impl Owned for PostedVAAData {
     fn owner(&self) -> AccountOwner {
-        use solana_program::pubkey::Pubkey;
         use std::str::FromStr;
         AccountOwner::Other(Pubkey::from_str(env!("BRIDGE_ADDRESS")).unwrap())
     }
 }
+
+/// Zero-copy view of a posted VAA account. Posted VAAs share the layout of posted messages, the
+/// guardian set that signed them is recorded in the signature set they link to.
+pub struct PostedVAAV1<'a> {
+    data: Ref<'a, [u8]>,
+}
+
+impl<'a> PostedVAAV1<'a> {
+    /// Borrow the data of `info`, checking the magic and that the payload fits the account. The
+    /// owner is not checked, callers must make sure the account belongs to the core bridge.
+    pub fn load(info: &'a AccountInfo) -> Result<Self> {
+        let data = Ref::map(info.data.borrow(), |data| &**data);
+        if data.len() < PAYLOAD_OFFSET || &data[..3] != POSTED_VAA_MAGIC {
+            return Err(InvalidPostedVAA.into());
+        }
+
+        let vaa = PostedVAAV1 { data };
+        if vaa.data.len() < PAYLOAD_OFFSET + vaa.payload_len() {
+            return Err(InvalidPostedVAA.into());
+        }
+        Ok(vaa)
+    }
+
+    pub fn vaa_version(&self) -> u8 {
+        self.data[VAA_VERSION_OFFSET]
+    }
+
+    pub fn consistency_level(&self) -> u8 {
+        self.data[CONSISTENCY_LEVEL_OFFSET]
+    }
+
+    /// Timestamp of the VAA body.
+    pub fn vaa_time(&self) -> u32 {
+        u32::from_le_bytes(self.read_at(VAA_TIME_OFFSET))
+    }
+
+    /// Signature set the VAA was posted with, see `SignatureSetV1`.
+    pub fn signature_set(&self) -> Pubkey {
+        Pubkey::new_from_array(self.read_at(VAA_SIGNATURE_ACCOUNT_OFFSET))
+    }
+
+    pub fn nonce(&self) -> u32 {
+        u32::from_le_bytes(self.read_at(NONCE_OFFSET))
+    }
+
+    pub fn sequence(&self) -> u64 {
+        u64::from_le_bytes(self.read_at(SEQUENCE_OFFSET))
+    }
+
+    pub fn emitter_chain(&self) -> u16 {
+        u16::from_le_bytes(self.read_at(EMITTER_CHAIN_OFFSET))
+    }
+
+    pub fn emitter_chain_id(&self) -> ChainId {
+        self.emitter_chain().into()
+    }
+
+    pub fn emitter_address(&self) -> [u8; 32] {
+        self.read_at(EMITTER_ADDRESS_OFFSET)
+    }
+
+    pub fn emitter(&self) -> ForeignEmitter {
+        ForeignEmitter {
+            chain: self.emitter_chain(),
+            address: self.emitter_address(),
+        }
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.data[PAYLOAD_OFFSET..PAYLOAD_OFFSET + self.payload_len()]
+    }
+
+    fn payload_len(&self) -> usize {
+        u32::from_le_bytes(self.read_at(PAYLOAD_LEN_OFFSET)) as usize
+    }
+
+    fn read_at<const N: usize>(&self, offset: usize) -> [u8; N] {
+        self.data[offset..offset + N].try_into().unwrap()
+    }
+}
@@ -11,6 +11,9 @@ use solitaire::{
     Owned,
 };
 
+/// Seed prefix of sequence trackers, followed by the emitter.
+pub const SEQUENCE_SEED: &[u8] = b"Sequence";
+
 pub type Sequence<'b> = Data<'b, SequenceTracker, { AccountState::MaybeInitialized }>;
 
 #[derive(Default, Clone, Copy, BorshDeserialize, BorshSerialize)]
@@ -24,13 +27,16 @@ pub struct SequenceDerivationData<'a> {
 
 impl<'b> Seeded<&SequenceDerivationData<'b>> for Sequence<'b> {
     fn seeds(data: &SequenceDerivationData) -> Vec<Vec<u8>> {
-        vec![
-            "Sequence".as_bytes().to_vec(),
-            data.emitter_key.to_bytes().to_vec(),
-        ]
+        vec![SEQUENCE_SEED.to_vec(), data.emitter_key.to_bytes().to_vec()]
     }
 }
 
+/// Address of the sequence tracker of `emitter` in the core bridge deployed at `program_id`, along
+/// with its bump.
+pub fn find_emitter_sequence(program_id: &Pubkey, emitter: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SEQUENCE_SEED, emitter.as_ref()], program_id)
+}
+
 impl Owned for SequenceTracker {
     fn owner(&self) -> AccountOwner {
         AccountOwner::This
@@ -1,14 +1,21 @@
 //! PostedMessage
 
+use crate::error::Error::InvalidSignatureSet;
 use borsh::{
     BorshDeserialize,
     BorshSerialize,
 };
+use solana_program::account_info::AccountInfo;
 use solitaire::{
     AccountOwner,
     AccountState,
     Data,
     Owned,
+    Result,
+};
+use std::{
+    cell::Ref,
+    convert::TryInto,
 };
 
 pub type SignatureSet<'b, const State: AccountState> = Data<'b, SignatureSetData, { State }>;
@@ -25,8 +32,84 @@ pub struct SignatureSetData {
     pub guardian_set_index: u32,
 }
 
+impl SignatureSetData {
+    /// Number of guardians whose signature has been verified so far.
+    pub fn num_verified(&self) -> usize {
+        self.signatures.iter().filter(|v| **v).count()
+    }
+
+    /// Whether enough signatures have been verified to post the VAA. Verification may be spread
+    /// over any number of transactions, the guardian set is pinned by the first one.
+    pub fn is_verified(&self) -> bool {
+        self.num_verified() >= super::quorum(self.signatures.len())
+    }
+}
+
 impl Owned for SignatureSetData {
     fn owner(&self) -> AccountOwner {
         AccountOwner::This
     }
 }
+
+/// Zero-copy view of a signature set account, for auditing which guardians signed a posted VAA
+/// without deserializing the account.
+pub struct SignatureSetV1<'a> {
+    data: Ref<'a, [u8]>,
+}
+
+impl<'a> SignatureSetV1<'a> {
+    /// Borrow the data of `info`, checking that it holds a signature set. The owner is not checked,
+    /// callers must make sure the account belongs to the core bridge.
+    pub fn load(info: &'a AccountInfo) -> Result<Self> {
+        let data = Ref::map(info.data.borrow(), |data| &**data);
+        if data.len() < 4 {
+            return Err(InvalidSignatureSet.into());
+        }
+
+        let set = SignatureSetV1 { data };
+        let num_guardians = set.num_guardians();
+        if set.data.len() != 4 + num_guardians + 32 + 4
+            || set.signatures().iter().any(|signed| *signed > 1)
+        {
+            return Err(InvalidSignatureSet.into());
+        }
+        Ok(set)
+    }
+
+    /// Size of the guardian set the signatures were verified against.
+    pub fn num_guardians(&self) -> usize {
+        u32::from_le_bytes(self.data[..4].try_into().unwrap()) as usize
+    }
+
+    /// Whether the signature of the guardian at `index` has been verified.
+    pub fn is_signed(&self, index: usize) -> bool {
+        self.signatures().get(index) == Some(&1)
+    }
+
+    pub fn num_verified(&self) -> usize {
+        self.signatures()
+            .iter()
+            .filter(|signed| **signed == 1)
+            .count()
+    }
+
+    /// Whether the verified signatures reach quorum for the guardian set.
+    pub fn is_verified(&self) -> bool {
+        self.num_verified() >= super::quorum(self.num_guardians())
+    }
+
+    /// Hash of the VAA body the signatures are over.
+    pub fn hash(&self) -> [u8; 32] {
+        let offset = 4 + self.num_guardians();
+        self.data[offset..offset + 32].try_into().unwrap()
+    }
+
+    pub fn guardian_set_index(&self) -> u32 {
+        let offset = 4 + self.num_guardians() + 32;
+        u32::from_le_bytes(self.data[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn signatures(&self) -> &[u8] {
+        &self.data[4..4 + self.num_guardians()]
+    }
+}
@@ -0,0 +1,42 @@
+//! VAA consumers are the programs governance allows to close posted VAAs they have claimed, see
+//! `api::close_posted_vaa`. They are created by the set-VAA-consumer decree and are never written
+//! to by anything else.
+
+use borsh::{
+    BorshDeserialize,
+    BorshSerialize,
+};
+use solana_program::pubkey::Pubkey;
+use solitaire::{
+    processors::seeded::Seeded,
+    AccountOwner,
+    AccountState,
+    Data,
+    Owned,
+};
+
+pub type VAAConsumer<'b, const State: AccountState> = Data<'b, VAAConsumerData, { State }>;
+
+#[derive(Default, Clone, Copy, BorshDeserialize, BorshSerialize)]
+pub struct VAAConsumerData {
+    /// Cleared when governance removes the consumer, the account itself is kept.
+    pub allowed: bool,
+}
+
+pub struct VAAConsumerDerivationData {
+    pub consumer: Pubkey,
+}
+
+impl<'b, const State: AccountState> Seeded<&VAAConsumerDerivationData>
+    for VAAConsumer<'b, { State }>
+{
+    fn seeds(data: &VAAConsumerDerivationData) -> Vec<Vec<u8>> {
+        vec![b"vaa_consumer".to_vec(), data.consumer.to_bytes().to_vec()]
+    }
+}
+
+impl Owned for VAAConsumerData {
+    fn owner(&self) -> AccountOwner {
+        AccountOwner::This
+    }
+}
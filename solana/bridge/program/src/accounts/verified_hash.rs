@@ -0,0 +1,50 @@
+//! Verified hashes record that a quorum of guardians signed an arbitrary 32 byte hash, for
+//! protocols that only need guardian attestation and not a full VAA posted on chain.
+
+use borsh::{
+    BorshDeserialize,
+    BorshSerialize,
+};
+use solana_program::pubkey::Pubkey;
+use solitaire::{
+    processors::seeded::Seeded,
+    AccountOwner,
+    AccountState,
+    Data,
+    Owned,
+};
+
+pub type VerifiedHash<'b, const State: AccountState> = Data<'b, VerifiedHashData, { State }>;
+
+pub struct VerifiedHashDerivationData {
+    pub hash: [u8; 32],
+}
+
+impl<'a, const State: AccountState> Seeded<&VerifiedHashDerivationData>
+    for VerifiedHash<'a, { State }>
+{
+    fn seeds(data: &VerifiedHashDerivationData) -> Vec<Vec<u8>> {
+        vec![b"VerifiedHash".to_vec(), data.hash.to_vec()]
+    }
+}
+
+#[derive(Default, BorshSerialize, BorshDeserialize)]
+pub struct VerifiedHashData {
+    /// The hash the guardians signed, as passed to the secp256k1 program.
+    pub hash: [u8; 32],
+
+    /// Index of the guardian set that signed the hash
+    pub guardian_set_index: u32,
+
+    /// Signature set the hash was verified with
+    pub signature_set: Pubkey,
+
+    /// Time the hash was recorded
+    pub verification_time: u32,
+}
+
+impl Owned for VerifiedHashData {
+    fn owner(&self) -> AccountOwner {
+        AccountOwner::This
+    }
+}
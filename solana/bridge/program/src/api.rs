@@ -1,11 +1,21 @@
+pub mod close_posted_vaa;
+pub mod close_signature_set;
+pub mod draft_message;
 pub mod governance;
 pub mod initialize;
 pub mod post_message;
 pub mod post_vaa;
+pub mod query_fee;
+pub mod verify_hash;
 pub mod verify_signature;
 
+pub use close_posted_vaa::*;
+pub use close_signature_set::*;
+pub use draft_message::*;
 pub use governance::*;
 pub use initialize::*;
 pub use post_message::*;
 pub use post_vaa::*;
+pub use query_fee::*;
+pub use verify_hash::*;
 pub use verify_signature::*;
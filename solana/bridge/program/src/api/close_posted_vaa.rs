@@ -0,0 +1,130 @@
+use solitaire::*;
+
+use solana_program::pubkey::Pubkey;
+
+use crate::{
+    accounts::{
+        Bridge,
+        Claim,
+        ClaimData,
+        ClaimDerivationData,
+        FeeCollector,
+        PostedVAA,
+        PostedVAAData,
+        PostedVAADerivationData,
+        VAAConsumer,
+        VAAConsumerDerivationData,
+    },
+    api::post_vaa::vaa_body_hash,
+    error::Error::{
+        InvalidPostedVAA,
+        MathOverflow,
+        VAAConsumerNotAllowed,
+        VAANotClaimed,
+    },
+    PostVAAData,
+};
+use solitaire::processors::seeded::Seeded;
+
+#[derive(FromAccounts)]
+pub struct ClosePostedVAA<'b> {
+    /// Posted VAA to close.
+    pub posted_vaa: Mut<Info<'b>>,
+
+    /// Claim of the VAA, owned by the consuming program.
+    pub claim: Info<'b>,
+
+    /// Entry governance created for the consuming program, see `api::set_vaa_consumer`.
+    pub vaa_consumer: VAAConsumer<'b, { AccountState::Initialized }>,
+
+    /// Bridge config, the fee collector balance it expects grows by the rent of the posted VAA.
+    pub bridge: Mut<Bridge<'b, { AccountState::Initialized }>>,
+
+    /// Receives the rent of the posted VAA.
+    pub fee_collector: Mut<FeeCollector<'b>>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ClosePostedVAAData {
+    /// Program that consumed the VAA, its claim is derived by `claim::consume`.
+    pub consumer: Pubkey,
+}
+
+/// Close a posted VAA once `consumer` has claimed it. Only programs governance allowed count as
+/// consumers, any other program could create a claim for any VAA. Anyone may close it, posted VAAs
+/// do not record who paid for them, so the rent goes to the fee collector.
+///
+/// Other programs may still need a VAA after one consumer has claimed it. The signature set of a
+/// closed VAA stays around, so it can be posted again.
+pub fn close_posted_vaa(
+    ctx: &ExecutionContext,
+    accs: &mut ClosePostedVAA,
+    data: ClosePostedVAAData,
+) -> Result<()> {
+    accs.vaa_consumer.verify_derivation(
+        ctx.program_id,
+        &VAAConsumerDerivationData {
+            consumer: data.consumer,
+        },
+    )?;
+    if !accs.vaa_consumer.allowed {
+        return Err(VAAConsumerNotAllowed.into());
+    }
+
+    if accs.posted_vaa.owner != ctx.program_id {
+        return Err(InvalidPostedVAA.into());
+    }
+    let posted = PostedVAAData::try_from_slice(&accs.posted_vaa.data.borrow())
+        .map_err(|_| InvalidPostedVAA)?;
+
+    // Only accounts created by `post_vaa` are derived from the hash of the body they hold.
+    let payload_hash = vaa_body_hash(&PostVAAData {
+        version: posted.vaa_version,
+        guardian_set_index: 0,
+        timestamp: posted.vaa_time,
+        nonce: posted.nonce,
+        emitter_chain: posted.emitter_chain,
+        emitter_address: posted.emitter_address,
+        sequence: posted.sequence,
+        consistency_level: posted.consistency_level,
+        payload: posted.payload.clone(),
+    })?;
+    let expected = PostedVAA::<'_, { AccountState::Initialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: payload_hash.to_vec(),
+        },
+        ctx.program_id,
+    );
+    if *accs.posted_vaa.key != expected {
+        return Err(InvalidPostedVAA.into());
+    }
+
+    let claim = Claim::key(
+        &ClaimDerivationData {
+            emitter_address: posted.emitter_address,
+            emitter_chain: posted.emitter_chain,
+            sequence: posted.sequence,
+        },
+        &data.consumer,
+    );
+    let claimed = *accs.claim.key == claim
+        && *accs.claim.owner == data.consumer
+        && ClaimData::try_from_slice(&accs.claim.data.borrow())
+            .map(|claim| claim.claimed)
+            .unwrap_or(false);
+    if !claimed {
+        return Err(VAANotClaimed.into());
+    }
+
+    // The rent is not a fee, it must not count towards the surplus instructions charging fees
+    // from the fee collector balance take as paid.
+    let rent = accs.posted_vaa.lamports();
+    close_account_safe(ctx.program_id, &accs.posted_vaa, &accs.fee_collector)?;
+    accs.bridge.last_lamports = accs
+        .bridge
+        .last_lamports
+        .checked_add(rent)
+        .ok_or(MathOverflow)?;
+
+    Ok(())
+}
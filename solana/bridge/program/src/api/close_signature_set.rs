@@ -0,0 +1,66 @@
+use solitaire::*;
+
+use crate::{
+    accounts::{
+        PostedVAA,
+        PostedVAADerivationData,
+        SignatureSetV1,
+        VerifiedHash,
+        VerifiedHashDerivationData,
+    },
+    error::Error::{
+        InvalidSignatureSet,
+        SignatureSetInUse,
+    },
+};
+use solitaire::processors::seeded::Seeded;
+
+#[derive(FromAccounts)]
+pub struct CloseSignatureSet<'b> {
+    /// Signature set to close, signing with the keypair it was created with.
+    pub signature_set: Mut<Signer<Info<'b>>>,
+
+    /// Posted VAA or verified hash recorded with the signatures.
+    pub record: Info<'b>,
+
+    /// Receives the rent of the signature set.
+    pub recipient: Mut<Info<'b>>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct CloseSignatureSetData {}
+
+/// Close a signature set once the VAA or hash it verified has been recorded, refunding its rent.
+/// Signature sets do not record their payer, only the holder of the signature set keypair may
+/// close it. Sets still collecting signatures cannot be closed.
+pub fn close_signature_set(
+    ctx: &ExecutionContext,
+    accs: &mut CloseSignatureSet,
+    _data: CloseSignatureSetData,
+) -> Result<()> {
+    if accs.signature_set.owner != ctx.program_id {
+        return Err(InvalidSignatureSet.into());
+    }
+    let hash = SignatureSetV1::load(&accs.signature_set)?.hash();
+
+    let posted_vaa = PostedVAA::<'_, { AccountState::Initialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: hash.to_vec(),
+        },
+        ctx.program_id,
+    );
+    let verified_hash = VerifiedHash::<'_, { AccountState::Initialized }>::key(
+        &VerifiedHashDerivationData { hash },
+        ctx.program_id,
+    );
+    if (*accs.record.key != posted_vaa && *accs.record.key != verified_hash)
+        || accs.record.owner != ctx.program_id
+        || accs.record.data_is_empty()
+    {
+        return Err(SignatureSetInUse.into());
+    }
+
+    close_account_safe(ctx.program_id, &accs.signature_set, &accs.recipient)?;
+
+    Ok(())
+}
@@ -0,0 +1,301 @@
+use crate::{
+    accounts::{
+        Bridge,
+        DraftMessageHeader,
+        DraftStatus,
+        FeeCollector,
+        Sequence,
+        PAYLOAD_LEN_OFFSET,
+        PAYLOAD_OFFSET,
+        POSTED_MESSAGE_MAGIC,
+    },
+    api::post_message::{
+        check_payload_size,
+        post_message_internal,
+        FeePayment,
+    },
+    error::Error::{
+        DraftMessageFinalized,
+        DraftMessageNotFinalized,
+        DraftMessageOverflow,
+        InvalidDraftMessage,
+        InvalidDraftMessageEmitter,
+    },
+    types::ConsistencyLevel,
+    IsSigned::*,
+    MessageData,
+    PostMessageData,
+    POST_MESSAGE_ACCOUNTS,
+};
+use solana_program::{
+    program::set_return_data,
+    pubkey::Pubkey,
+    sysvar::clock::Clock,
+};
+use solitaire::{
+    CreationLamports::Exempt,
+    *,
+};
+
+#[derive(FromAccounts)]
+pub struct InitMessage<'b> {
+    /// Payer for the draft account
+    pub payer: Mut<Signer<Info<'b>>>,
+
+    /// Emitter the message will be posted from, the only key allowed to write to the draft
+    pub emitter: Signer<Info<'b>>,
+
+    /// Account to hold the draft, and later the posted message
+    pub message: Signer<Mut<Info<'b>>>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct InitMessageData {
+    /// Size of the payload that will be written
+    pub payload_size: u32,
+}
+
+pub fn init_message_v1(
+    ctx: &ExecutionContext,
+    accs: &mut InitMessage,
+    data: InitMessageData,
+) -> Result<()> {
+    check_payload_size(data.payload_size as usize)?;
+    if !accs.message.data_is_empty() {
+        return Err(SolitaireError::AlreadyInitialized(*accs.message.key));
+    }
+
+    create_account(
+        ctx,
+        &accs.message,
+        accs.payer.key,
+        Exempt,
+        DraftMessageHeader::account_size(data.payload_size),
+        ctx.program_id,
+        NotSigned,
+    )?;
+
+    DraftMessageHeader {
+        status: DraftStatus::Writing,
+        emitter: *accs.emitter.key,
+        payload_size: data.payload_size,
+    }
+    .write(&mut accs.message.data.borrow_mut());
+
+    Ok(())
+}
+
+#[derive(FromAccounts)]
+pub struct WriteMessage<'b> {
+    pub emitter: Signer<Info<'b>>,
+
+    pub message: Mut<Info<'b>>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct WriteMessageData {
+    /// Offset into the payload to write `data` at
+    pub offset: u32,
+
+    pub data: Vec<u8>,
+}
+
+/// Copy a chunk of the payload into a draft that is still being written.
+pub fn write_message_v1(
+    ctx: &ExecutionContext,
+    accs: &mut WriteMessage,
+    data: WriteMessageData,
+) -> Result<()> {
+    let header = load_draft(ctx, &accs.message, &accs.emitter)?;
+    if header.status != DraftStatus::Writing {
+        return Err(DraftMessageFinalized.into());
+    }
+
+    let start = data.offset as usize;
+    let end = start
+        .checked_add(data.data.len())
+        .filter(|end| *end <= header.payload_size as usize)
+        .ok_or(DraftMessageOverflow)?;
+
+    accs.message.data.borrow_mut()[PAYLOAD_OFFSET + start..PAYLOAD_OFFSET + end]
+        .copy_from_slice(&data.data);
+
+    Ok(())
+}
+
+#[derive(FromAccounts)]
+pub struct FinalizeMessage<'b> {
+    pub emitter: Signer<Info<'b>>,
+
+    pub message: Mut<Info<'b>>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct FinalizeMessageData {}
+
+/// Freeze the payload of a draft, after which it can only be posted.
+pub fn finalize_message_v1(
+    ctx: &ExecutionContext,
+    accs: &mut FinalizeMessage,
+    _data: FinalizeMessageData,
+) -> Result<()> {
+    let mut header = load_draft(ctx, &accs.message, &accs.emitter)?;
+    if header.status != DraftStatus::Writing {
+        return Err(DraftMessageFinalized.into());
+    }
+
+    header.status = DraftStatus::Finalized;
+    header.write(&mut accs.message.data.borrow_mut());
+
+    Ok(())
+}
+
+#[derive(FromAccounts)]
+pub struct PostPreparedMessage<'b> {
+    /// Bridge config needed for fee calculation.
+    pub bridge: Mut<Bridge<'b, { AccountState::Initialized }>>,
+
+    /// Finalized draft, turned into the posted message in place
+    pub message: Mut<Info<'b>>,
+
+    /// Emitter of the VAA, must be the emitter of the draft
+    pub emitter: Signer<MaybeMut<Info<'b>>>,
+
+    /// Tracker for the emitter sequence
+    pub sequence: Mut<Sequence<'b>>,
+
+    /// Payer for the sequence account
+    pub payer: Mut<Signer<Info<'b>>>,
+
+    /// Account to collect tx fee
+    pub fee_collector: Mut<FeeCollector<'b>>,
+
+    pub clock: Sysvar<'b, Clock>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct PostPreparedMessageData {
+    /// Unique nonce for this message
+    pub nonce: u32,
+
+    /// Commitment Level required for an attestation to be produced
+    pub consistency_level: ConsistencyLevel,
+}
+
+/// Post a finalized draft. Only the message header is written, the payload already sits where the
+/// posted message expects it. Like `post_message`, the fee must have been transferred to the fee
+/// collector before.
+pub fn post_prepared_message(
+    ctx: &ExecutionContext,
+    accs: &mut PostPreparedMessage,
+    data: PostPreparedMessageData,
+) -> Result<()> {
+    let draft = load_draft(ctx, &accs.message, &accs.emitter)?;
+    if draft.status != DraftStatus::Finalized {
+        return Err(DraftMessageNotFinalized.into());
+    }
+
+    let mut message = MessageData::default();
+    post_message_internal(
+        ctx,
+        &mut accs.bridge,
+        accs.message.key,
+        &mut message,
+        accs.emitter.key,
+        &mut accs.sequence,
+        &mut accs.payer,
+        &mut accs.fee_collector,
+        &mut accs.clock,
+        PostMessageData {
+            nonce: data.nonce,
+            payload: vec![],
+            consistency_level: data.consistency_level,
+        },
+        FeePayment::Surplus,
+        POST_MESSAGE_ACCOUNTS,
+    )?;
+
+    // Serialized with an empty payload, the header ends in a zero length prefix which must not
+    // overwrite the real one.
+    let mut header = POSTED_MESSAGE_MAGIC.to_vec();
+    message.serialize(&mut header)?;
+    accs.message.data.borrow_mut()[..PAYLOAD_LEN_OFFSET]
+        .copy_from_slice(&header[..PAYLOAD_LEN_OFFSET]);
+
+    set_return_data(&message.sequence.to_le_bytes());
+    Ok(())
+}
+
+#[derive(FromAccounts)]
+pub struct CloseMessage<'b> {
+    pub emitter: Signer<Info<'b>>,
+
+    pub message: Mut<Info<'b>>,
+
+    /// Receives the rent of the draft
+    pub recipient: Mut<Info<'b>>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct CloseMessageData {}
+
+/// Discard a draft, finalized or not, refunding its rent. Posted messages are no longer drafts and
+/// are rejected when the header is loaded.
+pub fn close_message_v1(
+    ctx: &ExecutionContext,
+    accs: &mut CloseMessage,
+    _data: CloseMessageData,
+) -> Result<()> {
+    load_draft(ctx, &accs.message, &accs.emitter)?;
+
+    close_account_safe(ctx.program_id, &accs.message, &accs.recipient)?;
+
+    Ok(())
+}
+
+#[derive(FromAccounts)]
+pub struct TransferMessageAuthority<'b> {
+    /// Current emitter of the draft
+    pub emitter: Signer<Info<'b>>,
+
+    pub message: Mut<Info<'b>>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct TransferMessageAuthorityData {
+    /// Emitter taking over the draft, which it will be posted from
+    pub new_emitter: Pubkey,
+}
+
+/// Hand a draft over to another emitter, for example when the key that started it is rotated.
+/// Posted messages are no longer drafts and keep their emitter.
+pub fn transfer_message_authority_v1(
+    ctx: &ExecutionContext,
+    accs: &mut TransferMessageAuthority,
+    data: TransferMessageAuthorityData,
+) -> Result<()> {
+    let mut header = load_draft(ctx, &accs.message, &accs.emitter)?;
+
+    header.emitter = data.new_emitter;
+    header.write(&mut accs.message.data.borrow_mut());
+
+    Ok(())
+}
+
+/// Read the header of a draft owned by this program, checking that it belongs to `emitter`.
+fn load_draft(
+    ctx: &ExecutionContext,
+    message: &Info,
+    emitter: &Info,
+) -> Result<DraftMessageHeader> {
+    if message.owner != ctx.program_id {
+        return Err(InvalidDraftMessage.into());
+    }
+
+    let header = DraftMessageHeader::read(&message.data.borrow())?;
+    if header.emitter != *emitter.key {
+        return Err(InvalidDraftMessageEmitter.into());
+    }
+
+    Ok(header)
+}
@@ -1,5 +1,9 @@
+use primitive_types::U256;
 use solana_program::{
-    program::invoke_signed,
+    program::{
+        invoke_signed,
+        set_return_data,
+    },
     pubkey::Pubkey,
     sysvar::{
         clock::Clock,
@@ -18,41 +22,53 @@ use crate::{
             self,
             Claim,
         },
+        valid_guardian_keys,
         Bridge,
+        FeeAuthority,
+        FeeCollector,
+        FeeExemption,
+        FeeExemptionDerivationData,
+        FeeTokenAccount,
+        FeeTokenAccountDerivationData,
         GuardianSet,
         GuardianSetDerivationData,
+        VAAConsumer,
+        VAAConsumerDerivationData,
     },
     error::Error::{
+        InsufficientSpendableFees,
+        InvalidFee,
+        InvalidFeeMint,
         InvalidFeeRecipient,
-        InvalidGovernanceKey,
+        InvalidFeeTokenAccount,
         InvalidGovernanceWithdrawal,
+        InvalidGuardianSetExpiration,
         InvalidGuardianSetUpgrade,
+        InvalidUpgradeBuffer,
+    },
+    events::{
+        self,
+        SetFeesEvent,
+        SweepFeeCollectorEvent,
+        UpgradeGuardianSetEvent,
     },
     types::{
         GovernancePayloadGuardianSetChange,
+        GovernancePayloadSetFeeExemption,
+        GovernancePayloadSetFeeMint,
+        GovernancePayloadSetGuardianSetExpiration,
         GovernancePayloadSetMessageFee,
+        GovernancePayloadSetVAAConsumer,
+        GovernancePayloadSweepFeeCollector,
         GovernancePayloadTransferFees,
         GovernancePayloadUpgrade,
     },
-    DeserializePayload,
+    vaa::require_governance_emitter,
     PayloadMessage,
-    CHAIN_ID_GOVERANCE,
+    MAX_GUARDIAN_SET_EXPIRATION_TIME,
+    MIN_GUARDIAN_SET_EXPIRATION_TIME,
 };
 
-/// Fail if the emitter is not the known governance key, or the emitting chain is not Solana.
-fn verify_governance<T>(vaa: &PayloadMessage<T>) -> Result<()>
-where
-    T: DeserializePayload,
-{
-    let expected_emitter = std::env!("EMITTER_ADDRESS");
-    let current_emitter = format!("{}", Pubkey::new_from_array(vaa.meta().emitter_address));
-    if expected_emitter != current_emitter || vaa.meta().emitter_chain != CHAIN_ID_GOVERANCE {
-        Err(InvalidGovernanceKey.into())
-    } else {
-        Ok(())
-    }
-}
-
 #[derive(FromAccounts)]
 pub struct UpgradeContract<'b> {
     /// Payer for account creation (vaa-claim)
@@ -97,9 +113,16 @@ pub fn upgrade_contract(
     accs: &mut UpgradeContract,
     _data: UpgradeContractData,
 ) -> Result<()> {
-    verify_governance(&accs.vaa)?;
+    require_governance_emitter(accs.vaa.meta())?;
     claim::consume(ctx, accs.payer.key, &mut accs.claim, &accs.vaa)?;
 
+    // Only the buffer the guardians decreed may be deployed, and only the loader can deploy it.
+    if *accs.buffer.key != accs.vaa.new_contract
+        || *accs.buffer.owner != solana_program::bpf_loader_upgradeable::id()
+    {
+        return Err(InvalidUpgradeBuffer.into());
+    }
+
     let upgrade_ix = solana_program::bpf_loader_upgradeable::upgrade(
         ctx.program_id,
         &accs.vaa.new_contract,
@@ -146,7 +169,7 @@ pub fn upgrade_guardian_set(
     accs: &mut UpgradeGuardianSet,
     _data: UpgradeGuardianSetData,
 ) -> Result<()> {
-    verify_governance(&accs.vaa)?;
+    require_governance_emitter(accs.vaa.meta())?;
     claim::consume(ctx, accs.payer.key, &mut accs.claim, &accs.vaa)?;
 
     // Enforce single increments when upgrading.
@@ -172,6 +195,10 @@ pub fn upgrade_guardian_set(
         },
     )?;
 
+    if !valid_guardian_keys(&accs.vaa.new_guardian_set) {
+        return Err(InvalidGuardianSetUpgrade.into());
+    }
+
     // Set expiration time for the old set
     accs.guardian_set_old.expiration_time =
         accs.vaa.meta().vaa_time + accs.bridge.config.guardian_set_expiration_time;
@@ -195,6 +222,14 @@ pub fn upgrade_guardian_set(
     // Set guardian set index
     accs.bridge.guardian_set_index = accs.vaa.new_guardian_set_index;
 
+    events::emit(&UpgradeGuardianSetEvent {
+        old_guardian_set_index: accs.guardian_set_old.index,
+        new_guardian_set_index: accs.guardian_set_new.index,
+        num_guardians: accs.guardian_set_new.keys.len() as u32,
+        old_guardian_set_expiration_time: accs.guardian_set_old.expiration_time,
+    })?;
+    set_return_data(&accs.guardian_set_new.index.to_le_bytes());
+
     Ok(())
 }
 
@@ -217,9 +252,60 @@ pub struct SetFees<'b> {
 pub struct SetFeesData {}
 
 pub fn set_fees(ctx: &ExecutionContext, accs: &mut SetFees, _data: SetFeesData) -> Result<()> {
-    verify_governance(&accs.vaa)?;
+    require_governance_emitter(accs.vaa.meta())?;
     claim::consume(ctx, accs.payer.key, &mut accs.claim, &accs.vaa)?;
+
+    // Fees are held as lamports, anything wider than u64 cannot be charged.
+    if accs.vaa.fee > U256::from(u64::MAX) {
+        return Err(InvalidFee.into());
+    }
+
+    let old_fee = accs.bridge.config.fee;
     accs.bridge.config.fee = accs.vaa.fee.as_u64();
+    events::emit(&SetFeesEvent {
+        old_fee,
+        new_fee: accs.bridge.config.fee,
+    })?;
+
+    Ok(())
+}
+
+#[derive(FromAccounts)]
+pub struct SetGuardianSetExpiration<'b> {
+    /// Payer for account creation (vaa-claim)
+    pub payer: Mut<Signer<Info<'b>>>,
+
+    /// Bridge config
+    pub bridge: Mut<Bridge<'b, { AccountState::Initialized }>>,
+
+    /// Governance VAA
+    pub vaa: PayloadMessage<'b, GovernancePayloadSetGuardianSetExpiration>,
+
+    /// An Uninitialized Claim account to consume the VAA.
+    pub claim: Mut<Claim<'b>>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct SetGuardianSetExpirationData {}
+
+/// Change how long guardian sets replaced from now on stay valid. Sets that were already replaced
+/// keep the expiration they were given.
+pub fn set_guardian_set_expiration(
+    ctx: &ExecutionContext,
+    accs: &mut SetGuardianSetExpiration,
+    _data: SetGuardianSetExpirationData,
+) -> Result<()> {
+    require_governance_emitter(accs.vaa.meta())?;
+    claim::consume(ctx, accs.payer.key, &mut accs.claim, &accs.vaa)?;
+
+    let expiration_time = accs.vaa.guardian_set_expiration_time;
+    if !(MIN_GUARDIAN_SET_EXPIRATION_TIME..=MAX_GUARDIAN_SET_EXPIRATION_TIME)
+        .contains(&expiration_time)
+    {
+        return Err(InvalidGuardianSetExpiration.into());
+    }
+
+    accs.bridge.config.guardian_set_expiration_time = expiration_time;
     Ok(())
 }
 
@@ -238,7 +324,7 @@ pub struct TransferFees<'b> {
     pub claim: Mut<Claim<'b>>,
 
     /// Account collecting tx fees
-    pub fee_collector: Mut<Derive<Info<'b>, "fee_collector">>,
+    pub fee_collector: Mut<FeeCollector<'b>>,
 
     /// Fee recipient
     pub recipient: Mut<Info<'b>>,
@@ -255,7 +341,7 @@ pub fn transfer_fees(
     accs: &mut TransferFees,
     _data: TransferFeesData,
 ) -> Result<()> {
-    verify_governance(&accs.vaa)?;
+    require_governance_emitter(accs.vaa.meta())?;
     claim::consume(ctx, accs.payer.key, &mut accs.claim, &accs.vaa)?;
 
     // Make sure the account loaded to receive funds is equal to the one the VAA requested.
@@ -263,16 +349,20 @@ pub fn transfer_fees(
         return Err(InvalidFeeRecipient.into());
     }
 
-    let new_balance = accs
-        .fee_collector
-        .lamports()
-        .saturating_sub(accs.vaa.amount.as_u64());
-
-    if new_balance < accs.rent.minimum_balance(accs.fee_collector.data_len()) {
+    // Anything wider than u64 is more than the fee collector could ever hold.
+    if accs.vaa.amount > U256::from(u64::MAX) {
         return Err(InvalidGovernanceWithdrawal.into());
     }
 
-    accs.bridge.last_lamports = new_balance;
+    if let Some(mint) = accs.vaa.mint {
+        return transfer_token_fees(ctx, &mint, accs.recipient.key, accs.vaa.amount.as_u64());
+    }
+
+    if accs.vaa.amount.as_u64() > spendable_fees(&accs.fee_collector, &accs.rent) {
+        return Err(InsufficientSpendableFees.into());
+    }
+
+    accs.bridge.last_lamports = accs.fee_collector.lamports() - accs.vaa.amount.as_u64();
     // Transfer fees
     let transfer_ix = solana_program::system_instruction::transfer(
         accs.fee_collector.key,
@@ -287,3 +377,306 @@ pub fn transfer_fees(
 
     Ok(())
 }
+
+/// Lamports the fee collector can pay out without dropping below the rent exemption for its data.
+fn spendable_fees(fee_collector: &Info, rent: &Rent) -> u64 {
+    fee_collector
+        .lamports()
+        .saturating_sub(rent.minimum_balance(fee_collector.data_len()))
+}
+
+/// Pay out token fees from the fee token account of `mint`. Like for posting messages, the accounts
+/// needed are passed last: the fee token account, the fee authority and the token program.
+fn transfer_token_fees(
+    ctx: &ExecutionContext,
+    mint: &Pubkey,
+    recipient: &Pubkey,
+    amount: u64,
+) -> Result<()> {
+    let (fee_account, fee_authority, token_program) = match ctx.accounts {
+        [.., fee_account, fee_authority, token_program] => {
+            (fee_account, fee_authority, token_program)
+        }
+        _ => return Err(InvalidFeeTokenAccount.into()),
+    };
+
+    let expected_fee_account = FeeTokenAccount::<'_, { AccountState::Initialized }>::key(
+        &FeeTokenAccountDerivationData { mint: *mint },
+        ctx.program_id,
+    );
+    if *fee_account.key != expected_fee_account
+        || *fee_authority.key != FeeAuthority::key(None, ctx.program_id)
+        || *token_program.key != spl_token::id()
+    {
+        return Err(InvalidFeeTokenAccount.into());
+    }
+
+    // The token program fails the transfer should the fee token account hold less than `amount`.
+    let transfer_ix = spl_token::instruction::transfer(
+        &spl_token::id(),
+        fee_account.key,
+        recipient,
+        fee_authority.key,
+        &[],
+        amount,
+    )?;
+    let seeds = FeeAuthority::bumped_seeds(None, ctx.program_id);
+    let seeds: Vec<&[u8]> = seeds.iter().map(|item| item.as_slice()).collect();
+    let seeds = seeds.as_slice();
+    invoke_signed(&transfer_ix, ctx.accounts, &[seeds])?;
+
+    Ok(())
+}
+
+#[derive(FromAccounts)]
+pub struct SweepFeeCollector<'b> {
+    /// Payer for account creation (vaa-claim)
+    pub payer: Mut<Signer<Info<'b>>>,
+
+    /// Bridge config
+    pub bridge: Mut<Bridge<'b, { AccountState::Initialized }>>,
+
+    /// Governance VAA
+    pub vaa: PayloadMessage<'b, GovernancePayloadSweepFeeCollector>,
+
+    /// An Uninitialized Claim account to consume the VAA.
+    pub claim: Mut<Claim<'b>>,
+
+    /// Account collecting tx fees
+    pub fee_collector: Mut<FeeCollector<'b>>,
+
+    /// Fee recipient
+    pub recipient: Mut<Info<'b>>,
+
+    /// Rent calculator to find the rent exemption of the fee collector.
+    pub rent: Sysvar<'b, Rent>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct SweepFeeCollectorData {}
+
+/// Pay out whatever the fee collector holds above its rent exemption and the decreed reserve.
+/// Unlike `transfer_fees` the amount is not part of the decree, so lamports donated to the
+/// collector are swept along with the fees and a sweep with nothing to pay out still succeeds.
+pub fn sweep_fee_collector(
+    ctx: &ExecutionContext,
+    accs: &mut SweepFeeCollector,
+    _data: SweepFeeCollectorData,
+) -> Result<()> {
+    require_governance_emitter(accs.vaa.meta())?;
+    claim::consume(ctx, accs.payer.key, &mut accs.claim, &accs.vaa)?;
+
+    if accs.vaa.to != accs.recipient.key.to_bytes() {
+        return Err(InvalidFeeRecipient.into());
+    }
+
+    let amount = spendable_fees(&accs.fee_collector, &accs.rent).saturating_sub(accs.vaa.reserve);
+
+    // The collector is a system account, so its lamports can only be moved by the system program.
+    if amount > 0 {
+        let transfer_ix = solana_program::system_instruction::transfer(
+            accs.fee_collector.key,
+            accs.recipient.key,
+            amount,
+        );
+        let seeds = accs.fee_collector.self_bumped_seeds(None, ctx.program_id);
+        let seeds: Vec<&[u8]> = seeds.iter().map(|item| item.as_slice()).collect();
+        let seeds = seeds.as_slice();
+        invoke_signed(&transfer_ix, ctx.accounts, &[seeds])?;
+    }
+
+    // Donations no longer count towards the fees of later messages.
+    accs.bridge.last_lamports = accs.fee_collector.lamports();
+
+    events::emit(&SweepFeeCollectorEvent {
+        amount,
+        recipient: accs.vaa.to,
+    })?;
+
+    Ok(())
+}
+
+#[derive(FromAccounts)]
+pub struct SetFeeMint<'b> {
+    /// Payer for account creation (vaa-claim, fee token account and bridge resize)
+    pub payer: Mut<Signer<Info<'b>>>,
+
+    /// Bridge config
+    pub bridge: Mut<Bridge<'b, { AccountState::Initialized }>>,
+
+    /// Governance VAA
+    pub vaa: PayloadMessage<'b, GovernancePayloadSetFeeMint>,
+
+    /// An Uninitialized Claim account to consume the VAA.
+    pub claim: Mut<Claim<'b>>,
+
+    /// Authority of the fee token account
+    pub fee_authority: FeeAuthority<'b>,
+
+    /// Fee token account of the decreed mint, created if it does not exist yet
+    pub fee_token_account: Mut<FeeTokenAccount<'b, { AccountState::MaybeInitialized }>>,
+
+    /// Decreed mint, unused when switching back to lamports
+    pub mint: Info<'b>,
+
+    /// Rent calculator for the fee token account.
+    pub rent: Sysvar<'b, Rent>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct SetFeeMintData {}
+
+/// Switch message fees to the decreed mint, or back to lamports when the decree names the default
+/// pubkey. The fee token account of a mint is kept when switching away from it, so fees collected
+/// in it can still be paid out with `transfer_fees`.
+pub fn set_fee_mint(
+    ctx: &ExecutionContext,
+    accs: &mut SetFeeMint,
+    _data: SetFeeMintData,
+) -> Result<()> {
+    require_governance_emitter(accs.vaa.meta())?;
+    claim::consume(ctx, accs.payer.key, &mut accs.claim, &accs.vaa)?;
+
+    if accs.vaa.mint == Pubkey::default() {
+        accs.bridge.config.fee_mint = None;
+        accs.bridge.config.token_fee = 0;
+    } else {
+        if *accs.mint.key != accs.vaa.mint {
+            return Err(InvalidFeeMint.into());
+        }
+
+        let derivation_data = FeeTokenAccountDerivationData {
+            mint: accs.vaa.mint,
+        };
+        let bump = accs
+            .fee_token_account
+            .verify_derivation_bump(ctx.program_id, &derivation_data)?;
+
+        if !accs.fee_token_account.is_initialized() {
+            accs.fee_token_account.create_with_bump(
+                &derivation_data,
+                bump,
+                ctx,
+                accs.payer.key,
+                Exempt,
+            )?;
+
+            // Fails unless the mint is an initialized token mint.
+            let init_ix = spl_token::instruction::initialize_account(
+                &spl_token::id(),
+                accs.fee_token_account.info().key,
+                accs.mint.key,
+                accs.fee_authority.key,
+            )?;
+            invoke_signed(&init_ix, ctx.accounts, &[])?;
+        }
+
+        accs.bridge.config.fee_mint = Some(accs.vaa.mint);
+        accs.bridge.config.token_fee = accs.vaa.fee;
+    }
+
+    // The token fee is only stored while a fee mint is set, resize the config to match.
+    let size = accs.bridge.size();
+    realloc_safe(ctx, accs.bridge.info(), size, accs.payer.key)?;
+
+    Ok(())
+}
+
+#[derive(FromAccounts)]
+pub struct SetFeeExemption<'b> {
+    /// Payer for account creation (vaa-claim and fee exemption)
+    pub payer: Mut<Signer<Info<'b>>>,
+
+    /// Bridge config
+    pub bridge: Mut<Bridge<'b, { AccountState::Initialized }>>,
+
+    /// Governance VAA
+    pub vaa: PayloadMessage<'b, GovernancePayloadSetFeeExemption>,
+
+    /// An Uninitialized Claim account to consume the VAA.
+    pub claim: Mut<Claim<'b>>,
+
+    /// Fee exemption of the decreed emitter, created if it does not exist yet
+    pub fee_exemption: Mut<FeeExemption<'b, { AccountState::MaybeInitialized }>>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct SetFeeExemptionData {}
+
+/// Exempt the decreed emitter from message fees, or revoke its exemption.
+pub fn set_fee_exemption(
+    ctx: &ExecutionContext,
+    accs: &mut SetFeeExemption,
+    _data: SetFeeExemptionData,
+) -> Result<()> {
+    require_governance_emitter(accs.vaa.meta())?;
+    claim::consume(ctx, accs.payer.key, &mut accs.claim, &accs.vaa)?;
+
+    let derivation_data = FeeExemptionDerivationData {
+        emitter: accs.vaa.emitter,
+    };
+    let bump = accs
+        .fee_exemption
+        .verify_derivation_bump(ctx.program_id, &derivation_data)?;
+
+    // Revoking an exemption that never existed leaves nothing to write.
+    if !accs.fee_exemption.is_initialized() {
+        if !accs.vaa.exempt {
+            return Ok(());
+        }
+        accs.fee_exemption
+            .create_with_bump(&derivation_data, bump, ctx, accs.payer.key, Exempt)?;
+    }
+    accs.fee_exemption.exempt = accs.vaa.exempt;
+
+    Ok(())
+}
+
+#[derive(FromAccounts)]
+pub struct SetVAAConsumer<'b> {
+    /// Payer for account creation (vaa-claim and VAA consumer)
+    pub payer: Mut<Signer<Info<'b>>>,
+
+    /// Bridge config
+    pub bridge: Mut<Bridge<'b, { AccountState::Initialized }>>,
+
+    /// Governance VAA
+    pub vaa: PayloadMessage<'b, GovernancePayloadSetVAAConsumer>,
+
+    /// An Uninitialized Claim account to consume the VAA.
+    pub claim: Mut<Claim<'b>>,
+
+    /// VAA consumer entry of the decreed program, created if it does not exist yet
+    pub vaa_consumer: Mut<VAAConsumer<'b, { AccountState::MaybeInitialized }>>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct SetVAAConsumerData {}
+
+/// Allow the decreed program to close the posted VAAs it has claimed, or take that back.
+pub fn set_vaa_consumer(
+    ctx: &ExecutionContext,
+    accs: &mut SetVAAConsumer,
+    _data: SetVAAConsumerData,
+) -> Result<()> {
+    require_governance_emitter(accs.vaa.meta())?;
+    claim::consume(ctx, accs.payer.key, &mut accs.claim, &accs.vaa)?;
+
+    let derivation_data = VAAConsumerDerivationData {
+        consumer: accs.vaa.consumer,
+    };
+    let bump = accs
+        .vaa_consumer
+        .verify_derivation_bump(ctx.program_id, &derivation_data)?;
+
+    if !accs.vaa_consumer.is_initialized() {
+        if !accs.vaa.allowed {
+            return Ok(());
+        }
+        accs.vaa_consumer
+            .create_with_bump(&derivation_data, bump, ctx, accs.payer.key, Exempt)?;
+    }
+    accs.vaa_consumer.allowed = accs.vaa.allowed;
+
+    Ok(())
+}
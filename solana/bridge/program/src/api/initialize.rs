@@ -1,12 +1,16 @@
 use crate::{
     accounts::{
+        valid_guardian_keys,
         Bridge,
         BridgeConfig,
         FeeCollector,
         GuardianSet,
         GuardianSetDerivationData,
     },
-    error::Error::TooManyGuardians,
+    error::Error::{
+        InvalidGuardianKeys,
+        TooManyGuardians,
+    },
     MAX_LEN_GUARDIAN_KEYS,
 };
 use solana_program::sysvar::clock::Clock;
@@ -59,6 +63,9 @@ pub fn initialize(
     if data.initial_guardians.len() > MAX_LEN_GUARDIAN_KEYS {
         return Err(TooManyGuardians.into());
     }
+    if !valid_guardian_keys(&data.initial_guardians) {
+        return Err(InvalidGuardianKeys.into());
+    }
 
     // Allocate initial guardian set with the provided keys.
     accs.guardian_set.index = index;
@@ -79,6 +86,8 @@ pub fn initialize(
     accs.bridge.config = BridgeConfig {
         guardian_set_expiration_time: data.guardian_set_expiration_time,
         fee: data.fee,
+        fee_mint: None,
+        token_fee: 0,
     };
 
     // Initialize the fee collector account so it's rent exempt and will keep funds
@@ -2,7 +2,13 @@ use crate::{
     accounts::{
         Bridge,
         FeeCollector,
+        FeeExemption,
+        FeeExemptionData,
+        FeeExemptionDerivationData,
+        FeeTokenAccount,
+        FeeTokenAccountDerivationData,
         PostedMessage,
+        PostedMessageDerivationData,
         PostedMessageUnreliable,
         Sequence,
         SequenceDerivationData,
@@ -10,17 +16,28 @@ use crate::{
     error::Error::{
         EmitterChanged,
         InsufficientFees,
+        InvalidEmitterAuthority,
+        InvalidFeeTokenAccount,
         InvalidPayloadLength,
         MathOverflow,
+        OversizedPayload,
     },
     types::ConsistencyLevel,
     IsSigned::*,
     MessageData,
     CHAIN_ID_SOLANA,
+    EMITTER_AUTHORITY_SEED,
+    MAX_MESSAGE_PAYLOAD_SIZE,
+    POST_MESSAGE_ACCOUNTS,
 };
 use solana_program::{
     msg,
+    program::{
+        invoke,
+        set_return_data,
+    },
     pubkey::Pubkey,
+    system_instruction,
     sysvar::clock::Clock,
 };
 use solitaire::{
@@ -46,7 +63,56 @@ pub struct PostMessage<'b> {
     /// Tracker for the emitter sequence
     pub sequence: Mut<Sequence<'b>>,
 
-    /// Payer for account creation
+    /// Payer for account creation, and for the message fee in `post_message_v2`
+    pub payer: Mut<Signer<Info<'b>>>,
+
+    /// Account to collect tx fee
+    pub fee_collector: Mut<FeeCollector<'b>>,
+
+    pub clock: Sysvar<'b, Clock>,
+}
+
+#[derive(FromAccounts)]
+pub struct PostMessageDerived<'b> {
+    /// Bridge config needed for fee calculation.
+    pub bridge: Mut<Bridge<'b, { AccountState::Initialized }>>,
+
+    /// Account to store the posted message, derived from the emitter and its next sequence
+    pub message: Mut<UninitializedMessage<'b>>,
+
+    /// Emitter of the VAA
+    pub emitter: Signer<MaybeMut<Info<'b>>>,
+
+    /// Tracker for the emitter sequence
+    pub sequence: Mut<Sequence<'b>>,
+
+    /// Payer for account creation and the message fee
+    pub payer: Mut<Signer<Info<'b>>>,
+
+    /// Account to collect tx fee
+    pub fee_collector: Mut<FeeCollector<'b>>,
+
+    pub clock: Sysvar<'b, Clock>,
+}
+
+#[derive(FromAccounts)]
+pub struct PostMessageProgramEmitter<'b> {
+    /// Bridge config needed for fee calculation.
+    pub bridge: Mut<Bridge<'b, { AccountState::Initialized }>>,
+
+    /// Account to store the posted message
+    pub message: Signer<Mut<UninitializedMessage<'b>>>,
+
+    /// PDA of `emitter_program` derived from `EMITTER_AUTHORITY_SEED`, signing for it
+    pub emitter_authority: Signer<Info<'b>>,
+
+    /// Program the message is posted from, its id is the emitter of the VAA
+    pub emitter_program: Info<'b>,
+
+    /// Tracker for the sequence of `emitter_program`
+    pub sequence: Mut<Sequence<'b>>,
+
+    /// Payer for account creation and the message fee
     pub payer: Mut<Signer<Info<'b>>>,
 
     /// Account to collect tx fee
@@ -90,22 +156,151 @@ pub struct PostMessageData {
     pub consistency_level: ConsistencyLevel,
 }
 
+/// How the lamport fee of a message is paid.
+#[derive(Clone, Copy)]
+pub(crate) enum FeePayment {
+    /// The fee was transferred to the fee collector before the instruction, the lamports the
+    /// collector gained since the last message pay for this one.
+    Surplus,
+    /// The bridge transfers the fee from the payer within the instruction.
+    Pull,
+}
+
+/// Post a message paid for by a transfer of the fee to the fee collector earlier in the
+/// transaction. As the fee is read from the balance of the collector, only one such message can
+/// be posted per transfer.
 pub fn post_message(
     ctx: &ExecutionContext,
     accs: &mut PostMessage,
     data: PostMessageData,
+) -> Result<()> {
+    post_message_paid(ctx, accs, data, FeePayment::Surplus)
+}
+
+/// Post a message, collecting the fee from the payer within the instruction. The fee never depends
+/// on transfers made earlier in the transaction, so any number of messages can be posted in a single
+/// transaction.
+pub fn post_message_v2(
+    ctx: &ExecutionContext,
+    accs: &mut PostMessage,
+    data: PostMessageData,
+) -> Result<()> {
+    post_message_paid(ctx, accs, data, FeePayment::Pull)
+}
+
+fn post_message_paid(
+    ctx: &ExecutionContext,
+    accs: &mut PostMessage,
+    data: PostMessageData,
+    fee_payment: FeePayment,
+) -> Result<()> {
+    post_message_internal(
+        ctx,
+        &mut accs.bridge,
+        accs.message.info().key,
+        &mut accs.message,
+        accs.emitter.key,
+        &mut accs.sequence,
+        &mut accs.payer,
+        &mut accs.fee_collector,
+        &mut accs.clock,
+        data,
+        fee_payment,
+        POST_MESSAGE_ACCOUNTS,
+    )?;
+
+    // Create message account
+    let size = accs.message.size();
+    create_account(
+        ctx,
+        accs.message.info(),
+        accs.payer.key,
+        Exempt,
+        size,
+        ctx.program_id,
+        NotSigned,
+    )?;
+
+    // Set last, creating the account above would clear it.
+    set_return_data(&accs.message.sequence.to_le_bytes());
+    Ok(())
+}
+
+/// Post a message to a PDA of the bridge derived from `["msg", emitter, sequence]` rather than a
+/// keypair account, so programs can post purely through CPI without the client signing for a new
+/// account. The fee is collected from the payer like in `post_message_v2`.
+pub fn post_message_derived(
+    ctx: &ExecutionContext,
+    accs: &mut PostMessageDerived,
+    data: PostMessageData,
 ) -> Result<()> {
     post_message_internal(
         ctx,
         &mut accs.bridge,
         accs.message.info().key,
         &mut accs.message,
-        &mut accs.emitter,
+        accs.emitter.key,
         &mut accs.sequence,
         &mut accs.payer,
         &mut accs.fee_collector,
         &mut accs.clock,
         data,
+        FeePayment::Pull,
+        POST_MESSAGE_ACCOUNTS,
+    )?;
+
+    // The message carries the sequence it was assigned, before the tracker was bumped.
+    let derivation_data = PostedMessageDerivationData {
+        emitter_key: accs.emitter.key,
+        sequence: accs.message.sequence,
+    };
+    let seeds = UninitializedMessage::bumped_seeds(&derivation_data, ctx.program_id);
+    let s: Vec<&[u8]> = seeds.iter().map(|item| item.as_slice()).collect();
+    let size = accs.message.size();
+    // Fails unless the message account is the one derived from the sequence.
+    create_pda_account(
+        ctx,
+        accs.message.info(),
+        accs.payer.key,
+        Exempt,
+        size,
+        ctx.program_id,
+        &s,
+    )?;
+
+    set_return_data(&accs.message.sequence.to_le_bytes());
+    Ok(())
+}
+
+/// Post a message with the id of the calling program as the emitter. This is a separate mode from
+/// posting with a PDA of the program as the emitter: the VAA carries the program id itself and the
+/// sequence is tracked for the program id, so foreign contracts can whitelist the program without
+/// knowing any seeds. Only the program can sign for its `EMITTER_AUTHORITY_SEED` PDA, which proves
+/// the message comes from it. The fee is collected from the payer like in `post_message_v2`.
+pub fn post_message_program_emitter(
+    ctx: &ExecutionContext,
+    accs: &mut PostMessageProgramEmitter,
+    data: PostMessageData,
+) -> Result<()> {
+    let (authority, _) =
+        Pubkey::find_program_address(&[EMITTER_AUTHORITY_SEED], accs.emitter_program.key);
+    if authority != *accs.emitter_authority.key {
+        return Err(InvalidEmitterAuthority.into());
+    }
+
+    post_message_internal(
+        ctx,
+        &mut accs.bridge,
+        accs.message.info().key,
+        &mut accs.message,
+        accs.emitter_program.key,
+        &mut accs.sequence,
+        &mut accs.payer,
+        &mut accs.fee_collector,
+        &mut accs.clock,
+        data,
+        FeePayment::Pull,
+        POST_MESSAGE_ACCOUNTS + 1,
     )?;
 
     // Create message account
@@ -120,11 +315,137 @@ pub fn post_message(
         NotSigned,
     )?;
 
+    set_return_data(&accs.message.sequence.to_le_bytes());
+    Ok(())
+}
+
+/// Reject payloads that would not fit a message account the bridge can allocate.
+pub(crate) fn check_payload_size(size: usize) -> Result<()> {
+    if size > MAX_MESSAGE_PAYLOAD_SIZE {
+        msg!(
+            "Payload of {} bytes exceeds the maximum of {}",
+            size,
+            MAX_MESSAGE_PAYLOAD_SIZE
+        );
+        return Err(OversizedPayload.into());
+    }
+    Ok(())
+}
+
+/// Charge the lamport fee of a message as `fee_payment` says. While governance has set a fee mint
+/// the fee is paid in tokens instead, always taken from the payer within the instruction, see
+/// `collect_token_fee`. Emitters exempted by governance pay nothing, see `is_fee_exempt`.
+#[allow(clippy::too_many_arguments)]
+fn collect_fee(
+    ctx: &ExecutionContext,
+    bridge: &mut Bridge<'_, { AccountState::Initialized }>,
+    emitter: &Pubkey,
+    payer: &Info,
+    fee_collector: &Info,
+    fee_payment: FeePayment,
+    fee_accounts: usize,
+) -> Result<()> {
+    if is_fee_exempt(ctx, emitter, fee_accounts)? {
+        return Ok(());
+    }
+
+    if let Some(mint) = bridge.config.fee_mint {
+        return collect_token_fee(ctx, &mint, bridge.config.token_fee, payer);
+    }
+
+    let fee = bridge.config.fee;
+    match fee_payment {
+        FeePayment::Surplus => {
+            // Checking the previously known balance allows us to not care who paid the fee.
+            if fee_collector
+                .lamports()
+                .checked_sub(bridge.last_lamports)
+                .ok_or(MathOverflow)?
+                < fee
+            {
+                trace!(
+                    "Expected fee not found: fee, last_lamports, collector: {} {} {}",
+                    fee,
+                    bridge.last_lamports,
+                    fee_collector.lamports(),
+                );
+                return Err(InsufficientFees.into());
+            }
+            bridge.last_lamports = fee_collector.lamports();
+        }
+        FeePayment::Pull => {
+            if fee > 0 {
+                let transfer_ix = system_instruction::transfer(payer.key, fee_collector.key, fee);
+                invoke(&transfer_ix, ctx.accounts)?;
+            }
+            // Only the pulled fee is accounted for, a transfer meant for a later `post_message` in
+            // the same transaction still pays for it.
+            bridge.last_lamports = bridge.last_lamports.checked_add(fee).ok_or(MathOverflow)?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether governance exempted `emitter` from message fees. The fee exemption of the emitter is not
+/// part of the instruction layouts, it is the first account after them at index `fee_accounts`,
+/// see `instructions::with_fee_exemption`. Any other account there, such as the first of the token
+/// fee accounts, leaves the fee as it is.
+fn is_fee_exempt(ctx: &ExecutionContext, emitter: &Pubkey, fee_accounts: usize) -> Result<bool> {
+    // Only accounts created by the bridge are worth deriving the exemption address for.
+    let exemption = match ctx.accounts.get(fee_accounts) {
+        Some(exemption) if exemption.owner == ctx.program_id && !exemption.data_is_empty() => {
+            exemption
+        }
+        _ => return Ok(false),
+    };
+
+    let expected = FeeExemption::<'_, { AccountState::Initialized }>::key(
+        &FeeExemptionDerivationData { emitter: *emitter },
+        ctx.program_id,
+    );
+    if *exemption.key != expected {
+        return Ok(false);
+    }
+
+    Ok(FeeExemptionData::try_from_slice(&exemption.data.borrow())?.exempt)
+}
+
+/// Transfer the token fee from a token account of the payer to the fee token account of `mint`.
+/// The accounts for that are not part of the instruction layouts, they must be passed last, after
+/// any fee exemption: the token account of the payer, the fee token account and the token program.
+/// See `instructions::with_token_fee`.
+fn collect_token_fee(ctx: &ExecutionContext, mint: &Pubkey, fee: u64, payer: &Info) -> Result<()> {
+    let (from, fee_account, token_program) = match ctx.accounts {
+        [.., from, fee_account, token_program] => (from, fee_account, token_program),
+        _ => return Err(InvalidFeeTokenAccount.into()),
+    };
+
+    // The token program checks that the payer owns `from` and that its mint matches.
+    let expected_fee_account = FeeTokenAccount::<'_, { AccountState::Initialized }>::key(
+        &FeeTokenAccountDerivationData { mint: *mint },
+        ctx.program_id,
+    );
+    if *fee_account.key != expected_fee_account || *token_program.key != spl_token::id() {
+        return Err(InvalidFeeTokenAccount.into());
+    }
+
+    if fee > 0 {
+        let transfer_ix = spl_token::instruction::transfer(
+            &spl_token::id(),
+            from.key,
+            fee_account.key,
+            payer.key,
+            &[],
+            fee,
+        )?;
+        invoke(&transfer_ix, ctx.accounts)?;
+    }
     Ok(())
 }
 
 /// Post a message while reusing the message account. This saves the rent that would be required for
-/// allocating a new message account. When an account is reused and the guardians don't pick up the
+/// allocating a new message account. The account is grown when a larger payload is posted, but
+/// payloads can never shrink. When an account is reused and the guardians don't pick up the
 /// message due to network instability or a bug there is NO way to recover the message if it has
 /// been overwritten. This makes this instruction useful for use-cases that require high number of
 /// messages to be published but don't require 100% delivery guarantee.
@@ -134,8 +455,10 @@ pub fn post_message_unreliable(
     accs: &mut PostMessageUnreliable,
     data: PostMessageData,
 ) -> Result<()> {
-    // Accounts can't be resized so the payload sizes need to match
-    if accs.message.is_initialized() && accs.message.payload.len() != data.payload.len() {
+    check_payload_size(data.payload.len())?;
+
+    // Accounts are only grown, a smaller payload would leave trailing bytes from the last message
+    if accs.message.is_initialized() && accs.message.payload.len() > data.payload.len() {
         return Err(InvalidPayloadLength.into());
     }
     // The emitter must be identical
@@ -144,17 +467,26 @@ pub fn post_message_unreliable(
         return Err(EmitterChanged.into());
     }
 
+    // Grow the account for a larger payload, topping up rent so it stays exempt.
+    if accs.message.is_initialized() && accs.message.payload.len() < data.payload.len() {
+        let info = accs.message.info();
+        let size = info.data_len() + data.payload.len() - accs.message.payload.len();
+        realloc_safe(ctx, info, size, accs.payer.key)?;
+    }
+
     post_message_internal(
         ctx,
         &mut accs.bridge,
         accs.message.info().key,
         &mut accs.message,
-        &mut accs.emitter,
+        accs.emitter.key,
         &mut accs.sequence,
         &mut accs.payer,
         &mut accs.fee_collector,
         &mut accs.clock,
         data,
+        FeePayment::Surplus,
+        POST_MESSAGE_ACCOUNTS,
     )?;
 
     if !accs.message.is_initialized() {
@@ -171,54 +503,50 @@ pub fn post_message_unreliable(
         )?;
     }
 
+    set_return_data(&accs.message.sequence.to_le_bytes());
     Ok(())
 }
 
 #[allow(clippy::too_many_arguments)]
-fn post_message_internal<'b>(
+pub(crate) fn post_message_internal<'b>(
     ctx: &ExecutionContext,
     bridge: &mut Mut<Bridge<'b, { AccountState::Initialized }>>,
     #[cfg_attr(not(feature = "trace"), allow(unused_variables))] message_key: &Pubkey,
     message: &mut MessageData,
-    emitter: &mut Signer<MaybeMut<Info<'b>>>,
+    emitter: &Pubkey,
     sequence: &mut Mut<Sequence<'b>>,
     payer: &mut Mut<Signer<Info<'b>>>,
     fee_collector: &mut Mut<FeeCollector<'b>>,
     clock: &mut Sysvar<'b, Clock>,
     data: PostMessageData,
+    fee_payment: FeePayment,
+    fee_accounts: usize,
 ) -> Result<()> {
+    check_payload_size(data.payload.len())?;
+
     trace!("Message Address: {}", message_key);
-    trace!("Emitter Address: {}", emitter.info().key);
+    trace!("Emitter Address: {}", emitter);
     trace!("Nonce: {}", data.nonce);
 
     let sequence_derivation = SequenceDerivationData {
-        emitter_key: emitter.key,
+        emitter_key: emitter,
     };
-    sequence.verify_derivation(ctx.program_id, &sequence_derivation)?;
+    let sequence_bump = sequence.verify_derivation_bump(ctx.program_id, &sequence_derivation)?;
 
-    let fee = bridge.config.fee;
-    // Fee handling, checking previously known balance allows us to not care who is the payer of
-    // this submission.
-    if fee_collector
-        .lamports()
-        .checked_sub(bridge.last_lamports)
-        .ok_or(MathOverflow)?
-        < fee
-    {
-        trace!(
-            "Expected fee not found: fee, last_lamports, collector: {} {} {}",
-            fee,
-            bridge.last_lamports,
-            fee_collector.lamports(),
-        );
-        return Err(InsufficientFees.into());
-    }
-    bridge.last_lamports = fee_collector.lamports();
+    collect_fee(
+        ctx,
+        bridge,
+        emitter,
+        payer,
+        fee_collector,
+        fee_payment,
+        fee_accounts,
+    )?;
 
     // Init sequence tracker if it does not exist yet.
     if !sequence.is_initialized() {
         trace!("Initializing Sequence account to 0.");
-        sequence.create(&sequence_derivation, ctx, payer.key, Exempt)?;
+        sequence.create_with_bump(&sequence_derivation, sequence_bump, ctx, payer.key, Exempt)?;
     }
 
     // DO NOT REMOVE - CRITICAL OUTPUT
@@ -228,14 +556,11 @@ fn post_message_internal<'b>(
     trace!("Setting Message Details");
     message.submission_time = clock.unix_timestamp as u32;
     message.emitter_chain = CHAIN_ID_SOLANA;
-    message.emitter_address = emitter.key.to_bytes();
+    message.emitter_address = emitter.to_bytes();
     message.nonce = data.nonce;
     message.payload = data.payload;
     message.sequence = sequence.sequence;
-    message.consistency_level = match data.consistency_level {
-        ConsistencyLevel::Confirmed => 1,
-        ConsistencyLevel::Finalized => 32,
-    };
+    message.consistency_level = data.consistency_level.into();
 
     // Bump sequence number
     trace!("New Sequence: {}", sequence.sequence + 1);
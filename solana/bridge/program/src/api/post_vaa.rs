@@ -11,6 +11,7 @@ use solana_program::{
 
 use crate::{
     accounts::{
+        quorum,
         Bridge,
         GuardianSet,
         GuardianSetDerivationData,
@@ -24,6 +25,11 @@ use crate::{
         PostVAAGuardianSetExpired,
         VAAInvalid,
     },
+    events::{
+        self,
+        PostVAAEvent,
+    },
+    vaa::hash_vaa_body,
 };
 use byteorder::{
     BigEndian,
@@ -33,7 +39,6 @@ use serde::{
     Deserialize,
     Serialize,
 };
-use sha3::Digest;
 use solana_program::program_error::ProgramError;
 use solitaire::{
     processors::seeded::Seeded,
@@ -104,10 +109,14 @@ pub fn post_vaa(ctx: &ExecutionContext, accs: &mut PostVAA, vaa: PostVAAData) ->
         payload_hash: accs.signature_set.hash.to_vec(),
     };
 
-    accs.message
-        .verify_derivation(ctx.program_id, &msg_derivation)?;
+    let bump = accs
+        .message
+        .verify_derivation_bump(ctx.program_id, &msg_derivation)?;
+    // Any guardian set may be passed as long as it is the one the VAA claims to be signed by, older
+    // sets stay usable until they expire.
     accs.guardian_set
-        .verify_derivation(ctx.program_id, &(&vaa).into())?;
+        .verify_derivation(ctx.program_id, &(&vaa).into())
+        .map_err(|_| GuardianSetMismatch)?;
 
     if accs.message.is_initialized() {
         return Ok(());
@@ -119,19 +128,10 @@ pub fn post_vaa(ctx: &ExecutionContext, accs: &mut PostVAA, vaa: PostVAAData) ->
     check_integrity(&vaa, &accs.signature_set)?;
 
     // Count the number of signatures currently present.
-    let signature_count: usize = accs.signature_set.signatures.iter().filter(|v| **v).count();
-
-    // Calculate how many signatures are required to reach consensus. This calculation is in
-    // expanded form to ease auditing.
-    let required_consensus_count = {
-        let len = accs.guardian_set.keys.len();
-        // Fixed point number transformation with one decimal to deal with rounding.
-        let len = (len * 10) / 3;
-        // Multiplication by two to get a 2/3 quorum.
-        let len = len * 2;
-        // Division to bring number back into range.
-        len / 10 + 1
-    };
+    let signature_count = accs.signature_set.num_verified();
+
+    // Calculate how many signatures are required to reach consensus.
+    let required_consensus_count = quorum(accs.guardian_set.keys.len());
 
     if signature_count < required_consensus_count {
         return Err(PostVAAConsensusFailed.into());
@@ -148,14 +148,23 @@ pub fn post_vaa(ctx: &ExecutionContext, accs: &mut PostVAA, vaa: PostVAAData) ->
     accs.message.vaa_time = vaa.timestamp;
     accs.message.vaa_signature_account = *accs.signature_set.info().key;
     accs.message
-        .create(&msg_derivation, ctx, accs.payer.key, Exempt)?;
+        .create_with_bump(&msg_derivation, bump, ctx, accs.payer.key, Exempt)?;
+
+    events::emit(&PostVAAEvent {
+        hash: accs.signature_set.hash,
+        emitter_chain: vaa.emitter_chain,
+        emitter_address: vaa.emitter_address,
+        sequence: vaa.sequence,
+        guardian_set_index: accs.guardian_set.index,
+    })?;
 
     Ok(())
 }
 
-/// A guardian set must not have expired.
+/// A guardian set must not have expired. Sets without an expiration time are those that were never
+/// replaced, anything else is valid strictly before its expiration time.
 #[inline(always)]
-fn check_active<'r>(
+pub(crate) fn check_active<'r>(
     guardian_set: &GuardianSet<'r, { AccountState::Initialized }>,
     clock: &Sysvar<'r, Clock>,
 ) -> Result<()> {
@@ -165,7 +174,7 @@ fn check_active<'r>(
         return Err(PostVAAGuardianSetExpired.into());
     }
     if guardian_set.expiration_time != 0
-        && (guardian_set.expiration_time as i64) < clock.unix_timestamp
+        && (guardian_set.expiration_time as i64) <= clock.unix_timestamp
     {
         return Err(PostVAAGuardianSetExpired.into());
     }
@@ -194,7 +203,7 @@ static INVALID_SIGNATURES: &[&str; 16] = &[
 
 /// The signatures in this instruction must be from the right guardian set.
 #[inline(always)]
-fn check_valid_sigs<'r>(
+pub(crate) fn check_valid_sigs<'r>(
     guardian_set: &GuardianSet<'r, { AccountState::Initialized }>,
     signatures: &SignatureSet<'r, { AccountState::Initialized }>,
 ) -> Result<()> {
@@ -215,6 +224,16 @@ fn check_integrity<'r>(
     vaa: &PostVAAData,
     signatures: &SignatureSet<'r, { AccountState::Initialized }>,
 ) -> Result<()> {
+    // The body hash is expected to be the same as the hash currently stored in the signature
+    // account, binding that set of signatures to this VAA.
+    if signatures.hash != vaa_body_hash(vaa)? {
+        return Err(ProgramError::InvalidAccountData.into());
+    }
+    Ok(())
+}
+
+/// Hash of the VAA body, the message the guardians sign and the seed of the posted VAA account.
+pub(crate) fn vaa_body_hash(vaa: &PostVAAData) -> Result<[u8; 32]> {
     // Serialize the VAA body into an array of bytes.
     let body = {
         let mut v = Cursor::new(Vec::new());
@@ -228,17 +247,5 @@ fn check_integrity<'r>(
         v.into_inner()
     };
 
-    // Hash this body, which is expected to be the same as the hash currently stored in the
-    // signature account, binding that set of signatures to this VAA.
-    let body_hash: [u8; 32] = {
-        let mut h = sha3::Keccak256::default();
-        h.write(body.as_slice())
-            .map_err(|_| ProgramError::InvalidArgument)?;
-        h.finalize().into()
-    };
-
-    if signatures.hash != body_hash {
-        return Err(ProgramError::InvalidAccountData.into());
-    }
-    Ok(())
+    Ok(hash_vaa_body(&body))
 }
@@ -0,0 +1,27 @@
+use solitaire::*;
+
+use solana_program::program::set_return_data;
+
+use crate::accounts::Bridge;
+
+#[derive(FromAccounts)]
+pub struct QueryFee<'b> {
+    /// Bridge config holding the current fee.
+    pub bridge: Bridge<'b, { AccountState::Initialized }>,
+}
+
+#[derive(Default, BorshSerialize, BorshDeserialize)]
+pub struct QueryFeeData {}
+
+/// Write the fee currently charged per message as return data, little endian like the sequence
+/// returned by `post_message`. Programs calling this in the same transaction they post in see the
+/// fee the post will be charged, even if a fee decree lands after the transaction was built. While
+/// a fee mint is set the fee is in the smallest unit of that mint rather than lamports.
+pub fn query_fee(_ctx: &ExecutionContext, accs: &mut QueryFee, _data: QueryFeeData) -> Result<()> {
+    let fee = match accs.bridge.config.fee_mint {
+        Some(_) => accs.bridge.config.token_fee,
+        None => accs.bridge.config.fee,
+    };
+    set_return_data(&fee.to_le_bytes());
+    Ok(())
+}
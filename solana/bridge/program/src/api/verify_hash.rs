@@ -0,0 +1,92 @@
+use solitaire::*;
+
+use solana_program::sysvar::clock::Clock;
+
+use crate::{
+    accounts::{
+        quorum,
+        GuardianSet,
+        GuardianSetDerivationData,
+        SignatureSet,
+        VerifiedHash,
+        VerifiedHashDerivationData,
+    },
+    api::post_vaa::{
+        check_active,
+        check_valid_sigs,
+    },
+    error::Error::{
+        GuardianSetMismatch,
+        PostVAAConsensusFailed,
+    },
+};
+use solitaire::{
+    processors::seeded::Seeded,
+    CreationLamports::Exempt,
+};
+
+#[derive(FromAccounts)]
+pub struct VerifyHash<'b> {
+    /// Guardian set the signatures were verified against.
+    pub guardian_set: GuardianSet<'b, { AccountState::Initialized }>,
+
+    /// Signature Info
+    pub signature_set: SignatureSet<'b, { AccountState::Initialized }>,
+
+    /// Record of the verified hash, derived from the hash of the signature set.
+    pub verified_hash: Mut<VerifiedHash<'b, { AccountState::MaybeInitialized }>>,
+
+    /// Payer for the verified hash account.
+    pub payer: Mut<Signer<Info<'b>>>,
+
+    /// Clock used for timestamping.
+    pub clock: Sysvar<'b, Clock>,
+}
+
+#[derive(Default, BorshSerialize, BorshDeserialize)]
+pub struct VerifyHashData {}
+
+/// Record that a quorum of guardians signed the hash of `signature_set`. The hash is not a VAA
+/// body, signatures are collected with `verify_signatures` as usual and attest to nothing but the
+/// hash itself. Like posted VAAs, a hash is only recorded once.
+pub fn verify_hash(
+    ctx: &ExecutionContext,
+    accs: &mut VerifyHash,
+    _data: VerifyHashData,
+) -> Result<()> {
+    let derivation = VerifiedHashDerivationData {
+        hash: accs.signature_set.hash,
+    };
+
+    let bump = accs
+        .verified_hash
+        .verify_derivation_bump(ctx.program_id, &derivation)?;
+    accs.guardian_set
+        .verify_derivation(
+            ctx.program_id,
+            &GuardianSetDerivationData {
+                index: accs.signature_set.guardian_set_index,
+            },
+        )
+        .map_err(|_| GuardianSetMismatch)?;
+
+    if accs.verified_hash.is_initialized() {
+        return Ok(());
+    }
+
+    check_active(&accs.guardian_set, &accs.clock)?;
+    check_valid_sigs(&accs.guardian_set, &accs.signature_set)?;
+
+    if accs.signature_set.num_verified() < quorum(accs.guardian_set.keys.len()) {
+        return Err(PostVAAConsensusFailed.into());
+    }
+
+    accs.verified_hash.hash = accs.signature_set.hash;
+    accs.verified_hash.guardian_set_index = accs.guardian_set.index;
+    accs.verified_hash.signature_set = *accs.signature_set.info().key;
+    accs.verified_hash.verification_time = accs.clock.unix_timestamp as u32;
+    accs.verified_hash
+        .create_with_bump(&derivation, bump, ctx, accs.payer.key, Exempt)?;
+
+    Ok(())
+}
@@ -8,13 +8,13 @@ use crate::{
         InvalidHash,
         InvalidSecpInstruction,
     },
+    precompile,
     GuardianSet,
     GuardianSetDerivationData,
     IsSigned::*,
     SignatureSet,
     MAX_LEN_GUARDIAN_KEYS,
 };
-use byteorder::ByteOrder;
 use solana_program::program_error::ProgramError;
 use solitaire::{
     processors::seeded::Seeded,
@@ -50,6 +50,12 @@ pub struct VerifySignaturesData {
     pub signers: [i8; MAX_LEN_GUARDIAN_KEYS],
 }
 
+#[derive(Default, BorshSerialize, BorshDeserialize)]
+pub struct VerifySignaturesV2Data {
+    /// instruction indices of signers (-1 for missing), one entry per guardian
+    pub signers: Vec<i8>,
+}
+
 /// SigInfo contains metadata about signers in a VerifySignature ix
 struct SigInfo {
     /// index of the signer in the guardianset
@@ -58,22 +64,33 @@ struct SigInfo {
     sig_index: u8,
 }
 
-struct SecpInstructionPart<'a> {
-    address: &'a [u8],
-    msg_offset: u16,
-    msg_size: u16,
-}
-
 pub fn verify_signatures(
     ctx: &ExecutionContext,
     accs: &mut VerifySignatures,
     data: VerifySignaturesData,
+) -> Result<()> {
+    verify_signatures_internal(ctx, accs, &data.signers)
+}
+
+/// Like `verify_signatures`, but without the limit of `MAX_LEN_GUARDIAN_KEYS` guardians the signer
+/// indices of the original instruction data are fixed to.
+pub fn verify_signatures_v2(
+    ctx: &ExecutionContext,
+    accs: &mut VerifySignatures,
+    data: VerifySignaturesV2Data,
+) -> Result<()> {
+    verify_signatures_internal(ctx, accs, &data.signers)
+}
+
+fn verify_signatures_internal(
+    ctx: &ExecutionContext,
+    accs: &mut VerifySignatures,
+    signers: &[i8],
 ) -> Result<()> {
     accs.guardian_set
         .verify_derivation(ctx.program_id, &(&*accs).into())?;
 
-    let sig_infos: Vec<SigInfo> = data
-        .signers
+    let sig_infos: Vec<SigInfo> = signers
         .iter()
         .enumerate()
         .filter_map(|(i, p)| {
@@ -95,60 +112,24 @@ pub fn verify_signatures(
     }
 
     // The previous ix must be a secp verification instruction
-    let secp_ix_index = (current_instruction - 1) as u8;
-    let secp_ix = solana_program::sysvar::instructions::load_instruction_at_checked(
-        secp_ix_index as usize,
-        &accs.instruction_acc,
-    )
-    .map_err(|_| ProgramError::InvalidAccountData)?;
+    let secp_ix_index = current_instruction - 1;
+    let secp_ix =
+        precompile::load_instruction_at_checked(secp_ix_index as usize, &accs.instruction_acc)?;
 
     // Check that the instruction is actually for the secp program
     if secp_ix.program_id != solana_program::secp256k1_program::id() {
         return Err(InvalidSecpInstruction.into());
     }
 
-    let secp_data_len = secp_ix.data.len();
-    if secp_data_len < 2 {
-        return Err(InvalidSecpInstruction.into());
-    }
+    let secp_ixs = precompile::parse_secp256k1_instruction(secp_ix_index, &secp_ix.data)?;
+    let sig_len = secp_ixs.len();
 
-    let sig_len = secp_ix.data[0];
-    let mut index = 1;
-
-    let mut secp_ixs: Vec<SecpInstructionPart> = Vec::with_capacity(sig_len as usize);
-    for i in 0..sig_len {
-        let _sig_offset = byteorder::LE::read_u16(&secp_ix.data[index..index + 2]) as usize;
-        index += 2;
-        let sig_ix = secp_ix.data[index];
-        index += 1;
-        let address_offset = byteorder::LE::read_u16(&secp_ix.data[index..index + 2]) as usize;
-        index += 2;
-        let address_ix = secp_ix.data[index];
-        index += 1;
-        let msg_offset = byteorder::LE::read_u16(&secp_ix.data[index..index + 2]);
-        index += 2;
-        let msg_size = byteorder::LE::read_u16(&secp_ix.data[index..index + 2]);
-        index += 2;
-        let msg_ix = secp_ix.data[index];
-        index += 1;
-
-        if address_ix != secp_ix_index || msg_ix != secp_ix_index || sig_ix != secp_ix_index {
-            return Err(InvalidSecpInstruction.into());
-        }
-
-        let address: &[u8] = &secp_ix.data[address_offset..address_offset + 20];
-
-        // Make sure that all messages are equal
-        if i > 0 {
-            if msg_offset != secp_ixs[0].msg_offset || msg_size != secp_ixs[0].msg_size {
-                return Err(InvalidSecpInstruction.into());
-            }
-        }
-        secp_ixs.push(SecpInstructionPart {
-            address,
-            msg_offset,
-            msg_size,
-        });
+    // Make sure that all messages are equal
+    let first = secp_ixs.first().ok_or(InvalidSecpInstruction)?;
+    if secp_ixs.iter().any(|secp| {
+        secp.message_offset != first.message_offset || secp.message_size != first.message_size
+    }) {
+        return Err(InvalidSecpInstruction.into());
     }
 
     if sig_infos.len() != secp_ixs.len() {
@@ -156,13 +137,12 @@ pub fn verify_signatures(
     }
 
     // Data must be a hash
-    if secp_ixs[0].msg_size != 32 {
+    if first.message_size != 32 {
         return Err(ProgramError::InvalidArgument.into());
     }
 
     // Extract message which is encoded in Solana Secp256k1 instruction data.
-    let message = &secp_ix.data
-        [secp_ixs[0].msg_offset as usize..(secp_ixs[0].msg_offset + secp_ixs[0].msg_size) as usize];
+    let message = first.message(&secp_ix.data);
 
     // Hash the message part, which contains the serialized VAA body.
     let mut msg_hash: [u8; 32] = [0u8; 32];
@@ -196,17 +176,17 @@ pub fn verify_signatures(
 
     // Write sigs of checked addresses into sig_state
     for s in sig_infos {
-        if s.signer_index > accs.guardian_set.num_guardians() {
+        if s.signer_index >= accs.guardian_set.num_guardians() {
             return Err(ProgramError::InvalidArgument.into());
         }
 
-        if s.sig_index + 1 > sig_len {
+        if s.sig_index as usize + 1 > sig_len {
             return Err(ProgramError::InvalidArgument.into());
         }
 
         let key = accs.guardian_set.keys[s.signer_index as usize];
         // Check key in ix
-        if key != secp_ixs[s.sig_index as usize].address {
+        if key != secp_ixs[s.sig_index as usize].eth_address {
             return Err(ProgramError::InvalidArgument.into());
         }
 
@@ -214,5 +194,10 @@ pub fn verify_signatures(
         accs.signature_set.signatures[s.signer_index as usize] = true;
     }
 
+    trace!(
+        "Verified signatures: {}, quorum reached: {}",
+        accs.signature_set.num_verified(),
+        accs.signature_set.is_verified()
+    );
     Ok(())
 }
@@ -0,0 +1,594 @@
+//! Wrappers for programs that call into the core bridge, so integrators do not have to assemble
+//! the instructions and account lists by hand.
+
+use crate::{
+    accounts::{
+        BridgeData,
+        BridgeV1,
+        GuardianSetData,
+        SequenceTracker,
+    },
+    api::post_message::check_payload_size,
+    error::Error::{
+        InvalidFeeTokenAccount,
+        InvalidReturnData,
+    },
+    instructions,
+    PostMessageData,
+    PostPreparedMessageData,
+    PostVAAData,
+    VerifySignaturesData,
+    VerifySignaturesV2Data,
+};
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::AccountInfo,
+    instruction::{
+        AccountMeta,
+        Instruction,
+    },
+    program::{
+        get_return_data,
+        invoke,
+        invoke_signed,
+    },
+};
+use solitaire::Result;
+use std::convert::TryInto;
+
+/// Accounts required by the core bridge to post a message.
+pub struct PostMessage<'a, 'b> {
+    pub bridge: &'a AccountInfo<'b>,
+    /// Either a fresh keypair or a PDA of the calling program, in which case its seeds must be
+    /// passed along to `post_message`.
+    pub message: &'a AccountInfo<'b>,
+    /// Authority the message is sequenced under, a keypair or a PDA of the calling program. To post
+    /// with the program id itself as the emitter use `post_message_program_emitter` instead.
+    pub emitter: &'a AccountInfo<'b>,
+    pub sequence: &'a AccountInfo<'b>,
+    /// Funds the message and sequence accounts. It signs on its own and need not be related to
+    /// the emitter, so a PDA emitter never has to hold lamports.
+    pub payer: &'a AccountInfo<'b>,
+    pub fee_collector: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+    pub rent: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub core_bridge_program: &'a AccountInfo<'b>,
+    /// Fee exemption of the emitter, see `instructions::with_fee_exemption`. Messages of an emitter
+    /// governance exempted are posted without a fee.
+    pub fee_exemption: Option<&'a AccountInfo<'b>>,
+    /// Required while governance has set a fee mint, unless the emitter is exempt.
+    pub token_fee: Option<TokenFee<'a, 'b>>,
+}
+
+/// Accounts paying the message fee in tokens while governance has set a fee mint, see
+/// `instructions::with_token_fee`.
+pub struct TokenFee<'a, 'b> {
+    /// Token account of the fee mint, owned by the payer of the message.
+    pub payer_token_account: &'a AccountInfo<'b>,
+    /// Fee token account of the bridge for the fee mint.
+    pub fee_token_account: &'a AccountInfo<'b>,
+    pub token_program: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> TokenFee<'a, 'b> {
+    /// Append these accounts to a posting instruction `ix` and the accounts it is invoked with.
+    /// They must be the last accounts of the instruction.
+    pub fn append_to(&self, ix: &mut Instruction, account_infos: &mut Vec<AccountInfo<'b>>) {
+        account_infos.extend(vec![
+            self.payer_token_account.clone(),
+            self.fee_token_account.clone(),
+            self.token_program.clone(),
+        ]);
+        ix.accounts.extend(token_fee_metas(
+            self.payer_token_account,
+            self.fee_token_account,
+            self.token_program,
+        ));
+    }
+}
+
+/// Post a message through the core bridge and return the sequence it was assigned.
+///
+/// This pays the fee because it builds `instructions::post_message_v2`, which transfers the current
+/// fee from `accs.payer` to the fee collector within the posting instruction. The legacy
+/// `instructions::post_message` instead expects the fee to have been transferred ahead of it and
+/// must not be substituted here.
+///
+/// `signer_seeds` are the seeds of any PDAs of the calling program signing for the message,
+/// usually the emitter.
+pub fn post_message(
+    accs: PostMessage,
+    data: PostMessageData,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<u64> {
+    check_payload_size(data.payload.len())?;
+
+    let ix = instructions::post_message_v2(
+        *accs.core_bridge_program.key,
+        *accs.payer.key,
+        *accs.emitter.key,
+        *accs.message.key,
+        data.nonce,
+        data.payload,
+        data.consistency_level,
+    )?;
+
+    invoke_post(&accs, ix, signer_seeds)
+}
+
+/// Name of `post_message` stating that it pays the fee and posts in one call, for integrators
+/// looking for the helper by what it does.
+pub fn post_message_with_fee(
+    accs: PostMessage,
+    data: PostMessageData,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<u64> {
+    post_message(accs, data, signer_seeds)
+}
+
+/// Like `post_message`, but the message account is a PDA of the bridge derived from the emitter and
+/// the sequence the message will be assigned, so no keypair has to sign for it. `accs.message` must
+/// be that address, see `instructions::post_message_derived`.
+pub fn post_message_derived(
+    accs: PostMessage,
+    data: PostMessageData,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<u64> {
+    check_payload_size(data.payload.len())?;
+
+    // The tracker is created with the first message, until then the next sequence is 0.
+    let sequence = if accs.sequence.data_is_empty() {
+        0
+    } else {
+        SequenceTracker::try_from_slice(&accs.sequence.data.borrow())?.sequence
+    };
+
+    let ix = instructions::post_message_derived(
+        *accs.core_bridge_program.key,
+        *accs.payer.key,
+        *accs.emitter.key,
+        sequence,
+        data.nonce,
+        data.payload,
+        data.consistency_level,
+    )?;
+
+    invoke_post(&accs, ix, signer_seeds)
+}
+
+/// Like `post_message`, but reusing the message account of the last unreliable message posted by
+/// the emitter. See `api::post_message_unreliable` for the delivery caveats. The message fee must
+/// already have been transferred to the fee collector, e.g. earlier in the transaction.
+pub fn post_message_unreliable(
+    accs: PostMessage,
+    data: PostMessageData,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<u64> {
+    check_payload_size(data.payload.len())?;
+
+    let ix = instructions::post_message_unreliable(
+        *accs.core_bridge_program.key,
+        *accs.payer.key,
+        *accs.emitter.key,
+        *accs.message.key,
+        data.nonce,
+        data.payload,
+        data.consistency_level,
+    )?;
+
+    invoke_post(&accs, ix, signer_seeds)
+}
+
+/// Fee currently charged per message, read from the bridge config without deserializing it. This is
+/// the amount to transfer to the fee collector before `post_message_unreliable` or
+/// `post_prepared_message`, the other wrappers have the bridge take it from the payer. While
+/// governance has set a fee mint messages pay `BridgeV1::token_fee` in that mint instead.
+pub fn message_fee(bridge: &AccountInfo) -> Result<u64> {
+    Ok(BridgeV1::load(bridge)?.fee())
+}
+
+/// Accounts required to query the message fee.
+pub struct QueryFee<'a, 'b> {
+    pub bridge: &'a AccountInfo<'b>,
+    pub core_bridge_program: &'a AccountInfo<'b>,
+}
+
+/// Fee currently charged per message, as reported by the bridge itself. Unlike `message_fee` this
+/// does not rely on the layout of the bridge config.
+pub fn query_fee(accs: QueryFee) -> Result<u64> {
+    let ix = instructions::query_fee(*accs.core_bridge_program.key);
+
+    invoke(
+        &ix,
+        &[accs.bridge.clone(), accs.core_bridge_program.clone()],
+    )?;
+
+    u64_from_return_data(accs.core_bridge_program)
+}
+
+/// Accounts required by the core bridge to post a message with the calling program as the emitter.
+pub struct PostMessageProgramEmitter<'a, 'b> {
+    pub bridge: &'a AccountInfo<'b>,
+    pub message: &'a AccountInfo<'b>,
+    /// PDA of the calling program derived from `EMITTER_AUTHORITY_SEED`, its seeds must be passed
+    /// along to `post_message_program_emitter`.
+    pub emitter_authority: &'a AccountInfo<'b>,
+    /// The calling program, which becomes the emitter of the message.
+    pub emitter_program: &'a AccountInfo<'b>,
+    /// Sequence tracker of the calling program.
+    pub sequence: &'a AccountInfo<'b>,
+    pub payer: &'a AccountInfo<'b>,
+    pub fee_collector: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+    pub rent: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub core_bridge_program: &'a AccountInfo<'b>,
+    /// Fee exemption of the emitter, see `instructions::with_fee_exemption`. Messages of an emitter
+    /// governance exempted are posted without a fee.
+    pub fee_exemption: Option<&'a AccountInfo<'b>>,
+    /// Required while governance has set a fee mint, unless the emitter is exempt.
+    pub token_fee: Option<TokenFee<'a, 'b>>,
+}
+
+/// Post a message whose emitter is the id of the calling program rather than one of its PDAs, and
+/// return the sequence it was assigned. Foreign contracts then whitelist the program id directly.
+/// The bridge takes the message fee from `accs.payer` itself.
+pub fn post_message_program_emitter(
+    accs: PostMessageProgramEmitter,
+    data: PostMessageData,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<u64> {
+    check_payload_size(data.payload.len())?;
+
+    let ix = instructions::post_message_program_emitter(
+        *accs.core_bridge_program.key,
+        *accs.payer.key,
+        *accs.emitter_program.key,
+        *accs.message.key,
+        data.nonce,
+        data.payload,
+        data.consistency_level,
+    )?;
+
+    let mut account_infos = vec![
+        accs.bridge.clone(),
+        accs.message.clone(),
+        accs.emitter_authority.clone(),
+        accs.emitter_program.clone(),
+        accs.sequence.clone(),
+        accs.payer.clone(),
+        accs.fee_collector.clone(),
+        accs.clock.clone(),
+        accs.rent.clone(),
+        accs.system_program.clone(),
+        accs.core_bridge_program.clone(),
+    ];
+    let ix = pass_fee_accounts(
+        ix,
+        accs.fee_exemption,
+        accs.token_fee.as_ref(),
+        &mut account_infos,
+    );
+
+    invoke_signed(&ix, &account_infos, signer_seeds)?;
+
+    u64_from_return_data(accs.core_bridge_program)
+}
+
+/// Accounts required to write a draft message.
+pub struct WriteMessage<'a, 'b> {
+    pub emitter: &'a AccountInfo<'b>,
+    pub message: &'a AccountInfo<'b>,
+    pub core_bridge_program: &'a AccountInfo<'b>,
+}
+
+/// Accounts required to allocate a draft message.
+pub struct InitMessage<'a, 'b> {
+    pub payer: &'a AccountInfo<'b>,
+    pub emitter: &'a AccountInfo<'b>,
+    pub message: &'a AccountInfo<'b>,
+    pub rent: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub core_bridge_program: &'a AccountInfo<'b>,
+}
+
+/// Allocate a draft message of `payload_size` bytes, writable by `accs.emitter` only.
+pub fn init_message(accs: InitMessage, payload_size: u32, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+    check_payload_size(payload_size as usize)?;
+
+    let ix = instructions::init_message(
+        *accs.core_bridge_program.key,
+        *accs.payer.key,
+        *accs.emitter.key,
+        *accs.message.key,
+        payload_size,
+    )?;
+
+    invoke_signed(
+        &ix,
+        &[
+            accs.payer.clone(),
+            accs.emitter.clone(),
+            accs.message.clone(),
+            accs.rent.clone(),
+            accs.system_program.clone(),
+            accs.core_bridge_program.clone(),
+        ],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// Write `data` into the payload of a draft message at `offset`.
+pub fn write_message(
+    accs: WriteMessage,
+    offset: u32,
+    data: Vec<u8>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let ix = instructions::write_message(
+        *accs.core_bridge_program.key,
+        *accs.emitter.key,
+        *accs.message.key,
+        offset,
+        data,
+    )?;
+
+    invoke_signed(
+        &ix,
+        &[
+            accs.emitter.clone(),
+            accs.message.clone(),
+            accs.core_bridge_program.clone(),
+        ],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// Freeze the payload of a draft message so it can be posted.
+pub fn finalize_message(accs: WriteMessage, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+    let ix = instructions::finalize_message(
+        *accs.core_bridge_program.key,
+        *accs.emitter.key,
+        *accs.message.key,
+    )?;
+
+    invoke_signed(
+        &ix,
+        &[
+            accs.emitter.clone(),
+            accs.message.clone(),
+            accs.core_bridge_program.clone(),
+        ],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// Post a finalized draft message and return the sequence it was assigned. The message fee must
+/// already have been transferred to the fee collector, e.g. earlier in the transaction.
+pub fn post_prepared_message(
+    accs: PostMessage,
+    data: PostPreparedMessageData,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<u64> {
+    let ix = instructions::post_prepared_message(
+        *accs.core_bridge_program.key,
+        *accs.payer.key,
+        *accs.emitter.key,
+        *accs.message.key,
+        data.nonce,
+        data.consistency_level,
+    )?;
+
+    invoke_post(&accs, ix, signer_seeds)
+}
+
+/// Accounts required to record guardian signatures in a signature set.
+pub struct VerifySignatures<'a, 'b> {
+    pub payer: &'a AccountInfo<'b>,
+    /// Guardian set the VAA was signed by.
+    pub guardian_set: &'a AccountInfo<'b>,
+    /// Either a fresh keypair or a PDA of the calling program, in which case its seeds must be
+    /// passed along. It has to sign every batch of signatures recorded in it.
+    pub signature_set: &'a AccountInfo<'b>,
+    pub instructions: &'a AccountInfo<'b>,
+    pub rent: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub core_bridge_program: &'a AccountInfo<'b>,
+}
+
+/// Record the signatures checked by the secp256k1 instruction in `accs.signature_set`. The bridge
+/// reads that instruction from the transaction, it must directly precede the instruction of the
+/// calling program.
+pub fn verify_signatures(
+    accs: VerifySignatures,
+    data: VerifySignaturesData,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let ix = instructions::verify_signatures(
+        *accs.core_bridge_program.key,
+        *accs.payer.key,
+        guardian_set_index(accs.guardian_set)?,
+        *accs.signature_set.key,
+        data,
+    )?;
+
+    invoke_verify_signatures(&accs, &ix, signer_seeds)
+}
+
+/// Like `verify_signatures`, for guardian sets larger than `MAX_LEN_GUARDIAN_KEYS`.
+pub fn verify_signatures_v2(
+    accs: VerifySignatures,
+    data: VerifySignaturesV2Data,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let ix = instructions::verify_signatures_v2(
+        *accs.core_bridge_program.key,
+        *accs.payer.key,
+        guardian_set_index(accs.guardian_set)?,
+        *accs.signature_set.key,
+        data,
+    )?;
+
+    invoke_verify_signatures(&accs, &ix, signer_seeds)
+}
+
+fn invoke_verify_signatures(
+    accs: &VerifySignatures,
+    ix: &Instruction,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    invoke_signed(
+        ix,
+        &[
+            accs.payer.clone(),
+            accs.guardian_set.clone(),
+            accs.signature_set.clone(),
+            accs.instructions.clone(),
+            accs.rent.clone(),
+            accs.system_program.clone(),
+            accs.core_bridge_program.clone(),
+        ],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+fn guardian_set_index(guardian_set: &AccountInfo) -> Result<u32> {
+    Ok(GuardianSetData::deserialize(&mut &guardian_set.data.borrow()[..])?.index)
+}
+
+/// Accounts required to post a VAA whose signatures have been verified.
+pub struct PostVAA<'a, 'b> {
+    pub guardian_set: &'a AccountInfo<'b>,
+    pub bridge: &'a AccountInfo<'b>,
+    /// Signature set holding a quorum of signatures for the VAA.
+    pub signature_set: &'a AccountInfo<'b>,
+    /// Posted VAA account, derived from the hash of the VAA body.
+    pub posted_vaa: &'a AccountInfo<'b>,
+    pub payer: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+    pub rent: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub core_bridge_program: &'a AccountInfo<'b>,
+}
+
+/// Post a VAA once `accs.signature_set` holds a quorum of signatures for it. Posting is
+/// permissionless, `signer_seeds` are only needed if the payer is a PDA of the calling program.
+pub fn post_vaa(accs: PostVAA, vaa: PostVAAData, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+    let ix = instructions::post_vaa(
+        *accs.core_bridge_program.key,
+        *accs.payer.key,
+        *accs.signature_set.key,
+        vaa,
+    );
+
+    invoke_signed(
+        &ix,
+        &[
+            accs.guardian_set.clone(),
+            accs.bridge.clone(),
+            accs.signature_set.clone(),
+            accs.posted_vaa.clone(),
+            accs.payer.clone(),
+            accs.clock.clone(),
+            accs.rent.clone(),
+            accs.system_program.clone(),
+            accs.core_bridge_program.clone(),
+        ],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+fn invoke_post(accs: &PostMessage, ix: Instruction, signer_seeds: &[&[&[u8]]]) -> Result<u64> {
+    let mut account_infos = vec![
+        accs.bridge.clone(),
+        accs.message.clone(),
+        accs.emitter.clone(),
+        accs.sequence.clone(),
+        accs.payer.clone(),
+        accs.fee_collector.clone(),
+        accs.clock.clone(),
+        accs.rent.clone(),
+        accs.system_program.clone(),
+        accs.core_bridge_program.clone(),
+    ];
+    let ix = pass_fee_accounts(
+        ix,
+        accs.fee_exemption,
+        accs.token_fee.as_ref(),
+        &mut account_infos,
+    );
+
+    invoke_signed(&ix, &account_infos, signer_seeds)?;
+
+    u64_from_return_data(accs.core_bridge_program)
+}
+
+/// Pass the fee exemption of the emitter and the token fee accounts along, if there are any. The
+/// token fee accounts stay last.
+fn pass_fee_accounts<'b>(
+    mut ix: Instruction,
+    fee_exemption: Option<&AccountInfo<'b>>,
+    token_fee: Option<&TokenFee<'_, 'b>>,
+    account_infos: &mut Vec<AccountInfo<'b>>,
+) -> Instruction {
+    if let Some(fee_exemption) = fee_exemption {
+        account_infos.push(fee_exemption.clone());
+        ix = instructions::insert_fee_exemption(ix, *fee_exemption.key);
+    }
+    if let Some(token_fee) = token_fee {
+        token_fee.append_to(&mut ix, account_infos);
+    }
+    ix
+}
+
+/// For programs that post messages on behalf of their callers and build the posting instruction
+/// `ix` themselves: while `bridge` has a fee mint set, pass on the token fee accounts the caller
+/// passed as the last accounts of the calling instruction, see `instructions::token_fee_accounts`.
+/// The bridge checks them when it collects the fee.
+pub fn forward_token_fee(
+    mut ix: Instruction,
+    bridge: &BridgeData,
+    accounts: &[AccountInfo],
+) -> Result<Instruction> {
+    if bridge.config.fee_mint.is_none() {
+        return Ok(ix);
+    }
+
+    match accounts {
+        [.., from, fee_account, token_program] => {
+            ix.accounts
+                .extend(token_fee_metas(from, fee_account, token_program));
+            Ok(ix)
+        }
+        _ => Err(InvalidFeeTokenAccount.into()),
+    }
+}
+
+fn token_fee_metas(
+    from: &AccountInfo,
+    fee_account: &AccountInfo,
+    token_program: &AccountInfo,
+) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(*from.key, false),
+        AccountMeta::new(*fee_account.key, false),
+        AccountMeta::new_readonly(*token_program.key, false),
+    ]
+}
+
+/// The bridge writes the sequence of a newly posted message, or the queried fee, as return data.
+fn u64_from_return_data(core_bridge_program: &AccountInfo) -> Result<u64> {
+    match get_return_data() {
+        Some((program, value)) if program == *core_bridge_program.key => {
+            let value = value.try_into().map_err(|_| InvalidReturnData)?;
+            Ok(u64::from_le_bytes(value))
+        }
+        _ => Err(InvalidReturnData.into()),
+    }
+}
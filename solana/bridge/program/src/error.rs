@@ -26,6 +26,31 @@ pub enum Error {
     VAAInvalid,
     InvalidPayloadLength,
     EmitterChanged,
+    OversizedPayload,
+    InvalidReturnData,
+    InvalidDraftMessage,
+    DraftMessageFinalized,
+    DraftMessageNotFinalized,
+    DraftMessageOverflow,
+    InvalidDraftMessageEmitter,
+    InvalidEmitterAuthority,
+    InvalidPostedMessage,
+    InvalidConsistencyLevel,
+    InvalidPostedVAA,
+    VAANotClaimed,
+    InvalidSignatureSet,
+    SignatureSetInUse,
+    InvalidFee,
+    InvalidUpgradeBuffer,
+    InvalidGuardianSetExpiration,
+    InvalidGuardianKeys,
+    InvalidBridge,
+    InvalidFeeMint,
+    InvalidFeeTokenAccount,
+    InsufficientSpendableFees,
+    InvalidAddress,
+    InvalidEmitter,
+    VAAConsumerNotAllowed,
 }
 
 /// Errors thrown by the program will bubble up to the solitaire wrapper, which needs a way to
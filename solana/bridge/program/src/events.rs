@@ -0,0 +1,91 @@
+use solana_program::{
+    hash::hash,
+    log::sol_log_data,
+};
+use solitaire::*;
+
+/// An event the program logs. The Borsh encoding goes behind an 8 byte discriminator, the first
+/// bytes of `sha256("event:<NAME>")`, so events that encode to the same length stay apart.
+pub trait Event: BorshSerialize + BorshDeserialize {
+    const NAME: &'static str;
+
+    fn discriminator() -> [u8; 8] {
+        let mut discriminator = [0u8; 8];
+        discriminator.copy_from_slice(&hash(format!("event:{}", Self::NAME).as_bytes()).0[..8]);
+        discriminator
+    }
+
+    /// Decode logged event data, `None` if it belongs to another event or does not parse.
+    fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < 8 || data[..8] != Self::discriminator() {
+            return None;
+        }
+        Self::try_from_slice(&data[8..]).ok()
+    }
+}
+
+/// Logged when a VAA is posted, once its signatures have reached quorum.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct PostVAAEvent {
+    /// Hash of the VAA body the guardians signed, from which the posted VAA account is derived.
+    pub hash: [u8; 32],
+    pub emitter_chain: u16,
+    pub emitter_address: [u8; 32],
+    pub sequence: u64,
+    pub guardian_set_index: u32,
+}
+
+impl Event for PostVAAEvent {
+    const NAME: &'static str = "PostVAAEvent";
+}
+
+/// Logged when a governance decree changes the fee for posting a message.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct SetFeesEvent {
+    /// Fee in lamports before the decree
+    pub old_fee: u64,
+    /// Fee in lamports charged from now on
+    pub new_fee: u64,
+}
+
+impl Event for SetFeesEvent {
+    const NAME: &'static str = "SetFeesEvent";
+}
+
+/// Logged when a governance decree replaces the active guardian set.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct UpgradeGuardianSetEvent {
+    pub old_guardian_set_index: u32,
+    pub new_guardian_set_index: u32,
+    /// Number of guardians in the new set
+    pub num_guardians: u32,
+    /// Time after which VAAs signed by the old set are rejected
+    pub old_guardian_set_expiration_time: u32,
+}
+
+impl Event for UpgradeGuardianSetEvent {
+    const NAME: &'static str = "UpgradeGuardianSetEvent";
+}
+
+/// Logged when a governance decree sweeps the fee collector.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct SweepFeeCollectorEvent {
+    /// Lamports paid out, zero if nothing was above the reserve
+    pub amount: u64,
+    pub recipient: [u8; 32],
+}
+
+impl Event for SweepFeeCollectorEvent {
+    const NAME: &'static str = "SweepFeeCollectorEvent";
+}
+
+/// Log an event as `Program data: <base64>`, its discriminator followed by its Borsh encoding.
+///
+/// Logs are best effort: the runtime truncates them once a transaction exceeds its log limit, so
+/// an event can be missing. Consumers that must not miss one should read the accounts it reports.
+pub fn emit<T: Event>(event: &T) -> Result<()> {
+    let mut data = T::discriminator().to_vec();
+    event.serialize(&mut data)?;
+    sol_log_data(&[&data]);
+    Ok(())
+}
@@ -10,41 +10,86 @@ use solana_program::{
 
 use byteorder::{
     BigEndian,
+    LittleEndian,
     WriteBytesExt,
 };
-use sha3::Digest;
 use solitaire::{
     processors::seeded::Seeded,
     AccountState,
 };
-use std::io::{
-    Cursor,
-    Write,
+use std::{
+    convert::TryInto,
+    io::{
+        Cursor,
+        Write,
+    },
 };
 
 use crate::{
     accounts::{
+        find_bridge,
+        find_emitter_sequence,
+        find_fee_collector,
+        find_guardian_set,
+        find_posted_vaa,
         Bridge,
         Claim,
         ClaimDerivationData,
+        FeeAuthority,
         FeeCollector,
+        FeeExemption,
+        FeeExemptionDerivationData,
+        FeeTokenAccount,
+        FeeTokenAccountDerivationData,
         GuardianSet,
         GuardianSetDerivationData,
-        PostedVAA,
-        PostedVAADerivationData,
-        Sequence,
-        SequenceDerivationData,
+        PostedMessage,
+        PostedMessageDerivationData,
+        SignatureSetData,
+        VAAConsumer,
+        VAAConsumerDerivationData,
+        VerifiedHash,
+        VerifiedHashDerivationData,
+    },
+    error::Error::{
+        GuardianSetMismatch,
+        TooManyGuardians,
     },
     types::ConsistencyLevel,
+    vaa::{
+        compute_message_hash,
+        hash_vaa_body,
+        VAASignature,
+        VAA,
+    },
+    CloseMessageData,
+    ClosePostedVAAData,
+    CloseSignatureSetData,
+    FinalizeMessageData,
+    InitMessageData,
     InitializeData,
     PostMessageData,
+    PostPreparedMessageData,
     PostVAAData,
+    QueryFeeData,
+    SetFeeExemptionData,
+    SetFeeMintData,
     SetFeesData,
+    SetGuardianSetExpirationData,
+    SetVAAConsumerData,
+    SweepFeeCollectorData,
     TransferFeesData,
+    TransferMessageAuthorityData,
     UpgradeContractData,
     UpgradeGuardianSetData,
+    VerifyHashData,
     VerifySignaturesData,
+    VerifySignaturesV2Data,
+    WriteMessageData,
     CHAIN_ID_GOVERANCE,
+    EMITTER_AUTHORITY_SEED,
+    MAX_LEN_GUARDIAN_KEYS,
+    POST_MESSAGE_ACCOUNTS,
 };
 
 pub fn initialize(
@@ -84,6 +129,8 @@ pub fn initialize(
     })
 }
 
+/// Post a message paid for by transferring the fee to the fee collector earlier in the same
+/// transaction, see `api::post_message`.
 pub fn post_message(
     program_id: Pubkey,
     payer: Pubkey,
@@ -92,165 +139,908 @@ pub fn post_message(
     nonce: u32,
     payload: Vec<u8>,
     commitment: ConsistencyLevel,
+) -> solitaire::Result<Instruction> {
+    let (bridge, _) = find_bridge(&program_id);
+    let (fee_collector, _) = find_fee_collector(&program_id);
+    let (sequence, _) = find_emitter_sequence(&program_id, &emitter);
+
+    Ok(Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(bridge, false),
+            AccountMeta::new(message, true),
+            AccountMeta::new_readonly(emitter, true),
+            AccountMeta::new(sequence, false),
+            AccountMeta::new(payer, true),
+            AccountMeta::new(fee_collector, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ],
+        data: (
+            crate::instruction::Instruction::PostMessage,
+            PostMessageData {
+                nonce,
+                payload,
+                consistency_level: commitment,
+            },
+        )
+            .try_to_vec()?,
+    })
+}
+
+/// Like `post_message`, but the fee is taken from `payer` by the bridge itself.
+pub fn post_message_v2(
+    program_id: Pubkey,
+    payer: Pubkey,
+    emitter: Pubkey,
+    message: Pubkey,
+    nonce: u32,
+    payload: Vec<u8>,
+    commitment: ConsistencyLevel,
+) -> solitaire::Result<Instruction> {
+    let (bridge, _) = find_bridge(&program_id);
+    let (fee_collector, _) = find_fee_collector(&program_id);
+    let (sequence, _) = find_emitter_sequence(&program_id, &emitter);
+
+    Ok(Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(bridge, false),
+            AccountMeta::new(message, true),
+            AccountMeta::new_readonly(emitter, true),
+            AccountMeta::new(sequence, false),
+            AccountMeta::new(payer, true),
+            AccountMeta::new(fee_collector, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ],
+        data: (
+            crate::instruction::Instruction::PostMessageV2,
+            PostMessageData {
+                nonce,
+                payload,
+                consistency_level: commitment,
+            },
+        )
+            .try_to_vec()?,
+    })
+}
+
+/// Post a message to the account derived from `emitter` and `sequence`, which must be the next
+/// sequence of the emitter as stored in its sequence tracker (0 if there is none yet).
+pub fn post_message_derived(
+    program_id: Pubkey,
+    payer: Pubkey,
+    emitter: Pubkey,
+    sequence: u64,
+    nonce: u32,
+    payload: Vec<u8>,
+    commitment: ConsistencyLevel,
+) -> solitaire::Result<Instruction> {
+    let (bridge, _) = find_bridge(&program_id);
+    let (fee_collector, _) = find_fee_collector(&program_id);
+    let message = PostedMessage::<'_, { AccountState::Uninitialized }>::key(
+        &PostedMessageDerivationData {
+            emitter_key: &emitter,
+            sequence,
+        },
+        &program_id,
+    );
+    let (sequence, _) = find_emitter_sequence(&program_id, &emitter);
+
+    Ok(Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(bridge, false),
+            AccountMeta::new(message, false),
+            AccountMeta::new_readonly(emitter, true),
+            AccountMeta::new(sequence, false),
+            AccountMeta::new(payer, true),
+            AccountMeta::new(fee_collector, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ],
+        data: (
+            crate::instruction::Instruction::PostMessageDerived,
+            PostMessageData {
+                nonce,
+                payload,
+                consistency_level: commitment,
+            },
+        )
+            .try_to_vec()?,
+    })
+}
+
+/// Post a message with `emitter_program` as the emitter. The instruction has to be invoked by that
+/// program, signing for its `EMITTER_AUTHORITY_SEED` PDA.
+pub fn post_message_program_emitter(
+    program_id: Pubkey,
+    payer: Pubkey,
+    emitter_program: Pubkey,
+    message: Pubkey,
+    nonce: u32,
+    payload: Vec<u8>,
+    commitment: ConsistencyLevel,
+) -> solitaire::Result<Instruction> {
+    let (bridge, _) = find_bridge(&program_id);
+    let (fee_collector, _) = find_fee_collector(&program_id);
+    let (emitter_authority, _) =
+        Pubkey::find_program_address(&[EMITTER_AUTHORITY_SEED], &emitter_program);
+    let (sequence, _) = find_emitter_sequence(&program_id, &emitter_program);
+
+    Ok(Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(bridge, false),
+            AccountMeta::new(message, true),
+            AccountMeta::new_readonly(emitter_authority, true),
+            AccountMeta::new_readonly(emitter_program, false),
+            AccountMeta::new(sequence, false),
+            AccountMeta::new(payer, true),
+            AccountMeta::new(fee_collector, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ],
+        data: (
+            crate::instruction::Instruction::PostMessageProgramEmitter,
+            PostMessageData {
+                nonce,
+                payload,
+                consistency_level: commitment,
+            },
+        )
+            .try_to_vec()?,
+    })
+}
+
+pub fn post_message_unreliable(
+    program_id: Pubkey,
+    payer: Pubkey,
+    emitter: Pubkey,
+    message: Pubkey,
+    nonce: u32,
+    payload: Vec<u8>,
+    commitment: ConsistencyLevel,
+) -> solitaire::Result<Instruction> {
+    let (bridge, _) = find_bridge(&program_id);
+    let (fee_collector, _) = find_fee_collector(&program_id);
+    let (sequence, _) = find_emitter_sequence(&program_id, &emitter);
+
+    Ok(Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(bridge, false),
+            AccountMeta::new(message, true),
+            AccountMeta::new_readonly(emitter, true),
+            AccountMeta::new(sequence, false),
+            AccountMeta::new(payer, true),
+            AccountMeta::new(fee_collector, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ],
+        data: (
+            crate::instruction::Instruction::PostMessageUnreliable,
+            PostMessageData {
+                nonce,
+                payload,
+                consistency_level: commitment,
+            },
+        )
+            .try_to_vec()?,
+    })
+}
+
+pub fn init_message(
+    program_id: Pubkey,
+    payer: Pubkey,
+    emitter: Pubkey,
+    message: Pubkey,
+    payload_size: u32,
+) -> solitaire::Result<Instruction> {
+    Ok(Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new_readonly(emitter, true),
+            AccountMeta::new(message, true),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ],
+        data: (
+            crate::instruction::Instruction::InitMessage,
+            InitMessageData { payload_size },
+        )
+            .try_to_vec()?,
+    })
+}
+
+pub fn write_message(
+    program_id: Pubkey,
+    emitter: Pubkey,
+    message: Pubkey,
+    offset: u32,
+    data: Vec<u8>,
+) -> solitaire::Result<Instruction> {
+    Ok(Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(emitter, true),
+            AccountMeta::new(message, false),
+        ],
+        data: (
+            crate::instruction::Instruction::WriteMessage,
+            WriteMessageData { offset, data },
+        )
+            .try_to_vec()?,
+    })
+}
+
+pub fn finalize_message(
+    program_id: Pubkey,
+    emitter: Pubkey,
+    message: Pubkey,
+) -> solitaire::Result<Instruction> {
+    Ok(Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(emitter, true),
+            AccountMeta::new(message, false),
+        ],
+        data: (
+            crate::instruction::Instruction::FinalizeMessage,
+            FinalizeMessageData {},
+        )
+            .try_to_vec()?,
+    })
+}
+
+pub fn close_message(
+    program_id: Pubkey,
+    emitter: Pubkey,
+    message: Pubkey,
+    recipient: Pubkey,
+) -> solitaire::Result<Instruction> {
+    Ok(Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(emitter, true),
+            AccountMeta::new(message, false),
+            AccountMeta::new(recipient, false),
+        ],
+        data: (
+            crate::instruction::Instruction::CloseMessage,
+            CloseMessageData {},
+        )
+            .try_to_vec()?,
+    })
+}
+
+pub fn transfer_message_authority(
+    program_id: Pubkey,
+    emitter: Pubkey,
+    message: Pubkey,
+    new_emitter: Pubkey,
+) -> solitaire::Result<Instruction> {
+    Ok(Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(emitter, true),
+            AccountMeta::new(message, false),
+        ],
+        data: (
+            crate::instruction::Instruction::TransferMessageAuthority,
+            TransferMessageAuthorityData { new_emitter },
+        )
+            .try_to_vec()?,
+    })
+}
+
+pub fn post_prepared_message(
+    program_id: Pubkey,
+    payer: Pubkey,
+    emitter: Pubkey,
+    message: Pubkey,
+    nonce: u32,
+    commitment: ConsistencyLevel,
+) -> solitaire::Result<Instruction> {
+    let (bridge, _) = find_bridge(&program_id);
+    let (fee_collector, _) = find_fee_collector(&program_id);
+    let (sequence, _) = find_emitter_sequence(&program_id, &emitter);
+
+    Ok(Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(bridge, false),
+            AccountMeta::new(message, false),
+            AccountMeta::new_readonly(emitter, true),
+            AccountMeta::new(sequence, false),
+            AccountMeta::new(payer, true),
+            AccountMeta::new(fee_collector, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ],
+        data: (
+            crate::instruction::Instruction::PostPreparedMessage,
+            PostPreparedMessageData {
+                nonce,
+                consistency_level: commitment,
+            },
+        )
+            .try_to_vec()?,
+    })
+}
+
+pub fn verify_signatures(
+    program_id: Pubkey,
+    payer: Pubkey,
+    guardian_set_index: u32,
+    signature_set: Pubkey,
+    data: VerifySignaturesData,
+) -> solitaire::Result<Instruction> {
+    let guardian_set = GuardianSet::<'_, { AccountState::Uninitialized }>::key(
+        &GuardianSetDerivationData {
+            index: guardian_set_index,
+        },
+        &program_id,
+    );
+
+    Ok(Instruction {
+        program_id,
+
+        accounts: vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new_readonly(guardian_set, false),
+            AccountMeta::new(signature_set, true),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ],
+
+        data: (crate::instruction::Instruction::VerifySignatures, data).try_to_vec()?,
+    })
+}
+
+/// Like `verify_signatures`, for guardian sets larger than `MAX_LEN_GUARDIAN_KEYS`.
+pub fn verify_signatures_v2(
+    program_id: Pubkey,
+    payer: Pubkey,
+    guardian_set_index: u32,
+    signature_set: Pubkey,
+    data: VerifySignaturesV2Data,
+) -> solitaire::Result<Instruction> {
+    let mut ix = verify_signatures(
+        program_id,
+        payer,
+        guardian_set_index,
+        signature_set,
+        VerifySignaturesData::default(),
+    )?;
+    ix.data = (crate::instruction::Instruction::VerifySignaturesV2, data).try_to_vec()?;
+    Ok(ix)
+}
+
+pub fn post_vaa(
+    program_id: Pubkey,
+    payer: Pubkey,
+    signature_set: Pubkey,
+    vaa: PostVAAData,
+) -> Instruction {
+    let (bridge, _) = find_bridge(&program_id);
+    let (guardian_set, _) = find_guardian_set(&program_id, vaa.guardian_set_index);
+    let (message, _) = find_posted_vaa(&program_id, &hash_vaa(&vaa));
+
+    Instruction {
+        program_id,
+
+        accounts: vec![
+            AccountMeta::new_readonly(guardian_set, false),
+            AccountMeta::new_readonly(bridge, false),
+            AccountMeta::new_readonly(signature_set, false),
+            AccountMeta::new(message, false),
+            AccountMeta::new(payer, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ],
+
+        data: (crate::instruction::Instruction::PostVAA, vaa)
+            .try_to_vec()
+            .unwrap(),
+    }
+}
+
+/// Record the hash of `signature_set` as signed by guardian set `guardian_set_index`, once enough
+/// signatures of it have been verified.
+pub fn verify_hash(
+    program_id: Pubkey,
+    payer: Pubkey,
+    signature_set: Pubkey,
+    guardian_set_index: u32,
+    hash: [u8; 32],
+) -> solitaire::Result<Instruction> {
+    let guardian_set = GuardianSet::<'_, { AccountState::Uninitialized }>::key(
+        &GuardianSetDerivationData {
+            index: guardian_set_index,
+        },
+        &program_id,
+    );
+    let verified_hash = VerifiedHash::<'_, { AccountState::MaybeInitialized }>::key(
+        &VerifiedHashDerivationData { hash },
+        &program_id,
+    );
+
+    Ok(Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(guardian_set, false),
+            AccountMeta::new_readonly(signature_set, false),
+            AccountMeta::new(verified_hash, false),
+            AccountMeta::new(payer, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ],
+        data: (
+            crate::instruction::Instruction::VerifyHash,
+            VerifyHashData::default(),
+        )
+            .try_to_vec()?,
+    })
+}
+
+/// Close `posted_vaa` once `consumer` has created `claim` for it, sending the rent to the fee
+/// collector. Governance must have allowed `consumer`, see `set_vaa_consumer`.
+pub fn close_posted_vaa(
+    program_id: Pubkey,
+    posted_vaa: Pubkey,
+    claim: Pubkey,
+    consumer: Pubkey,
 ) -> solitaire::Result<Instruction> {
     let bridge = Bridge::<'_, { AccountState::Uninitialized }>::key(None, &program_id);
     let fee_collector = FeeCollector::<'_>::key(None, &program_id);
-    let sequence = Sequence::<'_>::key(
-        &SequenceDerivationData {
-            emitter_key: &emitter,
+    let vaa_consumer = VAAConsumer::<'_, { AccountState::Uninitialized }>::key(
+        &VAAConsumerDerivationData { consumer },
+        &program_id,
+    );
+
+    Ok(Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(posted_vaa, false),
+            AccountMeta::new_readonly(claim, false),
+            AccountMeta::new_readonly(vaa_consumer, false),
+            AccountMeta::new(bridge, false),
+            AccountMeta::new(fee_collector, false),
+        ],
+        data: (
+            crate::instruction::Instruction::ClosePostedVAA,
+            ClosePostedVAAData { consumer },
+        )
+            .try_to_vec()?,
+    })
+}
+
+/// Close `signature_set` once `record`, the posted VAA or verified hash of its signatures, exists.
+/// Must be signed by the `signature_set` keypair.
+pub fn close_signature_set(
+    program_id: Pubkey,
+    signature_set: Pubkey,
+    record: Pubkey,
+    recipient: Pubkey,
+) -> solitaire::Result<Instruction> {
+    Ok(Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(signature_set, true),
+            AccountMeta::new_readonly(record, false),
+            AccountMeta::new(recipient, false),
+        ],
+        data: (
+            crate::instruction::Instruction::CloseSignatureSet,
+            CloseSignatureSetData::default(),
+        )
+            .try_to_vec()?,
+    })
+}
+
+/// Most signatures a single `verify_signatures` transaction can check. Each one adds 85 bytes of
+/// secp256k1 instruction data, and more than seven no longer fit the transaction size limit.
+pub const MAX_SIGNATURES_PER_TRANSACTION: usize = 7;
+
+/// Size of the signature set account `verify_signatures` allocates for a guardian set of
+/// `num_guardians`, for estimating the rent of posting a VAA.
+pub fn signature_set_size(num_guardians: usize) -> usize {
+    SignatureSetData {
+        signatures: vec![false; num_guardians],
+        ..Default::default()
+    }
+    .try_to_vec()
+    .unwrap()
+    .len()
+}
+
+/// Transactions verifying the signatures of the serialized `vaa` against `guardian_keys`, the keys
+/// of the guardian set it was signed by. Each is a batch of `verify_signatures_batch` and they must
+/// all be signed by the `signature_set` keypair.
+pub fn verify_signatures_transactions(
+    program_id: Pubkey,
+    payer: Pubkey,
+    signature_set: Pubkey,
+    guardian_keys: &[[u8; 20]],
+    vaa: &[u8],
+) -> solitaire::Result<Vec<Vec<Instruction>>> {
+    let parsed = VAA::deserialize(vaa)?;
+    let body_hash = compute_message_hash(vaa)?;
+
+    parsed
+        .signatures
+        .chunks(MAX_SIGNATURES_PER_TRANSACTION)
+        .map(|signatures| {
+            verify_signatures_batch(
+                program_id,
+                payer,
+                signature_set,
+                parsed.guardian_set_index,
+                guardian_keys,
+                &body_hash,
+                signatures,
+            )
+        })
+        .collect()
+}
+
+/// A secp256k1 instruction checking `signatures` of `body_hash`, followed by `verify_signatures`
+/// recording them in `signature_set`. Verification can be split into any number of batches, sent
+/// in separate transactions, and the VAA can be posted once the signature set reached quorum.
+///
+/// Guardian sets larger than `MAX_LEN_GUARDIAN_KEYS` are verified with `verify_signatures_v2`.
+pub fn verify_signatures_batch(
+    program_id: Pubkey,
+    payer: Pubkey,
+    signature_set: Pubkey,
+    guardian_set_index: u32,
+    guardian_keys: &[[u8; 20]],
+    body_hash: &[u8; 32],
+    signatures: &[VAASignature],
+) -> solitaire::Result<Vec<Instruction>> {
+    let mut secp_payload = Vec::new();
+    let mut signers = vec![-1i8; guardian_keys.len().max(MAX_LEN_GUARDIAN_KEYS)];
+
+    let data_offset = 1 + signatures.len() * 11;
+    let message_offset = data_offset + signatures.len() * 85;
+
+    // Number of signatures, followed by 11 bytes of offsets for each.
+    secp_payload.write_u8(signatures.len() as u8)?;
+    for (i, s) in signatures.iter().enumerate() {
+        secp_payload.write_u16::<LittleEndian>((data_offset + 85 * i) as u16)?;
+        secp_payload.write_u8(0)?;
+        secp_payload.write_u16::<LittleEndian>((data_offset + 85 * i + 65) as u16)?;
+        secp_payload.write_u8(0)?;
+        secp_payload.write_u16::<LittleEndian>(message_offset as u16)?;
+        secp_payload.write_u16::<LittleEndian>(body_hash.len() as u16)?;
+        secp_payload.write_u8(0)?;
+        *signers
+            .get_mut(s.guardian_index as usize)
+            .ok_or(TooManyGuardians)? = i as i8;
+    }
+
+    // Signatures and addresses, then the signed hash all of them share.
+    for s in signatures {
+        let key = guardian_keys
+            .get(s.guardian_index as usize)
+            .ok_or(GuardianSetMismatch)?;
+        secp_payload.write_all(&s.signature)?;
+        secp_payload.write_all(key)?;
+    }
+    secp_payload.write_all(body_hash)?;
+
+    Ok(vec![
+        Instruction {
+            program_id: solana_program::secp256k1_program::id(),
+            data: secp_payload,
+            accounts: vec![],
+        },
+        if signers.len() > MAX_LEN_GUARDIAN_KEYS {
+            verify_signatures_v2(
+                program_id,
+                payer,
+                guardian_set_index,
+                signature_set,
+                VerifySignaturesV2Data { signers },
+            )?
+        } else {
+            verify_signatures(
+                program_id,
+                payer,
+                guardian_set_index,
+                signature_set,
+                VerifySignaturesData {
+                    signers: signers.try_into().unwrap(),
+                },
+            )?
+        },
+    ])
+}
+
+/// Every transaction needed to post the serialized `vaa`, in the order they have to be sent: the
+/// signature verification of `verify_signatures_transactions` followed by `post_vaa`.
+pub fn post_vaa_transactions(
+    program_id: Pubkey,
+    payer: Pubkey,
+    signature_set: Pubkey,
+    guardian_keys: &[[u8; 20]],
+    vaa: &[u8],
+) -> solitaire::Result<Vec<Vec<Instruction>>> {
+    let mut transactions =
+        verify_signatures_transactions(program_id, payer, signature_set, guardian_keys, vaa)?;
+    transactions.push(vec![post_vaa(
+        program_id,
+        payer,
+        signature_set,
+        VAA::deserialize(vaa)?.into(),
+    )]);
+    Ok(transactions)
+}
+
+pub fn upgrade_contract(
+    program_id: Pubkey,
+    payer: Pubkey,
+    payload_message: Pubkey,
+    emitter: Pubkey,
+    new_contract: Pubkey,
+    spill: Pubkey,
+    sequence: u64,
+) -> Instruction {
+    let bridge = Bridge::<'_, { AccountState::Initialized }>::key(None, &program_id);
+    let claim = Claim::<'_>::key(
+        &ClaimDerivationData {
+            emitter_address: emitter.to_bytes(),
+            emitter_chain: CHAIN_ID_GOVERANCE,
+            sequence,
+        },
+        &program_id,
+    );
+
+    let (upgrade_authority, _) = Pubkey::find_program_address(&["upgrade".as_bytes()], &program_id);
+
+    let (program_data, _) = Pubkey::find_program_address(
+        &[program_id.as_ref()],
+        &solana_program::bpf_loader_upgradeable::id(),
+    );
+
+    Instruction {
+        program_id,
+
+        accounts: vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(bridge, false),
+            AccountMeta::new_readonly(payload_message, false),
+            AccountMeta::new(claim, false),
+            AccountMeta::new_readonly(upgrade_authority, false),
+            AccountMeta::new(spill, false),
+            AccountMeta::new(new_contract, false),
+            AccountMeta::new(program_data, false),
+            AccountMeta::new(program_id, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(solana_program::bpf_loader_upgradeable::id(), false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ],
+
+        data: (
+            crate::instruction::Instruction::UpgradeContract,
+            UpgradeContractData {},
+        )
+            .try_to_vec()
+            .unwrap(),
+    }
+}
+
+pub fn upgrade_guardian_set(
+    program_id: Pubkey,
+    payer: Pubkey,
+    payload_message: Pubkey,
+    emitter: Pubkey,
+    old_index: u32,
+    new_index: u32,
+    sequence: u64,
+) -> Instruction {
+    let bridge = Bridge::<'_, { AccountState::Uninitialized }>::key(None, &program_id);
+    let claim = Claim::<'_>::key(
+        &ClaimDerivationData {
+            emitter_address: emitter.to_bytes(),
+            emitter_chain: CHAIN_ID_GOVERANCE,
+            sequence,
         },
         &program_id,
     );
 
-    Ok(Instruction {
+    let guardian_set_old = GuardianSet::<'_, { AccountState::Initialized }>::key(
+        &GuardianSetDerivationData { index: old_index },
+        &program_id,
+    );
+
+    let guardian_set_new = GuardianSet::<'_, { AccountState::Uninitialized }>::key(
+        &GuardianSetDerivationData { index: new_index },
+        &program_id,
+    );
+
+    Instruction {
         program_id,
+
         accounts: vec![
-            AccountMeta::new(bridge, false),
-            AccountMeta::new(message, true),
-            AccountMeta::new_readonly(emitter, true),
-            AccountMeta::new(sequence, false),
             AccountMeta::new(payer, true),
-            AccountMeta::new(fee_collector, false),
-            AccountMeta::new_readonly(sysvar::clock::id(), false),
-            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new(bridge, false),
+            AccountMeta::new_readonly(payload_message, false),
+            AccountMeta::new(claim, false),
+            AccountMeta::new(guardian_set_old, false),
+            AccountMeta::new(guardian_set_new, false),
             AccountMeta::new_readonly(solana_program::system_program::id(), false),
         ],
+
         data: (
-            crate::instruction::Instruction::PostMessage,
-            PostMessageData {
-                nonce,
-                payload,
-                consistency_level: commitment,
-            },
+            crate::instruction::Instruction::UpgradeGuardianSet,
+            UpgradeGuardianSetData {},
         )
-            .try_to_vec()?,
-    })
+            .try_to_vec()
+            .unwrap(),
+    }
 }
 
-pub fn post_message_unreliable(
+pub fn set_fees(
     program_id: Pubkey,
     payer: Pubkey,
-    emitter: Pubkey,
     message: Pubkey,
-    nonce: u32,
-    payload: Vec<u8>,
-    commitment: ConsistencyLevel,
-) -> solitaire::Result<Instruction> {
+    emitter: Pubkey,
+    sequence: u64,
+) -> Instruction {
     let bridge = Bridge::<'_, { AccountState::Uninitialized }>::key(None, &program_id);
-    let fee_collector = FeeCollector::<'_>::key(None, &program_id);
-    let sequence = Sequence::<'_>::key(
-        &SequenceDerivationData {
-            emitter_key: &emitter,
+    let claim = Claim::<'_>::key(
+        &ClaimDerivationData {
+            emitter_address: emitter.to_bytes(),
+            emitter_chain: CHAIN_ID_GOVERANCE,
+            sequence,
         },
         &program_id,
     );
 
-    Ok(Instruction {
+    Instruction {
         program_id,
+
         accounts: vec![
-            AccountMeta::new(bridge, false),
-            AccountMeta::new(message, true),
-            AccountMeta::new_readonly(emitter, true),
-            AccountMeta::new(sequence, false),
             AccountMeta::new(payer, true),
-            AccountMeta::new(fee_collector, false),
-            AccountMeta::new_readonly(sysvar::clock::id(), false),
-            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new(bridge, false),
+            AccountMeta::new_readonly(message, false),
+            AccountMeta::new(claim, false),
             AccountMeta::new_readonly(solana_program::system_program::id(), false),
         ],
-        data: (
-            crate::instruction::Instruction::PostMessageUnreliable,
-            PostMessageData {
-                nonce,
-                payload,
-                consistency_level: commitment,
-            },
-        )
-            .try_to_vec()?,
-    })
+
+        data: (crate::instruction::Instruction::SetFees, SetFeesData {})
+            .try_to_vec()
+            .unwrap(),
+    }
 }
 
-pub fn verify_signatures(
+pub fn set_guardian_set_expiration(
     program_id: Pubkey,
     payer: Pubkey,
-    guardian_set_index: u32,
-    signature_set: Pubkey,
-    data: VerifySignaturesData,
-) -> solitaire::Result<Instruction> {
-    let guardian_set = GuardianSet::<'_, { AccountState::Uninitialized }>::key(
-        &GuardianSetDerivationData {
-            index: guardian_set_index,
+    message: Pubkey,
+    emitter: Pubkey,
+    sequence: u64,
+) -> Instruction {
+    let bridge = Bridge::<'_, { AccountState::Uninitialized }>::key(None, &program_id);
+    let claim = Claim::<'_>::key(
+        &ClaimDerivationData {
+            emitter_address: emitter.to_bytes(),
+            emitter_chain: CHAIN_ID_GOVERANCE,
+            sequence,
         },
         &program_id,
     );
 
-    Ok(Instruction {
+    Instruction {
         program_id,
 
         accounts: vec![
             AccountMeta::new(payer, true),
-            AccountMeta::new_readonly(guardian_set, false),
-            AccountMeta::new(signature_set, true),
-            AccountMeta::new_readonly(sysvar::instructions::id(), false),
-            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new(bridge, false),
+            AccountMeta::new_readonly(message, false),
+            AccountMeta::new(claim, false),
             AccountMeta::new_readonly(solana_program::system_program::id(), false),
         ],
 
-        data: (crate::instruction::Instruction::VerifySignatures, data).try_to_vec()?,
-    })
+        data: (
+            crate::instruction::Instruction::SetGuardianSetExpiration,
+            SetGuardianSetExpirationData {},
+        )
+            .try_to_vec()
+            .unwrap(),
+    }
 }
 
-pub fn post_vaa(
+pub fn transfer_fees(
     program_id: Pubkey,
     payer: Pubkey,
-    signature_set: Pubkey,
-    vaa: PostVAAData,
+    message: Pubkey,
+    emitter: Pubkey,
+    sequence: u64,
+    recipient: Pubkey,
 ) -> Instruction {
     let bridge = Bridge::<'_, { AccountState::Uninitialized }>::key(None, &program_id);
-    let guardian_set = GuardianSet::<'_, { AccountState::Uninitialized }>::key(
-        &GuardianSetDerivationData {
-            index: vaa.guardian_set_index,
+    let claim = Claim::<'_>::key(
+        &ClaimDerivationData {
+            emitter_address: emitter.to_bytes(),
+            emitter_chain: CHAIN_ID_GOVERANCE,
+            sequence,
         },
         &program_id,
     );
 
-    let msg_derivation_data = &PostedVAADerivationData {
-        payload_hash: hash_vaa(&vaa).to_vec(),
-    };
-
-    let message =
-        PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(msg_derivation_data, &program_id);
+    let fee_collector = FeeCollector::key(None, &program_id);
 
     Instruction {
         program_id,
 
         accounts: vec![
-            AccountMeta::new_readonly(guardian_set, false),
-            AccountMeta::new_readonly(bridge, false),
-            AccountMeta::new_readonly(signature_set, false),
-            AccountMeta::new(message, false),
             AccountMeta::new(payer, true),
-            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new(bridge, false),
+            AccountMeta::new_readonly(message, false),
+            AccountMeta::new(claim, false),
+            AccountMeta::new(fee_collector, false),
+            AccountMeta::new(recipient, false),
             AccountMeta::new_readonly(sysvar::rent::id(), false),
             AccountMeta::new_readonly(solana_program::system_program::id(), false),
         ],
 
-        data: (crate::instruction::Instruction::PostVAA, vaa)
+        data: (
+            crate::instruction::Instruction::TransferFees,
+            TransferFeesData {},
+        )
             .try_to_vec()
             .unwrap(),
     }
 }
 
-pub fn upgrade_contract(
+/// Like `transfer_fees`, for a decree paying out token fees of `mint` to the token account
+/// `recipient`.
+pub fn transfer_token_fees(
     program_id: Pubkey,
     payer: Pubkey,
-    payload_message: Pubkey,
+    message: Pubkey,
     emitter: Pubkey,
-    new_contract: Pubkey,
-    spill: Pubkey,
     sequence: u64,
+    mint: Pubkey,
+    recipient: Pubkey,
 ) -> Instruction {
-    let bridge = Bridge::<'_, { AccountState::Initialized }>::key(None, &program_id);
+    let mut ix = transfer_fees(program_id, payer, message, emitter, sequence, recipient);
+    let fee_token_account = FeeTokenAccount::<'_, { AccountState::Uninitialized }>::key(
+        &FeeTokenAccountDerivationData { mint },
+        &program_id,
+    );
+    ix.accounts.extend(vec![
+        AccountMeta::new(fee_token_account, false),
+        AccountMeta::new_readonly(FeeAuthority::key(None, &program_id), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ]);
+    ix
+}
+
+pub fn sweep_fee_collector(
+    program_id: Pubkey,
+    payer: Pubkey,
+    message: Pubkey,
+    emitter: Pubkey,
+    sequence: u64,
+    recipient: Pubkey,
+) -> Instruction {
+    let bridge = Bridge::<'_, { AccountState::Uninitialized }>::key(None, &program_id);
     let claim = Claim::<'_>::key(
         &ClaimDerivationData {
             emitter_address: emitter.to_bytes(),
@@ -260,12 +1050,7 @@ pub fn upgrade_contract(
         &program_id,
     );
 
-    let (upgrade_authority, _) = Pubkey::find_program_address(&["upgrade".as_bytes()], &program_id);
-
-    let (program_data, _) = Pubkey::find_program_address(
-        &[program_id.as_ref()],
-        &solana_program::bpf_loader_upgradeable::id(),
-    );
+    let fee_collector = FeeCollector::key(None, &program_id);
 
     Instruction {
         program_id,
@@ -273,36 +1058,30 @@ pub fn upgrade_contract(
         accounts: vec![
             AccountMeta::new(payer, true),
             AccountMeta::new(bridge, false),
-            AccountMeta::new_readonly(payload_message, false),
+            AccountMeta::new_readonly(message, false),
             AccountMeta::new(claim, false),
-            AccountMeta::new_readonly(upgrade_authority, false),
-            AccountMeta::new(spill, false),
-            AccountMeta::new(new_contract, false),
-            AccountMeta::new(program_data, false),
-            AccountMeta::new(program_id, false),
+            AccountMeta::new(fee_collector, false),
+            AccountMeta::new(recipient, false),
             AccountMeta::new_readonly(sysvar::rent::id(), false),
-            AccountMeta::new_readonly(sysvar::clock::id(), false),
-            AccountMeta::new_readonly(solana_program::bpf_loader_upgradeable::id(), false),
             AccountMeta::new_readonly(solana_program::system_program::id(), false),
         ],
 
         data: (
-            crate::instruction::Instruction::UpgradeContract,
-            UpgradeContractData {},
+            crate::instruction::Instruction::SweepFeeCollector,
+            SweepFeeCollectorData {},
         )
             .try_to_vec()
             .unwrap(),
     }
 }
 
-pub fn upgrade_guardian_set(
+pub fn set_fee_mint(
     program_id: Pubkey,
     payer: Pubkey,
-    payload_message: Pubkey,
+    message: Pubkey,
     emitter: Pubkey,
-    old_index: u32,
-    new_index: u32,
     sequence: u64,
+    mint: Pubkey,
 ) -> Instruction {
     let bridge = Bridge::<'_, { AccountState::Uninitialized }>::key(None, &program_id);
     let claim = Claim::<'_>::key(
@@ -313,14 +1092,8 @@ pub fn upgrade_guardian_set(
         },
         &program_id,
     );
-
-    let guardian_set_old = GuardianSet::<'_, { AccountState::Initialized }>::key(
-        &GuardianSetDerivationData { index: old_index },
-        &program_id,
-    );
-
-    let guardian_set_new = GuardianSet::<'_, { AccountState::Uninitialized }>::key(
-        &GuardianSetDerivationData { index: new_index },
+    let fee_token_account = FeeTokenAccount::<'_, { AccountState::Uninitialized }>::key(
+        &FeeTokenAccountDerivationData { mint },
         &program_id,
     );
 
@@ -330,28 +1103,64 @@ pub fn upgrade_guardian_set(
         accounts: vec![
             AccountMeta::new(payer, true),
             AccountMeta::new(bridge, false),
-            AccountMeta::new_readonly(payload_message, false),
+            AccountMeta::new_readonly(message, false),
             AccountMeta::new(claim, false),
-            AccountMeta::new(guardian_set_old, false),
-            AccountMeta::new(guardian_set_new, false),
+            AccountMeta::new_readonly(FeeAuthority::key(None, &program_id), false),
+            AccountMeta::new(fee_token_account, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
             AccountMeta::new_readonly(solana_program::system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
         ],
 
         data: (
-            crate::instruction::Instruction::UpgradeGuardianSet,
-            UpgradeGuardianSetData {},
+            crate::instruction::Instruction::SetFeeMint,
+            SetFeeMintData {},
         )
             .try_to_vec()
             .unwrap(),
     }
 }
 
-pub fn set_fees(
+/// Append the accounts paying the message fee in tokens to a posting instruction, for while a fee
+/// mint is set. `payer_token_account` must be a token account of `mint` owned by the payer of the
+/// message. These must be the last accounts of the instruction.
+pub fn with_token_fee(
+    mut ix: Instruction,
+    mint: Pubkey,
+    payer_token_account: Pubkey,
+) -> Instruction {
+    let accounts = token_fee_accounts(ix.program_id, mint, payer_token_account);
+    ix.accounts.extend(accounts);
+    ix
+}
+
+/// The accounts `with_token_fee` appends, for the bridge at `program_id`. Programs that post
+/// messages on behalf of their callers, like the token bridge, take these as the last accounts of
+/// their own instructions and pass them on, see `cpi::forward_token_fee`.
+pub fn token_fee_accounts(
+    program_id: Pubkey,
+    mint: Pubkey,
+    payer_token_account: Pubkey,
+) -> Vec<AccountMeta> {
+    let fee_token_account = FeeTokenAccount::<'_, { AccountState::Uninitialized }>::key(
+        &FeeTokenAccountDerivationData { mint },
+        &program_id,
+    );
+    vec![
+        AccountMeta::new(payer_token_account, false),
+        AccountMeta::new(fee_token_account, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ]
+}
+
+pub fn set_fee_exemption(
     program_id: Pubkey,
     payer: Pubkey,
     message: Pubkey,
     emitter: Pubkey,
     sequence: u64,
+    exempt_emitter: Pubkey,
 ) -> Instruction {
     let bridge = Bridge::<'_, { AccountState::Uninitialized }>::key(None, &program_id);
     let claim = Claim::<'_>::key(
@@ -362,6 +1171,12 @@ pub fn set_fees(
         },
         &program_id,
     );
+    let fee_exemption = FeeExemption::<'_, { AccountState::Uninitialized }>::key(
+        &FeeExemptionDerivationData {
+            emitter: exempt_emitter,
+        },
+        &program_id,
+    );
 
     Instruction {
         program_id,
@@ -371,22 +1186,26 @@ pub fn set_fees(
             AccountMeta::new(bridge, false),
             AccountMeta::new_readonly(message, false),
             AccountMeta::new(claim, false),
+            AccountMeta::new(fee_exemption, false),
             AccountMeta::new_readonly(solana_program::system_program::id(), false),
         ],
 
-        data: (crate::instruction::Instruction::SetFees, SetFeesData {})
+        data: (
+            crate::instruction::Instruction::SetFeeExemption,
+            SetFeeExemptionData {},
+        )
             .try_to_vec()
             .unwrap(),
     }
 }
 
-pub fn transfer_fees(
+pub fn set_vaa_consumer(
     program_id: Pubkey,
     payer: Pubkey,
     message: Pubkey,
     emitter: Pubkey,
     sequence: u64,
-    recipient: Pubkey,
+    consumer: Pubkey,
 ) -> Instruction {
     let bridge = Bridge::<'_, { AccountState::Uninitialized }>::key(None, &program_id);
     let claim = Claim::<'_>::key(
@@ -397,8 +1216,10 @@ pub fn transfer_fees(
         },
         &program_id,
     );
-
-    let fee_collector = FeeCollector::key(None, &program_id);
+    let vaa_consumer = VAAConsumer::<'_, { AccountState::Uninitialized }>::key(
+        &VAAConsumerDerivationData { consumer },
+        &program_id,
+    );
 
     Instruction {
         program_id,
@@ -408,21 +1229,61 @@ pub fn transfer_fees(
             AccountMeta::new(bridge, false),
             AccountMeta::new_readonly(message, false),
             AccountMeta::new(claim, false),
-            AccountMeta::new(fee_collector, false),
-            AccountMeta::new(recipient, false),
-            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new(vaa_consumer, false),
             AccountMeta::new_readonly(solana_program::system_program::id(), false),
         ],
 
         data: (
-            crate::instruction::Instruction::TransferFees,
-            TransferFeesData {},
+            crate::instruction::Instruction::SetVAAConsumer,
+            SetVAAConsumerData {},
         )
             .try_to_vec()
             .unwrap(),
     }
 }
 
+/// Add the fee exemption of `emitter` to a posting instruction, so that the message is posted
+/// without a fee if governance exempted the emitter. The exemption goes right after the accounts of
+/// the instruction, see `POST_MESSAGE_ACCOUNTS`, so this combines with `with_token_fee` in either
+/// order. Exempt emitters need no token fee accounts.
+pub fn with_fee_exemption(ix: Instruction, emitter: Pubkey) -> Instruction {
+    let fee_exemption = FeeExemption::<'_, { AccountState::Uninitialized }>::key(
+        &FeeExemptionDerivationData { emitter },
+        &ix.program_id,
+    );
+    insert_fee_exemption(ix, fee_exemption)
+}
+
+/// Like `with_fee_exemption`, for an exemption address that is already known.
+pub(crate) fn insert_fee_exemption(mut ix: Instruction, fee_exemption: Pubkey) -> Instruction {
+    let index = match crate::instruction::Instruction::decode(&ix.data) {
+        Ok((crate::instruction::Instruction::PostMessageProgramEmitter, _)) => {
+            POST_MESSAGE_ACCOUNTS + 1
+        }
+        _ => POST_MESSAGE_ACCOUNTS,
+    };
+    ix.accounts.insert(
+        index.min(ix.accounts.len()),
+        AccountMeta::new_readonly(fee_exemption, false),
+    );
+    ix
+}
+
+/// Query the fee currently charged per message, which the bridge writes as its return data.
+pub fn query_fee(program_id: Pubkey) -> Instruction {
+    let bridge = Bridge::<'_, { AccountState::Uninitialized }>::key(None, &program_id);
+
+    Instruction {
+        program_id,
+
+        accounts: vec![AccountMeta::new_readonly(bridge, false)],
+
+        data: (crate::instruction::Instruction::QueryFee, QueryFeeData {})
+            .try_to_vec()
+            .unwrap(),
+    }
+}
+
 // Convert a full VAA structure into the serialization of its unique components, this structure is
 // what is hashed and verified by Guardians.
 pub fn serialize_vaa(vaa: &PostVAAData) -> Vec<u8> {
@@ -439,8 +1300,5 @@ pub fn serialize_vaa(vaa: &PostVAAData) -> Vec<u8> {
 
 // Hash a VAA, this combines serialization and hashing.
 pub fn hash_vaa(vaa: &PostVAAData) -> [u8; 32] {
-    let body = serialize_vaa(vaa);
-    let mut h = sha3::Keccak256::default();
-    h.write_all(body.as_slice()).unwrap();
-    h.finalize().into()
+    hash_vaa_body(&serialize_vaa(vaa))
 }
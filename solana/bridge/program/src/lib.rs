@@ -8,9 +8,33 @@ pub const MAX_LEN_GUARDIAN_KEYS: usize = 19;
 pub const CHAIN_ID_SOLANA: u16 = 1;
 pub const CHAIN_ID_GOVERANCE: u16 = 1;
 
+/// Largest payload a message can carry. Message accounts are allocated by the bridge itself, and a
+/// program can grow account data by at most `MAX_PERMITTED_DATA_INCREASE` in one instruction.
+pub const MAX_MESSAGE_PAYLOAD_SIZE: usize =
+    solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE - accounts::PAYLOAD_OFFSET;
+
+/// Bounds on how long a replaced guardian set stays valid, as set by governance.
+pub const MIN_GUARDIAN_SET_EXPIRATION_TIME: u32 = 60 * 60;
+pub const MAX_GUARDIAN_SET_EXPIRATION_TIME: u32 = 30 * 24 * 60 * 60;
+
+/// Seed of the PDA a program signs with to post messages with its own program id as the emitter.
+pub const EMITTER_AUTHORITY_SEED: &[u8] = b"emitter";
+
+/// Accounts the posting instructions take, as passed by the builders in `instructions`, with one
+/// more for `post_message_program_emitter`. Any fee accounts follow them: the fee exemption of the
+/// emitter right after, the token fee accounts last. See `instructions::with_fee_exemption` and
+/// `instructions::with_token_fee`.
+pub const POST_MESSAGE_ACCOUNTS: usize = 9;
+
 #[cfg(feature = "instructions")]
 pub mod instructions;
 
+#[cfg(feature = "instructions")]
+pub mod cpi;
+
+#[cfg(feature = "instructions")]
+pub mod prelude;
+
 #[cfg(feature = "wasm")]
 #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
 extern crate wasm_bindgen;
@@ -27,58 +51,127 @@ pub use accounts::{
     Claim,
     ClaimData,
     ClaimDerivationData,
+    FeeAuthority,
     FeeCollector,
+    FeeExemption,
+    FeeExemptionData,
+    FeeExemptionDerivationData,
+    FeeTokenAccount,
+    FeeTokenAccountDerivationData,
     GuardianSet,
     GuardianSetData,
     GuardianSetDerivationData,
     MessageData,
     PostedMessage,
     PostedMessageData,
+    PostedMessageDerivationData,
     PostedMessageUnreliable,
     PostedMessageUnreliableData,
+    PostedMessageV1,
     PostedVAA,
     PostedVAAData,
+    PostedVAAV1,
     Sequence,
     SequenceDerivationData,
     SequenceTracker,
     SignatureSet,
     SignatureSetData,
+    SignatureSetV1,
+    VAAConsumer,
+    VAAConsumerData,
+    VAAConsumerDerivationData,
+    VerifiedHash,
+    VerifiedHashData,
 };
 
 pub mod api;
 
 pub use api::{
+    close_message_v1,
+    close_posted_vaa,
+    close_signature_set,
+    finalize_message_v1,
+    init_message_v1,
     initialize,
     post_message,
+    post_message_derived,
+    post_message_program_emitter,
     post_message_unreliable,
+    post_message_v2,
+    post_prepared_message,
     post_vaa,
+    query_fee,
+    set_fee_exemption,
+    set_fee_mint,
     set_fees,
+    set_guardian_set_expiration,
+    set_vaa_consumer,
+    sweep_fee_collector,
     transfer_fees,
+    transfer_message_authority_v1,
     upgrade_contract,
     upgrade_guardian_set,
+    verify_hash,
     verify_signatures,
+    verify_signatures_v2,
+    write_message_v1,
+    CloseMessage,
+    CloseMessageData,
+    ClosePostedVAA,
+    ClosePostedVAAData,
+    CloseSignatureSet,
+    CloseSignatureSetData,
+    FinalizeMessage,
+    FinalizeMessageData,
+    InitMessage,
+    InitMessageData,
     Initialize,
     InitializeData,
     PostMessage,
     PostMessageData,
+    PostMessageDerived,
+    PostMessageProgramEmitter,
     PostMessageUnreliable,
+    PostPreparedMessage,
+    PostPreparedMessageData,
     PostVAA,
     PostVAAData,
+    QueryFee,
+    QueryFeeData,
+    SetFeeExemption,
+    SetFeeExemptionData,
+    SetFeeMint,
+    SetFeeMintData,
     SetFees,
     SetFeesData,
+    SetGuardianSetExpiration,
+    SetGuardianSetExpirationData,
+    SetVAAConsumer,
+    SetVAAConsumerData,
     Signature,
+    SweepFeeCollector,
+    SweepFeeCollectorData,
     TransferFees,
     TransferFeesData,
+    TransferMessageAuthority,
+    TransferMessageAuthorityData,
     UninitializedMessage,
     UpgradeContract,
     UpgradeContractData,
     UpgradeGuardianSet,
     UpgradeGuardianSetData,
+    VerifyHash,
+    VerifyHashData,
     VerifySignatures,
     VerifySignaturesData,
+    VerifySignaturesV2Data,
+    WriteMessage,
+    WriteMessageData,
 };
 
 pub mod error;
+pub mod events;
+pub mod precompile;
 pub mod types;
 pub mod vaa;
 
@@ -100,4 +193,23 @@ solitaire! {
     UpgradeGuardianSet => upgrade_guardian_set,
     VerifySignatures   => verify_signatures,
     PostMessageUnreliable        => post_message_unreliable,
+    InitMessage        => init_message_v1,
+    WriteMessage       => write_message_v1,
+    FinalizeMessage    => finalize_message_v1,
+    PostPreparedMessage => post_prepared_message,
+    CloseMessage       => close_message_v1,
+    PostMessageV2      => post_message_v2,
+    PostMessageDerived => post_message_derived,
+    PostMessageProgramEmitter => post_message_program_emitter,
+    TransferMessageAuthority => transfer_message_authority_v1,
+    VerifyHash         => verify_hash,
+    ClosePostedVAA     => close_posted_vaa,
+    VerifySignaturesV2 => verify_signatures_v2,
+    CloseSignatureSet  => close_signature_set,
+    SetGuardianSetExpiration => set_guardian_set_expiration,
+    SweepFeeCollector  => sweep_fee_collector,
+    QueryFee           => query_fee,
+    SetFeeMint         => set_fee_mint,
+    SetFeeExemption    => set_fee_exemption,
+    SetVAAConsumer     => set_vaa_consumer,
 }
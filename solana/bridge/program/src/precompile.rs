@@ -0,0 +1,182 @@
+//! Reading precompile instructions through the instructions sysvar.
+//!
+//! The secp256k1 program checks signatures before the transaction executes, the bridge only has to
+//! find out what was checked. Each verification is described by an offsets table in the
+//! instruction data, pointing at the signature, the Ethereum address and the message. Every offset
+//! is bounds checked here, any malformed table is rejected rather than read past its end.
+
+use crate::error::Error::{
+    InstructionAtWrongIndex,
+    InvalidSecpInstruction,
+};
+use byteorder::ByteOrder;
+use solana_program::{
+    account_info::AccountInfo,
+    instruction::Instruction,
+    program_error::ProgramError,
+    secp256k1_program,
+    sysvar::instructions,
+};
+use solitaire::Result;
+use std::convert::TryFrom;
+
+/// Size of an entry of the offsets table of a secp256k1 instruction, following the byte holding
+/// the number of entries.
+pub const SECP256K1_OFFSETS_SIZE: usize = 11;
+
+/// Size of a recoverable secp256k1 signature, including the recovery id.
+pub const SECP256K1_SIGNATURE_SIZE: usize = 65;
+
+/// Size of an Ethereum address.
+pub const ETH_ADDRESS_SIZE: usize = 20;
+
+/// A signature verified by a secp256k1 instruction.
+///
+/// The bridge only accepts instructions whose signatures, addresses and messages are all stored in
+/// the instruction itself, so the offsets below are into its own data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Secp256k1Verification {
+    /// Index of the secp256k1 instruction in the transaction.
+    pub instruction_index: u16,
+    pub signature_offset: u16,
+    pub eth_address: [u8; ETH_ADDRESS_SIZE],
+    pub message_offset: u16,
+    pub message_size: u16,
+}
+
+impl Secp256k1Verification {
+    /// The message that was signed, `data` being the data of the secp256k1 instruction.
+    pub fn message<'a>(&self, data: &'a [u8]) -> &'a [u8] {
+        let start = self.message_offset as usize;
+        &data[start..start + self.message_size as usize]
+    }
+}
+
+/// Load the instruction at `index` of the transaction from the instructions `sysvar`.
+pub fn load_instruction_at_checked(index: usize, sysvar: &AccountInfo) -> Result<Instruction> {
+    instructions::load_instruction_at_checked(index, sysvar)
+        .map_err(|_| ProgramError::InvalidAccountData.into())
+}
+
+/// Fail with `InstructionAtWrongIndex` unless the instruction executing is at `expected` in the
+/// transaction.
+pub fn require_current_index(sysvar: &AccountInfo, expected: u16) -> Result<()> {
+    if instructions::load_current_index_checked(sysvar)? != expected {
+        return Err(InstructionAtWrongIndex.into());
+    }
+    Ok(())
+}
+
+/// Parse the offsets table of the secp256k1 instruction at `instruction_index`, whose data is
+/// `data`, failing with `InvalidSecpInstruction` if anything it points at is out of bounds or in
+/// another instruction.
+pub fn parse_secp256k1_instruction(
+    instruction_index: u16,
+    data: &[u8],
+) -> Result<Vec<Secp256k1Verification>> {
+    let count = *data.first().ok_or(InvalidSecpInstruction)? as usize;
+    let own_index = u8::try_from(instruction_index).map_err(|_| InvalidSecpInstruction)?;
+    let table = data
+        .get(1..1 + count * SECP256K1_OFFSETS_SIZE)
+        .ok_or(InvalidSecpInstruction)?;
+
+    table
+        .chunks_exact(SECP256K1_OFFSETS_SIZE)
+        .map(|offsets| {
+            let signature_offset = byteorder::LE::read_u16(&offsets[0..2]);
+            let signature_ix = offsets[2];
+            let address_offset = byteorder::LE::read_u16(&offsets[3..5]);
+            let address_ix = offsets[5];
+            let message_offset = byteorder::LE::read_u16(&offsets[6..8]);
+            let message_size = byteorder::LE::read_u16(&offsets[8..10]);
+            let message_ix = offsets[10];
+
+            if signature_ix != own_index || address_ix != own_index || message_ix != own_index {
+                return Err(InvalidSecpInstruction.into());
+            }
+            check_range(data, signature_offset, SECP256K1_SIGNATURE_SIZE)?;
+            let eth_address = check_range(data, address_offset, ETH_ADDRESS_SIZE)?;
+            check_range(data, message_offset, message_size as usize)?;
+
+            let mut address = [0u8; ETH_ADDRESS_SIZE];
+            address.copy_from_slice(eth_address);
+            Ok(Secp256k1Verification {
+                instruction_index,
+                signature_offset,
+                eth_address: address,
+                message_offset,
+                message_size,
+            })
+        })
+        .collect()
+}
+
+fn check_range(data: &[u8], offset: u16, size: usize) -> Result<&[u8]> {
+    let start = offset as usize;
+    data.get(start..start + size)
+        .ok_or_else(|| InvalidSecpInstruction.into())
+}
+
+/// Iterate over the signatures verified by all secp256k1 instructions of the transaction, read
+/// from the instructions `sysvar`. Malformed instructions yield an error, after which iteration
+/// stops.
+pub fn iter_precompile_verifications<'a, 'b>(
+    sysvar: &'a AccountInfo<'b>,
+) -> Result<PrecompileVerifications<'a, 'b>> {
+    if !instructions::check_id(sysvar.key) {
+        return Err(ProgramError::UnsupportedSysvar.into());
+    }
+    // The sysvar starts with the number of instructions of the transaction.
+    let count = sysvar
+        .try_borrow_data()?
+        .get(0..2)
+        .map(byteorder::LE::read_u16)
+        .ok_or(ProgramError::InvalidAccountData)?;
+
+    Ok(PrecompileVerifications {
+        sysvar,
+        next: 0,
+        count,
+        pending: Vec::new().into_iter(),
+    })
+}
+
+/// Iterator returned by [`iter_precompile_verifications`].
+pub struct PrecompileVerifications<'a, 'b> {
+    sysvar: &'a AccountInfo<'b>,
+    next: u16,
+    count: u16,
+    pending: std::vec::IntoIter<Secp256k1Verification>,
+}
+
+impl Iterator for PrecompileVerifications<'_, '_> {
+    type Item = Result<Secp256k1Verification>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(verification) = self.pending.next() {
+                return Some(Ok(verification));
+            }
+            if self.next >= self.count {
+                return None;
+            }
+
+            let index = self.next;
+            self.next += 1;
+            let parsed = load_instruction_at_checked(index as usize, self.sysvar).and_then(|ix| {
+                if ix.program_id == secp256k1_program::id() {
+                    parse_secp256k1_instruction(index, &ix.data)
+                } else {
+                    Ok(Vec::new())
+                }
+            });
+            match parsed {
+                Ok(verifications) => self.pending = verifications.into_iter(),
+                Err(e) => {
+                    self.next = self.count;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! Everything a program receiving or emitting Wormhole messages usually needs, so that it can start
+//! with a single `use bridge::prelude::*`.
+//!
+//! A minimal receiver, accepting the payload of one emitter once it has been posted as a VAA:
+//!
+//! ```rust,no_run
+//! use bridge::prelude::*;
+//!
+//! const SOURCE_CHAIN: u16 = 2;
+//! const SOURCE_EMITTER: [u8; 32] = [1; 32];
+//!
+//! pub fn receive(core_bridge: &Pubkey, posted_vaa: &AccountInfo) -> ProgramResult {
+//!     // The bridge only writes posted VAAs once the guardians signed them, so the owner check is
+//!     // what makes the contents trustworthy.
+//!     if posted_vaa.owner != core_bridge {
+//!         return Err(ProgramError::IncorrectProgramId);
+//!     }
+//!     let vaa = PostedVAAV1::load(posted_vaa)?;
+//!     if vaa.emitter_chain() != SOURCE_CHAIN || vaa.emitter_address() != SOURCE_EMITTER {
+//!         return Err(ProgramError::InvalidAccountData);
+//!     }
+//!     if vaa.payload().is_empty() {
+//!         return Err(ProgramError::InvalidInstructionData);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+pub use crate::{
+    accounts::{
+        claim::{
+            claim_vaa,
+            consume,
+        },
+        find_bridge,
+        find_emitter_sequence,
+        find_fee_collector,
+        find_guardian_set,
+        find_posted_vaa,
+        BridgeV1,
+        Claim,
+        ClaimData,
+        ClaimDerivationData,
+        PostedMessageV1,
+        PostedVAA,
+        PostedVAAData,
+        PostedVAADerivationData,
+        PostedVAAV1,
+        GOVERNANCE_CLAIM_SEED,
+    },
+    cpi,
+    error::Error as BridgeError,
+    types::{
+        ChainId,
+        ConsistencyLevel,
+        ForeignEmitter,
+        UniversalAddress,
+    },
+    vaa::{
+        compute_digest,
+        compute_message_hash,
+        DeserializePayload,
+        PayloadMessage,
+        SerializePayload,
+    },
+    PostMessageData,
+    CHAIN_ID_SOLANA,
+    EMITTER_AUTHORITY_SEED,
+    MAX_MESSAGE_PAYLOAD_SIZE,
+};
+pub use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+pub use solitaire::SolitaireError;
@@ -1,5 +1,14 @@
 use crate::{
+    accounts::PostedVAAV1,
     api::ForeignAddress,
+    error::{
+        Error,
+        Error::{
+            InvalidAddress,
+            InvalidConsistencyLevel,
+            InvalidEmitter,
+        },
+    },
     vaa::{
         DeserializeGovernancePayload,
         DeserializePayload,
@@ -24,9 +33,18 @@ use solana_program::{
     program_error::ProgramError::InvalidAccountData,
     pubkey::Pubkey,
 };
-use solitaire::SolitaireError;
+use solitaire::{
+    pack_type,
+    processors::seeded::AccountOwner,
+    SolitaireError,
+};
+use spl_token::state::Account;
 use std::{
     self,
+    convert::{
+        TryFrom,
+        TryInto,
+    },
     io::{
         Cursor,
         Read,
@@ -37,13 +55,292 @@ use std::{
 /// Type representing an Ethereum style public key for Guardians.
 pub type GuardianPublicKey = [u8; 20];
 
+/// Commitment a message must reach before guardians attest to it. Instructions carry it as the
+/// Borsh variant index, posted messages and VAAs as the wire value given by `u8::from`.
 #[repr(u8)]
-#[derive(BorshSerialize, BorshDeserialize, Clone, Serialize, Deserialize)]
+#[derive(BorshSerialize, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum ConsistencyLevel {
     Confirmed,
     Finalized,
 }
 
+impl BorshDeserialize for ConsistencyLevel {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        match u8::deserialize(buf)? {
+            0 => Ok(ConsistencyLevel::Confirmed),
+            1 => Ok(ConsistencyLevel::Finalized),
+            n => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Invalid consistency level {}", n),
+            )),
+        }
+    }
+}
+
+impl From<ConsistencyLevel> for u8 {
+    fn from(level: ConsistencyLevel) -> u8 {
+        match level {
+            ConsistencyLevel::Confirmed => 1,
+            ConsistencyLevel::Finalized => 32,
+        }
+    }
+}
+
+impl TryFrom<u8> for ConsistencyLevel {
+    type Error = Error;
+
+    fn try_from(level: u8) -> Result<Self, Error> {
+        match level {
+            1 => Ok(ConsistencyLevel::Confirmed),
+            32 => Ok(ConsistencyLevel::Finalized),
+            _ => Err(InvalidConsistencyLevel),
+        }
+    }
+}
+
+macro_rules! chain_ids {
+    ($($chain:ident = $id:literal, $name:literal, $kind:ident;)*) => {
+        /// Wormhole chain ids. Messages, VAAs and instruction data keep carrying the raw `u16`, this
+        /// is for code that needs to tell chains apart. Ids without a variant convert to `Unknown`
+        /// so that they still round-trip.
+        #[non_exhaustive]
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum ChainId {
+            $($chain,)*
+            Unknown(u16),
+        }
+
+        impl From<u16> for ChainId {
+            fn from(id: u16) -> ChainId {
+                match id {
+                    $($id => ChainId::$chain,)*
+                    id => ChainId::Unknown(id),
+                }
+            }
+        }
+
+        impl From<ChainId> for u16 {
+            fn from(chain: ChainId) -> u16 {
+                match chain {
+                    $(ChainId::$chain => $id,)*
+                    ChainId::Unknown(id) => id,
+                }
+            }
+        }
+
+        impl ChainId {
+            /// Every chain with a variant of its own.
+            pub const KNOWN: &'static [ChainId] = &[$(ChainId::$chain,)*];
+
+            /// Whether the chain runs the EVM, so that its addresses are 20 byte Ethereum addresses
+            /// left-padded to 32 bytes.
+            pub fn is_evm(self) -> bool {
+                match self {
+                    $(ChainId::$chain => chain_ids!(@$kind),)*
+                    ChainId::Unknown(_) => false,
+                }
+            }
+
+            fn name(self) -> Option<&'static str> {
+                match self {
+                    $(ChainId::$chain => Some($name),)*
+                    ChainId::Unknown(_) => None,
+                }
+            }
+        }
+    };
+    (@evm) => {
+        true
+    };
+    (@other) => {
+        false
+    };
+}
+
+chain_ids! {
+    Solana = 1, "Solana", other;
+    Ethereum = 2, "Ethereum", evm;
+    Terra = 3, "Terra", other;
+    Bsc = 4, "BSC", evm;
+    Polygon = 5, "Polygon", evm;
+    Avalanche = 6, "Avalanche", evm;
+    Oasis = 7, "Oasis", evm;
+    Algorand = 8, "Algorand", other;
+    Aurora = 9, "Aurora", evm;
+    Fantom = 10, "Fantom", evm;
+    Karura = 11, "Karura", evm;
+    Acala = 12, "Acala", evm;
+    Klaytn = 13, "Klaytn", evm;
+    Celo = 14, "Celo", evm;
+    Near = 15, "NEAR", other;
+    Moonbeam = 16, "Moonbeam", evm;
+    Neon = 17, "Neon", evm;
+    Terra2 = 18, "Terra 2", other;
+    Injective = 19, "Injective", other;
+    Osmosis = 20, "Osmosis", other;
+    Sui = 21, "Sui", other;
+    Aptos = 22, "Aptos", other;
+    Arbitrum = 23, "Arbitrum", evm;
+    Optimism = 24, "Optimism", evm;
+    Gnosis = 25, "Gnosis", evm;
+    Pythnet = 26, "Pythnet", other;
+    Xpla = 28, "XPLA", other;
+    Btc = 29, "Bitcoin", other;
+    Base = 30, "Base", evm;
+    Sei = 32, "Sei", other;
+    Rootstock = 33, "Rootstock", evm;
+    Scroll = 34, "Scroll", evm;
+    Mantle = 35, "Mantle", evm;
+    Wormchain = 3104, "Wormchain", other;
+    CosmosHub = 4000, "Cosmos Hub", other;
+    Evmos = 4001, "Evmos", other;
+    Kujira = 4002, "Kujira", other;
+    Neutron = 4003, "Neutron", other;
+    Celestia = 4004, "Celestia", other;
+    Stargaze = 4005, "Stargaze", other;
+    Seda = 4006, "SEDA", other;
+    Dymension = 4007, "Dymension", other;
+    Provenance = 4008, "Provenance", other;
+    Sepolia = 10002, "Sepolia", evm;
+}
+
+impl std::fmt::Display for ChainId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.name() {
+            Some(name) => f.write_str(name),
+            None => write!(f, "Unknown chain {}", u16::from(*self)),
+        }
+    }
+}
+
+/// Address of an account or contract on any chain, as carried by messages. Foreign addresses
+/// shorter than 32 bytes are left-padded with zeros.
+#[derive(
+    Default,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+pub struct UniversalAddress(pub [u8; 32]);
+
+impl UniversalAddress {
+    pub fn from_pubkey(pubkey: &Pubkey) -> UniversalAddress {
+        UniversalAddress(pubkey.to_bytes())
+    }
+
+    pub fn to_pubkey(&self) -> Pubkey {
+        Pubkey::new_from_array(self.0)
+    }
+
+    /// Left-pad an EVM address.
+    pub fn from_evm(address: [u8; 20]) -> UniversalAddress {
+        let mut bytes = [0u8; 32];
+        bytes[12..].copy_from_slice(&address);
+        UniversalAddress(bytes)
+    }
+
+    /// The EVM address this is the padded form of, failing if any of the padding is set.
+    pub fn try_to_evm(&self) -> Result<[u8; 20], Error> {
+        if self.0[..12].iter().any(|b| *b != 0) {
+            return Err(InvalidAddress);
+        }
+        Ok(self.0[12..].try_into().unwrap())
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == [0u8; 32]
+    }
+}
+
+impl From<[u8; 32]> for UniversalAddress {
+    fn from(bytes: [u8; 32]) -> UniversalAddress {
+        UniversalAddress(bytes)
+    }
+}
+
+impl From<UniversalAddress> for [u8; 32] {
+    fn from(address: UniversalAddress) -> [u8; 32] {
+        address.0
+    }
+}
+
+impl From<Pubkey> for UniversalAddress {
+    fn from(pubkey: Pubkey) -> UniversalAddress {
+        UniversalAddress::from_pubkey(&pubkey)
+    }
+}
+
+/// Lowercase hex, without a prefix.
+impl std::fmt::Display for UniversalAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.iter().try_for_each(|b| write!(f, "{:02x}", b))
+    }
+}
+
+/// Parses 64 hex digits, optionally prefixed with `0x`.
+impl std::str::FromStr for UniversalAddress {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<UniversalAddress, Error> {
+        let digits = s.strip_prefix("0x").unwrap_or(s).as_bytes();
+        if digits.len() != 64 {
+            return Err(InvalidAddress);
+        }
+        let mut bytes = [0u8; 32];
+        for (byte, pair) in bytes.iter_mut().zip(digits.chunks(2)) {
+            let high = (pair[0] as char).to_digit(16).ok_or(InvalidAddress)?;
+            let low = (pair[1] as char).to_digit(16).ok_or(InvalidAddress)?;
+            *byte = ((high << 4) | low) as u8;
+        }
+        Ok(UniversalAddress(bytes))
+    }
+}
+
+/// A contract on another chain, identified the way VAAs identify their emitter. Programs
+/// accepting messages from a fixed set of counterparts can store these and check VAAs against
+/// them.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Hash, BorshSerialize, BorshDeserialize)]
+pub struct ForeignEmitter {
+    pub chain: u16,
+    pub address: ForeignAddress,
+}
+
+impl ForeignEmitter {
+    /// Fail unless `vaa` was emitted by this emitter. An emitter with an all zero address, such as
+    /// an unset entry of a whitelist, never verifies.
+    pub fn verify(&self, vaa: &PostedVAAV1) -> Result<(), Error> {
+        if UniversalAddress(self.address).is_zero() || self != vaa {
+            return Err(InvalidEmitter);
+        }
+        Ok(())
+    }
+}
+
+impl PartialEq<PostedVAAV1<'_>> for ForeignEmitter {
+    fn eq(&self, vaa: &PostedVAAV1) -> bool {
+        self.chain == vaa.emitter_chain() && self.address == vaa.emitter_address()
+    }
+}
+
+/// The name of the chain and the address in hex, separated by a slash.
+impl std::fmt::Display for ForeignEmitter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}/{}",
+            ChainId::from(self.chain),
+            UniversalAddress(self.address)
+        )
+    }
+}
+
 pub struct GovernancePayloadUpgrade {
     // Address of the new Implementation
     pub new_contract: Pubkey,
@@ -83,6 +380,8 @@ impl SerializeGovernancePayload for GovernancePayloadUpgrade {
 }
 
 impl DeserializeGovernancePayload for GovernancePayloadUpgrade {
+    // An upgrade names a buffer on Solana, so it cannot be meant for every chain.
+    const ALLOW_ANY_CHAIN: bool = false;
 }
 
 pub struct GovernancePayloadGuardianSetChange {
@@ -185,12 +484,60 @@ impl SerializeGovernancePayload for GovernancePayloadSetMessageFee {
 impl DeserializeGovernancePayload for GovernancePayloadSetMessageFee {
 }
 
+/// Solana specific decree changing how long a guardian set stays valid after being replaced.
+pub struct GovernancePayloadSetGuardianSetExpiration {
+    // Expiration time in seconds
+    pub guardian_set_expiration_time: u32,
+}
+
+impl SerializePayload for GovernancePayloadSetGuardianSetExpiration {
+    fn serialize<W: Write>(&self, v: &mut W) -> std::result::Result<(), SolitaireError> {
+        use byteorder::WriteBytesExt;
+        v.write_u32::<BigEndian>(self.guardian_set_expiration_time)?;
+        Ok(())
+    }
+}
+
+impl DeserializePayload for GovernancePayloadSetGuardianSetExpiration
+where
+    Self: DeserializeGovernancePayload,
+{
+    fn deserialize(buf: &mut &[u8]) -> Result<Self, SolitaireError> {
+        let mut c = Cursor::new(buf);
+        Self::check_governance_header(&mut c)?;
+
+        let guardian_set_expiration_time = c.read_u32::<BigEndian>()?;
+
+        if c.position() != c.into_inner().len() as u64 {
+            return Err(InvalidAccountData.into());
+        }
+
+        Ok(GovernancePayloadSetGuardianSetExpiration {
+            guardian_set_expiration_time,
+        })
+    }
+}
+
+impl SerializeGovernancePayload for GovernancePayloadSetGuardianSetExpiration {
+    const MODULE: &'static str = "Core";
+    const ACTION: u8 = 6;
+}
+
+impl DeserializeGovernancePayload for GovernancePayloadSetGuardianSetExpiration {
+    // Only the Solana bridge knows this action.
+    const ALLOW_ANY_CHAIN: bool = false;
+}
+
 pub struct GovernancePayloadTransferFees {
     // Amount to be transferred
     pub amount: U256,
 
     // Recipient
     pub to: ForeignAddress,
+
+    // Fee mint to pay out in, with `to` a token account of it, rather than lamports. Only encoded
+    // when set, which only the Solana bridge understands.
+    pub mint: Option<Pubkey>,
 }
 
 impl SerializePayload for GovernancePayloadTransferFees {
@@ -199,6 +546,9 @@ impl SerializePayload for GovernancePayloadTransferFees {
         self.amount.to_big_endian(&mut amount_data);
         v.write_all(&amount_data)?;
         v.write_all(&self.to)?;
+        if let Some(mint) = &self.mint {
+            v.write_all(&mint.to_bytes())?;
+        }
         Ok(())
     }
 }
@@ -218,11 +568,19 @@ where
         let mut to = ForeignAddress::default();
         c.read_exact(&mut to)?;
 
+        let mint = if c.position() == c.get_ref().len() as u64 {
+            None
+        } else {
+            let mut mint = [0u8; 32];
+            c.read_exact(&mut mint)?;
+            Some(Pubkey::new_from_array(mint))
+        };
+
         if c.position() != c.into_inner().len() as u64 {
             return Err(InvalidAccountData.into());
         }
 
-        Ok(GovernancePayloadTransferFees { amount, to })
+        Ok(GovernancePayloadTransferFees { amount, to, mint })
     }
 }
 
@@ -233,3 +591,218 @@ impl SerializeGovernancePayload for GovernancePayloadTransferFees {
 
 impl DeserializeGovernancePayload for GovernancePayloadTransferFees {
 }
+
+/// Solana specific decree paying out everything the fee collector holds above its rent exemption
+/// and `reserve`, donations included.
+pub struct GovernancePayloadSweepFeeCollector {
+    // Lamports to keep on top of the rent exemption
+    pub reserve: u64,
+
+    // Recipient
+    pub to: ForeignAddress,
+}
+
+impl SerializePayload for GovernancePayloadSweepFeeCollector {
+    fn serialize<W: Write>(&self, v: &mut W) -> std::result::Result<(), SolitaireError> {
+        use byteorder::WriteBytesExt;
+        v.write_u64::<BigEndian>(self.reserve)?;
+        v.write_all(&self.to)?;
+        Ok(())
+    }
+}
+
+impl DeserializePayload for GovernancePayloadSweepFeeCollector
+where
+    Self: DeserializeGovernancePayload,
+{
+    fn deserialize(buf: &mut &[u8]) -> Result<Self, SolitaireError> {
+        let mut c = Cursor::new(buf);
+        Self::check_governance_header(&mut c)?;
+
+        let reserve = c.read_u64::<BigEndian>()?;
+
+        let mut to = ForeignAddress::default();
+        c.read_exact(&mut to)?;
+
+        if c.position() != c.into_inner().len() as u64 {
+            return Err(InvalidAccountData.into());
+        }
+
+        Ok(GovernancePayloadSweepFeeCollector { reserve, to })
+    }
+}
+
+impl SerializeGovernancePayload for GovernancePayloadSweepFeeCollector {
+    const MODULE: &'static str = "Core";
+    const ACTION: u8 = 7;
+}
+
+impl DeserializeGovernancePayload for GovernancePayloadSweepFeeCollector {
+    // Only the Solana bridge knows this action.
+    const ALLOW_ANY_CHAIN: bool = false;
+}
+
+/// Solana specific decree switching message fees to an SPL token, or back to lamports when `mint`
+/// is the default pubkey.
+pub struct GovernancePayloadSetFeeMint {
+    // Mint fees are paid in
+    pub mint: Pubkey,
+
+    // Fee in the smallest unit of the mint
+    pub fee: u64,
+}
+
+impl SerializePayload for GovernancePayloadSetFeeMint {
+    fn serialize<W: Write>(&self, v: &mut W) -> std::result::Result<(), SolitaireError> {
+        use byteorder::WriteBytesExt;
+        v.write_all(&self.mint.to_bytes())?;
+        v.write_u64::<BigEndian>(self.fee)?;
+        Ok(())
+    }
+}
+
+impl DeserializePayload for GovernancePayloadSetFeeMint
+where
+    Self: DeserializeGovernancePayload,
+{
+    fn deserialize(buf: &mut &[u8]) -> Result<Self, SolitaireError> {
+        let mut c = Cursor::new(buf);
+        Self::check_governance_header(&mut c)?;
+
+        let mut mint = [0u8; 32];
+        c.read_exact(&mut mint)?;
+        let fee = c.read_u64::<BigEndian>()?;
+
+        if c.position() != c.into_inner().len() as u64 {
+            return Err(InvalidAccountData.into());
+        }
+
+        Ok(GovernancePayloadSetFeeMint {
+            mint: Pubkey::new_from_array(mint),
+            fee,
+        })
+    }
+}
+
+impl SerializeGovernancePayload for GovernancePayloadSetFeeMint {
+    const MODULE: &'static str = "Core";
+    const ACTION: u8 = 8;
+}
+
+impl DeserializeGovernancePayload for GovernancePayloadSetFeeMint {
+    // Only the Solana bridge knows this action.
+    const ALLOW_ANY_CHAIN: bool = false;
+}
+
+/// Solana specific decree exempting `emitter` from message fees, or revoking its exemption.
+pub struct GovernancePayloadSetFeeExemption {
+    // Emitter the exemption applies to
+    pub emitter: Pubkey,
+
+    // Whether the emitter posts without a fee, encoded as a single 0 or 1 byte
+    pub exempt: bool,
+}
+
+impl SerializePayload for GovernancePayloadSetFeeExemption {
+    fn serialize<W: Write>(&self, v: &mut W) -> std::result::Result<(), SolitaireError> {
+        use byteorder::WriteBytesExt;
+        v.write_all(&self.emitter.to_bytes())?;
+        v.write_u8(self.exempt as u8)?;
+        Ok(())
+    }
+}
+
+impl DeserializePayload for GovernancePayloadSetFeeExemption
+where
+    Self: DeserializeGovernancePayload,
+{
+    fn deserialize(buf: &mut &[u8]) -> Result<Self, SolitaireError> {
+        let mut c = Cursor::new(buf);
+        Self::check_governance_header(&mut c)?;
+
+        let mut emitter = [0u8; 32];
+        c.read_exact(&mut emitter)?;
+        let exempt = match c.read_u8()? {
+            0 => false,
+            1 => true,
+            _ => return Err(InvalidAccountData.into()),
+        };
+
+        if c.position() != c.into_inner().len() as u64 {
+            return Err(InvalidAccountData.into());
+        }
+
+        Ok(GovernancePayloadSetFeeExemption {
+            emitter: Pubkey::new_from_array(emitter),
+            exempt,
+        })
+    }
+}
+
+impl SerializeGovernancePayload for GovernancePayloadSetFeeExemption {
+    const MODULE: &'static str = "Core";
+    const ACTION: u8 = 9;
+}
+
+impl DeserializeGovernancePayload for GovernancePayloadSetFeeExemption {
+    // Only the Solana bridge knows this action.
+    const ALLOW_ANY_CHAIN: bool = false;
+}
+
+/// Solana specific decree allowing `consumer` to close the posted VAAs it has claimed, or
+/// disallowing it again.
+pub struct GovernancePayloadSetVAAConsumer {
+    // Program whose claims close posted VAAs
+    pub consumer: Pubkey,
+
+    // Whether the program may close them, encoded as a single 0 or 1 byte
+    pub allowed: bool,
+}
+
+impl SerializePayload for GovernancePayloadSetVAAConsumer {
+    fn serialize<W: Write>(&self, v: &mut W) -> std::result::Result<(), SolitaireError> {
+        use byteorder::WriteBytesExt;
+        v.write_all(&self.consumer.to_bytes())?;
+        v.write_u8(self.allowed as u8)?;
+        Ok(())
+    }
+}
+
+impl DeserializePayload for GovernancePayloadSetVAAConsumer
+where
+    Self: DeserializeGovernancePayload,
+{
+    fn deserialize(buf: &mut &[u8]) -> Result<Self, SolitaireError> {
+        let mut c = Cursor::new(buf);
+        Self::check_governance_header(&mut c)?;
+
+        let mut consumer = [0u8; 32];
+        c.read_exact(&mut consumer)?;
+        let allowed = match c.read_u8()? {
+            0 => false,
+            1 => true,
+            _ => return Err(InvalidAccountData.into()),
+        };
+
+        if c.position() != c.into_inner().len() as u64 {
+            return Err(InvalidAccountData.into());
+        }
+
+        Ok(GovernancePayloadSetVAAConsumer {
+            consumer: Pubkey::new_from_array(consumer),
+            allowed,
+        })
+    }
+}
+
+impl SerializeGovernancePayload for GovernancePayloadSetVAAConsumer {
+    const MODULE: &'static str = "Core";
+    const ACTION: u8 = 10;
+}
+
+impl DeserializeGovernancePayload for GovernancePayloadSetVAAConsumer {
+    // Only the Solana bridge knows this action.
+    const ALLOW_ANY_CHAIN: bool = false;
+}
+
+pack_type!(SplAccount, Account, AccountOwner::Other(spl_token::id()));
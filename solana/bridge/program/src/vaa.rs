@@ -6,10 +6,15 @@ use crate::{
     error::Error::{
         InvalidGovernanceAction,
         InvalidGovernanceChain,
+        InvalidGovernanceKey,
         InvalidGovernanceModule,
+        InvalidPayloadLength,
+        VAAInvalid,
     },
+    MessageData,
     PostedVAAData,
     Result,
+    CHAIN_ID_GOVERANCE,
     CHAIN_ID_SOLANA,
 };
 use byteorder::{
@@ -20,6 +25,7 @@ use serde::{
     Deserialize,
     Serialize,
 };
+use sha3::Digest;
 use solana_program::{
     account_info::AccountInfo,
     pubkey::Pubkey,
@@ -32,6 +38,7 @@ use solitaire::{
     *,
 };
 use std::{
+    convert::TryInto,
     io::{
         Cursor,
         Read,
@@ -80,27 +87,95 @@ pub trait SerializeGovernancePayload: SerializePayload {
 }
 
 pub trait DeserializeGovernancePayload: DeserializePayload + SerializeGovernancePayload {
+    /// Whether the decree may address every chain at once with chain id 0, rather than Solana only.
+    const ALLOW_ANY_CHAIN: bool = true;
+
     fn check_governance_header(
         c: &mut Cursor<&mut &[u8]>,
     ) -> std::result::Result<(), SolitaireError> {
-        let mut module = [0u8; 32];
-        c.read_exact(&mut module)?;
-        if module != format!("{:\0>32}", Self::MODULE).as_bytes() {
-            return Err(InvalidGovernanceModule.into());
-        }
+        let start = c.position() as usize;
+        parse_governance_header(
+            &c.get_ref()[start..],
+            governance_module(Self::MODULE),
+            Self::ACTION,
+            Self::ALLOW_ANY_CHAIN,
+        )?;
+        c.set_position((start + GOVERNANCE_HEADER_LEN) as u64);
+        Ok(())
+    }
+}
 
-        let action = c.read_u8()?;
-        if action != Self::ACTION {
-            return Err(InvalidGovernanceAction.into());
-        }
+/// Length of the header every governance payload starts with: module, action and target chain.
+pub const GOVERNANCE_HEADER_LEN: usize = 35;
 
-        let chain = c.read_u16::<BigEndian>()?;
-        if chain != CHAIN_ID_SOLANA && chain != 0 {
-            return Err(InvalidGovernanceChain.into());
-        }
+/// Header of a governance payload, followed by the action specific body.
+#[derive(Debug, PartialEq)]
+pub struct GovernanceHeader<'a> {
+    /// Name of the module the decree is for, left padded with zeroes.
+    pub module: [u8; 32],
+    pub action: u8,
+    /// Chain the decree targets, Solana or 0 for every chain.
+    pub chain: u16,
+    pub body: &'a [u8],
+}
 
-        Ok(())
+/// Module name as it appears in governance payloads, left padded with zeroes to 32 bytes.
+pub fn governance_module(name: &str) -> [u8; 32] {
+    let mut module = [0u8; 32];
+    module[32 - name.len()..].copy_from_slice(name.as_bytes());
+    module
+}
+
+/// Fail unless `message` was emitted by the governance emitter the bridge was built with, see the
+/// `EMITTER_ADDRESS` build variable.
+pub fn require_governance_emitter(message: &MessageData) -> Result<()> {
+    let expected_emitter = std::env!("EMITTER_ADDRESS");
+    let current_emitter = format!("{}", Pubkey::new_from_array(message.emitter_address));
+    if expected_emitter != current_emitter || message.emitter_chain != CHAIN_ID_GOVERANCE {
+        return Err(InvalidGovernanceKey.into());
     }
+    Ok(())
+}
+
+/// Check that `message` is a governance decree for `module` and `action`, aimed at Solana or at
+/// every chain, and return its header. Programs accepting governance VAAs of their own can use this
+/// to apply the same rules as the bridge.
+pub fn require_valid_governance_vaa(
+    message: &MessageData,
+    module: [u8; 32],
+    action: u8,
+) -> Result<GovernanceHeader> {
+    require_governance_emitter(message)?;
+    parse_governance_header(&message.payload, module, action, true)
+}
+
+fn parse_governance_header(
+    payload: &[u8],
+    module: [u8; 32],
+    action: u8,
+    allow_any_chain: bool,
+) -> Result<GovernanceHeader> {
+    if payload.len() < GOVERNANCE_HEADER_LEN {
+        return Err(InvalidPayloadLength.into());
+    }
+
+    let header = GovernanceHeader {
+        module: payload[..32].try_into().unwrap(),
+        action: payload[32],
+        chain: u16::from_be_bytes([payload[33], payload[34]]),
+        body: &payload[GOVERNANCE_HEADER_LEN..],
+    };
+    if header.module != module {
+        return Err(InvalidGovernanceModule.into());
+    }
+    if header.action != action {
+        return Err(InvalidGovernanceAction.into());
+    }
+    if header.chain != CHAIN_ID_SOLANA && !(allow_any_chain && header.chain == 0) {
+        return Err(InvalidGovernanceChain.into());
+    }
+
+    Ok(header)
 }
 
 pub struct PayloadMessage<'b, T: DeserializePayload>(
@@ -221,6 +296,37 @@ impl VAA {
     }
 }
 
+/// Length of the fixed part of a VAA body, which is followed by the payload.
+pub const VAA_BODY_HEADER_LEN: usize = 51;
+
+/// Offset of the body of a serialized VAA, past its version, guardian set index and signatures.
+pub fn vaa_body_offset(vaa: &[u8]) -> Result<usize> {
+    let num_signatures = *vaa.get(VAA::HEADER_LEN - 1).ok_or(VAAInvalid)? as usize;
+    let offset = VAA::HEADER_LEN + VAA::SIGNATURE_LEN * num_signatures;
+    if vaa.len() < offset + VAA_BODY_HEADER_LEN {
+        return Err(VAAInvalid.into());
+    }
+    Ok(offset)
+}
+
+/// Keccak256 hash of a serialized VAA body. Signature sets are bound to it and posted VAA accounts
+/// are derived from it.
+pub fn hash_vaa_body(body: &[u8]) -> [u8; 32] {
+    sha3::Keccak256::digest(body).into()
+}
+
+/// Message hash of a serialized VAA, see `hash_vaa_body`. Only the header is parsed to find the
+/// body, so this matches the hash of whatever bytes a guardian RPC returned.
+pub fn compute_message_hash(vaa: &[u8]) -> Result<[u8; 32]> {
+    Ok(hash_vaa_body(&vaa[vaa_body_offset(vaa)?..]))
+}
+
+/// Digest the guardians sign, the hash of the message hash. The secp256k1 program hashes the
+/// message hash once more before recovering signers, so this is what an `ecrecover` expects.
+pub fn compute_digest(vaa: &[u8]) -> Result<[u8; 32]> {
+    Ok(sha3::Keccak256::digest(&compute_message_hash(vaa)?).into())
+}
+
 impl From<VAA> for PostVAAData {
     fn from(vaa: VAA) -> Self {
         PostVAAData {
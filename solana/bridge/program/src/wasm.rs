@@ -5,13 +5,13 @@ use solana_program::{
 use std::str::FromStr;
 
 use crate::vaa::{
+    compute_message_hash,
     DeserializePayload,
     SignatureItem,
     VAA,
 };
 use borsh::BorshDeserialize;
 use byteorder::WriteBytesExt;
-use sha3::Digest;
 use solitaire::{
     processors::seeded::Seeded,
     AccountState,
@@ -35,8 +35,13 @@ use crate::{
         post_message,
         post_message_unreliable,
         post_vaa,
+        set_fee_exemption,
+        set_fee_mint,
         set_fees,
+        set_vaa_consumer,
+        sweep_fee_collector,
         transfer_fees,
+        transfer_token_fees,
         upgrade_contract,
         upgrade_guardian_set,
         verify_signatures,
@@ -44,6 +49,10 @@ use crate::{
     types::{
         ConsistencyLevel,
         GovernancePayloadGuardianSetChange,
+        GovernancePayloadSetFeeExemption,
+        GovernancePayloadSetFeeMint,
+        GovernancePayloadSetVAAConsumer,
+        GovernancePayloadSweepFeeCollector,
         GovernancePayloadTransferFees,
         GovernancePayloadUpgrade,
     },
@@ -194,7 +203,112 @@ pub fn transfer_fees_ix(program_id: String, payer: String, vaa: Vec<u8>) -> JsVa
         },
         &program_id,
     );
-    let ix = transfer_fees(
+    let payer = Pubkey::from_str(payer.as_str()).unwrap();
+    let emitter = Pubkey::new(&vaa.emitter_address);
+    let recipient = Pubkey::new(&payload.to[..]);
+    let ix = match payload.mint {
+        Some(mint) => transfer_token_fees(
+            program_id,
+            payer,
+            message_key,
+            emitter,
+            vaa.sequence,
+            mint,
+            recipient,
+        ),
+        None => transfer_fees(
+            program_id,
+            payer,
+            message_key,
+            emitter,
+            vaa.sequence,
+            recipient,
+        ),
+    };
+    return JsValue::from_serde(&ix).unwrap();
+}
+
+#[wasm_bindgen]
+pub fn set_fee_mint_ix(program_id: String, payer: String, vaa: Vec<u8>) -> JsValue {
+    let program_id = Pubkey::from_str(program_id.as_str()).unwrap();
+    let vaa = VAA::deserialize(vaa.as_slice()).unwrap();
+    let payload = GovernancePayloadSetFeeMint::deserialize(&mut vaa.payload.as_slice()).unwrap();
+    let message_key = PostedVAA::<'_, { AccountState::Uninitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: hash_vaa(&vaa.clone().into()).to_vec(),
+        },
+        &program_id,
+    );
+    let ix = set_fee_mint(
+        program_id,
+        Pubkey::from_str(payer.as_str()).unwrap(),
+        message_key,
+        Pubkey::new(&vaa.emitter_address),
+        vaa.sequence,
+        payload.mint,
+    );
+    return JsValue::from_serde(&ix).unwrap();
+}
+
+#[wasm_bindgen]
+pub fn set_fee_exemption_ix(program_id: String, payer: String, vaa: Vec<u8>) -> JsValue {
+    let program_id = Pubkey::from_str(program_id.as_str()).unwrap();
+    let vaa = VAA::deserialize(vaa.as_slice()).unwrap();
+    let payload =
+        GovernancePayloadSetFeeExemption::deserialize(&mut vaa.payload.as_slice()).unwrap();
+    let message_key = PostedVAA::<'_, { AccountState::Uninitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: hash_vaa(&vaa.clone().into()).to_vec(),
+        },
+        &program_id,
+    );
+    let ix = set_fee_exemption(
+        program_id,
+        Pubkey::from_str(payer.as_str()).unwrap(),
+        message_key,
+        Pubkey::new(&vaa.emitter_address),
+        vaa.sequence,
+        payload.emitter,
+    );
+    return JsValue::from_serde(&ix).unwrap();
+}
+
+#[wasm_bindgen]
+pub fn set_vaa_consumer_ix(program_id: String, payer: String, vaa: Vec<u8>) -> JsValue {
+    let program_id = Pubkey::from_str(program_id.as_str()).unwrap();
+    let vaa = VAA::deserialize(vaa.as_slice()).unwrap();
+    let payload =
+        GovernancePayloadSetVAAConsumer::deserialize(&mut vaa.payload.as_slice()).unwrap();
+    let message_key = PostedVAA::<'_, { AccountState::Uninitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: hash_vaa(&vaa.clone().into()).to_vec(),
+        },
+        &program_id,
+    );
+    let ix = set_vaa_consumer(
+        program_id,
+        Pubkey::from_str(payer.as_str()).unwrap(),
+        message_key,
+        Pubkey::new(&vaa.emitter_address),
+        vaa.sequence,
+        payload.consumer,
+    );
+    return JsValue::from_serde(&ix).unwrap();
+}
+
+#[wasm_bindgen]
+pub fn sweep_fee_collector_ix(program_id: String, payer: String, vaa: Vec<u8>) -> JsValue {
+    let program_id = Pubkey::from_str(program_id.as_str()).unwrap();
+    let vaa = VAA::deserialize(vaa.as_slice()).unwrap();
+    let payload =
+        GovernancePayloadSweepFeeCollector::deserialize(&mut vaa.payload.as_slice()).unwrap();
+    let message_key = PostedVAA::<'_, { AccountState::Uninitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: hash_vaa(&vaa.clone().into()).to_vec(),
+        },
+        &program_id,
+    );
+    let ix = sweep_fee_collector(
         program_id,
         Pubkey::from_str(payer.as_str()).unwrap(),
         message_key,
@@ -263,12 +377,7 @@ pub fn verify_signatures_ix(
         signature_items.push(item);
     }
 
-    let vaa_body = &vaa_data[VAA::HEADER_LEN + VAA::SIGNATURE_LEN * vaa.signatures.len()..];
-    let body_hash: [u8; 32] = {
-        let mut h = sha3::Keccak256::default();
-        h.write(vaa_body).unwrap();
-        h.finalize().into()
-    };
+    let body_hash = compute_message_hash(&vaa_data).unwrap();
 
     let mut verify_txs: Vec<Vec<Instruction>> = Vec::new();
     for (_tx_index, chunk) in signature_items.chunks(7).enumerate() {
@@ -1,4 +1,7 @@
-use borsh::BorshDeserialize;
+use borsh::{
+    BorshDeserialize,
+    BorshSerialize,
+};
 use byteorder::{
     BigEndian,
     WriteBytesExt,
@@ -10,14 +13,19 @@ use libsecp256k1::{
 };
 use sha3::Digest;
 use solana_program::{
-    instruction::Instruction,
+    instruction::{
+        AccountMeta,
+        Instruction,
+    },
     pubkey::Pubkey,
     system_instruction,
+    sysvar,
 };
 use solana_program_test::{
     BanksClient,
     BanksClientError,
     ProgramTest,
+    ProgramTestContext,
 };
 use solana_sdk::{
     commitment_config::CommitmentLevel,
@@ -27,7 +35,10 @@ use solana_sdk::{
         Signer,
     },
     signers::Signers,
-    transaction::Transaction,
+    transaction::{
+        Transaction,
+        TransactionError,
+    },
 };
 use std::{
     env,
@@ -39,13 +50,27 @@ use std::{
 };
 
 use bridge::{
-    accounts::FeeCollector,
+    accounts::{
+        Bridge,
+        FeeCollector,
+        GuardianSet,
+        GuardianSetDerivationData,
+        PostedMessage,
+        PostedMessageDerivationData,
+        PostedVAA,
+        PostedVAADerivationData,
+        Sequence,
+        SequenceDerivationData,
+        SequenceTracker,
+    },
     instruction,
     instructions,
     types::ConsistencyLevel,
+    PostMessageData,
     PostVAAData,
     VerifySignaturesData,
 };
+use solitaire::AccountState;
 
 use solitaire::processors::seeded::Seeded;
 
@@ -68,22 +93,329 @@ pub async fn execute<T: Signers>(
         .await
 }
 
+/// Like `execute`, but also returns the program logs, in which the bridge reports which of its
+/// errors made the transaction fail.
+pub async fn execute_with_logs<T: Signers>(
+    client: &mut BanksClient,
+    payer: &Keypair,
+    signers: &T,
+    instructions: &[Instruction],
+) -> Result<(Result<(), TransactionError>, Vec<String>), BanksClientError> {
+    let mut transaction = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+    let recent_blockhash = client.get_latest_blockhash().await?;
+    transaction.sign(signers, recent_blockhash);
+
+    let processed = client
+        .process_transaction_with_metadata(transaction)
+        .await?;
+    let logs = processed
+        .metadata
+        .map(|metadata| metadata.log_messages)
+        .unwrap_or_default();
+    Ok((processed.result, logs))
+}
+
 mod helpers {
     use super::*;
+    use bridge::{
+        accounts::{
+            Claim,
+            ClaimData,
+            ClaimDerivationData,
+            PostedVAAData,
+        },
+        cpi,
+        PostedMessageData,
+        EMITTER_AUTHORITY_SEED,
+    };
+    use solana_program::{
+        account_info::AccountInfo,
+        entrypoint::ProgramResult,
+        program::{
+            invoke_signed,
+            set_return_data,
+        },
+        program_error::ProgramError,
+        program_pack::Pack,
+        rent::Rent,
+    };
     use solana_program_test::processor;
 
+    /// Seed of the emitter PDA owned by the stand-in integrator program.
+    pub const EMITTER_SEED: &[u8] = b"emitter";
+
     /// Initialize the test environment, spins up a solana-test-validator in the background so that
     /// each test has a fresh environment to work within.
     pub async fn setup() -> (BanksClient, Keypair, Pubkey) {
+        let (builder, program) = program_test();
+        let (client, payer, _) = builder.start().await;
+
+        (client, payer, program)
+    }
+
+    /// Like `setup`, but keeps the test context around for tests that need to modify sysvars.
+    pub async fn setup_with_context() -> (ProgramTestContext, Pubkey) {
+        let (builder, program) = program_test();
+
+        (builder.start_with_context().await, program)
+    }
+
+    fn program_test() -> (ProgramTest, Pubkey) {
         let program = env::var("BRIDGE_PROGRAM")
             .unwrap_or_else(|_| "Bridge1p5gheXUvJ6jGWGeCsgPKgnE3YgdGKRVCMY9o".to_string())
             .parse::<Pubkey>()
             .unwrap();
-        let builder = ProgramTest::new("bridge", program, processor!(instruction::solitaire));
+        let mut builder = ProgramTest::new("bridge", program, processor!(instruction::solitaire));
+        builder.add_program(
+            "message_poster",
+            message_poster_id(),
+            processor!(message_poster),
+        );
+        builder.add_program(
+            "derived_message_poster",
+            derived_message_poster_id(),
+            processor!(derived_message_poster),
+        );
+        builder.add_program(
+            "program_emitter_poster",
+            program_emitter_poster_id(),
+            processor!(program_emitter_poster),
+        );
+        builder.add_program("vaa_consumer", vaa_consumer_id(), processor!(vaa_consumer));
+        builder.add_program("fee_querier", fee_querier_id(), processor!(fee_querier));
+        builder.add_program("vaa_poster", vaa_poster_id(), processor!(vaa_poster));
 
-        let (client, payer, _) = builder.start().await;
+        (builder, program)
+    }
 
-        (client, payer, program)
+    /// Address of the stand-in integrator program posting messages over CPI.
+    pub fn message_poster_id() -> Pubkey {
+        "MessagePoster111111111111111111111111111111"
+            .parse::<Pubkey>()
+            .unwrap()
+    }
+
+    /// Address of the stand-in integrator program posting messages to derived accounts over CPI.
+    pub fn derived_message_poster_id() -> Pubkey {
+        "DerivedPoster111111111111111111111111111111"
+            .parse::<Pubkey>()
+            .unwrap()
+    }
+
+    /// Stand-in for an integrator program. It posts the `PostMessageData` in its instruction data
+    /// through `bridge::cpi`, signing with its emitter PDA, and checks the sequence it is handed
+    /// back against the one stored in the message account.
+    fn message_poster(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+        post_from_emitter_pda(program_id, accounts, data, cpi::post_message_with_fee)
+    }
+
+    /// Same as `message_poster`, but posting to the message account derived by the bridge.
+    fn derived_message_poster(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        data: &[u8],
+    ) -> ProgramResult {
+        post_from_emitter_pda(program_id, accounts, data, cpi::post_message_derived)
+    }
+
+    /// Address of the stand-in integrator program posting messages as itself over CPI.
+    pub fn program_emitter_poster_id() -> Pubkey {
+        "ProgramEmitter11111111111111111111111111111"
+            .parse::<Pubkey>()
+            .unwrap()
+    }
+
+    /// Same as `message_poster`, but posting with its own program id as the emitter.
+    fn program_emitter_poster(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        data: &[u8],
+    ) -> ProgramResult {
+        let (_, bump) = Pubkey::find_program_address(&[EMITTER_AUTHORITY_SEED], program_id);
+        let data = PostMessageData::try_from_slice(data)?;
+
+        let sequence = cpi::post_message_program_emitter(
+            cpi::PostMessageProgramEmitter {
+                bridge: &accounts[0],
+                message: &accounts[1],
+                emitter_authority: &accounts[2],
+                emitter_program: &accounts[3],
+                sequence: &accounts[4],
+                payer: &accounts[5],
+                fee_collector: &accounts[6],
+                clock: &accounts[7],
+                rent: &accounts[8],
+                system_program: &accounts[9],
+                core_bridge_program: &accounts[10],
+                fee_exemption: accounts.get(11),
+                token_fee: None,
+            },
+            data,
+            &[&[EMITTER_AUTHORITY_SEED, &[bump]]],
+        )?;
+
+        let posted = PostedMessageData::deserialize(&mut &accounts[1].data.borrow()[..])?;
+        if posted.sequence != sequence {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(())
+    }
+
+    /// Address of the stand-in program claiming posted VAAs.
+    pub fn vaa_consumer_id() -> Pubkey {
+        "VaaConsumer11111111111111111111111111111111"
+            .parse::<Pubkey>()
+            .unwrap()
+    }
+
+    /// Stand-in for a program consuming VAAs. It creates the claim of the posted VAA it is passed,
+    /// derived like `claim::consume` does, without acting on the VAA in any way.
+    fn vaa_consumer(program_id: &Pubkey, accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+        let posted = PostedVAAData::deserialize(&mut &accounts[0].data.borrow()[..])?;
+        let seeds = Claim::seeds(&ClaimDerivationData {
+            emitter_address: posted.emitter_address,
+            emitter_chain: posted.emitter_chain,
+            sequence: posted.sequence,
+        });
+        let seeds: Vec<&[u8]> = seeds.iter().map(|seed| seed.as_slice()).collect();
+        let (claim, bump) = Pubkey::find_program_address(&seeds, program_id);
+        if claim != *accounts[1].key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let claim_data = ClaimData { claimed: true }.try_to_vec()?;
+        let bump = [bump];
+        let mut signer_seeds = seeds.clone();
+        signer_seeds.push(&bump);
+        invoke_signed(
+            &system_instruction::create_account(
+                accounts[2].key,
+                &claim,
+                Rent::default().minimum_balance(claim_data.len()),
+                claim_data.len() as u64,
+                program_id,
+            ),
+            &[
+                accounts[2].clone(),
+                accounts[1].clone(),
+                accounts[3].clone(),
+            ],
+            &[&signer_seeds],
+        )?;
+        accounts[1].data.borrow_mut().copy_from_slice(&claim_data);
+        Ok(())
+    }
+
+    /// Address of the stand-in program querying the message fee.
+    pub fn fee_querier_id() -> Pubkey {
+        "FeeQuerier111111111111111111111111111111111"
+            .parse::<Pubkey>()
+            .unwrap()
+    }
+
+    /// Stand-in for a program funding messages. It queries the fee over CPI and fails unless it
+    /// matches both the bridge config and the fee in its instruction data.
+    fn fee_querier(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+        let expected = u64::try_from_slice(data)?;
+        let fee = cpi::query_fee(cpi::QueryFee {
+            bridge: &accounts[0],
+            core_bridge_program: &accounts[1],
+        })?;
+
+        if fee != expected || fee != cpi::message_fee(&accounts[0])? {
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(())
+    }
+
+    /// Seed of the signature set PDAs owned by the stand-in VAA poster, followed by the body hash.
+    pub const SIGNATURE_SET_SEED: &[u8] = b"signature_set";
+
+    /// Address of the stand-in program posting VAAs over CPI.
+    pub fn vaa_poster_id() -> Pubkey {
+        "VaaPoster1111111111111111111111111111111111"
+            .parse::<Pubkey>()
+            .unwrap()
+    }
+
+    /// Stand-in for a relayer program posting VAAs on behalf of users. Its instruction data is
+    /// `(body hash, VerifySignaturesData, Option<PostVAAData>)`: it records the signatures of the
+    /// preceding secp256k1 instruction in its signature set PDA for the body, then posts the VAA if
+    /// one is passed.
+    fn vaa_poster(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+        let (body, signers, vaa) =
+            <([u8; 32], VerifySignaturesData, Option<PostVAAData>)>::try_from_slice(data)?;
+        let (_, bump) = Pubkey::find_program_address(&[SIGNATURE_SET_SEED, &body], program_id);
+
+        cpi::verify_signatures(
+            cpi::VerifySignatures {
+                payer: &accounts[0],
+                guardian_set: &accounts[1],
+                signature_set: &accounts[2],
+                instructions: &accounts[3],
+                rent: &accounts[4],
+                system_program: &accounts[5],
+                core_bridge_program: &accounts[9],
+            },
+            signers,
+            &[&[SIGNATURE_SET_SEED, &body, &[bump]]],
+        )?;
+
+        if let Some(vaa) = vaa {
+            cpi::post_vaa(
+                cpi::PostVAA {
+                    guardian_set: &accounts[1],
+                    bridge: &accounts[6],
+                    signature_set: &accounts[2],
+                    posted_vaa: &accounts[7],
+                    payer: &accounts[0],
+                    clock: &accounts[8],
+                    rent: &accounts[4],
+                    system_program: &accounts[5],
+                    core_bridge_program: &accounts[9],
+                },
+                vaa,
+                &[],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn post_from_emitter_pda(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        data: &[u8],
+        post: fn(cpi::PostMessage, PostMessageData, &[&[&[u8]]]) -> solitaire::Result<u64>,
+    ) -> ProgramResult {
+        let (_, bump) = Pubkey::find_program_address(&[EMITTER_SEED], program_id);
+        let data = PostMessageData::try_from_slice(data)?;
+
+        let sequence = post(
+            cpi::PostMessage {
+                bridge: &accounts[0],
+                message: &accounts[1],
+                emitter: &accounts[2],
+                sequence: &accounts[3],
+                payer: &accounts[4],
+                fee_collector: &accounts[5],
+                clock: &accounts[6],
+                rent: &accounts[7],
+                system_program: &accounts[8],
+                core_bridge_program: &accounts[9],
+                fee_exemption: accounts.get(10),
+                token_fee: None,
+            },
+            data,
+            &[&[EMITTER_SEED, &[bump]]],
+        )?;
+
+        let posted = PostedMessageData::deserialize(&mut &accounts[1].data.borrow()[..])?;
+        if posted.sequence != sequence {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        // Hand the sequence on so that tests can compare it with the one they expect.
+        set_return_data(&sequence.to_le_bytes());
+        Ok(())
     }
 
     /// Fetch account data, the loop is there to re-attempt until data is available.
@@ -100,6 +432,14 @@ mod helpers {
         client.get_account(account).await.unwrap().unwrap().lamports
     }
 
+    /// Fetch the balance of a token account
+    pub async fn get_token_balance(client: &mut BanksClient, account: Pubkey) -> u64 {
+        let account = client.get_account(account).await.unwrap().unwrap();
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
     /// Generate `count` secp256k1 private keys, along with their ethereum-styled public key
     /// encoding: 0x0123456789ABCDEF01234
     pub fn generate_keys(count: u8) -> (Vec<[u8; 20]>, Vec<SecretKey>) {
@@ -183,6 +523,24 @@ mod helpers {
         (vaa, body, body_hash)
     }
 
+    /// Serialize `vaa` with a signature of `body_hash` from each of `secret_keys`, in the wire
+    /// format guardians publish VAAs in.
+    pub fn sign_vaa(vaa: &PostVAAData, body_hash: [u8; 32], secret_keys: &[SecretKey]) -> Vec<u8> {
+        let mut v = Cursor::new(Vec::new());
+        v.write_u8(vaa.version).unwrap();
+        v.write_u32::<BigEndian>(vaa.guardian_set_index).unwrap();
+        v.write_u8(secret_keys.len() as u8).unwrap();
+        for (i, key) in secret_keys.iter().enumerate() {
+            let (signature, recovery_id) =
+                libsecp256k1::sign(&libsecp256k1::Message::parse(&body_hash), key);
+            v.write_u8(i as u8).unwrap();
+            v.write_all(&signature.serialize()).unwrap();
+            v.write_u8(recovery_id.serialize()).unwrap();
+        }
+        v.write_all(&instructions::serialize_vaa(vaa)).unwrap();
+        v.into_inner()
+    }
+
     pub async fn initialize(
         client: &mut BanksClient,
         program: Pubkey,
@@ -217,11 +575,7 @@ mod helpers {
         message: Option<&Keypair>,
         nonce: u32,
         data: Vec<u8>,
-        fee: u64,
     ) -> Result<Pubkey, BanksClientError> {
-        // Transfer money into the fee collector as it needs a balance/must exist.
-        let fee_collector = FeeCollector::<'_>::key(None, program);
-
         let new_message_pair = &Keypair::new();
 
         let message: &Keypair = match message {
@@ -230,7 +584,7 @@ mod helpers {
         };
 
         // Capture the resulting message, later functions will need this.
-        let instruction = instructions::post_message(
+        let instruction = instructions::post_message_v2(
             *program,
             payer.pubkey(),
             emitter.pubkey(),
@@ -245,10 +599,49 @@ mod helpers {
             client,
             payer,
             &[payer, emitter, message],
-            &[
-                system_instruction::transfer(&payer.pubkey(), &fee_collector, fee),
+            &[instruction],
+            CommitmentLevel::Processed,
+        )
+        .await?;
+
+        Ok(message.pubkey())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    /// Like `post_message`, paying the fee in `mint` from the token account `payer_tokens`, for
+    /// while governance has set a fee mint.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn post_message_with_token_fee(
+        client: &mut BanksClient,
+        program: &Pubkey,
+        payer: &Keypair,
+        emitter: &Keypair,
+        nonce: u32,
+        data: Vec<u8>,
+        mint: &Pubkey,
+        payer_tokens: &Pubkey,
+    ) -> Result<Pubkey, BanksClientError> {
+        let message = Keypair::new();
+        let instruction = instructions::post_message(
+            *program,
+            payer.pubkey(),
+            emitter.pubkey(),
+            message.pubkey(),
+            nonce,
+            data,
+            ConsistencyLevel::Confirmed,
+        )
+        .unwrap();
+
+        execute(
+            client,
+            payer,
+            &[payer, emitter, &message],
+            &[instructions::with_token_fee(
                 instruction,
-            ],
+                *mint,
+                *payer_tokens,
+            )],
             CommitmentLevel::Processed,
         )
         .await?;
@@ -334,6 +727,154 @@ mod helpers {
         Ok(signature_set.pubkey())
     }
 
+    /// Claim `posted_vaa` through the stand-in consumer program, returning the claim account.
+    pub async fn consume_vaa(
+        client: &mut BanksClient,
+        payer: &Keypair,
+        posted_vaa: Pubkey,
+    ) -> Result<Pubkey, BanksClientError> {
+        let posted: PostedVAAData = get_account_data(client, posted_vaa).await;
+        let claim = Claim::key(
+            &ClaimDerivationData {
+                emitter_address: posted.emitter_address,
+                emitter_chain: posted.emitter_chain,
+                sequence: posted.sequence,
+            },
+            &vaa_consumer_id(),
+        );
+        execute(
+            client,
+            payer,
+            &[payer],
+            &[Instruction {
+                program_id: vaa_consumer_id(),
+                accounts: vec![
+                    AccountMeta::new_readonly(posted_vaa, false),
+                    AccountMeta::new(claim, false),
+                    AccountMeta::new(payer.pubkey(), true),
+                    AccountMeta::new_readonly(solana_program::system_program::id(), false),
+                ],
+                data: vec![],
+            }],
+            CommitmentLevel::Processed,
+        )
+        .await?;
+        Ok(claim)
+    }
+
+    /// Verify and post `vaa` entirely through the stand-in VAA poster, one guardian signature per
+    /// transaction, returning the signature set it created.
+    pub async fn post_vaa_cpi(
+        client: &mut BanksClient,
+        program: &Pubkey,
+        payer: &Keypair,
+        vaa: PostVAAData,
+        body: [u8; 32],
+        secret_keys: &[SecretKey],
+    ) -> Result<Pubkey, BanksClientError> {
+        let (signature_set, _) =
+            Pubkey::find_program_address(&[SIGNATURE_SET_SEED, &body], &vaa_poster_id());
+        let guardian_set = GuardianSet::<'_, { AccountState::Uninitialized }>::key(
+            &GuardianSetDerivationData {
+                index: vaa.guardian_set_index,
+            },
+            program,
+        );
+        let posted_vaa = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+            &PostedVAADerivationData {
+                payload_hash: body.to_vec(),
+            },
+            program,
+        );
+
+        for (i, key) in secret_keys.iter().enumerate() {
+            let mut signers = [-1; 19];
+            signers[i] = 0;
+            let last = i + 1 == secret_keys.len();
+            let data = (
+                body,
+                VerifySignaturesData { signers },
+                if last { Some(vaa.clone()) } else { None },
+            );
+
+            execute(
+                client,
+                payer,
+                &[payer],
+                &[
+                    new_secp256k1_instruction(key, &body),
+                    Instruction {
+                        program_id: vaa_poster_id(),
+                        accounts: vec![
+                            AccountMeta::new(payer.pubkey(), true),
+                            AccountMeta::new_readonly(guardian_set, false),
+                            AccountMeta::new(signature_set, false),
+                            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+                            AccountMeta::new_readonly(sysvar::rent::id(), false),
+                            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+                            AccountMeta::new_readonly(
+                                Bridge::<'_, { AccountState::Uninitialized }>::key(None, program),
+                                false,
+                            ),
+                            AccountMeta::new(posted_vaa, false),
+                            AccountMeta::new_readonly(sysvar::clock::id(), false),
+                            AccountMeta::new_readonly(*program, false),
+                        ],
+                        data: data.try_to_vec().unwrap(),
+                    },
+                ],
+                CommitmentLevel::Processed,
+            )
+            .await?;
+        }
+
+        Ok(signature_set)
+    }
+
+    /// Query the message fee through the stand-in querier program, which fails unless it is
+    /// `expected`.
+    pub async fn query_fee_cpi(
+        client: &mut BanksClient,
+        program: &Pubkey,
+        payer: &Keypair,
+        expected: u64,
+    ) -> Result<(), BanksClientError> {
+        let bridge = Bridge::<'_, { AccountState::Uninitialized }>::key(None, program);
+        execute(
+            client,
+            payer,
+            &[payer],
+            &[Instruction {
+                program_id: fee_querier_id(),
+                accounts: vec![
+                    AccountMeta::new_readonly(bridge, false),
+                    AccountMeta::new_readonly(*program, false),
+                ],
+                data: expected.try_to_vec().unwrap(),
+            }],
+            CommitmentLevel::Processed,
+        )
+        .await
+    }
+
+    pub async fn close_posted_vaa(
+        client: &mut BanksClient,
+        program: &Pubkey,
+        payer: &Keypair,
+        posted_vaa: Pubkey,
+        claim: Pubkey,
+        consumer: Pubkey,
+    ) -> Result<(), BanksClientError> {
+        execute(
+            client,
+            payer,
+            &[payer],
+            &[instructions::close_posted_vaa(*program, posted_vaa, claim, consumer).unwrap()],
+            CommitmentLevel::Processed,
+        )
+        .await
+    }
+
     pub async fn post_vaa(
         client: &mut BanksClient,
         program: &Pubkey,
@@ -463,4 +1004,432 @@ mod helpers {
         )
         .await
     }
+
+    /// Post a message through the stand-in integrator program, returning the emitter PDA the
+    /// message was posted from and the sequence `cpi::post_message_with_fee` returned to the
+    /// program. `message_payer` funds the message accounts and the fee, `payer` the transaction.
+    /// `fee_exemption` is handed to the program for it to pass on to the bridge.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn post_message_cpi(
+        client: &mut BanksClient,
+        program: &Pubkey,
+        payer: &Keypair,
+        message_payer: &Keypair,
+        message: &Keypair,
+        nonce: u32,
+        data: Vec<u8>,
+        fee_exemption: Option<Pubkey>,
+    ) -> Result<(Pubkey, u64), BanksClientError> {
+        let poster = message_poster_id();
+        let (emitter, _) = Pubkey::find_program_address(&[EMITTER_SEED], &poster);
+        let fee_collector = FeeCollector::<'_>::key(None, program);
+        let sequence = Sequence::<'_>::key(
+            &SequenceDerivationData {
+                emitter_key: &emitter,
+            },
+            program,
+        );
+
+        let mut instruction = Instruction {
+            program_id: poster,
+            accounts: vec![
+                AccountMeta::new(
+                    Bridge::<'_, { AccountState::Initialized }>::key(None, program),
+                    false,
+                ),
+                AccountMeta::new(message.pubkey(), true),
+                AccountMeta::new_readonly(emitter, false),
+                AccountMeta::new(sequence, false),
+                AccountMeta::new(message_payer.pubkey(), true),
+                AccountMeta::new(fee_collector, false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(solana_program::system_program::id(), false),
+                AccountMeta::new_readonly(*program, false),
+            ],
+            data: PostMessageData {
+                nonce,
+                payload: data,
+                consistency_level: ConsistencyLevel::Confirmed,
+            }
+            .try_to_vec()
+            .unwrap(),
+        };
+        if let Some(fee_exemption) = fee_exemption {
+            instruction
+                .accounts
+                .push(AccountMeta::new_readonly(fee_exemption, false));
+        }
+
+        let (result, logs) = execute_with_logs(
+            client,
+            payer,
+            &[payer, message_payer, message],
+            &[instruction],
+        )
+        .await?;
+        result.map_err(BanksClientError::TransactionError)?;
+
+        // The runtime logs the return data of each program as `Program return: <id> <base64>`.
+        let prefix = format!("Program return: {} ", poster);
+        let returned = logs
+            .iter()
+            .rev()
+            .find_map(|log| log.strip_prefix(&prefix))
+            .map(|data| base64::decode(data).unwrap())
+            .unwrap();
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&returned);
+        Ok((emitter, u64::from_le_bytes(bytes)))
+    }
+
+    /// Post a message through the stand-in integrator program to the message account the bridge
+    /// derives, returning the emitter PDA and the message address. Only `payer` signs.
+    pub async fn post_message_derived_cpi(
+        client: &mut BanksClient,
+        program: &Pubkey,
+        payer: &Keypair,
+        nonce: u32,
+        data: Vec<u8>,
+    ) -> Result<(Pubkey, Pubkey), BanksClientError> {
+        let poster = derived_message_poster_id();
+        let (emitter, _) = Pubkey::find_program_address(&[EMITTER_SEED], &poster);
+        let fee_collector = FeeCollector::<'_>::key(None, program);
+        let sequence = Sequence::<'_>::key(
+            &SequenceDerivationData {
+                emitter_key: &emitter,
+            },
+            program,
+        );
+
+        // The message is derived from the sequence it will be assigned.
+        let next_sequence = match client.get_account(sequence).await? {
+            Some(account) => {
+                SequenceTracker::try_from_slice(&account.data)
+                    .unwrap()
+                    .sequence
+            }
+            None => 0,
+        };
+        let message = PostedMessage::<'_, { AccountState::Uninitialized }>::key(
+            &PostedMessageDerivationData {
+                emitter_key: &emitter,
+                sequence: next_sequence,
+            },
+            program,
+        );
+
+        let instruction = Instruction {
+            program_id: poster,
+            accounts: vec![
+                AccountMeta::new(
+                    Bridge::<'_, { AccountState::Initialized }>::key(None, program),
+                    false,
+                ),
+                AccountMeta::new(message, false),
+                AccountMeta::new_readonly(emitter, false),
+                AccountMeta::new(sequence, false),
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new(fee_collector, false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(solana_program::system_program::id(), false),
+                AccountMeta::new_readonly(*program, false),
+            ],
+            data: PostMessageData {
+                nonce,
+                payload: data,
+                consistency_level: ConsistencyLevel::Confirmed,
+            }
+            .try_to_vec()
+            .unwrap(),
+        };
+
+        execute(
+            client,
+            payer,
+            &[payer],
+            &[instruction],
+            CommitmentLevel::Processed,
+        )
+        .await?;
+
+        Ok((emitter, message))
+    }
+
+    /// Post a message through the stand-in integrator program with its program id as the emitter.
+    pub async fn post_message_program_emitter_cpi(
+        client: &mut BanksClient,
+        program: &Pubkey,
+        payer: &Keypair,
+        message: &Keypair,
+        nonce: u32,
+        data: Vec<u8>,
+    ) -> Result<(), BanksClientError> {
+        let poster = program_emitter_poster_id();
+        let (emitter_authority, _) =
+            Pubkey::find_program_address(&[EMITTER_AUTHORITY_SEED], &poster);
+        let fee_collector = FeeCollector::<'_>::key(None, program);
+        let sequence = Sequence::<'_>::key(
+            &SequenceDerivationData {
+                emitter_key: &poster,
+            },
+            program,
+        );
+
+        let instruction = Instruction {
+            program_id: poster,
+            accounts: vec![
+                AccountMeta::new(
+                    Bridge::<'_, { AccountState::Initialized }>::key(None, program),
+                    false,
+                ),
+                AccountMeta::new(message.pubkey(), true),
+                AccountMeta::new_readonly(emitter_authority, false),
+                AccountMeta::new_readonly(poster, false),
+                AccountMeta::new(sequence, false),
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new(fee_collector, false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(solana_program::system_program::id(), false),
+                AccountMeta::new_readonly(*program, false),
+            ],
+            data: PostMessageData {
+                nonce,
+                payload: data,
+                consistency_level: ConsistencyLevel::Confirmed,
+            }
+            .try_to_vec()
+            .unwrap(),
+        };
+
+        execute(
+            client,
+            payer,
+            &[payer, message],
+            &[instruction],
+            CommitmentLevel::Processed,
+        )
+        .await
+    }
+
+    pub async fn init_message(
+        client: &mut BanksClient,
+        program: &Pubkey,
+        payer: &Keypair,
+        emitter: &Keypair,
+        message: &Keypair,
+        payload_size: u32,
+    ) -> Result<(), BanksClientError> {
+        execute(
+            client,
+            payer,
+            &[payer, emitter, message],
+            &[instructions::init_message(
+                *program,
+                payer.pubkey(),
+                emitter.pubkey(),
+                message.pubkey(),
+                payload_size,
+            )
+            .unwrap()],
+            CommitmentLevel::Processed,
+        )
+        .await
+    }
+
+    pub async fn write_message(
+        client: &mut BanksClient,
+        program: &Pubkey,
+        payer: &Keypair,
+        emitter: &Keypair,
+        message: &Pubkey,
+        offset: u32,
+        data: Vec<u8>,
+    ) -> Result<(), BanksClientError> {
+        execute(
+            client,
+            payer,
+            &[payer, emitter],
+            &[
+                instructions::write_message(*program, emitter.pubkey(), *message, offset, data)
+                    .unwrap(),
+            ],
+            CommitmentLevel::Processed,
+        )
+        .await
+    }
+
+    pub async fn finalize_message(
+        client: &mut BanksClient,
+        program: &Pubkey,
+        payer: &Keypair,
+        emitter: &Keypair,
+        message: &Pubkey,
+    ) -> Result<(), BanksClientError> {
+        execute(
+            client,
+            payer,
+            &[payer, emitter],
+            &[instructions::finalize_message(*program, emitter.pubkey(), *message).unwrap()],
+            CommitmentLevel::Processed,
+        )
+        .await
+    }
+
+    pub async fn post_prepared_message(
+        client: &mut BanksClient,
+        program: &Pubkey,
+        payer: &Keypair,
+        emitter: &Keypair,
+        message: &Pubkey,
+        nonce: u32,
+        fee: u64,
+    ) -> Result<(), BanksClientError> {
+        let fee_collector = FeeCollector::<'_>::key(None, program);
+
+        execute(
+            client,
+            payer,
+            &[payer, emitter],
+            &[
+                system_instruction::transfer(&payer.pubkey(), &fee_collector, fee),
+                instructions::post_prepared_message(
+                    *program,
+                    payer.pubkey(),
+                    emitter.pubkey(),
+                    *message,
+                    nonce,
+                    ConsistencyLevel::Confirmed,
+                )
+                .unwrap(),
+            ],
+            CommitmentLevel::Processed,
+        )
+        .await
+    }
+
+    pub async fn close_message(
+        client: &mut BanksClient,
+        program: &Pubkey,
+        payer: &Keypair,
+        emitter: &Keypair,
+        message: &Pubkey,
+        recipient: &Pubkey,
+    ) -> Result<(), BanksClientError> {
+        execute(
+            client,
+            payer,
+            &[payer, emitter],
+            &[
+                instructions::close_message(*program, emitter.pubkey(), *message, *recipient)
+                    .unwrap(),
+            ],
+            CommitmentLevel::Processed,
+        )
+        .await
+    }
+
+    pub async fn transfer_message_authority(
+        client: &mut BanksClient,
+        program: &Pubkey,
+        payer: &Keypair,
+        emitter: &Keypair,
+        message: &Pubkey,
+        new_emitter: &Pubkey,
+    ) -> Result<(), BanksClientError> {
+        execute(
+            client,
+            payer,
+            &[payer, emitter],
+            &[instructions::transfer_message_authority(
+                *program,
+                emitter.pubkey(),
+                *message,
+                *new_emitter,
+            )
+            .unwrap()],
+            CommitmentLevel::Processed,
+        )
+        .await
+    }
+
+    /// Create a mint without decimals, with `mint_authority` allowed to mint.
+    pub async fn create_mint(
+        client: &mut BanksClient,
+        payer: &Keypair,
+        mint_authority: &Pubkey,
+        mint: &Keypair,
+    ) -> Result<(), BanksClientError> {
+        execute(
+            client,
+            payer,
+            &[payer, mint],
+            &[
+                system_instruction::create_account(
+                    &payer.pubkey(),
+                    &mint.pubkey(),
+                    Rent::default().minimum_balance(spl_token::state::Mint::LEN),
+                    spl_token::state::Mint::LEN as u64,
+                    &spl_token::id(),
+                ),
+                spl_token::instruction::initialize_mint(
+                    &spl_token::id(),
+                    &mint.pubkey(),
+                    mint_authority,
+                    None,
+                    0,
+                )
+                .unwrap(),
+            ],
+            CommitmentLevel::Processed,
+        )
+        .await
+    }
+
+    /// Create a token account of `mint` owned by `owner`, holding `amount` minted by
+    /// `mint_authority`.
+    pub async fn create_token_account(
+        client: &mut BanksClient,
+        payer: &Keypair,
+        token_account: &Keypair,
+        owner: &Pubkey,
+        mint: &Pubkey,
+        mint_authority: &Keypair,
+        amount: u64,
+    ) -> Result<(), BanksClientError> {
+        execute(
+            client,
+            payer,
+            &[payer, token_account, mint_authority],
+            &[
+                system_instruction::create_account(
+                    &payer.pubkey(),
+                    &token_account.pubkey(),
+                    Rent::default().minimum_balance(spl_token::state::Account::LEN),
+                    spl_token::state::Account::LEN as u64,
+                    &spl_token::id(),
+                ),
+                spl_token::instruction::initialize_account(
+                    &spl_token::id(),
+                    &token_account.pubkey(),
+                    mint,
+                    owner,
+                )
+                .unwrap(),
+                spl_token::instruction::mint_to(
+                    &spl_token::id(),
+                    mint,
+                    &token_account.pubkey(),
+                    &mint_authority.pubkey(),
+                    &[],
+                    amount,
+                )
+                .unwrap(),
+            ],
+            CommitmentLevel::Processed,
+        )
+        .await
+    }
 }
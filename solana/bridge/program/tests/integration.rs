@@ -1,12 +1,23 @@
+use borsh::{
+    BorshDeserialize,
+    BorshSerialize,
+};
 use libsecp256k1::SecretKey;
 use rand::Rng;
 use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::MAX_PERMITTED_DATA_INCREASE,
+    instruction::AccountMeta,
+    native_token::LAMPORTS_PER_SOL,
     pubkey::Pubkey,
     system_instruction,
+    sysvar::clock::Clock,
 };
 use solana_program_test::{
     tokio,
     BanksClient,
+    BanksClientError,
+    ProgramTestContext,
 };
 use solana_sdk::{
     commitment_config::CommitmentLevel,
@@ -14,34 +25,111 @@ use solana_sdk::{
         Keypair,
         Signer,
     },
+    transaction::TransactionError,
 };
 use solitaire::{
     processors::seeded::Seeded,
     AccountState,
+    SolitaireError,
 };
+use std::convert::TryFrom;
 
 use bridge::{
     accounts::{
+        find_bridge,
+        find_emitter_sequence,
+        find_fee_collector,
+        find_guardian_set,
+        find_posted_vaa,
         Bridge,
+        BridgeConfig,
         BridgeData,
+        BridgeV1,
+        Claim,
+        ClaimDerivationData,
         FeeCollector,
+        FeeExemption,
+        FeeExemptionData,
+        FeeExemptionDerivationData,
+        FeeTokenAccount,
+        FeeTokenAccountDerivationData,
         GuardianSet,
         GuardianSetData,
         GuardianSetDerivationData,
+        MessageData,
+        PostedMessageData,
+        PostedMessageV1,
         PostedVAA,
         PostedVAAData,
         PostedVAADerivationData,
+        PostedVAAV1,
+        Sequence,
+        SequenceDerivationData,
+        SequenceTracker,
         SignatureSetData,
+        SignatureSetV1,
+        VerifiedHash,
+        VerifiedHashData,
+        VerifiedHashDerivationData,
+        BRIDGE_SIZE,
+        BRIDGE_WITH_FEE_MINT_SIZE,
+        EMITTER_ADDRESS_OFFSET,
+        EMITTER_CHAIN_OFFSET,
+        NONCE_OFFSET,
+        PAYLOAD_LEN_OFFSET,
+        PAYLOAD_OFFSET,
+        SEQUENCE_OFFSET,
+        SUBMISSION_TIME_OFFSET,
+        VAA_SIGNATURE_ACCOUNT_OFFSET,
+        VAA_TIME_OFFSET,
+        VAA_VERSION_OFFSET,
+    },
+    cpi,
+    error::Error,
+    events::{
+        Event,
+        PostVAAEvent,
+        SetFeesEvent,
+        SweepFeeCollectorEvent,
+        UpgradeGuardianSetEvent,
     },
     instructions,
+    precompile::{
+        self,
+        Secp256k1Verification,
+    },
     types::{
+        ChainId,
         ConsistencyLevel,
+        ForeignEmitter,
         GovernancePayloadGuardianSetChange,
+        GovernancePayloadSetFeeExemption,
+        GovernancePayloadSetFeeMint,
+        GovernancePayloadSetGuardianSetExpiration,
         GovernancePayloadSetMessageFee,
+        GovernancePayloadSetVAAConsumer,
+        GovernancePayloadSweepFeeCollector,
         GovernancePayloadTransferFees,
         GovernancePayloadUpgrade,
+        UniversalAddress,
+    },
+    vaa::{
+        compute_digest,
+        compute_message_hash,
+        governance_module,
+        require_valid_governance_vaa,
+        GovernanceHeader,
+        VAA,
     },
+    PostMessageData,
+    PostVAAData,
     SerializeGovernancePayload,
+    VerifySignaturesData,
+    CHAIN_ID_SOLANA,
+    MAX_GUARDIAN_SET_EXPIRATION_TIME,
+    MAX_MESSAGE_PAYLOAD_SIZE,
+    MIN_GUARDIAN_SET_EXPIRATION_TIME,
+    POST_MESSAGE_ACCOUNTS,
 };
 use primitive_types::U256;
 use solana_program::rent::Rent;
@@ -79,6 +167,26 @@ impl Sequencer {
 }
 
 async fn initialize() -> (Context, BanksClient, Keypair, Pubkey) {
+    let (mut client, payer, program) = common::setup().await;
+    let context = initialize_bridge(&mut client, &payer, &program).await;
+
+    (context, client, payer, program)
+}
+
+/// Like `initialize`, but returns the test context so the test can warp the clock.
+async fn initialize_with_context() -> (Context, ProgramTestContext, Pubkey) {
+    let (mut test_context, program) = common::setup_with_context().await;
+    let context = initialize_bridge(
+        &mut test_context.banks_client,
+        &test_context.payer,
+        &program,
+    )
+    .await;
+
+    (context, test_context, program)
+}
+
+async fn initialize_bridge(client: &mut BanksClient, payer: &Keypair, program: &Pubkey) -> Context {
     let (public_keys, secret_keys) = common::generate_keys(6);
     let context = Context {
         public: public_keys,
@@ -87,7 +195,7 @@ async fn initialize() -> (Context, BanksClient, Keypair, Pubkey) {
             sequences: std::collections::HashMap::new(),
         },
     };
-    let (mut client, payer, program) = common::setup().await;
+    let program = *program;
 
     // Use a timestamp from a few seconds earlier for testing to simulate thread::sleep();
     let now = std::time::SystemTime::now()
@@ -96,7 +204,7 @@ async fn initialize() -> (Context, BanksClient, Keypair, Pubkey) {
         .as_secs()
         - 10;
 
-    common::initialize(&mut client, program, &payer, &context.public, 500)
+    common::initialize(client, program, payer, &context.public, 500)
         .await
         .unwrap();
 
@@ -108,9 +216,8 @@ async fn initialize() -> (Context, BanksClient, Keypair, Pubkey) {
     );
 
     // Fetch account states.
-    let bridge: BridgeData = common::get_account_data(&mut client, bridge_key).await;
-    let guardian_set: GuardianSetData =
-        common::get_account_data(&mut client, guardian_set_key).await;
+    let bridge: BridgeData = common::get_account_data(client, bridge_key).await;
+    let guardian_set: GuardianSetData = common::get_account_data(client, guardian_set_key).await;
 
     // Bridge Config should be as expected.
     assert_eq!(bridge.guardian_set_index, 0);
@@ -122,7 +229,167 @@ async fn initialize() -> (Context, BanksClient, Keypair, Pubkey) {
     assert_eq!(guardian_set.keys, context.public);
     assert!(guardian_set.creation_time as u64 > now);
 
-    (context, client, payer, program)
+    context
+}
+
+/// Replace the current guardian set with `new_keys` through governance, signed by the current set
+/// which is assumed to hold the keys in `context`. Returns the time of the governance VAA, from
+/// which the expiration of the old set is counted.
+async fn update_guardian_set(
+    context: &mut Context,
+    client: &mut BanksClient,
+    payer: &Keypair,
+    program: &Pubkey,
+    new_index: u32,
+    new_keys: Vec<[u8; 20]>,
+) -> u32 {
+    let (result, _logs) =
+        try_update_guardian_set(context, client, payer, program, new_index, new_keys).await;
+    result.unwrap()
+}
+
+/// Like `update_guardian_set`, but returning the result and logs of the upgrade instruction
+/// itself. The decree is posted as a draft message, which keeps it within the transaction size
+/// limit for large guardian sets.
+async fn try_update_guardian_set(
+    context: &mut Context,
+    client: &mut BanksClient,
+    payer: &Keypair,
+    program: &Pubkey,
+    new_index: u32,
+    new_keys: Vec<[u8; 20]>,
+) -> (Result<u32, TransactionError>, Vec<String>) {
+    let governance = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
+    let sequence = context.seq.next(governance.pubkey().to_bytes());
+    let nonce = rand::thread_rng().gen();
+    let message = GovernancePayloadGuardianSetChange {
+        new_guardian_set_index: new_index,
+        new_guardian_set: new_keys,
+    }
+    .try_to_vec()
+    .unwrap();
+
+    let message_key = Keypair::new();
+    common::init_message(
+        client,
+        program,
+        payer,
+        &governance,
+        &message_key,
+        message.len() as u32,
+    )
+    .await
+    .unwrap();
+    for (i, chunk) in message.chunks(512).enumerate() {
+        common::write_message(
+            client,
+            program,
+            payer,
+            &governance,
+            &message_key.pubkey(),
+            (i * 512) as u32,
+            chunk.to_vec(),
+        )
+        .await
+        .unwrap();
+    }
+    common::finalize_message(client, program, payer, &governance, &message_key.pubkey())
+        .await
+        .unwrap();
+    common::post_prepared_message(
+        client,
+        program,
+        payer,
+        &governance,
+        &message_key.pubkey(),
+        nonce,
+        10_000,
+    )
+    .await
+    .unwrap();
+
+    let (vaa, body, _body_hash) =
+        common::generate_vaa(&governance, message, nonce, sequence, new_index - 1, 1);
+    let vaa_time = vaa.timestamp;
+    let signature_set =
+        common::verify_signatures(client, program, payer, body, &context.secret, new_index - 1)
+            .await
+            .unwrap();
+    common::post_vaa(client, program, payer, signature_set, vaa)
+        .await
+        .unwrap();
+    let ix = instructions::upgrade_guardian_set(
+        *program,
+        payer.pubkey(),
+        message_key.pubkey(),
+        governance.pubkey(),
+        new_index - 1,
+        new_index,
+        sequence,
+    );
+    let (result, logs) = common::execute_with_logs(client, payer, &[payer], &[ix])
+        .await
+        .unwrap();
+
+    (result.map(|()| vaa_time), logs)
+}
+
+fn assert_bridge_error(logs: &[String], error: Error) {
+    let expected = format!("Error: Custom({})", error as u64);
+    assert!(
+        logs.iter().any(|log| log.ends_with(&expected)),
+        "expected {:?} in {:#?}",
+        expected,
+        logs
+    );
+}
+
+#[tokio::test]
+async fn initialize_guardian_keys() {
+    let (ref mut client, ref payer, ref program) = common::setup().await;
+    let (public_keys, secret_keys) = common::generate_keys(3);
+
+    let duplicate = vec![public_keys[0], public_keys[1], public_keys[0]];
+    let zero = vec![public_keys[0], [0; 20]];
+    for keys in [duplicate, zero, vec![]] {
+        let ix =
+            instructions::initialize(*program, payer.pubkey(), 500, 2_000_000_000, &keys).unwrap();
+        let (result, logs) = common::execute_with_logs(client, payer, &[payer], &[ix])
+            .await
+            .unwrap();
+        assert!(result.is_err());
+        assert_bridge_error(&logs, Error::InvalidGuardianKeys);
+    }
+
+    common::initialize(client, *program, payer, &public_keys, 500)
+        .await
+        .unwrap();
+    // The bridge can only be initialized once.
+    assert!(
+        common::initialize(client, *program, payer, &public_keys[..1], 500)
+            .await
+            .is_err()
+    );
+
+    // VAAs signed by the initial guardian set are accepted.
+    let (vaa, body, _body_hash) = common::generate_vaa(&Keypair::new(), vec![1; 8], 0, 0, 0, 2);
+    let signature_set = common::verify_signatures(client, program, payer, body, &secret_keys, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, program, payer, signature_set, vaa)
+        .await
+        .unwrap();
+    let posted: PostedVAAData = common::get_account_data(
+        client,
+        PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+            &PostedVAADerivationData {
+                payload_hash: body.to_vec(),
+            },
+            program,
+        ),
+    )
+    .await;
+    assert_eq!(posted.payload, vec![1; 8]);
 }
 
 #[tokio::test]
@@ -147,7 +414,6 @@ async fn bridge_messages() {
             None,
             nonce,
             message.clone(),
-            10_000,
         )
         .await
         .unwrap();
@@ -237,7 +503,6 @@ async fn bridge_messages() {
         None,
         nonce,
         message.clone(),
-        10_000,
     )
     .await
     .unwrap();
@@ -413,11 +678,12 @@ async fn test_bridge_messages_unreliable() {
     .await
     .is_err());
 
-    // Make sure that posting a message with a different length fails (>len)
+    // A larger payload grows the account and overwrites the previous message (>len)
     let nonce = rand::thread_rng().gen();
-    let message: [u8; 128] = [0u8; 128];
+    let message: [u8; 128] = [1u8; 128];
+    let sequence = context.seq.next(emitter.pubkey().to_bytes());
 
-    assert!(common::post_message_unreliable(
+    common::post_message_unreliable(
         client,
         program,
         payer,
@@ -428,7 +694,21 @@ async fn test_bridge_messages_unreliable() {
         10_000,
     )
     .await
-    .is_err());
+    .unwrap();
+
+    let posted_message: PostedVAAData =
+        common::get_account_data(client, message_key.pubkey()).await;
+    assert_eq!(posted_message.message.nonce, nonce);
+    assert_eq!(posted_message.message.sequence, sequence);
+    assert_eq!(posted_message.message.payload, message.to_vec());
+
+    // The grown account must still be rent exempt.
+    let account = client
+        .get_account(message_key.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(Rent::default().is_exempt(account.lamports, account.data.len()));
 }
 
 #[tokio::test]
@@ -450,7 +730,6 @@ async fn test_bridge_messages_unreliable_do_not_override_reliable() {
         Some(&message_key),
         nonce,
         message.to_vec(),
-        10_000,
     )
     .await
     .unwrap();
@@ -491,6 +770,7 @@ async fn bridge_works_after_transfer_fees() {
         let message = GovernancePayloadTransferFees {
             amount: 500u128.into(),
             to: payer.pubkey().to_bytes(),
+            mint: None,
         }
         .try_to_vec()
         .unwrap();
@@ -503,7 +783,6 @@ async fn bridge_works_after_transfer_fees() {
             None,
             nonce,
             message.clone(),
-            500,
         )
         .await
         .unwrap();
@@ -540,7 +819,6 @@ async fn bridge_works_after_transfer_fees() {
             None,
             nonce,
             message.to_vec(),
-            500,
         )
         .await
         .unwrap();
@@ -622,205 +900,3011 @@ async fn test_bridge_message_prefunded_account() {
 }
 
 #[tokio::test]
-async fn invalid_emitter() {
+async fn bridge_messages_v2_single_transaction() {
     let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+    let fee_collector = FeeCollector::<'_>::key(None, program);
+    let bridge_key = Bridge::<'_, { AccountState::Uninitialized }>::key(None, program);
+    let bridge: BridgeData = common::get_account_data(client, bridge_key).await;
+    let fee = bridge.config.fee;
 
-    // Generate a message we want to persist.
-    let message = [0u8; 32].to_vec();
+    // Both messages pay their own fee, no transfer to the fee collector is needed beforehand.
     let emitter = Keypair::new();
-    let nonce = rand::thread_rng().gen();
-    let _sequence = context.seq.next(emitter.pubkey().to_bytes());
-
-    let fee_collector = FeeCollector::key(None, program);
-
-    let msg_account = Keypair::new();
-    // Manually send a message that isn't signed by the emitter, which should be rejected to
-    // prevent fraudulant transactions sent on behalf of an emitter.
-    let mut instruction = bridge::instructions::post_message(
-        *program,
-        payer.pubkey(),
-        emitter.pubkey(),
-        msg_account.pubkey(),
-        nonce,
-        message,
-        ConsistencyLevel::Confirmed,
-    )
-    .unwrap();
-
-    // Modify account list to not require the emitter signs.
-    instruction.accounts[2].is_signer = false;
-
-    // Executing this should fail.
-    assert!(common::execute(
+    let messages = [Keypair::new(), Keypair::new()];
+    let instructions: Vec<_> = messages
+        .iter()
+        .enumerate()
+        .map(|(i, message)| {
+            instructions::post_message_v2(
+                *program,
+                payer.pubkey(),
+                emitter.pubkey(),
+                message.pubkey(),
+                i as u32,
+                vec![i as u8; 32],
+                ConsistencyLevel::Confirmed,
+            )
+            .unwrap()
+        })
+        .collect();
+
+    let balance = common::get_account_balance(client, fee_collector).await;
+    common::execute(
         client,
         payer,
-        &[payer, &msg_account],
-        &[
-            system_instruction::transfer(&payer.pubkey(), &fee_collector, 10_000),
-            instruction,
-        ],
-        solana_sdk::commitment_config::CommitmentLevel::Processed,
+        &[payer, &emitter, &messages[0], &messages[1]],
+        &instructions,
+        CommitmentLevel::Processed,
     )
     .await
-    .is_err());
+    .unwrap();
+
+    assert_eq!(
+        common::get_account_balance(client, fee_collector).await,
+        balance + 2 * fee
+    );
+
+    for (i, message) in messages.iter().enumerate() {
+        let sequence = context.seq.next(emitter.pubkey().to_bytes());
+        let posted_message: PostedVAAData =
+            common::get_account_data(client, message.pubkey()).await;
+        assert_eq!(posted_message.message.nonce, i as u32);
+        assert_eq!(posted_message.message.sequence, sequence);
+        assert_eq!(posted_message.message.payload, vec![i as u8; 32]);
+    }
 }
 
 #[tokio::test]
-async fn guardian_set_change() {
-    // Initialize a wormhole bridge on Solana to test with.
+async fn bridge_messages_single_transaction() {
     let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+    let fee_collector = FeeCollector::<'_>::key(None, program);
+    let bridge_key = Bridge::<'_, { AccountState::Uninitialized }>::key(None, program);
+    let bridge: BridgeData = common::get_account_data(client, bridge_key).await;
+    let fee = bridge.config.fee;
 
-    // Use a timestamp from a few seconds earlier for testing to simulate thread::sleep();
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
+    // Messages posted with `post_message` are paid for by a transfer to the fee collector ahead of
+    // them. A `post_message_v2` in between pays its own fee and leaves the transfer for the
+    // `post_message` after it.
+    let emitter = Keypair::new();
+    let messages = vec![Keypair::new(), Keypair::new(), Keypair::new()];
+    let post = |i: usize, v2: bool| {
+        let builder = if v2 {
+            instructions::post_message_v2
+        } else {
+            instructions::post_message
+        };
+        builder(
+            *program,
+            payer.pubkey(),
+            emitter.pubkey(),
+            messages[i].pubkey(),
+            i as u32,
+            vec![i as u8; 32],
+            ConsistencyLevel::Confirmed,
+        )
         .unwrap()
-        .as_secs()
-        - 10;
-
-    // Upgrade the guardian set with a new set of guardians.
-    let (new_public_keys, new_secret_keys) = common::generate_keys(1);
-
-    let nonce = rand::thread_rng().gen();
-    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
-    let sequence = context.seq.next(emitter.pubkey().to_bytes());
-    let message = GovernancePayloadGuardianSetChange {
-        new_guardian_set_index: 1,
-        new_guardian_set: new_public_keys.clone(),
-    }
-    .try_to_vec()
-    .unwrap();
-
-    let message_key = common::post_message(
+    };
+    let instructions = vec![
+        system_instruction::transfer(&payer.pubkey(), &fee_collector, fee),
+        post(0, false),
+        system_instruction::transfer(&payer.pubkey(), &fee_collector, fee),
+        post(1, true),
+        post(2, false),
+    ];
+
+    let balance = common::get_account_balance(client, fee_collector).await;
+    common::execute(
         client,
-        program,
         payer,
-        &emitter,
-        None,
-        nonce,
-        message.clone(),
-        10_000,
+        &[payer, &emitter, &messages[0], &messages[1], &messages[2]],
+        &instructions,
+        CommitmentLevel::Processed,
     )
     .await
     .unwrap();
 
-    let posted_message: PostedVAAData = common::get_account_data(client, message_key).await;
-    assert_eq!(posted_message.message.vaa_version, 0);
-    assert_eq!(posted_message.message.consistency_level, 1);
-    assert_eq!(posted_message.message.nonce, nonce);
-    assert_eq!(posted_message.message.sequence, sequence);
-    assert_eq!(posted_message.message.emitter_chain, 1);
-    assert_eq!(
-        &posted_message.message.emitter_address,
-        emitter.pubkey().as_ref()
-    );
-    assert_eq!(posted_message.message.payload, message);
     assert_eq!(
-        posted_message.message.emitter_address,
-        emitter.pubkey().to_bytes()
+        common::get_account_balance(client, fee_collector).await,
+        balance + 3 * fee
     );
 
-    let (vaa, body, _body_hash) =
-        common::generate_vaa(&emitter, message.clone(), nonce, sequence, 0, 1);
-    let vaa_time = vaa.timestamp;
-    let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 0)
-        .await
-        .unwrap();
-    let message_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
-        &PostedVAADerivationData {
-            payload_hash: body.to_vec(),
-        },
-        program,
-    );
-    common::post_vaa(client, program, payer, signature_set, vaa)
-        .await
-        .unwrap();
-    common::upgrade_guardian_set(
-        client,
-        program,
-        payer,
-        message_key,
+    for (i, message) in messages.iter().enumerate() {
+        let sequence = context.seq.next(emitter.pubkey().to_bytes());
+        let posted_message: PostedVAAData =
+            common::get_account_data(client, message.pubkey()).await;
+        assert_eq!(posted_message.message.nonce, i as u32);
+        assert_eq!(posted_message.message.sequence, sequence);
+        assert_eq!(posted_message.message.payload, vec![i as u8; 32]);
+    }
+
+    // Without a transfer ahead of it `post_message` is not paid for.
+    let message = Keypair::new();
+    let ix = instructions::post_message(
+        *program,
+        payer.pubkey(),
         emitter.pubkey(),
-        0,
-        1,
-        sequence,
+        message.pubkey(),
+        3,
+        vec![3; 32],
+        ConsistencyLevel::Confirmed,
     )
-    .await
     .unwrap();
+    let (result, logs) =
+        common::execute_with_logs(client, payer, &[payer, &emitter, &message], &[ix])
+            .await
+            .unwrap();
+    assert!(result.is_err());
+    assert_bridge_error(&logs, Error::InsufficientFees);
+}
 
-    // Derive keys for accounts we want to check.
-    let bridge_key = Bridge::<'_, { AccountState::Uninitialized }>::key(None, program);
-    let guardian_set_key = GuardianSet::<'_, { AccountState::Uninitialized }>::key(
-        &GuardianSetDerivationData { index: 1 },
-        program,
-    );
+#[tokio::test]
+async fn bridge_messages_cpi() {
+    let (ref mut _context, ref mut client, ref payer, ref program) = initialize().await;
+    let fee_collector = FeeCollector::key(None, program);
 
-    // Fetch account states.
+    // Post twice, the program checks each returned sequence against the message account.
+    for sequence in 0..2 {
+        let payload = [sequence as u8; 32].to_vec();
+        let nonce = rand::thread_rng().gen();
+        let message = Keypair::new();
+        let balance = common::get_account_balance(client, fee_collector).await;
+
+        let (emitter, returned) = common::post_message_cpi(
+            client,
+            program,
+            payer,
+            payer,
+            &message,
+            nonce,
+            payload.clone(),
+            None,
+        )
+        .await
+        .unwrap();
+        // `cpi::post_message_with_fee` hands the program the sequence the message was assigned.
+        assert_eq!(returned, sequence);
+
+        let posted_message: PostedVAAData =
+            common::get_account_data(client, message.pubkey()).await;
+        assert_eq!(posted_message.message.nonce, nonce);
+        assert_eq!(posted_message.message.sequence, sequence);
+        assert_eq!(posted_message.message.emitter_chain, 1);
+        assert_eq!(posted_message.message.payload, payload);
+        assert_eq!(posted_message.message.emitter_address, emitter.to_bytes());
+
+        // The bridge took exactly the message fee from the payer within the same instruction.
+        assert_eq!(
+            common::get_account_balance(client, fee_collector).await,
+            balance + 500
+        );
+    }
+}
+
+#[tokio::test]
+async fn bridge_messages_cpi_derived() {
+    let (ref mut _context, ref mut client, ref payer, ref program) = initialize().await;
+
+    // The transaction payer is the only signer, the message account is derived by the bridge.
+    for sequence in 0..2 {
+        let payload = [sequence as u8; 32].to_vec();
+        let nonce = rand::thread_rng().gen();
+
+        let (emitter, message) =
+            common::post_message_derived_cpi(client, program, payer, nonce, payload.clone())
+                .await
+                .unwrap();
+
+        let posted_message: PostedVAAData = common::get_account_data(client, message).await;
+        assert_eq!(posted_message.message.nonce, nonce);
+        assert_eq!(posted_message.message.sequence, sequence);
+        assert_eq!(posted_message.message.emitter_chain, 1);
+        assert_eq!(posted_message.message.payload, payload);
+        assert_eq!(posted_message.message.emitter_address, emitter.to_bytes());
+        assert_eq!(posted_message.message.consistency_level, 1);
+    }
+
+    // The message must be derived from the next sequence of the emitter.
+    let emitter = Keypair::new();
+    let instruction = instructions::post_message_derived(
+        *program,
+        payer.pubkey(),
+        emitter.pubkey(),
+        1,
+        0,
+        vec![],
+        ConsistencyLevel::Confirmed,
+    )
+    .unwrap();
+    assert!(common::execute(
+        client,
+        payer,
+        &[payer, &emitter],
+        &[instruction],
+        CommitmentLevel::Processed,
+    )
+    .await
+    .is_err());
+
+    // Funding the derived message up front does not keep it from being created.
+    let instruction = instructions::post_message_derived(
+        *program,
+        payer.pubkey(),
+        emitter.pubkey(),
+        0,
+        0,
+        vec![7; 32],
+        ConsistencyLevel::Confirmed,
+    )
+    .unwrap();
+    let message = instruction.accounts[1].pubkey;
+    common::execute(
+        client,
+        payer,
+        &[payer],
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            &message,
+            Rent::default().minimum_balance(0),
+        )],
+        CommitmentLevel::Processed,
+    )
+    .await
+    .unwrap();
+    common::execute(
+        client,
+        payer,
+        &[payer, &emitter],
+        &[instruction],
+        CommitmentLevel::Processed,
+    )
+    .await
+    .unwrap();
+
+    let posted_message: PostedVAAData = common::get_account_data(client, message).await;
+    assert_eq!(posted_message.message.sequence, 0);
+    assert_eq!(posted_message.message.payload, vec![7; 32]);
+    assert_eq!(
+        posted_message.message.emitter_address,
+        emitter.pubkey().to_bytes()
+    );
+}
+
+#[tokio::test]
+async fn bridge_messages_cpi_program_emitter() {
+    let (ref mut _context, ref mut client, ref payer, ref program) = initialize().await;
+    let poster = common::program_emitter_poster_id();
+
+    for sequence in 0..2 {
+        let payload = [sequence as u8; 32].to_vec();
+        let nonce = rand::thread_rng().gen();
+        let message = Keypair::new();
+
+        common::post_message_program_emitter_cpi(
+            client,
+            program,
+            payer,
+            &message,
+            nonce,
+            payload.clone(),
+        )
+        .await
+        .unwrap();
+
+        // The emitter is the program itself, not the PDA it signed with.
+        let posted_message: PostedVAAData =
+            common::get_account_data(client, message.pubkey()).await;
+        assert_eq!(posted_message.message.emitter_address, poster.to_bytes());
+        assert_eq!(posted_message.message.sequence, sequence);
+        assert_eq!(posted_message.message.payload, payload);
+    }
+
+    let sequence: SequenceTracker = common::get_account_data(
+        client,
+        Sequence::<'_>::key(
+            &SequenceDerivationData {
+                emitter_key: &poster,
+            },
+            program,
+        ),
+    )
+    .await;
+    assert_eq!(sequence.sequence, 2);
+
+    // Any other signer than the program's authority PDA is rejected.
+    let authority = Keypair::new();
+    let message = Keypair::new();
+    let mut instruction = instructions::post_message_program_emitter(
+        *program,
+        payer.pubkey(),
+        poster,
+        message.pubkey(),
+        0,
+        vec![],
+        ConsistencyLevel::Confirmed,
+    )
+    .unwrap();
+    instruction.accounts[2] = AccountMeta::new_readonly(authority.pubkey(), true);
+    assert!(common::execute(
+        client,
+        payer,
+        &[payer, &message, &authority],
+        &[instruction],
+        CommitmentLevel::Processed,
+    )
+    .await
+    .is_err());
+}
+
+#[test]
+fn posted_message_reader_offsets() {
+    let message = PostedMessageData {
+        message: MessageData {
+            vaa_version: 1,
+            consistency_level: 32,
+            vaa_time: 0x0102_0304,
+            vaa_signature_account: Pubkey::new_unique(),
+            submission_time: 0x0506_0708,
+            nonce: 0x090a_0b0c,
+            sequence: 0x0d0e_0f10_1112_1314,
+            emitter_chain: 0x1516,
+            emitter_address: [0x17; 32],
+            payload: vec![0x18; 7],
+        },
+    };
+    let mut data = message.try_to_vec().unwrap();
+    let (key, owner, mut lamports) = (Pubkey::new_unique(), Pubkey::new_unique(), 0);
+    let info = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+
+    assert_eq!(SUBMISSION_TIME_OFFSET, 41);
+    assert_eq!(NONCE_OFFSET, 45);
+    assert_eq!(SEQUENCE_OFFSET, 49);
+    assert_eq!(EMITTER_CHAIN_OFFSET, 57);
+    assert_eq!(EMITTER_ADDRESS_OFFSET, 59);
+    assert_eq!(PAYLOAD_LEN_OFFSET, 91);
+    assert_eq!(PAYLOAD_OFFSET, 95);
+
+    let reader = PostedMessageV1::load(&info).unwrap();
+    assert!(!reader.is_unreliable());
+    assert_eq!(reader.consistency_level(), 32);
+    assert_eq!(reader.submission_time(), 0x0506_0708);
+    assert_eq!(reader.nonce(), 0x090a_0b0c);
+    assert_eq!(reader.sequence(), 0x0d0e_0f10_1112_1314);
+    assert_eq!(reader.emitter_chain(), 0x1516);
+    assert_eq!(reader.emitter_address(), [0x17; 32]);
+    assert_eq!(reader.payload(), &[0x18; 7]);
+    drop(reader);
+
+    // A truncated payload or a foreign magic is rejected.
+    info.data.borrow_mut()[PAYLOAD_LEN_OFFSET] = 8;
+    assert!(PostedMessageV1::load(&info).is_err());
+    info.data.borrow_mut()[PAYLOAD_LEN_OFFSET] = 7;
+    info.data.borrow_mut()[..3].copy_from_slice(b"vaa");
+    assert!(PostedMessageV1::load(&info).is_err());
+}
+
+#[test]
+fn vaa_digest_known_answer() {
+    // Body of the governance VAA registering the Ethereum token bridge. The Rust SDK pins the same
+    // digest for it, so both implementations find the body and hash it alike.
+    let body = hex::decode(
+        "000000010000000100010000000000000000000000000000000000000000000000000000000000000004000000\
+         0003b456b800000000000000000000000000000000000000000000546f6b656e42726964676501000000020000\
+         000000000000000000000290fb167208af455bb137780163b7b7a9a10c16",
+    )
+    .unwrap();
+    let message_hash =
+        hex::decode("830de4755939fdbaea21db9ec1882bbc4ad75c01b0cd81a4ff4ad078830209c4").unwrap();
+    let digest =
+        hex::decode("05d1fcc531746c7efd7feea20a81d2799f777f302b8a6a6424b81209dc3f511f").unwrap();
+
+    // The signatures are not checked, only skipped over to find the body.
+    for num_signatures in [0u8, 1, 13] {
+        let mut vaa = vec![1, 0, 0, 0, 3, num_signatures];
+        vaa.extend(std::iter::repeat(7).take(66 * num_signatures as usize));
+        vaa.extend(&body);
+        assert_eq!(compute_message_hash(&vaa).unwrap().to_vec(), message_hash);
+        assert_eq!(compute_digest(&vaa).unwrap().to_vec(), digest);
+
+        // A VAA cut short of its body header is rejected rather than hashed.
+        assert!(compute_message_hash(&vaa[..vaa.len() - body.len() + 50]).is_err());
+    }
+
+    // The message hash of signed bytes is the hash the bridge binds signature sets to.
+    let (vaa, _, body_hash) = common::generate_vaa(&Keypair::new(), vec![8; 16], 0, 0, 0, 1);
+    let signed = common::sign_vaa(&vaa, body_hash, &[SecretKey::parse(&[1; 32]).unwrap()]);
+    assert_eq!(compute_message_hash(&signed).unwrap(), body_hash);
+    assert_eq!(body_hash, instructions::hash_vaa(&vaa));
+}
+
+#[test]
+fn instruction_data_round_trip() {
+    use bridge::instruction::Instruction;
+
+    // Selectors are the variant indices, so each instruction decodes back from its own data.
+    let args = (7u32, vec![1u8, 2, 3]);
+    for (selector, instruction) in Instruction::ALL.iter().enumerate() {
+        let data = instruction.data(&args).unwrap();
+        assert_eq!(data[0] as usize, selector);
+
+        let (decoded, encoded_args) = Instruction::decode(&data).unwrap();
+        assert_eq!(decoded, *instruction);
+        assert_eq!(
+            <(u32, Vec<u8>)>::try_from_slice(encoded_args).unwrap(),
+            args
+        );
+    }
+    assert!(Instruction::decode(&[]).is_err());
+    assert!(Instruction::decode(&[Instruction::ALL.len() as u8]).is_err());
+
+    // The builders encode their arguments the same way.
+    let payload = vec![9; 16];
+    let ix = instructions::post_message(
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        5,
+        payload.clone(),
+        ConsistencyLevel::Finalized,
+    )
+    .unwrap();
+    let (decoded, encoded_args) = Instruction::decode(&ix.data).unwrap();
+    assert_eq!(decoded, Instruction::PostMessage);
+    let data = PostMessageData::try_from_slice(encoded_args).unwrap();
+    assert_eq!((data.nonce, data.payload), (5, payload));
+    assert_eq!(
+        Instruction::PostMessage
+            .data(&PostMessageData {
+                nonce: 5,
+                payload: vec![9; 16],
+                consistency_level: ConsistencyLevel::Finalized,
+            })
+            .unwrap(),
+        ix.data
+    );
+}
+
+#[tokio::test]
+async fn posted_message_reader_round_trip() {
+    let (ref mut _context, ref mut client, ref payer, ref program) = initialize().await;
+
+    let emitter = Keypair::new();
+    let payload = [7u8; 48].to_vec();
+    let nonce = rand::thread_rng().gen();
+    let message_key = common::post_message(
+        client,
+        program,
+        payer,
+        &emitter,
+        None,
+        nonce,
+        payload.clone(),
+    )
+    .await
+    .unwrap();
+
+    let mut account = client.get_account(message_key).await.unwrap().unwrap();
+    let mut lamports = account.lamports;
+    let info = AccountInfo::new(
+        &message_key,
+        false,
+        false,
+        &mut lamports,
+        &mut account.data,
+        &account.owner,
+        false,
+        0,
+    );
+    let reader = PostedMessageV1::load(&info).unwrap();
+    assert_eq!(reader.nonce(), nonce);
+    assert_eq!(reader.sequence(), 0);
+    assert_eq!(reader.consistency_level(), 1);
+    assert_eq!(reader.emitter_chain(), 1);
+    assert_eq!(reader.emitter_chain_id(), ChainId::Solana);
+    assert_eq!(reader.emitter_address(), emitter.pubkey().to_bytes());
+    assert_eq!(reader.payload(), &payload[..]);
+}
+
+#[tokio::test]
+async fn bridge_messages_cpi_separate_payer() {
+    let (ref mut _context, ref mut client, ref payer, ref program) = initialize().await;
+
+    // A throwaway keypair pays for the accounts while the emitter stays a PDA of the poster.
+    let message_payer = Keypair::new();
+    common::execute(
+        client,
+        payer,
+        &[payer],
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            &message_payer.pubkey(),
+            100_000_000,
+        )],
+        CommitmentLevel::Processed,
+    )
+    .await
+    .unwrap();
+
+    let payload = [1u8; 32].to_vec();
+    let nonce = rand::thread_rng().gen();
+    let message = Keypair::new();
+    let (emitter, _) = common::post_message_cpi(
+        client,
+        program,
+        payer,
+        &message_payer,
+        &message,
+        nonce,
+        payload.clone(),
+        None,
+    )
+    .await
+    .unwrap();
+
+    let posted_message: PostedVAAData = common::get_account_data(client, message.pubkey()).await;
+    assert_eq!(posted_message.message.payload, payload);
+    assert_eq!(posted_message.message.emitter_address, emitter.to_bytes());
+
+    // Rent for the message came out of the throwaway payer, not the emitter.
+    let rent = common::get_account_balance(client, message.pubkey()).await;
+    assert!(
+        common::get_account_balance(client, message_payer.pubkey()).await <= 100_000_000 - rent
+    );
+    assert!(client.get_account(emitter).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn prepared_message() {
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+
+    // Too large to post in a single transaction.
+    let payload: Vec<u8> = (0..3 * 1024).map(|i| i as u8).collect();
+    let emitter = Keypair::new();
+    let message = Keypair::new();
+    let nonce = rand::thread_rng().gen();
+
+    common::init_message(
+        client,
+        program,
+        payer,
+        &emitter,
+        &message,
+        payload.len() as u32,
+    )
+    .await
+    .unwrap();
+
+    // Only the emitter may write to the draft.
+    assert!(common::write_message(
+        client,
+        program,
+        payer,
+        &Keypair::new(),
+        &message.pubkey(),
+        0,
+        payload[..900].to_vec(),
+    )
+    .await
+    .is_err());
+
+    for (i, chunk) in payload.chunks(900).enumerate() {
+        common::write_message(
+            client,
+            program,
+            payer,
+            &emitter,
+            &message.pubkey(),
+            (i * 900) as u32,
+            chunk.to_vec(),
+        )
+        .await
+        .unwrap();
+    }
+
+    // Writing past the declared size fails.
+    assert!(common::write_message(
+        client,
+        program,
+        payer,
+        &emitter,
+        &message.pubkey(),
+        payload.len() as u32,
+        vec![0],
+    )
+    .await
+    .is_err());
+
+    // A draft can't be posted before it is finalized.
+    assert!(common::post_prepared_message(
+        client,
+        program,
+        payer,
+        &emitter,
+        &message.pubkey(),
+        nonce.wrapping_add(1),
+        10_000,
+    )
+    .await
+    .is_err());
+
+    common::finalize_message(client, program, payer, &emitter, &message.pubkey())
+        .await
+        .unwrap();
+
+    // A finalized draft can no longer be written.
+    assert!(common::write_message(
+        client,
+        program,
+        payer,
+        &emitter,
+        &message.pubkey(),
+        0,
+        vec![0],
+    )
+    .await
+    .is_err());
+
+    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+    common::post_prepared_message(
+        client,
+        program,
+        payer,
+        &emitter,
+        &message.pubkey(),
+        nonce,
+        10_000,
+    )
+    .await
+    .unwrap();
+
+    let posted_message: PostedVAAData = common::get_account_data(client, message.pubkey()).await;
+    assert_eq!(posted_message.message.vaa_version, 0);
+    assert_eq!(posted_message.message.consistency_level, 1);
+    assert_eq!(posted_message.message.nonce, nonce);
+    assert_eq!(posted_message.message.sequence, sequence);
+    assert_eq!(posted_message.message.emitter_chain, 1);
+    assert_eq!(
+        posted_message.message.emitter_address,
+        emitter.pubkey().to_bytes()
+    );
+    assert_eq!(posted_message.message.payload, payload);
+
+    // Once posted the message is no longer a draft and can't be posted again.
+    assert!(common::post_prepared_message(
+        client,
+        program,
+        payer,
+        &emitter,
+        &message.pubkey(),
+        nonce.wrapping_add(2),
+        10_000,
+    )
+    .await
+    .is_err());
+}
+
+#[tokio::test]
+async fn close_draft_message() {
+    let (ref mut _context, ref mut client, ref payer, ref program) = initialize().await;
+    let emitter = Keypair::new();
+    let recipient = Pubkey::new_unique();
+
+    // Drafts can be closed both while being written and once finalized.
+    for finalize in [false, true] {
+        let message = Keypair::new();
+        common::init_message(client, program, payer, &emitter, &message, 64)
+            .await
+            .unwrap();
+        common::write_message(
+            client,
+            program,
+            payer,
+            &emitter,
+            &message.pubkey(),
+            0,
+            vec![1; 64],
+        )
+        .await
+        .unwrap();
+        if finalize {
+            common::finalize_message(client, program, payer, &emitter, &message.pubkey())
+                .await
+                .unwrap();
+        }
+
+        // Only the emitter of the draft may close it.
+        assert!(common::close_message(
+            client,
+            program,
+            payer,
+            &Keypair::new(),
+            &message.pubkey(),
+            &recipient,
+        )
+        .await
+        .is_err());
+
+        let rent = common::get_account_balance(client, message.pubkey()).await;
+        let balance = client.get_balance(recipient).await.unwrap();
+        common::close_message(
+            client,
+            program,
+            payer,
+            &emitter,
+            &message.pubkey(),
+            &recipient,
+        )
+        .await
+        .unwrap();
+
+        assert!(client
+            .get_account(message.pubkey())
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(client.get_balance(recipient).await.unwrap(), balance + rent);
+    }
+
+    // A posted message is no longer a draft and can't be closed.
+    let message = Keypair::new();
+    common::init_message(client, program, payer, &emitter, &message, 0)
+        .await
+        .unwrap();
+    common::finalize_message(client, program, payer, &emitter, &message.pubkey())
+        .await
+        .unwrap();
+    common::post_prepared_message(
+        client,
+        program,
+        payer,
+        &emitter,
+        &message.pubkey(),
+        0,
+        10_000,
+    )
+    .await
+    .unwrap();
+    assert!(common::close_message(
+        client,
+        program,
+        payer,
+        &emitter,
+        &message.pubkey(),
+        &recipient,
+    )
+    .await
+    .is_err());
+}
+
+#[tokio::test]
+async fn closed_draft_cannot_be_resurrected() {
+    let (ref mut _context, ref mut client, ref payer, ref program) = initialize().await;
+    let emitter = Keypair::new();
+    let message = Keypair::new();
+    common::init_message(client, program, payer, &emitter, &message, 64)
+        .await
+        .unwrap();
+    let rent = common::get_account_balance(client, message.pubkey()).await;
+
+    let close =
+        instructions::close_message(*program, emitter.pubkey(), message.pubkey(), payer.pubkey())
+            .unwrap();
+    let refund = system_instruction::transfer(&payer.pubkey(), &message.pubkey(), rent);
+    let write =
+        instructions::write_message(*program, emitter.pubkey(), message.pubkey(), 0, vec![1; 64])
+            .unwrap();
+
+    // Closing into the draft itself would burn its rent.
+    let close_into_itself = instructions::close_message(
+        *program,
+        emitter.pubkey(),
+        message.pubkey(),
+        message.pubkey(),
+    )
+    .unwrap();
+    assert!(common::execute(
+        client,
+        payer,
+        &[payer, &emitter],
+        &[close_into_itself],
+        CommitmentLevel::Processed,
+    )
+    .await
+    .is_err());
+
+    // Funding the draft again right after closing it does not bring it back to the bridge.
+    assert!(common::execute(
+        client,
+        payer,
+        &[payer, &emitter],
+        &[close.clone(), refund.clone(), write],
+        CommitmentLevel::Processed,
+    )
+    .await
+    .is_err());
+    let account = client.get_account(message.pubkey()).await.unwrap().unwrap();
+    assert_eq!(account.owner, *program);
+
+    common::execute(
+        client,
+        payer,
+        &[payer, &emitter],
+        &[close, refund],
+        CommitmentLevel::Processed,
+    )
+    .await
+    .unwrap();
+    let account = client.get_account(message.pubkey()).await.unwrap().unwrap();
+    assert_eq!(account.owner, solana_program::system_program::id());
+    assert!(account.data.is_empty());
+    assert_eq!(account.lamports, rent);
+}
+
+#[test]
+fn transfer_lamports_from_owned_checks() {
+    let program = Pubkey::new_unique();
+    let (from_key, to_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (mut from_lamports, mut to_lamports) = (100, u64::MAX - 10);
+    let (mut from_data, mut to_data) = (vec![0; 8], vec![]);
+    let from = AccountInfo::new(
+        &from_key,
+        false,
+        true,
+        &mut from_lamports,
+        &mut from_data,
+        &program,
+        false,
+        0,
+    );
+    let to = AccountInfo::new(
+        &to_key,
+        false,
+        true,
+        &mut to_lamports,
+        &mut to_data,
+        &to_key,
+        false,
+        0,
+    );
+
+    // The recipient would overflow.
+    assert!(matches!(
+        solitaire::transfer_lamports_from_owned(&program, &from, &to, 11, 0),
+        Err(SolitaireError::ProgramError(_))
+    ));
+    **to.lamports.borrow_mut() = 0;
+
+    // More than the account holds, or than it may spend above its floor.
+    assert!(matches!(
+        solitaire::transfer_lamports_from_owned(&program, &from, &to, 101, 0),
+        Err(SolitaireError::InsufficientFunds)
+    ));
+    assert!(matches!(
+        solitaire::transfer_lamports_from_owned(&program, &from, &to, 41, 60),
+        Err(SolitaireError::InsufficientFunds)
+    ));
+    assert_eq!((from.lamports(), to.lamports()), (100, 0));
+
+    solitaire::transfer_lamports_from_owned(&program, &from, &to, 40, 60).unwrap();
+    assert_eq!((from.lamports(), to.lamports()), (60, 40));
+
+    // Only accounts of the program can be drained, and not into themselves.
+    assert!(matches!(
+        solitaire::transfer_lamports_from_owned(&program, &to, &from, 1, 0),
+        Err(SolitaireError::InvalidOwner(owner)) if owner == to_key
+    ));
+    assert!(solitaire::transfer_lamports_from_owned(&program, &from, &from, 1, 0).is_err());
+    assert_eq!((from.lamports(), to.lamports()), (60, 40));
+}
+
+#[tokio::test]
+async fn oversized_payload() {
+    let (ref mut _context, ref mut client, ref payer, ref program) = initialize().await;
+    let emitter = Keypair::new();
+
+    // A draft at the limit fills the largest account the bridge can allocate.
+    let message = Keypair::new();
+    common::init_message(
+        client,
+        program,
+        payer,
+        &emitter,
+        &message,
+        MAX_MESSAGE_PAYLOAD_SIZE as u32,
+    )
+    .await
+    .unwrap();
+    let account = client.get_account(message.pubkey()).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), MAX_PERMITTED_DATA_INCREASE);
+
+    assert!(common::init_message(
+        client,
+        program,
+        payer,
+        &emitter,
+        &Keypair::new(),
+        MAX_MESSAGE_PAYLOAD_SIZE as u32 + 1,
+    )
+    .await
+    .is_err());
+}
+
+#[test]
+fn oversized_payload_cpi() {
+    let (key, mut lamports, mut data) = (Pubkey::new_unique(), 0, vec![]);
+    let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &key, false, 0);
+    let result = cpi::post_message(
+        cpi::PostMessage {
+            bridge: &info,
+            message: &info,
+            emitter: &info,
+            sequence: &info,
+            payer: &info,
+            fee_collector: &info,
+            clock: &info,
+            rent: &info,
+            system_program: &info,
+            core_bridge_program: &info,
+            fee_exemption: None,
+            token_fee: None,
+        },
+        PostMessageData {
+            nonce: 0,
+            payload: vec![0; MAX_MESSAGE_PAYLOAD_SIZE + 1],
+            consistency_level: ConsistencyLevel::Confirmed,
+        },
+        &[],
+    );
+
+    // The wrapper rejects an oversized payload before the bridge is ever invoked.
+    assert!(matches!(
+        result,
+        Err(SolitaireError::Custom(code)) if code == Error::OversizedPayload as u64
+    ));
+}
+
+#[test]
+fn consistency_level_encoding() {
+    for (level, index, wire) in [
+        (ConsistencyLevel::Confirmed, 0u8, 1u8),
+        (ConsistencyLevel::Finalized, 1, 32),
+    ] {
+        assert_eq!(level.try_to_vec().unwrap(), vec![index]);
+        assert_eq!(ConsistencyLevel::try_from_slice(&[index]).unwrap(), level);
+        assert_eq!(u8::from(level), wire);
+        assert_eq!(ConsistencyLevel::try_from(wire).unwrap(), level);
+    }
+
+    assert!(ConsistencyLevel::try_from_slice(&[2]).is_err());
+    for wire in [0, 2, 31, 33, 255] {
+        assert!(matches!(
+            ConsistencyLevel::try_from(wire),
+            Err(Error::InvalidConsistencyLevel)
+        ));
+    }
+}
+
+#[test]
+fn chain_id_round_trip() {
+    for id in 0..=u16::MAX {
+        let chain = ChainId::from(id);
+        assert_eq!(u16::from(chain), id);
+        assert_eq!(ChainId::try_from(id).unwrap(), chain);
+        assert_eq!(
+            matches!(chain, ChainId::Unknown(_)),
+            !ChainId::KNOWN.contains(&chain)
+        );
+    }
+
+    for (chain, id, name) in [
+        (ChainId::Solana, CHAIN_ID_SOLANA, "Solana"),
+        (ChainId::Ethereum, 2, "Ethereum"),
+        (ChainId::Bsc, 4, "BSC"),
+        (ChainId::Polygon, 5, "Polygon"),
+        (ChainId::Wormchain, 3104, "Wormchain"),
+        (ChainId::Sepolia, 10002, "Sepolia"),
+        (ChainId::Unknown(27), 27, "Unknown chain 27"),
+    ] {
+        assert_eq!(ChainId::from(id), chain);
+        assert_eq!(chain.to_string(), name);
+    }
+
+    assert!(ChainId::Ethereum.is_evm());
+    assert!(ChainId::Bsc.is_evm());
+    assert!(ChainId::Arbitrum.is_evm());
+    assert!(!ChainId::Solana.is_evm());
+    assert!(!ChainId::Terra2.is_evm());
+    assert!(!ChainId::Unknown(27).is_evm());
+}
+
+#[test]
+fn universal_address_conversions() {
+    let pubkey = Pubkey::new_unique();
+    let address = UniversalAddress::from(pubkey);
+    assert_eq!(address, UniversalAddress::from_pubkey(&pubkey));
+    assert_eq!(address.to_pubkey(), pubkey);
+    assert_eq!(<[u8; 32]>::from(address), pubkey.to_bytes());
+    assert!(!address.is_zero());
+    assert!(UniversalAddress::default().is_zero());
+
+    // Borsh lays the address out as its bare 32 bytes, same as the `[u8; 32]` it replaces.
+    assert_eq!(address.try_to_vec().unwrap(), pubkey.to_bytes().to_vec());
+    assert_eq!(
+        UniversalAddress::try_from_slice(&pubkey.to_bytes()).unwrap(),
+        address
+    );
+
+    let evm = [0xab; 20];
+    let padded = UniversalAddress::from_evm(evm);
+    assert_eq!(padded.0[..12], [0u8; 12]);
+    assert_eq!(padded.0[12..], evm);
+    assert_eq!(padded.try_to_evm().unwrap(), evm);
+    let mut dirty = padded;
+    dirty.0[0] = 1;
+    assert!(matches!(dirty.try_to_evm(), Err(Error::InvalidAddress)));
+
+    let hex = format!("{}{}", "00".repeat(12), "ab".repeat(20));
+    assert_eq!(padded.to_string(), hex);
+    assert_eq!(hex.parse::<UniversalAddress>().unwrap(), padded);
+    assert_eq!(
+        format!("0x{}", hex).parse::<UniversalAddress>().unwrap(),
+        padded
+    );
+    assert_eq!(
+        hex.to_uppercase().parse::<UniversalAddress>().unwrap(),
+        padded
+    );
+    for bad in vec![
+        String::new(),
+        "0x".to_string(),
+        hex[2..].to_string(),
+        format!("{}00", hex),
+        format!("{}zz", &hex[2..]),
+        format!("{}+1", &hex[2..]),
+    ] {
+        assert!(matches!(
+            bad.parse::<UniversalAddress>(),
+            Err(Error::InvalidAddress)
+        ));
+    }
+}
+
+#[test]
+fn foreign_emitter_verifies_vaas() {
+    let emitter = ForeignEmitter {
+        chain: 2,
+        address: [0x17; 32],
+    };
+
+    // Whether `emitter` equals the emitter of a VAA from the given emitter, and its verification.
+    let check = |emitter: &ForeignEmitter, emitter_chain, emitter_address| {
+        let vaa = PostedVAAData {
+            message: MessageData {
+                emitter_chain,
+                emitter_address,
+                payload: vec![1, 2, 3],
+                ..Default::default()
+            },
+        };
+        let mut data = vaa.try_to_vec().unwrap();
+        let (key, owner, mut lamports) = (Pubkey::new_unique(), Pubkey::new_unique(), 0);
+        let info = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+        let reader = PostedVAAV1::load(&info).unwrap();
+        assert_eq!(reader.emitter() == *emitter, *emitter == reader);
+        (*emitter == reader, emitter.verify(&reader))
+    };
+
+    assert!(matches!(check(&emitter, 2, [0x17; 32]), (true, Ok(()))));
+    assert!(matches!(
+        check(&emitter, 3, [0x17; 32]),
+        (false, Err(Error::InvalidEmitter))
+    ));
+    assert!(matches!(
+        check(&emitter, 2, [0x18; 32]),
+        (false, Err(Error::InvalidEmitter))
+    ));
+
+    // An unset emitter equals the emitter of a VAA from the zero address, but never verifies it.
+    let unset = ForeignEmitter::default();
+    assert!(matches!(
+        check(&unset, 0, [0; 32]),
+        (true, Err(Error::InvalidEmitter))
+    ));
+    assert!(matches!(
+        check(
+            &ForeignEmitter {
+                chain: 2,
+                address: [0; 32]
+            },
+            2,
+            [0; 32]
+        ),
+        (true, Err(Error::InvalidEmitter))
+    ));
+
+    let bytes = emitter.try_to_vec().unwrap();
+    assert_eq!(bytes.len(), 34);
+    assert_eq!(bytes[..2], 2u16.to_le_bytes());
+    assert_eq!(ForeignEmitter::try_from_slice(&bytes).unwrap(), emitter);
+    assert_eq!(emitter.to_string(), format!("Ethereum/{}", "17".repeat(32)));
+}
+
+#[tokio::test]
+async fn invalid_consistency_level() {
+    let (ref mut _context, ref mut client, ref payer, ref program) = initialize().await;
+    let emitter = Keypair::new();
+    let message = Keypair::new();
+
+    // The consistency level is the last byte of the instruction data.
+    let mut instruction = instructions::post_message_v2(
+        *program,
+        payer.pubkey(),
+        emitter.pubkey(),
+        message.pubkey(),
+        0,
+        vec![],
+        ConsistencyLevel::Finalized,
+    )
+    .unwrap();
+    *instruction.data.last_mut().unwrap() = 32;
+
+    assert!(common::execute(
+        client,
+        payer,
+        &[payer, &emitter, &message],
+        &[instruction],
+        CommitmentLevel::Processed,
+    )
+    .await
+    .is_err());
+}
+
+/// A secp256k1 instruction at `index` verifying one signature, laid out like the ones clients
+/// build: the offsets table, then the signature, the address and the message.
+fn secp256k1_instruction(index: u8, address: [u8; 20], message: [u8; 32]) -> Vec<u8> {
+    let (signature_offset, address_offset, message_offset) = (12u16, 77u16, 97u16);
+    let mut data = vec![1];
+    data.extend_from_slice(&signature_offset.to_le_bytes());
+    data.push(index);
+    data.extend_from_slice(&address_offset.to_le_bytes());
+    data.push(index);
+    data.extend_from_slice(&message_offset.to_le_bytes());
+    data.extend_from_slice(&32u16.to_le_bytes());
+    data.push(index);
+    data.extend_from_slice(&[7; 65]);
+    data.extend_from_slice(&address);
+    data.extend_from_slice(&message);
+    data
+}
+
+/// Whether `verification` only points into `data`, at the instruction it was parsed from.
+fn verification_in_bounds(index: u16, data: &[u8], verification: &Secp256k1Verification) -> bool {
+    let address = verification.eth_address;
+    let address_found = data.windows(20).any(|window| window == address);
+    verification.instruction_index == index
+        && verification.signature_offset as usize + 65 <= data.len()
+        && verification.message_offset as usize + verification.message_size as usize <= data.len()
+        && address_found
+}
+
+#[test]
+fn secp256k1_instruction_parsing() {
+    let data = secp256k1_instruction(3, [1; 20], [2; 32]);
+    let verifications = precompile::parse_secp256k1_instruction(3, &data).unwrap();
+    assert_eq!(
+        verifications,
+        vec![Secp256k1Verification {
+            instruction_index: 3,
+            signature_offset: 12,
+            eth_address: [1; 20],
+            message_offset: 97,
+            message_size: 32,
+        }]
+    );
+    assert_eq!(verifications[0].message(&data), &[2; 32]);
+
+    // Anything pointing into another instruction, or past the end of the data, is rejected.
+    let invalid = |result: solitaire::Result<Vec<Secp256k1Verification>>| {
+        matches!(
+            result,
+            Err(SolitaireError::Custom(code)) if code == Error::InvalidSecpInstruction as u64
+        )
+    };
+    assert!(invalid(precompile::parse_secp256k1_instruction(2, &data)));
+    assert!(invalid(precompile::parse_secp256k1_instruction(259, &data)));
+    for len in 0..data.len() {
+        assert!(invalid(precompile::parse_secp256k1_instruction(
+            3,
+            &data[..len]
+        )));
+    }
+    let mut more_signatures = data.clone();
+    more_signatures[0] = 2;
+    assert!(invalid(precompile::parse_secp256k1_instruction(
+        3,
+        &more_signatures
+    )));
+
+    // Random and corrupted tables never panic, and never yield offsets out of bounds.
+    let mut rng = rand::thread_rng();
+    for _ in 0..10_000 {
+        let mut data = if rng.gen() {
+            let len = rng.gen_range(0, 200);
+            let mut data: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+            if let Some(count) = data.first_mut() {
+                *count %= 4;
+            }
+            data
+        } else {
+            secp256k1_instruction(3, rng.gen(), rng.gen())
+        };
+        for _ in 0..rng.gen_range(0, 4) {
+            if !data.is_empty() {
+                let at = rng.gen_range(0, data.len().min(12));
+                data[at] = rng.gen();
+            }
+        }
+
+        if let Ok(verifications) = precompile::parse_secp256k1_instruction(3, &data) {
+            assert_eq!(verifications.len(), data[0] as usize);
+            for verification in &verifications {
+                assert!(verification_in_bounds(3, &data, verification));
+                verification.message(&data);
+            }
+        }
+    }
+}
+
+#[test]
+fn verify_signatures_transactions_chunking() {
+    let (public_keys, secret_keys) = common::generate_keys(10);
+    let (vaa, body, body_hash) = common::generate_vaa(&Keypair::new(), vec![1, 2, 3], 0, 0, 0, 1);
+    let signed = common::sign_vaa(&vaa, body_hash, &secret_keys);
+    let (program, payer, signature_set) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+
+    let transactions = instructions::verify_signatures_transactions(
+        program,
+        payer,
+        signature_set,
+        &public_keys,
+        &signed,
+    )
+    .unwrap();
+    assert_eq!(transactions.len(), 2);
+
+    // Reassembled from the secp256k1 instructions, the signatures must match the VAA byte for
+    // byte, and each guardian must be mapped to its position in its batch.
+    let mut signatures = Vec::new();
+    let mut verified = Vec::new();
+    for transaction in &transactions {
+        let (secp_ix, verify_ix) = (&transaction[0], &transaction[1]);
+        assert_eq!(secp_ix.program_id, solana_program::secp256k1_program::id());
+        assert_eq!(
+            verify_ix.data[0],
+            bridge::instruction::Instruction::VerifySignatures as u8
+        );
+
+        let count = secp_ix.data[0] as usize;
+        assert!(count <= instructions::MAX_SIGNATURES_PER_TRANSACTION);
+        let data_offset = 1 + count * 11;
+        for i in 0..count {
+            let item = &secp_ix.data[data_offset + 85 * i..data_offset + 85 * (i + 1)];
+            signatures.push(item[..65].to_vec());
+            assert_eq!(item[65..], public_keys[verified.len()]);
+            verified.push(i as i8);
+        }
+        assert_eq!(secp_ix.data[data_offset + 85 * count..], body);
+
+        let data = VerifySignaturesData::try_from_slice(&verify_ix.data[1..]).unwrap();
+        let batch = &data.signers[verified.len() - count..verified.len()];
+        assert_eq!(batch, &verified[verified.len() - count..]);
+    }
+
+    let reassembled: Vec<u8> = signatures
+        .iter()
+        .enumerate()
+        .flat_map(|(i, signature)| std::iter::once(i as u8).chain(signature.iter().copied()))
+        .collect();
+    assert_eq!(reassembled, signed[6..6 + 66 * 10]);
+
+    // A signature from outside the guardian set can't be mapped to a key.
+    assert!(instructions::verify_signatures_transactions(
+        program,
+        payer,
+        signature_set,
+        &public_keys[..9],
+        &signed,
+    )
+    .is_err());
+}
+
+#[tokio::test]
+async fn post_vaa_transactions() {
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+
+    let emitter = Keypair::new();
+    let (vaa, body, body_hash) = common::generate_vaa(&emitter, vec![4, 5, 6], 0, 0, 0, 1);
+    let signed = common::sign_vaa(&vaa, body_hash, &context.secret);
+
+    let signature_set = Keypair::new();
+    let transactions = instructions::post_vaa_transactions(
+        *program,
+        payer.pubkey(),
+        signature_set.pubkey(),
+        &context.public,
+        &signed,
+    )
+    .unwrap();
+    let (post, verify) = transactions.split_last().unwrap();
+    for ixs in verify {
+        common::execute(
+            client,
+            payer,
+            &[payer, &signature_set],
+            ixs,
+            CommitmentLevel::Processed,
+        )
+        .await
+        .unwrap();
+    }
+    common::execute(client, payer, &[payer], post, CommitmentLevel::Processed)
+        .await
+        .unwrap();
+
+    let message_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: body.to_vec(),
+        },
+        program,
+    );
+    let posted: PostedVAAData = common::get_account_data(client, message_key).await;
+    assert_eq!(posted.message.payload, vec![4, 5, 6]);
+    assert_eq!(posted.message.vaa_signature_account, signature_set.pubkey());
+
+    let account = client
+        .get_account(signature_set.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        account.data.len(),
+        instructions::signature_set_size(context.public.len())
+    );
+}
+
+#[tokio::test]
+async fn verify_signatures_resumable() {
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+
+    // Rotate to a second guardian set holding the same keys, so signatures are valid under both.
+    let keys = context.public.clone();
+    update_guardian_set(context, client, payer, program, 1, keys).await;
+
+    let (vaa, body, body_hash) = common::generate_vaa(&Keypair::new(), vec![8; 16], 0, 0, 0, 1);
+    let signatures = VAA::deserialize(&common::sign_vaa(&vaa, body_hash, &context.secret))
+        .unwrap()
+        .signatures;
+    let signature_set = Keypair::new();
+    let batch = |guardian_set_index, range: std::ops::Range<usize>| {
+        instructions::verify_signatures_batch(
+            *program,
+            payer.pubkey(),
+            signature_set.pubkey(),
+            guardian_set_index,
+            &context.public,
+            &body,
+            &signatures[range],
+        )
+        .unwrap()
+    };
+
+    // The first batch pins guardian set 0, the second may not switch to guardian set 1.
+    let steps = [
+        (0, 0..2, Some(2)),
+        (1, 2..4, None),
+        (0, 2..4, Some(4)),
+        // Index 3 is verified again, which must not count twice.
+        (0, 3..6, Some(6)),
+    ];
+    for (guardian_set_index, range, verified) in steps {
+        let result = common::execute(
+            client,
+            payer,
+            &[payer, &signature_set],
+            &batch(guardian_set_index, range),
+            CommitmentLevel::Processed,
+        )
+        .await;
+
+        match verified {
+            Some(count) => {
+                result.unwrap();
+                let set: SignatureSetData =
+                    common::get_account_data(client, signature_set.pubkey()).await;
+                assert_eq!(set.num_verified(), count);
+                assert_eq!(set.guardian_set_index, 0);
+                // Quorum of six guardians is five signatures.
+                assert_eq!(set.is_verified(), count >= 5);
+            }
+            None => assert!(result.is_err()),
+        }
+    }
+
+    common::post_vaa(client, program, payer, signature_set.pubkey(), vaa)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn transfer_draft_message_authority() {
+    let (ref mut _context, ref mut client, ref payer, ref program) = initialize().await;
+    let old_emitter = Keypair::new();
+    let new_emitter = Keypair::new();
+    let message = Keypair::new();
+
+    common::init_message(client, program, payer, &old_emitter, &message, 64)
+        .await
+        .unwrap();
+    common::write_message(
+        client,
+        program,
+        payer,
+        &old_emitter,
+        &message.pubkey(),
+        0,
+        vec![1; 32],
+    )
+    .await
+    .unwrap();
+
+    // Only the current emitter may hand the draft over.
+    assert!(common::transfer_message_authority(
+        client,
+        program,
+        payer,
+        &new_emitter,
+        &message.pubkey(),
+        &new_emitter.pubkey(),
+    )
+    .await
+    .is_err());
+    common::transfer_message_authority(
+        client,
+        program,
+        payer,
+        &old_emitter,
+        &message.pubkey(),
+        &new_emitter.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    // The old emitter is locked out, the new one finishes and posts the draft.
+    assert!(common::write_message(
+        client,
+        program,
+        payer,
+        &old_emitter,
+        &message.pubkey(),
+        32,
+        vec![2; 32],
+    )
+    .await
+    .is_err());
+    common::write_message(
+        client,
+        program,
+        payer,
+        &new_emitter,
+        &message.pubkey(),
+        32,
+        vec![2; 32],
+    )
+    .await
+    .unwrap();
+    common::finalize_message(client, program, payer, &new_emitter, &message.pubkey())
+        .await
+        .unwrap();
+    common::post_prepared_message(
+        client,
+        program,
+        payer,
+        &new_emitter,
+        &message.pubkey(),
+        0,
+        10_000,
+    )
+    .await
+    .unwrap();
+
+    let posted: PostedVAAData = common::get_account_data(client, message.pubkey()).await;
+    assert_eq!(
+        posted.message.emitter_address,
+        new_emitter.pubkey().to_bytes()
+    );
+    assert_eq!(posted.message.payload, [[1; 32], [2; 32]].concat());
+
+    // Once posted, the emitter is fixed.
+    assert!(common::transfer_message_authority(
+        client,
+        program,
+        payer,
+        &new_emitter,
+        &message.pubkey(),
+        &old_emitter.pubkey(),
+    )
+    .await
+    .is_err());
+}
+
+#[tokio::test]
+async fn invalid_emitter() {
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+
+    // Generate a message we want to persist.
+    let message = [0u8; 32].to_vec();
+    let emitter = Keypair::new();
+    let nonce = rand::thread_rng().gen();
+    let _sequence = context.seq.next(emitter.pubkey().to_bytes());
+
+    let fee_collector = FeeCollector::key(None, program);
+
+    let msg_account = Keypair::new();
+    // Manually send a message that isn't signed by the emitter, which should be rejected to
+    // prevent fraudulant transactions sent on behalf of an emitter.
+    let mut instruction = bridge::instructions::post_message(
+        *program,
+        payer.pubkey(),
+        emitter.pubkey(),
+        msg_account.pubkey(),
+        nonce,
+        message,
+        ConsistencyLevel::Confirmed,
+    )
+    .unwrap();
+
+    // Modify account list to not require the emitter signs.
+    instruction.accounts[2].is_signer = false;
+
+    // Executing this should fail.
+    assert!(common::execute(
+        client,
+        payer,
+        &[payer, &msg_account],
+        &[
+            system_instruction::transfer(&payer.pubkey(), &fee_collector, 10_000),
+            instruction,
+        ],
+        solana_sdk::commitment_config::CommitmentLevel::Processed,
+    )
+    .await
+    .is_err());
+}
+
+#[tokio::test]
+async fn guardian_set_change() {
+    // Initialize a wormhole bridge on Solana to test with.
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+
+    // Use a timestamp from a few seconds earlier for testing to simulate thread::sleep();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        - 10;
+
+    // Upgrade the guardian set with a new set of guardians.
+    let (new_public_keys, new_secret_keys) = common::generate_keys(1);
+
+    let nonce = rand::thread_rng().gen();
+    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
+    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+    let message = GovernancePayloadGuardianSetChange {
+        new_guardian_set_index: 1,
+        new_guardian_set: new_public_keys.clone(),
+    }
+    .try_to_vec()
+    .unwrap();
+
+    let message_key = common::post_message(
+        client,
+        program,
+        payer,
+        &emitter,
+        None,
+        nonce,
+        message.clone(),
+    )
+    .await
+    .unwrap();
+
+    let posted_message: PostedVAAData = common::get_account_data(client, message_key).await;
+    assert_eq!(posted_message.message.vaa_version, 0);
+    assert_eq!(posted_message.message.consistency_level, 1);
+    assert_eq!(posted_message.message.nonce, nonce);
+    assert_eq!(posted_message.message.sequence, sequence);
+    assert_eq!(posted_message.message.emitter_chain, 1);
+    assert_eq!(
+        &posted_message.message.emitter_address,
+        emitter.pubkey().as_ref()
+    );
+    assert_eq!(posted_message.message.payload, message);
+    assert_eq!(
+        posted_message.message.emitter_address,
+        emitter.pubkey().to_bytes()
+    );
+
+    let (vaa, body, _body_hash) =
+        common::generate_vaa(&emitter, message.clone(), nonce, sequence, 0, 1);
+    let vaa_time = vaa.timestamp;
+    let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 0)
+        .await
+        .unwrap();
+    let message_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: body.to_vec(),
+        },
+        program,
+    );
+    common::post_vaa(client, program, payer, signature_set, vaa)
+        .await
+        .unwrap();
+    common::upgrade_guardian_set(
+        client,
+        program,
+        payer,
+        message_key,
+        emitter.pubkey(),
+        0,
+        1,
+        sequence,
+    )
+    .await
+    .unwrap();
+
+    // Derive keys for accounts we want to check.
+    let bridge_key = Bridge::<'_, { AccountState::Uninitialized }>::key(None, program);
+    let guardian_set_key = GuardianSet::<'_, { AccountState::Uninitialized }>::key(
+        &GuardianSetDerivationData { index: 1 },
+        program,
+    );
+
+    // Fetch account states.
+    let posted_message: PostedVAAData = common::get_account_data(client, message_key).await;
+    let bridge: BridgeData = common::get_account_data(client, bridge_key).await;
+    let guardian_set: GuardianSetData = common::get_account_data(client, guardian_set_key).await;
+
+    // Verify on chain Message
+    assert_eq!(posted_message.message.vaa_version, 0);
+    assert_eq!(
+        posted_message.message.consistency_level,
+        ConsistencyLevel::Confirmed as u8
+    );
+    assert_eq!(posted_message.message.vaa_time, vaa_time);
+    assert_eq!(posted_message.message.vaa_signature_account, signature_set);
+    assert_eq!(posted_message.message.nonce, nonce);
+    assert_eq!(posted_message.message.sequence, sequence);
+    assert_eq!(posted_message.message.emitter_chain, 1);
+    assert_eq!(
+        &posted_message.message.emitter_address,
+        emitter.pubkey().as_ref()
+    );
+    assert_eq!(posted_message.message.payload, message);
+    assert_eq!(
+        posted_message.message.emitter_address,
+        emitter.pubkey().to_bytes()
+    );
+
+    // Confirm the bridge now has a new guardian set, and no other fields have shifted.
+    assert_eq!(bridge.guardian_set_index, 1);
+    assert_eq!(bridge.config.guardian_set_expiration_time, 2_000_000_000);
+    assert_eq!(bridge.config.fee, 500);
+
+    // Verify Created Guardian Set
+    assert_eq!(guardian_set.index, 1);
+    assert_eq!(guardian_set.keys, new_public_keys);
+    assert!(guardian_set.creation_time as u64 > now);
+
+    // Submit the message a second time with a new nonce.
+    let nonce = rand::thread_rng().gen();
+    let _message_key = common::post_message(
+        client,
+        program,
+        payer,
+        &emitter,
+        None,
+        nonce,
+        message.clone(),
+    )
+    .await
+    .unwrap();
+
+    context.public = new_public_keys;
+    context.secret = new_secret_keys;
+
+    // Emulate Guardian behaviour, verifying the data and publishing signatures/VAA.
+    let (vaa, body, _body_hash) =
+        common::generate_vaa(&emitter, message.clone(), nonce, sequence, 1, 1);
+    let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 1)
+        .await
+        .unwrap();
+    let vaa_time = vaa.timestamp;
+    let message_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: body.to_vec(),
+        },
+        program,
+    );
+    common::post_vaa(client, program, payer, signature_set, vaa)
+        .await
+        .unwrap();
+
+    // Fetch chain accounts to verify state.
+    let posted_message: PostedVAAData = common::get_account_data(client, message_key).await;
+    let signatures: SignatureSetData = common::get_account_data(client, signature_set).await;
+
+    // Verify on chain Message
+    assert_eq!(posted_message.message.vaa_version, 0);
+    assert_eq!(
+        posted_message.message.consistency_level,
+        ConsistencyLevel::Confirmed as u8
+    );
+    assert_eq!(posted_message.message.vaa_time, vaa_time);
+    assert_eq!(posted_message.message.vaa_signature_account, signature_set);
+    assert_eq!(posted_message.message.nonce, nonce);
+    assert_eq!(posted_message.message.sequence, sequence);
+    assert_eq!(posted_message.message.emitter_chain, 1);
+    assert_eq!(posted_message.message.payload, message);
+    assert_eq!(
+        posted_message.message.emitter_address,
+        emitter.pubkey().to_bytes()
+    );
+
+    // Verify on chain Signatures
+    assert_eq!(signatures.hash, body);
+    assert_eq!(signatures.guardian_set_index, 1);
+
+    for (signature, _secret_key) in signatures.signatures.iter().zip(context.secret.iter()) {
+        assert!(*signature);
+    }
+}
+
+#[tokio::test]
+async fn guardian_set_change_fails() {
+    // Initialize a wormhole bridge on Solana to test with.
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+
+    // Use a random emitter key to confirm the bridge rejects transactions from non-governance key.
+    let emitter = Keypair::new();
+    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+
+    // Upgrade the guardian set with a new set of guardians.
+    let (new_public_keys, _new_secret_keys) = common::generate_keys(6);
+    let nonce = rand::thread_rng().gen();
+    let message = GovernancePayloadGuardianSetChange {
+        new_guardian_set_index: 2,
+        new_guardian_set: new_public_keys.clone(),
+    }
+    .try_to_vec()
+    .unwrap();
+
+    let message_key = common::post_message(
+        client,
+        program,
+        payer,
+        &emitter,
+        None,
+        nonce,
+        message.clone(),
+    )
+    .await
+    .unwrap();
+
+    assert!(common::upgrade_guardian_set(
+        client,
+        program,
+        payer,
+        message_key,
+        emitter.pubkey(),
+        1,
+        2,
+        sequence,
+    )
+    .await
+    .is_err());
+}
+
+#[tokio::test]
+async fn set_fees() {
+    // Initialize a wormhole bridge on Solana to test with.
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
+    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+
+    let nonce = rand::thread_rng().gen();
+    let message = GovernancePayloadSetMessageFee {
+        fee: U256::from(100u128),
+    }
+    .try_to_vec()
+    .unwrap();
+
+    let message_key = common::post_message(
+        client,
+        program,
+        payer,
+        &emitter,
+        None,
+        nonce,
+        message.clone(),
+    )
+    .await
+    .unwrap();
+
+    let (vaa, body, _body_hash) =
+        common::generate_vaa(&emitter, message.clone(), nonce, sequence, 0, 1);
+    let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, program, payer, signature_set, vaa)
+        .await
+        .unwrap();
+    common::set_fees(
+        client,
+        program,
+        payer,
+        message_key,
+        emitter.pubkey(),
+        sequence,
+    )
+    .await
+    .unwrap();
+
+    // Fetch Bridge to check on-state value.
+    let bridge_key = Bridge::<'_, { AccountState::Uninitialized }>::key(None, program);
+    let fee_collector = FeeCollector::key(None, program);
+    let bridge: BridgeData = common::get_account_data(client, bridge_key).await;
+    assert_eq!(bridge.config.fee, 100);
+
+    // Check that the next message is charged the new fee.
+    let account_balance = common::get_account_balance(client, fee_collector).await;
+    let emitter = Keypair::new();
+    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+    let nonce = rand::thread_rng().gen();
+    let message = [0u8; 32].to_vec();
+    let _message_key = common::post_message(
+        client,
+        program,
+        payer,
+        &emitter,
+        None,
+        nonce,
+        message.clone(),
+    )
+    .await
+    .unwrap();
+
+    let (vaa, body, _body_hash) =
+        common::generate_vaa(&emitter, message.clone(), nonce, sequence, 0, 1);
+    let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 0)
+        .await
+        .unwrap();
+    let vaa_time = vaa.timestamp;
+    let message_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: body.to_vec(),
+        },
+        program,
+    );
+    common::post_vaa(client, program, payer, signature_set, vaa)
+        .await
+        .unwrap();
+
+    // Verify that the fee collector was paid.
+    assert_eq!(
+        common::get_account_balance(client, fee_collector).await,
+        account_balance + 100,
+    );
+
+    // And that the new message is on chain.
     let posted_message: PostedVAAData = common::get_account_data(client, message_key).await;
+    let signatures: SignatureSetData = common::get_account_data(client, signature_set).await;
+
+    // Verify on chain Message
+    assert_eq!(posted_message.message.vaa_version, 0);
+    assert_eq!(
+        posted_message.message.consistency_level,
+        ConsistencyLevel::Confirmed as u8
+    );
+    assert_eq!(posted_message.message.vaa_time, vaa_time);
+    assert_eq!(posted_message.message.vaa_signature_account, signature_set);
+    assert_eq!(posted_message.message.nonce, nonce);
+    assert_eq!(posted_message.message.sequence, sequence);
+    assert_eq!(posted_message.message.emitter_chain, 1);
+    assert_eq!(
+        &posted_message.message.emitter_address,
+        emitter.pubkey().as_ref()
+    );
+    assert_eq!(posted_message.message.payload, message);
+    assert_eq!(
+        posted_message.message.emitter_address,
+        emitter.pubkey().to_bytes()
+    );
+
+    // Verify on chain Signatures
+    assert_eq!(signatures.hash, body);
+    assert_eq!(signatures.guardian_set_index, 0);
+
+    for (signature, _secret_key) in signatures.signatures.iter().zip(context.secret.iter()) {
+        assert!(*signature);
+    }
+}
+
+#[tokio::test]
+async fn set_fees_fails() {
+    // Initialize a wormhole bridge on Solana to test with.
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+
+    // Use a random key to confirm only the governance key is respected.
+    let emitter = Keypair::new();
+    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+
+    let nonce = rand::thread_rng().gen();
+    let message = GovernancePayloadSetMessageFee {
+        fee: U256::from(100u128),
+    }
+    .try_to_vec()
+    .unwrap();
+
+    let message_key = common::post_message(
+        client,
+        program,
+        payer,
+        &emitter,
+        None,
+        nonce,
+        message.clone(),
+    )
+    .await
+    .unwrap();
+
+    let (vaa, body, _body_hash) =
+        common::generate_vaa(&emitter, message.clone(), nonce, sequence, 0, 1);
+    let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, program, payer, signature_set, vaa)
+        .await
+        .unwrap();
+    assert!(common::set_fees(
+        client,
+        program,
+        payer,
+        message_key,
+        emitter.pubkey(),
+        sequence,
+    )
+    .await
+    .is_err());
+}
+
+/// Post a set-fee decree for `fee` from the governance emitter, returning the logs of the
+/// instruction applying it.
+async fn apply_fee_decree(
+    context: &mut Context,
+    client: &mut BanksClient,
+    payer: &Keypair,
+    program: &Pubkey,
+    fee: U256,
+) -> (Result<(), TransactionError>, Vec<String>) {
+    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
+    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+    let nonce = rand::thread_rng().gen();
+    let message = GovernancePayloadSetMessageFee { fee }.try_to_vec().unwrap();
+    let message_key = common::post_message(
+        client,
+        program,
+        payer,
+        &emitter,
+        None,
+        nonce,
+        message.clone(),
+    )
+    .await
+    .unwrap();
+
+    let (vaa, body, _body_hash) = common::generate_vaa(&emitter, message, nonce, sequence, 0, 1);
+    let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, program, payer, signature_set, vaa)
+        .await
+        .unwrap();
+
+    let ix = instructions::set_fees(
+        *program,
+        payer.pubkey(),
+        message_key,
+        emitter.pubkey(),
+        sequence,
+    );
+    common::execute_with_logs(client, payer, &[payer], &[ix])
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn set_fees_event() {
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+
+    let (result, logs) = apply_fee_decree(context, client, payer, program, U256::from(1234)).await;
+    result.unwrap();
+    let events: Vec<SetFeesEvent> = logs
+        .iter()
+        .filter_map(|log| log.strip_prefix("Program data: "))
+        .map(|data| SetFeesEvent::decode(&base64::decode(data).unwrap()).unwrap())
+        .collect();
+    assert_eq!(
+        events,
+        vec![SetFeesEvent {
+            old_fee: 500,
+            new_fee: 1234,
+        }]
+    );
+    let data = logs
+        .iter()
+        .find_map(|log| log.strip_prefix("Program data: "))
+        .map(|data| base64::decode(data).unwrap())
+        .unwrap();
+    assert_eq!(data[..8], SetFeesEvent::discriminator());
+    assert_eq!(UpgradeGuardianSetEvent::decode(&data), None);
+
+    // The new fee is charged on the next message.
+    let fee_collector = FeeCollector::key(None, program);
+    let balance = common::get_account_balance(client, fee_collector).await;
+    common::post_message(client, program, payer, &Keypair::new(), None, 0, vec![1; 8])
+        .await
+        .unwrap();
+    assert_eq!(
+        common::get_account_balance(client, fee_collector).await,
+        balance + 1234
+    );
+}
+
+#[tokio::test]
+async fn query_fee_cpi() {
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+    let bridge_key = Bridge::<'_, { AccountState::Uninitialized }>::key(None, program);
+
+    // The querier fails unless the fee it gets back is the one it is passed.
+    let bridge: BridgeData = common::get_account_data(client, bridge_key).await;
+    common::query_fee_cpi(client, program, payer, bridge.config.fee)
+        .await
+        .unwrap();
+    assert!(
+        common::query_fee_cpi(client, program, payer, bridge.config.fee + 1)
+            .await
+            .is_err()
+    );
+
+    // The query reflects fee decrees as soon as they are applied.
+    let (result, _logs) = apply_fee_decree(context, client, payer, program, U256::from(1234)).await;
+    result.unwrap();
+    let bridge: BridgeData = common::get_account_data(client, bridge_key).await;
+    assert_eq!(bridge.config.fee, 1234);
+    common::query_fee_cpi(client, program, payer, 1234)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn post_vaa_cpi() {
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+
+    // The poster program records every signature in its own signature set PDA and posts the VAA
+    // along with the last one, without any keypair besides the payer.
+    let emitter = Keypair::new();
+    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+    let (vaa, body, _) = common::generate_vaa(&emitter, vec![7; 16], 0, sequence, 0, 2);
+    let signature_set =
+        common::post_vaa_cpi(client, program, payer, vaa.clone(), body, &context.secret)
+            .await
+            .unwrap();
+
+    let signatures: SignatureSetData = common::get_account_data(client, signature_set).await;
+    assert_eq!(signatures.hash, body);
+    assert!(signatures.signatures.iter().all(|signed| *signed));
+
+    let posted: PostedVAAData = common::get_account_data(
+        client,
+        PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+            &PostedVAADerivationData {
+                payload_hash: body.to_vec(),
+            },
+            program,
+        ),
+    )
+    .await;
+    assert_eq!(posted.payload, vaa.payload);
+    assert_eq!(posted.emitter_address, emitter.pubkey().to_bytes());
+    assert_eq!(posted.sequence, sequence);
+    assert_eq!(posted.vaa_signature_account, signature_set);
+}
+
+#[tokio::test]
+async fn set_fees_overflow() {
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+
+    let fee = U256::from(u64::MAX) + 1;
+    let (result, logs) = apply_fee_decree(context, client, payer, program, fee).await;
+    assert!(result.is_err());
+    assert_bridge_error(&logs, Error::InvalidFee);
+
+    let bridge: BridgeData = common::get_account_data(
+        client,
+        Bridge::<'_, { AccountState::Uninitialized }>::key(None, program),
+    )
+    .await;
+    assert_eq!(bridge.config.fee, 500);
+
+    // The largest fee that fits is accepted.
+    let (result, _logs) =
+        apply_fee_decree(context, client, payer, program, U256::from(u64::MAX)).await;
+    result.unwrap();
+}
+
+#[tokio::test]
+async fn free_fees() {
+    // Initialize a wormhole bridge on Solana to test with.
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
+    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+
+    // Set Fees to 0.
+    let nonce = rand::thread_rng().gen();
+    let message = GovernancePayloadSetMessageFee {
+        fee: U256::from(0u128),
+    }
+    .try_to_vec()
+    .unwrap();
+
+    let message_key = common::post_message(
+        client,
+        program,
+        payer,
+        &emitter,
+        None,
+        nonce,
+        message.clone(),
+    )
+    .await
+    .unwrap();
+
+    let (vaa, body, _body_hash) =
+        common::generate_vaa(&emitter, message.clone(), nonce, sequence, 0, 1);
+    let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, program, payer, signature_set, vaa)
+        .await
+        .unwrap();
+    common::set_fees(
+        client,
+        program,
+        payer,
+        message_key,
+        emitter.pubkey(),
+        sequence,
+    )
+    .await
+    .unwrap();
+
+    // Fetch Bridge to check on-state value.
+    let bridge_key = Bridge::<'_, { AccountState::Uninitialized }>::key(None, program);
+    let fee_collector = FeeCollector::key(None, program);
     let bridge: BridgeData = common::get_account_data(client, bridge_key).await;
-    let guardian_set: GuardianSetData = common::get_account_data(client, guardian_set_key).await;
+    assert_eq!(bridge.config.fee, 0);
+
+    // Check that posting a new message is free.
+    let account_balance = common::get_account_balance(client, fee_collector).await;
+    let emitter = Keypair::new();
+    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+    let nonce = rand::thread_rng().gen();
+    let message = [0u8; 32].to_vec();
+    let _message_key = common::post_message(
+        client,
+        program,
+        payer,
+        &emitter,
+        None,
+        nonce,
+        message.clone(),
+    )
+    .await
+    .unwrap();
+
+    let (vaa, body, _body_hash) =
+        common::generate_vaa(&emitter, message.clone(), nonce, sequence, 0, 1);
+    let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 0)
+        .await
+        .unwrap();
+    let vaa_time = vaa.timestamp;
+    let message_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: body.to_vec(),
+        },
+        program,
+    );
+    common::post_vaa(client, program, payer, signature_set, vaa)
+        .await
+        .unwrap();
+
+    // Verify that the fee collector was paid.
+    assert_eq!(
+        common::get_account_balance(client, fee_collector).await,
+        account_balance,
+    );
+
+    // And that the new message is on chain.
+    let posted_message: PostedVAAData = common::get_account_data(client, message_key).await;
+    let signatures: SignatureSetData = common::get_account_data(client, signature_set).await;
+
+    // Verify on chain Message
+    assert_eq!(posted_message.message.vaa_version, 0);
+    assert_eq!(
+        posted_message.message.consistency_level,
+        ConsistencyLevel::Confirmed as u8
+    );
+    assert_eq!(posted_message.message.vaa_time, vaa_time);
+    assert_eq!(posted_message.message.vaa_signature_account, signature_set);
+    assert_eq!(posted_message.message.nonce, nonce);
+    assert_eq!(posted_message.message.sequence, sequence);
+    assert_eq!(posted_message.message.emitter_chain, 1);
+    assert_eq!(
+        &posted_message.message.emitter_address,
+        emitter.pubkey().as_ref()
+    );
+    assert_eq!(posted_message.message.payload, message);
+    assert_eq!(
+        posted_message.message.emitter_address,
+        emitter.pubkey().to_bytes()
+    );
+
+    // Verify on chain Signatures
+    assert_eq!(signatures.hash, body);
+    assert_eq!(signatures.guardian_set_index, 0);
+
+    for (signature, _secret_key) in signatures.signatures.iter().zip(context.secret.iter()) {
+        assert!(*signature);
+    }
+}
+
+#[tokio::test]
+async fn transfer_fees() {
+    // Initialize a wormhole bridge on Solana to test with.
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
+    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+
+    let nonce = rand::thread_rng().gen();
+    let message = GovernancePayloadTransferFees {
+        amount: 100u128.into(),
+        to: payer.pubkey().to_bytes(),
+        mint: None,
+    }
+    .try_to_vec()
+    .unwrap();
+
+    // Fetch accounts for chain state checking.
+    let fee_collector = FeeCollector::key(None, program);
+
+    let message_key = common::post_message(
+        client,
+        program,
+        payer,
+        &emitter,
+        None,
+        nonce,
+        message.clone(),
+    )
+    .await
+    .unwrap();
+
+    let (vaa, body, _body_hash) =
+        common::generate_vaa(&emitter, message.clone(), nonce, sequence, 0, 1);
+    let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, program, payer, signature_set, vaa)
+        .await
+        .unwrap();
+
+    let previous_balance = common::get_account_balance(client, fee_collector).await;
+
+    common::transfer_fees(
+        client,
+        program,
+        payer,
+        message_key,
+        emitter.pubkey(),
+        payer.pubkey(),
+        sequence,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        common::get_account_balance(client, fee_collector).await,
+        previous_balance - 100
+    );
+}
+
+#[tokio::test]
+async fn transfer_fees_fails() {
+    // Initialize a wormhole bridge on Solana to test with.
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+
+    // Use an invalid emitter.
+    let emitter = Keypair::new();
+    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+
+    let nonce = rand::thread_rng().gen();
+    let message = GovernancePayloadTransferFees {
+        amount: 100u128.into(),
+        to: payer.pubkey().to_bytes(),
+        mint: None,
+    }
+    .try_to_vec()
+    .unwrap();
+
+    // Fetch accounts for chain state checking.
+    let fee_collector = FeeCollector::key(None, program);
+
+    let message_key = common::post_message(
+        client,
+        program,
+        payer,
+        &emitter,
+        None,
+        nonce,
+        message.clone(),
+    )
+    .await
+    .unwrap();
+
+    let (vaa, body, _body_hash) =
+        common::generate_vaa(&emitter, message.clone(), nonce, sequence, 0, 1);
+    let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, program, payer, signature_set, vaa)
+        .await
+        .unwrap();
+
+    let previous_balance = common::get_account_balance(client, fee_collector).await;
+
+    assert!(common::transfer_fees(
+        client,
+        program,
+        payer,
+        message_key,
+        emitter.pubkey(),
+        payer.pubkey(),
+        sequence,
+    )
+    .await
+    .is_err());
+    assert_eq!(
+        common::get_account_balance(client, fee_collector).await,
+        previous_balance
+    );
+}
+
+#[tokio::test]
+async fn transfer_too_much() {
+    // Initialize a wormhole bridge on Solana to test with.
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
+    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+
+    let nonce = rand::thread_rng().gen();
+    let message = GovernancePayloadTransferFees {
+        amount: 100_000_000_000u64.into(),
+        to: payer.pubkey().to_bytes(),
+        mint: None,
+    }
+    .try_to_vec()
+    .unwrap();
+
+    // Fetch accounts for chain state checking.
+    let fee_collector = FeeCollector::key(None, program);
+
+    let message_key = common::post_message(
+        client,
+        program,
+        payer,
+        &emitter,
+        None,
+        nonce,
+        message.clone(),
+    )
+    .await
+    .unwrap();
+
+    let (vaa, body, _body_hash) =
+        common::generate_vaa(&emitter, message.clone(), nonce, sequence, 0, 1);
+    let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, program, payer, signature_set, vaa)
+        .await
+        .unwrap();
+
+    let previous_balance = common::get_account_balance(client, fee_collector).await;
+
+    // Should fail to transfer.
+    assert!(common::transfer_fees(
+        client,
+        program,
+        payer,
+        message_key,
+        emitter.pubkey(),
+        payer.pubkey(),
+        sequence,
+    )
+    .await
+    .is_err());
+    assert_eq!(
+        common::get_account_balance(client, fee_collector).await,
+        previous_balance
+    );
+}
+
+#[tokio::test]
+async fn transfer_fees_full_balance() {
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
+    let (fee_collector, _) = find_fee_collector(program);
+    assert_eq!(fee_collector, FeeCollector::key(None, program));
+    let rent = client.get_rent().await.unwrap().minimum_balance(0);
+    let recipient = Keypair::new().pubkey();
 
-    // Verify on chain Message
-    assert_eq!(posted_message.message.vaa_version, 0);
+    // Fund the collector well enough that the payout can fund a fresh recipient account.
+    common::execute(
+        client,
+        payer,
+        &[payer],
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            &fee_collector,
+            2 * rent,
+        )],
+        CommitmentLevel::Processed,
+    )
+    .await
+    .unwrap();
+
+    // Amounts wider than u64 are rejected.
+    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+    let nonce = rand::thread_rng().gen();
+    let message = GovernancePayloadTransferFees {
+        amount: U256::from(u64::MAX) + 1,
+        to: recipient.to_bytes(),
+        mint: None,
+    }
+    .try_to_vec()
+    .unwrap();
+    let message_key = common::post_message(client, program, payer, &emitter, None, nonce, message)
+        .await
+        .unwrap();
+    let balance = common::get_account_balance(client, fee_collector).await;
+    assert!(common::transfer_fees(
+        client,
+        program,
+        payer,
+        message_key,
+        emitter.pubkey(),
+        recipient,
+        sequence,
+    )
+    .await
+    .is_err());
     assert_eq!(
-        posted_message.message.consistency_level,
-        ConsistencyLevel::Confirmed as u8
+        common::get_account_balance(client, fee_collector).await,
+        balance
     );
-    assert_eq!(posted_message.message.vaa_time, vaa_time);
-    assert_eq!(posted_message.message.vaa_signature_account, signature_set);
-    assert_eq!(posted_message.message.nonce, nonce);
-    assert_eq!(posted_message.message.sequence, sequence);
-    assert_eq!(posted_message.message.emitter_chain, 1);
+
+    // A single lamport more than the collector holds above its rent exemption, counting the fee of
+    // the decree itself, is refused.
+    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+    let nonce = rand::thread_rng().gen();
+    let message = GovernancePayloadTransferFees {
+        amount: (balance - rent + 500 + 1).into(),
+        to: recipient.to_bytes(),
+        mint: None,
+    }
+    .try_to_vec()
+    .unwrap();
+    let message_key = common::post_message(client, program, payer, &emitter, None, nonce, message)
+        .await
+        .unwrap();
+    let balance = common::get_account_balance(client, fee_collector).await;
+    let ix = instructions::transfer_fees(
+        *program,
+        payer.pubkey(),
+        message_key,
+        emitter.pubkey(),
+        sequence,
+        recipient,
+    );
+    let (result, logs) = common::execute_with_logs(client, payer, &[payer], &[ix])
+        .await
+        .unwrap();
+    assert!(result.is_err());
+    assert_bridge_error(&logs, Error::InsufficientSpendableFees);
+    assert_eq!(
+        common::get_account_balance(client, fee_collector).await,
+        balance
+    );
+
+    // Pay out everything above the rent exemption of the fee collector, including the fee of the
+    // decree itself.
+    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+    let nonce = rand::thread_rng().gen();
+    let spendable = balance - rent + 500;
+    let message = GovernancePayloadTransferFees {
+        amount: spendable.into(),
+        to: recipient.to_bytes(),
+        mint: None,
+    }
+    .try_to_vec()
+    .unwrap();
+    let message_key = common::post_message(client, program, payer, &emitter, None, nonce, message)
+        .await
+        .unwrap();
+    common::transfer_fees(
+        client,
+        program,
+        payer,
+        message_key,
+        emitter.pubkey(),
+        recipient,
+        sequence,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        common::get_account_balance(client, fee_collector).await,
+        rent
+    );
+    assert_eq!(
+        common::get_account_balance(client, recipient).await,
+        spendable
+    );
+
+    // The bridge keeps accepting fees afterwards.
+    common::post_message(client, program, payer, &Keypair::new(), None, 0, vec![0; 8])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn sweep_fee_collector() {
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
+    let fee_collector = FeeCollector::key(None, program);
+    let rent = client.get_rent().await.unwrap().minimum_balance(0);
+    let recipient = Keypair::new().pubkey();
+
+    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+    let message = GovernancePayloadSweepFeeCollector {
+        reserve: 1_000,
+        to: recipient.to_bytes(),
+    }
+    .try_to_vec()
+    .unwrap();
+    let message_key = common::post_message(
+        client,
+        program,
+        payer,
+        &emitter,
+        None,
+        rand::thread_rng().gen(),
+        message,
+    )
+    .await
+    .unwrap();
+
+    // Dust donated to the collector is swept along with the fees.
+    common::execute(
+        client,
+        payer,
+        &[payer],
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            &fee_collector,
+            2 * rent,
+        )],
+        CommitmentLevel::Processed,
+    )
+    .await
+    .unwrap();
+    let balance = common::get_account_balance(client, fee_collector).await;
+
+    let ix = instructions::sweep_fee_collector(
+        *program,
+        payer.pubkey(),
+        message_key,
+        emitter.pubkey(),
+        sequence,
+        recipient,
+    );
+    let (result, logs) = common::execute_with_logs(client, payer, &[payer], &[ix])
+        .await
+        .unwrap();
+    result.unwrap();
+    let events: Vec<SweepFeeCollectorEvent> = logs
+        .iter()
+        .filter_map(|log| log.strip_prefix("Program data: "))
+        .map(|data| SweepFeeCollectorEvent::decode(&base64::decode(data).unwrap()).unwrap())
+        .collect();
+    assert_eq!(
+        events,
+        vec![SweepFeeCollectorEvent {
+            amount: balance - rent - 1_000,
+            recipient: recipient.to_bytes(),
+        }]
+    );
+    assert_eq!(
+        common::get_account_balance(client, fee_collector).await,
+        rent + 1_000
+    );
+    assert_eq!(
+        common::get_account_balance(client, recipient).await,
+        balance - rent - 1_000
+    );
+
+    // Sweeping with nothing above the reserve succeeds without moving any lamports.
+    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+    let message = GovernancePayloadSweepFeeCollector {
+        reserve: u64::MAX,
+        to: recipient.to_bytes(),
+    }
+    .try_to_vec()
+    .unwrap();
+    let message_key = common::post_message(
+        client,
+        program,
+        payer,
+        &emitter,
+        None,
+        rand::thread_rng().gen(),
+        message,
+    )
+    .await
+    .unwrap();
+    let balance = common::get_account_balance(client, fee_collector).await;
+    let recipient_balance = common::get_account_balance(client, recipient).await;
+    let ix = instructions::sweep_fee_collector(
+        *program,
+        payer.pubkey(),
+        message_key,
+        emitter.pubkey(),
+        sequence,
+        recipient,
+    );
+    common::execute(client, payer, &[payer], &[ix], CommitmentLevel::Processed)
+        .await
+        .unwrap();
+    assert_eq!(
+        common::get_account_balance(client, fee_collector).await,
+        balance
+    );
+    assert_eq!(
+        common::get_account_balance(client, recipient).await,
+        recipient_balance
+    );
+}
+
+/// Post a governance decree, paying the message fee in `mint` from `payer_tokens` while a fee mint
+/// is set. Returns the message and sequence of the decree, ready to be applied.
+async fn post_decree(
+    context: &mut Context,
+    client: &mut BanksClient,
+    payer: &Keypair,
+    program: &Pubkey,
+    message: Vec<u8>,
+    token_fee: Option<(&Pubkey, &Pubkey)>,
+) -> (Pubkey, u64) {
+    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
+    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+    let nonce = rand::thread_rng().gen();
+    let message_key = match token_fee {
+        Some((mint, payer_tokens)) => common::post_message_with_token_fee(
+            client,
+            program,
+            payer,
+            &emitter,
+            nonce,
+            message.clone(),
+            mint,
+            payer_tokens,
+        )
+        .await
+        .unwrap(),
+        None => common::post_message(
+            client,
+            program,
+            payer,
+            &emitter,
+            None,
+            nonce,
+            message.clone(),
+        )
+        .await
+        .unwrap(),
+    };
+
+    let (vaa, body, _body_hash) = common::generate_vaa(&emitter, message, nonce, sequence, 0, 1);
+    let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, program, payer, signature_set, vaa)
+        .await
+        .unwrap();
+
+    (message_key, sequence)
+}
+
+#[tokio::test]
+async fn token_fees() {
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
+    let bridge_key = Bridge::<'_, { AccountState::Uninitialized }>::key(None, program);
+    let fee_collector = FeeCollector::key(None, program);
+
+    let mint_authority = Keypair::new();
+    let mint = Keypair::new();
+    common::create_mint(client, payer, &mint_authority.pubkey(), &mint)
+        .await
+        .unwrap();
+    let payer_tokens = Keypair::new();
+    common::create_token_account(
+        client,
+        payer,
+        &payer_tokens,
+        &payer.pubkey(),
+        &mint.pubkey(),
+        &mint_authority,
+        1000,
+    )
+    .await
+    .unwrap();
+    let fee_tokens = FeeTokenAccount::<'_, { AccountState::Uninitialized }>::key(
+        &FeeTokenAccountDerivationData {
+            mint: mint.pubkey(),
+        },
+        program,
+    );
+
+    // Messages pay lamports until governance sets a fee mint.
+    let balance = common::get_account_balance(client, fee_collector).await;
+    common::post_message(client, program, payer, &Keypair::new(), None, 0, vec![1; 8])
+        .await
+        .unwrap();
+    assert_eq!(
+        common::get_account_balance(client, fee_collector).await,
+        balance + 500
+    );
+
+    let message = GovernancePayloadSetFeeMint {
+        mint: mint.pubkey(),
+        fee: 25,
+    }
+    .try_to_vec()
+    .unwrap();
+    let (message_key, sequence) = post_decree(context, client, payer, program, message, None).await;
+    let ix = instructions::set_fee_mint(
+        *program,
+        payer.pubkey(),
+        message_key,
+        emitter.pubkey(),
+        sequence,
+        mint.pubkey(),
+    );
+    common::execute(client, payer, &[payer], &[ix], CommitmentLevel::Processed)
+        .await
+        .unwrap();
+
+    let account = client.get_account(bridge_key).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), BRIDGE_WITH_FEE_MINT_SIZE);
+    // Growing the config tops up its rent.
+    assert!(Rent::default().is_exempt(account.lamports, account.data.len()));
+    let bridge = BridgeData::try_from_slice(&account.data).unwrap();
+    assert_eq!(bridge.config.fee_mint, Some(mint.pubkey()));
+    assert_eq!(bridge.config.token_fee, 25);
+    assert_eq!(bridge.config.fee, 500);
+    assert_eq!(common::get_token_balance(client, fee_tokens).await, 0);
+
+    // Messages without the token accounts, or paying into another token account, are rejected.
+    let message = Keypair::new();
+    let ix = instructions::post_message(
+        *program,
+        payer.pubkey(),
+        payer.pubkey(),
+        message.pubkey(),
+        0,
+        vec![2; 8],
+        ConsistencyLevel::Confirmed,
+    )
+    .unwrap();
+    let (result, logs) =
+        common::execute_with_logs(client, payer, &[payer, &message], &[ix.clone()])
+            .await
+            .unwrap();
+    assert!(result.is_err());
+    assert_bridge_error(&logs, Error::InvalidFeeTokenAccount);
+
+    let mut redirected = instructions::with_token_fee(ix, mint.pubkey(), payer_tokens.pubkey());
+    let fee_tokens_index = redirected.accounts.len() - 2;
+    redirected.accounts[fee_tokens_index].pubkey = payer_tokens.pubkey();
+    let (result, logs) =
+        common::execute_with_logs(client, payer, &[payer, &message], &[redirected])
+            .await
+            .unwrap();
+    assert!(result.is_err());
+    assert_bridge_error(&logs, Error::InvalidFeeTokenAccount);
+
+    // Paying in tokens leaves the fee collector alone.
+    let balance = common::get_account_balance(client, fee_collector).await;
+    common::post_message_with_token_fee(
+        client,
+        program,
+        payer,
+        &Keypair::new(),
+        0,
+        vec![3; 8],
+        &mint.pubkey(),
+        &payer_tokens.pubkey(),
+    )
+    .await
+    .unwrap();
     assert_eq!(
-        &posted_message.message.emitter_address,
-        emitter.pubkey().as_ref()
+        common::get_account_balance(client, fee_collector).await,
+        balance
     );
-    assert_eq!(posted_message.message.payload, message);
     assert_eq!(
-        posted_message.message.emitter_address,
-        emitter.pubkey().to_bytes()
+        common::get_token_balance(client, payer_tokens.pubkey()).await,
+        975
     );
+    assert_eq!(common::get_token_balance(client, fee_tokens).await, 25);
 
-    // Confirm the bridge now has a new guardian set, and no other fields have shifted.
-    assert_eq!(bridge.guardian_set_index, 1);
-    assert_eq!(bridge.config.guardian_set_expiration_time, 2_000_000_000);
-    assert_eq!(bridge.config.fee, 500);
-
-    // Verify Created Guardian Set
-    assert_eq!(guardian_set.index, 1);
-    assert_eq!(guardian_set.keys, new_public_keys);
-    assert!(guardian_set.creation_time as u64 > now);
-
-    // Submit the message a second time with a new nonce.
-    let nonce = rand::thread_rng().gen();
-    let _message_key = common::post_message(
+    // Token fees are paid out like lamports, the decree naming the mint. Posting it pays 25 more.
+    let recipient = Keypair::new();
+    common::create_token_account(
         client,
-        program,
         payer,
-        &emitter,
-        None,
-        nonce,
-        message.clone(),
-        10_000,
+        &recipient,
+        &Keypair::new().pubkey(),
+        &mint.pubkey(),
+        &mint_authority,
+        0,
     )
     .await
     .unwrap();
+    let message = GovernancePayloadTransferFees {
+        amount: 40u128.into(),
+        to: recipient.pubkey().to_bytes(),
+        mint: Some(mint.pubkey()),
+    }
+    .try_to_vec()
+    .unwrap();
+    let token_fee = Some((&mint.pubkey(), &payer_tokens.pubkey()));
+    let (message_key, sequence) =
+        post_decree(context, client, payer, program, message, token_fee).await;
+    let ix = instructions::transfer_token_fees(
+        *program,
+        payer.pubkey(),
+        message_key,
+        emitter.pubkey(),
+        sequence,
+        mint.pubkey(),
+        recipient.pubkey(),
+    );
+    common::execute(client, payer, &[payer], &[ix], CommitmentLevel::Processed)
+        .await
+        .unwrap();
+    assert_eq!(
+        common::get_token_balance(client, recipient.pubkey()).await,
+        40
+    );
+    assert_eq!(common::get_token_balance(client, fee_tokens).await, 10);
 
-    context.public = new_public_keys;
-    context.secret = new_secret_keys;
+    // Switching back to lamports shrinks the config to its original size.
+    let message = GovernancePayloadSetFeeMint {
+        mint: Pubkey::default(),
+        fee: 0,
+    }
+    .try_to_vec()
+    .unwrap();
+    let (message_key, sequence) =
+        post_decree(context, client, payer, program, message, token_fee).await;
+    let ix = instructions::set_fee_mint(
+        *program,
+        payer.pubkey(),
+        message_key,
+        emitter.pubkey(),
+        sequence,
+        Pubkey::default(),
+    );
+    common::execute(client, payer, &[payer], &[ix], CommitmentLevel::Processed)
+        .await
+        .unwrap();
 
-    // Emulate Guardian behaviour, verifying the data and publishing signatures/VAA.
-    let (vaa, body, _body_hash) =
-        common::generate_vaa(&emitter, message.clone(), nonce, sequence, 1, 1);
-    let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 1)
+    let account = client.get_account(bridge_key).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), BRIDGE_SIZE);
+    let bridge = BridgeData::try_from_slice(&account.data).unwrap();
+    assert_eq!(bridge.config.fee_mint, None);
+    assert_eq!(bridge.config.fee, 500);
+
+    let balance = common::get_account_balance(client, fee_collector).await;
+    common::post_message(client, program, payer, &Keypair::new(), None, 0, vec![4; 8])
         .await
         .unwrap();
-    let vaa_time = vaa.timestamp;
+    assert_eq!(
+        common::get_account_balance(client, fee_collector).await,
+        balance + 500
+    );
+    assert_eq!(
+        common::get_token_balance(client, payer_tokens.pubkey()).await,
+        925
+    );
+    assert_eq!(common::get_token_balance(client, fee_tokens).await, 35);
+}
+
+#[tokio::test]
+async fn foreign_bridge_messages() {
+    // Initialize a wormhole bridge on Solana to test with.
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+    let nonce = rand::thread_rng().gen();
+    let message = [0u8; 32].to_vec();
+    let emitter = Keypair::new();
+    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+
+    // Verify the VAA generated on a foreign chain.
+    let (vaa, body, _body_hash) =
+        common::generate_vaa(&emitter, message.clone(), nonce, sequence, 0, 2);
+
+    // Derive where we expect created accounts to be.
     let message_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
         &PostedVAADerivationData {
             payload_hash: body.to_vec(),
         },
         program,
     );
+
+    let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 0)
+        .await
+        .unwrap();
     common::post_vaa(client, program, payer, signature_set, vaa)
         .await
         .unwrap();
@@ -829,17 +3913,11 @@ async fn guardian_set_change() {
     let posted_message: PostedVAAData = common::get_account_data(client, message_key).await;
     let signatures: SignatureSetData = common::get_account_data(client, signature_set).await;
 
-    // Verify on chain Message
     assert_eq!(posted_message.message.vaa_version, 0);
-    assert_eq!(
-        posted_message.message.consistency_level,
-        ConsistencyLevel::Confirmed as u8
-    );
-    assert_eq!(posted_message.message.vaa_time, vaa_time);
     assert_eq!(posted_message.message.vaa_signature_account, signature_set);
     assert_eq!(posted_message.message.nonce, nonce);
     assert_eq!(posted_message.message.sequence, sequence);
-    assert_eq!(posted_message.message.emitter_chain, 1);
+    assert_eq!(posted_message.message.emitter_chain, 2);
     assert_eq!(posted_message.message.payload, message);
     assert_eq!(
         posted_message.message.emitter_address,
@@ -848,7 +3926,7 @@ async fn guardian_set_change() {
 
     // Verify on chain Signatures
     assert_eq!(signatures.hash, body);
-    assert_eq!(signatures.guardian_set_index, 1);
+    assert_eq!(signatures.guardian_set_index, 0);
 
     for (signature, _secret_key) in signatures.signatures.iter().zip(context.secret.iter()) {
         assert!(*signature);
@@ -856,61 +3934,23 @@ async fn guardian_set_change() {
 }
 
 #[tokio::test]
-async fn guardian_set_change_fails() {
+async fn transfer_total_fails() {
     // Initialize a wormhole bridge on Solana to test with.
     let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
-
-    // Use a random emitter key to confirm the bridge rejects transactions from non-governance key.
-    let emitter = Keypair::new();
+    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
     let sequence = context.seq.next(emitter.pubkey().to_bytes());
 
-    // Upgrade the guardian set with a new set of guardians.
-    let (new_public_keys, _new_secret_keys) = common::generate_keys(6);
-    let nonce = rand::thread_rng().gen();
-    let message = GovernancePayloadGuardianSetChange {
-        new_guardian_set_index: 2,
-        new_guardian_set: new_public_keys.clone(),
-    }
-    .try_to_vec()
-    .unwrap();
-
-    let message_key = common::post_message(
-        client,
-        program,
-        payer,
-        &emitter,
-        None,
-        nonce,
-        message.clone(),
-        10_000,
-    )
-    .await
-    .unwrap();
-
-    assert!(common::upgrade_guardian_set(
-        client,
-        program,
-        payer,
-        message_key,
-        emitter.pubkey(),
-        1,
-        2,
-        sequence,
-    )
-    .await
-    .is_err());
-}
+    // Be sure any previous tests have fully committed.
 
-#[tokio::test]
-async fn set_fees() {
-    // Initialize a wormhole bridge on Solana to test with.
-    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
-    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
-    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+    let fee_collector = FeeCollector::key(None, program);
+    let account_balance = common::get_account_balance(client, fee_collector).await;
 
+    // Prepare to remove total balance, adding 500 to include the fee we're about to pay.
     let nonce = rand::thread_rng().gen();
-    let message = GovernancePayloadSetMessageFee {
-        fee: U256::from(100u128),
+    let message = GovernancePayloadTransferFees {
+        amount: (account_balance + 500).into(),
+        to: payer.pubkey().to_bytes(),
+        mint: None,
     }
     .try_to_vec()
     .unwrap();
@@ -923,7 +3963,6 @@ async fn set_fees() {
         None,
         nonce,
         message.clone(),
-        10_000,
     )
     .await
     .unwrap();
@@ -936,135 +3975,48 @@ async fn set_fees() {
     common::post_vaa(client, program, payer, signature_set, vaa)
         .await
         .unwrap();
-    common::set_fees(
+
+    // Transferring total fees should fail, to prevent the account being de-allocated.
+    assert!(common::transfer_fees(
         client,
         program,
         payer,
         message_key,
         emitter.pubkey(),
+        payer.pubkey(),
         sequence,
     )
     .await
-    .unwrap();
-
-    // Fetch Bridge to check on-state value.
-    let bridge_key = Bridge::<'_, { AccountState::Uninitialized }>::key(None, program);
-    let fee_collector = FeeCollector::key(None, program);
-    let bridge: BridgeData = common::get_account_data(client, bridge_key).await;
-    assert_eq!(bridge.config.fee, 100);
-
-    // Check that posting a new message fails with too small a fee.
-    let account_balance = common::get_account_balance(client, fee_collector).await;
-    let emitter = Keypair::new();
-    let nonce = rand::thread_rng().gen();
-    let message = [0u8; 32].to_vec();
-    assert!(common::post_message(
-        client,
-        program,
-        payer,
-        &emitter,
-        None,
-        nonce,
-        message.clone(),
-        50
-    )
-    .await
     .is_err());
 
+    // The fee should have been paid, but other than that the balance should be exactly the same,
+    // I.E non-zero.
     assert_eq!(
         common::get_account_balance(client, fee_collector).await,
-        account_balance,
-    );
-
-    // And succeeds with the new.
-    let emitter = Keypair::new();
-    let sequence = context.seq.next(emitter.pubkey().to_bytes());
-    let nonce = rand::thread_rng().gen();
-    let message = [0u8; 32].to_vec();
-    let _message_key = common::post_message(
-        client,
-        program,
-        payer,
-        &emitter,
-        None,
-        nonce,
-        message.clone(),
-        100,
-    )
-    .await
-    .unwrap();
-
-    let (vaa, body, _body_hash) =
-        common::generate_vaa(&emitter, message.clone(), nonce, sequence, 0, 1);
-    let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 0)
-        .await
-        .unwrap();
-    let vaa_time = vaa.timestamp;
-    let message_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
-        &PostedVAADerivationData {
-            payload_hash: body.to_vec(),
-        },
-        program,
-    );
-    common::post_vaa(client, program, payer, signature_set, vaa)
-        .await
-        .unwrap();
-
-    // Verify that the fee collector was paid.
-    assert_eq!(
-        common::get_account_balance(client, fee_collector).await,
-        account_balance + 100,
-    );
-
-    // And that the new message is on chain.
-    let posted_message: PostedVAAData = common::get_account_data(client, message_key).await;
-    let signatures: SignatureSetData = common::get_account_data(client, signature_set).await;
-
-    // Verify on chain Message
-    assert_eq!(posted_message.message.vaa_version, 0);
-    assert_eq!(
-        posted_message.message.consistency_level,
-        ConsistencyLevel::Confirmed as u8
-    );
-    assert_eq!(posted_message.message.vaa_time, vaa_time);
-    assert_eq!(posted_message.message.vaa_signature_account, signature_set);
-    assert_eq!(posted_message.message.nonce, nonce);
-    assert_eq!(posted_message.message.sequence, sequence);
-    assert_eq!(posted_message.message.emitter_chain, 1);
-    assert_eq!(
-        &posted_message.message.emitter_address,
-        emitter.pubkey().as_ref()
+        account_balance + 500
     );
-    assert_eq!(posted_message.message.payload, message);
-    assert_eq!(
-        posted_message.message.emitter_address,
-        emitter.pubkey().to_bytes()
-    );
-
-    // Verify on chain Signatures
-    assert_eq!(signatures.hash, body);
-    assert_eq!(signatures.guardian_set_index, 0);
-
-    for (signature, _secret_key) in signatures.signatures.iter().zip(context.secret.iter()) {
-        assert!(*signature);
-    }
 }
 
+// `solana-program-test` doesn't use an upgradeable loader so it's not currently possible to test
+// the contract upgrade logic this way. See https://github.com/solana-labs/solana/issues/22950 for
+// more details. This test is here mainly as a reference in case the issue above gets fixed, at
+// which point this test can be re-enabled.
 #[tokio::test]
-async fn set_fees_fails() {
+#[ignore]
+async fn upgrade_contract() {
     // Initialize a wormhole bridge on Solana to test with.
     let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
 
-    // Use a random key to confirm only the governance key is respected.
-    let emitter = Keypair::new();
-    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+    // New Contract Address
+    // let new_contract = Pubkey::new_unique();
+    let new_contract = *program;
 
     let nonce = rand::thread_rng().gen();
-    let message = GovernancePayloadSetMessageFee {
-        fee: U256::from(100u128),
-    }
-    .try_to_vec()
-    .unwrap();
+    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
+    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+    let message = GovernancePayloadUpgrade { new_contract }
+        .try_to_vec()
+        .unwrap();
 
     let message_key = common::post_message(
         client,
@@ -1074,7 +4026,6 @@ async fn set_fees_fails() {
         None,
         nonce,
         message.clone(),
-        10_000,
     )
     .await
     .unwrap();
@@ -1087,179 +4038,692 @@ async fn set_fees_fails() {
     common::post_vaa(client, program, payer, signature_set, vaa)
         .await
         .unwrap();
-    assert!(common::set_fees(
+    common::upgrade_contract(
         client,
         program,
         payer,
         message_key,
         emitter.pubkey(),
+        new_contract,
+        Pubkey::new_unique(),
         sequence,
     )
     .await
-    .is_err());
+    .unwrap();
 }
 
 #[tokio::test]
-async fn free_fees() {
-    // Initialize a wormhole bridge on Solana to test with.
+async fn upgrade_contract_invalid_decree() {
     let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
     let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
-    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+    let buffer = Pubkey::new_unique();
 
-    // Set Fees to 0.
-    let nonce = rand::thread_rng().gen();
-    let message = GovernancePayloadSetMessageFee {
-        fee: U256::from(0u128),
+    let on_solana = GovernancePayloadUpgrade {
+        new_contract: buffer,
     }
     .try_to_vec()
     .unwrap();
+    // The same decree addressed to every chain.
+    let mut on_all_chains = on_solana.clone();
+    on_all_chains[33..35].copy_from_slice(&0u16.to_be_bytes());
+
+    for (message, error) in [
+        // Not a buffer of the upgradeable loader.
+        (on_solana, Error::InvalidUpgradeBuffer),
+        (on_all_chains, Error::InvalidGovernanceChain),
+    ] {
+        let sequence = context.seq.next(emitter.pubkey().to_bytes());
+        let nonce = rand::thread_rng().gen();
+        let message_key =
+            common::post_message(client, program, payer, &emitter, None, nonce, message)
+                .await
+                .unwrap();
+        let ix = instructions::upgrade_contract(
+            *program,
+            payer.pubkey(),
+            message_key,
+            emitter.pubkey(),
+            buffer,
+            Pubkey::new_unique(),
+            sequence,
+        );
+        let (result, logs) = common::execute_with_logs(client, payer, &[payer], &[ix])
+            .await
+            .unwrap();
+        assert!(result.is_err());
+        assert_bridge_error(&logs, error);
+    }
+}
 
-    let message_key = common::post_message(
+#[tokio::test]
+async fn set_guardian_set_expiration() {
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
+
+    for (expiration_time, error) in [
+        (
+            MIN_GUARDIAN_SET_EXPIRATION_TIME - 1,
+            Some(Error::InvalidGuardianSetExpiration),
+        ),
+        (
+            MAX_GUARDIAN_SET_EXPIRATION_TIME + 1,
+            Some(Error::InvalidGuardianSetExpiration),
+        ),
+        (2 * 60 * 60, None),
+    ] {
+        let sequence = context.seq.next(emitter.pubkey().to_bytes());
+        let nonce = rand::thread_rng().gen();
+        let message = GovernancePayloadSetGuardianSetExpiration {
+            guardian_set_expiration_time: expiration_time,
+        }
+        .try_to_vec()
+        .unwrap();
+        let message_key =
+            common::post_message(client, program, payer, &emitter, None, nonce, message)
+                .await
+                .unwrap();
+        let ix = instructions::set_guardian_set_expiration(
+            *program,
+            payer.pubkey(),
+            message_key,
+            emitter.pubkey(),
+            sequence,
+        );
+        let (result, logs) = common::execute_with_logs(client, payer, &[payer], &[ix])
+            .await
+            .unwrap();
+        match error {
+            Some(error) => {
+                assert!(result.is_err());
+                assert_bridge_error(&logs, error);
+            }
+            None => result.unwrap(),
+        }
+    }
+
+    let bridge_key = Bridge::<'_, { AccountState::Uninitialized }>::key(None, program);
+    let bridge: BridgeData = common::get_account_data(client, bridge_key).await;
+    assert_eq!(bridge.config.guardian_set_expiration_time, 2 * 60 * 60);
+
+    // The next guardian set upgrade stamps the old set with the new expiration time.
+    let keys = context.public.clone();
+    let vaa_time = update_guardian_set(context, client, payer, program, 1, keys).await;
+    let guardian_set: GuardianSetData = common::get_account_data(
+        client,
+        GuardianSet::<'_, { AccountState::Uninitialized }>::key(
+            &GuardianSetDerivationData { index: 0 },
+            program,
+        ),
+    )
+    .await;
+    assert_eq!(guardian_set.expiration_time, vaa_time + 2 * 60 * 60);
+}
+
+#[tokio::test]
+async fn post_vaa_old_guardian_set() {
+    let (ref mut context, ref mut test_context, ref program) = initialize_with_context().await;
+    let (client, payer) = (&mut test_context.banks_client, &test_context.payer);
+
+    let keys = context.public.clone();
+    let vaa_time = update_guardian_set(context, client, payer, program, 1, keys).await;
+    let bridge: BridgeData = common::get_account_data(
+        client,
+        Bridge::<'_, { AccountState::Uninitialized }>::key(None, program),
+    )
+    .await;
+    assert_eq!(bridge.guardian_set_index, 1);
+    let guardian_set: GuardianSetData = common::get_account_data(
         client,
+        GuardianSet::<'_, { AccountState::Uninitialized }>::key(
+            &GuardianSetDerivationData { index: 0 },
+            program,
+        ),
+    )
+    .await;
+    let expiration_time = vaa_time + bridge.config.guardian_set_expiration_time;
+    assert_eq!(guardian_set.expiration_time, expiration_time);
+
+    // Guardian set 0 has been replaced, but VAAs it signed stay valid until it expires.
+    let mut clock: Clock = client.get_sysvar().await.unwrap();
+    for (unix_timestamp, error) in [
+        (None, None),
+        (Some(expiration_time - 1), None),
+        (
+            Some(expiration_time),
+            Some(Error::PostVAAGuardianSetExpired),
+        ),
+    ] {
+        if let Some(unix_timestamp) = unix_timestamp {
+            clock.unix_timestamp = unix_timestamp as i64;
+            test_context.set_sysvar(&clock);
+        }
+        let (client, payer) = (&mut test_context.banks_client, &test_context.payer);
+
+        let nonce = rand::thread_rng().gen();
+        let (vaa, body, _body_hash) =
+            common::generate_vaa(&Keypair::new(), vec![1, 2, 3], nonce, 0, 0, 1);
+        let signature_set =
+            common::verify_signatures(client, program, payer, body, &context.secret, 0)
+                .await
+                .unwrap();
+        let (result, logs) = common::execute_with_logs(
+            client,
+            payer,
+            &[payer],
+            &[instructions::post_vaa(
+                *program,
+                payer.pubkey(),
+                signature_set,
+                vaa,
+            )],
+        )
+        .await
+        .unwrap();
+
+        match error {
+            None => result.unwrap(),
+            Some(error) => {
+                assert!(result.is_err());
+                assert_bridge_error(&logs, error);
+            }
+        }
+    }
+
+    // A VAA must be posted with the guardian set it claims to be signed by.
+    let (client, payer) = (&mut test_context.banks_client, &test_context.payer);
+    let (vaa, body, _body_hash) = common::generate_vaa(&Keypair::new(), vec![4, 5, 6], 0, 0, 1, 1);
+    let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 1)
+        .await
+        .unwrap();
+    let mut ix = instructions::post_vaa(*program, payer.pubkey(), signature_set, vaa);
+    ix.accounts[0].pubkey = GuardianSet::<'_, { AccountState::Uninitialized }>::key(
+        &GuardianSetDerivationData { index: 0 },
+        program,
+    );
+    let (result, logs) = common::execute_with_logs(client, payer, &[payer], &[ix])
+        .await
+        .unwrap();
+    assert!(result.is_err());
+    assert_bridge_error(&logs, Error::GuardianSetMismatch);
+}
+
+#[tokio::test]
+async fn post_vaa_event() {
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+
+    let emitter = Keypair::new();
+    let (vaa, body, _body_hash) = common::generate_vaa(&emitter, vec![7; 8], 42, 3, 0, 2);
+    let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 0)
+        .await
+        .unwrap();
+    let post = instructions::post_vaa(*program, payer.pubkey(), signature_set, vaa.clone());
+    let (result, logs) = common::execute_with_logs(client, payer, &[payer], &[post])
+        .await
+        .unwrap();
+    result.unwrap();
+
+    let events: Vec<PostVAAEvent> = logs
+        .iter()
+        .filter_map(|log| log.strip_prefix("Program data: "))
+        .map(|data| PostVAAEvent::decode(&base64::decode(data).unwrap()).unwrap())
+        .collect();
+    assert_eq!(
+        events,
+        vec![PostVAAEvent {
+            hash: body,
+            emitter_chain: 2,
+            emitter_address: emitter.pubkey().to_bytes(),
+            sequence: 3,
+            guardian_set_index: 0,
+        }]
+    );
+
+    // The hash is the one the posted VAA is derived from, and matches the body it stores.
+    let message_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: events[0].hash.to_vec(),
+        },
         program,
+    );
+    let posted: PostedVAAData = common::get_account_data(client, message_key).await;
+    let stored = PostVAAData {
+        version: posted.message.vaa_version,
+        guardian_set_index: 0,
+        timestamp: posted.message.vaa_time,
+        nonce: posted.message.nonce,
+        emitter_chain: posted.message.emitter_chain,
+        emitter_address: posted.message.emitter_address,
+        sequence: posted.message.sequence,
+        consistency_level: posted.message.consistency_level,
+        payload: posted.message.payload,
+    };
+    assert_eq!(instructions::hash_vaa(&stored), events[0].hash);
+
+    // Posting again is a no-op and must not signal the VAA a second time.
+    let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 0)
+        .await
+        .unwrap();
+    let post = instructions::post_vaa(*program, payer.pubkey(), signature_set, vaa);
+    let (result, logs) = common::execute_with_logs(client, payer, &[payer], &[post])
+        .await
+        .unwrap();
+    result.unwrap();
+    assert!(!logs.iter().any(|log| log.starts_with("Program data: ")));
+}
+
+#[tokio::test]
+async fn verify_hash() {
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+    let hash: [u8; 32] = rand::thread_rng().gen();
+
+    // Four of six guardians are one short of quorum.
+    let signature_set =
+        common::verify_signatures(client, program, payer, hash, &context.secret[..4], 0)
+            .await
+            .unwrap();
+    let (result, logs) = common::execute_with_logs(
+        client,
         payer,
-        &emitter,
-        None,
-        nonce,
-        message.clone(),
-        10_000,
+        &[payer],
+        &[instructions::verify_hash(*program, payer.pubkey(), signature_set, 0, hash).unwrap()],
     )
     .await
     .unwrap();
+    assert!(result.is_err());
+    assert_bridge_error(&logs, Error::PostVAAConsensusFailed);
 
-    let (vaa, body, _body_hash) =
-        common::generate_vaa(&emitter, message.clone(), nonce, sequence, 0, 1);
-    let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 0)
+    let signature_set = common::verify_signatures(client, program, payer, hash, &context.secret, 0)
         .await
         .unwrap();
-    common::post_vaa(client, program, payer, signature_set, vaa)
-        .await
-        .unwrap();
-    common::set_fees(
+    common::execute(
         client,
-        program,
         payer,
-        message_key,
-        emitter.pubkey(),
-        sequence,
+        &[payer],
+        &[instructions::verify_hash(*program, payer.pubkey(), signature_set, 0, hash).unwrap()],
+        CommitmentLevel::Processed,
     )
     .await
     .unwrap();
 
-    // Fetch Bridge to check on-state value.
-    let bridge_key = Bridge::<'_, { AccountState::Uninitialized }>::key(None, program);
+    let verified: VerifiedHashData = common::get_account_data(
+        client,
+        VerifiedHash::<'_, { AccountState::MaybeInitialized }>::key(
+            &VerifiedHashDerivationData { hash },
+            program,
+        ),
+    )
+    .await;
+    assert_eq!(verified.hash, hash);
+    assert_eq!(verified.guardian_set_index, 0);
+    assert_eq!(verified.signature_set, signature_set);
+}
+
+#[tokio::test]
+async fn close_posted_vaa() {
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+    let consumer = common::vaa_consumer_id();
     let fee_collector = FeeCollector::key(None, program);
-    let bridge: BridgeData = common::get_account_data(client, bridge_key).await;
-    assert_eq!(bridge.config.fee, 0);
 
-    // Check that posting a new message is free.
-    let account_balance = common::get_account_balance(client, fee_collector).await;
-    let emitter = Keypair::new();
-    let sequence = context.seq.next(emitter.pubkey().to_bytes());
-    let nonce = rand::thread_rng().gen();
-    let message = [0u8; 32].to_vec();
-    let _message_key = common::post_message(
+    let mut posted = vec![];
+    for sequence in 0..3 {
+        let (vaa, body, _body_hash) =
+            common::generate_vaa(&Keypair::new(), vec![1; 32], 0, sequence, 0, 2);
+        let signature_set =
+            common::verify_signatures(client, program, payer, body, &context.secret, 0)
+                .await
+                .unwrap();
+        common::post_vaa(client, program, payer, signature_set, vaa.clone())
+            .await
+            .unwrap();
+        let key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+            &PostedVAADerivationData {
+                payload_hash: body.to_vec(),
+            },
+            program,
+        );
+        posted.push((key, signature_set, vaa));
+    }
+    let mut claims = vec![];
+    for (key, _, _) in &posted {
+        let vaa_data: PostedVAAData = common::get_account_data(client, *key).await;
+        claims.push(Claim::key(
+            &ClaimDerivationData {
+                emitter_address: vaa_data.emitter_address,
+                emitter_chain: vaa_data.emitter_chain,
+                sequence: vaa_data.sequence,
+            },
+            &consumer,
+        ));
+    }
+    let (posted_vaa, signature_set, vaa) = posted[0].clone();
+    let (claim, other_claim) = (claims[0], claims[1]);
+
+    // Any program can create claims, only those of consumers governance allowed count.
+    assert_eq!(
+        common::consume_vaa(client, payer, posted[2].0)
+            .await
+            .unwrap(),
+        claims[2]
+    );
+    let (result, logs) = common::execute_with_logs(
+        client,
+        payer,
+        &[payer],
+        &[instructions::close_posted_vaa(*program, posted[2].0, claims[2], consumer).unwrap()],
+    )
+    .await
+    .unwrap();
+    assert!(result.is_err());
+    assert_bridge_error(&logs, Error::VAAConsumerNotAllowed);
+
+    let message = GovernancePayloadSetVAAConsumer {
+        consumer,
+        allowed: true,
+    }
+    .try_to_vec()
+    .unwrap();
+    let (message_key, sequence) = post_decree(context, client, payer, program, message, None).await;
+    let ix = instructions::set_vaa_consumer(
+        *program,
+        payer.pubkey(),
+        message_key,
+        Keypair::from_bytes(&GOVERNANCE_KEY).unwrap().pubkey(),
+        sequence,
+        consumer,
+    );
+    common::execute(client, payer, &[payer], &[ix], CommitmentLevel::Processed)
+        .await
+        .unwrap();
+
+    // Not claimed yet. Every failing close below differs in its accounts, so none of them is
+    // rejected as a duplicate of an earlier transaction instead.
+    let (result, logs) = common::execute_with_logs(
+        client,
+        payer,
+        &[payer],
+        &[instructions::close_posted_vaa(*program, posted[1].0, other_claim, consumer).unwrap()],
+    )
+    .await
+    .unwrap();
+    assert!(result.is_err());
+    assert_bridge_error(&logs, Error::VAANotClaimed);
+
+    // The claim of another VAA, or one for the wrong consumer, does not count.
+    assert_eq!(
+        common::consume_vaa(client, payer, posted[1].0)
+            .await
+            .unwrap(),
+        other_claim
+    );
+    assert!(
+        common::close_posted_vaa(client, program, payer, posted_vaa, other_claim, consumer)
+            .await
+            .is_err()
+    );
+    assert_eq!(
+        common::consume_vaa(client, payer, posted_vaa)
+            .await
+            .unwrap(),
+        claim
+    );
+    assert!(common::close_posted_vaa(
         client,
         program,
         payer,
-        &emitter,
-        None,
-        nonce,
-        message.clone(),
-        0,
+        posted_vaa,
+        claim,
+        Pubkey::new_unique()
+    )
+    .await
+    .is_err());
+
+    let rent = client
+        .get_account(posted_vaa)
+        .await
+        .unwrap()
+        .unwrap()
+        .lamports;
+    let fees = common::get_account_balance(client, fee_collector).await;
+
+    // The rent is not a fee, messages posted after the close are not paid for by it.
+    let (emitter, message) = (Keypair::new(), Keypair::new());
+    let (result, logs) = common::execute_with_logs(
+        client,
+        payer,
+        &[payer, &emitter, &message],
+        &[
+            instructions::close_posted_vaa(*program, posted_vaa, claim, consumer).unwrap(),
+            instructions::post_message(
+                *program,
+                payer.pubkey(),
+                emitter.pubkey(),
+                message.pubkey(),
+                0,
+                vec![0; 32],
+                ConsistencyLevel::Confirmed,
+            )
+            .unwrap(),
+        ],
     )
     .await
     .unwrap();
+    assert!(result.is_err());
+    assert_bridge_error(&logs, Error::InsufficientFees);
 
-    let (vaa, body, _body_hash) =
-        common::generate_vaa(&emitter, message.clone(), nonce, sequence, 0, 1);
-    let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 0)
+    let bridge_key = Bridge::<'_, { AccountState::Uninitialized }>::key(None, program);
+    let bridge: BridgeData = common::get_account_data(client, bridge_key).await;
+    common::close_posted_vaa(client, program, payer, posted_vaa, claim, consumer)
         .await
         .unwrap();
-    let vaa_time = vaa.timestamp;
-    let message_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
-        &PostedVAADerivationData {
-            payload_hash: body.to_vec(),
-        },
-        program,
+    assert!(client.get_account(posted_vaa).await.unwrap().is_none());
+    assert_eq!(
+        common::get_account_balance(client, fee_collector).await,
+        fees + rent
     );
+    let closed: BridgeData = common::get_account_data(client, bridge_key).await;
+    assert_eq!(closed.last_lamports, bridge.last_lamports + rent);
+
+    // The signature set is untouched, so the VAA can be posted again.
     common::post_vaa(client, program, payer, signature_set, vaa)
         .await
         .unwrap();
+}
 
-    // Verify that the fee collector was paid.
-    assert_eq!(
-        common::get_account_balance(client, fee_collector).await,
-        account_balance,
+#[test]
+fn posted_vaa_reader_offsets() {
+    let signature_set = Pubkey::new_unique();
+    let vaa = PostedVAAData {
+        message: MessageData {
+            vaa_version: 1,
+            consistency_level: 32,
+            vaa_time: 0x0102_0304,
+            vaa_signature_account: signature_set,
+            submission_time: 0,
+            nonce: 0x090a_0b0c,
+            sequence: 0x0d0e_0f10_1112_1314,
+            emitter_chain: 0x1516,
+            emitter_address: [0x17; 32],
+            payload: vec![0x18; 7],
+        },
+    };
+    let mut data = vaa.try_to_vec().unwrap();
+    let (key, owner, mut lamports) = (Pubkey::new_unique(), Pubkey::new_unique(), 0);
+    let info = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
     );
 
-    // And that the new message is on chain.
-    let posted_message: PostedVAAData = common::get_account_data(client, message_key).await;
-    let signatures: SignatureSetData = common::get_account_data(client, signature_set).await;
+    assert_eq!(VAA_VERSION_OFFSET, 3);
+    assert_eq!(VAA_TIME_OFFSET, 5);
+    assert_eq!(VAA_SIGNATURE_ACCOUNT_OFFSET, 9);
+
+    let reader = PostedVAAV1::load(&info).unwrap();
+    assert_eq!(reader.vaa_version(), 1);
+    assert_eq!(reader.consistency_level(), 32);
+    assert_eq!(reader.vaa_time(), 0x0102_0304);
+    assert_eq!(reader.signature_set(), signature_set);
+    assert_eq!(reader.nonce(), 0x090a_0b0c);
+    assert_eq!(reader.sequence(), 0x0d0e_0f10_1112_1314);
+    assert_eq!(reader.emitter_chain(), 0x1516);
+    assert_eq!(reader.emitter_chain_id(), ChainId::Unknown(0x1516));
+    assert_eq!(reader.emitter_address(), [0x17; 32]);
+    assert_eq!(reader.payload(), &[0x18; 7]);
+    drop(reader);
+
+    // Posted messages share the layout but are not VAAs.
+    info.data.borrow_mut()[..3].copy_from_slice(b"msg");
+    assert!(PostedVAAV1::load(&info).is_err());
+}
 
-    // Verify on chain Message
-    assert_eq!(posted_message.message.vaa_version, 0);
-    assert_eq!(
-        posted_message.message.consistency_level,
-        ConsistencyLevel::Confirmed as u8
+#[test]
+fn signature_set_reader_offsets() {
+    let set = SignatureSetData {
+        signatures: vec![true, false, true, true],
+        hash: [0x19; 32],
+        guardian_set_index: 0x1a1b_1c1d,
+    };
+    let mut data = set.try_to_vec().unwrap();
+    let (key, owner, mut lamports) = (Pubkey::new_unique(), Pubkey::new_unique(), 0);
+    let info = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
     );
-    assert_eq!(posted_message.message.vaa_time, vaa_time);
-    assert_eq!(posted_message.message.vaa_signature_account, signature_set);
-    assert_eq!(posted_message.message.nonce, nonce);
-    assert_eq!(posted_message.message.sequence, sequence);
-    assert_eq!(posted_message.message.emitter_chain, 1);
+
+    let reader = SignatureSetV1::load(&info).unwrap();
+    assert_eq!(reader.num_guardians(), 4);
     assert_eq!(
-        &posted_message.message.emitter_address,
-        emitter.pubkey().as_ref()
+        (0..5).map(|i| reader.is_signed(i)).collect::<Vec<_>>(),
+        vec![true, false, true, true, false]
     );
-    assert_eq!(posted_message.message.payload, message);
-    assert_eq!(
-        posted_message.message.emitter_address,
-        emitter.pubkey().to_bytes()
+    assert_eq!(reader.num_verified(), 3);
+    // Quorum of four guardians is three signatures.
+    assert!(reader.is_verified());
+    assert_eq!(reader.hash(), [0x19; 32]);
+    assert_eq!(reader.guardian_set_index(), 0x1a1b_1c1d);
+    drop(reader);
+
+    // Flags other than 0 and 1 are not a signature set.
+    info.data.borrow_mut()[5] = 2;
+    assert!(SignatureSetV1::load(&info).is_err());
+}
+
+#[test]
+fn bridge_reader_offsets() {
+    let bridge = BridgeData {
+        guardian_set_index: 0x0102_0304,
+        last_lamports: 0x0506_0708_090a_0b0c,
+        config: BridgeConfig {
+            guardian_set_expiration_time: 0x0d0e_0f10,
+            fee: 0x1112_1314_1516_1718,
+            fee_mint: None,
+            token_fee: 0,
+        },
+    };
+    let mut data = bridge.try_to_vec().unwrap();
+    let (key, owner, mut lamports) = (Pubkey::new_unique(), Pubkey::new_unique(), 0);
+    let info = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
     );
 
-    // Verify on chain Signatures
-    assert_eq!(signatures.hash, body);
-    assert_eq!(signatures.guardian_set_index, 0);
+    let reader = BridgeV1::load(&info).unwrap();
+    assert_eq!(reader.guardian_set_index(), 0x0102_0304);
+    assert_eq!(reader.last_lamports(), 0x0506_0708_090a_0b0c);
+    assert_eq!(reader.guardian_set_expiration_time(), 0x0d0e_0f10);
+    assert_eq!(reader.fee(), 0x1112_1314_1516_1718);
+    assert_eq!(reader.fee_mint(), None);
+    assert_eq!(reader.token_fee(), 0);
+    assert_eq!(cpi::message_fee(&info).unwrap(), 0x1112_1314_1516_1718);
+    drop(reader);
+
+    // The token fee is only stored while a fee mint is set.
+    let fee_mint = Pubkey::new_unique();
+    let mut config = bridge.config.clone();
+    config.fee_mint = Some(fee_mint);
+    config.token_fee = 0x191a_1b1c_1d1e_1f20;
+    let bridge = BridgeData { config, ..bridge };
+    let mut data = bridge.try_to_vec().unwrap();
+    assert_eq!(data.len(), BRIDGE_WITH_FEE_MINT_SIZE);
+    let info = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
 
-    for (signature, _secret_key) in signatures.signatures.iter().zip(context.secret.iter()) {
-        assert!(*signature);
-    }
+    let reader = BridgeV1::load(&info).unwrap();
+    assert_eq!(reader.fee(), 0x1112_1314_1516_1718);
+    assert_eq!(reader.fee_mint(), Some(fee_mint));
+    assert_eq!(reader.token_fee(), 0x191a_1b1c_1d1e_1f20);
+    drop(reader);
+
+    let decoded = BridgeData::try_from_slice(&info.data.borrow()).unwrap();
+    assert_eq!(decoded.config.fee_mint, Some(fee_mint));
+    assert_eq!(decoded.config.token_fee, 0x191a_1b1c_1d1e_1f20);
 }
 
 #[tokio::test]
-async fn transfer_fees() {
-    // Initialize a wormhole bridge on Solana to test with.
+async fn bridge_reader_live() {
     let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
-    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
-    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+    let (result, _logs) = apply_fee_decree(context, client, payer, program, U256::from(777)).await;
+    result.unwrap();
 
-    let nonce = rand::thread_rng().gen();
-    let message = GovernancePayloadTransferFees {
-        amount: 100u128.into(),
-        to: payer.pubkey().to_bytes(),
-    }
-    .try_to_vec()
-    .unwrap();
+    let (bridge_key, _) = find_bridge(program);
+    assert_eq!(
+        bridge_key,
+        Bridge::<'_, { AccountState::Uninitialized }>::key(None, program)
+    );
+    let bridge: BridgeData = common::get_account_data(client, bridge_key).await;
+    let mut account = client.get_account(bridge_key).await.unwrap().unwrap();
+    let mut lamports = account.lamports;
+    let info = AccountInfo::new(
+        &bridge_key,
+        false,
+        false,
+        &mut lamports,
+        &mut account.data,
+        &account.owner,
+        false,
+        0,
+    );
 
-    // Fetch accounts for chain state checking.
-    let fee_collector = FeeCollector::key(None, program);
+    let reader = BridgeV1::load(&info).unwrap();
+    assert_eq!(reader.guardian_set_index(), bridge.guardian_set_index);
+    assert_eq!(reader.last_lamports(), bridge.last_lamports);
+    assert_eq!(
+        reader.guardian_set_expiration_time(),
+        bridge.config.guardian_set_expiration_time
+    );
+    assert_eq!(reader.fee(), 777);
+}
 
-    let message_key = common::post_message(
-        client,
-        program,
-        payer,
-        &emitter,
-        None,
-        nonce,
-        message.clone(),
-        10_000,
-    )
-    .await
-    .unwrap();
+#[tokio::test]
+async fn posted_vaa_signature_set_link() {
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
 
-    let (vaa, body, _body_hash) =
-        common::generate_vaa(&emitter, message.clone(), nonce, sequence, 0, 1);
+    let (vaa, body, _body_hash) = common::generate_vaa(&Keypair::new(), vec![2; 16], 0, 0, 0, 2);
     let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 0)
         .await
         .unwrap();
@@ -1267,284 +4731,664 @@ async fn transfer_fees() {
         .await
         .unwrap();
 
-    let previous_balance = common::get_account_balance(client, fee_collector).await;
-
-    common::transfer_fees(
-        client,
+    // Follow the posted VAA to its signature set and check quorum was met for the same body.
+    let posted_vaa = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: body.to_vec(),
+        },
         program,
-        payer,
-        message_key,
-        emitter.pubkey(),
-        payer.pubkey(),
-        sequence,
-    )
-    .await
-    .unwrap();
-    assert_eq!(
-        common::get_account_balance(client, fee_collector).await,
-        previous_balance - 100
     );
+    let mut account = client.get_account(posted_vaa).await.unwrap().unwrap();
+    let mut lamports = account.lamports;
+    let info = AccountInfo::new(
+        &posted_vaa,
+        false,
+        false,
+        &mut lamports,
+        &mut account.data,
+        &account.owner,
+        false,
+        0,
+    );
+    let linked = PostedVAAV1::load(&info).unwrap().signature_set();
+    assert_eq!(linked, signature_set);
+
+    let mut account = client.get_account(linked).await.unwrap().unwrap();
+    let mut lamports = account.lamports;
+    let info = AccountInfo::new(
+        &linked,
+        false,
+        false,
+        &mut lamports,
+        &mut account.data,
+        &account.owner,
+        false,
+        0,
+    );
+    let set = SignatureSetV1::load(&info).unwrap();
+    assert_eq!(set.hash(), body);
+    assert_eq!(set.guardian_set_index(), 0);
+    assert_eq!(set.num_guardians(), context.public.len());
+    assert!(set.is_verified());
 }
 
 #[tokio::test]
-async fn transfer_fees_fails() {
-    // Initialize a wormhole bridge on Solana to test with.
+async fn post_vaa_large_guardian_set() {
     let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
 
-    // Use an invalid emitter.
-    let emitter = Keypair::new();
-    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+    // More guardians than the signer indices of `verify_signatures` can address.
+    let (public_keys, secret_keys) = common::generate_keys(25);
+    update_guardian_set(context, client, payer, program, 1, public_keys.clone()).await;
 
-    let nonce = rand::thread_rng().gen();
-    let message = GovernancePayloadTransferFees {
-        amount: 100u128.into(),
-        to: payer.pubkey().to_bytes(),
-    }
-    .try_to_vec()
+    let (vaa, body, body_hash) = common::generate_vaa(&Keypair::new(), vec![3; 16], 0, 0, 1, 2);
+    let signed = common::sign_vaa(&vaa, body_hash, &secret_keys);
+    let signature_set = Keypair::new();
+    let transactions = instructions::post_vaa_transactions(
+        *program,
+        payer.pubkey(),
+        signature_set.pubkey(),
+        &public_keys,
+        &signed,
+    )
     .unwrap();
+    let (post, verify) = transactions.split_last().unwrap();
+    for ixs in verify {
+        assert_eq!(
+            ixs[1].data[0],
+            bridge::instruction::Instruction::VerifySignaturesV2 as u8
+        );
+        common::execute(
+            client,
+            payer,
+            &[payer, &signature_set],
+            ixs,
+            CommitmentLevel::Processed,
+        )
+        .await
+        .unwrap();
+    }
 
-    // Fetch accounts for chain state checking.
-    let fee_collector = FeeCollector::key(None, program);
+    let account = client
+        .get_account(signature_set.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.data.len(), instructions::signature_set_size(25));
+    let set: SignatureSetData = common::get_account_data(client, signature_set.pubkey()).await;
+    assert_eq!(set.num_verified(), 25);
+    assert_eq!(set.guardian_set_index, 1);
 
-    let message_key = common::post_message(
+    common::execute(client, payer, &[payer], post, CommitmentLevel::Processed)
+        .await
+        .unwrap();
+    let posted: PostedVAAData = common::get_account_data(
         client,
+        PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+            &PostedVAADerivationData {
+                payload_hash: body.to_vec(),
+            },
+            program,
+        ),
+    )
+    .await;
+    assert_eq!(posted.message.payload, vec![3; 16]);
+}
+
+#[tokio::test]
+async fn close_signature_set() {
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+
+    let (vaa, body, body_hash) = common::generate_vaa(&Keypair::new(), vec![5; 8], 0, 0, 0, 2);
+    let signed = common::sign_vaa(&vaa, body_hash, &context.secret);
+    let signature_set = Keypair::new();
+    let transactions = instructions::post_vaa_transactions(
+        *program,
+        payer.pubkey(),
+        signature_set.pubkey(),
+        &context.public,
+        &signed,
+    )
+    .unwrap();
+    let (post, verify) = transactions.split_last().unwrap();
+    for ixs in verify {
+        common::execute(
+            client,
+            payer,
+            &[payer, &signature_set],
+            ixs,
+            CommitmentLevel::Processed,
+        )
+        .await
+        .unwrap();
+    }
+
+    let posted_vaa = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: body.to_vec(),
+        },
         program,
+    );
+    let recipient = Keypair::new().pubkey();
+    let close = |recipient| {
+        instructions::close_signature_set(*program, signature_set.pubkey(), posted_vaa, recipient)
+            .unwrap()
+    };
+
+    // The VAA is not posted yet, so the signatures are still needed.
+    let (result, logs) = common::execute_with_logs(
+        client,
         payer,
-        &emitter,
-        None,
-        nonce,
-        message.clone(),
-        10_000,
+        &[payer, &signature_set],
+        &[close(Keypair::new().pubkey())],
     )
     .await
     .unwrap();
+    assert!(result.is_err());
+    assert_bridge_error(&logs, Error::SignatureSetInUse);
 
-    let (vaa, body, _body_hash) =
-        common::generate_vaa(&emitter, message.clone(), nonce, sequence, 0, 1);
-    let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 0)
+    common::execute(client, payer, &[payer], post, CommitmentLevel::Processed)
         .await
         .unwrap();
-    common::post_vaa(client, program, payer, signature_set, vaa)
+    let rent = common::get_account_balance(client, signature_set.pubkey()).await;
+    common::execute(
+        client,
+        payer,
+        &[payer, &signature_set],
+        &[close(recipient)],
+        CommitmentLevel::Processed,
+    )
+    .await
+    .unwrap();
+    assert!(client
+        .get_account(signature_set.pubkey())
         .await
-        .unwrap();
+        .unwrap()
+        .is_none());
+    assert_eq!(common::get_account_balance(client, recipient).await, rent);
+}
 
-    let previous_balance = common::get_account_balance(client, fee_collector).await;
+/// Rotate to a fresh set of `count` guardians and post a VAA signed by all of them.
+async fn rotate_and_post_vaa(count: u8) {
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
 
-    assert!(common::transfer_fees(
+    let (public_keys, secret_keys) = common::generate_keys(count);
+    let (result, logs) =
+        try_update_guardian_set(context, client, payer, program, 1, public_keys.clone()).await;
+    let vaa_time = result.unwrap();
+
+    let bridge: BridgeData = common::get_account_data(
         client,
-        program,
-        payer,
-        message_key,
-        emitter.pubkey(),
-        payer.pubkey(),
-        sequence,
+        Bridge::<'_, { AccountState::Uninitialized }>::key(None, program),
     )
-    .await
-    .is_err());
+    .await;
+    assert_eq!(bridge.guardian_set_index, 1);
+    let old_set: GuardianSetData = common::get_account_data(
+        client,
+        GuardianSet::<'_, { AccountState::Uninitialized }>::key(
+            &GuardianSetDerivationData { index: 0 },
+            program,
+        ),
+    )
+    .await;
     assert_eq!(
-        common::get_account_balance(client, fee_collector).await,
-        previous_balance
+        old_set.expiration_time,
+        vaa_time + bridge.config.guardian_set_expiration_time
     );
+
+    let events: Vec<UpgradeGuardianSetEvent> = logs
+        .iter()
+        .filter_map(|log| log.strip_prefix("Program data: "))
+        .map(|data| UpgradeGuardianSetEvent::decode(&base64::decode(data).unwrap()).unwrap())
+        .collect();
+    assert_eq!(
+        events,
+        vec![UpgradeGuardianSetEvent {
+            old_guardian_set_index: 0,
+            new_guardian_set_index: 1,
+            num_guardians: count as u32,
+            old_guardian_set_expiration_time: old_set.expiration_time,
+        }]
+    );
+
+    // Both events encode to 16 bytes, only the discriminator keeps them apart.
+    let data = logs
+        .iter()
+        .find_map(|log| log.strip_prefix("Program data: "))
+        .map(|data| base64::decode(data).unwrap())
+        .unwrap();
+    assert_eq!(data.len(), 8 + 16);
+    assert_eq!(data[..8], UpgradeGuardianSetEvent::discriminator());
+    assert_ne!(
+        UpgradeGuardianSetEvent::discriminator(),
+        SetFeesEvent::discriminator()
+    );
+    assert_eq!(SetFeesEvent::decode(&data), None);
+    let new_set_key = GuardianSet::<'_, { AccountState::Uninitialized }>::key(
+        &GuardianSetDerivationData { index: 1 },
+        program,
+    );
+    let new_set: GuardianSetData = common::get_account_data(client, new_set_key).await;
+    assert_eq!(new_set.index, 1);
+    assert_eq!(new_set.keys, public_keys);
+    assert_eq!(new_set.expiration_time, 0);
+    let account = client.get_account(new_set_key).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), 4 + 4 + 20 * count as usize + 4 + 4);
+
+    let (vaa, body, body_hash) = common::generate_vaa(&Keypair::new(), vec![6; 16], 0, 0, 1, 2);
+    let signed = common::sign_vaa(&vaa, body_hash, &secret_keys);
+    let signature_set = Keypair::new();
+    let transactions = instructions::post_vaa_transactions(
+        *program,
+        payer.pubkey(),
+        signature_set.pubkey(),
+        &public_keys,
+        &signed,
+    )
+    .unwrap();
+    let (post, verify) = transactions.split_last().unwrap();
+    for ixs in verify {
+        common::execute(
+            client,
+            payer,
+            &[payer, &signature_set],
+            ixs,
+            CommitmentLevel::Processed,
+        )
+        .await
+        .unwrap();
+    }
+    common::execute(client, payer, &[payer], post, CommitmentLevel::Processed)
+        .await
+        .unwrap();
+
+    let posted: PostedVAAData = common::get_account_data(
+        client,
+        PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+            &PostedVAADerivationData {
+                payload_hash: body.to_vec(),
+            },
+            program,
+        ),
+    )
+    .await;
+    assert_eq!(posted.payload, vec![6; 16]);
+}
+
+#[tokio::test]
+async fn upgrade_guardian_set_single_guardian() {
+    rotate_and_post_vaa(1).await;
+}
+
+#[tokio::test]
+async fn upgrade_guardian_set_19_guardians() {
+    rotate_and_post_vaa(19).await;
+}
+
+#[tokio::test]
+async fn upgrade_guardian_set_30_guardians() {
+    rotate_and_post_vaa(30).await;
+}
+
+#[tokio::test]
+async fn upgrade_guardian_set_invalid_keys() {
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+
+    let (public_keys, _) = common::generate_keys(4);
+    let duplicate = {
+        let mut keys = public_keys.clone();
+        keys[3] = keys[1];
+        keys
+    };
+    let zero = {
+        let mut keys = public_keys.clone();
+        keys[2] = [0; 20];
+        keys
+    };
+    for keys in [duplicate, zero, vec![]] {
+        let (result, logs) =
+            try_update_guardian_set(context, client, payer, program, 1, keys).await;
+        assert!(result.is_err());
+        assert_bridge_error(&logs, Error::InvalidGuardianSetUpgrade);
+    }
+
+    let bridge: BridgeData = common::get_account_data(
+        client,
+        Bridge::<'_, { AccountState::Uninitialized }>::key(None, program),
+    )
+    .await;
+    assert_eq!(bridge.guardian_set_index, 0);
+
+    // The rejected decrees left nothing behind, so a valid one can still take index 1.
+    update_guardian_set(context, client, payer, program, 1, public_keys).await;
 }
 
-#[tokio::test]
-async fn transfer_too_much() {
-    // Initialize a wormhole bridge on Solana to test with.
-    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
-    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
-    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+#[test]
+fn governance_vaa_validation() {
+    let governance = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
+    let decree = |module: &str, action: u8, chain: u16| {
+        let mut payload = governance_module(module).to_vec();
+        payload.push(action);
+        payload.extend_from_slice(&chain.to_be_bytes());
+        payload.extend_from_slice(&[7; 4]);
+        MessageData {
+            emitter_chain: 1,
+            emitter_address: governance.pubkey().to_bytes(),
+            payload,
+            ..Default::default()
+        }
+    };
+    let check = |message: &MessageData| {
+        require_valid_governance_vaa(message, governance_module("Example"), 2).map(|header| {
+            assert_eq!(header.module, governance_module("Example"));
+            assert_eq!(header.action, 2);
+            header.chain
+        })
+    };
 
-    let nonce = rand::thread_rng().gen();
-    let message = GovernancePayloadTransferFees {
-        amount: 100_000_000_000u64.into(),
-        to: payer.pubkey().to_bytes(),
-    }
-    .try_to_vec()
-    .unwrap();
+    // Decrees may target Solana or every chain.
+    assert_eq!(check(&decree("Example", 2, 1)).unwrap(), 1);
+    assert_eq!(check(&decree("Example", 2, 0)).unwrap(), 0);
+    assert_eq!(
+        require_valid_governance_vaa(&decree("Example", 2, 1), governance_module("Example"), 2)
+            .unwrap(),
+        GovernanceHeader {
+            module: governance_module("Example"),
+            action: 2,
+            chain: 1,
+            body: &[7; 4],
+        }
+    );
 
-    // Fetch accounts for chain state checking.
-    let fee_collector = FeeCollector::key(None, program);
+    let mut wrong_emitter = decree("Example", 2, 1);
+    wrong_emitter.emitter_address = Keypair::new().pubkey().to_bytes();
+    let mut wrong_emitter_chain = decree("Example", 2, 1);
+    wrong_emitter_chain.emitter_chain = 2;
+    let mut truncated = decree("Example", 2, 1);
+    truncated.payload.truncate(34);
+    for (message, error) in [
+        (decree("Core", 2, 1), Error::InvalidGovernanceModule),
+        (decree("Example", 3, 1), Error::InvalidGovernanceAction),
+        (decree("Example", 2, 2), Error::InvalidGovernanceChain),
+        (wrong_emitter, Error::InvalidGovernanceKey),
+        (wrong_emitter_chain, Error::InvalidGovernanceKey),
+        (truncated, Error::InvalidPayloadLength),
+    ] {
+        assert!(matches!(
+            check(&message),
+            Err(SolitaireError::Custom(code)) if code == error as u64
+        ));
+    }
+}
 
+/// Apply a set-fee-exemption decree for `emitter`.
+async fn apply_fee_exemption_decree(
+    context: &mut Context,
+    client: &mut BanksClient,
+    payer: &Keypair,
+    program: &Pubkey,
+    emitter: Pubkey,
+    exempt: bool,
+) {
+    let governance = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
+    let sequence = context.seq.next(governance.pubkey().to_bytes());
+    let message = GovernancePayloadSetFeeExemption { emitter, exempt }
+        .try_to_vec()
+        .unwrap();
     let message_key = common::post_message(
         client,
         program,
         payer,
-        &emitter,
+        &governance,
         None,
-        nonce,
-        message.clone(),
-        10_000,
+        rand::thread_rng().gen(),
+        message,
     )
     .await
     .unwrap();
 
-    let (vaa, body, _body_hash) =
-        common::generate_vaa(&emitter, message.clone(), nonce, sequence, 0, 1);
-    let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 0)
-        .await
-        .unwrap();
-    common::post_vaa(client, program, payer, signature_set, vaa)
+    let ix = instructions::set_fee_exemption(
+        *program,
+        payer.pubkey(),
+        message_key,
+        governance.pubkey(),
+        sequence,
+        emitter,
+    );
+    common::execute(client, payer, &[payer], &[ix], CommitmentLevel::Processed)
         .await
         .unwrap();
+}
 
-    let previous_balance = common::get_account_balance(client, fee_collector).await;
-
-    // Should fail to transfer.
-    assert!(common::transfer_fees(
+/// Post a message from `emitter` paid for by `poster`, passing the fee exemption of
+/// `exemption_of`.
+async fn post_with_fee_exemption(
+    client: &mut BanksClient,
+    program: &Pubkey,
+    payer: &Keypair,
+    poster: &Keypair,
+    emitter: &Keypair,
+    exemption_of: Pubkey,
+) -> Result<(), BanksClientError> {
+    let message = Keypair::new();
+    let ix = instructions::post_message(
+        *program,
+        poster.pubkey(),
+        emitter.pubkey(),
+        message.pubkey(),
+        0,
+        vec![0; 8],
+        ConsistencyLevel::Confirmed,
+    )
+    .unwrap();
+    let ix = instructions::with_fee_exemption(ix, exemption_of);
+    common::execute(
         client,
-        program,
         payer,
-        message_key,
-        emitter.pubkey(),
-        payer.pubkey(),
-        sequence,
+        &[payer, poster, emitter, &message],
+        &[ix],
+        CommitmentLevel::Processed,
     )
     .await
-    .is_err());
-    assert_eq!(
-        common::get_account_balance(client, fee_collector).await,
-        previous_balance
-    );
 }
 
 #[tokio::test]
-async fn foreign_bridge_messages() {
-    // Initialize a wormhole bridge on Solana to test with.
+async fn fee_exemption() {
     let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
-    let nonce = rand::thread_rng().gen();
-    let message = [0u8; 32].to_vec();
-    let emitter = Keypair::new();
-    let sequence = context.seq.next(emitter.pubkey().to_bytes());
-
-    // Verify the VAA generated on a foreign chain.
-    let (vaa, body, _body_hash) =
-        common::generate_vaa(&emitter, message.clone(), nonce, sequence, 0, 2);
+    let fee_collector = FeeCollector::key(None, program);
+    let exempt = Keypair::new();
+    let other = Keypair::new();
 
-    // Derive where we expect created accounts to be.
-    let message_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
-        &PostedVAADerivationData {
-            payload_hash: body.to_vec(),
+    apply_fee_exemption_decree(context, client, payer, program, exempt.pubkey(), true).await;
+    let exemption_key = FeeExemption::<'_, { AccountState::Initialized }>::key(
+        &FeeExemptionDerivationData {
+            emitter: exempt.pubkey(),
         },
         program,
     );
+    let exemption: FeeExemptionData = common::get_account_data(client, exemption_key).await;
+    assert!(exemption.exempt);
 
-    let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 0)
-        .await
-        .unwrap();
-    common::post_vaa(client, program, payer, signature_set, vaa)
+    // Raise the fee beyond what the poster holds, so that only exempt emitters get messages out.
+    let (result, _logs) = apply_fee_decree(
+        context,
+        client,
+        payer,
+        program,
+        U256::from(2 * LAMPORTS_PER_SOL),
+    )
+    .await;
+    result.unwrap();
+    let poster = Keypair::new();
+    common::execute(
+        client,
+        payer,
+        &[payer],
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            &poster.pubkey(),
+            LAMPORTS_PER_SOL,
+        )],
+        CommitmentLevel::Processed,
+    )
+    .await
+    .unwrap();
+
+    // The exempt emitter posts without paying anything.
+    let balance = common::get_account_balance(client, fee_collector).await;
+    post_with_fee_exemption(client, program, payer, &poster, &exempt, exempt.pubkey())
         .await
         .unwrap();
-
-    // Fetch chain accounts to verify state.
-    let posted_message: PostedVAAData = common::get_account_data(client, message_key).await;
-    let signatures: SignatureSetData = common::get_account_data(client, signature_set).await;
-
-    assert_eq!(posted_message.message.vaa_version, 0);
-    assert_eq!(posted_message.message.vaa_signature_account, signature_set);
-    assert_eq!(posted_message.message.nonce, nonce);
-    assert_eq!(posted_message.message.sequence, sequence);
-    assert_eq!(posted_message.message.emitter_chain, 2);
-    assert_eq!(posted_message.message.payload, message);
     assert_eq!(
-        posted_message.message.emitter_address,
-        emitter.pubkey().to_bytes()
+        common::get_account_balance(client, fee_collector).await,
+        balance
     );
 
-    // Verify on chain Signatures
-    assert_eq!(signatures.hash, body);
-    assert_eq!(signatures.guardian_set_index, 0);
+    // Other emitters still pay, whether they pass a missing exemption or that of another emitter.
+    assert!(
+        post_with_fee_exemption(client, program, payer, &poster, &other, other.pubkey())
+            .await
+            .is_err()
+    );
+    assert!(
+        post_with_fee_exemption(client, program, payer, &poster, &other, exempt.pubkey())
+            .await
+            .is_err()
+    );
 
-    for (signature, _secret_key) in signatures.signatures.iter().zip(context.secret.iter()) {
-        assert!(*signature);
-    }
+    // Once revoked, the exemption no longer waives the fee. Only the revoking decree itself paid.
+    apply_fee_exemption_decree(context, client, payer, program, exempt.pubkey(), false).await;
+    let exemption: FeeExemptionData = common::get_account_data(client, exemption_key).await;
+    assert!(!exemption.exempt);
+    assert!(
+        post_with_fee_exemption(client, program, payer, &poster, &exempt, exempt.pubkey())
+            .await
+            .is_err()
+    );
+    assert_eq!(
+        common::get_account_balance(client, fee_collector).await,
+        balance + 2 * LAMPORTS_PER_SOL
+    );
 }
 
 #[tokio::test]
-async fn transfer_total_fails() {
-    // Initialize a wormhole bridge on Solana to test with.
+async fn fee_exemption_cpi() {
     let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
-    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
-    let sequence = context.seq.next(emitter.pubkey().to_bytes());
-
-    // Be sure any previous tests have fully committed.
-
     let fee_collector = FeeCollector::key(None, program);
-    let account_balance = common::get_account_balance(client, fee_collector).await;
-
-    // Prepare to remove total balance, adding 10_000 to include the fee we're about to pay.
-    let nonce = rand::thread_rng().gen();
-    let message = GovernancePayloadTransferFees {
-        amount: (account_balance + 10_000).into(),
-        to: payer.pubkey().to_bytes(),
-    }
-    .try_to_vec()
-    .unwrap();
+    let (emitter, _) =
+        Pubkey::find_program_address(&[common::EMITTER_SEED], &common::message_poster_id());
+    apply_fee_exemption_decree(context, client, payer, program, emitter, true).await;
+    let exemption_key = FeeExemption::<'_, { AccountState::Initialized }>::key(
+        &FeeExemptionDerivationData { emitter },
+        program,
+    );
 
-    let message_key = common::post_message(
+    // The integrator hands the exemption of its emitter to the bridge, which waives the fee.
+    let balance = common::get_account_balance(client, fee_collector).await;
+    common::post_message_cpi(
         client,
         program,
         payer,
-        &emitter,
-        None,
-        nonce,
-        message.clone(),
-        10_000,
+        payer,
+        &Keypair::new(),
+        0,
+        vec![0; 8],
+        Some(exemption_key),
     )
     .await
     .unwrap();
+    assert_eq!(
+        common::get_account_balance(client, fee_collector).await,
+        balance
+    );
 
-    let (vaa, body, _body_hash) =
-        common::generate_vaa(&emitter, message.clone(), nonce, sequence, 0, 1);
-    let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 0)
-        .await
-        .unwrap();
-    common::post_vaa(client, program, payer, signature_set, vaa)
-        .await
-        .unwrap();
-
-    // Transferring total fees should fail, to prevent the account being de-allocated.
-    assert!(common::transfer_fees(
+    // Without it the fee is charged as usual.
+    common::post_message_cpi(
         client,
         program,
         payer,
-        message_key,
-        emitter.pubkey(),
-        payer.pubkey(),
-        sequence,
+        payer,
+        &Keypair::new(),
+        1,
+        vec![0; 8],
+        None,
     )
     .await
-    .is_err());
-
-    // The fee should have been paid, but other than that the balance should be exactly the same,
-    // I.E non-zero.
+    .unwrap();
     assert_eq!(
         common::get_account_balance(client, fee_collector).await,
-        account_balance + 10_000
+        balance + 500
     );
 }
 
-// `solana-program-test` doesn't use an upgradeable loader so it's not currently possible to test
-// the contract upgrade logic this way. See https://github.com/solana-labs/solana/issues/22950 for
-// more details. This test is here mainly as a reference in case the issue above gets fixed, at
-// which point this test can be re-enabled.
-#[tokio::test]
-#[ignore]
-async fn upgrade_contract() {
-    // Initialize a wormhole bridge on Solana to test with.
-    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+#[test]
+fn fee_accounts_order() {
+    let program = Pubkey::new_unique();
+    let (payer, emitter, message) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let (mint, payer_tokens) = (Pubkey::new_unique(), Pubkey::new_unique());
 
-    // New Contract Address
-    // let new_contract = Pubkey::new_unique();
-    let new_contract = *program;
+    let post = instructions::post_message(
+        program,
+        payer,
+        emitter,
+        message,
+        0,
+        vec![0; 8],
+        ConsistencyLevel::Confirmed,
+    )
+    .unwrap();
+    let program_emitter = instructions::post_message_program_emitter(
+        program,
+        payer,
+        emitter,
+        message,
+        0,
+        vec![0; 8],
+        ConsistencyLevel::Confirmed,
+    )
+    .unwrap();
+
+    // The exemption follows the accounts of the instruction and the token fee accounts come last,
+    // whichever is added first.
+    for (ix, index) in [
+        (post, POST_MESSAGE_ACCOUNTS),
+        (program_emitter, POST_MESSAGE_ACCOUNTS + 1),
+    ] {
+        assert_eq!(ix.accounts.len(), index);
+        let exemption_first = instructions::with_token_fee(
+            instructions::with_fee_exemption(ix.clone(), emitter),
+            mint,
+            payer_tokens,
+        );
+        let token_fee_first = instructions::with_fee_exemption(
+            instructions::with_token_fee(ix, mint, payer_tokens),
+            emitter,
+        );
+        assert_eq!(exemption_first.accounts, token_fee_first.accounts);
+        assert_eq!(
+            exemption_first.accounts[index].pubkey,
+            FeeExemption::<'_, { AccountState::Initialized }>::key(
+                &FeeExemptionDerivationData { emitter },
+                &program,
+            )
+        );
+        assert_eq!(exemption_first.accounts[index + 1].pubkey, payer_tokens);
+        assert_eq!(exemption_first.accounts.len(), index + 4);
+    }
+}
 
+#[tokio::test]
+async fn pda_helpers_live() {
+    let (ref mut context, ref mut client, ref payer, ref program) = initialize().await;
+    let emitter = Keypair::new();
     let nonce = rand::thread_rng().gen();
-    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
-    let sequence = context.seq.next(emitter.pubkey().to_bytes());
-    let message = GovernancePayloadUpgrade { new_contract }
-        .try_to_vec()
-        .unwrap();
+    let message = b"pda".to_vec();
 
-    let message_key = common::post_message(
+    let sequence = context.seq.next(emitter.pubkey().to_bytes());
+    common::post_message(
         client,
         program,
         payer,
@@ -1552,29 +5396,37 @@ async fn upgrade_contract() {
         None,
         nonce,
         message.clone(),
-        10_000,
     )
     .await
     .unwrap();
 
-    let (vaa, body, _body_hash) =
-        common::generate_vaa(&emitter, message.clone(), nonce, sequence, 0, 1);
+    let (vaa, body, _) = common::generate_vaa(&emitter, message.clone(), nonce, sequence, 0, 1);
     let signature_set = common::verify_signatures(client, program, payer, body, &context.secret, 0)
         .await
         .unwrap();
     common::post_vaa(client, program, payer, signature_set, vaa)
         .await
         .unwrap();
-    common::upgrade_contract(
-        client,
-        program,
-        payer,
-        message_key,
-        emitter.pubkey(),
-        new_contract,
-        Pubkey::new_unique(),
-        sequence,
-    )
-    .await
-    .unwrap();
+
+    let (bridge_key, _) = find_bridge(program);
+    let bridge: BridgeData = common::get_account_data(client, bridge_key).await;
+    assert_eq!(bridge.guardian_set_index, 0);
+
+    let (guardian_set_key, _) = find_guardian_set(program, 0);
+    let guardian_set: GuardianSetData = common::get_account_data(client, guardian_set_key).await;
+    assert_eq!(guardian_set.index, 0);
+    assert_eq!(guardian_set.keys, context.public);
+
+    let (fee_collector, _) = find_fee_collector(program);
+    assert!(common::get_account_balance(client, fee_collector).await > 0);
+
+    let (sequence_key, _) = find_emitter_sequence(program, &emitter.pubkey());
+    let tracker: SequenceTracker = common::get_account_data(client, sequence_key).await;
+    assert_eq!(tracker.sequence, sequence + 1);
+
+    let (posted_vaa_key, _) = find_posted_vaa(program, &body);
+    let posted: PostedVAAData = common::get_account_data(client, posted_vaa_key).await;
+    assert_eq!(posted.message.sequence, sequence);
+    assert_eq!(posted.message.emitter_address, emitter.pubkey().to_bytes());
+    assert_eq!(posted.message.payload, message);
 }
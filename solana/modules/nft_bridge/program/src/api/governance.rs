@@ -18,6 +18,7 @@ use bridge::{
         self,
         Claim,
     },
+    vaa::require_governance_emitter,
     DeserializePayload,
     PayloadMessage,
     CHAIN_ID_SOLANA,
@@ -25,7 +26,6 @@ use bridge::{
 use solana_program::{
     account_info::AccountInfo,
     program::invoke_signed,
-    pubkey::Pubkey,
     sysvar::{
         clock::Clock,
         rent::Rent,
@@ -42,14 +42,8 @@ fn verify_governance<T>(vaa: &PayloadMessage<T>) -> Result<()>
 where
     T: DeserializePayload,
 {
-    let expected_emitter = std::env!("EMITTER_ADDRESS");
-    let current_emitter = format!("{}", Pubkey::new_from_array(vaa.meta().emitter_address));
-    // Fail if the emitter is not the known governance key, or the emitting chain is not Solana.
-    if expected_emitter != current_emitter || vaa.meta().emitter_chain != CHAIN_ID_SOLANA {
-        Err(InvalidGovernanceKey.into())
-    } else {
-        Ok(())
-    }
+    // Keep reporting the error code of this program rather than the one of the core bridge.
+    require_governance_emitter(vaa.meta()).map_err(|_| InvalidGovernanceKey.into())
 }
 
 #[derive(FromAccounts)]
@@ -140,8 +134,9 @@ pub fn register_chain(
     _data: RegisterChainData,
 ) -> Result<()> {
     let derivation_data: EndpointDerivationData = (&*accs).into();
-    accs.endpoint
-        .verify_derivation(ctx.program_id, &derivation_data)?;
+    let bump = accs
+        .endpoint
+        .verify_derivation_bump(ctx.program_id, &derivation_data)?;
 
     // Claim VAA
     verify_governance(&accs.vaa)?;
@@ -153,7 +148,7 @@ pub fn register_chain(
 
     // Create endpoint
     accs.endpoint
-        .create(&((&*accs).into()), ctx, accs.payer.key, Exempt)?;
+        .create_with_bump(&derivation_data, bump, ctx, accs.payer.key, Exempt)?;
 
     accs.endpoint.chain = accs.vaa.chain;
     accs.endpoint.contract = accs.vaa.endpoint_address;
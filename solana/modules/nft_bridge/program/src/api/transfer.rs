@@ -32,10 +32,7 @@ use solana_program::{
         AccountMeta,
         Instruction,
     },
-    program::{
-        invoke,
-        invoke_signed,
-    },
+    program::invoke_signed,
     program_option::COption,
     sysvar::clock::Clock,
 };
@@ -116,8 +113,9 @@ pub fn transfer_native(
 ) -> Result<()> {
     // Verify that the custody account is derived correctly
     let derivation_data: CustodyAccountDerivationData = (&*accs).into();
-    accs.custody
-        .verify_derivation(ctx.program_id, &derivation_data)?;
+    let bump = accs
+        .custody
+        .verify_derivation_bump(ctx.program_id, &derivation_data)?;
 
     // Verify mints
     if accs.from.mint != *accs.mint.info().key {
@@ -133,7 +131,7 @@ pub fn transfer_native(
 
     if !accs.custody.is_initialized() {
         accs.custody
-            .create(&(&*accs).into(), ctx, accs.payer.key, Exempt)?;
+            .create_with_bump(&derivation_data, bump, ctx, accs.payer.key, Exempt)?;
 
         let init_ix = spl_token::instruction::initialize_account(
             &spl_token::id(),
@@ -155,14 +153,6 @@ pub fn transfer_native(
     )?;
     invoke_seeded(&transfer_ix, ctx, &accs.authority_signer, None)?;
 
-    // Pay fee
-    let transfer_ix = solana_program::system_instruction::transfer(
-        accs.payer.key,
-        accs.fee_collector.key,
-        accs.bridge.config.fee,
-    );
-    invoke(&transfer_ix, ctx.accounts)?;
-
     let metadata = deserialize_and_verify_metadata(&accs.spl_metadata, (&*accs).into())?;
 
     // Post message
@@ -180,7 +170,7 @@ pub fn transfer_native(
         token_id: U256::from_big_endian(&accs.mint.info().key.to_bytes()),
     };
     let params = (
-        bridge::instruction::Instruction::PostMessage,
+        bridge::instruction::Instruction::PostMessageV2,
         PostMessageData {
             nonce: data.nonce,
             payload: payload.try_to_vec()?,
@@ -203,6 +193,7 @@ pub fn transfer_native(
             AccountMeta::new_readonly(solana_program::sysvar::rent::ID, false),
         ],
     );
+    let ix = bridge::cpi::forward_token_fee(ix, &accs.bridge, ctx.accounts)?;
     invoke_seeded(&ix, ctx, &accs.emitter, None)?;
 
     Ok(())
@@ -294,15 +285,6 @@ pub fn transfer_wrapped(
     )?;
     invoke_seeded(&burn_ix, ctx, &accs.authority_signer, None)?;
 
-    // Pay fee
-    let transfer_ix = solana_program::system_instruction::transfer(
-        accs.payer.key,
-        accs.fee_collector.key,
-        accs.bridge.config.fee,
-    );
-
-    invoke(&transfer_ix, ctx.accounts)?;
-
     // Enfoce wrapped meta to be uninitialized.
     let derivation_data: WrappedMetaDerivationData = (&*accs).into();
     accs.wrapped_meta
@@ -322,7 +304,7 @@ pub fn transfer_wrapped(
         uri: metadata.data.uri,
     };
     let params = (
-        bridge::instruction::Instruction::PostMessage,
+        bridge::instruction::Instruction::PostMessageV2,
         PostMessageData {
             nonce: data.nonce,
             payload: payload.try_to_vec()?,
@@ -345,6 +327,7 @@ pub fn transfer_wrapped(
             AccountMeta::new_readonly(solana_program::sysvar::rent::ID, false),
         ],
     );
+    let ix = bridge::cpi::forward_token_fee(ix, &accs.bridge, ctx.accounts)?;
     invoke_seeded(&ix, ctx, &accs.emitter, None)?;
 
     Ok(())
@@ -313,6 +313,9 @@ fn claimable_vaa(
     )
 }
 
+/// While the core bridge charges message fees in a fee mint, append
+/// `bridge::instructions::token_fee_accounts(bridge_id, fee_mint, payer_token_account)` for the
+/// payer to pay the fee with.
 pub fn transfer_native(
     program_id: Pubkey,
     bridge_id: Pubkey,
@@ -376,6 +379,7 @@ pub fn transfer_native(
     })
 }
 
+/// Takes the token fee accounts of the core bridge last, like `transfer_native`.
 #[allow(clippy::too_many_arguments)]
 pub fn transfer_wrapped(
     program_id: Pubkey,
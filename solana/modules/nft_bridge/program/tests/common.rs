@@ -12,7 +12,6 @@ use solana_program::{
     instruction::Instruction,
     program_pack::Pack,
     pubkey::Pubkey,
-    system_instruction,
 };
 use solana_program_test::{
     BanksClient,
@@ -66,7 +65,6 @@ pub async fn execute<T: Signers>(
 mod helpers {
     use super::*;
     use bridge::{
-        accounts::FeeCollector,
         types::ConsistencyLevel,
         PostVAAData,
     };
@@ -682,13 +680,9 @@ mod helpers {
         message: &Keypair,
         nonce: u32,
         data: Vec<u8>,
-        fee: u64,
     ) -> Result<(), BanksClientError> {
-        // Transfer money into the fee collector as it needs a balance/must exist.
-        let fee_collector = FeeCollector::<'_>::key(None, &program);
-
         // Capture the resulting message, later functions will need this.
-        let instruction = bridge::instructions::post_message(
+        let instruction = bridge::instructions::post_message_v2(
             program,
             payer.pubkey(),
             emitter.pubkey(),
@@ -703,10 +697,7 @@ mod helpers {
             client,
             payer,
             &[payer, emitter, message],
-            &[
-                system_instruction::transfer(&payer.pubkey(), &fee_collector, fee),
-                instruction,
-            ],
+            &[instruction],
             CommitmentLevel::Processed,
         )
         .await
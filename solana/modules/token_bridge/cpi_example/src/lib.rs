@@ -0,0 +1,73 @@
+//! Example program calling into both bridges. It exists so that the workspace build checks that
+//! the program crates link as libraries under their `cpi` feature, without a second entrypoint.
+//!
+//! It sends native tokens with a payload, signing as its own sender PDA. Its instruction data is
+//! the `TransferNativeWithPayloadData` to send, its accounts are those of
+//! `token_bridge::instructions::transfer_native_with_payload` followed by the token bridge itself.
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use token_bridge::{
+    accounts::{
+        find_sender,
+        SENDER_SEED,
+    },
+    cpi,
+    TransferNativeWithPayloadData,
+};
+
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
+
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let mut data = TransferNativeWithPayloadData::try_from_slice(data)?;
+    data.cpi_program_id = Some(*program_id);
+    let (_, bump) = find_sender(program_id);
+
+    let a = accounts;
+    if a.len() < 19 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    // The bridge takes the message fee from the payer, fail early rather than within the CPI.
+    if a[0].lamports() < bridge::cpi::message_fee(&a[7])? {
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    cpi::transfer_native_with_payload(
+        cpi::TransferNative {
+            payer: &a[0],
+            config: &a[1],
+            from: &a[2],
+            mint: &a[3],
+            custody: &a[4],
+            authority_signer: &a[5],
+            custody_signer: &a[6],
+            bridge_config: &a[7],
+            message: &a[8],
+            emitter: &a[9],
+            sequence: &a[10],
+            fee_collector: &a[11],
+            clock: &a[12],
+            rent: &a[14],
+            system_program: &a[15],
+            core_bridge_program: &a[16],
+            token_program: &a[17],
+            token_bridge_program: &a[18],
+            token_fee: None,
+        },
+        &a[13],
+        data,
+        &[&[SENDER_SEED.as_bytes(), &[bump]]],
+    )?;
+    Ok(())
+}
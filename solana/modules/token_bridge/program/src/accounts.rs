@@ -3,28 +3,52 @@ use crate::{
     TokenBridgeError,
 };
 use bridge::{
-    accounts::BridgeData,
+    accounts::{
+        find_bridge,
+        find_emitter_sequence,
+        find_fee_collector,
+        BridgeData,
+        PostedVAAV1,
+    },
     api::ForeignAddress,
 };
-use solana_program::pubkey::Pubkey;
+use solana_program::{
+    account_info::AccountInfo,
+    pubkey::Pubkey,
+};
 use solitaire::{
     processors::seeded::Seeded,
     *,
 };
 use spl_token_metadata::state::Key::MetadataV1;
 
-pub type AuthoritySigner<'b> = Derive<Info<'b>, "authority_signer">;
-pub type CustodySigner<'b> = Derive<Info<'b>, "custody_signer">;
-pub type MintSigner<'b> = Derive<Info<'b>, "mint_signer">;
+/// Seed of the PDA delegated to move tokens out of a sender's account.
+pub const AUTHORITY_SIGNER_SEED: &str = "authority_signer";
+/// Seed of the PDA owning all custody accounts.
+pub const CUSTODY_SIGNER_SEED: &str = "custody_signer";
+/// Seed of the PDA holding the mint authority of every wrapped mint.
+pub const MINT_SIGNER_SEED: &str = "mint_signer";
+/// Seed of the PDA the token bridge posts its messages as.
+pub const EMITTER_SEED: &str = "emitter";
+/// Seed of the PDA a program signs as when it sends a transfer with payload.
+pub const SENDER_SEED: &str = "sender";
+/// Seed of the PDA a program signs as when it redeems a transfer with payload.
+pub const REDEEMER_SEED: &str = "redeemer";
+
+pub type AuthoritySigner<'b> = Derive<Info<'b>, { AUTHORITY_SIGNER_SEED }>;
+pub type CustodySigner<'b> = Derive<Info<'b>, { CUSTODY_SIGNER_SEED }>;
+pub type MintSigner<'b> = Derive<Info<'b>, { MINT_SIGNER_SEED }>;
 
 pub type CoreBridge<'a, const STATE: AccountState> = Data<'a, BridgeData, { STATE }>;
 
-pub type EmitterAccount<'b> = Derive<Info<'b>, "emitter">;
+pub type EmitterAccount<'b> = Derive<Info<'b>, { EMITTER_SEED }>;
 
+// The config, wrapped assets and registrations are all PDAs, so they are loaded tolerating the
+// trailing bytes some of them were written with historically.
 pub type ConfigAccount<'b, const STATE: AccountState> =
-    Derive<Data<'b, Config, { STATE }>, "config">;
+    Derive<Data<'b, Legacy<Config>, { STATE }>, "config">;
 
-pub type CustodyAccount<'b, const STATE: AccountState> = Data<'b, SplAccount, { STATE }>;
+pub type CustodyAccount<'b, const STATE: AccountState> = Data<'b, SplInterfaceAccount, { STATE }>;
 
 pub struct CustodyAccountDerivationData {
     pub mint: Pubkey,
@@ -55,7 +79,7 @@ impl<'b, const STATE: AccountState> Seeded<&WrappedDerivationData> for WrappedMi
     }
 }
 
-pub type WrappedTokenMeta<'b, const STATE: AccountState> = Data<'b, WrappedMeta, { STATE }>;
+pub type WrappedTokenMeta<'b, const STATE: AccountState> = Data<'b, Legacy<WrappedMeta>, { STATE }>;
 
 pub struct WrappedMetaDerivationData {
     pub mint_key: Pubkey,
@@ -73,7 +97,8 @@ impl<'b, const STATE: AccountState> Seeded<&WrappedMetaDerivationData>
 }
 
 /// Registered chain endpoint
-pub type Endpoint<'b, const STATE: AccountState> = Data<'b, EndpointRegistration, { STATE }>;
+pub type Endpoint<'b, const STATE: AccountState> =
+    Data<'b, Legacy<EndpointRegistration>, { STATE }>;
 
 pub struct EndpointDerivationData {
     pub emitter_chain: u16,
@@ -90,6 +115,179 @@ impl<'b, const STATE: AccountState> Seeded<&EndpointDerivationData> for Endpoint
     }
 }
 
+/// Derive the registered emitter (endpoint) account for a foreign chain, along with its bump.
+/// Relayers can use this to find the `chain_registration` account of a redemption without
+/// knowing the seed layout.
+pub fn find_registered_emitter(
+    program_id: &Pubkey,
+    emitter_chain: u16,
+    emitter_address: &ForeignAddress,
+) -> (Pubkey, u8) {
+    let seeds = Endpoint::<'_, { AccountState::Initialized }>::seeds(&EndpointDerivationData {
+        emitter_chain,
+        emitter_address: *emitter_address,
+    });
+    let seeds: Vec<&[u8]> = seeds.iter().map(|item| item.as_slice()).collect();
+    Pubkey::find_program_address(seeds.as_slice(), program_id)
+}
+
+/// Derive the custody account holding native tokens of `mint`, along with its bump.
+pub fn find_custody_token(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[mint.as_ref()], program_id)
+}
+
+/// Derive the owner of all custody accounts, along with its bump.
+pub fn find_custody_authority(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CUSTODY_SIGNER_SEED.as_bytes()], program_id)
+}
+
+/// Derive the mint authority of wrapped tokens, along with its bump.
+pub fn find_mint_authority(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[MINT_SIGNER_SEED.as_bytes()], program_id)
+}
+
+/// Derive the account senders approve as delegate before a transfer, along with its bump.
+pub fn find_transfer_authority(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[AUTHORITY_SIGNER_SEED.as_bytes()], program_id)
+}
+
+/// Derive the emitter of the token bridge's messages, along with its bump.
+pub fn find_emitter(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[EMITTER_SEED.as_bytes()], program_id)
+}
+
+/// Derive the sender PDA of the calling program `cpi_program_id`, along with its bump.
+pub fn find_sender(cpi_program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SENDER_SEED.as_bytes()], cpi_program_id)
+}
+
+/// Derive the redeemer PDA of the recipient program `cpi_program_id`, along with its bump.
+pub fn find_redeemer(cpi_program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REDEEMER_SEED.as_bytes()], cpi_program_id)
+}
+
+/// The accounts of both bridges taking part in moving `mint` in or out of Solana, so clients and
+/// CPI callers do not have to derive them one by one. `T` holds the accounts specific to the kind
+/// of the mint, either [`NativeAccounts`] or [`WrappedAccounts`]. Resolving allocates nothing, so it
+/// is as cheap on-chain as deriving the accounts by hand.
+pub struct TokenBridgeAccounts<T> {
+    pub config: Pubkey,
+    pub mint: Pubkey,
+    pub transfer_authority: Pubkey,
+    pub emitter: Pubkey,
+    pub bridge_config: Pubkey,
+    pub fee_collector: Pubkey,
+    pub sequence: Pubkey,
+    pub asset: T,
+}
+
+/// Accounts holding and releasing a Solana native mint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NativeAccounts {
+    pub custody_token: Pubkey,
+    pub custody_authority: Pubkey,
+}
+
+/// Accounts describing and minting a wrapped mint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WrappedAccounts {
+    pub wrapped_meta: Pubkey,
+    pub mint_authority: Pubkey,
+}
+
+impl<T> TokenBridgeAccounts<T> {
+    fn new(program_id: &Pubkey, bridge_id: &Pubkey, mint: &Pubkey, asset: T) -> Self {
+        let (emitter, _) = find_emitter(program_id);
+        TokenBridgeAccounts {
+            config: Pubkey::find_program_address(&[b"config"], program_id).0,
+            mint: *mint,
+            transfer_authority: find_transfer_authority(program_id).0,
+            emitter,
+            bridge_config: find_bridge(bridge_id).0,
+            fee_collector: find_fee_collector(bridge_id).0,
+            sequence: find_emitter_sequence(bridge_id, &emitter).0,
+            asset,
+        }
+    }
+}
+
+impl TokenBridgeAccounts<NativeAccounts> {
+    pub fn for_native(program_id: &Pubkey, bridge_id: &Pubkey, mint: &Pubkey) -> Self {
+        let asset = NativeAccounts {
+            custody_token: find_custody_token(program_id, mint).0,
+            custody_authority: find_custody_authority(program_id).0,
+        };
+        TokenBridgeAccounts::new(program_id, bridge_id, mint, asset)
+    }
+}
+
+impl TokenBridgeAccounts<WrappedAccounts> {
+    /// `wrapped_mint` is the mint derived from the origin of the token, see
+    /// [`WrappedDerivationData`].
+    pub fn for_wrapped(program_id: &Pubkey, bridge_id: &Pubkey, wrapped_mint: &Pubkey) -> Self {
+        let asset = WrappedAccounts {
+            wrapped_meta: Pubkey::find_program_address(
+                &[b"meta", wrapped_mint.as_ref()],
+                program_id,
+            )
+            .0,
+            mint_authority: find_mint_authority(program_id).0,
+        };
+        TokenBridgeAccounts::new(program_id, bridge_id, wrapped_mint, asset)
+    }
+}
+
+/// Fail unless `endpoint` is the token bridge registration for the given emitter and has not been
+/// deregistered. This is the same check the token bridge performs on redemption, exposed for
+/// programs consuming token bridge VAAs themselves.
+pub fn require_registered_emitter(
+    program_id: &Pubkey,
+    endpoint: &Endpoint<'_, { AccountState::Initialized }>,
+    emitter_chain: u16,
+    emitter_address: &ForeignAddress,
+) -> Result<()> {
+    endpoint.verify_derivation(
+        program_id,
+        &EndpointDerivationData {
+            emitter_chain,
+            emitter_address: *emitter_address,
+        },
+    )?;
+    if endpoint.deregistered {
+        return Err(TokenBridgeError::EmitterDeregistered.into());
+    }
+    Ok(())
+}
+
+/// Fail unless `vaa` was emitted by a token bridge registered with the token bridge `program_id`,
+/// `registered_emitter` being the registration account of its emitter. This is the check of
+/// `require_registered_emitter` for programs reading token bridge VAAs from raw accounts, the
+/// owner of the VAA account itself is left to the caller.
+pub fn require_from_registered_token_bridge(
+    program_id: &Pubkey,
+    vaa: &PostedVAAV1,
+    registered_emitter: &AccountInfo,
+) -> Result<()> {
+    let (expected, _) =
+        find_registered_emitter(program_id, vaa.emitter_chain(), &vaa.emitter_address());
+    if *registered_emitter.key != expected {
+        return Err(SolitaireError::InvalidDerive(
+            *registered_emitter.key,
+            expected,
+        ));
+    }
+    if registered_emitter.owner != program_id {
+        return Err(SolitaireError::InvalidOwner(*registered_emitter.owner));
+    }
+
+    let registration: EndpointRegistration = legacy_deserialize(&registered_emitter.data.borrow())?;
+    if registration.deregistered {
+        return Err(TokenBridgeError::EmitterDeregistered.into());
+    }
+    registration.emitter().verify(vaa)?;
+    Ok(())
+}
+
 pub type SplTokenMeta<'b> = Info<'b>;
 
 pub struct SplTokenMetaDerivationData {
@@ -146,3 +344,123 @@ pub fn deserialize_and_verify_metadata(
         _ => Err(TokenBridgeError::InvalidMetadata.into()),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bridge::{
+        MessageData,
+        PostedVAAData,
+    };
+
+    const EMITTER_CHAIN: u16 = 2;
+    const EMITTER_ADDRESS: ForeignAddress = [7u8; 32];
+
+    fn posted_vaa() -> Vec<u8> {
+        PostedVAAData {
+            message: MessageData {
+                emitter_chain: EMITTER_CHAIN,
+                emitter_address: EMITTER_ADDRESS,
+                payload: vec![1, 2, 3],
+                ..Default::default()
+            },
+        }
+        .try_to_vec()
+        .unwrap()
+    }
+
+    fn registration(chain: u16, contract: ForeignAddress) -> Vec<u8> {
+        EndpointRegistration {
+            chain,
+            contract,
+            deregistered: false,
+        }
+        .try_to_vec()
+        .unwrap()
+    }
+
+    /// Run the check against a registration account at `key`, owned by the token bridge.
+    fn check(program_id: &Pubkey, key: Pubkey, mut registration: Vec<u8>) -> Result<()> {
+        let vaa_key = Pubkey::new_unique();
+        let mut vaa_lamports = 0;
+        let mut vaa_data = posted_vaa();
+        let vaa_info = AccountInfo::new(
+            &vaa_key,
+            false,
+            false,
+            &mut vaa_lamports,
+            &mut vaa_data,
+            program_id,
+            false,
+            0,
+        );
+        let vaa = PostedVAAV1::load(&vaa_info)?;
+
+        let mut lamports = 0;
+        let info = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut registration,
+            program_id,
+            false,
+            0,
+        );
+        require_from_registered_token_bridge(program_id, &vaa, &info)
+    }
+
+    #[test]
+    fn test_registered_token_bridge() {
+        let program_id = Pubkey::new_unique();
+        let (key, _) = find_registered_emitter(&program_id, EMITTER_CHAIN, &EMITTER_ADDRESS);
+        assert!(check(
+            &program_id,
+            key,
+            registration(EMITTER_CHAIN, EMITTER_ADDRESS)
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_wrong_chain_registration() {
+        let program_id = Pubkey::new_unique();
+        let (key, _) = find_registered_emitter(&program_id, 3, &EMITTER_ADDRESS);
+        assert!(matches!(
+            check(&program_id, key, registration(3, EMITTER_ADDRESS)),
+            Err(SolitaireError::InvalidDerive(..))
+        ));
+    }
+
+    #[test]
+    fn test_spoofed_registration() {
+        let program_id = Pubkey::new_unique();
+        assert!(matches!(
+            check(
+                &program_id,
+                Pubkey::new_unique(),
+                registration(EMITTER_CHAIN, EMITTER_ADDRESS)
+            ),
+            Err(SolitaireError::InvalidDerive(..))
+        ));
+    }
+
+    #[test]
+    fn test_registration_for_other_emitter() {
+        let program_id = Pubkey::new_unique();
+        let (key, _) = find_registered_emitter(&program_id, EMITTER_CHAIN, &EMITTER_ADDRESS);
+        assert!(matches!(
+            check(&program_id, key, registration(EMITTER_CHAIN, [8u8; 32])),
+            Err(SolitaireError::Custom(code)) if code == bridge::error::Error::InvalidEmitter as u64
+        ));
+    }
+
+    #[test]
+    fn test_registration_with_trailing_bytes() {
+        let program_id = Pubkey::new_unique();
+        let (key, _) = find_registered_emitter(&program_id, EMITTER_CHAIN, &EMITTER_ADDRESS);
+        let mut padded = registration(EMITTER_CHAIN, EMITTER_ADDRESS);
+        padded.extend_from_slice(&[0u8; 8]);
+        assert!(check(&program_id, key, padded).is_ok());
+    }
+}
@@ -4,6 +4,7 @@ pub mod complete_transfer_payload;
 pub mod create_wrapped;
 pub mod governance;
 pub mod initialize;
+pub mod query;
 pub mod transfer;
 pub mod transfer_payload;
 
@@ -13,5 +14,6 @@ pub use complete_transfer_payload::*;
 pub use create_wrapped::*;
 pub use governance::*;
 pub use initialize::*;
+pub use query::*;
 pub use transfer::*;
 pub use transfer_payload::*;
@@ -9,8 +9,11 @@ use crate::{
         WrappedMetaDerivationData,
         WrappedTokenMeta,
     },
+    api::truncate_utf8,
     messages::PayloadAssetMeta,
+    token,
     types::*,
+    TokenBridgeError::OutboundTransfersPaused,
 };
 use bridge::{
     api::PostMessageData,
@@ -24,7 +27,6 @@ use solana_program::{
         AccountMeta,
         Instruction,
     },
-    program::invoke,
     sysvar::clock::Clock,
 };
 use solitaire::{
@@ -41,8 +43,8 @@ pub struct AttestToken<'b> {
 
     pub config: Mut<ConfigAccount<'b, { AccountState::Initialized }>>,
 
-    /// Mint to attest
-    pub mint: Data<'b, SplMint, { AccountState::Initialized }>,
+    /// Mint to attest, of either token program
+    pub mint: Data<'b, SplInterfaceMint, { AccountState::Initialized }>,
     pub wrapped_meta: WrappedTokenMeta<'b, { AccountState::Uninitialized }>,
 
     /// SPL Metadata for the associated Mint
@@ -92,14 +94,13 @@ pub fn attest_token(
     accs: &mut AttestToken,
     data: AttestTokenData,
 ) -> Result<()> {
-    // Pay fee
-    let transfer_ix = solana_program::system_instruction::transfer(
-        accs.payer.key,
-        accs.fee_collector.key,
-        accs.bridge.config.fee,
-    );
+    if accs.config.paused {
+        return Err(OutboundTransfersPaused.into());
+    }
 
-    invoke(&transfer_ix, ctx.accounts)?;
+    // Only mints the bridge can take into custody are attested.
+    token::token_program_of(accs.mint.info())?;
+    token::assert_mint_bridgeable(accs.mint.info())?;
 
     // Enfoce wrapped meta to be uninitialized.
     let derivation_data: WrappedMetaDerivationData = (&*accs).into();
@@ -115,15 +116,21 @@ pub fn attest_token(
         name: "".to_string(),
     };
 
-    // Assign metadata if an SPL Metadata account exists for the SPL token in question.
-    if !accs.spl_metadata.data_is_empty() {
+    // Assign Token-2022 metadata if the mint has any, truncated as its names and symbols are not
+    // limited like those of SPL Metadata. Otherwise assign metadata if an SPL Metadata account
+    // exists for the SPL token in question. The account a metadata pointer designates follows the
+    // accounts of the instruction.
+    if let Some(metadata) = token::token_metadata(accs.mint.info(), ctx.accounts)? {
+        payload.name = truncate_utf8(&metadata.name, 32);
+        payload.symbol = truncate_utf8(&metadata.symbol, 32);
+    } else if !accs.spl_metadata.data_is_empty() {
         let metadata = deserialize_and_verify_metadata(&accs.spl_metadata, (&*accs).into())?;
         payload.name = metadata.data.name.clone();
         payload.symbol = metadata.data.symbol;
     }
 
     let params = (
-        bridge::instruction::Instruction::PostMessage,
+        bridge::instruction::Instruction::PostMessageV2,
         PostMessageData {
             nonce: data.nonce,
             payload: payload.try_to_vec()?,
@@ -146,6 +153,7 @@ pub fn attest_token(
             AccountMeta::new_readonly(solana_program::sysvar::rent::ID, false),
         ],
     );
+    let ix = bridge::cpi::forward_token_fee(ix, &accs.bridge, ctx.accounts)?;
     invoke_seeded(&ix, ctx, &accs.emitter, None)?;
 
     Ok(())
@@ -9,10 +9,14 @@ use crate::{
         MintSigner,
         WrappedDerivationData,
         WrappedMetaDerivationData,
-        WrappedMint,
         WrappedTokenMeta,
     },
+    events::{
+        self,
+        CompleteNativeEvent,
+    },
     messages::PayloadTransfer,
+    token,
     types::*,
     TokenBridgeError::*,
     INVALID_VAAS,
@@ -25,14 +29,18 @@ use bridge::{
     PayloadMessage,
     CHAIN_ID_SOLANA,
 };
-use solana_program::account_info::AccountInfo;
-use solitaire::{
-    processors::seeded::{
-        invoke_seeded,
-        Seeded,
+use solana_program::{
+    account_info::AccountInfo,
+    sysvar::{
+        clock::Clock,
+        Sysvar as _,
     },
+};
+use solitaire::{
+    processors::seeded::Seeded,
     *,
 };
+use std::convert::TryFrom;
 
 #[derive(FromAccounts)]
 pub struct CompleteNative<'b> {
@@ -43,10 +51,10 @@ pub struct CompleteNative<'b> {
     pub claim: Mut<Claim<'b>>,
     pub chain_registration: Endpoint<'b, { AccountState::Initialized }>,
 
-    pub to: Mut<Data<'b, SplAccount, { AccountState::Initialized }>>,
-    pub to_fees: Mut<Data<'b, SplAccount, { AccountState::Initialized }>>,
+    pub to: Mut<Data<'b, SplInterfaceAccount, { AccountState::Initialized }>>,
+    pub to_fees: Mut<Data<'b, SplInterfaceAccount, { AccountState::Initialized }>>,
     pub custody: Mut<CustodyAccount<'b, { AccountState::Initialized }>>,
-    pub mint: Data<'b, SplMint, { AccountState::Initialized }>,
+    pub mint: Data<'b, SplInterfaceMint, { AccountState::Initialized }>,
 
     pub custody_signer: CustodySigner<'b>,
 }
@@ -80,13 +88,21 @@ pub fn complete_native(
     let derivation_data: EndpointDerivationData = (&*accs).into();
     accs.chain_registration
         .verify_derivation(ctx.program_id, &derivation_data)?;
+    if accs.chain_registration.deregistered {
+        return Err(EmitterDeregistered.into());
+    }
 
     // Verify that the custody account is derived correctly
     let derivation_data: CustodyAccountDerivationData = (&*accs).into();
     accs.custody
         .verify_derivation(ctx.program_id, &derivation_data)?;
 
-    // Verify mints
+    // Verify mints, the token accounts must belong to the token program of the mint
+    let token_program = token::token_program_of(accs.mint.info())?;
+    token::require_token_program(
+        &token_program,
+        &[accs.to.info(), accs.to_fees.info(), accs.custody.info()],
+    )?;
     if *accs.mint.info().key != accs.to.mint {
         return Err(InvalidMint.into());
     }
@@ -120,40 +136,53 @@ pub fn complete_native(
     // Prevent vaa double signing
     claim::consume(ctx, accs.payer.key, &mut accs.claim, &accs.vaa)?;
 
-    let mut amount = accs.vaa.amount.as_u64();
-    let mut fee = accs.vaa.fee.as_u64();
-
     // Wormhole always caps transfers at 8 decimals; un-truncate if the local token has more
-    if accs.mint.decimals > 8 {
-        amount *= 10u64.pow((accs.mint.decimals - 8) as u32);
-        fee *= 10u64.pow((accs.mint.decimals - 8) as u32);
-    }
+    let amount = NormalizedAmount::try_from(accs.vaa.amount)?.denormalize(accs.mint.decimals)?;
+    let fee = NormalizedAmount::try_from(accs.vaa.fee)?.denormalize(accs.mint.decimals)?;
 
+    // Token-2022 transfer fees are charged to the recipient, the relayer is sent enough for its
+    // fee to arrive in full.
+    let epoch = Clock::get()?.epoch;
+    let fee_sent = token::amount_before_fee(accs.mint.info(), epoch, fee)?;
     let token_amount = amount
-        .checked_sub(fee)
+        .checked_sub(fee_sent)
         .ok_or(SolitaireError::InsufficientFunds)?;
 
     // Transfer tokens
-    let transfer_ix = spl_token::instruction::transfer(
-        &spl_token::id(),
+    token::transfer_checked(
+        ctx,
+        &token_program,
         accs.custody.info().key,
         accs.to.info().key,
-        accs.custody_signer.key,
-        &[],
+        accs.mint.info().key,
+        &accs.custody_signer,
+        None,
         token_amount,
+        accs.mint.decimals,
     )?;
-    invoke_seeded(&transfer_ix, ctx, &accs.custody_signer, None)?;
 
     // Transfer fees
-    let transfer_ix = spl_token::instruction::transfer(
-        &spl_token::id(),
+    token::transfer_checked(
+        ctx,
+        &token_program,
         accs.custody.info().key,
         accs.to_fees.info().key,
-        accs.custody_signer.key,
-        &[],
-        fee,
+        accs.mint.info().key,
+        &accs.custody_signer,
+        None,
+        fee_sent,
+        accs.mint.decimals,
     )?;
-    invoke_seeded(&transfer_ix, ctx, &accs.custody_signer, None)?;
+
+    events::emit(&CompleteNativeEvent {
+        mint: *accs.mint.info().key,
+        to: *accs.to.info().key,
+        to_fees: *accs.to_fees.info().key,
+        amount_sent: token_amount,
+        amount_received: token_amount - token::transfer_fee(accs.mint.info(), epoch, token_amount)?,
+        fee_sent,
+        fee_received: fee_sent - token::transfer_fee(accs.mint.info(), epoch, fee_sent)?,
+    })?;
 
     Ok(())
 }
@@ -169,9 +198,11 @@ pub struct CompleteWrapped<'b> {
 
     pub chain_registration: Endpoint<'b, { AccountState::Initialized }>,
 
-    pub to: Mut<Data<'b, SplAccount, { AccountState::Initialized }>>,
-    pub to_fees: Mut<Data<'b, SplAccount, { AccountState::Initialized }>>,
-    pub mint: Mut<WrappedMint<'b, { AccountState::Initialized }>>,
+    pub to: Mut<Data<'b, SplInterfaceAccount, { AccountState::Initialized }>>,
+    pub to_fees: Mut<Data<'b, SplInterfaceAccount, { AccountState::Initialized }>>,
+    /// Wrapped mint of the transfer, of either token program. Its derivation is proven by
+    /// `wrapped_meta`.
+    pub mint: Mut<Data<'b, SplInterfaceMint, { AccountState::Initialized }>>,
     pub wrapped_meta: WrappedTokenMeta<'b, { AccountState::Initialized }>,
 
     pub mint_authority: MintSigner<'b>,
@@ -207,6 +238,9 @@ pub fn complete_wrapped(
     let derivation_data: EndpointDerivationData = (&*accs).into();
     accs.chain_registration
         .verify_derivation(ctx.program_id, &derivation_data)?;
+    if accs.chain_registration.deregistered {
+        return Err(EmitterDeregistered.into());
+    }
 
     // Verify mint
     accs.wrapped_meta.verify_derivation(
@@ -221,13 +255,15 @@ pub fn complete_wrapped(
         return Err(InvalidMint.into());
     }
 
-    // Verify mints
+    // Verify mints, the recipients must belong to the token program of the mint
     if *accs.mint.info().key != accs.to.mint {
         return Err(InvalidMint.into());
     }
     if *accs.mint.info().key != accs.to_fees.mint {
         return Err(InvalidMint.into());
     }
+    let token_program = token::token_program_of(accs.mint.info())?;
+    token::require_token_program(&token_program, &[accs.to.info(), accs.to_fees.info()])?;
 
     // Verify VAA
     if accs.vaa.to_chain != CHAIN_ID_SOLANA {
@@ -242,34 +278,37 @@ pub fn complete_wrapped(
 
     claim::consume(ctx, accs.payer.key, &mut accs.claim, &accs.vaa)?;
 
-    let token_amount: u64 = accs
-        .vaa
-        .amount
-        .as_u64()
-        .checked_sub(accs.vaa.fee.as_u64())
+    // Wrapped mints have at most 8 decimals, amounts are minted as they are.
+    let decimals = accs.mint.decimals;
+    let amount = NormalizedAmount::try_from(accs.vaa.amount)?.denormalize(decimals)?;
+    let fee = NormalizedAmount::try_from(accs.vaa.fee)?.denormalize(decimals)?;
+    let token_amount = amount
+        .checked_sub(fee)
         .ok_or(SolitaireError::InsufficientFunds)?;
 
     // Mint tokens
-    let mint_ix = spl_token::instruction::mint_to(
-        &spl_token::id(),
+    token::mint_to_checked(
+        ctx,
+        &token_program,
         accs.mint.info().key,
         accs.to.info().key,
-        accs.mint_authority.key,
-        &[],
+        &accs.mint_authority,
+        None,
         token_amount,
+        decimals,
     )?;
-    invoke_seeded(&mint_ix, ctx, &accs.mint_authority, None)?;
 
     // Mint fees
-    let mint_ix = spl_token::instruction::mint_to(
-        &spl_token::id(),
+    token::mint_to_checked(
+        ctx,
+        &token_program,
         accs.mint.info().key,
         accs.to_fees.info().key,
-        accs.mint_authority.key,
-        &[],
-        accs.vaa.fee.as_u64(),
+        &accs.mint_authority,
+        None,
+        fee,
+        decimals,
     )?;
-    invoke_seeded(&mint_ix, ctx, &accs.mint_authority, None)?;
 
     Ok(())
 }
@@ -9,10 +9,15 @@ use crate::{
         MintSigner,
         WrappedDerivationData,
         WrappedMetaDerivationData,
-        WrappedMint,
         WrappedTokenMeta,
+        REDEEMER_SEED,
+    },
+    events::{
+        self,
+        CompleteNativeEvent,
     },
     messages::PayloadTransferWithPayload,
+    token,
     types::*,
     TokenBridgeError::*,
 };
@@ -24,14 +29,18 @@ use bridge::{
     PayloadMessage,
     CHAIN_ID_SOLANA,
 };
-use solana_program::account_info::AccountInfo;
-use solitaire::{
-    processors::seeded::{
-        invoke_seeded,
-        Seeded,
+use solana_program::{
+    account_info::AccountInfo,
+    sysvar::{
+        clock::Clock,
+        Sysvar as _,
     },
+};
+use solitaire::{
+    processors::seeded::Seeded,
     *,
 };
+use std::convert::TryFrom;
 
 use solana_program::pubkey::Pubkey;
 
@@ -58,7 +67,7 @@ impl<'a, 'b: 'a> Peel<'a, 'b> for RedeemerAccount<'b> {
 // [`Seeded`] directly.
 impl<'b> Seeded<()> for RedeemerAccount<'b> {
     fn seeds(_accs: ()) -> Vec<Vec<u8>> {
-        vec![String::from("redeemer").as_bytes().to_vec()]
+        vec![REDEEMER_SEED.as_bytes().to_vec()]
     }
 }
 
@@ -102,13 +111,13 @@ pub struct CompleteNativeWithPayload<'b> {
     pub claim: Mut<Claim<'b>>,
     pub chain_registration: Endpoint<'b, { AccountState::Initialized }>,
 
-    pub to: Mut<Data<'b, SplAccount, { AccountState::Initialized }>>,
+    pub to: Mut<Data<'b, SplInterfaceAccount, { AccountState::Initialized }>>,
 
     /// See [`verify_recipient_address`]
     pub redeemer: RedeemerAccount<'b>,
-    pub to_fees: Mut<Data<'b, SplAccount, { AccountState::Initialized }>>,
+    pub to_fees: Mut<Data<'b, SplInterfaceAccount, { AccountState::Initialized }>>,
     pub custody: Mut<CustodyAccount<'b, { AccountState::Initialized }>>,
-    pub mint: Data<'b, SplMint, { AccountState::Initialized }>,
+    pub mint: Data<'b, SplInterfaceMint, { AccountState::Initialized }>,
 
     pub custody_signer: CustodySigner<'b>,
 }
@@ -142,13 +151,21 @@ pub fn complete_native_with_payload(
     let derivation_data: EndpointDerivationData = (&*accs).into();
     accs.chain_registration
         .verify_derivation(ctx.program_id, &derivation_data)?;
+    if accs.chain_registration.deregistered {
+        return Err(EmitterDeregistered.into());
+    }
 
     // Verify that the custody account is derived correctly
     let derivation_data: CustodyAccountDerivationData = (&*accs).into();
     accs.custody
         .verify_derivation(ctx.program_id, &derivation_data)?;
 
-    // Verify mints
+    // Verify mints, the token accounts must belong to the token program of the mint
+    let token_program = token::token_program_of(accs.mint.info())?;
+    token::require_token_program(
+        &token_program,
+        &[accs.to.info(), accs.to_fees.info(), accs.custody.info()],
+    )?;
     if *accs.mint.info().key != accs.to.mint {
         return Err(InvalidMint.into());
     }
@@ -186,23 +203,32 @@ pub fn complete_native_with_payload(
     // Prevent vaa double signing
     claim::consume(ctx, accs.payer.key, &mut accs.claim, &accs.vaa)?;
 
-    let mut amount = accs.vaa.amount.as_u64();
-
     // Wormhole always caps transfers at 8 decimals; un-truncate if the local token has more
-    if accs.mint.decimals > 8 {
-        amount *= 10u64.pow((accs.mint.decimals - 8) as u32);
-    }
+    let amount = NormalizedAmount::try_from(accs.vaa.amount)?.denormalize(accs.mint.decimals)?;
 
-    // Transfer tokens
-    let transfer_ix = spl_token::instruction::transfer(
-        &spl_token::id(),
+    // Transfer tokens, payloads carry no relayer fee
+    token::transfer_checked(
+        ctx,
+        &token_program,
         accs.custody.info().key,
         accs.to.info().key,
-        accs.custody_signer.key,
-        &[],
+        accs.mint.info().key,
+        &accs.custody_signer,
+        None,
         amount,
+        accs.mint.decimals,
     )?;
-    invoke_seeded(&transfer_ix, ctx, &accs.custody_signer, None)?;
+
+    let epoch = Clock::get()?.epoch;
+    events::emit(&CompleteNativeEvent {
+        mint: *accs.mint.info().key,
+        to: *accs.to.info().key,
+        to_fees: *accs.to_fees.info().key,
+        amount_sent: amount,
+        amount_received: amount - token::transfer_fee(accs.mint.info(), epoch, amount)?,
+        fee_sent: 0,
+        fee_received: 0,
+    })?;
 
     Ok(())
 }
@@ -218,12 +244,14 @@ pub struct CompleteWrappedWithPayload<'b> {
 
     pub chain_registration: Endpoint<'b, { AccountState::Initialized }>,
 
-    pub to: Mut<Data<'b, SplAccount, { AccountState::Initialized }>>,
+    pub to: Mut<Data<'b, SplInterfaceAccount, { AccountState::Initialized }>>,
 
     /// See [`verify_recipient_address`]
     pub redeemer: RedeemerAccount<'b>,
-    pub to_fees: Mut<Data<'b, SplAccount, { AccountState::Initialized }>>,
-    pub mint: Mut<WrappedMint<'b, { AccountState::Initialized }>>,
+    pub to_fees: Mut<Data<'b, SplInterfaceAccount, { AccountState::Initialized }>>,
+    /// Wrapped mint of the transfer, of either token program. Its derivation is proven by
+    /// `wrapped_meta`.
+    pub mint: Mut<Data<'b, SplInterfaceMint, { AccountState::Initialized }>>,
     pub wrapped_meta: WrappedTokenMeta<'b, { AccountState::Initialized }>,
 
     pub mint_authority: MintSigner<'b>,
@@ -259,6 +287,9 @@ pub fn complete_wrapped_with_payload(
     let derivation_data: EndpointDerivationData = (&*accs).into();
     accs.chain_registration
         .verify_derivation(ctx.program_id, &derivation_data)?;
+    if accs.chain_registration.deregistered {
+        return Err(EmitterDeregistered.into());
+    }
 
     // Verify mint
     accs.wrapped_meta.verify_derivation(
@@ -273,13 +304,15 @@ pub fn complete_wrapped_with_payload(
         return Err(InvalidMint.into());
     }
 
-    // Verify mints
+    // Verify mints, the recipients must belong to the token program of the mint
     if *accs.mint.info().key != accs.to.mint {
         return Err(InvalidMint.into());
     }
     if *accs.mint.info().key != accs.to_fees.mint {
         return Err(InvalidMint.into());
     }
+    let token_program = token::token_program_of(accs.mint.info())?;
+    token::require_token_program(&token_program, &[accs.to.info(), accs.to_fees.info()])?;
 
     // Verify VAA
     if accs.vaa.to_chain != CHAIN_ID_SOLANA {
@@ -298,16 +331,21 @@ pub fn complete_wrapped_with_payload(
 
     claim::consume(ctx, accs.payer.key, &mut accs.claim, &accs.vaa)?;
 
+    // Wrapped mints have at most 8 decimals, amounts are minted as they are.
+    let decimals = accs.mint.decimals;
+    let amount = NormalizedAmount::try_from(accs.vaa.amount)?.denormalize(decimals)?;
+
     // Mint tokens
-    let mint_ix = spl_token::instruction::mint_to(
-        &spl_token::id(),
+    token::mint_to_checked(
+        ctx,
+        &token_program,
         accs.mint.info().key,
         accs.to.info().key,
-        accs.mint_authority.key,
-        &[],
-        accs.vaa.amount.as_u64(),
+        &accs.mint_authority,
+        None,
+        amount,
+        decimals,
     )?;
-    invoke_seeded(&mint_ix, ctx, &accs.mint_authority, None)?;
 
     Ok(())
 }
@@ -14,6 +14,7 @@ use crate::{
     },
     messages::PayloadAssetMeta,
     TokenBridgeError::{
+        EmitterDeregistered,
         InvalidChain,
         InvalidVAA,
     },
@@ -111,6 +112,9 @@ pub fn create_wrapped(
     let derivation_data: EndpointDerivationData = (&*accs).into();
     accs.chain_registration
         .verify_derivation(ctx.program_id, &derivation_data)?;
+    if accs.chain_registration.deregistered {
+        return Err(EmitterDeregistered.into());
+    }
 
     if INVALID_VAAS.contains(&&*accs.vaa.info().key.to_string()) {
         return Err(InvalidVAA.into());
@@ -202,6 +206,11 @@ pub fn update_accounts(
         },
     )?;
 
+    // Governance may have handed the metadata to another update authority, which now curates it.
+    if metadata.update_authority != *accs.mint_authority.info().key {
+        return Ok(());
+    }
+
     // Normalize token metadata's name and symbol.
     let new_data_v2 = spl_token_metadata::state::DataV2 {
         name: truncate_utf8(&accs.vaa.name, 32 - 11) + " (Wormhole)",
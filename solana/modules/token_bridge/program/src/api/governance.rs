@@ -1,15 +1,33 @@
 use crate::{
     accounts::{
+        deserialize_and_verify_metadata,
         ConfigAccount,
         Endpoint,
         EndpointDerivationData,
+        MintSigner,
+        SplTokenMeta,
+        SplTokenMetaDerivationData,
+        WrappedMetaDerivationData,
+        WrappedTokenMeta,
+    },
+    events::{
+        self,
+        DeregisterChainEvent,
+        RegisterChainEvent,
     },
     messages::{
+        GovernancePayloadDeregisterChain,
+        GovernancePayloadSetMetadataAuthority,
+        GovernancePayloadSetPaused,
         GovernancePayloadUpgrade,
         PayloadGovernanceRegisterChain,
     },
     TokenBridgeError::{
+        EmitterChainIsSolana,
+        InvalidEmitterAddress,
+        InvalidEmitterChain,
         InvalidGovernanceKey,
+        InvalidUpgradeBuffer,
         InvalidVAA,
     },
     INVALID_VAAS,
@@ -19,6 +37,7 @@ use bridge::{
         self,
         Claim,
     },
+    vaa::require_governance_emitter,
     DeserializePayload,
     PayloadMessage,
     CHAIN_ID_SOLANA,
@@ -26,14 +45,17 @@ use bridge::{
 use solana_program::{
     account_info::AccountInfo,
     program::invoke_signed,
-    pubkey::Pubkey,
     sysvar::{
         clock::Clock,
         rent::Rent,
     },
 };
 use solitaire::{
-    processors::seeded::Seeded,
+    processors::seeded::{
+        invoke_seeded,
+        AccountSize,
+        Seeded,
+    },
     CreationLamports::Exempt,
     *,
 };
@@ -43,14 +65,8 @@ fn verify_governance<T>(vaa: &PayloadMessage<T>) -> Result<()>
 where
     T: DeserializePayload,
 {
-    let expected_emitter = std::env!("EMITTER_ADDRESS");
-    let current_emitter = format!("{}", Pubkey::new_from_array(vaa.meta().emitter_address));
-    // Fail if the emitter is not the known governance key, or the emitting chain is not Solana.
-    if expected_emitter != current_emitter || vaa.meta().emitter_chain != CHAIN_ID_SOLANA {
-        Err(InvalidGovernanceKey.into())
-    } else {
-        Ok(())
-    }
+    // Keep reporting the error code of this program rather than the one of the core bridge.
+    require_governance_emitter(vaa.meta()).map_err(|_| InvalidGovernanceKey.into())
 }
 
 #[derive(FromAccounts)]
@@ -61,6 +77,8 @@ pub struct UpgradeContract<'b> {
     /// GuardianSet change VAA
     pub vaa: PayloadMessage<'b, GovernancePayloadUpgrade>,
     pub claim: Mut<Claim<'b>>,
+    /// Unprefixed claim of the decree, see `claim_vaa`
+    pub legacy_claim: Info<'b>,
 
     /// PDA authority for the loader
     pub upgrade_authority: Derive<Info<'b>, "upgrade">,
@@ -97,7 +115,20 @@ pub fn upgrade_contract(
     }
 
     verify_governance(&accs.vaa)?;
-    claim::consume(ctx, accs.payer.key, &mut accs.claim, &accs.vaa)?;
+    claim::claim_vaa(
+        ctx,
+        accs.payer.key,
+        &mut accs.claim,
+        &accs.legacy_claim,
+        &accs.vaa,
+    )?;
+
+    // Only the buffer the guardians decreed may be deployed, and only the loader can deploy it.
+    if *accs.buffer.key != accs.vaa.new_contract
+        || *accs.buffer.owner != solana_program::bpf_loader_upgradeable::id()
+    {
+        return Err(InvalidUpgradeBuffer.into());
+    }
 
     let upgrade_ix = solana_program::bpf_loader_upgradeable::upgrade(
         ctx.program_id,
@@ -121,10 +152,13 @@ pub struct RegisterChain<'b> {
     pub payer: Mut<Signer<AccountInfo<'b>>>,
     pub config: ConfigAccount<'b, { AccountState::Initialized }>,
 
-    pub endpoint: Mut<Endpoint<'b, { AccountState::Uninitialized }>>,
+    /// Created, or enabled again if it was deregistered
+    pub endpoint: Mut<Endpoint<'b, { AccountState::MaybeInitialized }>>,
 
     pub vaa: PayloadMessage<'b, PayloadGovernanceRegisterChain>,
     pub claim: Mut<Claim<'b>>,
+    /// Unprefixed claim of the decree, see `claim_vaa`
+    pub legacy_claim: Info<'b>,
 }
 
 impl<'a> From<&RegisterChain<'a>> for EndpointDerivationData {
@@ -139,29 +173,294 @@ impl<'a> From<&RegisterChain<'a>> for EndpointDerivationData {
 #[derive(BorshDeserialize, BorshSerialize, Default)]
 pub struct RegisterChainData {}
 
+// Reject registrations that could never belong to a real foreign token bridge.
+fn check_registration(payload: &PayloadGovernanceRegisterChain) -> Result<()> {
+    if payload.chain == 0 {
+        return Err(InvalidEmitterChain.into());
+    }
+    if payload.chain == CHAIN_ID_SOLANA {
+        return Err(EmitterChainIsSolana.into());
+    }
+    if payload.endpoint_address == [0u8; 32] {
+        return Err(InvalidEmitterAddress.into());
+    }
+    Ok(())
+}
+
 pub fn register_chain(
     ctx: &ExecutionContext,
     accs: &mut RegisterChain,
     _data: RegisterChainData,
 ) -> Result<()> {
     let derivation_data: EndpointDerivationData = (&*accs).into();
-    accs.endpoint
-        .verify_derivation(ctx.program_id, &derivation_data)?;
+    let bump = accs
+        .endpoint
+        .verify_derivation_bump(ctx.program_id, &derivation_data)?;
 
     if INVALID_VAAS.contains(&&*accs.vaa.info().key.to_string()) {
         return Err(InvalidVAA.into());
     }
 
+    check_registration(&accs.vaa)?;
+
     // Claim VAA
     verify_governance(&accs.vaa)?;
-    claim::consume(ctx, accs.payer.key, &mut accs.claim, &accs.vaa)?;
+    claim::claim_vaa(
+        ctx,
+        accs.payer.key,
+        &mut accs.claim,
+        &accs.legacy_claim,
+        &accs.vaa,
+    )?;
+
+    let created = !accs.endpoint.is_initialized();
+    if created {
+        accs.endpoint
+            .create_with_bump(&derivation_data, bump, ctx, accs.payer.key, Exempt)?;
+
+        accs.endpoint.chain = accs.vaa.chain;
+        accs.endpoint.contract = accs.vaa.endpoint_address;
+    } else {
+        // Only a deregistered endpoint can be registered again.
+        if !accs.endpoint.deregistered {
+            return Err(SolitaireError::AlreadyInitialized(
+                *accs.endpoint.info().key,
+            ));
+        }
+
+        accs.endpoint.deregistered = false;
+        let size = accs.endpoint.size();
+        realloc_safe(ctx, accs.endpoint.info(), size, accs.payer.key)?;
+    }
+
+    events::emit(&RegisterChainEvent {
+        chain: accs.vaa.chain,
+        emitter_address: accs.vaa.endpoint_address,
+        sequence: accs.vaa.meta().sequence,
+        created,
+    })?;
+
+    Ok(())
+}
+
+#[derive(FromAccounts)]
+pub struct DeregisterChain<'b> {
+    pub payer: Mut<Signer<AccountInfo<'b>>>,
+
+    pub endpoint: Mut<Endpoint<'b, { AccountState::Initialized }>>,
 
-    // Create endpoint
+    pub vaa: PayloadMessage<'b, GovernancePayloadDeregisterChain>,
+    pub claim: Mut<Claim<'b>>,
+    /// Unprefixed claim of the decree, see `claim_vaa`
+    pub legacy_claim: Info<'b>,
+}
+
+impl<'a> From<&DeregisterChain<'a>> for EndpointDerivationData {
+    fn from(accs: &DeregisterChain<'a>) -> Self {
+        EndpointDerivationData {
+            emitter_chain: accs.vaa.chain,
+            emitter_address: accs.vaa.endpoint_address,
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct DeregisterChainData {}
+
+/// Stop honoring VAAs of a registered endpoint, until a registration decree enables it again.
+pub fn deregister_chain(
+    ctx: &ExecutionContext,
+    accs: &mut DeregisterChain,
+    _data: DeregisterChainData,
+) -> Result<()> {
+    let derivation_data: EndpointDerivationData = (&*accs).into();
     accs.endpoint
-        .create(&((&*accs).into()), ctx, accs.payer.key, Exempt)?;
+        .verify_derivation(ctx.program_id, &derivation_data)?;
+
+    if INVALID_VAAS.contains(&&*accs.vaa.info().key.to_string()) {
+        return Err(InvalidVAA.into());
+    }
+
+    verify_governance(&accs.vaa)?;
+    claim::claim_vaa(
+        ctx,
+        accs.payer.key,
+        &mut accs.claim,
+        &accs.legacy_claim,
+        &accs.vaa,
+    )?;
+
+    accs.endpoint.deregistered = true;
+    let size = accs.endpoint.size();
+    realloc_safe(ctx, accs.endpoint.info(), size, accs.payer.key)?;
+
+    events::emit(&DeregisterChainEvent {
+        chain: accs.vaa.chain,
+        emitter_address: accs.vaa.endpoint_address,
+        sequence: accs.vaa.meta().sequence,
+    })?;
+
+    Ok(())
+}
+
+#[derive(FromAccounts)]
+pub struct SetPaused<'b> {
+    pub payer: Mut<Signer<AccountInfo<'b>>>,
+    pub config: Mut<ConfigAccount<'b, { AccountState::Initialized }>>,
 
-    accs.endpoint.chain = accs.vaa.chain;
-    accs.endpoint.contract = accs.vaa.endpoint_address;
+    pub vaa: PayloadMessage<'b, GovernancePayloadSetPaused>,
+    pub claim: Mut<Claim<'b>>,
+    /// Unprefixed claim of the decree, see `claim_vaa`
+    pub legacy_claim: Info<'b>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct SetPausedData {}
+
+pub fn set_paused(
+    ctx: &ExecutionContext,
+    accs: &mut SetPaused,
+    _data: SetPausedData,
+) -> Result<()> {
+    if INVALID_VAAS.contains(&&*accs.vaa.info().key.to_string()) {
+        return Err(InvalidVAA.into());
+    }
+
+    verify_governance(&accs.vaa)?;
+    claim::claim_vaa(
+        ctx,
+        accs.payer.key,
+        &mut accs.claim,
+        &accs.legacy_claim,
+        &accs.vaa,
+    )?;
+
+    accs.config.paused = accs.vaa.paused;
+    let size = accs.config.size();
+    // Flags only stored while set change the size of the config, resize it so the new encoding
+    // can be persisted.
+    realloc_safe(ctx, accs.config.info(), size, accs.payer.key)?;
+
+    Ok(())
+}
+
+#[derive(FromAccounts)]
+pub struct SetMetadataAuthority<'b> {
+    pub payer: Mut<Signer<AccountInfo<'b>>>,
+
+    pub vaa: PayloadMessage<'b, GovernancePayloadSetMetadataAuthority>,
+    pub claim: Mut<Claim<'b>>,
+    /// Unprefixed claim of the decree, see `claim_vaa`
+    pub legacy_claim: Info<'b>,
+
+    /// Wrapped asset account of the decreed mint, proving it was created by this token bridge
+    pub meta: WrappedTokenMeta<'b, { AccountState::Initialized }>,
+
+    /// SPL Metadata for the decreed mint
+    pub spl_metadata: Mut<SplTokenMeta<'b>>,
+
+    pub mint_authority: MintSigner<'b>,
+}
+
+impl<'a> From<&SetMetadataAuthority<'a>> for WrappedMetaDerivationData {
+    fn from(accs: &SetMetadataAuthority<'a>) -> Self {
+        WrappedMetaDerivationData {
+            mint_key: accs.vaa.mint,
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct SetMetadataAuthorityData {}
+
+pub fn set_metadata_authority(
+    ctx: &ExecutionContext,
+    accs: &mut SetMetadataAuthority,
+    _data: SetMetadataAuthorityData,
+) -> Result<()> {
+    let derivation_data: WrappedMetaDerivationData = (&*accs).into();
+    accs.meta
+        .verify_derivation(ctx.program_id, &derivation_data)?;
+
+    if INVALID_VAAS.contains(&&*accs.vaa.info().key.to_string()) {
+        return Err(InvalidVAA.into());
+    }
+
+    verify_governance(&accs.vaa)?;
+    claim::claim_vaa(
+        ctx,
+        accs.payer.key,
+        &mut accs.claim,
+        &accs.legacy_claim,
+        &accs.vaa,
+    )?;
+
+    deserialize_and_verify_metadata(
+        &accs.spl_metadata,
+        SplTokenMetaDerivationData {
+            mint: accs.vaa.mint,
+        },
+    )?;
+
+    let spl_token_metadata_ix = spl_token_metadata::instruction::update_metadata_accounts_v2(
+        spl_token_metadata::id(),
+        *accs.spl_metadata.key,
+        *accs.mint_authority.info().key,
+        Some(accs.vaa.new_authority),
+        None,
+        None,
+        None,
+    );
+    invoke_seeded(&spl_token_metadata_ix, ctx, &accs.mint_authority, None)?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TokenBridgeError;
+
+    fn registration(chain: u16, endpoint_address: [u8; 32]) -> PayloadGovernanceRegisterChain {
+        PayloadGovernanceRegisterChain {
+            chain,
+            endpoint_address,
+        }
+    }
+
+    fn assert_rejected(payload: PayloadGovernanceRegisterChain, expected: TokenBridgeError) {
+        match check_registration(&payload) {
+            Err(SolitaireError::Custom(code)) => assert_eq!(code, expected as u64),
+            _ => panic!("registration should have been rejected"),
+        }
+    }
+
+    #[test]
+    fn test_register_chain_zero() {
+        assert_rejected(
+            registration(0, [1u8; 32]),
+            TokenBridgeError::InvalidEmitterChain,
+        );
+    }
+
+    #[test]
+    fn test_register_chain_solana() {
+        assert_rejected(
+            registration(CHAIN_ID_SOLANA, [1u8; 32]),
+            TokenBridgeError::EmitterChainIsSolana,
+        );
+    }
+
+    #[test]
+    fn test_register_zero_emitter() {
+        assert_rejected(
+            registration(2, [0u8; 32]),
+            TokenBridgeError::InvalidEmitterAddress,
+        );
+    }
+
+    #[test]
+    fn test_register_valid_chain() {
+        assert!(check_registration(&registration(2, [1u8; 32])).is_ok());
+    }
+}
@@ -0,0 +1,63 @@
+use crate::{
+    accounts::{
+        Endpoint,
+        EndpointDerivationData,
+    },
+    TokenBridgeError::InvalidChain,
+};
+use bridge::api::ForeignAddress;
+use solana_program::program::set_return_data;
+use solitaire::{
+    processors::seeded::Seeded,
+    *,
+};
+
+#[derive(FromAccounts)]
+pub struct QueryRegisteredEmitter<'b> {
+    pub endpoint: Endpoint<'b, { AccountState::MaybeInitialized }>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct QueryRegisteredEmitterData {
+    pub chain: u16,
+    /// Emitter the caller expects, the endpoint account is derived from it together with `chain`.
+    pub emitter_address: ForeignAddress,
+}
+
+/// Write the emitter address registered for `chain` into return data. An endpoint account that
+/// was never created, or was deregistered, leaves the return data empty, signalling that the chain
+/// is not registered with that emitter.
+pub fn query_registered_emitter(
+    ctx: &ExecutionContext,
+    accs: &mut QueryRegisteredEmitter,
+    data: QueryRegisteredEmitterData,
+) -> Result<()> {
+    // The endpoint must live at the address the queried chain and emitter derive to. Otherwise any
+    // empty account could be passed in to answer "not registered", and any account owned by the
+    // token bridge with the right layout to answer for a chain it does not belong to.
+    accs.endpoint.verify_derivation(
+        ctx.program_id,
+        &EndpointDerivationData {
+            emitter_chain: data.chain,
+            emitter_address: data.emitter_address,
+        },
+    )?;
+
+    if !accs.endpoint.is_initialized() {
+        set_return_data(&[]);
+        return Ok(());
+    }
+
+    if accs.endpoint.chain != data.chain || accs.endpoint.contract != data.emitter_address {
+        return Err(InvalidChain.into());
+    }
+
+    // A deregistered endpoint is reported like a chain that was never registered.
+    if accs.endpoint.deregistered {
+        set_return_data(&[]);
+        return Ok(());
+    }
+
+    set_return_data(&accs.endpoint.contract);
+    Ok(())
+}
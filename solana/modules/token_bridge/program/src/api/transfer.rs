@@ -10,15 +10,17 @@ use crate::{
         MintSigner,
         WrappedDerivationData,
         WrappedMetaDerivationData,
-        WrappedMint,
         WrappedTokenMeta,
     },
     messages::PayloadTransfer,
+    token,
     types::*,
     TokenBridgeError,
     TokenBridgeError::{
         InvalidChain,
         InvalidFee,
+        InvalidRecipient,
+        OutboundTransfersPaused,
         WrongAccountOwner,
     },
 };
@@ -35,11 +37,8 @@ use solana_program::{
         AccountMeta,
         Instruction,
     },
-    program::{
-        invoke,
-        invoke_signed,
-    },
     program_option::COption,
+    program_pack::Pack,
     sysvar::clock::Clock,
 };
 use solitaire::{
@@ -57,9 +56,9 @@ pub struct TransferNative<'b> {
 
     pub config: ConfigAccount<'b, { AccountState::Initialized }>,
 
-    pub from: Mut<Data<'b, SplAccount, { AccountState::Initialized }>>,
+    pub from: Mut<Data<'b, SplInterfaceAccount, { AccountState::Initialized }>>,
 
-    pub mint: Mut<Data<'b, SplMint, { AccountState::Initialized }>>,
+    pub mint: Mut<Data<'b, SplInterfaceMint, { AccountState::Initialized }>>,
 
     pub custody: Mut<CustodyAccount<'b, { AccountState::MaybeInitialized }>>,
 
@@ -104,16 +103,74 @@ pub struct TransferNativeData {
     pub target_chain: ChainID,
 }
 
+impl TransferNativeData {
+    /// Build the arguments of a transfer, failing on any value `transfer_native` would reject.
+    pub fn new(
+        nonce: u32,
+        amount: u64,
+        fee: u64,
+        target_address: impl Into<Address>,
+        target_chain: ChainID,
+    ) -> Result<Self> {
+        let data = TransferNativeData {
+            nonce,
+            amount,
+            fee,
+            target_address: target_address.into(),
+            target_chain,
+        };
+        data.validate()?;
+        Ok(data)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        validate_transfer(
+            self.amount,
+            self.fee,
+            &self.target_address,
+            self.target_chain,
+        )
+    }
+}
+
+/// The checks of an outbound transfer that do not depend on any account. Off-chain builders and
+/// CPI callers run them through the `validate` of each transfer data type, so they fail before
+/// sending a transaction the handler would reject.
+pub fn validate_transfer(
+    amount: u64,
+    fee: u64,
+    target_address: &Address,
+    target_chain: ChainID,
+) -> Result<()> {
+    // Prevent transferring to the same chain.
+    if target_chain == CHAIN_ID_SOLANA {
+        return Err(InvalidChain.into());
+    }
+
+    // Tokens sent to the zero address cannot be redeemed by anyone.
+    if *target_address == [0u8; 32] {
+        return Err(InvalidRecipient.into());
+    }
+
+    // Fee must be less than amount
+    if fee > amount {
+        return Err(InvalidFee.into());
+    }
+
+    Ok(())
+}
+
 pub fn transfer_native(
     ctx: &ExecutionContext,
     accs: &mut TransferNative,
     data: TransferNativeData,
 ) -> Result<()> {
-    // Prevent transferring to the same chain.
-    if data.target_chain == CHAIN_ID_SOLANA {
-        return Err(InvalidChain.into());
+    if accs.config.paused {
+        return Err(OutboundTransfersPaused.into());
     }
 
+    data.validate()?;
+
     let derivation_data: CustodyAccountDerivationData = (&*accs).into();
     let (amount, fee) = verify_and_execute_native_transfers(
         ctx,
@@ -124,23 +181,21 @@ pub fn transfer_native(
         &accs.custody,
         &accs.authority_signer,
         &accs.custody_signer,
-        &accs.bridge,
-        &accs.fee_collector,
         data.amount,
         data.fee,
     )?;
 
     // Post message
     let payload = PayloadTransfer {
-        amount: U256::from(amount),
+        amount: amount.into(),
         token_address: accs.mint.info().key.to_bytes(),
         token_chain: CHAIN_ID_SOLANA,
         to: data.target_address,
         to_chain: data.target_chain,
-        fee: U256::from(fee),
+        fee: fee.into(),
     };
     let params = (
-        bridge::instruction::Instruction::PostMessage,
+        bridge::instruction::Instruction::PostMessageV2,
         PostMessageData {
             nonce: data.nonce,
             payload: payload.try_to_vec()?,
@@ -163,6 +218,7 @@ pub fn transfer_native(
             AccountMeta::new_readonly(solana_program::sysvar::rent::ID, false),
         ],
     );
+    let ix = bridge::cpi::forward_token_fee(ix, &accs.bridge, ctx.accounts)?;
     invoke_seeded(&ix, ctx, &accs.emitter, None)?;
 
     Ok(())
@@ -173,20 +229,24 @@ pub fn verify_and_execute_native_transfers(
     ctx: &ExecutionContext,
     derivation_data: &CustodyAccountDerivationData,
     payer: &Mut<Signer<AccountInfo>>,
-    from: &Mut<Data<SplAccount, { AccountState::Initialized }>>,
-    mint: &Mut<Data<SplMint, { AccountState::Initialized }>>,
+    from: &Mut<Data<SplInterfaceAccount, { AccountState::Initialized }>>,
+    mint: &Mut<Data<SplInterfaceMint, { AccountState::Initialized }>>,
     custody: &Mut<CustodyAccount<{ AccountState::MaybeInitialized }>>,
     authority_signer: &AuthoritySigner,
     custody_signer: &CustodySigner,
-    bridge: &Mut<CoreBridge<{ AccountState::Initialized }>>,
-    fee_collector: &Mut<Info>,
     raw_amount: u64,
     raw_fee: u64,
-) -> Result<(u64, u64)> {
+) -> Result<(NormalizedAmount, NormalizedAmount)> {
     // Verify that the custody account is derived correctly
-    custody.verify_derivation(ctx.program_id, derivation_data)?;
-
-    // Verify mints
+    let custody_bump = custody.verify_derivation_bump(ctx.program_id, derivation_data)?;
+
+    // Verify mints, the sender and custody must belong to the token program of the mint
+    let token_program = token::token_program_of(mint.info())?;
+    token::assert_mint_bridgeable(mint.info())?;
+    token::require_token_program(&token_program, &[from.info()])?;
+    if custody.is_initialized() {
+        token::require_token_program(&token_program, &[custody.info()])?;
+    }
     if from.mint != *mint.info().key {
         return Err(TokenBridgeError::InvalidMint.into());
     }
@@ -204,42 +264,58 @@ pub fn verify_and_execute_native_transfers(
     }
 
     if !custody.is_initialized() {
-        custody.create(derivation_data, ctx, payer.key, Exempt)?;
-
-        let init_ix = spl_token::instruction::initialize_account(
-            &spl_token::id(),
+        // Custody accounts belong to the token program of their mint, so the owner is given here
+        // rather than by the account type. Seeds are the same under either program.
+        let mut seeds =
+            CustodyAccount::<'_, { AccountState::MaybeInitialized }>::seeds(derivation_data);
+        seeds.push(vec![custody_bump]);
+        let seeds: Vec<&[u8]> = seeds.iter().map(|seed| seed.as_slice()).collect();
+        create_pda_account(
+            ctx,
+            custody.info(),
+            payer.key,
+            Exempt,
+            token::account_len(&token_program, mint.info())?,
+            &token_program,
+            &seeds,
+        )?;
+        token::initialize_account(
+            ctx,
+            &token_program,
             custody.info().key,
             mint.info().key,
             custody_signer.key,
         )?;
-        invoke_signed(&init_ix, ctx.accounts, &[])?;
     }
 
-    let trunc_divisor = 10u64.pow(8.max(mint.decimals as u32) - 8);
-    // Truncate to 8 decimals
-    let amount: u64 = raw_amount / trunc_divisor;
-    let fee: u64 = raw_fee / trunc_divisor;
-    // Untruncate the amount to drop the remainder so we don't  "burn" user's funds.
-    let amount_trunc: u64 = amount * trunc_divisor;
+    let (_, dust) = NormalizedAmount::normalize(raw_amount, mint.decimals);
+    let (fee, _) = NormalizedAmount::normalize(raw_fee, mint.decimals);
+    // Leave the dust with the sender so we don't "burn" user's funds.
+    let amount_trunc: u64 = raw_amount - dust;
 
-    // Transfer tokens
-    let transfer_ix = spl_token::instruction::transfer(
-        &spl_token::id(),
+    // Transfer tokens, Token-2022 mints with a transfer fee credit custody with less than is
+    // debited. Only what custody received is published, the dust of that stays in custody.
+    let custody_before = token::balance(custody.info())?;
+    token::transfer_checked(
+        ctx,
+        &token_program,
         from.info().key,
         custody.info().key,
-        authority_signer.key,
-        &[],
+        mint.info().key,
+        authority_signer,
+        None,
         amount_trunc,
+        mint.decimals,
     )?;
-    invoke_seeded(&transfer_ix, ctx, authority_signer, None)?;
+    let received = token::balance(custody.info())?
+        .checked_sub(custody_before)
+        .ok_or(SolitaireError::InsufficientFunds)?;
+    let (amount, _) = NormalizedAmount::normalize(received, mint.decimals);
 
-    // Pay fee
-    let transfer_ix = solana_program::system_instruction::transfer(
-        payer.key,
-        fee_collector.key,
-        bridge.config.fee,
-    );
-    invoke(&transfer_ix, ctx.accounts)?;
+    // The relayer fee is paid out of what custody received
+    if fee > amount {
+        return Err(InvalidFee.into());
+    }
 
     Ok((amount, fee))
 }
@@ -249,9 +325,11 @@ pub struct TransferWrapped<'b> {
     pub payer: Mut<Signer<AccountInfo<'b>>>,
     pub config: ConfigAccount<'b, { AccountState::Initialized }>,
 
-    pub from: Mut<Data<'b, SplAccount, { AccountState::Initialized }>>,
+    pub from: Mut<Data<'b, SplInterfaceAccount, { AccountState::Initialized }>>,
     pub from_owner: MaybeMut<Signer<Info<'b>>>,
-    pub mint: Mut<WrappedMint<'b, { AccountState::Initialized }>>,
+    /// Wrapped mint of the transfer, of either token program. Its derivation is proven by
+    /// `wrapped_meta`.
+    pub mint: Mut<Data<'b, SplInterfaceMint, { AccountState::Initialized }>>,
     pub wrapped_meta: WrappedTokenMeta<'b, { AccountState::Initialized }>,
 
     pub authority_signer: AuthoritySigner<'b>,
@@ -300,28 +378,56 @@ pub struct TransferWrappedData {
     pub target_chain: ChainID,
 }
 
+impl TransferWrappedData {
+    /// Build the arguments of a transfer, failing on any value `transfer_wrapped` would reject.
+    pub fn new(
+        nonce: u32,
+        amount: u64,
+        fee: u64,
+        target_address: impl Into<Address>,
+        target_chain: ChainID,
+    ) -> Result<Self> {
+        let data = TransferWrappedData {
+            nonce,
+            amount,
+            fee,
+            target_address: target_address.into(),
+            target_chain,
+        };
+        data.validate()?;
+        Ok(data)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        validate_transfer(
+            self.amount,
+            self.fee,
+            &self.target_address,
+            self.target_chain,
+        )
+    }
+}
+
 pub fn transfer_wrapped(
     ctx: &ExecutionContext,
     accs: &mut TransferWrapped,
     data: TransferWrappedData,
 ) -> Result<()> {
-    // Prevent transferring to the same chain.
-    if data.target_chain == CHAIN_ID_SOLANA {
-        return Err(InvalidChain.into());
+    if accs.config.paused {
+        return Err(OutboundTransfersPaused.into());
     }
 
+    data.validate()?;
+
     let derivation_data: WrappedMetaDerivationData = (&*accs).into();
     verify_and_execute_wrapped_transfers(
         ctx,
         &derivation_data,
-        &accs.payer,
         &accs.from,
         &accs.from_owner,
         &accs.mint,
         &accs.wrapped_meta,
         &accs.authority_signer,
-        &accs.bridge,
-        &accs.fee_collector,
         data.amount,
         data.fee,
     )?;
@@ -336,7 +442,7 @@ pub fn transfer_wrapped(
         fee: U256::from(data.fee),
     };
     let params = (
-        bridge::instruction::Instruction::PostMessage,
+        bridge::instruction::Instruction::PostMessageV2,
         PostMessageData {
             nonce: data.nonce,
             payload: payload.try_to_vec()?,
@@ -359,6 +465,7 @@ pub fn transfer_wrapped(
             AccountMeta::new_readonly(solana_program::sysvar::rent::ID, false),
         ],
     );
+    let ix = bridge::cpi::forward_token_fee(ix, &accs.bridge, ctx.accounts)?;
     invoke_seeded(&ix, ctx, &accs.emitter, None)?;
 
     Ok(())
@@ -368,14 +475,11 @@ pub fn transfer_wrapped(
 pub fn verify_and_execute_wrapped_transfers(
     ctx: &ExecutionContext,
     derivation_data: &WrappedMetaDerivationData,
-    payer: &Mut<Signer<AccountInfo>>,
-    from: &Mut<Data<SplAccount, { AccountState::Initialized }>>,
+    from: &Mut<Data<SplInterfaceAccount, { AccountState::Initialized }>>,
     from_owner: &MaybeMut<Signer<Info>>,
-    mint: &Mut<WrappedMint<{ AccountState::Initialized }>>,
+    mint: &Mut<Data<SplInterfaceMint, { AccountState::Initialized }>>,
     wrapped_meta: &WrappedTokenMeta<{ AccountState::Initialized }>,
     authority_signer: &AuthoritySigner,
-    bridge: &Mut<CoreBridge<{ AccountState::Initialized }>>,
-    fee_collector: &Mut<Info>,
     amount: u64,
     fee: u64,
 ) -> Result<()> {
@@ -384,10 +488,12 @@ pub fn verify_and_execute_wrapped_transfers(
         return Err(WrongAccountOwner.into());
     }
 
-    // Verify mints
+    // Verify mints, the sender must belong to the token program of the mint
     if mint.info().key != &from.mint {
         return Err(TokenBridgeError::InvalidMint.into());
     }
+    let token_program = token::token_program_of(mint.info())?;
+    token::require_token_program(&token_program, &[from.info()])?;
 
     // Fee must be less than amount
     if fee > amount {
@@ -398,24 +504,71 @@ pub fn verify_and_execute_wrapped_transfers(
     wrapped_meta.verify_derivation(ctx.program_id, derivation_data)?;
 
     // Burn tokens
-    let burn_ix = spl_token::instruction::burn(
-        &spl_token::id(),
+    token::burn_checked(
+        ctx,
+        &token_program,
         from.info().key,
         mint.info().key,
-        authority_signer.key,
-        &[],
+        authority_signer,
+        None,
         amount,
+        mint.decimals,
     )?;
-    invoke_seeded(&burn_ix, ctx, authority_signer, None)?;
 
-    // Pay fee
-    let transfer_ix = solana_program::system_instruction::transfer(
-        payer.key,
-        fee_collector.key,
-        bridge.config.fee,
-    );
+    Ok(())
+}
 
-    invoke(&transfer_ix, ctx.accounts)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solitaire::SolitaireError;
 
-    Ok(())
+    fn rejected_with(result: Result<impl Sized>, error: TokenBridgeError) -> bool {
+        matches!(result, Err(SolitaireError::Custom(code)) if code == error as u64)
+    }
+
+    #[test]
+    pub fn test_transfer_native_data() {
+        assert!(TransferNativeData::new(0, 100, 100, [1u8; 32], 2).is_ok());
+        assert!(rejected_with(
+            TransferNativeData::new(0, 100, 0, [1u8; 32], CHAIN_ID_SOLANA),
+            InvalidChain
+        ));
+        assert!(rejected_with(
+            TransferNativeData::new(0, 100, 0, [0u8; 32], 2),
+            InvalidRecipient
+        ));
+        assert!(rejected_with(
+            TransferNativeData::new(0, 100, 101, [1u8; 32], 2),
+            InvalidFee
+        ));
+    }
+
+    #[test]
+    pub fn test_transfer_wrapped_data() {
+        assert!(TransferWrappedData::new(0, 100, 100, [1u8; 32], 2).is_ok());
+        assert!(rejected_with(
+            TransferWrappedData::new(0, 100, 0, [1u8; 32], CHAIN_ID_SOLANA),
+            InvalidChain
+        ));
+        assert!(rejected_with(
+            TransferWrappedData::new(0, 100, 0, [0u8; 32], 2),
+            InvalidRecipient
+        ));
+        assert!(rejected_with(
+            TransferWrappedData::new(0, 100, 101, [1u8; 32], 2),
+            InvalidFee
+        ));
+    }
+
+    #[test]
+    pub fn test_transfer_data_universal_address() {
+        let target = bridge::types::UniversalAddress::from_evm([0xab; 20]);
+        let data = TransferNativeData::new(0, 100, 0, target, 2).unwrap();
+        assert_eq!(data.target_address, target.0);
+        assert!(rejected_with(
+            TransferWrappedData::new(0, 100, 0, bridge::types::UniversalAddress::default(), 2),
+            InvalidRecipient
+        ));
+    }
 }
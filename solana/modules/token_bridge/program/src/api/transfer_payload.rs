@@ -9,12 +9,12 @@ use crate::{
         EmitterAccount,
         WrappedDerivationData,
         WrappedMetaDerivationData,
-        WrappedMint,
         WrappedTokenMeta,
+        SENDER_SEED,
     },
     messages::PayloadTransferWithPayload,
     types::*,
-    TokenBridgeError::InvalidChain,
+    TokenBridgeError::OutboundTransfersPaused,
 };
 use bridge::{
     api::PostMessageData,
@@ -38,6 +38,7 @@ use solitaire::{
 };
 
 use super::{
+    validate_transfer,
     verify_and_execute_native_transfers,
     verify_and_execute_wrapped_transfers,
 };
@@ -65,7 +66,7 @@ impl<'a, 'b: 'a> Peel<'a, 'b> for SenderAccount<'b> {
 // [`Seeded`] directly.
 impl<'b> Seeded<()> for SenderAccount<'b> {
     fn seeds(_accs: ()) -> Vec<Vec<u8>> {
-        vec![String::from("sender").as_bytes().to_vec()]
+        vec![SENDER_SEED.as_bytes().to_vec()]
     }
 }
 
@@ -127,8 +128,8 @@ pub struct TransferNativeWithPayload<'b> {
     pub payer: Mut<Signer<AccountInfo<'b>>>,
     pub config: ConfigAccount<'b, { AccountState::Initialized }>,
 
-    pub from: Mut<Data<'b, SplAccount, { AccountState::Initialized }>>,
-    pub mint: Mut<Data<'b, SplMint, { AccountState::Initialized }>>,
+    pub from: Mut<Data<'b, SplInterfaceAccount, { AccountState::Initialized }>>,
+    pub mint: Mut<Data<'b, SplInterfaceMint, { AccountState::Initialized }>>,
     pub custody: Mut<CustodyAccount<'b, { AccountState::MaybeInitialized }>>,
 
     // This could allow someone to race someone else's tx if they do the approval in a separate tx.
@@ -177,16 +178,44 @@ pub struct TransferNativeWithPayloadData {
     pub cpi_program_id: Option<Pubkey>,
 }
 
+impl TransferNativeWithPayloadData {
+    /// Build the arguments of a transfer, failing on any value `transfer_native_with_payload` would reject.
+    pub fn new(
+        nonce: u32,
+        amount: u64,
+        target_address: impl Into<Address>,
+        target_chain: ChainID,
+        payload: Vec<u8>,
+        cpi_program_id: Option<Pubkey>,
+    ) -> Result<Self> {
+        let data = TransferNativeWithPayloadData {
+            nonce,
+            amount,
+            target_address: target_address.into(),
+            target_chain,
+            payload,
+            cpi_program_id,
+        };
+        data.validate()?;
+        Ok(data)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        validate_transfer(self.amount, 0, &self.target_address, self.target_chain)
+    }
+}
+
 pub fn transfer_native_with_payload(
     ctx: &ExecutionContext,
     accs: &mut TransferNativeWithPayload,
     data: TransferNativeWithPayloadData,
 ) -> Result<()> {
-    // Prevent transferring to the same chain.
-    if data.target_chain == CHAIN_ID_SOLANA {
-        return Err(InvalidChain.into());
+    if accs.config.paused {
+        return Err(OutboundTransfersPaused.into());
     }
 
+    data.validate()?;
+
     let derivation_data: CustodyAccountDerivationData = (&*accs).into();
     let (amount, _fee) = verify_and_execute_native_transfers(
         ctx,
@@ -197,15 +226,13 @@ pub fn transfer_native_with_payload(
         &accs.custody,
         &accs.authority_signer,
         &accs.custody_signer,
-        &accs.bridge,
-        &accs.fee_collector,
         data.amount,
         0,
     )?;
 
     // Post message
     let payload = PayloadTransferWithPayload {
-        amount: U256::from(amount),
+        amount: amount.into(),
         token_address: accs.mint.info().key.to_bytes(),
         token_chain: CHAIN_ID_SOLANA,
         to: data.target_address,
@@ -214,7 +241,7 @@ pub fn transfer_native_with_payload(
         payload: data.payload,
     };
     let params = (
-        bridge::instruction::Instruction::PostMessage,
+        bridge::instruction::Instruction::PostMessageV2,
         PostMessageData {
             nonce: data.nonce,
             payload: payload.try_to_vec()?,
@@ -237,6 +264,7 @@ pub fn transfer_native_with_payload(
             AccountMeta::new_readonly(solana_program::sysvar::rent::ID, false),
         ],
     );
+    let ix = bridge::cpi::forward_token_fee(ix, &accs.bridge, ctx.accounts)?;
     invoke_seeded(&ix, ctx, &accs.emitter, None)?;
 
     Ok(())
@@ -250,9 +278,11 @@ pub struct TransferWrappedWithPayload<'b> {
     pub payer: Mut<Signer<AccountInfo<'b>>>,
     pub config: ConfigAccount<'b, { AccountState::Initialized }>,
 
-    pub from: Mut<Data<'b, SplAccount, { AccountState::Initialized }>>,
+    pub from: Mut<Data<'b, SplInterfaceAccount, { AccountState::Initialized }>>,
     pub from_owner: MaybeMut<Signer<Info<'b>>>,
-    pub mint: Mut<WrappedMint<'b, { AccountState::Initialized }>>,
+    /// Wrapped mint of the transfer, of either token program. Its derivation is proven by
+    /// `wrapped_meta`.
+    pub mint: Mut<Data<'b, SplInterfaceMint, { AccountState::Initialized }>>,
     pub wrapped_meta: WrappedTokenMeta<'b, { AccountState::Initialized }>,
 
     pub authority_signer: AuthoritySigner<'b>,
@@ -306,28 +336,53 @@ pub struct TransferWrappedWithPayloadData {
     pub cpi_program_id: Option<Pubkey>,
 }
 
+impl TransferWrappedWithPayloadData {
+    /// Build the arguments of a transfer, failing on any value `transfer_wrapped_with_payload` would reject.
+    pub fn new(
+        nonce: u32,
+        amount: u64,
+        target_address: impl Into<Address>,
+        target_chain: ChainID,
+        payload: Vec<u8>,
+        cpi_program_id: Option<Pubkey>,
+    ) -> Result<Self> {
+        let data = TransferWrappedWithPayloadData {
+            nonce,
+            amount,
+            target_address: target_address.into(),
+            target_chain,
+            payload,
+            cpi_program_id,
+        };
+        data.validate()?;
+        Ok(data)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        validate_transfer(self.amount, 0, &self.target_address, self.target_chain)
+    }
+}
+
 pub fn transfer_wrapped_with_payload(
     ctx: &ExecutionContext,
     accs: &mut TransferWrappedWithPayload,
     data: TransferWrappedWithPayloadData,
 ) -> Result<()> {
-    // Prevent transferring to the same chain.
-    if data.target_chain == CHAIN_ID_SOLANA {
-        return Err(InvalidChain.into());
+    if accs.config.paused {
+        return Err(OutboundTransfersPaused.into());
     }
 
+    data.validate()?;
+
     let derivation_data: WrappedMetaDerivationData = (&*accs).into();
     verify_and_execute_wrapped_transfers(
         ctx,
         &derivation_data,
-        &accs.payer,
         &accs.from,
         &accs.from_owner,
         &accs.mint,
         &accs.wrapped_meta,
         &accs.authority_signer,
-        &accs.bridge,
-        &accs.fee_collector,
         data.amount,
         0,
     )?;
@@ -343,7 +398,7 @@ pub fn transfer_wrapped_with_payload(
         payload: data.payload,
     };
     let params = (
-        bridge::instruction::Instruction::PostMessage,
+        bridge::instruction::Instruction::PostMessageV2,
         PostMessageData {
             nonce: data.nonce,
             payload: payload.try_to_vec()?,
@@ -366,7 +421,43 @@ pub fn transfer_wrapped_with_payload(
             AccountMeta::new_readonly(solana_program::sysvar::rent::ID, false),
         ],
     );
+    let ix = bridge::cpi::forward_token_fee(ix, &accs.bridge, ctx.accounts)?;
     invoke_seeded(&ix, ctx, &accs.emitter, None)?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TokenBridgeError::{
+        self,
+        InvalidChain,
+        InvalidRecipient,
+    };
+    use solitaire::SolitaireError;
+
+    fn rejected_with(result: Result<impl Sized>, error: TokenBridgeError) -> bool {
+        matches!(result, Err(SolitaireError::Custom(code)) if code == error as u64)
+    }
+
+    #[test]
+    pub fn test_transfer_native_with_payload_data() {
+        let new = |target_address, target_chain| {
+            TransferNativeWithPayloadData::new(0, 100, target_address, target_chain, vec![1], None)
+        };
+        assert!(new([1u8; 32], 2).is_ok());
+        assert!(rejected_with(new([1u8; 32], CHAIN_ID_SOLANA), InvalidChain));
+        assert!(rejected_with(new([0u8; 32], 2), InvalidRecipient));
+    }
+
+    #[test]
+    pub fn test_transfer_wrapped_with_payload_data() {
+        let new = |target_address, target_chain| {
+            TransferWrappedWithPayloadData::new(0, 100, target_address, target_chain, vec![1], None)
+        };
+        assert!(new([1u8; 32], 2).is_ok());
+        assert!(rejected_with(new([1u8; 32], CHAIN_ID_SOLANA), InvalidChain));
+        assert!(rejected_with(new([0u8; 32], 2), InvalidRecipient));
+    }
+}
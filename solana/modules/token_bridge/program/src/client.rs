@@ -0,0 +1,240 @@
+//! Helpers for relayers redeeming token bridge transfers off-chain. Given a posted transfer VAA
+//! they work out which completion instruction redeems it and every account it needs, so relayers
+//! do not have to reimplement the checks of the program to pick between the native and wrapped
+//! paths. Relayers quoting a redemption up front can estimate its cost as well.
+
+use crate::{
+    accounts::{
+        find_redeemer,
+        find_registered_emitter,
+        NativeAccounts,
+        TokenBridgeAccounts,
+        WrappedAccounts,
+        WrappedDerivationData,
+        WrappedMint,
+    },
+    messages::{
+        PayloadTransfer,
+        PayloadTransferWithPayload,
+    },
+    types::{
+        Address,
+        ChainID,
+    },
+    TokenBridgeError::{
+        InvalidChain,
+        InvalidPayload,
+        InvalidRecipient,
+    },
+};
+use bridge::{
+    accounts::{
+        find_posted_vaa,
+        posted_vaa_size,
+        Claim,
+        ClaimDerivationData,
+        CLAIM_SIZE,
+    },
+    instructions::hash_vaa,
+    vaa::DeserializePayload,
+    PostVAAData,
+    CHAIN_ID_SOLANA,
+};
+use solana_program::{
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use solitaire::{
+    processors::seeded::Seeded,
+    AccountState,
+    Result,
+};
+
+/// Accounts specific to the mint being redeemed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RedemptionPath {
+    /// The token is native to Solana and released from custody, by `CompleteNative` or
+    /// `CompleteNativeWithPayload`.
+    Native(NativeAccounts),
+    /// The token is minted by the token bridge, by `CompleteWrapped` or
+    /// `CompleteWrappedWithPayload`.
+    Wrapped(WrappedAccounts),
+}
+
+/// Everything needed to redeem a transfer VAA, see [`resolve_redemption`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RedemptionPlan {
+    pub path: RedemptionPath,
+    pub config: Pubkey,
+    pub posted_vaa: Pubkey,
+    pub claim: Pubkey,
+    /// Registration of the token bridge the VAA was emitted by.
+    pub registered_emitter: Pubkey,
+    pub mint: Pubkey,
+    /// Token account receiving the tokens.
+    pub to: Pubkey,
+    /// For transfers with payload, the accounts that may sign the redemption: the recipient of
+    /// the VAA if it is a wallet, or its redeemer PDA if it is a program. `to` must be owned by
+    /// the one signing.
+    pub redeemer_candidates: Option<[Pubkey; 2]>,
+    /// The token account `to` does not exist yet and must be created before redeeming.
+    pub create_to: bool,
+    /// The wrapped mint has not been created yet, the attestation of the token must be redeemed
+    /// first.
+    pub create_mint: bool,
+}
+
+/// Work out how to redeem the transfer `vaa`, posted to the core bridge `bridge_id`, with the
+/// token bridge `program_id`.
+///
+/// `recipient` is the token account the tokens are redeemed to. Transfers without payload name it
+/// in the VAA, so it must match, transfers with payload may be redeemed to any account of the
+/// redeemer. `account_exists` tells whether an account has been created, it is only asked about
+/// the recipient and the wrapped mint.
+pub fn resolve_redemption(
+    program_id: &Pubkey,
+    bridge_id: &Pubkey,
+    vaa: &PostVAAData,
+    recipient: &Pubkey,
+    mut account_exists: impl FnMut(&Pubkey) -> bool,
+) -> Result<RedemptionPlan> {
+    let transfer = Transfer::parse(&vaa.payload)?;
+    if transfer.to_chain != CHAIN_ID_SOLANA {
+        return Err(InvalidChain.into());
+    }
+
+    let vaa_recipient = Pubkey::new_from_array(transfer.to);
+    let redeemer_candidates = if transfer.with_payload {
+        Some([vaa_recipient, find_redeemer(&vaa_recipient).0])
+    } else if *recipient != vaa_recipient {
+        return Err(InvalidRecipient.into());
+    } else {
+        None
+    };
+
+    let (mint, config, path) = if transfer.token_chain == CHAIN_ID_SOLANA {
+        let mint = Pubkey::new_from_array(transfer.token_address);
+        let accounts = TokenBridgeAccounts::for_native(program_id, bridge_id, &mint);
+        (
+            mint,
+            accounts.config,
+            RedemptionPath::Native(accounts.asset),
+        )
+    } else {
+        let mint = WrappedMint::<'_, { AccountState::Uninitialized }>::key(
+            &WrappedDerivationData {
+                token_chain: transfer.token_chain,
+                token_address: transfer.token_address,
+            },
+            program_id,
+        );
+        let accounts = TokenBridgeAccounts::for_wrapped(program_id, bridge_id, &mint);
+        (
+            mint,
+            accounts.config,
+            RedemptionPath::Wrapped(accounts.asset),
+        )
+    };
+
+    let claim = Claim::<'_>::key(
+        &ClaimDerivationData {
+            emitter_address: vaa.emitter_address,
+            emitter_chain: vaa.emitter_chain,
+            sequence: vaa.sequence,
+        },
+        program_id,
+    );
+    Ok(RedemptionPlan {
+        path,
+        config,
+        posted_vaa: find_posted_vaa(bridge_id, &hash_vaa(vaa)).0,
+        claim,
+        registered_emitter: find_registered_emitter(
+            program_id,
+            vaa.emitter_chain,
+            &vaa.emitter_address,
+        )
+        .0,
+        mint,
+        to: *recipient,
+        redeemer_candidates,
+        create_to: !account_exists(recipient),
+        create_mint: matches!(path, RedemptionPath::Wrapped(_)) && !account_exists(&mint),
+    })
+}
+
+/// Lamports a relayer spends on accounts to redeem a transfer, see [`estimate_redemption_cost`].
+/// None of them are refunded to the relayer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RedemptionCost {
+    /// Rent of the claim, which is never closed.
+    pub claim_rent: u64,
+    /// Rent of the posted VAA. `close_posted_vaa` reclaims it once the VAA is claimed, but to
+    /// the fee collector of the core bridge rather than to whoever posted it.
+    pub posted_vaa_rent: u64,
+    /// Rent of the recipient token account if the relayer creates it, it belongs to the recipient
+    /// afterwards.
+    pub ata_rent: u64,
+    pub total: u64,
+}
+
+/// Estimate the rent spent on redeeming a VAA with `payload_len` bytes of payload, including
+/// posting it, and creating the recipient token account if `needs_ata`. Transaction fees and the
+/// signature set, whose rent `close_signature_set` refunds, are not included.
+pub fn estimate_redemption_cost(
+    payload_len: usize,
+    needs_ata: bool,
+    rent: &Rent,
+) -> RedemptionCost {
+    let claim_rent = rent.minimum_balance(CLAIM_SIZE);
+    let posted_vaa_rent = rent.minimum_balance(posted_vaa_size(payload_len));
+    let ata_rent = if needs_ata {
+        rent.minimum_balance(spl_token::state::Account::LEN)
+    } else {
+        0
+    };
+    RedemptionCost {
+        claim_rent,
+        posted_vaa_rent,
+        ata_rent,
+        total: claim_rent + posted_vaa_rent + ata_rent,
+    }
+}
+
+/// The fields both kinds of transfer have in common.
+struct Transfer {
+    token_address: Address,
+    token_chain: ChainID,
+    to: Address,
+    to_chain: ChainID,
+    with_payload: bool,
+}
+
+impl Transfer {
+    fn parse(payload: &[u8]) -> Result<Transfer> {
+        match payload.first() {
+            Some(1) => {
+                let transfer = PayloadTransfer::deserialize(&mut &payload[..])?;
+                Ok(Transfer {
+                    token_address: transfer.token_address,
+                    token_chain: transfer.token_chain,
+                    to: transfer.to,
+                    to_chain: transfer.to_chain,
+                    with_payload: false,
+                })
+            }
+            Some(3) => {
+                let transfer = PayloadTransferWithPayload::deserialize(&mut &payload[..])?;
+                Ok(Transfer {
+                    token_address: transfer.token_address,
+                    token_chain: transfer.token_chain,
+                    to: transfer.to,
+                    to_chain: transfer.to_chain,
+                    with_payload: true,
+                })
+            }
+            _ => Err(InvalidPayload.into()),
+        }
+    }
+}
@@ -0,0 +1,548 @@
+//! Wrappers for programs that call into the token bridge, so integrators do not have to assemble
+//! the instructions and account lists by hand. The PDAs of both bridges are derived from the
+//! accounts passed in, invoking fails if any of them is not the account the token bridge expects.
+
+use crate::{
+    instructions,
+    messages::{
+        PayloadTransfer,
+        PayloadTransferWithPayload,
+    },
+    types::WrappedMeta,
+    AttestTokenData,
+    CompleteNativeData,
+    CompleteNativeWithPayloadData,
+    CompleteWrappedData,
+    CompleteWrappedWithPayloadData,
+    TransferNativeData,
+    TransferNativeWithPayloadData,
+    TransferWrappedData,
+    TransferWrappedWithPayloadData,
+};
+use borsh::BorshDeserialize;
+use bridge::{
+    accounts::PostedVAAData,
+    cpi::TokenFee,
+    vaa::DeserializePayload,
+    PostVAAData,
+};
+use solana_program::{
+    account_info::AccountInfo,
+    instruction::Instruction,
+    program::invoke_signed,
+};
+use solitaire::Result;
+
+/// Accounts required to attest a token.
+pub struct AttestToken<'a, 'b> {
+    pub payer: &'a AccountInfo<'b>,
+    pub config: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    /// Wrapped meta of the mint, which must not exist for the mint to be attested.
+    pub wrapped_meta: &'a AccountInfo<'b>,
+    /// Metaplex metadata of the mint, the name and symbol are read from it if it exists.
+    pub spl_metadata: &'a AccountInfo<'b>,
+    /// Account a metadata pointer of a Token-2022 mint designates, when it is not the mint itself.
+    /// Its name and symbol take precedence over those of `spl_metadata`.
+    pub token_metadata: Option<&'a AccountInfo<'b>>,
+    pub bridge_config: &'a AccountInfo<'b>,
+    /// Either a fresh keypair or a PDA of the calling program, in which case its seeds must be
+    /// passed along to `attest_token`.
+    pub message: &'a AccountInfo<'b>,
+    pub emitter: &'a AccountInfo<'b>,
+    pub sequence: &'a AccountInfo<'b>,
+    pub fee_collector: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+    pub rent: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub core_bridge_program: &'a AccountInfo<'b>,
+    pub token_bridge_program: &'a AccountInfo<'b>,
+    /// Accounts paying the message fee while the core bridge charges it in tokens.
+    pub token_fee: Option<TokenFee<'a, 'b>>,
+}
+
+/// Publish the metadata of a Solana mint, so that it can be wrapped on other chains.
+pub fn attest_token(
+    accs: AttestToken,
+    data: AttestTokenData,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let mut ix = instructions::attest(
+        *accs.token_bridge_program.key,
+        *accs.core_bridge_program.key,
+        *accs.payer.key,
+        *accs.message.key,
+        *accs.mint.key,
+        accs.token_metadata.map(|account| *account.key),
+        data.nonce,
+    )?;
+
+    let mut infos = vec![
+        accs.payer.clone(),
+        accs.config.clone(),
+        accs.mint.clone(),
+        accs.wrapped_meta.clone(),
+        accs.spl_metadata.clone(),
+        accs.bridge_config.clone(),
+        accs.message.clone(),
+        accs.emitter.clone(),
+        accs.sequence.clone(),
+        accs.fee_collector.clone(),
+        accs.clock.clone(),
+        accs.rent.clone(),
+        accs.system_program.clone(),
+        accs.core_bridge_program.clone(),
+        accs.token_bridge_program.clone(),
+    ];
+    infos.extend(accs.token_metadata.cloned());
+    if let Some(token_fee) = &accs.token_fee {
+        token_fee.append_to(&mut ix, &mut infos);
+    }
+
+    invoke_signed(&ix, &infos, signer_seeds)?;
+    Ok(())
+}
+
+/// Accounts required to send native tokens. The owner of `from` must have approved the
+/// authority signer of the token bridge to spend the amount beforehand.
+pub struct TransferNative<'a, 'b> {
+    pub payer: &'a AccountInfo<'b>,
+    pub config: &'a AccountInfo<'b>,
+    pub from: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub custody: &'a AccountInfo<'b>,
+    pub authority_signer: &'a AccountInfo<'b>,
+    pub custody_signer: &'a AccountInfo<'b>,
+    pub bridge_config: &'a AccountInfo<'b>,
+    /// Either a fresh keypair or a PDA of the calling program, in which case its seeds must be
+    /// passed along.
+    pub message: &'a AccountInfo<'b>,
+    pub emitter: &'a AccountInfo<'b>,
+    pub sequence: &'a AccountInfo<'b>,
+    pub fee_collector: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+    pub rent: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub core_bridge_program: &'a AccountInfo<'b>,
+    /// Program owning the mint, either the token program or Token-2022.
+    pub token_program: &'a AccountInfo<'b>,
+    pub token_bridge_program: &'a AccountInfo<'b>,
+    /// Accounts paying the message fee while the core bridge charges it in tokens.
+    pub token_fee: Option<TokenFee<'a, 'b>>,
+}
+
+/// Lock native tokens in custody and publish a transfer to another chain.
+pub fn transfer_native(
+    accs: TransferNative,
+    data: TransferNativeData,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let ix = instructions::transfer_native(
+        *accs.token_bridge_program.key,
+        *accs.core_bridge_program.key,
+        *accs.payer.key,
+        *accs.message.key,
+        *accs.from.key,
+        *accs.mint.key,
+        *accs.token_program.key,
+        data,
+    )?;
+
+    invoke_transfer_native(&accs, None, ix, signer_seeds)
+}
+
+/// Like `transfer_native`, but with a payload for the recipient. `sender` is the PDA of the
+/// calling program derived from "sender" when `data.cpi_program_id` is set, otherwise the payer.
+pub fn transfer_native_with_payload<'a, 'b>(
+    accs: TransferNative<'a, 'b>,
+    sender: &'a AccountInfo<'b>,
+    data: TransferNativeWithPayloadData,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let ix = instructions::transfer_native_with_payload(
+        *accs.token_bridge_program.key,
+        *accs.core_bridge_program.key,
+        *accs.payer.key,
+        *accs.message.key,
+        *accs.from.key,
+        *accs.mint.key,
+        *accs.token_program.key,
+        data,
+    )?;
+
+    invoke_transfer_native(&accs, Some(sender), ix, signer_seeds)
+}
+
+fn invoke_transfer_native<'b>(
+    accs: &TransferNative<'_, 'b>,
+    sender: Option<&AccountInfo<'b>>,
+    mut ix: Instruction,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let mut infos = vec![
+        accs.payer.clone(),
+        accs.config.clone(),
+        accs.from.clone(),
+        accs.mint.clone(),
+        accs.custody.clone(),
+        accs.authority_signer.clone(),
+        accs.custody_signer.clone(),
+        accs.bridge_config.clone(),
+        accs.message.clone(),
+        accs.emitter.clone(),
+        accs.sequence.clone(),
+        accs.fee_collector.clone(),
+        accs.clock.clone(),
+    ];
+    infos.extend(sender.cloned());
+    infos.extend(vec![
+        accs.rent.clone(),
+        accs.system_program.clone(),
+        accs.core_bridge_program.clone(),
+        accs.token_program.clone(),
+        accs.token_bridge_program.clone(),
+    ]);
+    if let Some(token_fee) = &accs.token_fee {
+        token_fee.append_to(&mut ix, &mut infos);
+    }
+
+    invoke_signed(&ix, &infos, signer_seeds)?;
+    Ok(())
+}
+
+/// Accounts required to send wrapped tokens back to their origin. The wrapped tokens are burnt,
+/// `from_owner` must have approved the authority signer of the token bridge to burn the amount
+/// beforehand.
+pub struct TransferWrapped<'a, 'b> {
+    pub payer: &'a AccountInfo<'b>,
+    pub config: &'a AccountInfo<'b>,
+    pub from: &'a AccountInfo<'b>,
+    pub from_owner: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    /// Wrapped meta of the mint, identifying the token on its origin chain.
+    pub wrapped_meta: &'a AccountInfo<'b>,
+    pub authority_signer: &'a AccountInfo<'b>,
+    pub bridge_config: &'a AccountInfo<'b>,
+    /// Either a fresh keypair or a PDA of the calling program, in which case its seeds must be
+    /// passed along.
+    pub message: &'a AccountInfo<'b>,
+    pub emitter: &'a AccountInfo<'b>,
+    pub sequence: &'a AccountInfo<'b>,
+    pub fee_collector: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+    pub rent: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub core_bridge_program: &'a AccountInfo<'b>,
+    pub token_program: &'a AccountInfo<'b>,
+    pub token_bridge_program: &'a AccountInfo<'b>,
+    /// Accounts paying the message fee while the core bridge charges it in tokens.
+    pub token_fee: Option<TokenFee<'a, 'b>>,
+}
+
+/// Burn wrapped tokens and publish a transfer releasing them on their origin chain.
+pub fn transfer_wrapped(
+    accs: TransferWrapped,
+    data: TransferWrappedData,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let meta = WrappedMeta::try_from_slice(&accs.wrapped_meta.data.borrow())?;
+    let ix = instructions::transfer_wrapped(
+        *accs.token_bridge_program.key,
+        *accs.core_bridge_program.key,
+        *accs.payer.key,
+        *accs.message.key,
+        *accs.from.key,
+        *accs.from_owner.key,
+        meta.chain,
+        meta.token_address,
+        *accs.token_program.key,
+        data,
+    )?;
+
+    invoke_transfer_wrapped(&accs, None, ix, signer_seeds)
+}
+
+/// Like `transfer_wrapped`, but with a payload for the recipient. See
+/// `transfer_native_with_payload` for `sender`.
+pub fn transfer_wrapped_with_payload<'a, 'b>(
+    accs: TransferWrapped<'a, 'b>,
+    sender: &'a AccountInfo<'b>,
+    data: TransferWrappedWithPayloadData,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let meta = WrappedMeta::try_from_slice(&accs.wrapped_meta.data.borrow())?;
+    let ix = instructions::transfer_wrapped_with_payload(
+        *accs.token_bridge_program.key,
+        *accs.core_bridge_program.key,
+        *accs.payer.key,
+        *accs.message.key,
+        *accs.from.key,
+        *accs.from_owner.key,
+        meta.chain,
+        meta.token_address,
+        *accs.token_program.key,
+        data,
+    )?;
+
+    invoke_transfer_wrapped(&accs, Some(sender), ix, signer_seeds)
+}
+
+fn invoke_transfer_wrapped<'b>(
+    accs: &TransferWrapped<'_, 'b>,
+    sender: Option<&AccountInfo<'b>>,
+    mut ix: Instruction,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let mut infos = vec![
+        accs.payer.clone(),
+        accs.config.clone(),
+        accs.from.clone(),
+        accs.from_owner.clone(),
+        accs.mint.clone(),
+        accs.wrapped_meta.clone(),
+        accs.authority_signer.clone(),
+        accs.bridge_config.clone(),
+        accs.message.clone(),
+        accs.emitter.clone(),
+        accs.sequence.clone(),
+        accs.fee_collector.clone(),
+        accs.clock.clone(),
+    ];
+    infos.extend(sender.cloned());
+    infos.extend(vec![
+        accs.rent.clone(),
+        accs.system_program.clone(),
+        accs.core_bridge_program.clone(),
+        accs.token_program.clone(),
+        accs.token_bridge_program.clone(),
+    ]);
+    if let Some(token_fee) = &accs.token_fee {
+        token_fee.append_to(&mut ix, &mut infos);
+    }
+
+    invoke_signed(&ix, &infos, signer_seeds)?;
+    Ok(())
+}
+
+/// Accounts required to redeem a transfer of native tokens.
+pub struct CompleteNative<'a, 'b> {
+    pub payer: &'a AccountInfo<'b>,
+    pub config: &'a AccountInfo<'b>,
+    /// Posted VAA of the transfer.
+    pub vaa: &'a AccountInfo<'b>,
+    pub claim: &'a AccountInfo<'b>,
+    /// Registration of the token bridge that emitted the transfer.
+    pub chain_registration: &'a AccountInfo<'b>,
+    pub to: &'a AccountInfo<'b>,
+    /// Token account receiving the relayer fee, may be `to` itself.
+    pub to_fees: &'a AccountInfo<'b>,
+    pub custody: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub custody_signer: &'a AccountInfo<'b>,
+    pub rent: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub core_bridge_program: &'a AccountInfo<'b>,
+    /// Program owning the mint, either the token program or Token-2022.
+    pub token_program: &'a AccountInfo<'b>,
+    pub token_bridge_program: &'a AccountInfo<'b>,
+}
+
+/// Release native tokens from custody to the recipient of a transfer.
+pub fn complete_native(
+    accs: CompleteNative,
+    data: CompleteNativeData,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let ix = instructions::complete_native(
+        *accs.token_bridge_program.key,
+        *accs.core_bridge_program.key,
+        *accs.payer.key,
+        *accs.vaa.key,
+        load_vaa(accs.vaa)?,
+        *accs.to.key,
+        Some(*accs.to_fees.key),
+        *accs.mint.key,
+        *accs.token_program.key,
+        data,
+    )?;
+
+    invoke_complete_native(&accs, None, &ix, signer_seeds)
+}
+
+/// Like `complete_native`, for transfers with a payload. `redeemer` is the recipient of the
+/// transfer, or the PDA of the recipient program derived from "redeemer", and must sign.
+pub fn complete_native_with_payload<'a, 'b>(
+    accs: CompleteNative<'a, 'b>,
+    redeemer: &'a AccountInfo<'b>,
+    data: CompleteNativeWithPayloadData,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let ix = instructions::complete_native_with_payload(
+        *accs.token_bridge_program.key,
+        *accs.core_bridge_program.key,
+        *accs.payer.key,
+        *accs.vaa.key,
+        load_vaa(accs.vaa)?,
+        *accs.to.key,
+        *redeemer.key,
+        Some(*accs.to_fees.key),
+        *accs.mint.key,
+        *accs.token_program.key,
+        data,
+    )?;
+
+    invoke_complete_native(&accs, Some(redeemer), &ix, signer_seeds)
+}
+
+fn invoke_complete_native<'b>(
+    accs: &CompleteNative<'_, 'b>,
+    redeemer: Option<&AccountInfo<'b>>,
+    ix: &Instruction,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let mut infos = vec![
+        accs.payer.clone(),
+        accs.config.clone(),
+        accs.vaa.clone(),
+        accs.claim.clone(),
+        accs.chain_registration.clone(),
+        accs.to.clone(),
+    ];
+    infos.extend(redeemer.cloned());
+    infos.extend(vec![
+        accs.to_fees.clone(),
+        accs.custody.clone(),
+        accs.mint.clone(),
+        accs.custody_signer.clone(),
+        accs.rent.clone(),
+        accs.system_program.clone(),
+        accs.core_bridge_program.clone(),
+        accs.token_program.clone(),
+        accs.token_bridge_program.clone(),
+    ]);
+
+    invoke_signed(ix, &infos, signer_seeds)?;
+    Ok(())
+}
+
+/// Accounts required to redeem a transfer of wrapped tokens.
+pub struct CompleteWrapped<'a, 'b> {
+    pub payer: &'a AccountInfo<'b>,
+    pub config: &'a AccountInfo<'b>,
+    /// Posted VAA of the transfer.
+    pub vaa: &'a AccountInfo<'b>,
+    pub claim: &'a AccountInfo<'b>,
+    /// Registration of the token bridge that emitted the transfer.
+    pub chain_registration: &'a AccountInfo<'b>,
+    pub to: &'a AccountInfo<'b>,
+    /// Token account receiving the relayer fee, may be `to` itself.
+    pub to_fees: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub wrapped_meta: &'a AccountInfo<'b>,
+    pub mint_authority: &'a AccountInfo<'b>,
+    pub rent: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub core_bridge_program: &'a AccountInfo<'b>,
+    /// Program owning the mint, either the token program or Token-2022.
+    pub token_program: &'a AccountInfo<'b>,
+    pub token_bridge_program: &'a AccountInfo<'b>,
+}
+
+/// Mint wrapped tokens to the recipient of a transfer.
+pub fn complete_wrapped(
+    accs: CompleteWrapped,
+    data: CompleteWrappedData,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let vaa = load_vaa(accs.vaa)?;
+    let payload = PayloadTransfer::deserialize(&mut vaa.payload.as_slice())?;
+    let ix = instructions::complete_wrapped(
+        *accs.token_bridge_program.key,
+        *accs.core_bridge_program.key,
+        *accs.payer.key,
+        *accs.vaa.key,
+        vaa,
+        payload,
+        *accs.to.key,
+        Some(*accs.to_fees.key),
+        *accs.token_program.key,
+        data,
+    )?;
+
+    invoke_complete_wrapped(&accs, None, &ix, signer_seeds)
+}
+
+/// Like `complete_wrapped`, for transfers with a payload. See `complete_native_with_payload` for
+/// `redeemer`.
+pub fn complete_wrapped_with_payload<'a, 'b>(
+    accs: CompleteWrapped<'a, 'b>,
+    redeemer: &'a AccountInfo<'b>,
+    data: CompleteWrappedWithPayloadData,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let vaa = load_vaa(accs.vaa)?;
+    let payload = PayloadTransferWithPayload::deserialize(&mut vaa.payload.as_slice())?;
+    let ix = instructions::complete_wrapped_with_payload(
+        *accs.token_bridge_program.key,
+        *accs.core_bridge_program.key,
+        *accs.payer.key,
+        *accs.vaa.key,
+        vaa,
+        payload,
+        *accs.to.key,
+        *redeemer.key,
+        Some(*accs.to_fees.key),
+        *accs.token_program.key,
+        data,
+    )?;
+
+    invoke_complete_wrapped(&accs, Some(redeemer), &ix, signer_seeds)
+}
+
+fn invoke_complete_wrapped<'b>(
+    accs: &CompleteWrapped<'_, 'b>,
+    redeemer: Option<&AccountInfo<'b>>,
+    ix: &Instruction,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let mut infos = vec![
+        accs.payer.clone(),
+        accs.config.clone(),
+        accs.vaa.clone(),
+        accs.claim.clone(),
+        accs.chain_registration.clone(),
+        accs.to.clone(),
+    ];
+    infos.extend(redeemer.cloned());
+    infos.extend(vec![
+        accs.to_fees.clone(),
+        accs.mint.clone(),
+        accs.wrapped_meta.clone(),
+        accs.mint_authority.clone(),
+        accs.rent.clone(),
+        accs.system_program.clone(),
+        accs.core_bridge_program.clone(),
+        accs.token_program.clone(),
+        accs.token_bridge_program.clone(),
+    ]);
+
+    invoke_signed(ix, &infos, signer_seeds)?;
+    Ok(())
+}
+
+/// The parts of the posted VAA the instruction builders derive the claim and the chain
+/// registration from. Posted VAAs do not record the guardian set index, no account depends on it.
+fn load_vaa(vaa: &AccountInfo) -> Result<PostVAAData> {
+    let posted = PostedVAAData::try_from_slice(&vaa.data.borrow())?;
+    Ok(PostVAAData {
+        version: posted.vaa_version,
+        guardian_set_index: 0,
+        timestamp: posted.vaa_time,
+        nonce: posted.nonce,
+        emitter_chain: posted.emitter_chain,
+        emitter_address: posted.emitter_address,
+        sequence: posted.sequence,
+        consistency_level: posted.consistency_level,
+        payload: posted.message.payload,
+    })
+}
@@ -0,0 +1,55 @@
+use bridge::api::ForeignAddress;
+pub use bridge::events::{
+    emit,
+    Event,
+};
+use solana_program::pubkey::Pubkey;
+use solitaire::*;
+
+/// Logged whenever governance registers a foreign token bridge.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct RegisterChainEvent {
+    pub chain: u16,
+    pub emitter_address: ForeignAddress,
+    /// Sequence of the governance VAA carrying the decree.
+    pub sequence: u64,
+    /// False when a deregistered endpoint was enabled again.
+    pub created: bool,
+}
+
+impl Event for RegisterChainEvent {
+    const NAME: &'static str = "RegisterChainEvent";
+}
+
+/// Logged whenever governance deregisters a foreign token bridge.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct DeregisterChainEvent {
+    pub chain: u16,
+    pub emitter_address: ForeignAddress,
+    /// Sequence of the governance VAA carrying the decree.
+    pub sequence: u64,
+}
+
+impl Event for DeregisterChainEvent {
+    const NAME: &'static str = "DeregisterChainEvent";
+}
+
+/// Logged whenever native tokens are released from custody. Mints with a Token-2022 transfer fee
+/// deliver less than leaves custody, the fee is charged to the recipient so the relayer receives
+/// the fee it quoted.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct CompleteNativeEvent {
+    pub mint: Pubkey,
+    pub to: Pubkey,
+    pub to_fees: Pubkey,
+    /// Amount sent to `to` and the part of it that arrived.
+    pub amount_sent: u64,
+    pub amount_received: u64,
+    /// Amount sent to `to_fees` and the part of it that arrived, the relayer fee of the transfer.
+    pub fee_sent: u64,
+    pub fee_received: u64,
+}
+
+impl Event for CompleteNativeEvent {
+    const NAME: &'static str = "CompleteNativeEvent";
+}
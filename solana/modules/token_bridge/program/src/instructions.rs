@@ -1,6 +1,5 @@
 use crate::{
     accounts::{
-        AuthoritySigner,
         ConfigAccount,
         CustodyAccount,
         CustodyAccountDerivationData,
@@ -11,6 +10,7 @@ use crate::{
         MintSigner,
         SplTokenMeta,
         SplTokenMetaDerivationData,
+        TokenBridgeAccounts,
         WrappedDerivationData,
         WrappedMetaDerivationData,
         WrappedMint,
@@ -23,13 +23,18 @@ use crate::{
         },
         AttestTokenData,
         CreateWrappedData,
+        DeregisterChainData,
+        QueryRegisteredEmitterData,
         RegisterChainData,
         SenderAccount,
+        SetMetadataAuthorityData,
+        SetPausedData,
         TransferNativeData,
         TransferWrappedData,
         UpgradeContractData,
     },
     messages::{
+        GovernancePayloadDeregisterChain,
         PayloadAssetMeta,
         PayloadGovernanceRegisterChain,
         PayloadTransfer,
@@ -47,6 +52,7 @@ use bridge::{
         Claim,
         ClaimDerivationData,
         FeeCollector,
+        GovernanceClaimDerivationData,
         Sequence,
         SequenceDerivationData,
     },
@@ -95,6 +101,7 @@ pub fn complete_native(
     to: Pubkey,
     fee_recipient: Option<Pubkey>,
     mint: Pubkey,
+    token_program: Pubkey,
     data: CompleteNativeData,
 ) -> solitaire::Result<Instruction> {
     let config_key = ConfigAccount::<'_, { AccountState::Uninitialized }>::key(None, &program_id);
@@ -134,7 +141,7 @@ pub fn complete_native(
             AccountMeta::new_readonly(solana_program::system_program::id(), false),
             // Program
             AccountMeta::new_readonly(bridge_id, false),
-            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(token_program, false),
         ],
         data: (crate::instruction::Instruction::CompleteNative, data).try_to_vec()?,
     })
@@ -151,6 +158,7 @@ pub fn complete_native_with_payload(
     to_owner: Pubkey,
     fee_recipient: Option<Pubkey>,
     mint: Pubkey,
+    token_program: Pubkey,
     data: CompleteNativeWithPayloadData,
 ) -> solitaire::Result<Instruction> {
     let config_key = ConfigAccount::<'_, { AccountState::Uninitialized }>::key(None, &program_id);
@@ -191,7 +199,7 @@ pub fn complete_native_with_payload(
             AccountMeta::new_readonly(solana_program::system_program::id(), false),
             // Program
             AccountMeta::new_readonly(bridge_id, false),
-            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(token_program, false),
         ],
         data: (
             crate::instruction::Instruction::CompleteNativeWithPayload,
@@ -211,6 +219,7 @@ pub fn complete_wrapped(
     payload: PayloadTransfer,
     to: Pubkey,
     fee_recipient: Option<Pubkey>,
+    token_program: Pubkey,
     data: CompleteWrappedData,
 ) -> solitaire::Result<Instruction> {
     let config_key = ConfigAccount::<'_, { AccountState::Uninitialized }>::key(None, &program_id);
@@ -257,7 +266,7 @@ pub fn complete_wrapped(
             AccountMeta::new_readonly(solana_program::system_program::id(), false),
             // Program
             AccountMeta::new_readonly(bridge_id, false),
-            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(token_program, false),
         ],
         data: (crate::instruction::Instruction::CompleteWrapped, data).try_to_vec()?,
     })
@@ -274,6 +283,7 @@ pub fn complete_wrapped_with_payload(
     to: Pubkey,
     to_owner: Pubkey,
     fee_recipient: Option<Pubkey>,
+    token_program: Pubkey,
     data: CompleteWrappedWithPayloadData,
 ) -> solitaire::Result<Instruction> {
     let config_key = ConfigAccount::<'_, { AccountState::Uninitialized }>::key(None, &program_id);
@@ -321,7 +331,7 @@ pub fn complete_wrapped_with_payload(
             AccountMeta::new_readonly(solana_program::system_program::id(), false),
             // Program
             AccountMeta::new_readonly(bridge_id, false),
-            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(token_program, false),
         ],
         data: (
             crate::instruction::Instruction::CompleteWrappedWithPayload,
@@ -401,7 +411,12 @@ pub fn register_chain(
     data: RegisterChainData,
 ) -> solitaire::Result<Instruction> {
     let config_key = ConfigAccount::<'_, { AccountState::Uninitialized }>::key(None, &program_id);
-    let (message_acc, claim_acc) = claimable_vaa(program_id, message_key, vaa);
+    let (claim_acc, legacy_claim_acc) = governance_claims(
+        program_id,
+        vaa.emitter_address,
+        vaa.emitter_chain,
+        vaa.sequence,
+    );
     let endpoint = Endpoint::<'_, { AccountState::Initialized }>::key(
         &EndpointDerivationData {
             emitter_chain: payload.chain,
@@ -416,8 +431,9 @@ pub fn register_chain(
             AccountMeta::new(payer, true),
             AccountMeta::new_readonly(config_key, false),
             AccountMeta::new(endpoint, false),
-            message_acc,
+            AccountMeta::new_readonly(message_key, false),
             claim_acc,
+            legacy_claim_acc,
             // Dependencies
             AccountMeta::new(solana_program::sysvar::rent::id(), false),
             AccountMeta::new(solana_program::system_program::id(), false),
@@ -428,6 +444,63 @@ pub fn register_chain(
     })
 }
 
+pub fn deregister_chain(
+    program_id: Pubkey,
+    payer: Pubkey,
+    payload_message: Pubkey,
+    emitter: Pubkey,
+    sequence: u64,
+    payload: GovernancePayloadDeregisterChain,
+) -> Instruction {
+    let (claim, legacy_claim) =
+        governance_claims(program_id, emitter.to_bytes(), CHAIN_ID_SOLANA, sequence);
+    let endpoint = Endpoint::<'_, { AccountState::Initialized }>::key(
+        &EndpointDerivationData {
+            emitter_chain: payload.chain,
+            emitter_address: payload.endpoint_address,
+        },
+        &program_id,
+    );
+
+    Instruction {
+        program_id,
+
+        accounts: vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(endpoint, false),
+            AccountMeta::new_readonly(payload_message, false),
+            claim,
+            legacy_claim,
+            // Dependencies
+            AccountMeta::new_readonly(solana_program::sysvar::rent::id(), false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ],
+
+        data: (
+            crate::instruction::Instruction::DeregisterChain,
+            DeregisterChainData {},
+        )
+            .try_to_vec()
+            .unwrap(),
+    }
+}
+
+pub fn query_registered_emitter(
+    program_id: Pubkey,
+    endpoint: Pubkey,
+    data: QueryRegisteredEmitterData,
+) -> solitaire::Result<Instruction> {
+    Ok(Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new_readonly(endpoint, false)],
+        data: (
+            crate::instruction::Instruction::QueryRegisteredEmitter,
+            data,
+        )
+            .try_to_vec()?,
+    })
+}
+
 fn claimable_vaa(
     bridge_id: Pubkey,
     message_key: Pubkey,
@@ -448,6 +521,37 @@ fn claimable_vaa(
     )
 }
 
+/// Claim of a governance decree followed by its legacy unprefixed claim, see
+/// `bridge::accounts::claim::claim_vaa`.
+fn governance_claims(
+    program_id: Pubkey,
+    emitter_address: [u8; 32],
+    emitter_chain: u16,
+    sequence: u64,
+) -> (AccountMeta, AccountMeta) {
+    let claim_key = Claim::<'_>::key(
+        &GovernanceClaimDerivationData {
+            emitter_address,
+            emitter_chain,
+            sequence,
+        },
+        &program_id,
+    );
+    let legacy_claim_key = Claim::<'_>::key(
+        &ClaimDerivationData {
+            emitter_address,
+            emitter_chain,
+            sequence,
+        },
+        &program_id,
+    );
+
+    (
+        AccountMeta::new(claim_key, false),
+        AccountMeta::new_readonly(legacy_claim_key, false),
+    )
+}
+
 /// Required accounts
 ///
 /// | name             | account                                                           | signer |
@@ -467,7 +571,11 @@ fn claimable_vaa(
 /// | rent             | rent sysvar                                                       | false  |
 /// | system_program   | system program                                                    | false  |
 /// | bridge_id        | bridge_id program                                                 | false  |
-/// | spl_token        | spl_token program                                                 | false  |
+/// | token_program    | program owning the mint, spl_token or Token-2022                  | false  |
+///
+/// While the core bridge charges message fees in a fee mint, append
+/// `bridge::instructions::token_fee_accounts(bridge_id, fee_mint, payer_token_account)` for the
+/// payer to pay the fee with.
 pub fn transfer_native(
     program_id: Pubkey,
     bridge_id: Pubkey,
@@ -475,27 +583,10 @@ pub fn transfer_native(
     message_key: Pubkey,
     from: Pubkey,
     mint: Pubkey,
+    token_program: Pubkey,
     data: TransferNativeData,
 ) -> solitaire::Result<Instruction> {
-    let config_key = ConfigAccount::<'_, { AccountState::Uninitialized }>::key(None, &program_id);
-    let custody_key = CustodyAccount::<'_, { AccountState::Initialized }>::key(
-        &CustodyAccountDerivationData { mint },
-        &program_id,
-    );
-
-    let authority_signer_key = AuthoritySigner::key(None, &program_id);
-    let custody_signer_key = CustodySigner::key(None, &program_id);
-    let emitter_key = EmitterAccount::key(None, &program_id);
-
-    // Bridge keys
-    let bridge_config = Bridge::<'_, { AccountState::Uninitialized }>::key(None, &bridge_id);
-    let sequence_key = Sequence::key(
-        &SequenceDerivationData {
-            emitter_key: &emitter_key,
-        },
-        &bridge_id,
-    );
-    let fee_collector_key = FeeCollector::key(None, &bridge_id);
+    let accounts = TokenBridgeAccounts::for_native(&program_id, &bridge_id, &mint);
 
     let instruction = crate::instruction::Instruction::TransferNative;
 
@@ -503,24 +594,24 @@ pub fn transfer_native(
         program_id,
         accounts: vec![
             AccountMeta::new(payer, true),
-            AccountMeta::new_readonly(config_key, false),
+            AccountMeta::new_readonly(accounts.config, false),
             AccountMeta::new(from, false),
-            AccountMeta::new(mint, false),
-            AccountMeta::new(custody_key, false),
-            AccountMeta::new_readonly(authority_signer_key, false),
-            AccountMeta::new_readonly(custody_signer_key, false),
-            AccountMeta::new(bridge_config, false),
+            AccountMeta::new(accounts.mint, false),
+            AccountMeta::new(accounts.asset.custody_token, false),
+            AccountMeta::new_readonly(accounts.transfer_authority, false),
+            AccountMeta::new_readonly(accounts.asset.custody_authority, false),
+            AccountMeta::new(accounts.bridge_config, false),
             AccountMeta::new(message_key, true),
-            AccountMeta::new_readonly(emitter_key, false),
-            AccountMeta::new(sequence_key, false),
-            AccountMeta::new(fee_collector_key, false),
+            AccountMeta::new_readonly(accounts.emitter, false),
+            AccountMeta::new(accounts.sequence, false),
+            AccountMeta::new(accounts.fee_collector, false),
             AccountMeta::new_readonly(solana_program::sysvar::clock::id(), false),
             // Dependencies
             AccountMeta::new_readonly(solana_program::sysvar::rent::id(), false),
             AccountMeta::new_readonly(solana_program::system_program::id(), false),
             // Program
             AccountMeta::new_readonly(bridge_id, false),
-            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(token_program, false),
         ],
         data: (instruction, data).try_to_vec()?,
     })
@@ -547,7 +638,10 @@ pub fn transfer_native(
 /// | rent             | rent sysvar                                                            | false  |
 /// | system_program   | system program                                                         | false  |
 /// | bridge_id        | bridge_id program                                                      | false  |
-/// | spl_token        | spl_token program                                                      | false  |
+/// | token_program    | program owning the mint, spl_token or Token-2022                       | false  |
+///
+/// Takes the token fee accounts of the core bridge last, like `transfer_native`.
+#[allow(clippy::too_many_arguments)]
 pub fn transfer_native_with_payload(
     program_id: Pubkey,
     bridge_id: Pubkey,
@@ -555,27 +649,10 @@ pub fn transfer_native_with_payload(
     message_key: Pubkey,
     from: Pubkey,
     mint: Pubkey,
+    token_program: Pubkey,
     data: TransferNativeWithPayloadData,
 ) -> solitaire::Result<Instruction> {
-    let config_key = ConfigAccount::<'_, { AccountState::Uninitialized }>::key(None, &program_id);
-    let custody_key = CustodyAccount::<'_, { AccountState::Initialized }>::key(
-        &CustodyAccountDerivationData { mint },
-        &program_id,
-    );
-
-    let authority_signer_key = AuthoritySigner::key(None, &program_id);
-    let custody_signer_key = CustodySigner::key(None, &program_id);
-    let emitter_key = EmitterAccount::key(None, &program_id);
-
-    // Bridge keys
-    let bridge_config = Bridge::<'_, { AccountState::Uninitialized }>::key(None, &bridge_id);
-    let sequence_key = Sequence::key(
-        &SequenceDerivationData {
-            emitter_key: &emitter_key,
-        },
-        &bridge_id,
-    );
-    let fee_collector_key = FeeCollector::key(None, &bridge_id);
+    let accounts = TokenBridgeAccounts::for_native(&program_id, &bridge_id, &mint);
 
     let sender = match data.cpi_program_id {
         Some(cpi_program_id) => SenderAccount::key((), &cpi_program_id),
@@ -588,17 +665,17 @@ pub fn transfer_native_with_payload(
         program_id,
         accounts: vec![
             AccountMeta::new(payer, true),
-            AccountMeta::new_readonly(config_key, false),
+            AccountMeta::new_readonly(accounts.config, false),
             AccountMeta::new(from, false),
-            AccountMeta::new(mint, false),
-            AccountMeta::new(custody_key, false),
-            AccountMeta::new_readonly(authority_signer_key, false),
-            AccountMeta::new_readonly(custody_signer_key, false),
-            AccountMeta::new(bridge_config, false),
+            AccountMeta::new(accounts.mint, false),
+            AccountMeta::new(accounts.asset.custody_token, false),
+            AccountMeta::new_readonly(accounts.transfer_authority, false),
+            AccountMeta::new_readonly(accounts.asset.custody_authority, false),
+            AccountMeta::new(accounts.bridge_config, false),
             AccountMeta::new(message_key, true),
-            AccountMeta::new_readonly(emitter_key, false),
-            AccountMeta::new(sequence_key, false),
-            AccountMeta::new(fee_collector_key, false),
+            AccountMeta::new_readonly(accounts.emitter, false),
+            AccountMeta::new(accounts.sequence, false),
+            AccountMeta::new(accounts.fee_collector, false),
             AccountMeta::new_readonly(solana_program::sysvar::clock::id(), false),
             AccountMeta::new(sender, true),
             // Dependencies
@@ -606,7 +683,7 @@ pub fn transfer_native_with_payload(
             AccountMeta::new_readonly(solana_program::system_program::id(), false),
             // Program
             AccountMeta::new_readonly(bridge_id, false),
-            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(token_program, false),
         ],
         data: (instruction, data).try_to_vec()?,
     })
@@ -632,7 +709,9 @@ pub fn transfer_native_with_payload(
 /// | rent             | rent sysvar                                                            | false  |
 /// | system_program   | system program                                                         | false  |
 /// | bridge_id        | bridge_id program                                                      | false  |
-/// | spl_token        | spl_token program                                                      | false  |
+/// | token_program    | program owning the mint, spl_token or Token-2022                       | false  |
+///
+/// Takes the token fee accounts of the core bridge last, like `transfer_native`.
 #[allow(clippy::too_many_arguments)]
 pub fn transfer_wrapped(
     program_id: Pubkey,
@@ -643,10 +722,9 @@ pub fn transfer_wrapped(
     from_owner: Pubkey,
     token_chain: u16,
     token_address: ForeignAddress,
+    token_program: Pubkey,
     data: TransferWrappedData,
 ) -> solitaire::Result<Instruction> {
-    let config_key = ConfigAccount::<'_, { AccountState::Uninitialized }>::key(None, &program_id);
-
     let wrapped_mint_key = WrappedMint::<'_, { AccountState::Uninitialized }>::key(
         &WrappedDerivationData {
             token_chain,
@@ -654,25 +732,7 @@ pub fn transfer_wrapped(
         },
         &program_id,
     );
-    let wrapped_meta_key = WrappedTokenMeta::<'_, { AccountState::Uninitialized }>::key(
-        &WrappedMetaDerivationData {
-            mint_key: wrapped_mint_key,
-        },
-        &program_id,
-    );
-
-    let authority_signer = AuthoritySigner::key(None, &program_id);
-    let emitter_key = EmitterAccount::key(None, &program_id);
-
-    // Bridge keys
-    let bridge_config = Bridge::<'_, { AccountState::Uninitialized }>::key(None, &bridge_id);
-    let sequence_key = Sequence::key(
-        &SequenceDerivationData {
-            emitter_key: &emitter_key,
-        },
-        &bridge_id,
-    );
-    let fee_collector_key = FeeCollector::key(None, &bridge_id);
+    let accounts = TokenBridgeAccounts::for_wrapped(&program_id, &bridge_id, &wrapped_mint_key);
 
     let instruction = crate::instruction::Instruction::TransferWrapped;
 
@@ -680,24 +740,24 @@ pub fn transfer_wrapped(
         program_id,
         accounts: vec![
             AccountMeta::new(payer, true),
-            AccountMeta::new_readonly(config_key, false),
+            AccountMeta::new_readonly(accounts.config, false),
             AccountMeta::new(from, false),
             AccountMeta::new_readonly(from_owner, true),
-            AccountMeta::new(wrapped_mint_key, false),
-            AccountMeta::new_readonly(wrapped_meta_key, false),
-            AccountMeta::new_readonly(authority_signer, false),
-            AccountMeta::new(bridge_config, false),
+            AccountMeta::new(accounts.mint, false),
+            AccountMeta::new_readonly(accounts.asset.wrapped_meta, false),
+            AccountMeta::new_readonly(accounts.transfer_authority, false),
+            AccountMeta::new(accounts.bridge_config, false),
             AccountMeta::new(message_key, true),
-            AccountMeta::new_readonly(emitter_key, false),
-            AccountMeta::new(sequence_key, false),
-            AccountMeta::new(fee_collector_key, false),
+            AccountMeta::new_readonly(accounts.emitter, false),
+            AccountMeta::new(accounts.sequence, false),
+            AccountMeta::new(accounts.fee_collector, false),
             AccountMeta::new_readonly(solana_program::sysvar::clock::id(), false),
             // Dependencies
             AccountMeta::new_readonly(solana_program::sysvar::rent::id(), false),
             AccountMeta::new_readonly(solana_program::system_program::id(), false),
             // Program
             AccountMeta::new_readonly(bridge_id, false),
-            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(token_program, false),
         ],
         data: (instruction, data).try_to_vec()?,
     })
@@ -724,7 +784,9 @@ pub fn transfer_wrapped(
 /// | rent             | rent sysvar                                                            | false  |
 /// | system_program   | system program                                                         | false  |
 /// | bridge_id        | bridge_id program                                                      | false  |
-/// | spl_token        | spl_token program                                                      | false  |
+/// | token_program    | program owning the mint, spl_token or Token-2022                       | false  |
+///
+/// Takes the token fee accounts of the core bridge last, like `transfer_native`.
 #[allow(clippy::too_many_arguments)]
 pub fn transfer_wrapped_with_payload(
     program_id: Pubkey,
@@ -735,10 +797,9 @@ pub fn transfer_wrapped_with_payload(
     from_owner: Pubkey,
     token_chain: u16,
     token_address: ForeignAddress,
+    token_program: Pubkey,
     data: TransferWrappedWithPayloadData,
 ) -> solitaire::Result<Instruction> {
-    let config_key = ConfigAccount::<'_, { AccountState::Uninitialized }>::key(None, &program_id);
-
     let wrapped_mint_key = WrappedMint::<'_, { AccountState::Uninitialized }>::key(
         &WrappedDerivationData {
             token_chain,
@@ -746,25 +807,7 @@ pub fn transfer_wrapped_with_payload(
         },
         &program_id,
     );
-    let wrapped_meta_key = WrappedTokenMeta::<'_, { AccountState::Uninitialized }>::key(
-        &WrappedMetaDerivationData {
-            mint_key: wrapped_mint_key,
-        },
-        &program_id,
-    );
-
-    let authority_signer = AuthoritySigner::key(None, &program_id);
-    let emitter_key = EmitterAccount::key(None, &program_id);
-
-    // Bridge keys
-    let bridge_config = Bridge::<'_, { AccountState::Uninitialized }>::key(None, &bridge_id);
-    let sequence_key = Sequence::key(
-        &SequenceDerivationData {
-            emitter_key: &emitter_key,
-        },
-        &bridge_id,
-    );
-    let fee_collector_key = FeeCollector::key(None, &bridge_id);
+    let accounts = TokenBridgeAccounts::for_wrapped(&program_id, &bridge_id, &wrapped_mint_key);
 
     let sender = match data.cpi_program_id {
         Some(cpi_program_id) => SenderAccount::key((), &cpi_program_id),
@@ -777,17 +820,17 @@ pub fn transfer_wrapped_with_payload(
         program_id,
         accounts: vec![
             AccountMeta::new(payer, true),
-            AccountMeta::new_readonly(config_key, false),
+            AccountMeta::new_readonly(accounts.config, false),
             AccountMeta::new(from, false),
             AccountMeta::new_readonly(from_owner, true),
-            AccountMeta::new(wrapped_mint_key, false),
-            AccountMeta::new_readonly(wrapped_meta_key, false),
-            AccountMeta::new_readonly(authority_signer, false),
-            AccountMeta::new(bridge_config, false),
+            AccountMeta::new(accounts.mint, false),
+            AccountMeta::new_readonly(accounts.asset.wrapped_meta, false),
+            AccountMeta::new_readonly(accounts.transfer_authority, false),
+            AccountMeta::new(accounts.bridge_config, false),
             AccountMeta::new(message_key, true),
-            AccountMeta::new_readonly(emitter_key, false),
-            AccountMeta::new(sequence_key, false),
-            AccountMeta::new(fee_collector_key, false),
+            AccountMeta::new_readonly(accounts.emitter, false),
+            AccountMeta::new(accounts.sequence, false),
+            AccountMeta::new(accounts.fee_collector, false),
             AccountMeta::new_readonly(solana_program::sysvar::clock::id(), false),
             AccountMeta::new(sender, true),
             // Dependencies
@@ -795,18 +838,23 @@ pub fn transfer_wrapped_with_payload(
             AccountMeta::new_readonly(solana_program::system_program::id(), false),
             // Program
             AccountMeta::new_readonly(bridge_id, false),
-            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(token_program, false),
         ],
         data: (instruction, data).try_to_vec()?,
     })
 }
 
+/// `token_metadata` is the account a metadata pointer of a Token-2022 `mint` designates, when it
+/// is not the mint itself.
+///
+/// Takes the token fee accounts of the core bridge last, like `transfer_native`.
 pub fn attest(
     program_id: Pubkey,
     bridge_id: Pubkey,
     payer: Pubkey,
     message_key: Pubkey,
     mint: Pubkey,
+    token_metadata: Option<Pubkey>,
     nonce: u32,
 ) -> solitaire::Result<Instruction> {
     let config_key = ConfigAccount::<'_, { AccountState::Uninitialized }>::key(None, &program_id);
@@ -834,27 +882,30 @@ pub fn attest(
     );
     let fee_collector_key = FeeCollector::key(None, &bridge_id);
 
+    let mut accounts = vec![
+        AccountMeta::new(payer, true),
+        AccountMeta::new(config_key, false),
+        AccountMeta::new_readonly(mint, false),
+        AccountMeta::new_readonly(mint_meta, false),
+        AccountMeta::new_readonly(spl_metadata, false),
+        // Bridge accounts
+        AccountMeta::new(bridge_config, false),
+        AccountMeta::new(message_key, true),
+        AccountMeta::new_readonly(emitter_key, false),
+        AccountMeta::new(sequence_key, false),
+        AccountMeta::new(fee_collector_key, false),
+        AccountMeta::new_readonly(solana_program::sysvar::clock::id(), false),
+        // Dependencies
+        AccountMeta::new(solana_program::sysvar::rent::id(), false),
+        AccountMeta::new(solana_program::system_program::id(), false),
+        // Program
+        AccountMeta::new_readonly(bridge_id, false),
+    ];
+    accounts.extend(token_metadata.map(|key| AccountMeta::new_readonly(key, false)));
+
     Ok(Instruction {
         program_id,
-        accounts: vec![
-            AccountMeta::new(payer, true),
-            AccountMeta::new(config_key, false),
-            AccountMeta::new_readonly(mint, false),
-            AccountMeta::new_readonly(mint_meta, false),
-            AccountMeta::new_readonly(spl_metadata, false),
-            // Bridge accounts
-            AccountMeta::new(bridge_config, false),
-            AccountMeta::new(message_key, true),
-            AccountMeta::new_readonly(emitter_key, false),
-            AccountMeta::new(sequence_key, false),
-            AccountMeta::new(fee_collector_key, false),
-            AccountMeta::new_readonly(solana_program::sysvar::clock::id(), false),
-            // Dependencies
-            AccountMeta::new(solana_program::sysvar::rent::id(), false),
-            AccountMeta::new(solana_program::system_program::id(), false),
-            // Program
-            AccountMeta::new_readonly(bridge_id, false),
-        ],
+        accounts,
         data: (
             crate::instruction::Instruction::AttestToken,
             AttestTokenData { nonce },
@@ -872,14 +923,8 @@ pub fn upgrade_contract(
     spill: Pubkey,
     sequence: u64,
 ) -> Instruction {
-    let claim = Claim::<'_>::key(
-        &ClaimDerivationData {
-            emitter_address: emitter.to_bytes(),
-            emitter_chain: CHAIN_ID_SOLANA,
-            sequence,
-        },
-        &program_id,
-    );
+    let (claim, legacy_claim) =
+        governance_claims(program_id, emitter.to_bytes(), CHAIN_ID_SOLANA, sequence);
 
     let (upgrade_authority, _) = Pubkey::find_program_address(&["upgrade".as_bytes()], &program_id);
 
@@ -894,7 +939,8 @@ pub fn upgrade_contract(
         accounts: vec![
             AccountMeta::new(payer, true),
             AccountMeta::new_readonly(payload_message, false),
-            AccountMeta::new(claim, false),
+            claim,
+            legacy_claim,
             AccountMeta::new_readonly(upgrade_authority, false),
             AccountMeta::new(spill, false),
             AccountMeta::new(new_contract, false),
@@ -914,3 +960,81 @@ pub fn upgrade_contract(
             .unwrap(),
     }
 }
+
+pub fn set_paused(
+    program_id: Pubkey,
+    payer: Pubkey,
+    payload_message: Pubkey,
+    emitter: Pubkey,
+    sequence: u64,
+) -> Instruction {
+    let config_key = ConfigAccount::<'_, { AccountState::Uninitialized }>::key(None, &program_id);
+    let (claim, legacy_claim) =
+        governance_claims(program_id, emitter.to_bytes(), CHAIN_ID_SOLANA, sequence);
+
+    Instruction {
+        program_id,
+
+        accounts: vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(config_key, false),
+            AccountMeta::new_readonly(payload_message, false),
+            claim,
+            legacy_claim,
+            // Dependencies
+            AccountMeta::new_readonly(solana_program::sysvar::rent::id(), false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ],
+
+        data: (crate::instruction::Instruction::SetPaused, SetPausedData {})
+            .try_to_vec()
+            .unwrap(),
+    }
+}
+
+pub fn set_metadata_authority(
+    program_id: Pubkey,
+    payer: Pubkey,
+    payload_message: Pubkey,
+    emitter: Pubkey,
+    sequence: u64,
+    mint: Pubkey,
+) -> Instruction {
+    let (claim, legacy_claim) =
+        governance_claims(program_id, emitter.to_bytes(), CHAIN_ID_SOLANA, sequence);
+    let mint_meta_key = WrappedTokenMeta::<'_, { AccountState::Uninitialized }>::key(
+        &WrappedMetaDerivationData { mint_key: mint },
+        &program_id,
+    );
+    let mint_authority_key = MintSigner::key(None, &program_id);
+    let spl_metadata = SplTokenMeta::key(
+        &SplTokenMetaDerivationData { mint },
+        &spl_token_metadata::id(),
+    );
+
+    Instruction {
+        program_id,
+
+        accounts: vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new_readonly(payload_message, false),
+            claim,
+            legacy_claim,
+            AccountMeta::new_readonly(mint_meta_key, false),
+            AccountMeta::new(spl_metadata, false),
+            AccountMeta::new_readonly(mint_authority_key, false),
+            // Dependencies
+            AccountMeta::new_readonly(solana_program::sysvar::rent::id(), false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+            // Program
+            AccountMeta::new_readonly(spl_token_metadata::id(), false),
+        ],
+
+        data: (
+            crate::instruction::Instruction::SetMetadataAuthority,
+            SetMetadataAuthorityData {},
+        )
+            .try_to_vec()
+            .unwrap(),
+    }
+}
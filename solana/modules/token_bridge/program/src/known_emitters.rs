@@ -0,0 +1,286 @@
+//! Token bridges deployed on each network, as registered with one another by governance. Relayers
+//! and receiver programs can check the emitter of a token bridge VAA against these instead of
+//! hardcoding addresses. The network a program is built for is picked at build time, see the
+//! Makefile, so each network has a module of its own. The tables mirror those of the Go SDK in
+//! `sdk/*_consts.go`.
+
+use bridge::CHAIN_ID_GOVERANCE;
+use hex_literal::hex;
+
+/// Chain and emitter of governance VAAs, the same on every network.
+pub const GOVERNANCE_EMITTER: (u16, [u8; 32]) = (
+    CHAIN_ID_GOVERANCE,
+    hex!("0000000000000000000000000000000000000000000000000000000000000004"),
+);
+
+macro_rules! known_emitters {
+    ($($rows:tt)*) => {
+        use super::*;
+
+        /// Token bridge emitter of each chain, ordered by chain id.
+        pub const KNOWN_TOKEN_BRIDGE_EMITTERS: &[(u16, [u8; 32])] = &[$($rows)*];
+
+        /// Emitter of the token bridge on `chain`, if one is known.
+        pub fn token_bridge_emitter(chain: u16) -> Option<[u8; 32]> {
+            KNOWN_TOKEN_BRIDGE_EMITTERS
+                .iter()
+                .find(|(known, _)| *known == chain)
+                .map(|(_, emitter)| *emitter)
+        }
+
+        /// Whether `emitter` is the known token bridge of `chain`.
+        pub fn is_known_token_bridge(chain: u16, emitter: &[u8; 32]) -> bool {
+            token_bridge_emitter(chain).as_ref() == Some(emitter)
+        }
+    };
+}
+
+pub mod mainnet {
+    known_emitters! {
+        // Solana
+        (1, hex!("ec7372995d5cc8732397fb0ad35c0121e0eaa90d26f828a534cab54391b3a4f5")),
+        // Ethereum
+        (2, hex!("0000000000000000000000003ee18b2214aff97000d974cf647e7c347e8fa585")),
+        // Terra
+        (3, hex!("0000000000000000000000007cf7b764e38a0a5e967972c1df77d432510564e2")),
+        // BSC
+        (4, hex!("000000000000000000000000b6f6d86a8f9879a9c87f643768d9efc38c1da6e7")),
+        // Polygon
+        (5, hex!("0000000000000000000000005a58505a96d1dbf8df91cb21b54419fc36e93fde")),
+        // Avalanche
+        (6, hex!("0000000000000000000000000e082f06ff657d94310cb8ce8b0d9a04541d8052")),
+        // Oasis
+        (7, hex!("0000000000000000000000005848c791e09901b40a9ef749f2a6735b418d7564")),
+        // Algorand
+        (8, hex!("67e93fa6c8ac5c819990aa7340c0c16b508abb1178be9b30d024b8ac25193d45")),
+        // Aurora
+        (9, hex!("00000000000000000000000051b5123a7b0f9b2ba265f9c4c8de7d78d52f510f")),
+        // Fantom
+        (10, hex!("0000000000000000000000007c9fc5741288cdfdd83ceb07f3ea7e22618d79d2")),
+        // Karura
+        (11, hex!("000000000000000000000000ae9d7fe007b3327aa64a32824aaac52c42a6e624")),
+        // Acala
+        (12, hex!("000000000000000000000000ae9d7fe007b3327aa64a32824aaac52c42a6e624")),
+        // Klaytn
+        (13, hex!("0000000000000000000000005b08ac39eaed75c0439fc750d9fe7e1f9dd0193f")),
+        // Celo
+        (14, hex!("000000000000000000000000796dff6d74f3e27060b71255fe517bfb23c93eed")),
+        // Near
+        (15, hex!("148410499d3fcda4dcfd68a1ebfcdddda16ab28326448d4aae4d2f0465cdfcb7")),
+        // Moonbeam
+        (16, hex!("000000000000000000000000b1731c586ca89a23809861c6103f0b96b3f57d92")),
+        // Terra 2
+        (18, hex!("a463ad028fb79679cfc8ce1efba35ac0e77b35080a1abe9bebe83461f176b0a3")),
+        // Injective
+        (19, hex!("00000000000000000000000045dbea4617971d93188eda21530bc6503d153313")),
+        // Sui
+        (21, hex!("ccceeb29348f71bdd22ffef43a2a19c1f5b5e17c5cca5411529120182672ade5")),
+        // Aptos
+        (22, hex!("0000000000000000000000000000000000000000000000000000000000000001")),
+        // Arbitrum
+        (23, hex!("0000000000000000000000000b2402144bb366a632d14b83f244d2e0e21bd39c")),
+        // Optimism
+        (24, hex!("0000000000000000000000001d68124e65fafc907325e3edbf8c4d84499daa8b")),
+        // Xpla
+        (28, hex!("8f9cf727175353b17a5f574270e370776123d90fd74956ae4277962b4fdee24c")),
+        // Base
+        (30, hex!("0000000000000000000000008d2de8d2f73f1f4cab472ac9a881c9b123c79627")),
+        // Sei
+        (32, hex!("86c5fd957e2db8389553e1728f9c27964b22a8154091ccba54d75f4b10c61f5e")),
+        // Scroll
+        (34, hex!("00000000000000000000000024850c6f61c438823f01b7a3bf2b89b72174fa9d")),
+        // Mantle
+        (35, hex!("00000000000000000000000024850c6f61c438823f01b7a3bf2b89b72174fa9d")),
+        // Blast
+        (36, hex!("00000000000000000000000024850c6f61c438823f01b7a3bf2b89b72174fa9d")),
+        // XLayer
+        (37, hex!("0000000000000000000000005537857664b0f9efe38c9f320f75fef23234d904")),
+        // Snaxchain
+        (43, hex!("0000000000000000000000008b94bfe456b48a6025b92e11be393baa86e68410")),
+        // Wormchain
+        (3104, hex!("aeb534c45c3049d380b9d9b966f9895f53abd4301bfaff407fa09dea8ae7a924")),
+    }
+}
+
+pub mod testnet {
+    known_emitters! {
+        // Solana
+        (1, hex!("3b26409f8aaded3f5ddca184695aa6a0fa829b0c85caf84856324896d214ca98")),
+        // Ethereum
+        (2, hex!("000000000000000000000000f890982f9310df57d00f659cf4fd87e65aded8d7")),
+        // Terra
+        (3, hex!("0000000000000000000000000c32d68d8f22613f6b9511872dad35a59bfdf7f0")),
+        // BSC
+        (4, hex!("0000000000000000000000009dcf9d205c9de35334d646bee44b2d2859712a09")),
+        // Polygon
+        (5, hex!("000000000000000000000000377d55a7928c046e18eebb61977e714d2a76472a")),
+        // Avalanche
+        (6, hex!("00000000000000000000000061e44e506ca5659e6c0bba9b678586fa2d729756")),
+        // Oasis
+        (7, hex!("00000000000000000000000088d8004a9bdbfd9d28090a02010c19897a29605c")),
+        // Algorand
+        (8, hex!("6241ffdc032b693bfb8544858f0403dec86f2e1720af9f34f8d65fe574b6238c")),
+        // Aurora
+        (9, hex!("000000000000000000000000d05ed3ad637b890d68a854d607eeaf11af456fba")),
+        // Fantom
+        (10, hex!("000000000000000000000000599cea2204b4faecd584ab1f2b6aca137a0afbe8")),
+        // Karura
+        (11, hex!("000000000000000000000000e157115ef34c93145fec2fe53706846853b07f42")),
+        // Acala
+        (12, hex!("000000000000000000000000e157115ef34c93145fec2fe53706846853b07f42")),
+        // Klaytn
+        (13, hex!("000000000000000000000000c7a13be098720840dea132d860fdfa030884b09a")),
+        // Celo
+        (14, hex!("00000000000000000000000005ca6037ec51f8b712ed2e6fa72219feae74e153")),
+        // Near
+        (15, hex!("c2c0b6ecbbe9ecf91b2b7999f0264018ba68126c2e83bf413f59f712f3a1df55")),
+        // Moonbeam
+        (16, hex!("000000000000000000000000bc976d4b9d57e57c3ca52e1fd136c45ff7955a96")),
+        // Terra 2
+        (18, hex!("c3d4c6c2bcba163de1defb7e8f505cdb40619eee4fa618678955e8790ae1448d")),
+        // Injective
+        (19, hex!("00000000000000000000000003f3e7b2e363f51cf6e57ef85f43a2b91dbce501")),
+        // Sui
+        (21, hex!("40440411a170b4842ae7dee4f4a7b7a58bc0a98566e998850a7bb87bf5dc05b9")),
+        // Aptos
+        (22, hex!("0000000000000000000000000000000000000000000000000000000000000001")),
+        // Arbitrum
+        (23, hex!("00000000000000000000000023908a62110e21c04f3a4e011d24f901f911744a")),
+        // Optimism
+        (24, hex!("000000000000000000000000c7a204bdbfe983fcd8d8e61d02b475d4073ff97e")),
+        // Xpla
+        (28, hex!("b66da121bd3621c8d2604c08c82965640fe682d606af26a302ee09094f5e62cf")),
+        // Base
+        (30, hex!("000000000000000000000000a31aa3fdb7af7db93d18dda4e19f811342edf780")),
+        // Sei
+        (32, hex!("9328673cb5de3fd99974cefbbd90fea033f4c59a572abfd7e1a4eebcc5d18157")),
+        // Scroll
+        (34, hex!("00000000000000000000000022427d90b7da3fa4642f7025a854c7254e4e45bf")),
+        // Mantle
+        (35, hex!("00000000000000000000000075bfa155a9d7a3714b0861c8a8af0c4633c45b5d")),
+        // Blast
+        (36, hex!("000000000000000000000000430855b4d43b8aeb9d2b9869b74d58dda79c0db2")),
+        // XLayer
+        (37, hex!("000000000000000000000000da91a06299bbf302091b053c6b9ef86eff0f930d")),
+        // Linea
+        (38, hex!("000000000000000000000000c7a204bdbfe983fcd8d8e61d02b475d4073ff97e")),
+        // Berachain
+        (39, hex!("000000000000000000000000a10f2ef61de1f19f586ab8b6f2eba89bace63f7a")),
+        // Snaxchain
+        (43, hex!("000000000000000000000000a10f2ef61de1f19f586ab8b6f2eba89bace63f7a")),
+        // Wormchain
+        (3104, hex!("ef5251ea1e99ae48732800ccc7b83b57881232a73eb796b63b1d86ed2ea44e27")),
+        // Sepolia
+        (10002, hex!("000000000000000000000000db5492265f6038831e89f495670ff909ade94bd9")),
+        // Arbitrum Sepolia
+        (10003, hex!("000000000000000000000000c7a204bdbfe983fcd8d8e61d02b475d4073ff97e")),
+        // Base Sepolia
+        (10004, hex!("00000000000000000000000086f55a04690fd7815a3d802bd587e83ea888b239")),
+        // Optimism Sepolia
+        (10005, hex!("00000000000000000000000099737ec4b815d816c49a385943baf0380e75c0ac")),
+        // Holesky
+        (10006, hex!("00000000000000000000000076d093bbae4529a342080546cafeec4acba59ec6")),
+        // Polygon Sepolia
+        (10007, hex!("000000000000000000000000c7a204bdbfe983fcd8d8e61d02b475d4073ff97e")),
+    }
+}
+
+/// Emitters of the local development network.
+pub mod devnet {
+    known_emitters! {
+        // Solana
+        (1, hex!("c69a1b1a65dd336bf1df6a77afb501fc25db7fc0938cb08595a9ef473265cb4f")),
+        // Ethereum
+        (2, hex!("0000000000000000000000000290fb167208af455bb137780163b7b7a9a10c16")),
+        // Terra
+        (3, hex!("9e28beafa966b2407bffb0d48651e94972a56e69f3c0897d9e8facbdaeb98386")),
+        // BSC
+        (4, hex!("0000000000000000000000000290fb167208af455bb137780163b7b7a9a10c16")),
+        // Algorand
+        (8, hex!("8ec299cb7f3efec28f542397e07f07118d74c875f85409ed8e6b93c17b60e992")),
+        // Sui
+        (21, hex!("8c6ba6a65f1b9c7fba4c5ad710086ace208e9ac21786a923425efc8167a419f0")),
+        // Wormchain
+        (3104, hex!("c9138c6e5bd7a2ab79c1a87486c9d7349d064b35ac9f7498f3b207b3a61e6013")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accounts::find_emitter;
+    use solana_program::pubkey::Pubkey;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_mainnet_emitters() {
+        assert_eq!(
+            mainnet::token_bridge_emitter(2),
+            Some(hex!(
+                "0000000000000000000000003ee18b2214aff97000d974cf647e7c347e8fa585"
+            ))
+        );
+        assert_eq!(
+            mainnet::token_bridge_emitter(4),
+            Some(hex!(
+                "000000000000000000000000b6f6d86a8f9879a9c87f643768d9efc38c1da6e7"
+            ))
+        );
+        assert_eq!(
+            mainnet::token_bridge_emitter(5),
+            Some(hex!(
+                "0000000000000000000000005a58505a96d1dbf8df91cb21b54419fc36e93fde"
+            ))
+        );
+        assert_eq!(
+            mainnet::token_bridge_emitter(22),
+            Some(hex!(
+                "0000000000000000000000000000000000000000000000000000000000000001"
+            ))
+        );
+        assert_eq!(mainnet::token_bridge_emitter(0), None);
+        assert_eq!(mainnet::token_bridge_emitter(27), None);
+    }
+
+    #[test]
+    fn test_is_known_token_bridge() {
+        let ethereum = mainnet::token_bridge_emitter(2).unwrap();
+        assert!(mainnet::is_known_token_bridge(2, &ethereum));
+        assert!(!mainnet::is_known_token_bridge(4, &ethereum));
+        assert!(!testnet::is_known_token_bridge(2, &ethereum));
+        assert!(!mainnet::is_known_token_bridge(27, &[0; 32]));
+    }
+
+    /// The Solana entries are the emitter PDAs of the token bridge deployed on each network.
+    #[test]
+    fn test_solana_emitters() {
+        for (program_id, emitter) in [
+            (
+                "wormDTUJ6AWPNvk59vGQbDvGJmqbDTdgWgAqcLBCgUb",
+                mainnet::token_bridge_emitter(1),
+            ),
+            (
+                "DZnkkTmCiFWfYTfT41X3Rd1kDgozqzxWaHqsw6W4x2oe",
+                testnet::token_bridge_emitter(1),
+            ),
+            (
+                "B6RHG3mfcckmrYN1UhmJzyS1XX3fZKbkeUcpJe9Sy3FE",
+                devnet::token_bridge_emitter(1),
+            ),
+        ] {
+            let (expected, _) = find_emitter(&Pubkey::from_str(program_id).unwrap());
+            assert_eq!(emitter, Some(expected.to_bytes()));
+        }
+    }
+
+    #[test]
+    fn test_tables_sorted() {
+        for table in [
+            mainnet::KNOWN_TOKEN_BRIDGE_EMITTERS,
+            testnet::KNOWN_TOKEN_BRIDGE_EMITTERS,
+            devnet::KNOWN_TOKEN_BRIDGE_EMITTERS,
+        ] {
+            assert!(table.windows(2).all(|rows| rows[0].0 < rows[1].0));
+        }
+    }
+}
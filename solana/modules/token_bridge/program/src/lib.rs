@@ -7,6 +7,15 @@
 #[cfg(feature = "instructions")]
 pub mod instructions;
 
+#[cfg(feature = "instructions")]
+pub mod cpi;
+
+#[cfg(feature = "instructions")]
+pub mod prelude;
+
+#[cfg(feature = "client")]
+pub mod client;
+
 #[cfg(feature = "wasm")]
 #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
 extern crate wasm_bindgen;
@@ -17,7 +26,11 @@ pub mod wasm;
 
 pub mod accounts;
 pub mod api;
+pub mod events;
+pub mod known_emitters;
+pub mod memo;
 pub mod messages;
+pub mod token;
 pub mod types;
 
 pub use api::{
@@ -27,8 +40,12 @@ pub use api::{
     complete_wrapped,
     complete_wrapped_with_payload,
     create_wrapped,
+    deregister_chain,
     initialize,
+    query_registered_emitter,
     register_chain,
+    set_metadata_authority,
+    set_paused,
     transfer_native,
     transfer_native_with_payload,
     transfer_wrapped,
@@ -46,10 +63,18 @@ pub use api::{
     CompleteWrappedWithPayloadData,
     CreateWrapped,
     CreateWrappedData,
+    DeregisterChain,
+    DeregisterChainData,
     Initialize,
     InitializeData,
+    QueryRegisteredEmitter,
+    QueryRegisteredEmitterData,
     RegisterChain,
     RegisterChainData,
+    SetMetadataAuthority,
+    SetMetadataAuthorityData,
+    SetPaused,
+    SetPausedData,
     TransferNative,
     TransferNativeData,
     TransferNativeWithPayload,
@@ -91,6 +116,22 @@ pub enum TokenBridgeError {
     InvalidVAA,
     NonexistentTokenMetadataAccount,
     NotMetadataV1Account,
+    InvalidEmitterChain,
+    EmitterChainIsSolana,
+    InvalidEmitterAddress,
+    InvalidUpgradeBuffer,
+    OutboundTransfersPaused,
+    MalformedGovernancePayload,
+    EmitterDeregistered,
+    AmountOverflow,
+    InvalidAssociatedAccount,
+    MemoTooLong,
+    MemoNotUtf8,
+    UnsupportedTransferHook,
+    UnsupportedPermanentDelegate,
+    UnsupportedMintCloseAuthority,
+    UnsupportedNonTransferable,
+    UnsupportedMintExtension,
 }
 
 impl From<TokenBridgeError> for SolitaireError {
@@ -113,4 +154,8 @@ solitaire! {
     CompleteWrappedWithPayload => complete_wrapped_with_payload,
     TransferWrappedWithPayload => transfer_wrapped_with_payload,
     TransferNativeWithPayload => transfer_native_with_payload,
+    QueryRegisteredEmitter => query_registered_emitter,
+    SetPaused => set_paused,
+    SetMetadataAuthority => set_metadata_authority,
+    DeregisterChain => deregister_chain,
 }
@@ -0,0 +1,76 @@
+//! Memos posted alongside token bridge instructions, through the SPL Memo program.
+
+use crate::TokenBridgeError::{
+    MemoNotUtf8,
+    MemoTooLong,
+};
+use solana_program::{
+    msg,
+    program::invoke,
+    pubkey::Pubkey,
+};
+use solitaire::{
+    ExecutionContext,
+    Result,
+};
+
+/// Longest memo accepted, in bytes. The Memo program logs memos in full and has no limit of its
+/// own, so long ones would only fail later by running out of compute.
+pub const MAX_MEMO_LENGTH: usize = 256;
+
+/// Check that `memo` can be posted: it must be UTF-8, as the Memo program requires, and at most
+/// [`MAX_MEMO_LENGTH`] bytes long.
+pub fn check_memo(memo: &[u8]) -> Result<&str> {
+    if memo.len() > MAX_MEMO_LENGTH {
+        msg!(
+            "Memo of {} bytes exceeds the maximum of {}",
+            memo.len(),
+            MAX_MEMO_LENGTH
+        );
+        return Err(MemoTooLong.into());
+    }
+    std::str::from_utf8(memo).map_err(|_| MemoNotUtf8.into())
+}
+
+/// Post `memo` signed by `signers`, which must be signing the instruction already. The memo is
+/// checked with [`check_memo`] first, so invalid memos fail with an error of the token bridge
+/// rather than of the Memo program. The Memo program must be passed to the instruction.
+pub fn post_memo(ctx: &ExecutionContext, signers: &[&Pubkey], memo: &[u8]) -> Result<()> {
+    check_memo(memo)?;
+    let memo_ix = spl_memo::build_memo(memo, signers);
+    invoke(&memo_ix, ctx.accounts)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solitaire::SolitaireError;
+
+    #[test]
+    fn test_check_memo_length() {
+        assert_eq!(check_memo(&[]).unwrap(), "");
+        assert!(check_memo(&[b'a'; MAX_MEMO_LENGTH]).is_ok());
+        assert!(matches!(
+            check_memo(&[b'a'; MAX_MEMO_LENGTH + 1]),
+            Err(SolitaireError::Custom(code)) if code == MemoTooLong as u64
+        ));
+    }
+
+    #[test]
+    fn test_check_memo_utf8() {
+        assert_eq!(check_memo("wormhole ✓".as_bytes()).unwrap(), "wormhole ✓");
+
+        // A truncated multi-byte character, right at the boundary.
+        let mut memo = vec![b'a'; MAX_MEMO_LENGTH - 1];
+        memo.push(0xe2);
+        assert!(matches!(
+            check_memo(&memo),
+            Err(SolitaireError::Custom(code)) if code == MemoNotUtf8 as u64
+        ));
+        assert!(matches!(
+            check_memo(&[0xff, 0xfe]),
+            Err(SolitaireError::Custom(code)) if code == MemoNotUtf8 as u64
+        ));
+    }
+}
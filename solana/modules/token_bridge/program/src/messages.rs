@@ -1,6 +1,9 @@
-use crate::types::{
-    Address,
-    ChainID,
+use crate::{
+    types::{
+        Address,
+        ChainID,
+    },
+    TokenBridgeError::MalformedGovernancePayload,
 };
 use bridge::{
     vaa::{
@@ -297,14 +300,15 @@ where
         let mut v = Cursor::new(buf);
         Self::check_governance_header(&mut v)?;
 
+        // The chain followed by the endpoint address, and nothing else.
+        if v.get_ref().len() as u64 - v.position() != 2 + 32 {
+            return Err(MalformedGovernancePayload.into());
+        }
+
         let chain = v.read_u16::<BigEndian>()?;
         let mut endpoint_address = [0u8; 32];
         v.read_exact(&mut endpoint_address)?;
 
-        if v.position() != v.into_inner().len() as u64 {
-            return Err(InvalidAccountData.into());
-        }
-
         Ok(PayloadGovernanceRegisterChain {
             chain,
             endpoint_address,
@@ -326,6 +330,61 @@ where
     }
 }
 
+#[derive(PartialEq, Debug)]
+pub struct GovernancePayloadDeregisterChain {
+    /// Chain ID of the endpoint to be deregistered
+    pub chain: ChainID,
+    /// Address of the endpoint on the chain
+    pub endpoint_address: Address,
+}
+
+impl SerializeGovernancePayload for GovernancePayloadDeregisterChain {
+    const MODULE: &'static str = "TokenBridge";
+    const ACTION: u8 = 5;
+}
+
+impl DeserializeGovernancePayload for GovernancePayloadDeregisterChain {
+    // Only the registrations of this token bridge are changed.
+    const ALLOW_ANY_CHAIN: bool = false;
+}
+
+impl DeserializePayload for GovernancePayloadDeregisterChain
+where
+    Self: DeserializeGovernancePayload,
+{
+    fn deserialize(buf: &mut &[u8]) -> Result<Self, SolitaireError> {
+        let mut v = Cursor::new(buf);
+        Self::check_governance_header(&mut v)?;
+
+        // Laid out like a registration.
+        if v.get_ref().len() as u64 - v.position() != 2 + 32 {
+            return Err(MalformedGovernancePayload.into());
+        }
+
+        let chain = v.read_u16::<BigEndian>()?;
+        let mut endpoint_address = [0u8; 32];
+        v.read_exact(&mut endpoint_address)?;
+
+        Ok(GovernancePayloadDeregisterChain {
+            chain,
+            endpoint_address,
+        })
+    }
+}
+
+impl SerializePayload for GovernancePayloadDeregisterChain
+where
+    Self: SerializeGovernancePayload,
+{
+    fn serialize<W: Write>(&self, writer: &mut W) -> Result<(), SolitaireError> {
+        self.write_governance_header(writer)?;
+        writer.write_u16::<BigEndian>(self.chain)?;
+        writer.write_all(&self.endpoint_address[..])?;
+
+        Ok(())
+    }
+}
+
 #[derive(PartialEq, Debug)]
 pub struct GovernancePayloadUpgrade {
     /// Address of the new Implementation
@@ -367,17 +426,122 @@ impl SerializeGovernancePayload for GovernancePayloadUpgrade {
 }
 
 impl DeserializeGovernancePayload for GovernancePayloadUpgrade {
+    // An upgrade names a buffer on Solana, so it cannot be meant for every chain.
+    const ALLOW_ANY_CHAIN: bool = false;
+}
+
+#[derive(PartialEq, Debug)]
+pub struct GovernancePayloadSetPaused {
+    /// Whether outbound transfers and attestations are halted
+    pub paused: bool,
+}
+
+impl SerializePayload for GovernancePayloadSetPaused {
+    fn serialize<W: Write>(&self, v: &mut W) -> std::result::Result<(), SolitaireError> {
+        self.write_governance_header(v)?;
+        v.write_u8(self.paused as u8)?;
+        Ok(())
+    }
+}
+
+impl DeserializePayload for GovernancePayloadSetPaused
+where
+    Self: DeserializeGovernancePayload,
+{
+    fn deserialize(buf: &mut &[u8]) -> Result<Self, SolitaireError> {
+        let mut c = Cursor::new(buf);
+        Self::check_governance_header(&mut c)?;
+
+        let paused = match c.read_u8()? {
+            0 => false,
+            1 => true,
+            _ => return Err(InvalidAccountData.into()),
+        };
+
+        if c.position() != c.into_inner().len() as u64 {
+            return Err(InvalidAccountData.into());
+        }
+
+        Ok(GovernancePayloadSetPaused { paused })
+    }
+}
+
+impl SerializeGovernancePayload for GovernancePayloadSetPaused {
+    const MODULE: &'static str = "TokenBridge";
+    const ACTION: u8 = 3;
+}
+
+impl DeserializeGovernancePayload for GovernancePayloadSetPaused {
+    // Only the state of this token bridge is changed, other chains pause on their own decrees.
+    const ALLOW_ANY_CHAIN: bool = false;
+}
+
+#[derive(PartialEq, Debug)]
+pub struct GovernancePayloadSetMetadataAuthority {
+    /// Wrapped mint whose metadata changes hands
+    pub mint: Pubkey,
+    /// New update authority of the metadata
+    pub new_authority: Pubkey,
+}
+
+impl SerializePayload for GovernancePayloadSetMetadataAuthority {
+    fn serialize<W: Write>(&self, v: &mut W) -> std::result::Result<(), SolitaireError> {
+        self.write_governance_header(v)?;
+        v.write_all(&self.mint.to_bytes())?;
+        v.write_all(&self.new_authority.to_bytes())?;
+        Ok(())
+    }
+}
+
+impl DeserializePayload for GovernancePayloadSetMetadataAuthority
+where
+    Self: DeserializeGovernancePayload,
+{
+    fn deserialize(buf: &mut &[u8]) -> Result<Self, SolitaireError> {
+        let mut c = Cursor::new(buf);
+        Self::check_governance_header(&mut c)?;
+
+        let mut mint = [0u8; 32];
+        c.read_exact(&mut mint)?;
+        let mut new_authority = [0u8; 32];
+        c.read_exact(&mut new_authority)?;
+
+        if c.position() != c.into_inner().len() as u64 {
+            return Err(InvalidAccountData.into());
+        }
+
+        Ok(GovernancePayloadSetMetadataAuthority {
+            mint: Pubkey::new(&mint[..]),
+            new_authority: Pubkey::new(&new_authority[..]),
+        })
+    }
+}
+
+impl SerializeGovernancePayload for GovernancePayloadSetMetadataAuthority {
+    const MODULE: &'static str = "TokenBridge";
+    const ACTION: u8 = 4;
+}
+
+impl DeserializeGovernancePayload for GovernancePayloadSetMetadataAuthority {
+    // Names a mint on Solana, so it cannot be meant for every chain.
+    const ALLOW_ANY_CHAIN: bool = false;
 }
 
 #[cfg(test)]
 #[allow(unused_imports)]
 mod tests {
-    use crate::messages::{
-        GovernancePayloadUpgrade,
-        PayloadAssetMeta,
-        PayloadGovernanceRegisterChain,
-        PayloadTransfer,
-        PayloadTransferWithPayload,
+    use crate::{
+        messages::{
+            GovernancePayloadDeregisterChain,
+            GovernancePayloadSetMetadataAuthority,
+            GovernancePayloadSetPaused,
+            GovernancePayloadUpgrade,
+            PayloadAssetMeta,
+            PayloadGovernanceRegisterChain,
+            PayloadTransfer,
+            PayloadTransferWithPayload,
+        },
+        TokenBridgeError,
     };
     use bridge::{
         DeserializePayload,
@@ -386,6 +550,7 @@ mod tests {
     use primitive_types::U256;
     use rand::RngCore;
     use solana_program::pubkey::Pubkey;
+    use solitaire::SolitaireError;
 
     #[test]
     pub fn test_serde_transfer() {
@@ -440,6 +605,32 @@ mod tests {
         assert_eq!(original, deser);
     }
 
+    #[test]
+    pub fn test_serde_gov_set_paused() {
+        for paused in [false, true] {
+            let original = GovernancePayloadSetPaused { paused };
+
+            let data = original.try_to_vec().unwrap();
+            let deser = GovernancePayloadSetPaused::deserialize(&mut data.as_slice()).unwrap();
+
+            assert_eq!(original, deser);
+        }
+    }
+
+    #[test]
+    pub fn test_serde_gov_set_metadata_authority() {
+        let original = GovernancePayloadSetMetadataAuthority {
+            mint: Pubkey::new_unique(),
+            new_authority: Pubkey::new_unique(),
+        };
+
+        let data = original.try_to_vec().unwrap();
+        let deser =
+            GovernancePayloadSetMetadataAuthority::deserialize(&mut data.as_slice()).unwrap();
+
+        assert_eq!(original, deser);
+    }
+
     #[test]
     pub fn test_serde_gov_register_chain() {
         let mut endpoint_address = [0u8; 32];
@@ -456,6 +647,44 @@ mod tests {
         assert_eq!(original, deser);
     }
 
+    #[test]
+    pub fn test_serde_gov_deregister_chain() {
+        let mut endpoint_address = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut endpoint_address);
+
+        let original = GovernancePayloadDeregisterChain {
+            chain: 8,
+            endpoint_address,
+        };
+
+        let data = original.try_to_vec().unwrap();
+        let deser = GovernancePayloadDeregisterChain::deserialize(&mut data.as_slice()).unwrap();
+
+        assert_eq!(original, deser);
+    }
+
+    #[test]
+    pub fn test_gov_register_chain_length() {
+        let original = PayloadGovernanceRegisterChain {
+            chain: 8,
+            endpoint_address: [1u8; 32],
+        };
+        let data = original.try_to_vec().unwrap();
+
+        let mut long = data.clone();
+        long.push(0);
+        let short = &data[..data.len() - 1];
+
+        for payload in [&long[..], short] {
+            match PayloadGovernanceRegisterChain::deserialize(&mut &payload[..]) {
+                Err(SolitaireError::Custom(code)) => {
+                    assert_eq!(code, TokenBridgeError::MalformedGovernancePayload as u64)
+                }
+                _ => panic!("malformed registration should have been rejected"),
+            }
+        }
+    }
+
     #[test]
     pub fn test_serde_transfer_with_payload() {
         let mut token_address = [0u8; 32];
@@ -0,0 +1,68 @@
+//! Everything a program sending or redeeming tokens through the token bridge usually needs. It
+//! includes the core bridge prelude, except that `cpi` is the token bridge's own.
+//!
+//! A minimal redeemer, accepting transfers with payload addressed to itself:
+//!
+//! ```rust,no_run
+//! use token_bridge::prelude::*;
+//!
+//! pub fn receive(
+//!     program_id: &Pubkey,
+//!     core_bridge: &Pubkey,
+//!     posted_vaa: &AccountInfo,
+//! ) -> ProgramResult {
+//!     if posted_vaa.owner != core_bridge {
+//!         return Err(ProgramError::IncorrectProgramId);
+//!     }
+//!     let vaa = PostedVAAV1::load(posted_vaa)?;
+//!     let transfer = PayloadTransferWithPayload::deserialize(&mut vaa.payload())?;
+//!     if transfer.to_chain != CHAIN_ID_SOLANA || transfer.to != program_id.to_bytes() {
+//!         return Err(ProgramError::InvalidAccountData);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+pub use bridge::prelude::*;
+
+pub use crate::{
+    accounts::{
+        find_custody_authority,
+        find_custody_token,
+        find_emitter,
+        find_mint_authority,
+        find_redeemer,
+        find_registered_emitter,
+        find_sender,
+        find_transfer_authority,
+        require_from_registered_token_bridge,
+        require_registered_emitter,
+        Endpoint,
+        EndpointDerivationData,
+        NativeAccounts,
+        TokenBridgeAccounts,
+        WrappedAccounts,
+        REDEEMER_SEED,
+        SENDER_SEED,
+    },
+    cpi,
+    messages::{
+        PayloadAssetMeta,
+        PayloadTransfer,
+        PayloadTransferWithPayload,
+    },
+    types::{
+        Address,
+        ChainID,
+        EndpointRegistration,
+        NormalizedAmount,
+    },
+    AttestTokenData,
+    CompleteNativeWithPayloadData,
+    CompleteWrappedWithPayloadData,
+    TokenBridgeError,
+    TransferNativeData,
+    TransferNativeWithPayloadData,
+    TransferWrappedData,
+    TransferWrappedWithPayloadData,
+};
@@ -0,0 +1,967 @@
+//! SPL token instructions invoked by the token bridge, signed by one of its PDAs. Handlers moving
+//! tokens go through these rather than assembling the instructions themselves. Associated token
+//! accounts of recipients can be created or verified here as well.
+//!
+//! Native tokens may belong to Token-2022 rather than the token program, handlers accepting them
+//! find out which with [`token_program_of`].
+
+use crate::TokenBridgeError::{
+    AmountOverflow,
+    InvalidAssociatedAccount,
+    InvalidMetadata,
+    InvalidMint,
+    UnsupportedMintCloseAuthority,
+    UnsupportedMintExtension,
+    UnsupportedNonTransferable,
+    UnsupportedPermanentDelegate,
+    UnsupportedTransferHook,
+    WrongAccountOwner,
+};
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::AccountInfo,
+    hash::hash,
+    instruction::Instruction,
+    msg,
+    program::invoke,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+use solitaire::{
+    processors::seeded::{
+        invoke_seeded,
+        Seeded,
+    },
+    ExecutionContext,
+    Keyed,
+    Result,
+    SolitaireError,
+};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token_2022::{
+    extension::{
+        transfer_fee::{
+            TransferFee,
+            TransferFeeConfig,
+            MAX_FEE_BASIS_POINTS,
+        },
+        ExtensionType,
+        StateWithExtensions,
+    },
+    pod::pod_from_bytes,
+};
+use std::convert::TryFrom;
+
+/// Move `amount` tokens of `mint` from `from` to `to`, on behalf of `authority`. The token program
+/// rejects the transfer unless `mint` has `decimals` decimals. `token_program` is the program
+/// owning `mint`, either the token program or Token-2022.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_checked<'a, 'b: 'a, I, T: Seeded<I> + Keyed<'a, 'b>>(
+    ctx: &ExecutionContext,
+    token_program: &Pubkey,
+    from: &Pubkey,
+    to: &Pubkey,
+    mint: &Pubkey,
+    authority: &'a T,
+    seeds: I,
+    amount: u64,
+    decimals: u8,
+) -> Result<()> {
+    let transfer_ix = transfer_checked_ix(
+        token_program,
+        from,
+        to,
+        mint,
+        authority.info().key,
+        amount,
+        decimals,
+    )?;
+    invoke_seeded(&transfer_ix, ctx, authority, seeds)?;
+    Ok(())
+}
+
+/// Initialize `account` as a token account of `mint` held by `owner`, `token_program` being the
+/// program owning `mint`. The account must already be allocated and assigned to it.
+pub fn initialize_account(
+    ctx: &ExecutionContext,
+    token_program: &Pubkey,
+    account: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) -> Result<()> {
+    let init_ix = if *token_program == spl_token_2022::id() {
+        spl_token_2022::instruction::initialize_account(token_program, account, mint, owner)?
+    } else {
+        spl_token::instruction::initialize_account(token_program, account, mint, owner)?
+    };
+    invoke(&init_ix, ctx.accounts)?;
+    Ok(())
+}
+
+/// Mint `amount` tokens of `mint` to `to`, `authority` being its mint authority. The token program
+/// rejects the mint unless `mint` has `decimals` decimals. `token_program` is the program owning
+/// `mint`, either the token program or Token-2022.
+#[allow(clippy::too_many_arguments)]
+pub fn mint_to_checked<'a, 'b: 'a, I, T: Seeded<I> + Keyed<'a, 'b>>(
+    ctx: &ExecutionContext,
+    token_program: &Pubkey,
+    mint: &Pubkey,
+    to: &Pubkey,
+    authority: &'a T,
+    seeds: I,
+    amount: u64,
+    decimals: u8,
+) -> Result<()> {
+    let mint_ix = mint_to_checked_ix(
+        token_program,
+        mint,
+        to,
+        authority.info().key,
+        amount,
+        decimals,
+    )?;
+    invoke_seeded(&mint_ix, ctx, authority, seeds)?;
+    Ok(())
+}
+
+/// Burn `amount` tokens of `mint` held by `from`, on behalf of `authority`. The token program
+/// rejects the burn unless `mint` has `decimals` decimals. `token_program` is the program owning
+/// `mint`, either the token program or Token-2022.
+#[allow(clippy::too_many_arguments)]
+pub fn burn_checked<'a, 'b: 'a, I, T: Seeded<I> + Keyed<'a, 'b>>(
+    ctx: &ExecutionContext,
+    token_program: &Pubkey,
+    from: &Pubkey,
+    mint: &Pubkey,
+    authority: &'a T,
+    seeds: I,
+    amount: u64,
+    decimals: u8,
+) -> Result<()> {
+    let burn_ix = burn_checked_ix(
+        token_program,
+        from,
+        mint,
+        authority.info().key,
+        amount,
+        decimals,
+    )?;
+    invoke_seeded(&burn_ix, ctx, authority, seeds)?;
+    Ok(())
+}
+
+// The instructions are built by the crate of the program they are for, each only accepts its own
+// program id.
+
+fn transfer_checked_ix(
+    token_program: &Pubkey,
+    from: &Pubkey,
+    to: &Pubkey,
+    mint: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+    decimals: u8,
+) -> Result<Instruction> {
+    let ix = if *token_program == spl_token_2022::id() {
+        spl_token_2022::instruction::transfer_checked(
+            token_program,
+            from,
+            mint,
+            to,
+            authority,
+            &[],
+            amount,
+            decimals,
+        )?
+    } else {
+        spl_token::instruction::transfer_checked(
+            token_program,
+            from,
+            mint,
+            to,
+            authority,
+            &[],
+            amount,
+            decimals,
+        )?
+    };
+    Ok(ix)
+}
+
+fn mint_to_checked_ix(
+    token_program: &Pubkey,
+    mint: &Pubkey,
+    to: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+    decimals: u8,
+) -> Result<Instruction> {
+    let ix = if *token_program == spl_token_2022::id() {
+        spl_token_2022::instruction::mint_to_checked(
+            token_program,
+            mint,
+            to,
+            authority,
+            &[],
+            amount,
+            decimals,
+        )?
+    } else {
+        spl_token::instruction::mint_to_checked(
+            token_program,
+            mint,
+            to,
+            authority,
+            &[],
+            amount,
+            decimals,
+        )?
+    };
+    Ok(ix)
+}
+
+fn burn_checked_ix(
+    token_program: &Pubkey,
+    from: &Pubkey,
+    mint: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+    decimals: u8,
+) -> Result<Instruction> {
+    let ix = if *token_program == spl_token_2022::id() {
+        spl_token_2022::instruction::burn_checked(
+            token_program,
+            from,
+            mint,
+            authority,
+            &[],
+            amount,
+            decimals,
+        )?
+    } else {
+        spl_token::instruction::burn_checked(
+            token_program,
+            from,
+            mint,
+            authority,
+            &[],
+            amount,
+            decimals,
+        )?
+    };
+    Ok(ix)
+}
+
+/// The program owning `mint`, failing with `InvalidOwner` unless it is the token program or
+/// Token-2022.
+pub fn token_program_of(mint: &AccountInfo) -> Result<Pubkey> {
+    if *mint.owner != spl_token::id() && *mint.owner != spl_token_2022::id() {
+        return Err(SolitaireError::InvalidOwner(*mint.owner));
+    }
+    Ok(*mint.owner)
+}
+
+/// Check that all `accounts` belong to `token_program`, so tokens of one program are never moved
+/// through accounts of the other.
+pub fn require_token_program(token_program: &Pubkey, accounts: &[&AccountInfo]) -> Result<()> {
+    match accounts
+        .iter()
+        .find(|account| account.owner != token_program)
+    {
+        Some(account) => Err(SolitaireError::InvalidOwner(*account.owner)),
+        None => Ok(()),
+    }
+}
+
+/// Extension types of Token-2022 mints as numbered on chain. Token-2022 gained some of them after
+/// the version the bridge builds against, so they are looked up in the raw account data.
+const EXTENSION_TRANSFER_FEE_CONFIG: u16 = 1;
+const EXTENSION_MINT_CLOSE_AUTHORITY: u16 = 3;
+const EXTENSION_NON_TRANSFERABLE: u16 = 9;
+const EXTENSION_PERMANENT_DELEGATE: u16 = 12;
+const EXTENSION_TRANSFER_HOOK: u16 = 14;
+const EXTENSION_METADATA_POINTER: u16 = 18;
+const EXTENSION_TOKEN_METADATA: u16 = 19;
+
+/// The extensions of the Token-2022 mint holding `data`, as pairs of type and value.
+pub fn mint_extensions(data: &[u8]) -> Result<Vec<(u16, &[u8])>> {
+    // Extensions follow the base state padded to the size of a token account, and the account
+    // type.
+    let mut tlv = match data.get(spl_token_2022::state::Account::LEN + 1..) {
+        Some(tlv) => tlv,
+        None => return Ok(Vec::new()),
+    };
+    let mut extensions = Vec::new();
+    while tlv.len() >= 4 {
+        let kind = u16::from_le_bytes([tlv[0], tlv[1]]);
+        let len = u16::from_le_bytes([tlv[2], tlv[3]]) as usize;
+        // Space left for extensions still to be added.
+        if kind == 0 {
+            break;
+        }
+        let value = tlv
+            .get(4..4 + len)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        extensions.push((kind, value));
+        tlv = &tlv[4 + len..];
+    }
+    Ok(extensions)
+}
+
+/// The value of the extension `extension` of the Token-2022 mint holding `data`, if it has one.
+pub fn mint_extension(data: &[u8], extension: u16) -> Result<Option<&[u8]>> {
+    Ok(mint_extensions(data)?
+        .into_iter()
+        .find(|(kind, _)| *kind == extension)
+        .map(|(_, value)| value))
+}
+
+/// The authority stored as the value of an extension, `None` if unset.
+fn optional_authority(value: &[u8]) -> Result<Option<Pubkey>> {
+    let authority = <[u8; 32]>::try_from(value).map_err(|_| ProgramError::InvalidAccountData)?;
+    let authority = Pubkey::new_from_array(authority);
+    Ok(Some(authority).filter(|authority| *authority != Pubkey::default()))
+}
+
+/// Fail unless the bridge can hold tokens of `mint` in custody. All extension policies live here,
+/// Token-2022 mints with any extension not listed are rejected.
+///
+/// * Transfer fees are charged by the token program, the bridge accounts for them.
+/// * Metadata and metadata pointers only describe the token.
+/// * A mint-close authority could close the mint while custody holds its tokens.
+/// * Non-transferable tokens cannot be moved into custody at all.
+/// * A permanent delegate can move tokens out of any account of the mint, custody included, which
+///   would leave wrapped tokens on other chains without backing.
+/// * Mints with a transfer hook require extra accounts on every transfer, which the bridge does
+///   not pass yet. Handlers ignore the accounts following their own, so a later version can take
+///   the accounts of the hook from there.
+pub fn assert_mint_bridgeable(mint: &AccountInfo) -> Result<()> {
+    if *mint.owner != spl_token_2022::id() {
+        return Ok(());
+    }
+
+    let data = mint.data.borrow();
+    for (kind, value) in mint_extensions(&data)? {
+        match kind {
+            EXTENSION_TRANSFER_FEE_CONFIG
+            | EXTENSION_METADATA_POINTER
+            | EXTENSION_TOKEN_METADATA => {}
+            EXTENSION_MINT_CLOSE_AUTHORITY => {
+                if let Some(authority) = optional_authority(value)? {
+                    msg!("Mint {} has close authority {}", mint.key, authority);
+                    return Err(UnsupportedMintCloseAuthority.into());
+                }
+            }
+            EXTENSION_NON_TRANSFERABLE => return Err(UnsupportedNonTransferable.into()),
+            EXTENSION_PERMANENT_DELEGATE => {
+                if let Some(delegate) = optional_authority(value)? {
+                    msg!("Mint {} has permanent delegate {}", mint.key, delegate);
+                    return Err(UnsupportedPermanentDelegate.into());
+                }
+            }
+            EXTENSION_TRANSFER_HOOK => return Err(UnsupportedTransferHook.into()),
+            _ => {
+                msg!("Mint {} has unsupported extension {}", mint.key, kind);
+                return Err(UnsupportedMintExtension.into());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Name and symbol of a token, from Token-2022 token metadata.
+pub struct TokenMetadata {
+    pub name: String,
+    pub symbol: String,
+}
+
+/// Leading fields of token metadata as the token metadata interface lays it out, the URI and
+/// additional fields following them are not needed.
+#[derive(BorshDeserialize)]
+struct TokenMetadataFields {
+    _update_authority: [u8; 32],
+    mint: Pubkey,
+    name: Vec<u8>,
+    symbol: Vec<u8>,
+}
+
+/// Seed of the discriminator of token metadata stored in an account of its own.
+const TOKEN_METADATA_DISCRIMINATOR_SEED: &[u8] = b"spl_token_metadata_interface:token_metadata";
+
+/// The metadata of the Token-2022 `mint`, `None` for mints without any. Metadata is read from the
+/// mint itself, or from the account its metadata pointer designates, which has to be one of
+/// `accounts`. The metadata must name `mint`, an account pointed at but not passed is skipped.
+/// Bytes of the name and symbol that are not UTF-8 are replaced rather than rejected.
+pub fn token_metadata(
+    mint: &AccountInfo,
+    accounts: &[AccountInfo],
+) -> Result<Option<TokenMetadata>> {
+    if *mint.owner != spl_token_2022::id() {
+        return Ok(None);
+    }
+
+    let data = mint.data.borrow();
+    let pointer = match mint_extension(&data, EXTENSION_METADATA_POINTER)? {
+        Some(pointer) if pointer.len() == 64 => optional_authority(&pointer[32..])?,
+        Some(_) => return Err(ProgramError::InvalidAccountData.into()),
+        None => None,
+    };
+    let fields = match pointer {
+        Some(address) if address != *mint.key => {
+            let account = match accounts.iter().find(|account| *account.key == address) {
+                Some(account) => account,
+                None => return Ok(None),
+            };
+            let data = account.data.borrow();
+            match external_token_metadata(&data)? {
+                Some(value) => TokenMetadataFields::deserialize(&mut &*value)?,
+                None => return Ok(None),
+            }
+        }
+        _ => match mint_extension(&data, EXTENSION_TOKEN_METADATA)? {
+            Some(value) => TokenMetadataFields::deserialize(&mut &*value)?,
+            None => return Ok(None),
+        },
+    };
+
+    if fields.mint != *mint.key {
+        return Err(InvalidMetadata.into());
+    }
+    Ok(Some(TokenMetadata {
+        name: String::from_utf8_lossy(&fields.name).into_owned(),
+        symbol: String::from_utf8_lossy(&fields.symbol).into_owned(),
+    }))
+}
+
+/// The token metadata held by an account of a metadata program, stored as entries of an 8 byte
+/// discriminator, a 4 byte length and the value.
+fn external_token_metadata(data: &[u8]) -> Result<Option<&[u8]>> {
+    let discriminator = hash(TOKEN_METADATA_DISCRIMINATOR_SEED);
+    let discriminator = &discriminator.as_ref()[..8];
+    let mut tlv = data;
+    while tlv.len() >= 12 {
+        let len = u32::from_le_bytes([tlv[8], tlv[9], tlv[10], tlv[11]]) as usize;
+        let value = tlv
+            .get(12..12 + len)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        if &tlv[..8] == discriminator {
+            return Ok(Some(value));
+        }
+        tlv = &tlv[12 + len..];
+    }
+    Ok(None)
+}
+
+/// Size of a token account of `mint` under `token_program`, including the extensions Token-2022
+/// requires for accounts of `mint`, such as the withheld amount of mints with transfer fees.
+pub fn account_len(token_program: &Pubkey, mint: &AccountInfo) -> Result<usize> {
+    if *token_program != spl_token_2022::id() {
+        return Ok(spl_token::state::Account::LEN);
+    }
+
+    // Of the extensions `assert_mint_bridgeable` accepts, only transfer fees need one on token
+    // accounts.
+    let data = mint.data.borrow();
+    let required = match mint_extension(&data, EXTENSION_TRANSFER_FEE_CONFIG)? {
+        Some(_) => vec![ExtensionType::TransferFeeAmount],
+        None => Vec::new(),
+    };
+    Ok(ExtensionType::get_account_len::<
+        spl_token_2022::state::Account,
+    >(&required))
+}
+
+/// Amount held by the token account `account` of either token program.
+pub fn balance(account: &AccountInfo) -> Result<u64> {
+    let data = account.data.borrow();
+    Ok(
+        StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?
+            .base
+            .amount,
+    )
+}
+
+/// The Token-2022 transfer fee `mint` charges in `epoch` on a transfer of `amount`, 0 for mints
+/// without the transfer fee extension.
+pub fn transfer_fee(mint: &AccountInfo, epoch: u64, amount: u64) -> Result<u64> {
+    match transfer_fee_config(mint)? {
+        Some(config) => config
+            .calculate_epoch_fee(epoch, amount)
+            .ok_or_else(|| AmountOverflow.into()),
+        None => Ok(0),
+    }
+}
+
+/// The amount to transfer for `net` tokens of `mint` to arrive in `epoch`, see [`transfer_fee`].
+pub fn amount_before_fee(mint: &AccountInfo, epoch: u64, net: u64) -> Result<u64> {
+    match transfer_fee_config(mint)? {
+        Some(config) => gross_amount(config.get_epoch_fee(epoch), net),
+        None => Ok(net),
+    }
+}
+
+/// The smallest amount that `fee` leaves `net` of.
+fn gross_amount(fee: &TransferFee, net: u64) -> Result<u64> {
+    let basis_points = u128::from(u16::from(fee.transfer_fee_basis_points));
+    if net == 0 || basis_points == 0 {
+        return Ok(net);
+    }
+
+    // The fee is the ceiling of the basis points of the amount, capped at the maximum fee.
+    let all = u128::from(MAX_FEE_BASIS_POINTS);
+    let capped = u128::from(net) + u128::from(u64::from(fee.maximum_fee));
+    let gross = match all.checked_sub(basis_points) {
+        Some(kept) if kept > 0 => {
+            let uncapped = (u128::from(net) * all + kept - 1) / kept;
+            uncapped.min(capped)
+        }
+        _ => capped,
+    };
+    u64::try_from(gross).map_err(|_| AmountOverflow.into())
+}
+
+fn transfer_fee_config(mint: &AccountInfo) -> Result<Option<TransferFeeConfig>> {
+    if *mint.owner != spl_token_2022::id() {
+        return Ok(None);
+    }
+
+    let data = mint.data.borrow();
+    match mint_extension(&data, EXTENSION_TRANSFER_FEE_CONFIG)? {
+        Some(config) => Ok(Some(*pod_from_bytes::<TransferFeeConfig>(config)?)),
+        None => Ok(None),
+    }
+}
+
+/// Create the associated token account `ata` of `wallet` for `mint`, paid by `payer`, unless it
+/// exists already, in which case it is verified like [`verify_ata`]. Accounts holding lamports
+/// but no data yet are created as well.
+///
+/// The associated token, token and system programs and the rent sysvar must be passed to the
+/// instruction.
+pub fn create_idempotent(
+    ctx: &ExecutionContext,
+    payer: &Pubkey,
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    ata: &AccountInfo,
+) -> Result<()> {
+    if !ata.data_is_empty() {
+        return verify_ata(ata, wallet, mint);
+    }
+    if *ata.key != get_associated_token_address(wallet, mint) {
+        return Err(InvalidAssociatedAccount.into());
+    }
+
+    let create_ix = spl_associated_token_account::instruction::create_associated_token_account(
+        payer, wallet, mint,
+    );
+    invoke(&create_ix, ctx.accounts)?;
+    Ok(())
+}
+
+/// Check that `ata` is the associated token account of `wallet` for `mint` and still belongs to
+/// `wallet`, its owner can be changed after creation.
+pub fn verify_ata(ata: &AccountInfo, wallet: &Pubkey, mint: &Pubkey) -> Result<()> {
+    if *ata.key != get_associated_token_address(wallet, mint) {
+        return Err(InvalidAssociatedAccount.into());
+    }
+    if *ata.owner != spl_token::id() {
+        return Err(SolitaireError::InvalidOwner(*ata.owner));
+    }
+
+    let account = spl_token::state::Account::unpack(&ata.data.borrow())?;
+    if account.mint != *mint {
+        return Err(InvalidMint.into());
+    }
+    if account.owner != *wallet {
+        return Err(WrongAccountOwner.into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TokenBridgeError;
+    use solana_program::{
+        instruction::AccountMeta,
+        program_option::COption,
+    };
+    use spl_token::state::{
+        Account,
+        AccountState,
+    };
+
+    fn token_account(mint: Pubkey, owner: Pubkey) -> Vec<u8> {
+        let mut data = vec![0; Account::LEN];
+        Account {
+            mint,
+            owner,
+            amount: 0,
+            delegate: COption::None,
+            state: AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        }
+        .pack_into_slice(&mut data);
+        data
+    }
+
+    /// Run the check against an account at `key`, owned by `program` and holding `data`.
+    fn verify(
+        key: Pubkey,
+        program: Pubkey,
+        mut data: Vec<u8>,
+        wallet: &Pubkey,
+        mint: &Pubkey,
+    ) -> Result<()> {
+        let mut lamports = 0;
+        let info = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &program,
+            false,
+            0,
+        );
+        verify_ata(&info, wallet, mint)
+    }
+
+    #[test]
+    fn test_verify_ata() {
+        let (wallet, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let ata = get_associated_token_address(&wallet, &mint);
+        assert!(verify(
+            ata,
+            spl_token::id(),
+            token_account(mint, wallet),
+            &wallet,
+            &mint
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_verify_ata_not_created() {
+        let (wallet, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let ata = get_associated_token_address(&wallet, &mint);
+        assert!(matches!(
+            verify(ata, Pubkey::default(), vec![], &wallet, &mint),
+            Err(SolitaireError::InvalidOwner(owner)) if owner == Pubkey::default()
+        ));
+    }
+
+    #[test]
+    fn test_verify_ata_wrong_mint() {
+        let (wallet, mint, other_mint) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+
+        // The associated account of another mint.
+        let other_ata = get_associated_token_address(&wallet, &other_mint);
+        assert!(matches!(
+            verify(
+                other_ata,
+                spl_token::id(),
+                token_account(other_mint, wallet),
+                &wallet,
+                &mint
+            ),
+            Err(SolitaireError::Custom(code)) if code == InvalidAssociatedAccount as u64
+        ));
+
+        // An account at the right address holding another mint.
+        let ata = get_associated_token_address(&wallet, &mint);
+        assert!(matches!(
+            verify(ata, spl_token::id(), token_account(other_mint, wallet), &wallet, &mint),
+            Err(SolitaireError::Custom(code)) if code == InvalidMint as u64
+        ));
+    }
+
+    #[test]
+    fn test_verify_ata_reassigned() {
+        let (wallet, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let ata = get_associated_token_address(&wallet, &mint);
+        assert!(matches!(
+            verify(
+                ata,
+                spl_token::id(),
+                token_account(mint, Pubkey::new_unique()),
+                &wallet,
+                &mint
+            ),
+            Err(SolitaireError::Custom(code)) if code == WrongAccountOwner as u64
+        ));
+    }
+
+    #[test]
+    fn test_token_program_of() {
+        let key = Pubkey::new_unique();
+        for (owner, expected) in [
+            (spl_token::id(), Some(spl_token::id())),
+            (spl_token_2022::id(), Some(spl_token_2022::id())),
+            (Pubkey::new_unique(), None),
+        ] {
+            let (mut lamports, mut data) = (0, vec![]);
+            let mint = AccountInfo::new(
+                &key,
+                false,
+                false,
+                &mut lamports,
+                &mut data,
+                &owner,
+                false,
+                0,
+            );
+            assert_eq!(token_program_of(&mint).ok(), expected);
+        }
+    }
+
+    #[test]
+    fn test_checked_instructions_match_classic() {
+        let (from, to, mint, authority) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let build = |token_program: &Pubkey| {
+            [
+                transfer_checked_ix(token_program, &from, &to, &mint, &authority, 1003, 9).unwrap(),
+                mint_to_checked_ix(token_program, &mint, &to, &authority, 1003, 9).unwrap(),
+                burn_checked_ix(token_program, &from, &mint, &authority, 1003, 9).unwrap(),
+            ]
+        };
+
+        let classic = build(&spl_token::id());
+        let token_program = spl_token::id();
+        assert_eq!(
+            classic,
+            [
+                spl_token::instruction::transfer_checked(
+                    &token_program,
+                    &from,
+                    &mint,
+                    &to,
+                    &authority,
+                    &[],
+                    1003,
+                    9
+                )
+                .unwrap(),
+                spl_token::instruction::mint_to_checked(
+                    &token_program,
+                    &mint,
+                    &to,
+                    &authority,
+                    &[],
+                    1003,
+                    9
+                )
+                .unwrap(),
+                spl_token::instruction::burn_checked(
+                    &token_program,
+                    &from,
+                    &mint,
+                    &authority,
+                    &[],
+                    1003,
+                    9
+                )
+                .unwrap(),
+            ]
+        );
+
+        // The same accounts as the unchecked instructions, the transfer taking the mint as well.
+        let unchecked = [
+            spl_token::instruction::transfer(&token_program, &from, &to, &authority, &[], 1003)
+                .unwrap(),
+            spl_token::instruction::mint_to(&token_program, &mint, &to, &authority, &[], 1003)
+                .unwrap(),
+            spl_token::instruction::burn(&token_program, &from, &mint, &authority, &[], 1003)
+                .unwrap(),
+        ];
+        let mut transfer_accounts = unchecked[0].accounts.clone();
+        transfer_accounts.insert(1, AccountMeta::new_readonly(mint, false));
+        assert_eq!(classic[0].accounts, transfer_accounts);
+        assert_eq!(classic[1].accounts, unchecked[1].accounts);
+        assert_eq!(classic[2].accounts, unchecked[2].accounts);
+
+        // Token-2022 shares the layout of the token program, only the program differs.
+        for (token_2022, classic) in build(&spl_token_2022::id()).iter().zip(&classic) {
+            assert_eq!(token_2022.program_id, spl_token_2022::id());
+            assert_eq!(token_2022.accounts, classic.accounts);
+            assert_eq!(token_2022.data, classic.data);
+        }
+    }
+
+    #[test]
+    fn test_require_token_program() {
+        let (key, other_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut lamports, mut data) = (0, vec![]);
+        let (mut other_lamports, mut other_data) = (0, vec![]);
+        let token_program = spl_token::id();
+        let token_2022 = spl_token_2022::id();
+        let account = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &token_program,
+            false,
+            0,
+        );
+        let other = AccountInfo::new(
+            &other_key,
+            false,
+            false,
+            &mut other_lamports,
+            &mut other_data,
+            &token_2022,
+            false,
+            0,
+        );
+
+        assert!(require_token_program(&spl_token::id(), &[&account]).is_ok());
+        assert!(matches!(
+            require_token_program(&spl_token::id(), &[&account, &other]),
+            Err(SolitaireError::InvalidOwner(owner)) if owner == spl_token_2022::id()
+        ));
+    }
+
+    #[test]
+    fn test_gross_amount() {
+        for (basis_points, maximum_fee) in [
+            (0u16, 0u64),
+            (1, u64::MAX),
+            (100, u64::MAX),
+            (100, 3),
+            (9_999, 50),
+        ] {
+            let fee = TransferFee {
+                epoch: 0u64.into(),
+                maximum_fee: maximum_fee.into(),
+                transfer_fee_basis_points: basis_points.into(),
+            };
+            for net in [0, 1, 99, 100, 101, 12_345, 1_000_000] {
+                let gross = gross_amount(&fee, net).unwrap();
+                assert_eq!(gross - fee.calculate_fee(gross).unwrap(), net);
+                if gross > net {
+                    let less = gross - 1;
+                    assert!(less - fee.calculate_fee(less).unwrap() < net);
+                }
+            }
+        }
+
+        // Nothing can be left once the fee takes it all.
+        let fee = TransferFee {
+            epoch: 0u64.into(),
+            maximum_fee: u64::MAX.into(),
+            transfer_fee_basis_points: MAX_FEE_BASIS_POINTS.into(),
+        };
+        assert!(gross_amount(&fee, 1).is_err());
+    }
+
+    /// Run the check against a Token-2022 mint with the extensions `tlv`.
+    fn check_mint(tlv: &[u8]) -> Result<()> {
+        let (key, owner) = (Pubkey::new_unique(), spl_token_2022::id());
+        let mut data = vec![0; Account::LEN + 1];
+        data[Account::LEN] = 1;
+        data.extend_from_slice(tlv);
+        let mut lamports = 0;
+        let info = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+        assert_mint_bridgeable(&info)
+    }
+
+    fn is_error(result: Result<()>, expected: TokenBridgeError) -> bool {
+        matches!(result, Err(SolitaireError::Custom(code)) if code == expected as u64)
+    }
+
+    #[test]
+    fn test_assert_mint_bridgeable() {
+        assert!(check_mint(&[]).is_ok());
+        assert!(check_mint(&[1, 0, 4, 0, 0, 0, 0, 0]).is_ok());
+        assert!(check_mint(&[18, 0, 2, 0, 1, 1, 19, 0, 2, 0, 1, 1]).is_ok());
+
+        // Authorities are only a concern once set.
+        let mut close_authority = vec![3, 0, 32, 0];
+        close_authority.extend_from_slice(&[0; 32]);
+        assert!(check_mint(&close_authority).is_ok());
+        close_authority[4..].copy_from_slice(&[1; 32]);
+        assert!(is_error(
+            check_mint(&close_authority),
+            UnsupportedMintCloseAuthority
+        ));
+
+        let mut permanent_delegate = vec![12, 0, 32, 0];
+        permanent_delegate.extend_from_slice(&[0; 32]);
+        assert!(check_mint(&permanent_delegate).is_ok());
+        permanent_delegate[4..].copy_from_slice(&[1; 32]);
+        assert!(is_error(
+            check_mint(&permanent_delegate),
+            UnsupportedPermanentDelegate
+        ));
+
+        assert!(is_error(
+            check_mint(&[9, 0, 0, 0]),
+            UnsupportedNonTransferable
+        ));
+        assert!(is_error(
+            check_mint(&[14, 0, 2, 0, 1, 1]),
+            UnsupportedTransferHook
+        ));
+        // Extensions without a policy are rejected, interest-bearing mints here.
+        assert!(is_error(
+            check_mint(&[10, 0, 2, 0, 1, 1]),
+            UnsupportedMintExtension
+        ));
+    }
+
+    #[test]
+    fn test_mint_extension() {
+        // Base mint padded to the size of a token account, followed by the account type.
+        let mut data = vec![0; Account::LEN + 1];
+        assert_eq!(
+            mint_extension(&data, EXTENSION_TRANSFER_HOOK).unwrap(),
+            None
+        );
+
+        data.extend_from_slice(&[3, 0, 32, 0]);
+        data.extend_from_slice(&[1; 32]);
+        data.extend_from_slice(&[14, 0, 2, 0, 7, 8]);
+        assert_eq!(
+            mint_extension(&data, EXTENSION_TRANSFER_HOOK).unwrap(),
+            Some(&[7, 8][..])
+        );
+        assert_eq!(mint_extension(&data, 12).unwrap(), None);
+
+        // Uninitialized space ends the extensions.
+        let mut padded = vec![0; Account::LEN + 1];
+        padded.extend_from_slice(&[0; 8]);
+        padded.extend_from_slice(&[14, 0, 0, 0]);
+        assert_eq!(
+            mint_extension(&padded, EXTENSION_TRANSFER_HOOK).unwrap(),
+            None
+        );
+
+        // Lengths past the end of the account are rejected.
+        data.extend_from_slice(&[12, 0, 32, 0, 1]);
+        assert!(mint_extension(&data, 12).is_err());
+    }
+}
@@ -1,30 +1,92 @@
+use crate::TokenBridgeError::AmountOverflow;
 use borsh::{
     BorshDeserialize,
     BorshSerialize,
 };
+use bridge::types::ForeignEmitter;
+use primitive_types::U256;
 use serde::{
     Deserialize,
     Serialize,
 };
-use solana_program::pubkey::Pubkey;
+use solana_program::{
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
 use solitaire::{
     pack_type,
     processors::seeded::{
         AccountOwner,
         Owned,
     },
+    Result,
+    SolitaireError,
 };
 use spl_token::state::{
     Account,
     Mint,
 };
+use spl_token_2022::extension::StateWithExtensions;
+use std::{
+    convert::TryFrom,
+    io::{
+        self,
+        Write,
+    },
+};
 
 pub type Address = [u8; 32];
 pub type ChainID = u16;
 
-#[derive(Default, Clone, Copy, BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[derive(Default, Clone, Copy, Serialize, Deserialize)]
 pub struct Config {
     pub wormhole_bridge: Pubkey,
+
+    /// Outbound transfers and attestations are rejected while set, redemptions are unaffected.
+    pub paused: bool,
+}
+
+// The flag is only stored while set, so configs created before it existed keep deserializing and
+// an unpaused config keeps its original size. `set_paused` resizes the account accordingly.
+impl BorshSerialize for Config {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.wormhole_bridge.serialize(writer)?;
+        write_flag(writer, self.paused)
+    }
+}
+
+impl BorshDeserialize for Config {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        let wormhole_bridge = Pubkey::deserialize(buf)?;
+        let paused = read_flag(buf);
+        Ok(Config {
+            wormhole_bridge,
+            paused,
+        })
+    }
+}
+
+/// Version of the trailer holding the flag of a `Config` or `EndpointRegistration`, stored before
+/// the flag itself.
+const FLAG_VERSION: u8 = 1;
+
+/// Write the trailer of a flag that is set, nothing for one that is not.
+fn write_flag<W: Write>(writer: &mut W, flag: bool) -> io::Result<()> {
+    if flag {
+        writer.write_all(&[FLAG_VERSION, true as u8])?;
+    }
+    Ok(())
+}
+
+/// Read the flag from the trailer of an account. The trailer is only recognized as the exact end of
+/// the data, accounts resizing to the encoding when a flag changes. Anything else following the
+/// fields, such as the padding of historical accounts, is left to `Legacy` and reads as unset.
+fn read_flag(buf: &mut &[u8]) -> bool {
+    if *buf == [FLAG_VERSION, true as u8] {
+        *buf = &[];
+        return true;
+    }
+    false
 }
 
 #[cfg(not(feature = "cpi"))]
@@ -42,10 +104,45 @@ impl Owned for Config {
     }
 }
 
-#[derive(Default, Clone, Copy, BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[derive(Default, Clone, Copy, Serialize, Deserialize)]
 pub struct EndpointRegistration {
     pub chain: ChainID,
     pub contract: Address,
+
+    /// VAAs of the endpoint are rejected while set, until governance registers it again.
+    pub deregistered: bool,
+}
+
+impl EndpointRegistration {
+    /// The token bridge this registration is for.
+    pub fn emitter(&self) -> ForeignEmitter {
+        ForeignEmitter {
+            chain: self.chain,
+            address: self.contract,
+        }
+    }
+}
+
+// Encoded like the paused flag of `Config`, only stored while set.
+impl BorshSerialize for EndpointRegistration {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.chain.serialize(writer)?;
+        self.contract.serialize(writer)?;
+        write_flag(writer, self.deregistered)
+    }
+}
+
+impl BorshDeserialize for EndpointRegistration {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        let chain = ChainID::deserialize(buf)?;
+        let contract = Address::deserialize(buf)?;
+        let deregistered = read_flag(buf);
+        Ok(EndpointRegistration {
+            chain,
+            contract,
+            deregistered,
+        })
+    }
 }
 
 #[cfg(not(feature = "cpi"))]
@@ -87,3 +184,285 @@ impl Owned for WrappedMeta {
 
 pack_type!(SplMint, Mint, AccountOwner::Other(spl_token::id()));
 pack_type!(SplAccount, Account, AccountOwner::Other(spl_token::id()));
+
+/// Like `pack_type!`, for the state of either the token program or Token-2022. Both share the same
+/// base layout, Token-2022 extensions following it are skipped when loading and read from the
+/// account by the handlers that support them. The owner is not checked when loading, see
+/// `token::token_program_of`.
+macro_rules! interface_type {
+    ($name:ident, $embed:ty) => {
+        pub struct $name(pub $embed);
+
+        impl BorshDeserialize for $name {
+            fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+                let state = StateWithExtensions::<$embed>::unpack(*buf)
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+                let acc = $name(state.base);
+                // Extensions were skipped rather than read, Borsh must see all data consumed.
+                *buf = &buf[..0];
+
+                Ok(acc)
+            }
+        }
+
+        impl BorshSerialize for $name {
+            fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+                let mut data = [0u8; <$embed as Pack>::LEN];
+                Pack::pack_into_slice(&self.0, &mut data);
+                writer.write_all(&data)
+            }
+        }
+
+        impl Owned for $name {
+            fn owner(&self) -> AccountOwner {
+                AccountOwner::Any
+            }
+        }
+
+        impl std::ops::Deref for $name {
+            type Target = $embed;
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                $name(<$embed>::default())
+            }
+        }
+    };
+}
+
+interface_type!(SplInterfaceMint, spl_token_2022::state::Mint);
+interface_type!(SplInterfaceAccount, spl_token_2022::state::Account);
+
+/// Decimals amounts are carried with on the wire, tokens with more are truncated to these.
+pub const NORMALIZED_DECIMALS: u8 = 8;
+
+/// Amount of a transfer as carried on the wire, in units of at most `NORMALIZED_DECIMALS`
+/// decimals. Tokens with fewer decimals are carried as is.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NormalizedAmount(pub u64);
+
+impl NormalizedAmount {
+    /// Truncate `raw`, in units of a token with `decimals` decimals, returning the dust dropped
+    /// along with the amount. Tokens with more than 27 decimals normalize to zero.
+    pub fn normalize(raw: u64, decimals: u8) -> (NormalizedAmount, u64) {
+        match scale(decimals) {
+            Some(scale) => (NormalizedAmount(raw / scale), raw % scale),
+            None => (NormalizedAmount(0), raw),
+        }
+    }
+
+    /// The amount in units of a token with `decimals` decimals, failing if it does not fit.
+    pub fn denormalize(self, decimals: u8) -> Result<u64> {
+        scale(decimals)
+            .and_then(|scale| self.0.checked_mul(scale))
+            .ok_or_else(|| AmountOverflow.into())
+    }
+}
+
+/// Factor between amounts of a token with `decimals` decimals and normalized amounts, `None` if
+/// it exceeds a u64.
+fn scale(decimals: u8) -> Option<u64> {
+    10u64.checked_pow(u32::from(decimals.saturating_sub(NORMALIZED_DECIMALS)))
+}
+
+impl From<NormalizedAmount> for U256 {
+    fn from(amount: NormalizedAmount) -> U256 {
+        U256::from(amount.0)
+    }
+}
+
+impl TryFrom<U256> for NormalizedAmount {
+    type Error = SolitaireError;
+
+    fn try_from(amount: U256) -> Result<NormalizedAmount> {
+        if amount > U256::from(u64::MAX) {
+            return Err(AmountOverflow.into());
+        }
+        Ok(NormalizedAmount(amount.as_u64()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solitaire::Legacy;
+
+    #[test]
+    fn test_normalized_amount_round_trip() {
+        let mut values = vec![0, 1, 9, 10, 99_999_999, 100_000_000, u64::MAX - 1, u64::MAX];
+        let mut x = 0x2545_f491_4f6c_dd1du64;
+        for _ in 0..1000 {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            values.push(x);
+            values.push(x >> (x % 64));
+        }
+
+        for decimals in 0..=18 {
+            for &raw in &values {
+                let (amount, dust) = NormalizedAmount::normalize(raw, decimals);
+                assert_eq!(amount.denormalize(decimals).unwrap() + dust, raw);
+                if decimals <= NORMALIZED_DECIMALS {
+                    assert_eq!((amount, dust), (NormalizedAmount(raw), 0));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_normalized_amount_truncation() {
+        assert_eq!(
+            NormalizedAmount::normalize(1_234_567_891, 9),
+            (NormalizedAmount(123_456_789), 1)
+        );
+        assert_eq!(
+            NormalizedAmount(123_456_789).denormalize(9).unwrap(),
+            1_234_567_890
+        );
+        assert_eq!(
+            NormalizedAmount::normalize(u64::MAX, 255),
+            (NormalizedAmount(0), u64::MAX)
+        );
+        assert!(NormalizedAmount(u64::MAX).denormalize(9).is_err());
+        assert!(NormalizedAmount(1).denormalize(28).is_err());
+    }
+
+    #[test]
+    fn test_normalized_amount_wire() {
+        let amount = NormalizedAmount(1003);
+        assert_eq!(U256::from(amount), U256::from(1003));
+        assert_eq!(
+            NormalizedAmount::try_from(U256::from(1003)).unwrap(),
+            amount
+        );
+        assert_eq!(
+            NormalizedAmount::try_from(U256::from(u64::MAX)).unwrap(),
+            NormalizedAmount(u64::MAX)
+        );
+        assert!(NormalizedAmount::try_from(U256::from(u64::MAX) + 1).is_err());
+    }
+
+    #[test]
+    fn test_config_paused_encoding() {
+        let wormhole_bridge = Pubkey::new_unique();
+
+        // Configs written before the flag existed only hold the bridge address.
+        let legacy = wormhole_bridge.try_to_vec().unwrap();
+        let config = Config::try_from_slice(&legacy).unwrap();
+        assert_eq!(config.wormhole_bridge, wormhole_bridge);
+        assert!(!config.paused);
+        assert_eq!(config.try_to_vec().unwrap(), legacy);
+
+        let paused = Config {
+            wormhole_bridge,
+            paused: true,
+        };
+        let data = paused.try_to_vec().unwrap();
+        assert_eq!(data.len(), legacy.len() + 2);
+        assert!(Config::try_from_slice(&data).unwrap().paused);
+    }
+
+    #[test]
+    fn test_endpoint_deregistered_encoding() {
+        let mut endpoint = EndpointRegistration {
+            chain: 2,
+            contract: [1u8; 32],
+            deregistered: false,
+        };
+        let registered = endpoint.try_to_vec().unwrap();
+        assert_eq!(registered.len(), 2 + 32);
+        assert!(
+            !EndpointRegistration::try_from_slice(&registered)
+                .unwrap()
+                .deregistered
+        );
+
+        endpoint.deregistered = true;
+        let deregistered = endpoint.try_to_vec().unwrap();
+        assert_eq!(deregistered.len(), registered.len() + 2);
+        assert!(
+            EndpointRegistration::try_from_slice(&deregistered)
+                .unwrap()
+                .deregistered
+        );
+    }
+
+    /// Account data followed by zeroed padding, as left by allocating more than the encoding.
+    fn padded(mut data: Vec<u8>) -> Vec<u8> {
+        data.extend_from_slice(&[0u8; 8]);
+        data
+    }
+
+    #[test]
+    fn test_legacy_accounts_with_trailing_bytes() {
+        let config = padded([3u8; 32].to_vec());
+        assert!(Config::try_from_slice(&config).is_err());
+        let loaded = Legacy::<Config>::try_from_slice(&config).unwrap();
+        assert_eq!(loaded.wormhole_bridge, Pubkey::new_from_array([3u8; 32]));
+        assert!(!loaded.paused);
+
+        // Persisting the account again leaves the padding as it is.
+        let mut data = config.clone();
+        loaded.serialize(&mut &mut data[..]).unwrap();
+        assert_eq!(data, config);
+
+        let mut meta = 2u16.to_le_bytes().to_vec();
+        meta.extend_from_slice(&[0xaa; 32]);
+        meta.push(18);
+        let meta = padded(meta);
+        assert!(WrappedMeta::try_from_slice(&meta).is_err());
+        let loaded = Legacy::<WrappedMeta>::try_from_slice(&meta).unwrap();
+        assert_eq!(
+            (loaded.chain, loaded.token_address, loaded.original_decimals),
+            (2, [0xaa; 32], 18)
+        );
+
+        let mut registration = 2u16.to_le_bytes().to_vec();
+        registration.extend_from_slice(&[5u8; 32]);
+        let registration = padded(registration);
+        assert!(EndpointRegistration::try_from_slice(&registration).is_err());
+        let loaded = Legacy::<EndpointRegistration>::try_from_slice(&registration).unwrap();
+        assert_eq!((loaded.chain, loaded.contract), (2, [5u8; 32]));
+        assert!(!loaded.deregistered);
+    }
+
+    #[test]
+    fn test_legacy_padding_is_not_a_flag() {
+        let mut config = [3u8; 32].to_vec();
+        let mut registration = 2u16.to_le_bytes().to_vec();
+        registration.extend_from_slice(&[5u8; 32]);
+
+        // Bytes left over by historical encodings, including ones that start like a set flag.
+        for trailing in [
+            vec![1],
+            vec![1, 1, 0],
+            vec![FLAG_VERSION, 2],
+            vec![0xff; 8],
+            vec![true as u8, FLAG_VERSION, true as u8],
+        ] {
+            let data = [config.as_slice(), &trailing].concat();
+            assert!(!Legacy::<Config>::try_from_slice(&data).unwrap().paused);
+            let data = [registration.as_slice(), &trailing].concat();
+            assert!(
+                !Legacy::<EndpointRegistration>::try_from_slice(&data)
+                    .unwrap()
+                    .deregistered
+            );
+        }
+
+        // Resized to their encoding, set flags are found as the exact end of the data.
+        config.extend_from_slice(&[FLAG_VERSION, true as u8]);
+        assert!(Legacy::<Config>::try_from_slice(&config).unwrap().paused);
+        registration.extend_from_slice(&[FLAG_VERSION, true as u8]);
+        assert!(
+            Legacy::<EndpointRegistration>::try_from_slice(&registration)
+                .unwrap()
+                .deregistered
+        );
+    }
+}
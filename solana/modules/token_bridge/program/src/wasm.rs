@@ -13,7 +13,10 @@ use crate::{
         complete_native,
         complete_wrapped,
         create_wrapped,
+        deregister_chain,
         register_chain,
+        set_metadata_authority,
+        set_paused,
         transfer_native,
         transfer_native_with_payload,
         transfer_wrapped,
@@ -21,6 +24,9 @@ use crate::{
         upgrade_contract,
     },
     messages::{
+        GovernancePayloadDeregisterChain,
+        GovernancePayloadSetMetadataAuthority,
+        GovernancePayloadSetPaused,
         GovernancePayloadUpgrade,
         PayloadAssetMeta,
         PayloadGovernanceRegisterChain,
@@ -49,6 +55,7 @@ use bridge::{
 };
 use solana_program::pubkey::Pubkey;
 use solitaire::{
+    legacy_deserialize,
     processors::seeded::Seeded,
     AccountState,
 };
@@ -70,7 +77,7 @@ pub fn attest_ix(
     let message = Pubkey::from_str(message.as_str()).unwrap();
     let mint = Pubkey::from_str(mint.as_str()).unwrap();
 
-    let ix = attest(program_id, bridge_id, payer, message, mint, nonce).unwrap();
+    let ix = attest(program_id, bridge_id, payer, message, mint, None, nonce).unwrap();
 
     JsValue::from_serde(&ix).unwrap()
 }
@@ -106,6 +113,7 @@ pub fn transfer_native_ix(
         message,
         from,
         mint,
+        spl_token::id(),
         TransferNativeData {
             nonce,
             amount,
@@ -150,6 +158,7 @@ pub fn transfer_native_with_payload_ix(
         message,
         from,
         mint,
+        spl_token::id(),
         TransferNativeWithPayloadData {
             nonce,
             amount,
@@ -201,6 +210,7 @@ pub fn transfer_wrapped_ix(
         from_owner,
         token_chain,
         token_addr,
+        spl_token::id(),
         TransferWrappedData {
             nonce,
             amount,
@@ -251,6 +261,7 @@ pub fn transfer_wrapped_with_payload_ix(
         from_owner,
         token_chain,
         token_addr,
+        spl_token::id(),
         TransferWrappedWithPayloadData {
             nonce,
             amount,
@@ -309,6 +320,7 @@ pub fn complete_transfer_native_ix(
             None
         },
         Pubkey::new(&payload.token_address),
+        spl_token::id(),
         CompleteNativeData {},
     )
     .unwrap();
@@ -360,6 +372,7 @@ pub fn complete_transfer_wrapped_ix(
         } else {
             None
         },
+        spl_token::id(),
         CompleteWrappedData {},
     )
     .unwrap();
@@ -442,6 +455,92 @@ pub fn upgrade_contract_ix(
     return JsValue::from_serde(&ix).unwrap();
 }
 
+#[wasm_bindgen]
+pub fn set_paused_ix(
+    program_id: String,
+    bridge_id: String,
+    payer: String,
+    vaa: Vec<u8>,
+) -> JsValue {
+    let program_id = Pubkey::from_str(program_id.as_str()).unwrap();
+    let bridge_id = Pubkey::from_str(bridge_id.as_str()).unwrap();
+    let vaa = VAA::deserialize(vaa.as_slice()).unwrap();
+    // Only checked for well-formedness, the instruction does not depend on the decree.
+    GovernancePayloadSetPaused::deserialize(&mut vaa.payload.as_slice()).unwrap();
+    let message_key = bridge::accounts::PostedVAA::<'_, { AccountState::Uninitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: hash_vaa(&vaa.clone().into()).to_vec(),
+        },
+        &bridge_id,
+    );
+    let ix = set_paused(
+        program_id,
+        Pubkey::from_str(payer.as_str()).unwrap(),
+        message_key,
+        Pubkey::new(&vaa.emitter_address),
+        vaa.sequence,
+    );
+    return JsValue::from_serde(&ix).unwrap();
+}
+
+#[wasm_bindgen]
+pub fn set_metadata_authority_ix(
+    program_id: String,
+    bridge_id: String,
+    payer: String,
+    vaa: Vec<u8>,
+) -> JsValue {
+    let program_id = Pubkey::from_str(program_id.as_str()).unwrap();
+    let bridge_id = Pubkey::from_str(bridge_id.as_str()).unwrap();
+    let vaa = VAA::deserialize(vaa.as_slice()).unwrap();
+    let payload =
+        GovernancePayloadSetMetadataAuthority::deserialize(&mut vaa.payload.as_slice()).unwrap();
+    let message_key = bridge::accounts::PostedVAA::<'_, { AccountState::Uninitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: hash_vaa(&vaa.clone().into()).to_vec(),
+        },
+        &bridge_id,
+    );
+    let ix = set_metadata_authority(
+        program_id,
+        Pubkey::from_str(payer.as_str()).unwrap(),
+        message_key,
+        Pubkey::new(&vaa.emitter_address),
+        vaa.sequence,
+        payload.mint,
+    );
+    return JsValue::from_serde(&ix).unwrap();
+}
+
+#[wasm_bindgen]
+pub fn deregister_chain_ix(
+    program_id: String,
+    bridge_id: String,
+    payer: String,
+    vaa: Vec<u8>,
+) -> JsValue {
+    let program_id = Pubkey::from_str(program_id.as_str()).unwrap();
+    let bridge_id = Pubkey::from_str(bridge_id.as_str()).unwrap();
+    let vaa = VAA::deserialize(vaa.as_slice()).unwrap();
+    let payload =
+        GovernancePayloadDeregisterChain::deserialize(&mut vaa.payload.as_slice()).unwrap();
+    let message_key = bridge::accounts::PostedVAA::<'_, { AccountState::Uninitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: hash_vaa(&vaa.clone().into()).to_vec(),
+        },
+        &bridge_id,
+    );
+    let ix = deregister_chain(
+        program_id,
+        Pubkey::from_str(payer.as_str()).unwrap(),
+        message_key,
+        Pubkey::new(&vaa.emitter_address),
+        vaa.sequence,
+        payload,
+    );
+    return JsValue::from_serde(&ix).unwrap();
+}
+
 #[wasm_bindgen]
 pub fn register_chain_ix(
     program_id: String,
@@ -540,10 +639,10 @@ pub fn wrapped_meta_address(program_id: String, mint_address: Vec<u8>) -> Vec<u8
 
 #[wasm_bindgen]
 pub fn parse_wrapped_meta(data: Vec<u8>) -> JsValue {
-    JsValue::from_serde(&WrappedMeta::try_from_slice(data.as_slice()).unwrap()).unwrap()
+    JsValue::from_serde(&legacy_deserialize::<WrappedMeta>(&data).unwrap()).unwrap()
 }
 
 #[wasm_bindgen]
 pub fn parse_endpoint_registration(data: Vec<u8>) -> JsValue {
-    JsValue::from_serde(&EndpointRegistration::try_from_slice(data.as_slice()).unwrap()).unwrap()
+    JsValue::from_serde(&legacy_deserialize::<EndpointRegistration>(&data).unwrap()).unwrap()
 }
@@ -9,7 +9,10 @@ use libsecp256k1::{
 };
 use sha3::Digest;
 use solana_program::{
-    instruction::Instruction,
+    instruction::{
+        AccountMeta,
+        Instruction,
+    },
     program_pack::Pack,
     pubkey::Pubkey,
     system_instruction,
@@ -20,6 +23,7 @@ use solana_program_test::{
     ProgramTest,
 };
 use solana_sdk::{
+    account::Account,
     commitment_config::CommitmentLevel,
     rent::Rent,
     secp256k1_instruction::new_secp256k1_instruction,
@@ -64,21 +68,58 @@ pub async fn execute<T: Signers>(
         .await
 }
 
+/// Like `execute`, but hands back the program logs so tests can inspect emitted events.
+pub async fn execute_with_logs<T: Signers>(
+    client: &mut BanksClient,
+    payer: &Keypair,
+    signers: &T,
+    instructions: &[Instruction],
+) -> Result<Vec<String>, BanksClientError> {
+    let mut transaction = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+    let recent_blockhash = client.get_latest_blockhash().await?;
+    transaction.sign(signers, recent_blockhash);
+    let processed = client
+        .process_transaction_with_metadata(transaction)
+        .await?;
+    processed.result?;
+    Ok(processed
+        .metadata
+        .map(|metadata| metadata.log_messages)
+        .unwrap_or_default())
+}
+
 mod helpers {
     use super::*;
     use bridge::{
-        accounts::FeeCollector,
         types::ConsistencyLevel,
         PostVAAData,
     };
+    use solana_program::{
+        account_info::AccountInfo,
+        entrypoint::ProgramResult,
+        program::{
+            get_return_data,
+            invoke,
+        },
+        program_error::ProgramError,
+    };
     use solana_program_test::processor;
+    use spl_token_2022::extension::{
+        transfer_fee,
+        ExtensionType,
+        StateWithExtensions,
+    };
     use token_bridge::{
+        cpi,
+        AttestTokenData,
         CompleteNativeData,
         CompleteNativeWithPayloadData,
         CompleteWrappedData,
         CreateWrappedData,
+        QueryRegisteredEmitterData,
         RegisterChainData,
         TransferNativeData,
+        TransferNativeWithPayloadData,
         TransferWrappedData,
     };
 
@@ -116,10 +157,9 @@ mod helpers {
         )
     }
 
-    /// Initialize the test environment, spins up a solana-test-validator in the background so that
-    /// each test has a fresh environment to work within.
-    pub async fn setup() -> (BanksClient, Keypair, Pubkey, Pubkey) {
-        let (program, token_program) = (
+    /// Addresses of the core bridge and the token bridge under test.
+    pub fn program_ids() -> (Pubkey, Pubkey) {
+        (
             env::var("BRIDGE_PROGRAM")
                 .unwrap_or_else(|_| "Bridge1p5gheXUvJ6jGWGeCsgPKgnE3YgdGKRVCMY9o".to_string())
                 .parse::<Pubkey>()
@@ -128,15 +168,39 @@ mod helpers {
                 .unwrap_or_else(|_| "B6RHG3mfcckmrYN1UhmJzyS1XX3fZKbkeUcpJe9Sy3FE".to_string())
                 .parse::<Pubkey>()
                 .unwrap(),
-        );
+        )
+    }
+
+    /// Initialize the test environment, spins up a solana-test-validator in the background so that
+    /// each test has a fresh environment to work within.
+    pub async fn setup() -> (BanksClient, Keypair, Pubkey, Pubkey) {
+        setup_with_accounts(vec![]).await
+    }
+
+    /// Like `setup`, but starting from `accounts`, e.g. to recreate state left by earlier versions
+    /// of the programs.
+    pub async fn setup_with_accounts(
+        accounts: Vec<(Pubkey, Account)>,
+    ) -> (BanksClient, Keypair, Pubkey, Pubkey) {
+        let (program, token_program) = program_ids();
 
         let mut builder = ProgramTest::new("bridge", program, processor!(bridge::solitaire));
+        for (address, account) in accounts {
+            builder.add_account(address, account);
+        }
         builder.add_program("mpl_token_metadata", spl_token_metadata::id(), None);
+        builder.add_program(
+            "spl_token_2022",
+            spl_token_2022::id(),
+            processor!(spl_token_2022::processor::Processor::process),
+        );
         builder.add_program(
             "token_bridge",
             token_program,
             processor!(token_bridge::solitaire),
         );
+        builder.add_program("query_router", query_router_id(), processor!(query_router));
+        builder.add_program("token_router", token_router_id(), processor!(token_router));
 
         // Some instructions go over the limit when tracing is enabled but we need that for better
         // logging.  We don't really care about the limit during these tests anyway.
@@ -146,6 +210,182 @@ mod helpers {
         (client, payer, program, token_program)
     }
 
+    /// Address of the stand-in program used to exercise token bridge queries over CPI.
+    pub fn query_router_id() -> Pubkey {
+        "QueryRouter11111111111111111111111111111111"
+            .parse::<Pubkey>()
+            .unwrap()
+    }
+
+    /// Stand-in for a program consuming the token bridge over CPI. It asks the token bridge whether
+    /// an emitter is registered for a chain and fails unless the answer matches its instruction
+    /// data, laid out as `chain (u16 BE) | emitter ([u8; 32]) | registered (u8)`.
+    fn query_router(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+        let (token_bridge, endpoint) = (&accounts[0], &accounts[1]);
+        let chain = u16::from_be_bytes([data[0], data[1]]);
+        let mut emitter_address = [0u8; 32];
+        emitter_address.copy_from_slice(&data[2..34]);
+
+        let instruction = instructions::query_registered_emitter(
+            *token_bridge.key,
+            *endpoint.key,
+            QueryRegisteredEmitterData {
+                chain,
+                emitter_address,
+            },
+        )
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+        invoke(&instruction, &[endpoint.clone(), token_bridge.clone()])?;
+
+        let emitter = match get_return_data() {
+            Some((program, emitter)) if program == *token_bridge.key => emitter,
+            Some(_) => return Err(ProgramError::IncorrectProgramId),
+            None => vec![],
+        };
+        let expected: &[u8] = if data[34] != 0 { &emitter_address } else { &[] };
+        if emitter != expected {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(())
+    }
+
+    /// Address of the stand-in program used to exercise the token bridge `cpi` wrappers.
+    pub fn token_router_id() -> Pubkey {
+        "TokenRouter11111111111111111111111111111111"
+            .parse::<Pubkey>()
+            .unwrap()
+    }
+
+    /// PDA of the token router, it signs as its "sender" and "redeemer" PDAs.
+    pub fn token_router_pda(seed: &[u8]) -> Pubkey {
+        Pubkey::find_program_address(&[seed], &token_router_id()).0
+    }
+
+    /// Route a token bridge instruction through the token router. The router is passed the same
+    /// accounts and data, followed by the token bridge itself, and signs for its own PDAs.
+    pub fn route_through_token_router(instruction: Instruction) -> Instruction {
+        let router_pdas = [token_router_pda(b"sender"), token_router_pda(b"redeemer")];
+        let mut accounts: Vec<AccountMeta> = instruction
+            .accounts
+            .into_iter()
+            .map(|mut account| {
+                if router_pdas.contains(&account.pubkey) {
+                    account.is_signer = false;
+                }
+                account
+            })
+            .collect();
+        accounts.push(AccountMeta::new_readonly(instruction.program_id, false));
+
+        Instruction {
+            program_id: token_router_id(),
+            accounts,
+            data: instruction.data,
+        }
+    }
+
+    /// Stand-in for a program moving tokens through the token bridge with the `cpi` wrappers. Its
+    /// instruction data is that of the token bridge instruction to invoke, see
+    /// `route_through_token_router`.
+    fn token_router(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+        use token_bridge::instruction::Instruction::*;
+
+        let a = accounts;
+        let token_bridge = a.last().ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let (_, sender_bump) = Pubkey::find_program_address(&[b"sender"], program_id);
+        let (_, redeemer_bump) = Pubkey::find_program_address(&[b"redeemer"], program_id);
+
+        match data[0] {
+            n if n == AttestToken as u8 => cpi::attest_token(
+                cpi::AttestToken {
+                    payer: &a[0],
+                    config: &a[1],
+                    mint: &a[2],
+                    wrapped_meta: &a[3],
+                    spl_metadata: &a[4],
+                    token_metadata: None,
+                    bridge_config: &a[5],
+                    message: &a[6],
+                    emitter: &a[7],
+                    sequence: &a[8],
+                    fee_collector: &a[9],
+                    clock: &a[10],
+                    rent: &a[11],
+                    system_program: &a[12],
+                    core_bridge_program: &a[13],
+                    token_bridge_program: token_bridge,
+                    token_fee: None,
+                },
+                AttestTokenData::try_from_slice(&data[1..])?,
+                &[],
+            )?,
+            n if n == TransferNative as u8 => cpi::transfer_native(
+                transfer_native_accounts(a, 13),
+                TransferNativeData::try_from_slice(&data[1..])?,
+                &[],
+            )?,
+            n if n == TransferNativeWithPayload as u8 => cpi::transfer_native_with_payload(
+                transfer_native_accounts(a, 14),
+                &a[13],
+                TransferNativeWithPayloadData::try_from_slice(&data[1..])?,
+                &[&[b"sender", &[sender_bump]]],
+            )?,
+            n if n == CompleteNativeWithPayload as u8 => cpi::complete_native_with_payload(
+                cpi::CompleteNative {
+                    payer: &a[0],
+                    config: &a[1],
+                    vaa: &a[2],
+                    claim: &a[3],
+                    chain_registration: &a[4],
+                    to: &a[5],
+                    to_fees: &a[7],
+                    custody: &a[8],
+                    mint: &a[9],
+                    custody_signer: &a[10],
+                    rent: &a[11],
+                    system_program: &a[12],
+                    core_bridge_program: &a[13],
+                    token_program: &a[14],
+                    token_bridge_program: token_bridge,
+                },
+                &a[6],
+                CompleteNativeWithPayloadData::try_from_slice(&data[1..])?,
+                &[&[b"redeemer", &[redeemer_bump]]],
+            )?,
+            _ => return Err(ProgramError::InvalidInstructionData),
+        }
+        Ok(())
+    }
+
+    /// Accounts of a native transfer laid out as the token bridge expects them, `tail` being the
+    /// index of the rent sysvar which follows the sender on transfers with payload.
+    fn transfer_native_accounts<'a, 'b>(
+        a: &'a [AccountInfo<'b>],
+        tail: usize,
+    ) -> cpi::TransferNative<'a, 'b> {
+        cpi::TransferNative {
+            payer: &a[0],
+            config: &a[1],
+            from: &a[2],
+            mint: &a[3],
+            custody: &a[4],
+            authority_signer: &a[5],
+            custody_signer: &a[6],
+            bridge_config: &a[7],
+            message: &a[8],
+            emitter: &a[9],
+            sequence: &a[10],
+            fee_collector: &a[11],
+            clock: &a[12],
+            rent: &a[tail],
+            system_program: &a[tail + 1],
+            core_bridge_program: &a[tail + 2],
+            token_program: &a[tail + 3],
+            token_bridge_program: &a[tail + 4],
+            token_fee: None,
+        }
+    }
+
     /// Wait for a single transaction to fully finalize, guaranteeing chain state has been
     /// confirmed. Useful for consistently fetching data during state checks.
     #[allow(dead_code)]
@@ -181,6 +421,15 @@ mod helpers {
         client.get_account(account).await.unwrap().unwrap().lamports
     }
 
+    /// Fetch the amount held by a token account of either token program
+    pub async fn get_token_balance(client: &mut BanksClient, account: Pubkey) -> u64 {
+        let account = client.get_account(account).await.unwrap().unwrap();
+        StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+            .unwrap()
+            .base
+            .amount
+    }
+
     pub async fn initialize_bridge(
         client: &mut BanksClient,
         program: Pubkey,
@@ -252,6 +501,21 @@ mod helpers {
         message: &Keypair,
         mint: Pubkey,
         nonce: u32,
+    ) -> Result<(), BanksClientError> {
+        attest_with_token_metadata(client, program, bridge, payer, message, mint, None, nonce).await
+    }
+
+    /// Like `attest`, passing the account the metadata pointer of a Token-2022 `mint` designates.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn attest_with_token_metadata(
+        client: &mut BanksClient,
+        program: Pubkey,
+        bridge: Pubkey,
+        payer: &Keypair,
+        message: &Keypair,
+        mint: Pubkey,
+        token_metadata: Option<Pubkey>,
+        nonce: u32,
     ) -> Result<(), BanksClientError> {
         let instruction = instructions::attest(
             program,
@@ -259,6 +523,7 @@ mod helpers {
             payer.pubkey(),
             message.pubkey(),
             mint,
+            token_metadata,
             nonce,
         )
         .expect("Could not create Attest instruction");
@@ -288,6 +553,35 @@ mod helpers {
         from_owner: &Keypair,
         mint: Pubkey,
         amount: u64,
+    ) -> Result<(), BanksClientError> {
+        transfer_native_with_token_program(
+            client,
+            program,
+            bridge,
+            payer,
+            message,
+            from,
+            from_owner,
+            mint,
+            amount,
+            spl_token::id(),
+        )
+        .await
+    }
+
+    /// Like `transfer_native`, for mints owned by `token_program`.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn transfer_native_with_token_program(
+        client: &mut BanksClient,
+        program: Pubkey,
+        bridge: Pubkey,
+        payer: &Keypair,
+        message: &Keypair,
+        from: &Keypair,
+        from_owner: &Keypair,
+        mint: Pubkey,
+        amount: u64,
+        token_program: Pubkey,
     ) -> Result<(), BanksClientError> {
         let instruction = instructions::transfer_native(
             program,
@@ -296,11 +590,12 @@ mod helpers {
             message.pubkey(),
             from.pubkey(),
             mint,
+            token_program,
             TransferNativeData {
                 nonce: 0,
                 amount,
                 fee: 0,
-                target_address: [0u8; 32],
+                target_address: [1u8; 32],
                 target_chain: 2,
             },
         )
@@ -310,22 +605,32 @@ mod helpers {
             println!("{}: {}", account.0, account.1.pubkey);
         }
 
+        let authority_signer = token_bridge::accounts::AuthoritySigner::key(None, &program);
+        let approve = if token_program == spl_token_2022::id() {
+            spl_token_2022::instruction::approve(
+                &token_program,
+                &from.pubkey(),
+                &authority_signer,
+                &from_owner.pubkey(),
+                &[],
+                amount,
+            )
+        } else {
+            spl_token::instruction::approve(
+                &token_program,
+                &from.pubkey(),
+                &authority_signer,
+                &from_owner.pubkey(),
+                &[],
+                amount,
+            )
+        };
+
         execute(
             client,
             payer,
             &[payer, from_owner, message],
-            &[
-                spl_token::instruction::approve(
-                    &spl_token::id(),
-                    &from.pubkey(),
-                    &token_bridge::accounts::AuthoritySigner::key(None, &program),
-                    &from_owner.pubkey(),
-                    &[],
-                    amount,
-                )
-                .unwrap(),
-                instruction,
-            ],
+            &[approve.unwrap(), instruction],
             CommitmentLevel::Processed,
         )
         .await
@@ -353,6 +658,7 @@ mod helpers {
             from_owner.pubkey(),
             token_chain,
             token_address,
+            spl_token::id(),
             TransferWrappedData {
                 nonce: 0,
                 amount,
@@ -396,7 +702,7 @@ mod helpers {
         vaa: PostVAAData,
         payload: PayloadGovernanceRegisterChain,
         payer: &Keypair,
-    ) -> Result<(), BanksClientError> {
+    ) -> Result<Vec<String>, BanksClientError> {
         let instruction = instructions::register_chain(
             program,
             bridge,
@@ -412,17 +718,34 @@ mod helpers {
             println!("{}: {}", account.0, account.1.pubkey);
         }
 
-        execute(
+        execute_with_logs(client, payer, &[payer], &[instruction]).await
+    }
+
+    pub async fn complete_native(
+        client: &mut BanksClient,
+        program: Pubkey,
+        bridge: Pubkey,
+        message_acc: Pubkey,
+        vaa: PostVAAData,
+        payload: PayloadTransfer,
+        payer: &Keypair,
+    ) -> Result<(), BanksClientError> {
+        complete_native_with_token_program(
             client,
+            program,
+            bridge,
+            message_acc,
+            vaa,
+            payload,
             payer,
-            &[payer],
-            &[instruction],
-            CommitmentLevel::Processed,
+            spl_token::id(),
         )
         .await
     }
 
-    pub async fn complete_native(
+    /// Like `complete_native`, for mints owned by `token_program`.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn complete_native_with_token_program(
         client: &mut BanksClient,
         program: Pubkey,
         bridge: Pubkey,
@@ -430,6 +753,7 @@ mod helpers {
         vaa: PostVAAData,
         payload: PayloadTransfer,
         payer: &Keypair,
+        token_program: Pubkey,
     ) -> Result<(), BanksClientError> {
         let instruction = instructions::complete_native(
             program,
@@ -440,6 +764,7 @@ mod helpers {
             Pubkey::new(&payload.to[..]),
             None,
             Pubkey::new(&payload.token_address[..]),
+            token_program,
             CompleteNativeData {},
         )
         .expect("Could not create Complete Native instruction");
@@ -466,6 +791,34 @@ mod helpers {
         vaa: PostVAAData,
         payload: PayloadTransfer,
         payer: &Keypair,
+    ) -> Result<(), BanksClientError> {
+        complete_transfer_wrapped_with_token_program(
+            client,
+            program,
+            bridge,
+            message_acc,
+            vaa,
+            payload,
+            None,
+            payer,
+            spl_token::id(),
+        )
+        .await
+    }
+
+    /// Like `complete_transfer_wrapped`, for mints owned by `token_program` and with the fee going
+    /// to `fee_recipient`.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn complete_transfer_wrapped_with_token_program(
+        client: &mut BanksClient,
+        program: Pubkey,
+        bridge: Pubkey,
+        message_acc: Pubkey,
+        vaa: PostVAAData,
+        payload: PayloadTransfer,
+        fee_recipient: Option<Pubkey>,
+        payer: &Keypair,
+        token_program: Pubkey,
     ) -> Result<(), BanksClientError> {
         let to = Pubkey::new(&payload.to[..]);
 
@@ -477,7 +830,8 @@ mod helpers {
             vaa,
             payload,
             to,
-            None,
+            fee_recipient,
+            token_program,
             CompleteWrappedData {},
         )
         .expect("Could not create Complete Wrapped instruction");
@@ -518,6 +872,7 @@ mod helpers {
             redeemer.pubkey(),
             None,
             Pubkey::new(&payload.token_address[..]),
+            spl_token::id(),
             CompleteNativeWithPayloadData {},
         )
         .expect("Could not create Complete Native With Payload instruction");
@@ -676,6 +1031,156 @@ mod helpers {
         .await
     }
 
+    /// Create a Token-2022 mint without extensions.
+    pub async fn create_token_2022_mint(
+        client: &mut BanksClient,
+        payer: &Keypair,
+        mint_authority: &Pubkey,
+        mint: &Keypair,
+    ) -> Result<(), BanksClientError> {
+        create_token_2022_mint_with_extensions(client, payer, mint_authority, mint, &[], vec![])
+            .await
+    }
+
+    /// Create a Token-2022 mint charging `basis_points` of each transfer, up to `maximum_fee`.
+    /// `mint_authority` also controls the fee.
+    pub async fn create_token_2022_mint_with_transfer_fee(
+        client: &mut BanksClient,
+        payer: &Keypair,
+        mint_authority: &Pubkey,
+        mint: &Keypair,
+        basis_points: u16,
+        maximum_fee: u64,
+    ) -> Result<(), BanksClientError> {
+        let init_fee = transfer_fee::instruction::initialize_transfer_fee_config(
+            &spl_token_2022::id(),
+            &mint.pubkey(),
+            Some(mint_authority),
+            Some(mint_authority),
+            basis_points,
+            maximum_fee,
+        )
+        .unwrap();
+        create_token_2022_mint_with_extensions(
+            client,
+            payer,
+            mint_authority,
+            mint,
+            &[ExtensionType::TransferFeeConfig],
+            vec![init_fee],
+        )
+        .await
+    }
+
+    /// Create a Token-2022 mint, running `init_extensions` to set up `extensions` before the mint
+    /// itself is initialized.
+    async fn create_token_2022_mint_with_extensions(
+        client: &mut BanksClient,
+        payer: &Keypair,
+        mint_authority: &Pubkey,
+        mint: &Keypair,
+        extensions: &[ExtensionType],
+        init_extensions: Vec<Instruction>,
+    ) -> Result<(), BanksClientError> {
+        let mint_key = mint.pubkey();
+        let len = ExtensionType::get_account_len::<spl_token_2022::state::Mint>(extensions);
+        let mut instructions = vec![solana_sdk::system_instruction::create_account(
+            &payer.pubkey(),
+            &mint_key,
+            Rent::default().minimum_balance(len),
+            len as u64,
+            &spl_token_2022::id(),
+        )];
+        instructions.extend(init_extensions);
+        instructions.push(
+            spl_token_2022::instruction::initialize_mint(
+                &spl_token_2022::id(),
+                &mint_key,
+                mint_authority,
+                None,
+                0,
+            )
+            .unwrap(),
+        );
+        execute(
+            client,
+            payer,
+            &[payer, mint],
+            &instructions,
+            CommitmentLevel::Processed,
+        )
+        .await
+    }
+
+    /// Create a Token-2022 token account, with the extensions its mint requires.
+    pub async fn create_token_2022_account(
+        client: &mut BanksClient,
+        payer: &Keypair,
+        token_acc: &Keypair,
+        token_authority: &Pubkey,
+        mint: &Pubkey,
+    ) -> Result<(), BanksClientError> {
+        let mint_account = client.get_account(*mint).await.unwrap().unwrap();
+        let mint_state =
+            StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_account.data).unwrap();
+        let required = ExtensionType::get_required_init_account_extensions(
+            &mint_state.get_extension_types().unwrap(),
+        );
+        let len = ExtensionType::get_account_len::<spl_token_2022::state::Account>(&required);
+
+        let token_key = token_acc.pubkey();
+        execute(
+            client,
+            payer,
+            &[payer, token_acc],
+            &[
+                solana_sdk::system_instruction::create_account(
+                    &payer.pubkey(),
+                    &token_key,
+                    Rent::default().minimum_balance(len),
+                    len as u64,
+                    &spl_token_2022::id(),
+                ),
+                spl_token_2022::instruction::initialize_account(
+                    &spl_token_2022::id(),
+                    &token_key,
+                    mint,
+                    token_authority,
+                )
+                .unwrap(),
+            ],
+            CommitmentLevel::Processed,
+        )
+        .await
+    }
+
+    /// Mint tokens of a Token-2022 mint.
+    pub async fn mint_token_2022_tokens(
+        client: &mut BanksClient,
+        payer: &Keypair,
+        mint_authority: &Keypair,
+        mint: &Pubkey,
+        token_account: &Pubkey,
+        amount: u64,
+    ) -> Result<(), BanksClientError> {
+        execute(
+            client,
+            payer,
+            &[payer, mint_authority],
+            &[spl_token_2022::instruction::mint_to(
+                &spl_token_2022::id(),
+                mint,
+                token_account,
+                &mint_authority.pubkey(),
+                &[],
+                amount,
+            )
+            .unwrap()],
+            CommitmentLevel::Processed,
+        )
+        .await
+    }
+
     pub async fn mint_tokens(
         client: &mut BanksClient,
         payer: &Keypair,
@@ -830,13 +1335,9 @@ mod helpers {
         message: &Keypair,
         nonce: u32,
         data: Vec<u8>,
-        fee: u64,
     ) -> Result<(), BanksClientError> {
-        // Transfer money into the fee collector as it needs a balance/must exist.
-        let fee_collector = FeeCollector::<'_>::key(None, &program);
-
         // Capture the resulting message, later functions will need this.
-        let instruction = bridge::instructions::post_message(
+        let instruction = bridge::instructions::post_message_v2(
             program,
             payer.pubkey(),
             emitter.pubkey(),
@@ -855,10 +1356,39 @@ mod helpers {
             client,
             payer,
             &[payer, emitter, message],
-            &[
-                system_instruction::transfer(&payer.pubkey(), &fee_collector, fee),
-                instruction,
+            &[instruction],
+            CommitmentLevel::Processed,
+        )
+        .await
+    }
+
+    pub async fn query_registered_emitter(
+        client: &mut BanksClient,
+        program: Pubkey,
+        payer: &Keypair,
+        endpoint: Pubkey,
+        chain: u16,
+        emitter_address: [u8; 32],
+        registered: bool,
+    ) -> Result<(), BanksClientError> {
+        let mut data = chain.to_be_bytes().to_vec();
+        data.extend_from_slice(&emitter_address);
+        data.push(registered as u8);
+
+        let instruction = Instruction {
+            program_id: query_router_id(),
+            accounts: vec![
+                AccountMeta::new_readonly(program, false),
+                AccountMeta::new_readonly(endpoint, false),
             ],
+            data,
+        };
+
+        execute(
+            client,
+            payer,
+            &[payer],
+            &[instruction],
             CommitmentLevel::Processed,
         )
         .await
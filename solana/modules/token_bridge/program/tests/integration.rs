@@ -1,24 +1,50 @@
 #![allow(dead_code)]
+use borsh::{
+    BorshDeserialize,
+    BorshSerialize,
+};
 use bridge::{
     accounts::{
+        Claim,
+        ClaimData,
+        ClaimDerivationData,
+        FeeTokenAccount,
+        FeeTokenAccountDerivationData,
+        GovernanceClaimDerivationData,
+        PostedMessageData,
         PostedVAA,
         PostedVAADerivationData,
     },
+    types::GovernancePayloadSetFeeMint,
+    vaa::DeserializePayload,
+    PostVAAData,
     SerializePayload,
 };
+use hex_literal::hex;
 use libsecp256k1::SecretKey;
 use primitive_types::U256;
 use rand::Rng;
-use solana_program::pubkey::Pubkey;
+use solana_program::{
+    program_option::COption,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    sysvar,
+};
 use solana_program_test::{
     tokio,
     BanksClient,
+    BanksClientError,
 };
 use solana_sdk::{
+    account::Account,
+    commitment_config::CommitmentLevel,
+    instruction::InstructionError,
+    rent::Rent,
     signature::{
         Keypair,
         Signer,
     },
+    transaction::TransactionError,
     transport::TransportError,
 };
 use solitaire::{
@@ -30,19 +56,62 @@ use std::{
     collections::HashMap,
     str::FromStr,
 };
+#[cfg(feature = "client")]
+use token_bridge::client::{
+    estimate_redemption_cost,
+    resolve_redemption,
+    RedemptionCost,
+    RedemptionPath,
+};
 use token_bridge::{
     accounts::{
+        find_custody_authority,
+        find_custody_token,
+        find_emitter,
+        find_mint_authority,
+        find_redeemer,
+        find_registered_emitter,
+        find_sender,
+        find_transfer_authority,
         ConfigAccount,
+        CustodyAccount,
+        CustodyAccountDerivationData,
+        SplTokenMeta,
+        SplTokenMetaDerivationData,
+        TokenBridgeAccounts,
         WrappedDerivationData,
+        WrappedMetaDerivationData,
         WrappedMint,
+        WrappedTokenMeta,
+    },
+    events::{
+        CompleteNativeEvent,
+        DeregisterChainEvent,
+        Event,
+        RegisterChainEvent,
     },
+    instructions,
     messages::{
+        GovernancePayloadDeregisterChain,
+        GovernancePayloadSetMetadataAuthority,
+        GovernancePayloadSetPaused,
+        GovernancePayloadUpgrade,
         PayloadAssetMeta,
         PayloadGovernanceRegisterChain,
         PayloadTransfer,
         PayloadTransferWithPayload,
     },
-    types::Config,
+    types::{
+        Config,
+        EndpointRegistration,
+        WrappedMeta,
+    },
+    CompleteNativeWithPayloadData,
+    CompleteWrappedWithPayloadData,
+    TokenBridgeError,
+    TransferNativeData,
+    TransferNativeWithPayloadData,
+    TransferWrappedData,
 };
 
 mod common;
@@ -57,6 +126,10 @@ const GOVERNANCE_KEY: [u8; 64] = [
 const CHAIN_ID_SOLANA: u16 = 1;
 const CHAIN_ID_ETH: u16 = 2;
 
+// Ethereum mainnet token bridge, left-padded to 32 bytes.
+const ETH_TOKEN_BRIDGE: [u8; 32] =
+    hex!("0000000000000000000000003ee18b2214aff97000d974cf647e7c347e8fa585");
+
 struct Context {
     /// Guardian public keys.
     guardians: Vec<[u8; 20]>,
@@ -109,9 +182,13 @@ impl Sequencer {
 }
 
 async fn set_up() -> Result<Context, TransportError> {
+    set_up_with_accounts(vec![]).await
+}
+
+async fn set_up_with_accounts(accounts: Vec<(Pubkey, Account)>) -> Result<Context, TransportError> {
     let (guardians, guardian_keys) = common::generate_keys(6);
 
-    let (mut client, payer, bridge, token_bridge) = common::setup().await;
+    let (mut client, payer, bridge, token_bridge) = common::setup_with_accounts(accounts).await;
 
     // Setup a Bridge to test against.
     common::initialize_bridge(&mut client, bridge, &payer, &guardians).await?;
@@ -235,7 +312,7 @@ async fn create_wrapped(context: &mut Context) -> Pubkey {
     };
     let message = payload.try_to_vec().unwrap();
 
-    let (vaa, body, _) = common::generate_vaa([0u8; 32], 2, message, nonce, 2);
+    let (vaa, body, _) = common::generate_vaa(ETH_TOKEN_BRIDGE, 2, message, nonce, 2);
     let signature_set =
         common::verify_signatures(client, bridge, payer, body, &context.guardian_keys, 0)
             .await
@@ -327,6 +404,106 @@ async fn attest() {
     .unwrap();
 }
 
+#[tokio::test]
+async fn attest_token_2022_metadata() {
+    let mint_authority = Pubkey::new_unique();
+
+    // Metadata stored on the mint, with a name longer than an attestation holds.
+    let on_mint = Keypair::new();
+    let mut pointer = [0u8; 64];
+    pointer[32..].copy_from_slice(&on_mint.pubkey().to_bytes());
+    let name = format!("{}🔥", "A".repeat(31));
+    let metadata = token_metadata(&on_mint.pubkey(), &name, "FIRE");
+    let on_mint_account =
+        token_2022_mint_with_extensions(&mint_authority, &[(18, &pointer[..]), (19, &metadata)]);
+
+    // Metadata held by an account of its own, and one naming another mint.
+    let discriminator = solana_program::hash::hash(b"spl_token_metadata_interface:token_metadata");
+    let external_account = |mint: &Pubkey| {
+        let metadata = token_metadata(mint, "Pointed", "PTD");
+        let mut data = discriminator.as_ref()[..8].to_vec();
+        data.extend_from_slice(&(metadata.len() as u32).to_le_bytes());
+        data.extend_from_slice(&metadata);
+        Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: Pubkey::new_unique(),
+            executable: false,
+            rent_epoch: 0,
+        }
+    };
+    let pointing_to = |metadata: &Pubkey| {
+        let mut pointer = [0u8; 64];
+        pointer[32..].copy_from_slice(&metadata.to_bytes());
+        token_2022_mint_with_extension(&mint_authority, 18, &pointer)
+    };
+    let (pointed, pointed_metadata) = (Keypair::new(), Pubkey::new_unique());
+    let (misdirected, misdirected_metadata) = (Keypair::new(), Pubkey::new_unique());
+
+    let accounts = vec![
+        (on_mint.pubkey(), on_mint_account),
+        (pointed.pubkey(), pointing_to(&pointed_metadata)),
+        (pointed_metadata, external_account(&pointed.pubkey())),
+        (misdirected.pubkey(), pointing_to(&misdirected_metadata)),
+        (misdirected_metadata, external_account(&on_mint.pubkey())),
+    ];
+    let Context {
+        ref payer,
+        ref mut client,
+        bridge,
+        token_bridge,
+        ..
+    } = set_up_with_accounts(accounts).await.unwrap();
+
+    let mut attested = Vec::new();
+    for (mint, token_metadata) in [
+        (&on_mint, None),
+        (&pointed, Some(pointed_metadata)),
+        // Without the account pointed at, attestations fall back to SPL Metadata.
+        (&pointed, None),
+    ] {
+        let message = Keypair::new();
+        common::attest_with_token_metadata(
+            client,
+            token_bridge,
+            bridge,
+            payer,
+            &message,
+            mint.pubkey(),
+            token_metadata,
+            0,
+        )
+        .await
+        .unwrap();
+        let posted: PostedMessageData = common::get_account_data(client, message.pubkey())
+            .await
+            .unwrap();
+        let payload = PayloadAssetMeta::deserialize(&mut posted.payload.as_slice()).unwrap();
+        attested.push((payload.name, payload.symbol));
+    }
+    assert_eq!(
+        attested,
+        vec![
+            ("A".repeat(31), "FIRE".to_string()),
+            ("Pointed".to_string(), "PTD".to_string()),
+            (String::new(), String::new()),
+        ]
+    );
+
+    let result = common::attest_with_token_metadata(
+        client,
+        token_bridge,
+        bridge,
+        payer,
+        &Keypair::new(),
+        misdirected.pubkey(),
+        Some(misdirected_metadata),
+        0,
+    )
+    .await;
+    assert_token_bridge_error(result, TokenBridgeError::InvalidMetadata);
+}
+
 #[tokio::test]
 async fn transfer_native() {
     let Context {
@@ -357,264 +534,3379 @@ async fn transfer_native() {
     .unwrap();
 }
 
-async fn register_chain(context: &mut Context) {
+#[tokio::test]
+async fn transfer_native_token_2022() {
     let Context {
         ref payer,
         ref mut client,
-        ref bridge,
-        ref token_bridge,
-        ref guardian_keys,
+        bridge,
+        token_bridge,
+        ref mint_authority,
+        ref token_authority,
         ..
-    } = context;
-
-    let nonce = rand::thread_rng().gen();
-    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
-    let payload = PayloadGovernanceRegisterChain {
-        chain: 2,
-        endpoint_address: [0u8; 32],
-    };
-    let message = payload.try_to_vec().unwrap();
+    } = set_up().await.unwrap();
 
-    let (vaa, body, _) = common::generate_vaa(emitter.pubkey().to_bytes(), 1, message, nonce, 0);
-    let signature_set = common::verify_signatures(client, bridge, payer, body, guardian_keys, 0)
-        .await
-        .unwrap();
-    common::post_vaa(client, *bridge, payer, signature_set, vaa.clone())
+    // A Token-2022 mint without extensions.
+    let mint = Keypair::new();
+    let from = Keypair::new();
+    common::create_token_2022_mint(client, payer, &mint_authority.pubkey(), &mint)
         .await
         .unwrap();
+    common::create_token_2022_account(
+        client,
+        payer,
+        &from,
+        &token_authority.pubkey(),
+        &mint.pubkey(),
+    )
+    .await
+    .unwrap();
+    common::mint_token_2022_tokens(
+        client,
+        payer,
+        mint_authority,
+        &mint.pubkey(),
+        &from.pubkey(),
+        1000,
+    )
+    .await
+    .unwrap();
 
-    let msg_derivation_data = &PostedVAADerivationData {
-        payload_hash: body.to_vec(),
-    };
-    let message_key =
-        PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(msg_derivation_data, bridge);
-
-    common::register_chain(
+    let message = &Keypair::new();
+    common::transfer_native_with_token_program(
         client,
-        *token_bridge,
-        *bridge,
-        message_key,
-        vaa,
-        payload,
+        token_bridge,
+        bridge,
         payer,
+        message,
+        &from,
+        token_authority,
+        mint.pubkey(),
+        100,
+        spl_token_2022::id(),
     )
     .await
     .unwrap();
+
+    // The custody account was created under Token-2022, at the usual address.
+    let (custody, _) = find_custody_token(&token_bridge, &mint.pubkey());
+    let account = client.get_account(custody).await.unwrap().unwrap();
+    assert_eq!(account.owner, spl_token_2022::id());
+    let custody_account = spl_token_2022::state::Account::unpack(&account.data).unwrap();
+    assert_eq!(
+        custody_account.owner,
+        find_custody_authority(&token_bridge).0
+    );
+    assert_eq!(custody_account.amount, 100);
+    assert_eq!(common::get_token_balance(client, from.pubkey()).await, 900);
+
+    let posted: PostedMessageData = common::get_account_data(client, message.pubkey())
+        .await
+        .unwrap();
+    let payload = PayloadTransfer::deserialize(&mut posted.payload.as_slice()).unwrap();
+    assert_eq!(
+        payload,
+        PayloadTransfer {
+            amount: U256::from(100u128),
+            token_address: mint.pubkey().to_bytes(),
+            token_chain: CHAIN_ID_SOLANA,
+            to: [1u8; 32],
+            to_chain: CHAIN_ID_ETH,
+            fee: U256::from(0u128),
+        }
+    );
 }
 
 #[tokio::test]
-async fn transfer_native_in() {
-    let mut context = set_up().await.unwrap();
-    register_chain(&mut context).await;
+async fn transfer_native_token_2022_transfer_fee() {
     let Context {
         ref payer,
         ref mut client,
         bridge,
         token_bridge,
-        ref mint,
-        ref token_account,
+        ref mint_authority,
         ref token_authority,
-        ref guardian_keys,
         ..
-    } = context;
+    } = set_up().await.unwrap();
+
+    // A Token-2022 mint charging 1% of each transfer.
+    let mint = Keypair::new();
+    let from = Keypair::new();
+    common::create_token_2022_mint_with_transfer_fee(
+        client,
+        payer,
+        &mint_authority.pubkey(),
+        &mint,
+        100,
+        u64::MAX,
+    )
+    .await
+    .unwrap();
+    common::create_token_2022_account(
+        client,
+        payer,
+        &from,
+        &token_authority.pubkey(),
+        &mint.pubkey(),
+    )
+    .await
+    .unwrap();
+    common::mint_token_2022_tokens(
+        client,
+        payer,
+        mint_authority,
+        &mint.pubkey(),
+        &from.pubkey(),
+        1000,
+    )
+    .await
+    .unwrap();
 
-    // Do an initial transfer so that the bridge account has some native tokens. This also creates
-    // the custody account.
     let message = &Keypair::new();
-    common::transfer_native(
+    common::transfer_native_with_token_program(
         client,
         token_bridge,
         bridge,
         payer,
         message,
-        token_account,
+        &from,
         token_authority,
         mint.pubkey(),
-        100,
+        500,
+        spl_token_2022::id(),
     )
     .await
     .unwrap();
 
-    let nonce = rand::thread_rng().gen();
-
-    let payload = PayloadTransfer {
-        amount: U256::from(100u128),
-        token_address: mint.pubkey().to_bytes(),
-        token_chain: 1,
-        to: token_account.pubkey().to_bytes(),
-        to_chain: 1,
-        fee: U256::from(0u128),
-    };
-    let message = payload.try_to_vec().unwrap();
+    // The sender paid the full amount, custody received it less the fee, and only that is
+    // published.
+    let (custody, _) = find_custody_token(&token_bridge, &mint.pubkey());
+    assert_eq!(common::get_token_balance(client, from.pubkey()).await, 500);
+    let received = common::get_token_balance(client, custody).await;
+    assert_eq!(received, 495);
 
-    let (vaa, body, _) = common::generate_vaa([0u8; 32], 2, message, nonce, 1);
-    let signature_set = common::verify_signatures(client, &bridge, payer, body, guardian_keys, 0)
+    let posted: PostedMessageData = common::get_account_data(client, message.pubkey())
         .await
         .unwrap();
-    common::post_vaa(client, bridge, payer, signature_set, vaa.clone())
-        .await
-        .unwrap();
-    let msg_derivation_data = &PostedVAADerivationData {
-        payload_hash: body.to_vec(),
-    };
-    let message_key =
-        PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(msg_derivation_data, &bridge);
+    let payload = PayloadTransfer::deserialize(&mut posted.payload.as_slice()).unwrap();
+    assert_eq!(payload.amount, U256::from(received));
+}
 
-    common::complete_native(
-        client,
-        token_bridge,
-        bridge,
-        message_key,
-        vaa,
-        payload,
-        payer,
+/// A Token-2022 mint without extensions.
+fn token_2022_mint(mint_authority: &Pubkey, decimals: u8) -> Account {
+    let mut data = vec![0; spl_token_2022::state::Mint::LEN];
+    spl_token_2022::state::Mint {
+        mint_authority: COption::Some(*mint_authority),
+        supply: 0,
+        decimals,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: spl_token_2022::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// A Token-2022 mint carrying a single extension of type `extension`, laid out the way the token
+/// program stores it, for extensions the test environment cannot initialize.
+fn token_2022_mint_with_extension(
+    mint_authority: &Pubkey,
+    extension: u16,
+    value: &[u8],
+) -> Account {
+    token_2022_mint_with_extensions(mint_authority, &[(extension, value)])
+}
+
+/// Like `token_2022_mint_with_extension`, with several extensions.
+fn token_2022_mint_with_extensions(
+    mint_authority: &Pubkey,
+    extensions: &[(u16, &[u8])],
+) -> Account {
+    let mut account = token_2022_mint(mint_authority, 0);
+    let data = &mut account.data;
+    data.resize(spl_token_2022::state::Account::LEN, 0);
+    // Account type of mints, then the extensions.
+    data.push(1);
+    for (extension, value) in extensions {
+        data.extend_from_slice(&extension.to_le_bytes());
+        data.extend_from_slice(&(value.len() as u16).to_le_bytes());
+        data.extend_from_slice(value);
+    }
+    account.lamports = Rent::default().minimum_balance(account.data.len());
+    account
+}
+
+/// Token metadata of `mint` as the token metadata interface encodes it.
+fn token_metadata(mint: &Pubkey, name: &str, symbol: &str) -> Vec<u8> {
+    (
+        [0u8; 32],
+        *mint,
+        name.to_string(),
+        symbol.to_string(),
+        String::new(),
+        Vec::<(String, String)>::new(),
     )
-    .await
-    .unwrap();
+        .try_to_vec()
+        .unwrap()
+}
+
+/// A Token-2022 token account holding `amount` tokens of `mint`.
+fn token_2022_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Account {
+    let mut data = vec![0; spl_token_2022::state::Account::LEN];
+    spl_token_2022::state::Account {
+        mint: *mint,
+        owner: *owner,
+        amount,
+        state: spl_token_2022::state::AccountState::Initialized,
+        ..Default::default()
+    }
+    .pack_into_slice(&mut data);
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: spl_token_2022::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
 }
 
 #[tokio::test]
-async fn transfer_wrapped() {
-    let mut context = set_up().await.unwrap();
-    register_chain(&mut context).await;
-    let to = create_wrapped_account(&mut context).await.unwrap();
+async fn transfer_native_token_2022_transfer_hook() {
+    // Transfer hook extension, naming the hook program and its authority.
+    let mint = Keypair::new();
+    let from = Keypair::new();
+    let from_owner = Keypair::new();
+    let mut hook = [0u8; 64];
+    hook[32..].copy_from_slice(&Pubkey::new_unique().to_bytes());
+    let accounts = vec![
+        (
+            mint.pubkey(),
+            token_2022_mint_with_extension(&Pubkey::new_unique(), 14, &hook),
+        ),
+        (
+            from.pubkey(),
+            token_2022_account(&mint.pubkey(), &from_owner.pubkey(), 1000),
+        ),
+    ];
     let Context {
         ref payer,
         ref mut client,
         bridge,
         token_bridge,
-        ref token_authority,
-        ref guardian_keys,
         ..
-    } = context;
-
-    let nonce = rand::thread_rng().gen();
-
-    let payload = PayloadTransfer {
-        amount: U256::from(100000000),
-        token_address: [1u8; 32],
-        token_chain: 2,
-        to: to.to_bytes(),
-        to_chain: 1,
-        fee: U256::from(0),
-    };
-    let message = payload.try_to_vec().unwrap();
-
-    let (vaa, body, _) =
-        common::generate_vaa([0u8; 32], 2, message, nonce, rand::thread_rng().gen());
-    let signature_set = common::verify_signatures(client, &bridge, payer, body, guardian_keys, 0)
-        .await
-        .unwrap();
-    common::post_vaa(client, bridge, payer, signature_set, vaa.clone())
-        .await
-        .unwrap();
-    let msg_derivation_data = &PostedVAADerivationData {
-        payload_hash: body.to_vec(),
-    };
-    let message_key =
-        PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(msg_derivation_data, &bridge);
+    } = set_up_with_accounts(accounts).await.unwrap();
 
-    common::complete_transfer_wrapped(
+    let result = common::transfer_native_with_token_program(
         client,
         token_bridge,
         bridge,
-        message_key,
-        vaa,
-        payload,
         payer,
+        &Keypair::new(),
+        &from,
+        &from_owner,
+        mint.pubkey(),
+        100,
+        spl_token_2022::id(),
     )
-    .await
-    .unwrap();
+    .await;
+    assert_token_bridge_error(result, TokenBridgeError::UnsupportedTransferHook);
+    assert_eq!(common::get_token_balance(client, from.pubkey()).await, 1000);
 
-    // Now transfer the wrapped tokens back, which will burn them.
-    let message = &Keypair::new();
-    common::transfer_wrapped(
+    let result = common::attest(
         client,
         token_bridge,
         bridge,
         payer,
-        message,
-        to,
-        token_authority,
-        2,
-        [1u8; 32],
-        10000000,
+        &Keypair::new(),
+        mint.pubkey(),
+        0,
     )
-    .await
-    .unwrap();
+    .await;
+    assert_token_bridge_error(result, TokenBridgeError::UnsupportedTransferHook);
 }
 
 #[tokio::test]
-async fn transfer_native_with_payload_in() {
-    let mut context = set_up().await.unwrap();
-    register_chain(&mut context).await;
+async fn transfer_native_token_2022_permanent_delegate() {
+    let mint = Keypair::new();
+    let from = Keypair::new();
+    let from_owner = Keypair::new();
+    let delegate = Pubkey::new_unique();
+    let accounts = vec![
+        (
+            mint.pubkey(),
+            token_2022_mint_with_extension(&Pubkey::new_unique(), 12, &delegate.to_bytes()),
+        ),
+        (
+            from.pubkey(),
+            token_2022_account(&mint.pubkey(), &from_owner.pubkey(), 1000),
+        ),
+    ];
     let Context {
         ref payer,
         ref mut client,
         bridge,
         token_bridge,
-        ref mint,
-        ref token_account,
-        ref token_authority,
-        ref guardian_keys,
         ..
-    } = context;
+    } = set_up_with_accounts(accounts).await.unwrap();
 
-    // Do an initial transfer so that the bridge account has some native tokens. This also creates
-    // the custody account.
-    let message = &Keypair::new();
-    common::transfer_native(
+    let result = common::transfer_native_with_token_program(
         client,
         token_bridge,
         bridge,
         payer,
-        message,
-        token_account,
-        token_authority,
+        &Keypair::new(),
+        &from,
+        &from_owner,
         mint.pubkey(),
         100,
+        spl_token_2022::id(),
+    )
+    .await;
+    assert_token_bridge_error(result, TokenBridgeError::UnsupportedPermanentDelegate);
+
+    let message = Keypair::new();
+    let transfer = instructions::transfer_native_with_payload(
+        token_bridge,
+        bridge,
+        payer.pubkey(),
+        message.pubkey(),
+        from.pubkey(),
+        mint.pubkey(),
+        spl_token_2022::id(),
+        TransferNativeWithPayloadData {
+            nonce: 0,
+            amount: 100,
+            target_address: [1u8; 32],
+            target_chain: CHAIN_ID_ETH,
+            payload: vec![1, 2, 3],
+            cpi_program_id: None,
+        },
     )
-    .await
     .unwrap();
+    let result = common::execute(
+        client,
+        payer,
+        &[payer, &message],
+        &[transfer],
+        CommitmentLevel::Processed,
+    )
+    .await;
+    assert_token_bridge_error(result, TokenBridgeError::UnsupportedPermanentDelegate);
+    assert_eq!(common::get_token_balance(client, from.pubkey()).await, 1000);
 
-    let nonce = rand::thread_rng().gen();
-    let from_address = Keypair::new().pubkey().to_bytes();
-    let payload: Vec<u8> = vec![1, 2, 3];
-    let payload = PayloadTransferWithPayload {
-        amount: U256::from(100u128),
-        token_address: mint.pubkey().to_bytes(),
+    let result = common::attest(
+        client,
+        token_bridge,
+        bridge,
+        payer,
+        &Keypair::new(),
+        mint.pubkey(),
+        0,
+    )
+    .await;
+    assert_token_bridge_error(result, TokenBridgeError::UnsupportedPermanentDelegate);
+}
+
+#[tokio::test]
+async fn transfer_native_token_2022_unsupported_extensions() {
+    fn expected_error(extension: u16) -> TokenBridgeError {
+        match extension {
+            3 => TokenBridgeError::UnsupportedMintCloseAuthority,
+            9 => TokenBridgeError::UnsupportedNonTransferable,
+            _ => TokenBridgeError::UnsupportedMintExtension,
+        }
+    }
+
+    // A close authority, non-transferable tokens, and interest-bearing mints, which the bridge has
+    // no policy for.
+    let cases = vec![
+        (3, Pubkey::new_unique().to_bytes().to_vec()),
+        (9, vec![]),
+        (10, vec![0; 52]),
+    ];
+    let mint_authority = Pubkey::new_unique();
+    let from_owner = Keypair::new();
+    let mut accounts = Vec::new();
+    let mut transfers = Vec::new();
+    for (extension, value) in cases {
+        let (mint, from) = (Keypair::new(), Keypair::new());
+        accounts.push((
+            mint.pubkey(),
+            token_2022_mint_with_extension(&mint_authority, extension, &value),
+        ));
+        accounts.push((
+            from.pubkey(),
+            token_2022_account(&mint.pubkey(), &from_owner.pubkey(), 1000),
+        ));
+        transfers.push((mint, from, extension));
+    }
+    let Context {
+        ref payer,
+        ref mut client,
+        bridge,
+        token_bridge,
+        ..
+    } = set_up_with_accounts(accounts).await.unwrap();
+
+    for (mint, from, extension) in transfers {
+        let result = common::transfer_native_with_token_program(
+            client,
+            token_bridge,
+            bridge,
+            payer,
+            &Keypair::new(),
+            &from,
+            &from_owner,
+            mint.pubkey(),
+            100,
+            spl_token_2022::id(),
+        )
+        .await;
+        assert_token_bridge_error(result, expected_error(extension));
+
+        let result = common::attest(
+            client,
+            token_bridge,
+            bridge,
+            payer,
+            &Keypair::new(),
+            mint.pubkey(),
+            0,
+        )
+        .await;
+        assert_token_bridge_error(result, expected_error(extension));
+    }
+}
+
+#[tokio::test]
+async fn pda_helpers_match_accounts() {
+    let mut context = set_up().await.unwrap();
+    let Context {
+        ref payer,
+        ref mut client,
+        bridge,
+        token_bridge,
+        ref mint,
+        ref token_account,
+        ref token_authority,
+        ..
+    } = context;
+
+    let message = &Keypair::new();
+    let instruction = instructions::transfer_native(
+        token_bridge,
+        bridge,
+        payer.pubkey(),
+        message.pubkey(),
+        token_account.pubkey(),
+        mint.pubkey(),
+        spl_token::id(),
+        TransferNativeData {
+            nonce: 0,
+            amount: 100,
+            fee: 0,
+            target_address: [1u8; 32],
+            target_chain: CHAIN_ID_ETH,
+        },
+    )
+    .unwrap();
+    let (custody, _) = find_custody_token(&token_bridge, &mint.pubkey());
+    let (transfer_authority, _) = find_transfer_authority(&token_bridge);
+    let (custody_authority, _) = find_custody_authority(&token_bridge);
+    let (emitter, _) = find_emitter(&token_bridge);
+    assert_eq!(instruction.accounts[4].pubkey, custody);
+    assert_eq!(instruction.accounts[5].pubkey, transfer_authority);
+    assert_eq!(instruction.accounts[6].pubkey, custody_authority);
+    assert_eq!(instruction.accounts[9].pubkey, emitter);
+
+    common::transfer_native(
+        client,
+        token_bridge,
+        bridge,
+        payer,
+        message,
+        token_account,
+        token_authority,
+        mint.pubkey(),
+        100,
+    )
+    .await
+    .unwrap();
+
+    // The transfer created the custody account, owned by the custody authority.
+    let account = client.get_account(custody).await.unwrap().unwrap();
+    let custody_account = spl_token::state::Account::unpack(&account.data).unwrap();
+    assert_eq!(custody_account.owner, custody_authority);
+    assert_eq!(custody_account.mint, mint.pubkey());
+
+    // Its message was posted as the emitter.
+    let message: PostedMessageData = common::get_account_data(client, message.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(message.emitter_address, emitter.to_bytes());
+
+    let wrapped = create_wrapped(&mut context).await;
+    let account = context.client.get_account(wrapped).await.unwrap().unwrap();
+    let wrapped_mint = spl_token::state::Mint::unpack(&account.data).unwrap();
+    assert_eq!(
+        wrapped_mint.mint_authority,
+        COption::Some(find_mint_authority(&token_bridge).0)
+    );
+
+    let router = common::token_router_id();
+    assert_eq!(find_sender(&router).0, common::token_router_pda(b"sender"));
+    assert_eq!(
+        find_redeemer(&router).0,
+        common::token_router_pda(b"redeemer")
+    );
+}
+
+#[tokio::test]
+async fn token_bridge_accounts_match_transfers() {
+    let mut context = set_up().await.unwrap();
+    let Context {
+        ref payer,
+        ref mut client,
+        bridge,
+        token_bridge,
+        ref mint,
+        ref token_account,
+        ref token_authority,
+        ..
+    } = context;
+
+    let native = TokenBridgeAccounts::for_native(&token_bridge, &bridge, &mint.pubkey());
+    assert_eq!(
+        native.config,
+        ConfigAccount::<'_, { AccountState::Uninitialized }>::key(None, &token_bridge)
+    );
+    assert_eq!(native.mint, mint.pubkey());
+    assert_eq!(
+        native.asset.custody_token,
+        CustodyAccount::<'_, { AccountState::Initialized }>::key(
+            &CustodyAccountDerivationData {
+                mint: mint.pubkey()
+            },
+            &token_bridge
+        )
+    );
+
+    common::transfer_native(
+        client,
+        token_bridge,
+        bridge,
+        payer,
+        &Keypair::new(),
+        token_account,
+        token_authority,
+        mint.pubkey(),
+        100,
+    )
+    .await
+    .unwrap();
+
+    // Every account the transfer touched is live under the resolved keys.
+    let custody = client
+        .get_account(native.asset.custody_token)
+        .await
+        .unwrap()
+        .unwrap();
+    let custody = spl_token::state::Account::unpack(&custody.data).unwrap();
+    assert_eq!(custody.owner, native.asset.custody_authority);
+    for key in [
+        native.config,
+        native.bridge_config,
+        native.fee_collector,
+        native.sequence,
+    ] {
+        assert!(client.get_account(key).await.unwrap().is_some());
+    }
+
+    let wrapped_mint = create_wrapped(&mut context).await;
+    let wrapped = TokenBridgeAccounts::for_wrapped(&token_bridge, &bridge, &wrapped_mint);
+    assert_eq!(
+        wrapped_mint,
+        WrappedMint::<'_, { AccountState::Initialized }>::key(
+            &WrappedDerivationData {
+                token_chain: 2,
+                token_address: [1u8; 32],
+            },
+            &token_bridge
+        )
+    );
+    assert_eq!(
+        wrapped.asset.wrapped_meta,
+        WrappedTokenMeta::<'_, { AccountState::Initialized }>::key(
+            &WrappedMetaDerivationData {
+                mint_key: wrapped_mint
+            },
+            &token_bridge
+        )
+    );
+    assert_eq!(wrapped.emitter, native.emitter);
+    assert_eq!(wrapped.sequence, native.sequence);
+
+    let meta: WrappedMeta =
+        common::get_account_data(&mut context.client, wrapped.asset.wrapped_meta)
+            .await
+            .unwrap();
+    assert_eq!(meta.chain, 2);
+    assert_eq!(meta.token_address, [1u8; 32]);
+    let account = context
+        .client
+        .get_account(wrapped_mint)
+        .await
+        .unwrap()
+        .unwrap();
+    let mint_data = spl_token::state::Mint::unpack(&account.data).unwrap();
+    assert_eq!(
+        mint_data.mint_authority,
+        COption::Some(wrapped.asset.mint_authority)
+    );
+}
+
+async fn register_chain(context: &mut Context) {
+    let Context {
+        ref payer,
+        ref mut client,
+        ref bridge,
+        ref token_bridge,
+        ref guardian_keys,
+        ..
+    } = context;
+
+    let nonce = rand::thread_rng().gen();
+    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
+    let payload = PayloadGovernanceRegisterChain {
+        chain: 2,
+        endpoint_address: ETH_TOKEN_BRIDGE,
+    };
+    let message = payload.try_to_vec().unwrap();
+
+    let (vaa, body, _) = common::generate_vaa(emitter.pubkey().to_bytes(), 1, message, nonce, 0);
+    let signature_set = common::verify_signatures(client, bridge, payer, body, guardian_keys, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, *bridge, payer, signature_set, vaa.clone())
+        .await
+        .unwrap();
+
+    let msg_derivation_data = &PostedVAADerivationData {
+        payload_hash: body.to_vec(),
+    };
+    let message_key =
+        PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(msg_derivation_data, bridge);
+
+    let sequence = vaa.sequence;
+    let logs = common::register_chain(
+        client,
+        *token_bridge,
+        *bridge,
+        message_key,
+        vaa,
+        payload,
+        payer,
+    )
+    .await
+    .unwrap();
+
+    let events: Vec<RegisterChainEvent> = logs
+        .iter()
+        .filter_map(|log| log.strip_prefix("Program data: "))
+        .map(|data| RegisterChainEvent::decode(&base64::decode(data).unwrap()).unwrap())
+        .collect();
+    assert_eq!(
+        events,
+        vec![RegisterChainEvent {
+            chain: 2,
+            emitter_address: ETH_TOKEN_BRIDGE,
+            sequence,
+            created: true,
+        }]
+    );
+
+    // The registration must be found where relayers derive it from the emitter.
+    let (endpoint, _) = find_registered_emitter(token_bridge, 2, &ETH_TOKEN_BRIDGE);
+    let registration: EndpointRegistration =
+        common::get_account_data(client, endpoint).await.unwrap();
+    assert_eq!(registration.chain, 2);
+    assert_eq!(registration.contract, ETH_TOKEN_BRIDGE);
+
+    // The decree is claimed under the governance prefix only.
+    let claim = Claim::<'_>::key(
+        &GovernanceClaimDerivationData {
+            emitter_address: emitter.pubkey().to_bytes(),
+            emitter_chain: CHAIN_ID_SOLANA,
+            sequence,
+        },
+        token_bridge,
+    );
+    let claim: ClaimData = common::get_account_data(client, claim).await.unwrap();
+    assert!(claim.claimed);
+    let legacy_claim = Claim::<'_>::key(
+        &ClaimDerivationData {
+            emitter_address: emitter.pubkey().to_bytes(),
+            emitter_chain: CHAIN_ID_SOLANA,
+            sequence,
+        },
+        token_bridge,
+    );
+    assert!(client.get_account(legacy_claim).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn register_chain_malformed() {
+    let Context {
+        ref payer,
+        ref mut client,
+        ref bridge,
+        ref token_bridge,
+        ref guardian_keys,
+        ..
+    } = set_up().await.unwrap();
+    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
+
+    let payload = PayloadGovernanceRegisterChain {
+        chain: 2,
+        endpoint_address: ETH_TOKEN_BRIDGE,
+    };
+    let message = payload.try_to_vec().unwrap();
+    let mut long = message.clone();
+    long.push(0);
+    let short = message[..message.len() - 1].to_vec();
+
+    for (sequence, message) in vec![long, short].into_iter().enumerate() {
+        let nonce = rand::thread_rng().gen();
+        let (vaa, body, _) = common::generate_vaa(
+            emitter.pubkey().to_bytes(),
+            1,
+            message,
+            nonce,
+            sequence as u64,
+        );
+        let signature_set =
+            common::verify_signatures(client, bridge, payer, body, guardian_keys, 0)
+                .await
+                .unwrap();
+        common::post_vaa(client, *bridge, payer, signature_set, vaa.clone())
+            .await
+            .unwrap();
+        let message_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+            &PostedVAADerivationData {
+                payload_hash: body.to_vec(),
+            },
+            bridge,
+        );
+
+        let payload = PayloadGovernanceRegisterChain {
+            chain: 2,
+            endpoint_address: ETH_TOKEN_BRIDGE,
+        };
+        assert_token_bridge_error(
+            common::register_chain(
+                client,
+                *token_bridge,
+                *bridge,
+                message_key,
+                vaa,
+                payload,
+                payer,
+            )
+            .await,
+            TokenBridgeError::MalformedGovernancePayload,
+        );
+    }
+}
+
+#[tokio::test]
+async fn replay_pre_migration_decree() {
+    // Claim left by the chain registration below when it was processed before decrees were
+    // claimed under the governance prefix.
+    let (_, token_bridge) = common::program_ids();
+    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
+    let legacy_claim = Claim::<'_>::key(
+        &ClaimDerivationData {
+            emitter_address: emitter.pubkey().to_bytes(),
+            emitter_chain: CHAIN_ID_SOLANA,
+            sequence: 0,
+        },
+        &token_bridge,
+    );
+    let data = ClaimData { claimed: true }.try_to_vec().unwrap();
+    let account = Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: token_bridge,
+        executable: false,
+        rent_epoch: 0,
+    };
+
+    let Context {
+        ref payer,
+        ref mut client,
+        ref bridge,
+        ref token_bridge,
+        ref guardian_keys,
+        ..
+    } = set_up_with_accounts(vec![(legacy_claim, account)])
+        .await
+        .unwrap();
+
+    let nonce = rand::thread_rng().gen();
+    let payload = PayloadGovernanceRegisterChain {
+        chain: 2,
+        endpoint_address: ETH_TOKEN_BRIDGE,
+    };
+    let message = payload.try_to_vec().unwrap();
+    let (vaa, body, _) = common::generate_vaa(emitter.pubkey().to_bytes(), 1, message, nonce, 0);
+    let signature_set = common::verify_signatures(client, bridge, payer, body, guardian_keys, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, *bridge, payer, signature_set, vaa.clone())
+        .await
+        .unwrap();
+    let message_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: body.to_vec(),
+        },
+        bridge,
+    );
+
+    assert!(common::register_chain(
+        client,
+        *token_bridge,
+        *bridge,
+        message_key,
+        vaa,
+        payload,
+        payer,
+    )
+    .await
+    .is_err());
+    let (endpoint, _) = find_registered_emitter(token_bridge, 2, &ETH_TOKEN_BRIDGE);
+    assert!(client.get_account(endpoint).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn query_registered_emitter() {
+    let mut context = set_up().await.unwrap();
+    let (endpoint, _) = find_registered_emitter(&context.token_bridge, 2, &ETH_TOKEN_BRIDGE);
+
+    // Nothing is registered yet, so the query answers with empty return data.
+    common::query_registered_emitter(
+        &mut context.client,
+        context.token_bridge,
+        &context.payer,
+        endpoint,
+        2,
+        ETH_TOKEN_BRIDGE,
+        false,
+    )
+    .await
+    .unwrap();
+
+    // An empty account that is not the endpoint of the emitter cannot answer for it.
+    assert!(common::query_registered_emitter(
+        &mut context.client,
+        context.token_bridge,
+        &context.payer,
+        Pubkey::new_unique(),
+        2,
+        ETH_TOKEN_BRIDGE,
+        false,
+    )
+    .await
+    .is_err());
+
+    register_chain(&mut context).await;
+    let Context {
+        ref payer,
+        ref mut client,
+        token_bridge,
+        ..
+    } = context;
+
+    common::query_registered_emitter(
+        client,
+        token_bridge,
+        payer,
+        endpoint,
+        2,
+        ETH_TOKEN_BRIDGE,
+        true,
+    )
+    .await
+    .unwrap();
+
+    // The registration for chain 2 cannot answer for any other chain, nor for another emitter.
+    assert!(common::query_registered_emitter(
+        client,
+        token_bridge,
+        payer,
+        endpoint,
+        3,
+        ETH_TOKEN_BRIDGE,
+        true,
+    )
+    .await
+    .is_err());
+    assert!(common::query_registered_emitter(
+        client,
+        token_bridge,
+        payer,
+        endpoint,
+        2,
+        [9u8; 32],
+        false,
+    )
+    .await
+    .is_err());
+}
+
+// The upgrade itself needs the token bridge deployed through the upgradeable loader, which
+// solana-program-test does not do, so only decrees rejected before the loader is invoked are
+// covered.
+#[tokio::test]
+async fn upgrade_contract_invalid_decree() {
+    let Context {
+        ref payer,
+        ref mut client,
+        ref bridge,
+        ref token_bridge,
+        ref guardian_keys,
+        ref mut seq,
+        ..
+    } = set_up().await.unwrap();
+    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
+    let buffer = Pubkey::new_unique();
+
+    // Not a buffer of the upgradeable loader.
+    let on_solana = GovernancePayloadUpgrade {
+        new_contract: buffer,
+    }
+    .try_to_vec()
+    .unwrap();
+    // The same decree addressed to every chain.
+    let mut on_all_chains = on_solana.clone();
+    on_all_chains[33..35].copy_from_slice(&0u16.to_be_bytes());
+
+    for message in [on_solana, on_all_chains] {
+        let nonce = rand::thread_rng().gen();
+        let sequence = seq.next(emitter.pubkey().to_bytes());
+        let (vaa, body, _) =
+            common::generate_vaa(emitter.pubkey().to_bytes(), 1, message, nonce, sequence);
+        let signature_set =
+            common::verify_signatures(client, bridge, payer, body, guardian_keys, 0)
+                .await
+                .unwrap();
+        common::post_vaa(client, *bridge, payer, signature_set, vaa)
+            .await
+            .unwrap();
+        let message_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+            &PostedVAADerivationData {
+                payload_hash: body.to_vec(),
+            },
+            bridge,
+        );
+
+        let ix = instructions::upgrade_contract(
+            *token_bridge,
+            payer.pubkey(),
+            message_key,
+            emitter.pubkey(),
+            buffer,
+            Pubkey::new_unique(),
+            sequence,
+        );
+        assert!(
+            common::execute(client, payer, &[payer], &[ix], CommitmentLevel::Processed)
+                .await
+                .is_err()
+        );
+    }
+}
+
+/// Post a governance decree, returning its message account and sequence.
+async fn post_decree(context: &mut Context, message: Vec<u8>) -> (Pubkey, u64) {
+    let Context {
+        ref payer,
+        ref mut client,
+        ref bridge,
+        ref guardian_keys,
+        ref mut seq,
+        ..
+    } = context;
+
+    let nonce = rand::thread_rng().gen();
+    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
+
+    // Sequence 0 is taken by the chain registration.
+    let sequence = seq.next(emitter.pubkey().to_bytes()) + 1;
+    let (vaa, body, _) =
+        common::generate_vaa(emitter.pubkey().to_bytes(), 1, message, nonce, sequence);
+    let signature_set = common::verify_signatures(client, bridge, payer, body, guardian_keys, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, *bridge, payer, signature_set, vaa)
+        .await
+        .unwrap();
+    let message_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: body.to_vec(),
+        },
+        bridge,
+    );
+
+    (message_key, sequence)
+}
+
+async fn set_paused(context: &mut Context, paused: bool) {
+    let message = GovernancePayloadSetPaused { paused }.try_to_vec().unwrap();
+    let (message_key, sequence) = post_decree(context, message).await;
+    let Context {
+        ref payer,
+        ref mut client,
+        ref bridge,
+        ref token_bridge,
+        ..
+    } = context;
+
+    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
+    let ix = instructions::set_paused(
+        *token_bridge,
+        payer.pubkey(),
+        message_key,
+        emitter.pubkey(),
+        sequence,
+    );
+    common::execute(client, payer, &[payer], &[ix], CommitmentLevel::Processed)
+        .await
+        .unwrap();
+
+    let config_key = ConfigAccount::<'_, { AccountState::Uninitialized }>::key(None, token_bridge);
+    let config: Config = common::get_account_data(client, config_key).await.unwrap();
+    assert_eq!(config.paused, paused);
+    assert_eq!(config.wormhole_bridge, *bridge);
+}
+
+fn assert_token_bridge_error<T>(result: Result<T, BanksClientError>, expected: TokenBridgeError) {
+    match result {
+        Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+            _,
+            InstructionError::Custom(code),
+        ))) => assert_eq!(code, expected as u32),
+        _ => panic!("instruction should have been rejected"),
+    }
+}
+
+#[tokio::test]
+async fn pause_outbound_transfers() {
+    let mut context = set_up().await.unwrap();
+    register_chain(&mut context).await;
+
+    // Fund the custody account so a redemption can be completed while paused.
+    common::transfer_native(
+        &mut context.client,
+        context.token_bridge,
+        context.bridge,
+        &context.payer,
+        &Keypair::new(),
+        &context.token_account,
+        &context.token_authority,
+        context.mint.pubkey(),
+        100,
+    )
+    .await
+    .unwrap();
+
+    set_paused(&mut context, true).await;
+    {
+        let Context {
+            ref payer,
+            ref mut client,
+            bridge,
+            token_bridge,
+            ref mint,
+            ref token_account,
+            ref token_authority,
+            ref guardian_keys,
+            ..
+        } = context;
+
+        assert_token_bridge_error(
+            common::transfer_native(
+                client,
+                token_bridge,
+                bridge,
+                payer,
+                &Keypair::new(),
+                token_account,
+                token_authority,
+                mint.pubkey(),
+                100,
+            )
+            .await,
+            TokenBridgeError::OutboundTransfersPaused,
+        );
+        assert_token_bridge_error(
+            common::attest(
+                client,
+                token_bridge,
+                bridge,
+                payer,
+                &Keypair::new(),
+                mint.pubkey(),
+                0,
+            )
+            .await,
+            TokenBridgeError::OutboundTransfersPaused,
+        );
+
+        // Redemptions are unaffected.
+        let payload = PayloadTransfer {
+            amount: U256::from(100u128),
+            token_address: mint.pubkey().to_bytes(),
+            token_chain: 1,
+            to: token_account.pubkey().to_bytes(),
+            to_chain: 1,
+            fee: U256::from(0u128),
+        };
+        let message = payload.try_to_vec().unwrap();
+        let nonce = rand::thread_rng().gen();
+        let (vaa, body, _) = common::generate_vaa(ETH_TOKEN_BRIDGE, 2, message, nonce, 1);
+        let signature_set =
+            common::verify_signatures(client, &bridge, payer, body, guardian_keys, 0)
+                .await
+                .unwrap();
+        common::post_vaa(client, bridge, payer, signature_set, vaa.clone())
+            .await
+            .unwrap();
+        let message_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+            &PostedVAADerivationData {
+                payload_hash: body.to_vec(),
+            },
+            &bridge,
+        );
+        common::complete_native(
+            client,
+            token_bridge,
+            bridge,
+            message_key,
+            vaa,
+            payload,
+            payer,
+        )
+        .await
+        .unwrap();
+    }
+
+    set_paused(&mut context, false).await;
+    common::transfer_native(
+        &mut context.client,
+        context.token_bridge,
+        context.bridge,
+        &context.payer,
+        &Keypair::new(),
+        &context.token_account,
+        &context.token_authority,
+        context.mint.pubkey(),
+        100,
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn transfer_native_token_fee() {
+    let mut context = set_up().await.unwrap();
+    let fee_mint = Keypair::new();
+    let payer_tokens = Keypair::new();
+    {
+        let Context {
+            ref payer,
+            ref mut client,
+            ref mint_authority,
+            ..
+        } = context;
+        common::create_mint(client, payer, &mint_authority.pubkey(), &fee_mint)
+            .await
+            .unwrap();
+        common::create_token_account(
+            client,
+            payer,
+            &payer_tokens,
+            &payer.pubkey(),
+            &fee_mint.pubkey(),
+        )
+        .await
+        .unwrap();
+        common::mint_tokens(
+            client,
+            payer,
+            mint_authority,
+            &fee_mint,
+            &payer_tokens.pubkey(),
+            1000,
+        )
+        .await
+        .unwrap();
+    }
+
+    let message = GovernancePayloadSetFeeMint {
+        mint: fee_mint.pubkey(),
+        fee: 25,
+    }
+    .try_to_vec()
+    .unwrap();
+    let (message_key, sequence) = post_decree(&mut context, message).await;
+    let Context {
+        ref payer,
+        ref mut client,
+        bridge,
+        token_bridge,
+        ref mint,
+        ref token_account,
+        ref token_authority,
+        ..
+    } = context;
+
+    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
+    let ix = bridge::instructions::set_fee_mint(
+        bridge,
+        payer.pubkey(),
+        message_key,
+        emitter.pubkey(),
+        sequence,
+        fee_mint.pubkey(),
+    );
+    common::execute(client, payer, &[payer], &[ix], CommitmentLevel::Processed)
+        .await
+        .unwrap();
+
+    // Without the token fee accounts the core bridge rejects the message.
+    assert!(common::transfer_native(
+        client,
+        token_bridge,
+        bridge,
+        payer,
+        &Keypair::new(),
+        token_account,
+        token_authority,
+        mint.pubkey(),
+        100,
+    )
+    .await
+    .is_err());
+
+    let message = Keypair::new();
+    let mut ix = instructions::transfer_native(
+        token_bridge,
+        bridge,
+        payer.pubkey(),
+        message.pubkey(),
+        token_account.pubkey(),
+        mint.pubkey(),
+        spl_token::id(),
+        TransferNativeData {
+            nonce: 0,
+            amount: 100,
+            fee: 0,
+            target_address: [1u8; 32],
+            target_chain: CHAIN_ID_ETH,
+        },
+    )
+    .unwrap();
+    ix.accounts.extend(bridge::instructions::token_fee_accounts(
+        bridge,
+        fee_mint.pubkey(),
+        payer_tokens.pubkey(),
+    ));
+    let approve = spl_token::instruction::approve(
+        &spl_token::id(),
+        &token_account.pubkey(),
+        &token_bridge::accounts::AuthoritySigner::key(None, &token_bridge),
+        &token_authority.pubkey(),
+        &[],
+        100,
+    )
+    .unwrap();
+    common::execute(
+        client,
+        payer,
+        &[payer, token_authority, &message],
+        &[approve, ix],
+        CommitmentLevel::Processed,
+    )
+    .await
+    .unwrap();
+
+    let fee_tokens = FeeTokenAccount::<'_, { AccountState::Uninitialized }>::key(
+        &FeeTokenAccountDerivationData {
+            mint: fee_mint.pubkey(),
+        },
+        &bridge,
+    );
+    assert_eq!(
+        common::get_token_balance(client, payer_tokens.pubkey()).await,
+        975
+    );
+    assert_eq!(common::get_token_balance(client, fee_tokens).await, 25);
+}
+
+#[tokio::test]
+async fn deregister_chain() {
+    let mut context = set_up().await.unwrap();
+    register_chain(&mut context).await;
+
+    // Fund the custody account so the redemption can be completed once the endpoint is back.
+    common::transfer_native(
+        &mut context.client,
+        context.token_bridge,
+        context.bridge,
+        &context.payer,
+        &Keypair::new(),
+        &context.token_account,
+        &context.token_authority,
+        context.mint.pubkey(),
+        100,
+    )
+    .await
+    .unwrap();
+
+    let payload = GovernancePayloadDeregisterChain {
+        chain: 2,
+        endpoint_address: ETH_TOKEN_BRIDGE,
+    };
+    let (message_key, sequence) = post_decree(&mut context, payload.try_to_vec().unwrap()).await;
+    let Context {
+        ref payer,
+        ref mut client,
+        bridge,
+        token_bridge,
+        ref mint,
+        ref token_account,
+        ref guardian_keys,
+        ref mut seq,
+        ..
+    } = context;
+
+    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
+    let ix = instructions::deregister_chain(
+        token_bridge,
+        payer.pubkey(),
+        message_key,
+        emitter.pubkey(),
+        sequence,
+        payload,
+    );
+    let logs = common::execute_with_logs(client, payer, &[payer], &[ix])
+        .await
+        .unwrap();
+    let events: Vec<DeregisterChainEvent> = logs
+        .iter()
+        .filter_map(|log| log.strip_prefix("Program data: "))
+        .map(|data| DeregisterChainEvent::decode(&base64::decode(data).unwrap()).unwrap())
+        .collect();
+    assert_eq!(
+        events,
+        vec![DeregisterChainEvent {
+            chain: 2,
+            emitter_address: ETH_TOKEN_BRIDGE,
+            sequence,
+        }]
+    );
+
+    let (endpoint, _) = find_registered_emitter(&token_bridge, 2, &ETH_TOKEN_BRIDGE);
+    let registration: EndpointRegistration =
+        common::get_account_data(client, endpoint).await.unwrap();
+    assert!(registration.deregistered);
+
+    // Transfers from the deregistered endpoint can no longer be redeemed.
+    let transfer = PayloadTransfer {
+        amount: U256::from(100u128),
+        token_address: mint.pubkey().to_bytes(),
+        token_chain: 1,
+        to: token_account.pubkey().to_bytes(),
+        to_chain: 1,
+        fee: U256::from(0u128),
+    };
+    let nonce = rand::thread_rng().gen();
+    let (vaa, body, _) = common::generate_vaa(
+        ETH_TOKEN_BRIDGE,
+        2,
+        transfer.try_to_vec().unwrap(),
+        nonce,
+        1,
+    );
+    let signature_set = common::verify_signatures(client, &bridge, payer, body, guardian_keys, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, bridge, payer, signature_set, vaa.clone())
+        .await
+        .unwrap();
+    let transfer_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: body.to_vec(),
+        },
+        &bridge,
+    );
+    assert_token_bridge_error(
+        common::complete_native(
+            client,
+            token_bridge,
+            bridge,
+            transfer_key,
+            vaa.clone(),
+            transfer.clone(),
+            payer,
+        )
+        .await,
+        TokenBridgeError::EmitterDeregistered,
+    );
+
+    // Registering the endpoint again enables it without creating a new account.
+    let register = PayloadGovernanceRegisterChain {
+        chain: 2,
+        endpoint_address: ETH_TOKEN_BRIDGE,
+    };
+    let nonce = rand::thread_rng().gen();
+    let (register_vaa, body, _) = common::generate_vaa(
+        emitter.pubkey().to_bytes(),
+        1,
+        register.try_to_vec().unwrap(),
+        nonce,
+        seq.next(emitter.pubkey().to_bytes()) + 1,
+    );
+    let signature_set = common::verify_signatures(client, &bridge, payer, body, guardian_keys, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, bridge, payer, signature_set, register_vaa.clone())
+        .await
+        .unwrap();
+    let register_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: body.to_vec(),
+        },
+        &bridge,
+    );
+    let register_sequence = register_vaa.sequence;
+    let logs = common::register_chain(
+        client,
+        token_bridge,
+        bridge,
+        register_key,
+        register_vaa,
+        register,
+        payer,
+    )
+    .await
+    .unwrap();
+    let events: Vec<RegisterChainEvent> = logs
+        .iter()
+        .filter_map(|log| log.strip_prefix("Program data: "))
+        .map(|data| RegisterChainEvent::decode(&base64::decode(data).unwrap()).unwrap())
+        .collect();
+    assert_eq!(
+        events,
+        vec![RegisterChainEvent {
+            chain: 2,
+            emitter_address: ETH_TOKEN_BRIDGE,
+            sequence: register_sequence,
+            created: false,
+        }]
+    );
+    let registration: EndpointRegistration =
+        common::get_account_data(client, endpoint).await.unwrap();
+    assert!(!registration.deregistered);
+
+    // The rejected transfer was never claimed and now goes through.
+    common::complete_native(
+        client,
+        token_bridge,
+        bridge,
+        transfer_key,
+        vaa,
+        transfer,
+        payer,
+    )
+    .await
+    .unwrap();
+}
+
+async fn get_spl_metadata(
+    client: &mut BanksClient,
+    mint: Pubkey,
+) -> spl_token_metadata::state::Metadata {
+    let spl_metadata = SplTokenMeta::key(
+        &SplTokenMetaDerivationData { mint },
+        &spl_token_metadata::id(),
+    );
+    let account = client.get_account(spl_metadata).await.unwrap().unwrap();
+    spl_token_metadata::utils::meta_deser_unchecked(&mut account.data.as_slice()).unwrap()
+}
+
+#[tokio::test]
+async fn set_metadata_authority() {
+    let mut context = set_up().await.unwrap();
+    register_chain(&mut context).await;
+    let mint = create_wrapped(&mut context).await;
+    let new_authority = Keypair::new();
+
+    let message = GovernancePayloadSetMetadataAuthority {
+        mint,
+        new_authority: new_authority.pubkey(),
+    }
+    .try_to_vec()
+    .unwrap();
+    let (message_key, sequence) = post_decree(&mut context, message).await;
+    let Context {
+        ref payer,
+        ref mut client,
+        bridge,
+        token_bridge,
+        ref guardian_keys,
+        ..
+    } = context;
+
+    let emitter = Keypair::from_bytes(&GOVERNANCE_KEY).unwrap();
+    let ix = instructions::set_metadata_authority(
+        token_bridge,
+        payer.pubkey(),
+        message_key,
+        emitter.pubkey(),
+        sequence,
+        mint,
+    );
+    common::execute(client, payer, &[payer], &[ix], CommitmentLevel::Processed)
+        .await
+        .unwrap();
+
+    let metadata = get_spl_metadata(client, mint).await;
+    assert_eq!(metadata.update_authority, new_authority.pubkey());
+
+    // Attestations no longer update the metadata once the token bridge gave its authority away.
+    let payload = PayloadAssetMeta {
+        token_address: [1u8; 32],
+        token_chain: 2,
+        decimals: 7,
+        symbol: "REN".to_string(),
+        name: "Renamed".to_string(),
+    };
+    let nonce = rand::thread_rng().gen();
+    let (vaa, body, _) =
+        common::generate_vaa(ETH_TOKEN_BRIDGE, 2, payload.try_to_vec().unwrap(), nonce, 3);
+    let signature_set = common::verify_signatures(client, &bridge, payer, body, guardian_keys, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, bridge, payer, signature_set, vaa.clone())
+        .await
+        .unwrap();
+    let message_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: body.to_vec(),
+        },
+        &bridge,
+    );
+    common::create_wrapped(
+        client,
+        token_bridge,
+        bridge,
+        message_key,
+        vaa,
+        payload,
+        payer,
+    )
+    .await
+    .unwrap();
+    assert_eq!(get_spl_metadata(client, mint).await.data, metadata.data);
+
+    // The new authority can.
+    let data = spl_token_metadata::state::DataV2 {
+        name: "Curated".to_string(),
+        symbol: metadata.data.symbol.clone(),
+        uri: metadata.data.uri.clone(),
+        seller_fee_basis_points: metadata.data.seller_fee_basis_points,
+        creators: metadata.data.creators.clone(),
+        collection: metadata.collection.clone(),
+        uses: metadata.uses.clone(),
+    };
+    let ix = spl_token_metadata::instruction::update_metadata_accounts_v2(
+        spl_token_metadata::id(),
+        SplTokenMeta::key(
+            &SplTokenMetaDerivationData { mint },
+            &spl_token_metadata::id(),
+        ),
+        new_authority.pubkey(),
+        None,
+        Some(data),
+        None,
+        None,
+    );
+    common::execute(
+        client,
+        payer,
+        &[payer, &new_authority],
+        &[ix],
+        CommitmentLevel::Processed,
+    )
+    .await
+    .unwrap();
+    assert!(get_spl_metadata(client, mint)
+        .await
+        .data
+        .name
+        .starts_with("Curated"));
+}
+
+#[tokio::test]
+async fn transfer_native_in() {
+    let mut context = set_up().await.unwrap();
+    register_chain(&mut context).await;
+    let Context {
+        ref payer,
+        ref mut client,
+        bridge,
+        token_bridge,
+        ref mint,
+        ref token_account,
+        ref token_authority,
+        ref guardian_keys,
+        ..
+    } = context;
+
+    // Do an initial transfer so that the bridge account has some native tokens. This also creates
+    // the custody account.
+    let message = &Keypair::new();
+    common::transfer_native(
+        client,
+        token_bridge,
+        bridge,
+        payer,
+        message,
+        token_account,
+        token_authority,
+        mint.pubkey(),
+        100,
+    )
+    .await
+    .unwrap();
+
+    let nonce = rand::thread_rng().gen();
+
+    let payload = PayloadTransfer {
+        amount: U256::from(100u128),
+        token_address: mint.pubkey().to_bytes(),
+        token_chain: 1,
+        to: token_account.pubkey().to_bytes(),
+        to_chain: 1,
+        fee: U256::from(0u128),
+    };
+    let message = payload.try_to_vec().unwrap();
+
+    let (vaa, body, _) = common::generate_vaa(ETH_TOKEN_BRIDGE, 2, message, nonce, 1);
+    let signature_set = common::verify_signatures(client, &bridge, payer, body, guardian_keys, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, bridge, payer, signature_set, vaa.clone())
+        .await
+        .unwrap();
+    let msg_derivation_data = &PostedVAADerivationData {
+        payload_hash: body.to_vec(),
+    };
+    let message_key =
+        PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(msg_derivation_data, &bridge);
+
+    common::complete_native(
+        client,
+        token_bridge,
+        bridge,
+        message_key,
+        vaa,
+        payload,
+        payer,
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn transfer_native_in_token_2022() {
+    // Custody of a Token-2022 mint without extensions, holding tokens sent out earlier.
+    let mint = Keypair::new();
+    let (_, token_bridge) = common::program_ids();
+    let (custody, _) = find_custody_token(&token_bridge, &mint.pubkey());
+    let (custody_signer, _) = find_custody_authority(&token_bridge);
+    let mut data = vec![0; spl_token_2022::state::Account::LEN];
+    spl_token_2022::state::Account {
+        mint: mint.pubkey(),
+        owner: custody_signer,
+        amount: 1000,
+        state: spl_token_2022::state::AccountState::Initialized,
+        ..Default::default()
+    }
+    .pack_into_slice(&mut data);
+    let account = Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: spl_token_2022::id(),
+        executable: false,
+        rent_epoch: 0,
+    };
+
+    let mut context = set_up_with_accounts(vec![(custody, account)])
+        .await
+        .unwrap();
+    register_chain(&mut context).await;
+    let Context {
+        ref payer,
+        ref mut client,
+        bridge,
+        token_bridge,
+        ref mint_authority,
+        ref token_authority,
+        ref guardian_keys,
+        ..
+    } = context;
+
+    let to = Keypair::new();
+    common::create_token_2022_mint(client, payer, &mint_authority.pubkey(), &mint)
+        .await
+        .unwrap();
+    common::create_token_2022_account(
+        client,
+        payer,
+        &to,
+        &token_authority.pubkey(),
+        &mint.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    let payload = PayloadTransfer {
+        amount: U256::from(100u128),
+        token_address: mint.pubkey().to_bytes(),
+        token_chain: 1,
+        to: to.pubkey().to_bytes(),
+        to_chain: 1,
+        fee: U256::from(10u128),
+    };
+    let message = payload.try_to_vec().unwrap();
+    let nonce = rand::thread_rng().gen();
+    let (vaa, body, _) = common::generate_vaa(ETH_TOKEN_BRIDGE, 2, message, nonce, 1);
+    let signature_set = common::verify_signatures(client, &bridge, payer, body, guardian_keys, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, bridge, payer, signature_set, vaa.clone())
+        .await
+        .unwrap();
+    let message_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: body.to_vec(),
+        },
+        &bridge,
+    );
+
+    // The instruction must carry the token program of the mint.
+    assert!(common::complete_native_with_token_program(
+        client,
+        token_bridge,
+        bridge,
+        message_key,
+        vaa.clone(),
+        payload.clone(),
+        payer,
+        spl_token::id(),
+    )
+    .await
+    .is_err());
+
+    // With no fee recipient the fee goes to the recipient as well.
+    common::complete_native_with_token_program(
+        client,
+        token_bridge,
+        bridge,
+        message_key,
+        vaa,
+        payload,
+        payer,
+        spl_token_2022::id(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(common::get_token_balance(client, to.pubkey()).await, 100);
+    assert_eq!(common::get_token_balance(client, custody).await, 900);
+}
+
+#[tokio::test]
+async fn transfer_native_with_payload_in_token_2022() {
+    // Custody of a Token-2022 mint without extensions, holding tokens sent out earlier.
+    let mint = Keypair::new();
+    let (_, token_bridge) = common::program_ids();
+    let (custody, _) = find_custody_token(&token_bridge, &mint.pubkey());
+    let (custody_signer, _) = find_custody_authority(&token_bridge);
+    let mut data = vec![0; spl_token_2022::state::Account::LEN];
+    spl_token_2022::state::Account {
+        mint: mint.pubkey(),
+        owner: custody_signer,
+        amount: 1000,
+        state: spl_token_2022::state::AccountState::Initialized,
+        ..Default::default()
+    }
+    .pack_into_slice(&mut data);
+    let account = Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: spl_token_2022::id(),
+        executable: false,
+        rent_epoch: 0,
+    };
+
+    let mut context = set_up_with_accounts(vec![(custody, account)])
+        .await
+        .unwrap();
+    register_chain(&mut context).await;
+    let Context {
+        ref payer,
+        ref mut client,
+        bridge,
+        token_bridge,
+        ref mint_authority,
+        ref token_authority,
+        ref guardian_keys,
+        ..
+    } = context;
+
+    let to = Keypair::new();
+    common::create_token_2022_mint(client, payer, &mint_authority.pubkey(), &mint)
+        .await
+        .unwrap();
+    common::create_token_2022_account(
+        client,
+        payer,
+        &to,
+        &token_authority.pubkey(),
+        &mint.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    let payload = PayloadTransferWithPayload {
+        amount: U256::from(100u128),
+        token_address: mint.pubkey().to_bytes(),
+        token_chain: CHAIN_ID_SOLANA,
+        to: token_authority.pubkey().to_bytes(),
+        to_chain: CHAIN_ID_SOLANA,
+        from_address: Keypair::new().pubkey().to_bytes(),
+        payload: vec![1, 2, 3],
+    };
+    let message = payload.try_to_vec().unwrap();
+    let nonce = rand::thread_rng().gen();
+    let (vaa, body, _) = common::generate_vaa(ETH_TOKEN_BRIDGE, CHAIN_ID_ETH, message, nonce, 1);
+    let signature_set = common::verify_signatures(client, &bridge, payer, body, guardian_keys, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, bridge, payer, signature_set, vaa.clone())
+        .await
+        .unwrap();
+    let message_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: body.to_vec(),
+        },
+        &bridge,
+    );
+
+    let complete = instructions::complete_native_with_payload(
+        token_bridge,
+        bridge,
+        payer.pubkey(),
+        message_key,
+        vaa,
+        to.pubkey(),
+        token_authority.pubkey(),
+        None,
+        mint.pubkey(),
+        spl_token_2022::id(),
+        CompleteNativeWithPayloadData {},
+    )
+    .unwrap();
+    common::execute(
+        client,
+        payer,
+        &[payer, token_authority],
+        &[complete],
+        CommitmentLevel::Processed,
+    )
+    .await
+    .unwrap();
+    assert_eq!(common::get_token_balance(client, to.pubkey()).await, 100);
+    assert_eq!(common::get_token_balance(client, custody).await, 900);
+}
+
+#[tokio::test]
+async fn transfer_native_in_token_2022_transfer_fee() {
+    let mut context = set_up().await.unwrap();
+    register_chain(&mut context).await;
+    let Context {
+        ref payer,
+        ref mut client,
+        bridge,
+        token_bridge,
+        ref mint_authority,
+        ref token_authority,
+        ref guardian_keys,
+        ..
+    } = context;
+
+    // A Token-2022 mint charging 1% of each transfer, with tokens in custody sent out earlier.
+    let mint = Keypair::new();
+    let (from, to, relayer) = (Keypair::new(), Keypair::new(), Keypair::new());
+    common::create_token_2022_mint_with_transfer_fee(
+        client,
+        payer,
+        &mint_authority.pubkey(),
+        &mint,
+        100,
+        u64::MAX,
+    )
+    .await
+    .unwrap();
+    for account in [&from, &to, &relayer] {
+        common::create_token_2022_account(
+            client,
+            payer,
+            account,
+            &token_authority.pubkey(),
+            &mint.pubkey(),
+        )
+        .await
+        .unwrap();
+    }
+    common::mint_token_2022_tokens(
+        client,
+        payer,
+        mint_authority,
+        &mint.pubkey(),
+        &from.pubkey(),
+        1000,
+    )
+    .await
+    .unwrap();
+    common::transfer_native_with_token_program(
+        client,
+        token_bridge,
+        bridge,
+        payer,
+        &Keypair::new(),
+        &from,
+        token_authority,
+        mint.pubkey(),
+        1000,
+        spl_token_2022::id(),
+    )
+    .await
+    .unwrap();
+    let (custody, _) = find_custody_token(&token_bridge, &mint.pubkey());
+    assert_eq!(common::get_token_balance(client, custody).await, 990);
+
+    let payload = PayloadTransfer {
+        amount: U256::from(500u128),
+        token_address: mint.pubkey().to_bytes(),
+        token_chain: 1,
+        to: to.pubkey().to_bytes(),
+        to_chain: 1,
+        fee: U256::from(100u128),
+    };
+    let message = payload.try_to_vec().unwrap();
+    let nonce = rand::thread_rng().gen();
+    let (vaa, body, _) = common::generate_vaa(ETH_TOKEN_BRIDGE, 2, message, nonce, 1);
+    let signature_set = common::verify_signatures(client, &bridge, payer, body, guardian_keys, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, bridge, payer, signature_set, vaa.clone())
+        .await
+        .unwrap();
+    let message_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: body.to_vec(),
+        },
+        &bridge,
+    );
+
+    let instruction = instructions::complete_native(
+        token_bridge,
+        bridge,
+        payer.pubkey(),
+        message_key,
+        vaa,
+        to.pubkey(),
+        Some(relayer.pubkey()),
+        mint.pubkey(),
+        spl_token_2022::id(),
+        Default::default(),
+    )
+    .unwrap();
+    let logs = common::execute_with_logs(client, payer, &[payer], &[instruction])
+        .await
+        .unwrap();
+    let events: Vec<CompleteNativeEvent> = logs
+        .iter()
+        .filter_map(|log| log.strip_prefix("Program data: "))
+        .map(|data| CompleteNativeEvent::decode(&base64::decode(data).unwrap()).unwrap())
+        .collect();
+
+    // The relayer is sent 102 so that its fee of 100 arrives, the recipient is sent the remaining
+    // 398 and pays the transfer fee on it.
+    assert_eq!(
+        events,
+        vec![CompleteNativeEvent {
+            mint: mint.pubkey(),
+            to: to.pubkey(),
+            to_fees: relayer.pubkey(),
+            amount_sent: 398,
+            amount_received: 394,
+            fee_sent: 102,
+            fee_received: 100,
+        }]
+    );
+    assert_eq!(common::get_token_balance(client, to.pubkey()).await, 394);
+    assert_eq!(
+        common::get_token_balance(client, relayer.pubkey()).await,
+        100
+    );
+    assert_eq!(common::get_token_balance(client, custody).await, 490);
+}
+
+#[tokio::test]
+async fn transfer_native_in_prefunded_claim() {
+    let mut context = set_up().await.unwrap();
+    register_chain(&mut context).await;
+    let Context {
+        ref payer,
+        ref mut client,
+        bridge,
+        token_bridge,
+        ref mint,
+        ref token_account,
+        ref token_authority,
+        ref guardian_keys,
+        ..
+    } = context;
+
+    let message = &Keypair::new();
+    common::transfer_native(
+        client,
+        token_bridge,
+        bridge,
+        payer,
+        message,
+        token_account,
+        token_authority,
+        mint.pubkey(),
+        100,
+    )
+    .await
+    .unwrap();
+
+    let payload = PayloadTransfer {
+        amount: U256::from(100u128),
+        token_address: mint.pubkey().to_bytes(),
+        token_chain: 1,
+        to: token_account.pubkey().to_bytes(),
+        to_chain: 1,
+        fee: U256::from(0u128),
+    };
+    let (vaa, body, _) = common::generate_vaa(
+        ETH_TOKEN_BRIDGE,
+        2,
+        payload.try_to_vec().unwrap(),
+        rand::thread_rng().gen(),
+        1,
+    );
+    let signature_set = common::verify_signatures(client, &bridge, payer, body, guardian_keys, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, bridge, payer, signature_set, vaa.clone())
+        .await
+        .unwrap();
+    let message_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: body.to_vec(),
+        },
+        &bridge,
+    );
+
+    // Anyone can send lamports to the claim before the redemption, which must not block it.
+    let claim = Claim::<'_>::key(
+        &ClaimDerivationData {
+            emitter_address: vaa.emitter_address,
+            emitter_chain: vaa.emitter_chain,
+            sequence: vaa.sequence,
+        },
+        token_bridge,
+    );
+    common::execute(
+        client,
+        payer,
+        &[payer],
+        &[solana_program::system_instruction::transfer(
+            &payer.pubkey(),
+            &claim,
+            1,
+        )],
+        CommitmentLevel::Processed,
+    )
+    .await
+    .unwrap();
+
+    common::complete_native(
+        client,
+        token_bridge,
+        bridge,
+        message_key,
+        vaa,
+        payload,
+        payer,
+    )
+    .await
+    .unwrap();
+    let claim: ClaimData = common::get_account_data(client, claim).await.unwrap();
+    assert!(claim.claimed);
+}
+
+#[tokio::test]
+async fn transfer_wrapped() {
+    let mut context = set_up().await.unwrap();
+    register_chain(&mut context).await;
+    let to = create_wrapped_account(&mut context).await.unwrap();
+    let Context {
+        ref payer,
+        ref mut client,
+        bridge,
+        token_bridge,
+        ref token_authority,
+        ref guardian_keys,
+        ..
+    } = context;
+
+    let nonce = rand::thread_rng().gen();
+
+    let payload = PayloadTransfer {
+        amount: U256::from(100000000),
+        token_address: [1u8; 32],
+        token_chain: 2,
+        to: to.to_bytes(),
+        to_chain: 1,
+        fee: U256::from(0),
+    };
+    let message = payload.try_to_vec().unwrap();
+
+    let (vaa, body, _) = common::generate_vaa(
+        ETH_TOKEN_BRIDGE,
+        2,
+        message,
+        nonce,
+        rand::thread_rng().gen(),
+    );
+    let signature_set = common::verify_signatures(client, &bridge, payer, body, guardian_keys, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, bridge, payer, signature_set, vaa.clone())
+        .await
+        .unwrap();
+    let msg_derivation_data = &PostedVAADerivationData {
+        payload_hash: body.to_vec(),
+    };
+    let message_key =
+        PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(msg_derivation_data, &bridge);
+
+    common::complete_transfer_wrapped(
+        client,
+        token_bridge,
+        bridge,
+        message_key,
+        vaa,
+        payload,
+        payer,
+    )
+    .await
+    .unwrap();
+
+    // Now transfer the wrapped tokens back, which will burn them.
+    let message = &Keypair::new();
+    common::transfer_wrapped(
+        client,
+        token_bridge,
+        bridge,
+        payer,
+        message,
+        to,
+        token_authority,
+        2,
+        [1u8; 32],
+        10000000,
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn transfer_wrapped_in_token_2022() {
+    // Wrapped mint of Token-2022 for a token of Ethereum, and its wrapped meta.
+    let (_, token_bridge) = common::program_ids();
+    let token_address = [7u8; 32];
+    let mint = WrappedMint::<'_, { AccountState::Initialized }>::key(
+        &WrappedDerivationData {
+            token_chain: CHAIN_ID_ETH,
+            token_address,
+        },
+        &token_bridge,
+    );
+    let (mint_authority, _) = find_mint_authority(&token_bridge);
+    let mint_account = token_2022_mint(&mint_authority, 8);
+    let meta = WrappedTokenMeta::<'_, { AccountState::Initialized }>::key(
+        &WrappedMetaDerivationData { mint_key: mint },
+        &token_bridge,
+    );
+    let data = WrappedMeta {
+        chain: CHAIN_ID_ETH,
+        token_address,
+        original_decimals: 18,
+    }
+    .try_to_vec()
+    .unwrap();
+    let meta_account = Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: token_bridge,
+        executable: false,
+        rent_epoch: 0,
+    };
+
+    let mut context = set_up_with_accounts(vec![(mint, mint_account), (meta, meta_account)])
+        .await
+        .unwrap();
+    register_chain(&mut context).await;
+    let Context {
+        ref payer,
+        ref mut client,
+        bridge,
+        token_bridge,
+        ref token_authority,
+        ref guardian_keys,
+        ..
+    } = context;
+
+    let (to, fee_recipient) = (Keypair::new(), Keypair::new());
+    for account in [&to, &fee_recipient] {
+        common::create_token_2022_account(client, payer, account, &token_authority.pubkey(), &mint)
+            .await
+            .unwrap();
+    }
+
+    let payload = PayloadTransfer {
+        amount: U256::from(100u128),
+        token_address,
+        token_chain: CHAIN_ID_ETH,
+        to: to.pubkey().to_bytes(),
+        to_chain: CHAIN_ID_SOLANA,
+        fee: U256::from(10u128),
+    };
+    let message = payload.try_to_vec().unwrap();
+    let nonce = rand::thread_rng().gen();
+    let (vaa, body, _) = common::generate_vaa(ETH_TOKEN_BRIDGE, 2, message, nonce, 1);
+    let signature_set = common::verify_signatures(client, &bridge, payer, body, guardian_keys, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, bridge, payer, signature_set, vaa.clone())
+        .await
+        .unwrap();
+    let message_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: body.to_vec(),
+        },
+        &bridge,
+    );
+
+    // The instruction must carry the token program of the mint.
+    assert!(common::complete_transfer_wrapped_with_token_program(
+        client,
+        token_bridge,
+        bridge,
+        message_key,
+        vaa.clone(),
+        payload.clone(),
+        Some(fee_recipient.pubkey()),
+        payer,
+        spl_token::id(),
+    )
+    .await
+    .is_err());
+
+    common::complete_transfer_wrapped_with_token_program(
+        client,
+        token_bridge,
+        bridge,
+        message_key,
+        vaa,
+        payload,
+        Some(fee_recipient.pubkey()),
+        payer,
+        spl_token_2022::id(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(common::get_token_balance(client, to.pubkey()).await, 90);
+    assert_eq!(
+        common::get_token_balance(client, fee_recipient.pubkey()).await,
+        10
+    );
+}
+
+#[tokio::test]
+async fn transfer_wrapped_with_payload_in_token_2022() {
+    // Wrapped mint of Token-2022 for a token of Ethereum, and its wrapped meta.
+    let (_, token_bridge) = common::program_ids();
+    let token_address = [7u8; 32];
+    let mint = WrappedMint::<'_, { AccountState::Initialized }>::key(
+        &WrappedDerivationData {
+            token_chain: CHAIN_ID_ETH,
+            token_address,
+        },
+        &token_bridge,
+    );
+    let (mint_authority, _) = find_mint_authority(&token_bridge);
+    let mint_account = token_2022_mint(&mint_authority, 8);
+    let meta = WrappedTokenMeta::<'_, { AccountState::Initialized }>::key(
+        &WrappedMetaDerivationData { mint_key: mint },
+        &token_bridge,
+    );
+    let data = WrappedMeta {
+        chain: CHAIN_ID_ETH,
+        token_address,
+        original_decimals: 18,
+    }
+    .try_to_vec()
+    .unwrap();
+    let meta_account = Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: token_bridge,
+        executable: false,
+        rent_epoch: 0,
+    };
+
+    let mut context = set_up_with_accounts(vec![(mint, mint_account), (meta, meta_account)])
+        .await
+        .unwrap();
+    register_chain(&mut context).await;
+    let Context {
+        ref payer,
+        ref mut client,
+        bridge,
+        token_bridge,
+        ref token_authority,
+        ref guardian_keys,
+        ..
+    } = context;
+
+    let to = Keypair::new();
+    common::create_token_2022_account(client, payer, &to, &token_authority.pubkey(), &mint)
+        .await
+        .unwrap();
+
+    let payload = PayloadTransferWithPayload {
+        amount: U256::from(100u128),
+        token_address,
+        token_chain: CHAIN_ID_ETH,
+        to: token_authority.pubkey().to_bytes(),
+        to_chain: CHAIN_ID_SOLANA,
+        from_address: Keypair::new().pubkey().to_bytes(),
+        payload: vec![1, 2, 3],
+    };
+    let message = payload.try_to_vec().unwrap();
+    let nonce = rand::thread_rng().gen();
+    let (vaa, body, _) = common::generate_vaa(ETH_TOKEN_BRIDGE, CHAIN_ID_ETH, message, nonce, 1);
+    let signature_set = common::verify_signatures(client, &bridge, payer, body, guardian_keys, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, bridge, payer, signature_set, vaa.clone())
+        .await
+        .unwrap();
+    let message_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: body.to_vec(),
+        },
+        &bridge,
+    );
+
+    let complete = instructions::complete_wrapped_with_payload(
+        token_bridge,
+        bridge,
+        payer.pubkey(),
+        message_key,
+        vaa,
+        payload,
+        to.pubkey(),
+        token_authority.pubkey(),
+        None,
+        spl_token_2022::id(),
+        CompleteWrappedWithPayloadData {},
+    )
+    .unwrap();
+    common::execute(
+        client,
+        payer,
+        &[payer, token_authority],
+        &[complete],
+        CommitmentLevel::Processed,
+    )
+    .await
+    .unwrap();
+    assert_eq!(common::get_token_balance(client, to.pubkey()).await, 100);
+
+    // Sending them back burns them through Token-2022.
+    let message = Keypair::new();
+    let transfer = instructions::transfer_wrapped(
+        token_bridge,
+        bridge,
+        payer.pubkey(),
+        message.pubkey(),
+        to.pubkey(),
+        token_authority.pubkey(),
+        CHAIN_ID_ETH,
+        token_address,
+        spl_token_2022::id(),
+        TransferWrappedData::new(0, 40, 0, [5u8; 32], CHAIN_ID_ETH).unwrap(),
+    )
+    .unwrap();
+    common::execute(
+        client,
+        payer,
+        &[payer, token_authority, &message],
+        &[
+            spl_token_2022::instruction::approve(
+                &spl_token_2022::id(),
+                &to.pubkey(),
+                &token_bridge::accounts::AuthoritySigner::key(None, &token_bridge),
+                &token_authority.pubkey(),
+                &[],
+                40,
+            )
+            .unwrap(),
+            transfer,
+        ],
+        CommitmentLevel::Processed,
+    )
+    .await
+    .unwrap();
+    assert_eq!(common::get_token_balance(client, to.pubkey()).await, 60);
+}
+
+#[tokio::test]
+async fn transfer_native_with_payload_in() {
+    let mut context = set_up().await.unwrap();
+    register_chain(&mut context).await;
+    let Context {
+        ref payer,
+        ref mut client,
+        bridge,
+        token_bridge,
+        ref mint,
+        ref token_account,
+        ref token_authority,
+        ref guardian_keys,
+        ..
+    } = context;
+
+    // Do an initial transfer so that the bridge account has some native tokens. This also creates
+    // the custody account.
+    let message = &Keypair::new();
+    common::transfer_native(
+        client,
+        token_bridge,
+        bridge,
+        payer,
+        message,
+        token_account,
+        token_authority,
+        mint.pubkey(),
+        100,
+    )
+    .await
+    .unwrap();
+
+    let nonce = rand::thread_rng().gen();
+    let from_address = Keypair::new().pubkey().to_bytes();
+    let payload: Vec<u8> = vec![1, 2, 3];
+    let payload = PayloadTransferWithPayload {
+        amount: U256::from(100u128),
+        token_address: mint.pubkey().to_bytes(),
+        token_chain: CHAIN_ID_SOLANA,
+        to: token_authority.pubkey().to_bytes(),
+        to_chain: CHAIN_ID_SOLANA,
+        from_address,
+        payload,
+    };
+    let message = payload.try_to_vec().unwrap();
+
+    let (vaa, body, _) = common::generate_vaa(ETH_TOKEN_BRIDGE, CHAIN_ID_ETH, message, nonce, 1);
+    let signature_set = common::verify_signatures(client, &bridge, payer, body, guardian_keys, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, bridge, payer, signature_set, vaa.clone())
+        .await
+        .unwrap();
+    let msg_derivation_data = &PostedVAADerivationData {
+        payload_hash: body.to_vec(),
+    };
+    let message_key =
+        PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(msg_derivation_data, &bridge);
+
+    common::complete_native_with_payload(
+        client,
+        token_bridge,
+        bridge,
+        message_key,
+        vaa,
+        payload,
+        token_account.pubkey(),
+        token_authority,
+        payer,
+    )
+    .await
+    .unwrap();
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn resolve_native_redemption() {
+    let mut context = set_up().await.unwrap();
+    register_chain(&mut context).await;
+    let Context {
+        ref payer,
+        ref mut client,
+        bridge,
+        token_bridge,
+        ref mint,
+        ref token_account,
+        ref token_authority,
+        ref guardian_keys,
+        ..
+    } = context;
+
+    common::transfer_native(
+        client,
+        token_bridge,
+        bridge,
+        payer,
+        &Keypair::new(),
+        token_account,
+        token_authority,
+        mint.pubkey(),
+        100,
+    )
+    .await
+    .unwrap();
+
+    let payload = PayloadTransfer {
+        amount: U256::from(100u128),
+        token_address: mint.pubkey().to_bytes(),
+        token_chain: CHAIN_ID_SOLANA,
+        to: token_account.pubkey().to_bytes(),
+        to_chain: CHAIN_ID_SOLANA,
+        fee: U256::from(0u128),
+    };
+    let (vaa, body, _) = common::generate_vaa(
+        ETH_TOKEN_BRIDGE,
+        CHAIN_ID_ETH,
+        payload.try_to_vec().unwrap(),
+        rand::thread_rng().gen(),
+        rand::thread_rng().gen(),
+    );
+
+    // Transfers without payload must be redeemed to the account the VAA names.
+    let rejected = resolve_redemption(&token_bridge, &bridge, &vaa, &Pubkey::new_unique(), |_| {
+        true
+    });
+    assert!(matches!(
+        rejected,
+        Err(solitaire::SolitaireError::Custom(code)) if code == TokenBridgeError::InvalidRecipient as u64
+    ));
+
+    let plan = resolve_redemption(
+        &token_bridge,
+        &bridge,
+        &vaa,
+        &token_account.pubkey(),
+        |key| *key == token_account.pubkey(),
+    )
+    .unwrap();
+    let (custody, _) = find_custody_token(&token_bridge, &mint.pubkey());
+    assert_eq!(
+        plan.path,
+        RedemptionPath::Native(
+            TokenBridgeAccounts::for_native(&token_bridge, &bridge, &mint.pubkey()).asset
+        )
+    );
+    assert!(matches!(plan.path, RedemptionPath::Native(native) if native.custody_token == custody));
+    assert_eq!(plan.mint, mint.pubkey());
+    assert_eq!(plan.to, token_account.pubkey());
+    assert_eq!(plan.redeemer_candidates, None);
+    assert_eq!(
+        plan.registered_emitter,
+        find_registered_emitter(&token_bridge, CHAIN_ID_ETH, &ETH_TOKEN_BRIDGE).0
+    );
+    assert!(!plan.create_to);
+    assert!(!plan.create_mint);
+
+    let signature_set = common::verify_signatures(client, &bridge, payer, body, guardian_keys, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, bridge, payer, signature_set, vaa.clone())
+        .await
+        .unwrap();
+    let message_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: body.to_vec(),
+        },
+        &bridge,
+    );
+    assert_eq!(plan.posted_vaa, message_key);
+
+    assert!(client.get_account(plan.claim).await.unwrap().is_none());
+    common::complete_native(
+        client,
+        token_bridge,
+        bridge,
+        message_key,
+        vaa,
+        payload,
+        payer,
+    )
+    .await
+    .unwrap();
+    assert!(client.get_account(plan.claim).await.unwrap().is_some());
+
+    // With a payload the VAA names the redeemer instead, which a wallet or a program may be.
+    let payload = PayloadTransferWithPayload {
+        amount: U256::from(100u128),
+        token_address: mint.pubkey().to_bytes(),
         token_chain: CHAIN_ID_SOLANA,
         to: token_authority.pubkey().to_bytes(),
         to_chain: CHAIN_ID_SOLANA,
-        from_address,
+        from_address: [0u8; 32],
+        payload: vec![1, 2, 3],
+    };
+    let (vaa, _, _) = common::generate_vaa(
+        ETH_TOKEN_BRIDGE,
+        CHAIN_ID_ETH,
+        payload.try_to_vec().unwrap(),
+        0,
+        0,
+    );
+    let plan = resolve_redemption(
+        &token_bridge,
+        &bridge,
+        &vaa,
+        &token_account.pubkey(),
+        |_| true,
+    )
+    .unwrap();
+    assert_eq!(
+        plan.redeemer_candidates,
+        Some([
+            token_authority.pubkey(),
+            find_redeemer(&token_authority.pubkey()).0
+        ])
+    );
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn resolve_wrapped_redemption() {
+    let mut context = set_up().await.unwrap();
+    register_chain(&mut context).await;
+
+    let wrapped_mint = WrappedMint::<'_, { AccountState::Initialized }>::key(
+        &WrappedDerivationData {
+            token_chain: CHAIN_ID_ETH,
+            token_address: [1u8; 32],
+        },
+        &context.token_bridge,
+    );
+    let payload = |to: Pubkey| PayloadTransfer {
+        amount: U256::from(100000000),
+        token_address: [1u8; 32],
+        token_chain: CHAIN_ID_ETH,
+        to: to.to_bytes(),
+        to_chain: CHAIN_ID_SOLANA,
+        fee: U256::from(0),
+    };
+
+    // Before the token is attested neither the mint nor the recipient exist.
+    let to = Pubkey::new_unique();
+    let (vaa, _, _) = common::generate_vaa(
+        ETH_TOKEN_BRIDGE,
+        CHAIN_ID_ETH,
+        payload(to).try_to_vec().unwrap(),
+        0,
+        0,
+    );
+    let plan =
+        resolve_redemption(&context.token_bridge, &context.bridge, &vaa, &to, |_| false).unwrap();
+    assert_eq!(plan.mint, wrapped_mint);
+    assert!(plan.create_to);
+    assert!(plan.create_mint);
+
+    let to = create_wrapped_account(&mut context).await.unwrap();
+    let Context {
+        ref payer,
+        ref mut client,
+        bridge,
+        token_bridge,
+        ref guardian_keys,
+        ..
+    } = context;
+
+    let payload = payload(to);
+    let (vaa, body, _) = common::generate_vaa(
+        ETH_TOKEN_BRIDGE,
+        CHAIN_ID_ETH,
+        payload.try_to_vec().unwrap(),
+        rand::thread_rng().gen(),
+        rand::thread_rng().gen(),
+    );
+    let mut existing = Vec::new();
+    for key in [to, wrapped_mint] {
+        if client.get_account(key).await.unwrap().is_some() {
+            existing.push(key);
+        }
+    }
+    let plan = resolve_redemption(&token_bridge, &bridge, &vaa, &to, |key| {
+        existing.contains(key)
+    })
+    .unwrap();
+    assert_eq!(
+        plan.path,
+        RedemptionPath::Wrapped(
+            TokenBridgeAccounts::for_wrapped(&token_bridge, &bridge, &wrapped_mint).asset
+        )
+    );
+    assert!(!plan.create_to);
+    assert!(!plan.create_mint);
+
+    let signature_set = common::verify_signatures(client, &bridge, payer, body, guardian_keys, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, bridge, payer, signature_set, vaa.clone())
+        .await
+        .unwrap();
+    assert!(client.get_account(plan.posted_vaa).await.unwrap().is_some());
+
+    common::complete_transfer_wrapped(
+        client,
+        token_bridge,
+        bridge,
+        plan.posted_vaa,
+        vaa,
         payload,
+        payer,
+    )
+    .await
+    .unwrap();
+    assert!(client.get_account(plan.claim).await.unwrap().is_some());
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn redemption_cost_matches_rent() {
+    let mut context = set_up().await.unwrap();
+    register_chain(&mut context).await;
+    let Context {
+        ref payer,
+        ref mut client,
+        bridge,
+        token_bridge,
+        ref mint,
+        ref token_account,
+        ref token_authority,
+        ref guardian_keys,
+        ..
+    } = context;
+
+    common::transfer_native(
+        client,
+        token_bridge,
+        bridge,
+        payer,
+        &Keypair::new(),
+        token_account,
+        token_authority,
+        mint.pubkey(),
+        100,
+    )
+    .await
+    .unwrap();
+
+    let payload = PayloadTransfer {
+        amount: U256::from(100u128),
+        token_address: mint.pubkey().to_bytes(),
+        token_chain: CHAIN_ID_SOLANA,
+        to: token_account.pubkey().to_bytes(),
+        to_chain: CHAIN_ID_SOLANA,
+        fee: U256::from(0u128),
     };
     let message = payload.try_to_vec().unwrap();
+    let rent = client.get_rent().await.unwrap();
+    let cost = estimate_redemption_cost(message.len(), true, &rent);
+    assert_eq!(
+        cost.total,
+        cost.claim_rent + cost.posted_vaa_rent + cost.ata_rent
+    );
+    assert_eq!(
+        estimate_redemption_cost(message.len(), false, &rent),
+        RedemptionCost {
+            ata_rent: 0,
+            total: cost.claim_rent + cost.posted_vaa_rent,
+            ..cost
+        }
+    );
 
-    let (vaa, body, _) = common::generate_vaa([0u8; 32], CHAIN_ID_ETH, message, nonce, 1);
+    let (vaa, body, _) = common::generate_vaa(
+        ETH_TOKEN_BRIDGE,
+        CHAIN_ID_ETH,
+        message,
+        rand::thread_rng().gen(),
+        rand::thread_rng().gen(),
+    );
+    let plan = resolve_redemption(
+        &token_bridge,
+        &bridge,
+        &vaa,
+        &token_account.pubkey(),
+        |_| true,
+    )
+    .unwrap();
     let signature_set = common::verify_signatures(client, &bridge, payer, body, guardian_keys, 0)
         .await
         .unwrap();
     common::post_vaa(client, bridge, payer, signature_set, vaa.clone())
         .await
         .unwrap();
-    let msg_derivation_data = &PostedVAADerivationData {
-        payload_hash: body.to_vec(),
+    common::complete_native(
+        client,
+        token_bridge,
+        bridge,
+        plan.posted_vaa,
+        vaa,
+        payload,
+        payer,
+    )
+    .await
+    .unwrap();
+
+    // Both accounts start out empty, so their balance is what the relayer spent on them.
+    let posted_vaa = client.get_account(plan.posted_vaa).await.unwrap().unwrap();
+    assert_eq!(posted_vaa.lamports, cost.posted_vaa_rent);
+    let claim = client.get_account(plan.claim).await.unwrap().unwrap();
+    assert_eq!(claim.lamports, cost.claim_rent);
+
+    let recipient = Keypair::new();
+    common::create_token_account(
+        client,
+        payer,
+        &recipient,
+        &token_authority.pubkey(),
+        &mint.pubkey(),
+    )
+    .await
+    .unwrap();
+    let recipient = client
+        .get_account(recipient.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(recipient.lamports, cost.ata_rent);
+}
+
+#[tokio::test]
+async fn transfer_through_cpi() {
+    let mut context = set_up().await.unwrap();
+    register_chain(&mut context).await;
+    let Context {
+        ref payer,
+        ref mut client,
+        bridge,
+        token_bridge,
+        ref mint,
+        ref token_account,
+        ref token_authority,
+        ref guardian_keys,
+        ..
+    } = context;
+
+    let authority_signer = token_bridge::accounts::AuthoritySigner::key(None, &token_bridge);
+    let custody = CustodyAccount::<'_, { AccountState::Initialized }>::key(
+        &CustodyAccountDerivationData {
+            mint: mint.pubkey(),
+        },
+        &token_bridge,
+    );
+    let approve = |amount| {
+        spl_token::instruction::approve(
+            &spl_token::id(),
+            &token_account.pubkey(),
+            &authority_signer,
+            &token_authority.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap()
     };
-    let message_key =
-        PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(msg_derivation_data, &bridge);
 
-    common::complete_native_with_payload(
+    // Attest the mint through the router.
+    let message = &Keypair::new();
+    let attest = instructions::attest(
+        token_bridge,
+        bridge,
+        payer.pubkey(),
+        message.pubkey(),
+        mint.pubkey(),
+        None,
+        0,
+    )
+    .unwrap();
+    common::execute(
+        client,
+        payer,
+        &[payer, message],
+        &[common::route_through_token_router(attest)],
+        CommitmentLevel::Processed,
+    )
+    .await
+    .unwrap();
+
+    // Lock tokens in custody through the router.
+    let message = &Keypair::new();
+    let transfer = instructions::transfer_native(
+        token_bridge,
+        bridge,
+        payer.pubkey(),
+        message.pubkey(),
+        token_account.pubkey(),
+        mint.pubkey(),
+        spl_token::id(),
+        TransferNativeData {
+            nonce: 0,
+            amount: 100,
+            fee: 0,
+            target_address: [1u8; 32],
+            target_chain: CHAIN_ID_ETH,
+        },
+    )
+    .unwrap();
+    common::execute(
+        client,
+        payer,
+        &[payer, token_authority, message],
+        &[approve(100), common::route_through_token_router(transfer)],
+        CommitmentLevel::Processed,
+    )
+    .await
+    .unwrap();
+    assert_eq!(common::get_token_balance(client, custody).await, 100);
+
+    // With a payload, the router signs as its sender PDA and becomes the sender of the transfer.
+    let message = &Keypair::new();
+    let transfer = instructions::transfer_native_with_payload(
+        token_bridge,
+        bridge,
+        payer.pubkey(),
+        message.pubkey(),
+        token_account.pubkey(),
+        mint.pubkey(),
+        spl_token::id(),
+        TransferNativeWithPayloadData {
+            nonce: 0,
+            amount: 50,
+            target_address: [1u8; 32],
+            target_chain: CHAIN_ID_ETH,
+            payload: vec![1, 2, 3],
+            cpi_program_id: Some(common::token_router_id()),
+        },
+    )
+    .unwrap();
+    common::execute(
         client,
+        payer,
+        &[payer, token_authority, message],
+        &[approve(50), common::route_through_token_router(transfer)],
+        CommitmentLevel::Processed,
+    )
+    .await
+    .unwrap();
+    assert_eq!(common::get_token_balance(client, custody).await, 150);
+
+    let posted: PostedMessageData = common::get_account_data(client, message.pubkey())
+        .await
+        .unwrap();
+    let posted = PayloadTransferWithPayload::deserialize(&mut posted.payload.as_slice()).unwrap();
+    assert_eq!(posted.from_address, common::token_router_id().to_bytes());
+
+    // Redeem a transfer addressed to the router into a token account owned by its redeemer PDA.
+    let redeemer = common::token_router_pda(b"redeemer");
+    let to = Keypair::new();
+    common::create_token_account(client, payer, &to, &redeemer, &mint.pubkey())
+        .await
+        .unwrap();
+
+    let payload = PayloadTransferWithPayload {
+        amount: U256::from(120u128),
+        token_address: mint.pubkey().to_bytes(),
+        token_chain: CHAIN_ID_SOLANA,
+        to: common::token_router_id().to_bytes(),
+        to_chain: CHAIN_ID_SOLANA,
+        from_address: Keypair::new().pubkey().to_bytes(),
+        payload: vec![4, 5, 6],
+    };
+    let (vaa, body, _) = common::generate_vaa(
+        ETH_TOKEN_BRIDGE,
+        CHAIN_ID_ETH,
+        payload.try_to_vec().unwrap(),
+        rand::thread_rng().gen(),
+        1,
+    );
+    let signature_set = common::verify_signatures(client, &bridge, payer, body, guardian_keys, 0)
+        .await
+        .unwrap();
+    common::post_vaa(client, bridge, payer, signature_set, vaa.clone())
+        .await
+        .unwrap();
+    let message_key = PostedVAA::<'_, { AccountState::MaybeInitialized }>::key(
+        &PostedVAADerivationData {
+            payload_hash: body.to_vec(),
+        },
+        &bridge,
+    );
+
+    let complete = instructions::complete_native_with_payload(
         token_bridge,
         bridge,
+        payer.pubkey(),
         message_key,
         vaa,
-        payload,
-        token_account.pubkey(),
-        token_authority,
+        to.pubkey(),
+        redeemer,
+        None,
+        mint.pubkey(),
+        spl_token::id(),
+        CompleteNativeWithPayloadData {},
+    )
+    .unwrap();
+    common::execute(
+        client,
         payer,
+        &[payer],
+        &[common::route_through_token_router(complete)],
+        CommitmentLevel::Processed,
     )
     .await
     .unwrap();
+    assert_eq!(common::get_token_balance(client, to.pubkey()).await, 120);
+    assert_eq!(common::get_token_balance(client, custody).await, 30);
+}
+
+#[test]
+fn instruction_data_round_trip() {
+    use token_bridge::instruction::Instruction;
+
+    let args = TransferNativeData::new(3, 100, 1, [1u8; 32], CHAIN_ID_ETH).unwrap();
+    for (selector, instruction) in Instruction::ALL.iter().enumerate() {
+        let data = instruction.data(&args).unwrap();
+        assert_eq!(data[0] as usize, selector);
+
+        let (decoded, encoded_args) = Instruction::decode(&data).unwrap();
+        assert_eq!(decoded, *instruction);
+        assert_eq!(encoded_args, &data[1..]);
+        let decoded_args = TransferNativeData::try_from_slice(encoded_args).unwrap();
+        assert_eq!(
+            decoded_args.try_to_vec().unwrap(),
+            args.try_to_vec().unwrap()
+        );
+    }
+    assert!(Instruction::decode(&[]).is_err());
+    assert!(Instruction::decode(&[Instruction::ALL.len() as u8]).is_err());
+}
+
+/// The IDL is written by hand, so check it against the instruction builders: it must list every
+/// instruction in dispatch order, each with the accounts the builder passes.
+#[test]
+fn idl_matches_instructions() {
+    let idl: serde_json::Value =
+        serde_json::from_str(include_str!("../../../../idl/token_bridge.json")).unwrap();
+    let idl = idl["instructions"].as_array().unwrap();
+
+    let (bridge, program) = common::program_ids();
+    let key = Pubkey::new_unique;
+    let vaa = PostVAAData::default();
+    let transfer = PayloadTransfer {
+        amount: U256::zero(),
+        token_address: [0; 32],
+        token_chain: CHAIN_ID_ETH,
+        to: [0; 32],
+        to_chain: CHAIN_ID_SOLANA,
+        fee: U256::zero(),
+    };
+    let transfer_with_payload = PayloadTransferWithPayload {
+        amount: U256::zero(),
+        token_address: [0; 32],
+        token_chain: CHAIN_ID_ETH,
+        to: [0; 32],
+        to_chain: CHAIN_ID_SOLANA,
+        from_address: [0; 32],
+        payload: vec![],
+    };
+    let asset_meta = PayloadAssetMeta {
+        token_address: [0; 32],
+        token_chain: CHAIN_ID_ETH,
+        decimals: 8,
+        symbol: String::new(),
+        name: String::new(),
+    };
+
+    let builders = vec![
+        (
+            "initialize",
+            instructions::initialize(program, key(), bridge).unwrap(),
+        ),
+        (
+            "attestToken",
+            instructions::attest(program, bridge, key(), key(), key(), None, 0).unwrap(),
+        ),
+        (
+            "completeNative",
+            instructions::complete_native(
+                program,
+                bridge,
+                key(),
+                key(),
+                vaa.clone(),
+                key(),
+                Some(key()),
+                key(),
+                spl_token::id(),
+                Default::default(),
+            )
+            .unwrap(),
+        ),
+        (
+            "completeWrapped",
+            instructions::complete_wrapped(
+                program,
+                bridge,
+                key(),
+                key(),
+                vaa.clone(),
+                transfer,
+                key(),
+                Some(key()),
+                spl_token::id(),
+                Default::default(),
+            )
+            .unwrap(),
+        ),
+        (
+            "transferWrapped",
+            instructions::transfer_wrapped(
+                program,
+                bridge,
+                key(),
+                key(),
+                key(),
+                key(),
+                CHAIN_ID_ETH,
+                [0; 32],
+                key(),
+                Default::default(),
+            )
+            .unwrap(),
+        ),
+        (
+            "transferNative",
+            instructions::transfer_native(
+                program,
+                bridge,
+                key(),
+                key(),
+                key(),
+                key(),
+                spl_token::id(),
+                Default::default(),
+            )
+            .unwrap(),
+        ),
+        (
+            "registerChain",
+            instructions::register_chain(
+                program,
+                bridge,
+                key(),
+                key(),
+                vaa.clone(),
+                PayloadGovernanceRegisterChain {
+                    chain: CHAIN_ID_ETH,
+                    endpoint_address: [0; 32],
+                },
+                Default::default(),
+            )
+            .unwrap(),
+        ),
+        (
+            "createWrapped",
+            instructions::create_wrapped(
+                program,
+                bridge,
+                key(),
+                key(),
+                vaa.clone(),
+                asset_meta,
+                Default::default(),
+            )
+            .unwrap(),
+        ),
+        (
+            "upgradeContract",
+            instructions::upgrade_contract(program, key(), key(), key(), key(), key(), 0),
+        ),
+        (
+            "completeNativeWithPayload",
+            instructions::complete_native_with_payload(
+                program,
+                bridge,
+                key(),
+                key(),
+                vaa.clone(),
+                key(),
+                key(),
+                Some(key()),
+                key(),
+                key(),
+                Default::default(),
+            )
+            .unwrap(),
+        ),
+        (
+            "completeWrappedWithPayload",
+            instructions::complete_wrapped_with_payload(
+                program,
+                bridge,
+                key(),
+                key(),
+                vaa,
+                transfer_with_payload,
+                key(),
+                key(),
+                Some(key()),
+                key(),
+                Default::default(),
+            )
+            .unwrap(),
+        ),
+        (
+            "transferWrappedWithPayload",
+            instructions::transfer_wrapped_with_payload(
+                program,
+                bridge,
+                key(),
+                key(),
+                key(),
+                key(),
+                CHAIN_ID_ETH,
+                [0; 32],
+                key(),
+                Default::default(),
+            )
+            .unwrap(),
+        ),
+        (
+            "transferNativeWithPayload",
+            instructions::transfer_native_with_payload(
+                program,
+                bridge,
+                key(),
+                key(),
+                key(),
+                key(),
+                key(),
+                Default::default(),
+            )
+            .unwrap(),
+        ),
+        (
+            "queryRegisteredEmitter",
+            instructions::query_registered_emitter(program, key(), Default::default()).unwrap(),
+        ),
+        (
+            "setPaused",
+            instructions::set_paused(program, key(), key(), key(), 0),
+        ),
+        (
+            "setMetadataAuthority",
+            instructions::set_metadata_authority(program, key(), key(), key(), 0, key()),
+        ),
+        (
+            "deregisterChain",
+            instructions::deregister_chain(
+                program,
+                key(),
+                key(),
+                key(),
+                0,
+                GovernancePayloadDeregisterChain {
+                    chain: CHAIN_ID_ETH,
+                    endpoint_address: [0; 32],
+                },
+            ),
+        ),
+    ];
+
+    assert_eq!(idl.len(), builders.len());
+    for (index, ((name, instruction), entry)) in builders.iter().zip(idl).enumerate() {
+        assert_eq!(entry["name"], *name);
+        assert_eq!(instruction.data[0] as usize, index, "{}", name);
+
+        let accounts = entry["accounts"].as_array().unwrap();
+        assert_eq!(accounts.len(), instruction.accounts.len(), "{}", name);
+        for (account, meta) in accounts.iter().zip(&instruction.accounts) {
+            let account_name = &account["name"];
+            assert_eq!(
+                account["isSigner"], meta.is_signer,
+                "{}.{}",
+                name, account_name
+            );
+            // Programs and sysvars are never written to, the builders do not agree on how to
+            // pass them.
+            let executable_or_sysvar = sysvar::is_sysvar_id(&meta.pubkey)
+                || [
+                    bridge,
+                    solana_program::system_program::id(),
+                    spl_token::id(),
+                    spl_token_metadata::id(),
+                    solana_program::bpf_loader_upgradeable::id(),
+                ]
+                .contains(&meta.pubkey);
+            if !executable_or_sysvar {
+                assert_eq!(
+                    account["isMut"], meta.is_writable,
+                    "{}.{}",
+                    name, account_name
+                );
+            }
+        }
+    }
 }
@@ -74,22 +74,50 @@ macro_rules! solitaire {
             /// This Instruction contains a 1-1 mapping for each enum variant to function call. The
             /// function calls can be found below in the `api` module.
             #[repr(u8)]
-            #[derive(BorshSerialize, BorshDeserialize)]
+            #[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
             pub enum Instruction {
                 $($row,)*
             }
 
+            impl Instruction {
+                /// Every instruction, in selector order.
+                pub const ALL: &'static [Instruction] = &[$(Instruction::$row,)*];
+
+                /// Instruction selected by the first byte of instruction data.
+                pub fn from_selector(selector: u8) -> Result<Self> {
+                    $(
+                        if selector == Instruction::$row as u8 {
+                            return Ok(Instruction::$row);
+                        }
+                    )*
+                    Err(SolitaireError::UnknownInstruction(selector))
+                }
+
+                /// Instruction data calling this instruction with `args`, its selector followed by
+                /// the Borsh encoding of `args`. This is the encoding `dispatch` expects.
+                pub fn data<T: BorshSerialize>(self, args: &T) -> Result<Vec<u8>> {
+                    let mut data = vec![self as u8];
+                    args.serialize(&mut data)?;
+                    Ok(data)
+                }
+
+                /// Split instruction data into its instruction and the encoded arguments, the
+                /// inverse of `data`.
+                pub fn decode(data: &[u8]) -> Result<(Self, &[u8])> {
+                    match data.split_first() {
+                        Some((selector, args)) => Ok((Self::from_selector(*selector)?, args)),
+                        None => Err(ProgramError::InvalidInstructionData.into()),
+                    }
+                }
+            }
+
             /// This entrypoint is generated from the enum above, it deserializes incoming bytes
             /// and automatically dispatches to the correct method.
             pub fn dispatch<'a, 'b: 'a, 'c>(p: &Pubkey, a: &'c [AccountInfo<'b>], d: &[u8]) -> Result<()> {
-                match d[0] {
+                match Instruction::decode(d)? {
                     $(
-                        n if n == Instruction::$row as u8 => $row::execute(p, a, &d[1..]),
+                        (Instruction::$row, args) => $row::execute(p, a, args),
                     )*
-
-                    other => {
-                        Err(SolitaireError::UnknownInstruction(other))
-                    }
                 }
             }
 
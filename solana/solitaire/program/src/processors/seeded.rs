@@ -1,12 +1,11 @@
 use super::keyed::Keyed;
 use crate::{
-    create_account,
+    create_pda_account,
     AccountState,
     CreationLamports,
     Data,
     Derive,
     ExecutionContext,
-    IsSigned::*,
     Result,
     SolitaireError,
 };
@@ -79,6 +78,16 @@ pub trait Seeded<I> {
     }
 
     fn verify_derivation<'a, 'b: 'a>(&'a self, program_id: &'a Pubkey, accs: I) -> Result<()>
+    where
+        Self: Keyed<'a, 'b>,
+    {
+        self.verify_derivation_bump(program_id, accs).map(|_| ())
+    }
+
+    /// Like `verify_derivation`, returning the canonical bump of the account. Accounts created
+    /// after being verified can be passed it with `Creatable::create_with_bump`, rather than
+    /// searching for it again.
+    fn verify_derivation_bump<'a, 'b: 'a>(&'a self, program_id: &'a Pubkey, accs: I) -> Result<u8>
     where
         Self: Keyed<'a, 'b>,
     {
@@ -86,9 +95,9 @@ pub trait Seeded<I> {
         let s: Vec<&[u8]> = seeds.iter().map(|item| item.as_slice()).collect();
         let seed_slice = s.as_slice();
 
-        let (derived, _bump) = Pubkey::find_program_address(seed_slice, program_id);
+        let (derived, bump) = Pubkey::find_program_address(seed_slice, program_id);
         if &derived == self.info().key {
-            Ok(())
+            Ok(bump)
         } else {
             Err(SolitaireError::InvalidDerive(*self.info().key, derived))
         }
@@ -103,6 +112,19 @@ pub trait Creatable<'a, I> {
         payer: &'a Pubkey,
         lamports: CreationLamports,
     ) -> Result<()>;
+
+    /// Like `create`, signing with the seeds of `accs` followed by `bump` instead of searching for
+    /// the bump. Only the derivation from these seeds is checked, so `bump` must be the canonical
+    /// one, as returned by `Seeded::verify_derivation_bump`. Any other bump that derives a valid
+    /// address would create a second account for the same seeds.
+    fn create_with_bump(
+        &'a self,
+        accs: I,
+        bump: u8,
+        ctx: &'a ExecutionContext,
+        payer: &'a Pubkey,
+        lamports: CreationLamports,
+    ) -> Result<()>;
 }
 
 impl<T: BorshSerialize + Owned + Default, const IS_INITIALIZED: AccountState> AccountSize
@@ -122,23 +144,45 @@ impl<'a, 'b: 'a, K, T: AccountSize + Seeded<K> + Keyed<'a, 'b> + Owned> Creatabl
         lamports: CreationLamports,
     ) -> Result<()> {
         let seeds = T::bumped_seeds(accs, ctx.program_id);
-        let size = self.size();
-
-        let s: Vec<&[u8]> = seeds.iter().map(|item| item.as_slice()).collect();
-        let seed_slice = s.as_slice();
+        create_seeded(self, ctx, payer, lamports, &seeds)
+    }
 
-        create_account(
-            ctx,
-            self.info(),
-            payer,
-            lamports,
-            size,
-            &self.owner_pubkey(ctx.program_id)?,
-            SignedWithSeeds(&[seed_slice]),
-        )
+    fn create_with_bump(
+        &'a self,
+        accs: K,
+        bump: u8,
+        ctx: &'a ExecutionContext<'_, '_>,
+        payer: &'a Pubkey,
+        lamports: CreationLamports,
+    ) -> Result<()> {
+        let mut seeds = T::seeds(accs);
+        seeds.push(vec![bump]);
+        create_seeded(self, ctx, payer, lamports, &seeds)
     }
 }
 
+fn create_seeded<'a, 'b: 'a, T: AccountSize + Keyed<'a, 'b> + Owned>(
+    account: &'a T,
+    ctx: &'a ExecutionContext<'_, '_>,
+    payer: &'a Pubkey,
+    lamports: CreationLamports,
+    seeds: &[Vec<u8>],
+) -> Result<()> {
+    let size = account.size();
+    let s: Vec<&[u8]> = seeds.iter().map(|item| item.as_slice()).collect();
+
+    // Fails unless the account is the one derived from the seeds.
+    create_pda_account(
+        ctx,
+        account.info(),
+        payer,
+        lamports,
+        size,
+        &account.owner_pubkey(ctx.program_id)?,
+        &s,
+    )
+}
+
 impl<'a, const SEED: &'static str, T> Seeded<Option<()>> for Derive<T, SEED> {
     fn seeds(_accs: Option<()>) -> Vec<Vec<u8>> {
         vec![SEED.as_bytes().to_vec()]
@@ -7,12 +7,15 @@
 use borsh::BorshSerialize;
 use solana_program::{
     account_info::AccountInfo,
+    entrypoint::MAX_PERMITTED_DATA_INCREASE,
     program::{
         invoke,
         invoke_signed,
     },
+    program_error::ProgramError,
     pubkey::Pubkey,
     system_instruction,
+    system_program,
     sysvar::Sysvar as SolanaSysvar,
 };
 use std::ops::{
@@ -27,6 +30,7 @@ use crate::{
     ExecutionContext,
     Keyed,
     Result,
+    SolitaireError,
 };
 
 /// A short alias for AccountInfo.
@@ -211,3 +215,129 @@ pub fn create_account(
 
     Ok(())
 }
+
+/// Create the PDA `account` of the executing program, signing for it with `seeds_with_bump`.
+///
+/// The address is derived with `create_program_address`, so callers that already know the bump
+/// do not pay for searching it again. It must be `account`, otherwise creation fails with
+/// `InvalidDerive`. Accounts already holding lamports are created like in [`create_account`].
+pub fn create_pda_account(
+    ctx: &ExecutionContext,
+    account: &Info<'_>,
+    payer: &Pubkey,
+    lamports: CreationLamports,
+    size: usize,
+    owner: &Pubkey,
+    seeds_with_bump: &[&[u8]],
+) -> Result<()> {
+    let derived = Pubkey::create_program_address(seeds_with_bump, ctx.program_id)
+        .map_err(|_| ProgramError::InvalidSeeds)?;
+    if derived != *account.key {
+        return Err(SolitaireError::InvalidDerive(*account.key, derived));
+    }
+
+    create_account(
+        ctx,
+        account,
+        payer,
+        lamports,
+        size,
+        owner,
+        SignedWithSeeds(&[seeds_with_bump]),
+    )
+}
+
+/// Close `account`, which must be owned by `program_id`, moving all of its lamports to
+/// `destination`.
+///
+/// Its data is zeroed and truncated and it is handed back to the system program, so even if it
+/// is funded again later in the same transaction it comes back as an empty system account rather
+/// than with its old owner. Closing an account into itself would burn its lamports instead, so
+/// `destination` must be another account.
+pub fn close_account_safe(
+    program_id: &Pubkey,
+    account: &AccountInfo,
+    destination: &AccountInfo,
+) -> Result<()> {
+    if account.owner != program_id {
+        return Err(SolitaireError::InvalidOwner(*account.owner));
+    }
+    if account.key == destination.key {
+        return Err(ProgramError::InvalidArgument.into());
+    }
+
+    let lamports = account.lamports();
+    **destination.lamports.borrow_mut() = destination
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ProgramError::InvalidArgument)?;
+    **account.lamports.borrow_mut() = 0;
+
+    account.data.borrow_mut().fill(0);
+    account.realloc(0, false)?;
+    account.assign(&system_program::id());
+
+    Ok(())
+}
+
+/// Resize `account`, which must be owned by the executing program, towards `new_len`, topping up
+/// its rent from `payer` so it stays exempt. Space gained is zeroed, even if the account shrank
+/// earlier in the same instruction.
+///
+/// The runtime lets an instruction grow an account by at most `MAX_PERMITTED_DATA_INCREASE`, so
+/// larger growth stops at that increment and the number of bytes still missing is returned. Call
+/// it again from later instructions until it returns 0. Shrinking is never limited.
+pub fn realloc_safe(
+    ctx: &ExecutionContext,
+    account: &Info<'_>,
+    new_len: usize,
+    payer: &Pubkey,
+) -> Result<usize> {
+    let len = new_len.min(account.data_len() + MAX_PERMITTED_DATA_INCREASE);
+
+    let target_rent = CreationLamports::Exempt.amount(len)?;
+    if account.lamports() < target_rent {
+        let transfer_ix =
+            system_instruction::transfer(payer, account.key, target_rent - account.lamports());
+        invoke(&transfer_ix, ctx.accounts)?;
+    }
+    account.realloc(len, true)?;
+
+    Ok(new_len - len)
+}
+
+/// Move `amount` lamports out of `from`, which must be owned by `program_id`, into `to`. This is
+/// how lamports leave accounts holding data, which the system program refuses to transfer from.
+///
+/// At least `min_remaining` lamports must be left in `from`, such as the rent-exempt minimum of
+/// its data, moving more fails with `InsufficientFunds` rather than leaving the account to be
+/// purged.
+pub fn transfer_lamports_from_owned(
+    program_id: &Pubkey,
+    from: &AccountInfo,
+    to: &AccountInfo,
+    amount: u64,
+    min_remaining: u64,
+) -> Result<()> {
+    if from.owner != program_id {
+        return Err(SolitaireError::InvalidOwner(*from.owner));
+    }
+    if from.key == to.key {
+        return Err(ProgramError::InvalidArgument.into());
+    }
+
+    let remaining = from
+        .lamports()
+        .checked_sub(amount)
+        .filter(|remaining| *remaining >= min_remaining)
+        .ok_or(SolitaireError::InsufficientFunds)?;
+    let received = to
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ProgramError::InvalidArgument)?;
+
+    **from.lamports.borrow_mut() = remaining;
+    **to.lamports.borrow_mut() = received;
+
+    Ok(())
+}
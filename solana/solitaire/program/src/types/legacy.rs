@@ -0,0 +1,80 @@
+//! Accounts written by older versions of a program.
+//!
+//! Borsh rejects data with bytes left over once a value is read. Accounts that were allocated with
+//! padding, or shrunk by rewriting a shorter encoding in place, still start with a valid value
+//! though. Wrapping their type in [`Legacy`] makes `Data` load them from that prefix.
+
+use borsh::{
+    BorshDeserialize,
+    BorshSerialize,
+};
+use std::{
+    io::{
+        self,
+        Write,
+    },
+    ops::{
+        Deref,
+        DerefMut,
+    },
+};
+
+use crate::{
+    processors::seeded::{
+        AccountOwner,
+        Owned,
+    },
+    Result,
+};
+
+/// Deserialize `T` from the start of `data`, ignoring any bytes after it.
+pub fn legacy_deserialize<T: BorshDeserialize>(data: &[u8]) -> Result<T> {
+    let mut buf = data;
+    Ok(T::deserialize(&mut buf)?)
+}
+
+/// Deserialize `T` from all of `data`, bytes left over are an error.
+pub fn strict_deserialize<T: BorshDeserialize>(data: &[u8]) -> Result<T> {
+    Ok(T::try_from_slice(data)?)
+}
+
+/// An account type that tolerates trailing bytes, see [`legacy_deserialize`]. It dereferences to
+/// the type itself and serializes exactly like it, so trailing bytes are left untouched.
+///
+/// Only the prefix is checked, so this is meant for accounts whose address proves their type, such
+/// as PDAs verified against their derivation.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Legacy<T>(pub T);
+
+impl<T> Deref for Legacy<T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Legacy<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T: BorshSerialize> BorshSerialize for Legacy<T> {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.0.serialize(writer)
+    }
+}
+
+impl<T: BorshDeserialize> BorshDeserialize for Legacy<T> {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        let value = T::deserialize(buf)?;
+        *buf = &[];
+        Ok(Legacy(value))
+    }
+}
+
+impl<T: Owned> Owned for Legacy<T> {
+    fn owner(&self) -> AccountOwner {
+        self.0.owner()
+    }
+}